@@ -5,6 +5,29 @@ use alloc::string::{String, ToString};
 use anyhow::{Error, Result};
 use core::{cmp::Ordering, iter::Peekable, str::FromStr};
 
+/// loader_conf: Parsing of the BLS loader.conf file.
+pub mod loader_conf;
+
+/// Iterates over the key and value of each setting line in the `input` of a BLS file.
+/// Empty lines, comments, and lines without a value are skipped.
+/// Values are trimmed of surrounding whitespace.
+pub(crate) fn key_values(input: &str) -> impl Iterator<Item = (&str, &str)> {
+    // A lone carriage return also ends a line, so it never ends up inside a value
+    // where it could overwrite what is shown on the console.
+    input.split(['\n', '\r']).filter_map(|line| {
+        let line = line.trim();
+        // Skip over empty lines and comments.
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        // Split the line once by whitespace. This technically includes newlines but since
+        // the input is split into lines, there should never be a newline here.
+        let (key, value) = line.split_once(char::is_whitespace)?;
+        Some((key, value.trim()))
+    })
+}
+
 /// Represents a parsed BLS entry.
 /// Fields unrelated to Sprout are not included.
 #[derive(Default, Debug, Clone)]
@@ -44,57 +67,45 @@ impl FromStr for BlsEntry {
         let mut version: Option<String> = None;
         let mut machine_id: Option<String> = None;
 
-        // Iterate over each line in the input and parse it.
-        for line in input.lines() {
-            let line = line.trim();
-            // Skip over empty lines and comments.
-            if line.is_empty() || line.starts_with('#') {
-                continue;
-            }
-
-            // Split the line once by whitespace. This technically includes newlines but since
-            // the lines iterator is used, there should never be a newline here.
-            let Some((key, value)) = line.split_once(char::is_whitespace) else {
-                continue;
-            };
-
+        // Iterate over each setting in the input and parse it.
+        for (key, value) in key_values(input) {
             // Match the key to a field we understand.
             match key {
                 // The title of the entry.
                 "title" => {
-                    title = Some(value.trim().to_string());
+                    title = Some(value.to_string());
                 }
 
                 // The options to pass to the entry.
                 "options" => {
-                    options = Some(value.trim().to_string());
+                    options = Some(value.to_string());
                 }
 
                 // The path to the linux kernel.
                 "linux" => {
-                    linux = Some(value.trim().to_string());
+                    linux = Some(value.to_string());
                 }
 
                 // The path to the initrd.
                 "initrd" => {
-                    initrd = Some(value.trim().to_string());
+                    initrd = Some(value.to_string());
                 }
 
                 // The path to an EFI image.
                 "efi" => {
-                    efi = Some(value.trim().to_string());
+                    efi = Some(value.to_string());
                 }
 
                 "sort-key" => {
-                    sort_key = Some(value.trim().to_string());
+                    sort_key = Some(value.to_string());
                 }
 
                 "version" => {
-                    version = Some(value.trim().to_string());
+                    version = Some(value.to_string());
                 }
 
                 "machine-id" => {
-                    machine_id = Some(value.trim().to_string());
+                    machine_id = Some(value.to_string());
                 }
 
                 // Ignore any other key.
@@ -458,6 +469,16 @@ efi        /EFI/fedora/shimx64.efi
         assert_eq!(entry.linux.as_deref(), Some("/vmlinuz"));
     }
 
+    #[test]
+    fn parse_treats_carriage_returns_as_line_endings() {
+        let entry: BlsEntry = "title Fedora\r\nversion 6.1\rlinux /vmlinuz\r\n"
+            .parse()
+            .unwrap();
+        assert_eq!(entry.title.as_deref(), Some("Fedora"));
+        assert_eq!(entry.version.as_deref(), Some("6.1"));
+        assert_eq!(entry.linux.as_deref(), Some("/vmlinuz"));
+    }
+
     #[test]
     fn parse_skips_comment_lines() {
         let input = "# this is a comment\ntitle My Entry\n# another comment\nlinux /vmlinuz\n";
@@ -724,4 +745,26 @@ efi        /EFI/fedora/shimx64.efi
             Ordering::Equal
         );
     }
+
+    proptest::proptest! {
+        #[test]
+        fn property_compare_versions_is_a_consistent_ordering(
+            a in "[019aBz.~^_+ -]{0,11}",
+            b in "[019aBz.~^_+ -]{0,11}",
+        ) {
+            proptest::prop_assert_eq!(compare_versions(&a, &a), Ordering::Equal);
+            proptest::prop_assert_eq!(compare_versions(&a, &b), compare_versions(&b, &a).reverse());
+        }
+
+        #[test]
+        fn property_parsed_values_are_trimmed_single_lines(text in "[tile \t\n\r#x/]{0,11}") {
+            let input = alloc::format!("{}title{}", text, text);
+            let entry: BlsEntry = input.parse().unwrap();
+            if let Some(title) = entry.title {
+                proptest::prop_assert!(!title.is_empty());
+                proptest::prop_assert_eq!(title.trim(), title.as_str());
+                proptest::prop_assert!(!title.contains(['\n', '\r']));
+            }
+        }
+    }
 }
@@ -0,0 +1,122 @@
+use crate::key_values;
+use alloc::string::{String, ToString};
+use anyhow::{Error, Result};
+use core::str::FromStr;
+
+/// The menu timeout of a loader.conf file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoaderTimeout {
+    /// Show the menu for the specified number of seconds.
+    Seconds(u64),
+    /// Show the menu until an entry is selected.
+    MenuForce,
+    /// Hide the menu unless a key is pressed.
+    MenuHidden,
+    /// Never show the menu.
+    MenuDisabled,
+}
+
+impl LoaderTimeout {
+    /// Parses the `value` of a timeout setting, returning [None] if it is not valid.
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "menu-force" => Some(Self::MenuForce),
+            "menu-hidden" => Some(Self::MenuHidden),
+            "menu-disabled" => Some(Self::MenuDisabled),
+            seconds => seconds.parse().ok().map(Self::Seconds),
+        }
+    }
+}
+
+/// Represents a parsed BLS loader.conf file.
+/// Settings unrelated to Sprout are not included, and invalid values are ignored.
+/// Reference: <https://www.freedesktop.org/software/systemd/man/latest/loader.conf.html>
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct LoaderConf {
+    /// The pattern of the entry to boot by default.
+    pub default: Option<String>,
+    /// The menu timeout.
+    pub timeout: Option<LoaderTimeout>,
+    /// The console mode to use.
+    pub console_mode: Option<String>,
+    /// Whether entries for other boot loaders should be generated automatically.
+    pub auto_entries: Option<bool>,
+    /// Whether an entry to reboot into the firmware setup should be generated.
+    pub auto_firmware: Option<bool>,
+}
+
+/// Parses the `value` of a boolean setting, returning [None] if it is not valid.
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "1" | "yes" | "y" | "true" | "t" | "on" => Some(true),
+        "0" | "no" | "n" | "false" | "f" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parser for a loader.conf file.
+impl FromStr for LoaderConf {
+    type Err = Error;
+
+    /// Parses the `input` as a loader.conf file.
+    /// When a setting is specified multiple times, the last value wins.
+    fn from_str(input: &str) -> Result<Self> {
+        let mut conf = Self::default();
+        for (key, value) in key_values(input) {
+            match key {
+                "default" => conf.default = Some(value.to_string()),
+                "timeout" => conf.timeout = LoaderTimeout::parse(value),
+                "console-mode" => conf.console_mode = Some(value.to_string()),
+                "auto-entries" => conf.auto_entries = parse_bool(value),
+                "auto-firmware" => conf.auto_firmware = parse_bool(value),
+                // Ignore any other key.
+                _ => {}
+            }
+        }
+        Ok(conf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_known_settings() {
+        let conf: LoaderConf = "# comment\ndefault fedora-*\ntimeout 5\nconsole-mode max\n\
+            auto-entries no\nauto-firmware yes\neditor no\n"
+            .parse()
+            .unwrap();
+        assert_eq!(conf.default.as_deref(), Some("fedora-*"));
+        assert_eq!(conf.timeout, Some(LoaderTimeout::Seconds(5)));
+        assert_eq!(conf.console_mode.as_deref(), Some("max"));
+        assert_eq!(conf.auto_entries, Some(false));
+        assert_eq!(conf.auto_firmware, Some(true));
+    }
+
+    #[test]
+    fn parse_menu_timeouts() {
+        for (value, timeout) in [
+            ("menu-force", LoaderTimeout::MenuForce),
+            ("menu-hidden", LoaderTimeout::MenuHidden),
+            ("menu-disabled", LoaderTimeout::MenuDisabled),
+        ] {
+            let conf: LoaderConf = alloc::format!("timeout {}", value).parse().unwrap();
+            assert_eq!(conf.timeout, Some(timeout));
+        }
+    }
+
+    #[test]
+    fn invalid_values_are_ignored() {
+        let conf: LoaderConf = "timeout soon\nauto-entries maybe\ndefault\n"
+            .parse()
+            .unwrap();
+        assert_eq!(conf, LoaderConf::default());
+    }
+
+    #[test]
+    fn last_value_wins() {
+        let conf: LoaderConf = "timeout 3\ntimeout\t10\n".parse().unwrap();
+        assert_eq!(conf.timeout, Some(LoaderTimeout::Seconds(10)));
+    }
+}
@@ -1,7 +1,22 @@
-use edera_sprout_build::generate_sbat_module;
+use edera_sprout_build::{
+    generate_branding_module, generate_build_module, generate_config_module, generate_sbat_module,
+    generate_signing_key_module,
+};
 
 /// Build script entry point for Sprout.
 fn main() {
     // Generate the sbat.generated.rs file.
     generate_sbat_module();
+
+    // Generate the config.generated.rs file.
+    generate_config_module();
+
+    // Generate the build.generated.rs file.
+    generate_build_module();
+
+    // Generate the branding.generated.rs file.
+    generate_branding_module();
+
+    // Generate the pubkey.generated.rs file.
+    generate_signing_key_module();
 }
@@ -1,11 +1,27 @@
 use crate::context::SproutContext;
+use alloc::format;
 use alloc::rc::Rc;
 use anyhow::{Context, Result, bail};
+use core::time::Duration;
+use edera_sprout_config::actions::{ActionDeclaration, ActionErrorPolicy};
+use edera_sprout_parsing::condition;
+use eficore::deadline;
+use log::{info, warn};
 
+/// Boot record beacon action.
+pub mod beacon;
+/// Firmware boot variables backup and restore actions.
+pub mod boot_variables;
 /// EFI chainloader action.
 pub mod chainload;
 /// Edera hypervisor action.
 pub mod edera;
+/// Action group action.
+pub mod group;
+/// HTTP boot action.
+pub mod http_boot;
+/// Removable media boot path installer action.
+pub mod install_fallback;
 /// EFI console print action.
 pub mod print;
 
@@ -14,9 +30,20 @@ pub mod print;
 /// if the provided action executes an operating system or an EFI application
 /// that does not return control to sprout.
 pub fn execute(context: Rc<SproutContext>, name: impl AsRef<str>) -> Result<()> {
+    eficore::instrument!(format!("action {}", name.as_ref()));
+    // Track the chain of executing actions, so a fallback or group that leads back to an
+    // action that is executing fails instead of recursing until the stack overflows.
+    context.root().enter_action(name.as_ref())?;
+    let result = execute_action(context.clone(), name.as_ref());
+    context.root().exit_action();
+    result
+}
+
+/// Executes the action specified by `name` inside the provided `context`, see [execute].
+fn execute_action(context: Rc<SproutContext>, name: &str) -> Result<()> {
     // Retrieve the action from the root context.
-    let Some(action) = context.root().actions().get(name.as_ref()) else {
-        bail!("unknown action '{}'", name.as_ref());
+    let Some(action) = context.root().actions().get(name) else {
+        bail!("unknown action '{}'", name);
     };
     // Finalize the context and freeze it.
     let context = context
@@ -24,7 +51,57 @@ pub fn execute(context: Rc<SproutContext>, name: impl AsRef<str>) -> Result<()>
         .context("unable to finalize context")?
         .freeze();
 
-    // Execute the action.
+    // Skip the action if the condition is not met.
+    if let Some(ref when) = action.when
+        && !condition::evaluate(&context.stamp(when))
+    {
+        info!("skipping action '{}': condition not met", name);
+        return Ok(());
+    }
+
+    // In dry run mode, log the stamped action declaration instead of executing it.
+    if context.root().options().dry_run {
+        let declaration =
+            toml::to_string(action).context("unable to serialize action declaration")?;
+        info!(
+            "dry run: would execute action '{}':\n{}",
+            name,
+            context.stamp(declaration)
+        );
+        return Ok(());
+    }
+
+    // Execute the action, with a deadline if a timeout is configured.
+    let result = match action.timeout {
+        Some(timeout) => deadline::with_deadline(Duration::from_secs(timeout), || {
+            dispatch(context.clone(), action)
+        }),
+        None => dispatch(context.clone(), action),
+    };
+
+    // Handle the error according to the error policy.
+    let Err(error) = result else {
+        return Ok(());
+    };
+    match &action.on_error {
+        ActionErrorPolicy::Abort => Err(error),
+        ActionErrorPolicy::Continue => {
+            warn!("action '{}' failed, continuing: {:#}", name, error);
+            Ok(())
+        }
+        ActionErrorPolicy::Fallback(fallback) => {
+            warn!(
+                "action '{}' failed, executing fallback action '{}': {:#}",
+                name, fallback, error
+            );
+            execute(context, fallback)
+                .context(format!("unable to execute fallback action '{}'", fallback))
+        }
+    }
+}
+
+/// Dispatches the `action` to the implementation of its configuration.
+fn dispatch(context: Rc<SproutContext>, action: &ActionDeclaration) -> Result<()> {
     if let Some(chainload) = &action.chainload {
         chainload::chainload(context.clone(), chainload)?;
         return Ok(());
@@ -34,6 +111,24 @@ pub fn execute(context: Rc<SproutContext>, name: impl AsRef<str>) -> Result<()>
     } else if let Some(edera) = &action.edera {
         edera::edera(context.clone(), edera)?;
         return Ok(());
+    } else if let Some(action_group) = &action.action_group {
+        group::group(context.clone(), action_group)?;
+        return Ok(());
+    } else if let Some(backup) = &action.backup_boot_variables {
+        boot_variables::backup(context.clone(), backup)?;
+        return Ok(());
+    } else if let Some(restore) = &action.restore_boot_variables {
+        boot_variables::restore(context.clone(), restore)?;
+        return Ok(());
+    } else if let Some(install_fallback) = &action.install_fallback {
+        install_fallback::install_fallback(context.clone(), install_fallback)?;
+        return Ok(());
+    } else if let Some(http_boot) = &action.http_boot {
+        http_boot::http_boot(context.clone(), http_boot)?;
+        return Ok(());
+    } else if let Some(beacon) = &action.beacon {
+        beacon::beacon(context.clone(), beacon);
+        return Ok(());
     }
 
     // If we reach here, we don't know how to execute the action that was configured.
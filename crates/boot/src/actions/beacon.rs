@@ -0,0 +1,76 @@
+use crate::context::SproutContext;
+use crate::records;
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::ToString;
+use anyhow::{Context, Result};
+use core::time::Duration;
+use edera_sprout_config::actions::beacon::BeaconConfiguration;
+use edera_sprout_config::phases::FAILED_ACTION_KEY;
+use edera_sprout_parsing::json::JsonValue;
+use eficore::deadline;
+use log::warn;
+use toml::Value;
+
+/// The content type of the record sent by the beacon.
+const BEACON_CONTENT_TYPE: &str = "application/json";
+
+/// Converts the TOML `value` of a boot record to JSON.
+fn json_value(value: &Value) -> JsonValue {
+    match value {
+        Value::String(value) => JsonValue::String(value.clone()),
+        Value::Integer(value) => JsonValue::Number(*value as f64),
+        Value::Float(value) => JsonValue::Number(*value),
+        Value::Boolean(value) => JsonValue::Bool(*value),
+        Value::Datetime(value) => JsonValue::String(value.to_string()),
+        Value::Array(items) => JsonValue::Array(items.iter().map(json_value).collect()),
+        Value::Table(table) => JsonValue::Object(
+            table
+                .iter()
+                .map(|(key, value)| (key.clone(), json_value(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// Sends the boot record to the server configured by the beacon `configuration`.
+fn send(context: &SproutContext, configuration: &BeaconConfiguration) -> Result<()> {
+    // Refresh the timings, so that the record covers the boot up to the beacon.
+    records::set_timings(context.root().timing());
+    let mut record = match records::snapshot() {
+        Some(record) => json_value(&Value::Table(record)),
+        None => JsonValue::Object(BTreeMap::new()),
+    };
+
+    // The on-failure phase provides the action that failed.
+    if let JsonValue::Object(ref mut fields) = record
+        && let Some(action) = context.get(FAILED_ACTION_KEY)
+    {
+        fields.insert(
+            FAILED_ACTION_KEY.to_string(),
+            JsonValue::String(action.clone()),
+        );
+    }
+
+    let url = context.stamp(&configuration.url);
+    let timeout = Duration::from_secs(configuration.timeout);
+    deadline::with_deadline(timeout, || {
+        eficore::http::post(
+            &url,
+            BEACON_CONTENT_TYPE,
+            record.encode().as_bytes(),
+            timeout,
+        )
+    })
+    .context("unable to send boot record")
+}
+
+/// Executes the beacon action with the specified `configuration` inside the provided `context`.
+/// The beacon sends the record of the boot, which includes the selected entry, the timings,
+/// and the firmware information. Failures are logged, since a beacon should never
+/// prevent booting.
+pub fn beacon(context: Rc<SproutContext>, configuration: &BeaconConfiguration) {
+    if let Err(error) = send(&context, configuration) {
+        warn!("beacon failed, continuing boot: {:#}", error);
+    }
+}
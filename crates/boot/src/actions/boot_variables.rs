@@ -0,0 +1,30 @@
+use crate::context::SproutContext;
+use alloc::rc::Rc;
+use anyhow::Result;
+use edera_sprout_config::actions::boot_variables::BootVariablesConfiguration;
+
+/// Executes the backup-boot-variables action with the specified `configuration` inside the
+/// provided `context`, writing the firmware boot variables to the backup file.
+pub fn backup(
+    context: Rc<SproutContext>,
+    configuration: &BootVariablesConfiguration,
+) -> Result<()> {
+    eficore::boot_variables::backup(
+        context.root().loaded_image_path()?,
+        &context.stamp(&configuration.path),
+    )?;
+    Ok(())
+}
+
+/// Executes the restore-boot-variables action with the specified `configuration` inside the
+/// provided `context`, writing the firmware boot variables of the backup file.
+pub fn restore(
+    context: Rc<SproutContext>,
+    configuration: &BootVariablesConfiguration,
+) -> Result<()> {
+    eficore::boot_variables::restore(
+        context.root().loaded_image_path()?,
+        &context.stamp(&configuration.path),
+    )?;
+    Ok(())
+}
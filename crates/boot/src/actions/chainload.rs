@@ -2,19 +2,54 @@ use crate::context::SproutContext;
 use crate::phases::before_handoff;
 use alloc::boxed::Box;
 use alloc::rc::Rc;
+use alloc::string::String;
 use anyhow::{Context, Result, bail};
+use core::time::Duration;
 use edera_sprout_config::actions::chainload::ChainloadConfiguration;
 use edera_sprout_parsing::{combine_options, empty_is_none};
 use eficore::bootloader_interface::BootloaderInterface;
+use eficore::cleanup::Cleanups;
 use eficore::loader::source::ImageSource;
-use eficore::loader::{ImageLoadRequest, ImageLoader};
-use eficore::media_loader::MediaLoaderHandle;
+use eficore::loader::{ImageHandle, ImageLoadRequest, ImageLoader};
 use eficore::media_loader::constants::linux::LINUX_EFI_INITRD_MEDIA_GUID;
+use eficore::media_loader::{MediaLoaderData, MediaLoaderHandle};
+use eficore::progress::ProgressBar;
+use eficore::watchdog;
+use log::warn;
 use uefi::CString16;
+use uefi::proto::console::text::{Key, ScanCode};
 use uefi::proto::loaded_image::LoadedImage;
 
 /// Executes the chainload action using the specified `configuration` inside the provided `context`.
+/// The options appended to Sprout's command line are appended to the options of the image,
+/// unless Secure Boot is enabled.
 pub fn chainload(context: Rc<SproutContext>, configuration: &ChainloadConfiguration) -> Result<()> {
+    let append = context
+        .root()
+        .options()
+        .measured_append("sprout: chainload appended options")?;
+    chainload_image(context, configuration, append.as_deref())
+}
+
+/// Checks whether escape was pressed, discarding any other pending key presses.
+fn escape_pressed() -> bool {
+    uefi::system::with_stdin(|input| {
+        while let Ok(Some(key)) = input.read_key() {
+            if key == Key::Special(ScanCode::ESCAPE) {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+/// Chainloads the image specified by `configuration` inside the provided `context`.
+/// If `append` is specified, it is appended to the stamped options of the image.
+pub fn chainload_image(
+    context: Rc<SproutContext>,
+    configuration: &ChainloadConfiguration,
+    append: Option<&str>,
+) -> Result<()> {
     // Retrieve the current image handle of sprout.
     let sprout_image = uefi::boot::image_handle();
 
@@ -26,26 +61,75 @@ pub fn chainload(context: Rc<SproutContext>, configuration: &ChainloadConfigurat
     .context("unable to resolve chainload path")?;
 
     // Create a new image load request with the current image and the resolved path.
-    let request = ImageLoadRequest::new(sprout_image, ImageSource::ResolvedPath(&resolved));
+    // Pressing escape cancels loading an image that was selected by accident.
+    let request = ImageLoadRequest::new(sprout_image, ImageSource::ResolvedPath(&resolved))
+        .with_cancel(escape_pressed);
 
     // Load the image to chainload using the image loader support module.
     // It will determine if the image needs to be loaded via the shim or can be loaded directly.
-    let image = ImageLoader::load(request)?;
+    let image = context
+        .root()
+        .timing()
+        .measure("load image", || ImageLoader::load(request))?;
+
+    // Stamp and combine the options to pass to the image, followed by the appended options.
+    let options = combine_options(
+        context
+            .stamp_iter(configuration.options.iter())
+            .chain(append.map(String::from)),
+    );
 
+    // Stamp the initrd path, if provided.
+    let initrd = configuration
+        .linux_initrd
+        .as_ref()
+        .map(|item| context.stamp(item));
+    // The initrd can be None or empty, so we need to collapse that into a single Option.
+    let initrd = empty_is_none(initrd);
+
+    // If an initrd is provided, read it so it can be registered with the EFI stack.
+    let initrd = match initrd {
+        Some(linux_initrd) => {
+            // Read the initrd directly into pages, avoiding a copy into the heap.
+            // Large initrds on slow media can take a while, so progress is shown.
+            let mut progress = ProgressBar::new("loading initrd");
+            let content = eficore::path::resolve_path(
+                Some(context.root().loaded_image_path()?),
+                &linux_initrd,
+            )
+            .and_then(|path| {
+                path.read_file_pages_with_progress(|done, total| progress.update(done, total))
+            })
+            .context("unable to read linux initrd")?;
+            progress.finish();
+            Some(MediaLoaderData::from(content))
+        }
+        None => None,
+    };
+
+    start_image(context, &image, &options, initrd)
+}
+
+/// Starts the loaded `image` inside the provided `context`, passing `options` to it.
+/// If an `initrd` is provided, it is registered for the image with the Linux initrd media
+/// loader while the image runs. Returns when the image returns control to Sprout.
+pub fn start_image(
+    context: Rc<SproutContext>,
+    image: &ImageHandle,
+    options: &str,
+    initrd: Option<MediaLoaderData>,
+) -> Result<()> {
     // Open the LoadedImage protocol of the image to chainload.
     let mut loaded_image_protocol =
         uefi::boot::open_protocol_exclusive::<LoadedImage>(*image.handle())
             .context("unable to open loaded image protocol")?;
 
-    // Stamp and combine the options to pass to the image.
-    let options = combine_options(context.stamp_iter(configuration.options.iter()));
-
     // Pass the load options to the image.
     // If no options are provided, the resulting string will be empty.
     // The options are pinned and boxed to ensure that they are valid for the lifetime of this
     // function, which ensures the lifetime of the options for the image runtime.
     let options = Box::pin(
-        CString16::try_from(&options[..])
+        CString16::try_from(options)
             .context("unable to convert chainloader options to CString16")?,
     );
 
@@ -62,32 +146,40 @@ pub fn chainload(context: Rc<SproutContext>, configuration: &ChainloadConfigurat
             .set_load_options(options.as_ptr() as *const u8, options.num_bytes() as u32);
     }
 
-    // Stamp the initrd path, if provided.
-    let initrd = configuration
-        .linux_initrd
-        .as_ref()
-        .map(|item| context.stamp(item));
-    // The initrd can be None or empty, so we need to collapse that into a single Option.
-    let initrd = empty_is_none(initrd);
+    // The cleanups unregister the initrd, including on early returns.
+    let mut cleanups = Cleanups::new();
 
     // If an initrd is provided, register it with the EFI stack.
-    let mut initrd_handle = None;
-    if let Some(linux_initrd) = initrd {
-        let content = eficore::path::read_file_contents(
-            Some(context.root().loaded_image_path()?),
-            &linux_initrd,
-        )
-        .context("unable to read linux initrd")?;
-        let handle =
-            MediaLoaderHandle::register(LINUX_EFI_INITRD_MEDIA_GUID, content.into_boxed_slice())
-                .context("unable to register linux initrd")?;
-        initrd_handle = Some(handle);
+    if let Some(content) = initrd {
+        let handle = MediaLoaderHandle::register(LINUX_EFI_INITRD_MEDIA_GUID, content)
+            .context("unable to register linux initrd")?;
+        cleanups.defer("unregister linux initrd", move || handle.unregister());
     }
 
     // Mark execution of an entry in the bootloader interface.
     BootloaderInterface::mark_exec(context.root().timer())
         .context("unable to mark execution of boot entry in bootloader interface")?;
 
+    // Log the timing of the boot process, which helps diagnose slow boots in the field.
+    context.root().timing().log();
+
+    // Log the memory usage if requested, which helps diagnose memory exhaustion on small systems.
+    if context.root().options().show_memory
+        && let Err(error) = eficore::memory::log()
+    {
+        warn!("unable to show memory usage: {:#}", error);
+    }
+
+    // Publish the boot log to the OS, which should never prevent booting.
+    if let Err(error) = eficore::logger::publish() {
+        warn!("unable to publish boot log: {:#}", error);
+    }
+
+    // Flush the log sinks that buffer lines, like the file sink, which should never prevent booting.
+    if let Err(error) = eficore::logger::flush() {
+        warn!("unable to flush boot log: {:#}", error);
+    }
+
     // Since we are about to hand off control to another image, we need to execute the handoff hook.
     // This will perform operations like clearing the screen.
     before_handoff(&context).context("unable to execute before handoff hook")?;
@@ -96,16 +188,31 @@ pub fn chainload(context: Rc<SproutContext>, configuration: &ChainloadConfigurat
     // This call might return, or it may pass full control to another image that will never return.
     // Capture the result to ensure we can return an error if the image fails to start, but only
     // after the optional initrd has been unregistered.
+    // The watchdog resets the system if the image hangs before it takes ownership of the system.
+    let watchdog_timeout = context.root().options().watchdog_timeout.unwrap_or(0);
+    if watchdog_timeout > 0
+        && let Err(error) = watchdog::arm(Duration::from_secs(watchdog_timeout))
+    {
+        // The watchdog is a safety net, so failing to arm it should not prevent booting.
+        warn!("unable to arm watchdog: {:#}", error);
+    }
     let result = uefi::boot::start_image(*image.handle());
 
+    // Control has returned to sprout, so the watchdog is no longer needed.
+    if watchdog_timeout > 0
+        && let Err(error) = watchdog::disarm()
+    {
+        warn!("unable to disarm watchdog: {:#}", error);
+    }
+
     // Assert there was no error starting the image.
     result.context("unable to start image")?;
 
     // Explicitly drop the options to clarify the lifetime.
     drop(options);
 
-    // Explicitly drop the initrd handle to clarify when it should be unregistered.
-    drop(initrd_handle);
+    // Release the initrd, which is unregistered now or when the action group ends.
+    context.root().release_cleanups(cleanups);
 
     // Return control to sprout.
     Ok(())
@@ -2,23 +2,38 @@ use crate::{actions, context::SproutContext};
 use alloc::rc::Rc;
 use alloc::string::String;
 use alloc::{format, vec};
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use edera_sprout_config::actions::chainload::ChainloadConfiguration;
 use edera_sprout_config::actions::edera::EderaConfiguration;
 use edera_sprout_parsing::{build_xen_config, combine_options, empty_is_none};
+use eficore::cleanup::Cleanups;
 use eficore::media_loader::{
     MediaLoaderHandle,
     constants::xen::{
         XEN_EFI_CONFIG_MEDIA_GUID, XEN_EFI_KERNEL_MEDIA_GUID, XEN_EFI_RAMDISK_MEDIA_GUID,
     },
 };
+use eficore::progress::ProgressBar;
 use uefi::Guid;
 
 /// Builds a configuration string for the Xen EFI stub using the specified `configuration`.
-fn make_xen_config(context: Rc<SproutContext>, configuration: &EderaConfiguration) -> String {
+fn make_xen_config(
+    context: Rc<SproutContext>,
+    configuration: &EderaConfiguration,
+) -> Result<String> {
     let xen_options = combine_options(context.stamp_iter(configuration.xen_options.iter()));
-    let kernel_options = combine_options(context.stamp_iter(configuration.kernel_options.iter()));
-    build_xen_config(&xen_options, &kernel_options)
+    // The options appended to Sprout's command line are appended to the kernel options,
+    // unless Secure Boot is enabled.
+    let append = context
+        .root()
+        .options()
+        .measured_append("sprout: edera appended options")?;
+    let kernel_options = combine_options(
+        context
+            .stamp_iter(configuration.kernel_options.iter())
+            .chain(append),
+    );
+    Ok(build_xen_config(&xen_options, &kernel_options))
 }
 
 /// Register a media loader for some `text` with the vendor `guid`.
@@ -42,12 +57,16 @@ fn register_media_loader_file(
 ) -> Result<MediaLoaderHandle> {
     // Stamp the path to the file.
     let path = context.stamp(path);
-    // Read the file contents.
-    let content =
-        eficore::path::read_file_contents(Some(context.root().loaded_image_path()?), &path)
-            .context(format!("unable to read {} file", what))?;
+    // Read the file contents into page-aligned memory, showing progress for large files.
+    let mut progress = ProgressBar::new(format!("loading {}", what));
+    let content = eficore::path::resolve_path(Some(context.root().loaded_image_path()?), &path)
+        .and_then(|path| {
+            path.read_file_pages_with_progress(|done, total| progress.update(done, total))
+        })
+        .context(format!("unable to read {} file", what))?;
+    progress.finish();
     // Register the media loader.
-    let handle = MediaLoaderHandle::register(guid, content.into_boxed_slice())
+    let handle = MediaLoaderHandle::register(guid, content)
         .context(format!("unable to register {} media loader", what))?;
     Ok(handle)
 }
@@ -55,12 +74,23 @@ fn register_media_loader_file(
 /// Executes the edera action which will boot the Edera hypervisor with the specified
 /// `configuration` and `context`. This action uses Edera-specific Xen EFI stub functionality.
 pub fn edera(context: Rc<SproutContext>, configuration: &EderaConfiguration) -> Result<()> {
+    // Xen does not provide an EFI stub on 32-bit arm, where it must be booted by the firmware.
+    if cfg!(target_arch = "arm") {
+        bail!("the edera action is not supported on 32-bit arm");
+    }
+
     // Build the Xen config file content for this configuration.
-    let config = make_xen_config(context.clone(), configuration);
+    let config = make_xen_config(context.clone(), configuration)?;
+
+    // The cleanups unregister the media loaders, including on early returns.
+    let mut cleanups = Cleanups::new();
 
     // Register the media loader for the config.
     let config = register_media_loader_text(XEN_EFI_CONFIG_MEDIA_GUID, "config", config)
         .context("unable to register config media loader")?;
+    cleanups.defer("unregister config media loader", move || {
+        config.unregister()
+    });
 
     // Register the media loaders for the kernel.
     let kernel = register_media_loader_file(
@@ -70,31 +100,35 @@ pub fn edera(context: Rc<SproutContext>, configuration: &EderaConfiguration) ->
         &configuration.kernel,
     )
     .context("unable to register kernel media loader")?;
-
-    // Create a vector of media loaders to drop them only after this function completes.
-    let mut media_loaders = vec![config, kernel];
+    cleanups.defer("unregister kernel media loader", move || {
+        kernel.unregister()
+    });
 
     // Register the initrd if it is provided.
     if let Some(initrd) = empty_is_none(configuration.initrd.as_ref()) {
         let initrd =
             register_media_loader_file(&context, XEN_EFI_RAMDISK_MEDIA_GUID, "initrd", initrd)
                 .context("unable to register initrd media loader")?;
-        media_loaders.push(initrd);
+        cleanups.defer("unregister initrd media loader", move || {
+            initrd.unregister()
+        });
     }
 
     // Chainload to the Xen EFI stub.
-    let result = actions::chainload::chainload(
+    // The appended options are passed to the kernel, so they are not appended to Xen.
+    let result = actions::chainload::chainload_image(
         context.clone(),
         &ChainloadConfiguration {
             path: configuration.xen.clone(),
             options: vec![],
             linux_initrd: None,
         },
+        None,
     )
     .context("unable to chainload to xen");
 
-    // Explicitly drop the media loaders to clarify when they should be unregistered.
-    drop(media_loaders);
+    // Release the media loaders, which are unregistered now or when the action group ends.
+    context.root().release_cleanups(cleanups);
 
     result
 }
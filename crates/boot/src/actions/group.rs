@@ -0,0 +1,35 @@
+use crate::actions;
+use crate::context::SproutContext;
+use alloc::format;
+use alloc::rc::Rc;
+use anyhow::{Context, Result};
+use edera_sprout_config::actions::group::ActionGroupConfiguration;
+use log::warn;
+
+/// Executes the action group with the specified `configuration` inside the provided `context`.
+/// The cleanups released by the actions in the group are collected in a cleanup scope.
+/// If an action fails, the cleanups are run before the error is propagated. Otherwise, they
+/// are released to the enclosing action group, or run once the group ends.
+/// A group that contains itself fails once it is reached again, see [actions::execute].
+pub fn group(context: Rc<SproutContext>, configuration: &ActionGroupConfiguration) -> Result<()> {
+    context.root().enter_cleanup_scope();
+
+    // Execute the actions in order, stopping at the first failure.
+    let result = configuration.actions.iter().try_for_each(|action| {
+        let action = context.stamp(action);
+        actions::execute(context.clone(), &action)
+            .context(format!("unable to execute action '{}'", action))
+    });
+
+    let mut cleanups = context.root().exit_cleanup_scope();
+    if result.is_err() {
+        // Roll back the resources acquired by the group.
+        if !cleanups.is_empty() {
+            warn!("action group failed, rolling back");
+        }
+        cleanups.run();
+    } else {
+        context.root().release_cleanups(cleanups);
+    }
+    result
+}
@@ -0,0 +1,67 @@
+use crate::actions::chainload;
+use crate::context::SproutContext;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use anyhow::{Context, Result};
+use edera_sprout_config::actions::http_boot::HttpBootConfiguration;
+use edera_sprout_parsing::{combine_options, empty_is_none};
+use eficore::loader::source::ImageSource;
+use eficore::loader::{ImageLoadRequest, ImageLoader};
+use eficore::media_loader::MediaLoaderData;
+use eficore::progress::ProgressBar;
+
+/// Downloads the file at `url`, showing the progress with the specified `label`.
+fn download(label: &str, url: &str) -> Result<Vec<u8>> {
+    let mut progress = ProgressBar::new(label);
+    let content = eficore::http::download(url, |done, total| progress.update(done, total))?;
+    progress.finish();
+    Ok(content)
+}
+
+/// Executes the http-boot action using the specified `configuration` inside the provided `context`.
+/// The image is downloaded and chainloaded, with the optional initrd downloaded alongside it.
+pub fn http_boot(context: Rc<SproutContext>, configuration: &HttpBootConfiguration) -> Result<()> {
+    // Download the image and load it from memory, which still verifies it with the shim.
+    // The resolved URL gives the image a URI device path, like firmware HTTP boot does.
+    let url = context.stamp(&configuration.url);
+    let resolved = eficore::path::resolve_url(&url).context("unable to resolve image url")?;
+    let content = download("downloading image", &url).context("unable to download image")?;
+    let image = context.root().timing().measure("load image", || {
+        ImageLoader::load(ImageLoadRequest::new(
+            uefi::boot::image_handle(),
+            ImageSource::DataBuffer {
+                path: Some(&resolved),
+                buffer: &content,
+            },
+        ))
+    })?;
+
+    // Stamp and combine the options to pass to the image, followed by the appended options.
+    let append = context
+        .root()
+        .options()
+        .measured_append("sprout: http boot appended options")?;
+    let options = combine_options(
+        context
+            .stamp_iter(configuration.options.iter())
+            .chain(append),
+    );
+
+    // Download the initrd, if provided, so it can be registered with the EFI stack.
+    let initrd = empty_is_none(
+        configuration
+            .linux_initrd
+            .as_ref()
+            .map(|item| context.stamp(item)),
+    );
+    let initrd = match initrd {
+        Some(url) => {
+            let content =
+                download("downloading initrd", &url).context("unable to download linux initrd")?;
+            Some(MediaLoaderData::from(content.into_boxed_slice()))
+        }
+        None => None,
+    };
+
+    chainload::start_image(context, &image, &options, initrd)
+}
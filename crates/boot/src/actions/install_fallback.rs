@@ -0,0 +1,15 @@
+use crate::context::SproutContext;
+use alloc::rc::Rc;
+use anyhow::Result;
+use edera_sprout_config::actions::install_fallback::InstallFallbackConfiguration;
+
+/// Executes the install-fallback action with the specified `configuration` inside the provided
+/// `context`, copying the Sprout image to the removable media boot path when it is outdated.
+pub fn install_fallback(
+    context: Rc<SproutContext>,
+    configuration: &InstallFallbackConfiguration,
+) -> Result<()> {
+    let destination = configuration.path.as_ref().map(|path| context.stamp(path));
+    eficore::fallback::install(context.root().loaded_image_path()?, destination.as_deref())?;
+    Ok(())
+}
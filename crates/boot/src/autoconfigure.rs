@@ -1,8 +1,6 @@
 use anyhow::{Context, Result};
 use edera_sprout_config::RootConfiguration;
-use uefi::fs::FileSystem;
-use uefi::proto::device_path::DevicePath;
-use uefi::proto::media::fs::SimpleFileSystem;
+use eficore::filesystem::FilesystemScan;
 
 /// bls: autodetect and configure BLS-enabled filesystems.
 pub mod bls;
@@ -19,38 +17,28 @@ pub mod windows;
 /// Intakes a `config` to use as the basis of the autoconfiguration.
 pub fn autoconfigure(config: &mut RootConfiguration) -> Result<()> {
     // Find all the filesystems that are on the system.
-    let filesystem_handles =
-        uefi::boot::find_handles::<SimpleFileSystem>().context("unable to scan filesystems")?;
+    let mut scan = FilesystemScan::new().context("unable to scan filesystems")?;
 
     // For each filesystem that was detected, scan it for supported autoconfig mechanisms.
-    for handle in filesystem_handles {
+    for handle in scan.handles() {
         // Acquire the device path root for the filesystem.
-        let root = {
-            uefi::boot::open_protocol_exclusive::<DevicePath>(handle)
-                .context("unable to get root for filesystem")?
-                .to_boxed()
-        };
-
-        // Open the filesystem that was detected.
-        let filesystem = uefi::boot::open_protocol_exclusive::<SimpleFileSystem>(handle)
-            .context("unable to open filesystem")?;
-
-        // Trade the filesystem protocol for the uefi filesystem helper.
-        let mut filesystem = FileSystem::new(filesystem);
+        let root = scan
+            .root(handle)
+            .context("unable to get root for filesystem")?
+            .to_boxed();
 
         // Scan the filesystem for BLS supported configurations.
-        let bls_found = bls::scan(&mut filesystem, &root, config)
-            .context("unable to scan for bls configurations")?;
+        // The scanners access the filesystem through the firmware services, relative to its root.
+        let bls_found =
+            bls::scan(&root, config).context("unable to scan for bls configurations")?;
 
         // If BLS was not found, scan for Linux configurations.
         if !bls_found {
-            linux::scan(&mut filesystem, &root, config)
-                .context("unable to scan for linux configurations")?;
+            linux::scan(&root, config).context("unable to scan for linux configurations")?;
         }
 
         // Always look for Windows configurations.
-        windows::scan(&mut filesystem, &root, config)
-            .context("unable to scan for windows configurations")?;
+        windows::scan(&root, config).context("unable to scan for windows configurations")?;
     }
 
     Ok(())
@@ -8,8 +8,7 @@ use edera_sprout_config::entries::EntryDeclaration;
 use edera_sprout_config::generators::GeneratorDeclaration;
 use edera_sprout_config::generators::bls::BlsConfiguration;
 use edera_sprout_parsing::unique_hash;
-use uefi::cstr16;
-use uefi::fs::{FileSystem, Path};
+use eficore::services::services;
 use uefi::proto::device_path::DevicePath;
 use uefi::proto::device_path::text::{AllowShortcuts, DisplayOnly};
 
@@ -17,16 +16,25 @@ use uefi::proto::device_path::text::{AllowShortcuts, DisplayOnly};
 /// by the BLS generator to chainload entries.
 const BLS_CHAINLOAD_ACTION_PREFIX: &str = "bls-chainload-";
 
-/// Scan the specified `filesystem` for BLS configurations.
-pub fn scan(
-    filesystem: &mut FileSystem,
-    root: &DevicePath,
-    config: &mut RootConfiguration,
-) -> Result<bool> {
-    // BLS has a loader.conf file that can specify its own auto-entries mechanism.
-    let bls_loader_conf_path = Path::new(cstr16!("\\loader\\loader.conf"));
-    // BLS also has an entries directory that can specify explicit entries.
-    let bls_entries_path = Path::new(cstr16!("\\loader\\entries"));
+/// BLS has a loader.conf file that can specify its own auto-entries mechanism.
+const BLS_LOADER_CONF_PATH: &str = "\\loader\\loader.conf";
+
+/// BLS also has an entries directory that can specify explicit entries.
+const BLS_ENTRIES_PATH: &str = "\\loader\\entries";
+
+/// Scan the filesystem with the device path `root` for BLS configurations.
+pub fn scan(root: &DevicePath, config: &mut RootConfiguration) -> Result<bool> {
+    // Whether we have a loader.conf file.
+    let has_loader_conf = services()
+        .file_exists(Some(root), BLS_LOADER_CONF_PATH)
+        .context("unable to check for BLS loader.conf file")?;
+
+    // Whether we have an entries directory.
+    // We actually list the entries to see if there are any.
+    let has_entries_dir = services()
+        .list_directory(Some(root), BLS_ENTRIES_PATH)
+        .map(|entries| !entries.is_empty())
+        .unwrap_or(false);
 
     // Convert the device path root to a string we can use in the configuration.
     let mut root = root
@@ -39,20 +47,6 @@ pub fn scan(
     // Generate a unique hash of the root path.
     let root_unique_hash = unique_hash(&root);
 
-    // Whether we have a loader.conf file.
-    let has_loader_conf = filesystem
-        .try_exists(bls_loader_conf_path)
-        .context("unable to check for BLS loader.conf file")?;
-
-    // Whether we have an entries directory.
-    // We actually iterate the entries to see if there are any.
-    let has_entries_dir = filesystem
-        .read_dir(bls_entries_path)
-        .ok()
-        .and_then(|mut iterator| iterator.next())
-        .map(|entry| entry.is_ok())
-        .unwrap_or(false);
-
     // Detect if a BLS supported configuration is on this filesystem.
     // We check both loader.conf and entries directory as only one of them is required.
     if !(has_loader_conf || has_entries_dir) {
@@ -9,12 +9,13 @@ use edera_sprout_config::actions::chainload::ChainloadConfiguration;
 use edera_sprout_config::entries::EntryDeclaration;
 use edera_sprout_config::generators::GeneratorDeclaration;
 use edera_sprout_config::generators::list::ListConfiguration;
+use edera_sprout_parsing::kernel::{KERNEL_VERSION_PREFIX_LENGTH, kernel_version};
+use edera_sprout_parsing::path::join;
 use edera_sprout_parsing::{
-    LINUX_INITRAMFS_PREFIXES, LINUX_KERNEL_PREFIXES, initramfs_candidates, match_kernel_prefix,
-    unique_hash,
+    LINUX_INITRAMFS_PREFIXES, LINUX_KERNEL_PREFIXES, empty_is_none, initramfs_candidates,
+    match_kernel_prefix, unique_hash,
 };
-use uefi::CString16;
-use uefi::fs::{FileSystem, Path, PathBuf};
+use eficore::services::services;
 use uefi::proto::device_path::DevicePath;
 use uefi::proto::device_path::text::{AllowShortcuts, DisplayOnly};
 
@@ -41,65 +42,35 @@ const DEFAULT_LINUX_OPTIONS: &str = "placeholder";
 /// Pair of kernel and initramfs.
 /// This is what scanning a directory is meant to find.
 struct KernelPair {
+    /// The display name of the kernel.
+    name: String,
     /// The path to a kernel.
     kernel: String,
     /// The path to an initramfs, if any.
     initramfs: Option<String>,
+    /// The version of the kernel, if known.
+    /// This is taken from the kernel filename, or from the kernel image if the filename has none.
+    version: Option<String>,
 }
 
-/// Scan the specified `filesystem` at `path` for [KernelPair] results.
-fn scan_directory(filesystem: &mut FileSystem, path: &str) -> Result<Vec<KernelPair>> {
+/// Scan the directory at `path` of the filesystem with the device path `root`
+/// for [KernelPair] results.
+fn scan_directory(root: &DevicePath, path: &str) -> Result<Vec<KernelPair>> {
     // All the discovered kernel pairs.
     let mut pairs = Vec::new();
 
-    // We have to special-case the root directory due to path logic in the uefi crate.
-    let is_root = path.is_empty() || path == "\\";
+    // Keep the directory as a string, file paths are joined onto it below.
+    let base = path;
 
-    // Construct a filesystem path from the path string.
-    let path = CString16::try_from(path).context("unable to convert path to CString16")?;
-    let path = Path::new(&path);
-    let path = path.to_path_buf();
-
-    // Check if the path exists and is a directory.
-    let exists = filesystem
-        .metadata(&path)
-        .ok()
-        .map(|metadata| metadata.is_directory())
-        .unwrap_or(false);
-
-    // If the path does not exist, return an empty list.
-    if !exists {
-        return Ok(pairs);
-    }
-
-    // Open a directory iterator on the path to scan.
+    // List the regular files in the directory to scan.
+    // If the path does not exist or is not a directory, there is nothing to scan.
     // Ignore errors here as in some scenarios this might fail due to symlinks.
-    let Some(directory) = filesystem.read_dir(&path).ok() else {
+    let Ok(names) = services().list_directory(Some(root), path) else {
         return Ok(pairs);
     };
 
-    // Create a new path used for joining file names below.
-    // All attempts to derive paths for the files in the directory should use this instead.
-    // The uefi crate does not handle push correctly for the root directory.
-    // It will add a second slash, which will cause our path logic to fail.
-    let path_for_join = if is_root {
-        PathBuf::new()
-    } else {
-        path.clone()
-    };
-
-    // For each item in the directory, find a kernel.
-    for item in directory {
-        let item = item.context("unable to read directory item")?;
-
-        // Skip over any items that are not regular files.
-        if !item.is_regular_file() {
-            continue;
-        }
-
-        // Convert the name from a CString16 to a String.
-        let name = item.file_name().to_string();
-
+    // For each file in the directory, find a kernel.
+    for name in names {
         // Convert the name to lowercase to make all of this case-insensitive.
         let name_for_match = name.to_lowercase();
 
@@ -120,14 +91,11 @@ fn scan_directory(filesystem: &mut FileSystem, path: &str) -> Result<Vec<KernelP
                 break None;
             };
             // Construct an initramfs path.
-            let initramfs = CString16::try_from(candidate.as_str())
-                .context("unable to convert initramfs name to CString16")?;
-            let mut initramfs_path = path_for_join.clone();
-            initramfs_path.push(Path::new(&initramfs));
+            let initramfs_path = join(base, &candidate);
 
             // Check if the initramfs path exists, if it does, break out of the loop.
-            if filesystem
-                .try_exists(&initramfs_path)
+            if services()
+                .file_exists(Some(root), &initramfs_path)
                 .context("unable to check if initramfs path exists")?
             {
                 break Some(initramfs_path);
@@ -135,27 +103,53 @@ fn scan_directory(filesystem: &mut FileSystem, path: &str) -> Result<Vec<KernelP
         };
 
         // Construct a kernel path from the kernel name.
-        let mut kernel = path_for_join.clone();
-        kernel.push(Path::new(&item.file_name()));
-        let kernel = kernel.to_string();
-        let initramfs = matched_initramfs_path.map(|initramfs_path| initramfs_path.to_string());
+        let kernel = join(base, &name);
+        let initramfs = matched_initramfs_path;
+
+        // Acquire the kernel version from the suffix of the name, like vmlinuz-6.12.1.
+        // If the name does not include a version, read it from the kernel image header,
+        // which only requires the start of the kernel image.
+        // Failing to read the kernel image is not fatal, the version is just unknown.
+        let suffix_version = empty_is_none(Some(suffix.trim_start_matches('-'))).map(String::from);
+        let image_version = if suffix_version.is_none() {
+            services()
+                .read_file_prefix(Some(root), &kernel, KERNEL_VERSION_PREFIX_LENGTH)
+                .ok()
+                .and_then(|image| kernel_version(&image))
+        } else {
+            None
+        };
+
+        // The name is shown in the title, so include the version if the filename lacks it.
+        let name = match image_version {
+            Some(ref version) => format!("{} ({})", kernel, version),
+            None => kernel.clone(),
+        };
 
         // Produce a kernel pair.
-        let pair = KernelPair { kernel, initramfs };
+        let pair = KernelPair {
+            name,
+            kernel,
+            initramfs,
+            version: suffix_version.or(image_version),
+        };
         pairs.push(pair);
     }
 
     Ok(pairs)
 }
 
-/// Scan the specified `filesystem` for Linux kernels and matching initramfs.
-pub fn scan(
-    filesystem: &mut FileSystem,
-    root: &DevicePath,
-    config: &mut RootConfiguration,
-) -> Result<bool> {
+/// Scan the filesystem with the device path `root` for Linux kernels and matching initramfs.
+pub fn scan(root: &DevicePath, config: &mut RootConfiguration) -> Result<bool> {
     let mut pairs = Vec::new();
 
+    // Scan all locations for kernel pairs, adding them to the list.
+    for location in SCAN_LOCATIONS {
+        let scanned = scan_directory(root, location)
+            .with_context(|| format!("unable to scan directory {}", location))?;
+        pairs.extend(scanned);
+    }
+
     // Convert the device path root to a string we can use in the configuration.
     let mut root = root
         .to_string16(DisplayOnly(false), AllowShortcuts(false))
@@ -167,13 +161,6 @@ pub fn scan(
     // Generate a unique hash of the root path.
     let root_unique_hash = unique_hash(&root);
 
-    // Scan all locations for kernel pairs, adding them to the list.
-    for location in SCAN_LOCATIONS {
-        let scanned = scan_directory(filesystem, location)
-            .with_context(|| format!("unable to scan directory {}", location))?;
-        pairs.extend(scanned);
-    }
-
     // If no kernel pairs were found, return false.
     if pairs.is_empty() {
         return Ok(false);
@@ -187,14 +174,17 @@ pub fn scan(
         entry: EntryDeclaration {
             title: "Boot Linux $name".to_string(),
             actions: vec![chainload_action_name.clone()],
-            sort_key: Some("$kernel".to_string()),
+            sort_key: Some("$sort-key".to_string()),
             ..Default::default()
         },
         values: pairs
             .into_iter()
             .map(|pair| {
-                BTreeMap::from_iter(vec![
-                    ("name".to_string(), pair.kernel.clone()),
+                // Kernels without a known version are sorted by their path instead.
+                let sort_key = pair.version.clone().unwrap_or_else(|| pair.kernel.clone());
+                let mut values = BTreeMap::from_iter(vec![
+                    ("name".to_string(), pair.name),
+                    ("sort-key".to_string(), sort_key),
                     ("kernel".to_string(), format!("{}{}", root, pair.kernel)),
                     (
                         "initrd".to_string(),
@@ -202,9 +192,15 @@ pub fn scan(
                             .map(|initramfs| format!("{}{}", root, initramfs))
                             .unwrap_or_default(),
                     ),
-                ])
+                ]);
+                // The version is only provided when it is known.
+                if let Some(version) = pair.version {
+                    values.insert("version".to_string(), version);
+                }
+                values
             })
             .collect(),
+        ..Default::default()
     };
 
     // Generate a unique name for the Linux generator and insert the generator into the configuration.
@@ -6,8 +6,7 @@ use edera_sprout_config::actions::ActionDeclaration;
 use edera_sprout_config::actions::chainload::ChainloadConfiguration;
 use edera_sprout_config::entries::EntryDeclaration;
 use edera_sprout_parsing::unique_hash;
-use uefi::CString16;
-use uefi::fs::{FileSystem, Path};
+use eficore::services::services;
 use uefi::proto::device_path::DevicePath;
 use uefi::proto::device_path::text::{AllowShortcuts, DisplayOnly};
 
@@ -17,20 +16,11 @@ const WINDOWS_CHAINLOAD_ACTION_PREFIX: &str = "windows-chainload-";
 /// Windows boot manager path.
 const BOOTMGR_FW_PATH: &str = "\\EFI\\Microsoft\\Boot\\bootmgfw.efi";
 
-/// Scan the specified `filesystem` for Windows configurations.
-pub fn scan(
-    filesystem: &mut FileSystem,
-    root: &DevicePath,
-    config: &mut RootConfiguration,
-) -> Result<bool> {
-    // Convert the boot manager firmware path to a path.
-    let bootmgr_fw_path =
-        CString16::try_from(BOOTMGR_FW_PATH).context("unable to convert path to CString16")?;
-    let bootmgr_fw_path = Path::new(&bootmgr_fw_path);
-
+/// Scan the filesystem with the device path `root` for Windows configurations.
+pub fn scan(root: &DevicePath, config: &mut RootConfiguration) -> Result<bool> {
     // Check if the boot manager firmware path exists, if it doesn't, return false.
-    if !filesystem
-        .try_exists(bootmgr_fw_path)
+    if !services()
+        .file_exists(Some(root), BOOTMGR_FW_PATH)
         .context("unable to check if bootmgr firmware path exists")?
     {
         return Ok(false);
@@ -64,7 +54,7 @@ pub fn scan(
 
     // Generate a chainload configuration for Windows.
     let chainload = ChainloadConfiguration {
-        path: format!("{}{}", root, bootmgr_fw_path),
+        path: format!("{}{}", root, BOOTMGR_FW_PATH),
         options: vec![],
         ..Default::default()
     };
@@ -0,0 +1,137 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use anyhow::{Context, Result, bail};
+use edera_sprout_config::branding::ThemeConfiguration;
+use edera_sprout_parsing::bmp::decode_bmp;
+use eficore::framebuffer::{Framebuffer, PixelFormat};
+use log::warn;
+use spin::Mutex;
+use uefi::proto::console::gop::GraphicsOutput;
+use uefi::proto::console::text::Color;
+use uefi::proto::device_path::DevicePath;
+
+/// embedded: Branding embedded into Sprout at build time.
+pub mod embedded;
+
+/// The path of the splash image on the partition Sprout was loaded from.
+const SPLASH_PATH: &str = "\\sprout\\branding\\splash.bmp";
+
+/// The path of the file containing the product name on the partition Sprout was loaded from.
+const PRODUCT_NAME_PATH: &str = "\\sprout\\branding\\name.txt";
+
+/// The path of the menu theme on the partition Sprout was loaded from.
+const THEME_PATH: &str = "\\sprout\\branding\\theme.toml";
+
+/// The product name shown in the boot menu, once the branding is loaded.
+static PRODUCT_NAME: Mutex<Option<String>> = Mutex::new(None);
+
+/// Reads the branding file at `path`, falling back to the `embedded` data.
+/// Branding is cosmetic, so a file that can not be read is reported and ignored.
+fn read(loaded_image_path: &DevicePath, path: &str, embedded: Option<&[u8]>) -> Option<Vec<u8>> {
+    let content = eficore::path::resolve_path(Some(loaded_image_path), path).and_then(|resolved| {
+        if !resolved.exists()? {
+            return Ok(None);
+        }
+        resolved.read_file().map(Some)
+    });
+    match content {
+        Ok(Some(content)) => Some(content),
+        Ok(None) => embedded.map(<[u8]>::to_vec),
+        Err(error) => {
+            warn!("unable to read branding file {}: {:#}", path, error);
+            embedded.map(<[u8]>::to_vec)
+        }
+    }
+}
+
+/// Parses the console color `name`, like `light-gray`.
+fn parse_color(name: &str) -> Result<Color> {
+    Ok(match name {
+        "black" => Color::Black,
+        "blue" => Color::Blue,
+        "green" => Color::Green,
+        "cyan" => Color::Cyan,
+        "red" => Color::Red,
+        "magenta" => Color::Magenta,
+        "brown" => Color::Brown,
+        "light-gray" => Color::LightGray,
+        "dark-gray" => Color::DarkGray,
+        "light-blue" => Color::LightBlue,
+        "light-green" => Color::LightGreen,
+        "light-cyan" => Color::LightCyan,
+        "light-red" => Color::LightRed,
+        "light-magenta" => Color::LightMagenta,
+        "yellow" => Color::Yellow,
+        "white" => Color::White,
+        _ => bail!("unknown color '{}'", name),
+    })
+}
+
+/// Applies the colors of the `theme` to the console.
+fn apply_theme(theme: &ThemeConfiguration) -> Result<()> {
+    if theme.foreground.is_none() && theme.background.is_none() {
+        return Ok(());
+    }
+    let foreground = parse_color(theme.foreground.as_deref().unwrap_or("light-gray"))?;
+    let background = parse_color(theme.background.as_deref().unwrap_or("black"))?;
+    uefi::system::with_stdout(|stdout| stdout.set_color(foreground, background))
+        .context("unable to set console colors")
+}
+
+/// Shows the BMP `splash` image in the center of the screen.
+fn show_splash(splash: &[u8]) -> Result<()> {
+    let bitmap = decode_bmp(splash).context("splash image is not a supported BMP")?;
+    let handle = uefi::boot::get_handle_for_protocol::<GraphicsOutput>()
+        .context("unable to find graphics output")?;
+    let mut gop = uefi::boot::open_protocol_exclusive::<GraphicsOutput>(handle)
+        .context("unable to open graphics output")?;
+
+    // Draw over the current contents of the screen, so only the image region is changed.
+    let mut framebuffer = Framebuffer::capture(&mut gop)?;
+    let x = framebuffer.width().saturating_sub(bitmap.width) / 2;
+    let y = framebuffer.height().saturating_sub(bitmap.height) / 2;
+    framebuffer.draw_image(x, y, bitmap.width, &bitmap.pixels, PixelFormat::Bgrx8);
+    framebuffer.blit(&mut gop)
+}
+
+/// Loads the branding of Sprout and applies it.
+///
+/// Each branding resource is read from `\sprout\branding` on the partition Sprout was loaded
+/// from, relative to the `loaded_image_path`, and otherwise from the branding embedded into
+/// Sprout at build time. The theme is applied to the console, the splash image is shown,
+/// and the product name is used by the boot menu. Branding never prevents booting.
+pub fn load(loaded_image_path: &DevicePath) {
+    if let Some(theme) = read(loaded_image_path, THEME_PATH, embedded::embedded_theme()) {
+        let theme = core::str::from_utf8(&theme)
+            .context("theme is not valid UTF-8")
+            .and_then(|theme| {
+                toml::from_str::<ThemeConfiguration>(theme).context("unable to parse theme")
+            })
+            .and_then(|theme| apply_theme(&theme));
+        if let Err(error) = theme {
+            warn!("unable to apply menu theme: {:#}", error);
+        }
+    }
+
+    if let Some(name) = read(
+        loaded_image_path,
+        PRODUCT_NAME_PATH,
+        embedded::embedded_product_name(),
+    ) {
+        let name = String::from_utf8_lossy(&name).trim().to_string();
+        if !name.is_empty() {
+            *PRODUCT_NAME.lock() = Some(name);
+        }
+    }
+
+    if let Some(splash) = read(loaded_image_path, SPLASH_PATH, embedded::embedded_splash())
+        && let Err(error) = show_splash(&splash)
+    {
+        warn!("unable to show splash image: {:#}", error);
+    }
+}
+
+/// Acquires the product name shown in the boot menu, if the branding provides one.
+pub fn product_name() -> Option<String> {
+    PRODUCT_NAME.lock().clone()
+}
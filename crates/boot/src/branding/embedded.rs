@@ -0,0 +1,2 @@
+// Include the generated branding sections in this file.
+include!(concat!(env!("OUT_DIR"), "/branding.generated.rs"));
@@ -0,0 +1,2 @@
+// Include the generated build metadata section in this file.
+include!(concat!(env!("OUT_DIR"), "/build.generated.rs"));
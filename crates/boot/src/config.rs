@@ -1,2 +1,11 @@
+/// Dumping of the effective configuration for debugging.
+pub mod dump;
+
+/// The configuration embedded into Sprout at build time.
+pub mod embedded;
+
 /// The configuration loader mechanisms.
 pub mod loader;
+
+/// The public key that verifies configuration signatures, embedded into Sprout at build time.
+pub mod signing_key;
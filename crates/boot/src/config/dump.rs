@@ -0,0 +1,62 @@
+use crate::entries::BootableEntry;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use anyhow::{Context, Result};
+use edera_sprout_config::RootConfiguration;
+use log::info;
+use toml::map::Map;
+use toml::{Table, Value};
+
+/// Converts the bootable `entry` into a [Value] that describes it after stamping.
+fn entry_value(entry: &BootableEntry) -> Value {
+    let context = entry.context();
+    let mut table = Table::new();
+    table.insert(
+        "title".to_string(),
+        Value::String(entry.title().to_string()),
+    );
+    table.insert("default".to_string(), Value::Boolean(entry.is_default()));
+    table.insert(
+        "sort-key".to_string(),
+        Value::String(entry.sort_key().to_string()),
+    );
+
+    // Stamp the actions with the entry context so the actions that would be run are shown.
+    let actions = entry
+        .declaration()
+        .actions
+        .iter()
+        .map(|action| Value::String(context.stamp(action)))
+        .collect::<Vec<_>>();
+    table.insert("actions".to_string(), Value::Array(actions));
+
+    // Include all the values of the entry context, including the inherited values.
+    let values = context
+        .all_values()
+        .into_iter()
+        .map(|(key, value)| (key, Value::String(value)))
+        .collect::<Map<String, Value>>();
+    table.insert("values".to_string(), Value::Table(values));
+    Value::Table(table)
+}
+
+/// Logs the effective `config` and the assembled `entries` as TOML.
+/// The `config` should be the configuration after autoconfiguration is applied,
+/// and the `entries` should be fully generated and stamped.
+pub fn dump(config: &RootConfiguration, entries: &[BootableEntry]) -> Result<()> {
+    // Serialize the effective configuration.
+    let config = toml::to_string(config).context("unable to serialize effective configuration")?;
+
+    // Serialize the assembled entries, keyed by their names.
+    let mut table = Table::new();
+    for entry in entries {
+        table.insert(entry.name().to_string(), entry_value(entry));
+    }
+    let mut assembled = Table::new();
+    assembled.insert("assembled-entries".to_string(), Value::Table(table));
+    let assembled = toml::to_string(&assembled).context("unable to serialize assembled entries")?;
+
+    // The logger writes every line of the message separately.
+    info!("effective configuration:\n{}\n{}", config, assembled);
+    Ok(())
+}
@@ -0,0 +1,2 @@
+// Include the generated embedded configuration section in this file.
+include!(concat!(env!("OUT_DIR"), "/config.generated.rs"));
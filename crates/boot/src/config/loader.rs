@@ -1,27 +1,105 @@
+use crate::config::{embedded, signing_key};
 use crate::options::SproutOptions;
+use alloc::format;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
-use anyhow::{Context, Result, bail};
+use anyhow::{Context, Result};
 use core::ops::Deref;
-use edera_sprout_config::{RootConfiguration, latest_version};
+use core::time::Duration;
+use edera_sprout_config::RootConfiguration;
+use edera_sprout_parsing::url::is_http_url;
+use edera_sprout_parsing::{empty_is_none, unique_hash};
+use eficore::deadline;
 use eficore::platform::tpm::PlatformTpm;
-use log::info;
-use toml::Value;
-use uefi::proto::device_path::LoadedImageDevicePath;
+use eficore::secure::SecureBoot;
+use eficore::services::services;
+use eficore::variables::VariableController;
+use log::{info, warn};
+use uefi::proto::device_path::{DevicePath, LoadedImageDevicePath};
 
-/// Loads the raw configuration from the sprout config file as data.
-fn load_raw_config(options: &SproutOptions) -> Result<Vec<u8>> {
+/// Default configuration file path.
+const DEFAULT_CONFIG_PATH: &str = "\\sprout.toml";
+
+/// The name of the Sprout variable that can override the configuration file path.
+const CONFIG_PATH_VARIABLE: &str = "SproutConfigPath";
+
+/// The directory on the ESP that caches configurations downloaded from URLs.
+const CONFIG_CACHE_DIRECTORY: &str = "\\sprout\\cache";
+
+/// The timeout in seconds for downloading a configuration from a URL,
+/// after which the cached copy is used.
+const CONFIG_DOWNLOAD_TIMEOUT: u64 = 30;
+
+/// The path of the cached copy of the configuration downloaded from `url`.
+fn config_cache_path(url: &str) -> String {
+    format!("{}\\{}.toml", CONFIG_CACHE_DIRECTORY, unique_hash(url))
+}
+
+/// Downloads the configuration at `url`, caching it on the ESP of `image_path`.
+/// If the download fails, the last cached copy is used instead, so the machine can still boot
+/// with the last configuration that was downloaded while the network is unavailable.
+/// Downloads are refused while the ESP manifest is enforced, so the cached copy, which can't be
+/// in the manifest, is never read then.
+fn fetch_config(image_path: &DevicePath, url: &str) -> Result<Vec<u8>> {
+    let cache_path = config_cache_path(url);
+    let download = deadline::with_deadline(Duration::from_secs(CONFIG_DOWNLOAD_TIMEOUT), || {
+        eficore::http::download(url, |_, _| {})
+    });
+    let error = match download {
+        Ok(content) => {
+            // A stale cache only matters once the network is unavailable, so this is not fatal.
+            let cached = services()
+                .read_file_atomic(Some(image_path), &cache_path)
+                .ok();
+            if cached.as_deref() != Some(content.as_slice())
+                && let Err(error) =
+                    services().write_file_atomic(Some(image_path), &cache_path, &content)
+            {
+                warn!("unable to cache configuration from {}: {:#}", url, error);
+            }
+            return Ok(content);
+        }
+        Err(error) => error,
+    };
+
+    warn!(
+        "unable to download configuration, using cached copy {}: {:#}",
+        cache_path, error
+    );
+    services()
+        .read_file_atomic(Some(image_path), &cache_path)
+        .context(format!(
+            "unable to download configuration from {} and no cached copy is available",
+            url
+        ))
+}
+
+/// Loads the raw configuration from the configuration file at `path` as data.
+fn load_raw_config(path: &str) -> Result<Vec<u8>> {
     // Open the LoadedImageDevicePath protocol to get the path to the current image.
     let current_image_device_path_protocol =
         uefi::boot::open_protocol_exclusive::<LoadedImageDevicePath>(uefi::boot::image_handle())
             .context("unable to get loaded image device path")?;
     // Acquire the device path as a boxed device path.
-    let path = current_image_device_path_protocol.deref().to_boxed();
+    let image_path = current_image_device_path_protocol.deref().to_boxed();
 
-    info!("configuration file: {}", options.config);
+    info!("configuration file: {}", path);
 
-    // Read the contents of the sprout config file.
-    let content = eficore::path::read_file_contents(Some(&path), &options.config)
-        .context("unable to read sprout config file")?;
+    // Configurations from URLs are not in the ESP manifest and are not authenticated, so
+    // anyone who can set the path or an include could supply the configuration, which would
+    // undermine Secure Boot. Only configurations on disk are loaded while it is enabled.
+    let secure_boot = is_http_url(path)
+        && SecureBoot::enabled().context("unable to determine Secure Boot status")?;
+    eficore::manifest::check_download(path, secure_boot)?;
+
+    // Read the contents of the sprout config file, downloading it if it is a URL.
+    let content = if is_http_url(path) {
+        fetch_config(&image_path, path)?
+    } else {
+        services()
+            .read_file(Some(&image_path), path)
+            .context("unable to read sprout config file")?
+    };
 
     // Measure the sprout.toml into the TPM, if needed and possible.
     PlatformTpm::log_event(
@@ -35,33 +113,93 @@ fn load_raw_config(options: &SproutOptions) -> Result<Vec<u8>> {
     Ok(content)
 }
 
+/// Determines whether the configuration file at `path` exists.
+fn config_exists(path: &str) -> Result<bool> {
+    // A configuration URL always exists, as it falls back to a cached copy.
+    if is_http_url(path) {
+        return Ok(true);
+    }
+
+    // Open the LoadedImageDevicePath protocol to get the path to the current image.
+    let current_image_device_path_protocol =
+        uefi::boot::open_protocol_exclusive::<LoadedImageDevicePath>(uefi::boot::image_handle())
+            .context("unable to get loaded image device path")?;
+    // Acquire the device path as a boxed device path.
+    let image_path = current_image_device_path_protocol.deref().to_boxed();
+
+    // Resolve the path to the configuration file and check if it exists.
+    let resolved = eficore::path::resolve_path(Some(&image_path), path)
+        .context("unable to resolve sprout config path")?;
+    resolved.exists()
+}
+
+/// Determines the path to the configuration file to load.
+/// The `--config` option takes precedence over the SproutConfigPath variable,
+/// which takes precedence over the default configuration path.
+fn config_path(options: &SproutOptions) -> Result<String> {
+    // If the path is specified in the options, use it.
+    if let Some(ref path) = options.config {
+        return Ok(path.clone());
+    }
+
+    // If the path is specified by the SproutConfigPath variable, use it.
+    let variable = VariableController::SPROUT
+        .get_cstr16(CONFIG_PATH_VARIABLE)
+        .context("unable to get configuration path variable")?;
+    if let Some(path) = empty_is_none(variable) {
+        info!(
+            "configuration path overridden by {} variable",
+            CONFIG_PATH_VARIABLE
+        );
+        return Ok(path);
+    }
+
+    // Otherwise, use the default configuration path.
+    Ok(DEFAULT_CONFIG_PATH.to_string())
+}
+
 /// Loads the [RootConfiguration] for Sprout.
 pub fn load(options: &SproutOptions) -> Result<RootConfiguration> {
-    // Load the raw configuration from the sprout config file.
-    let content = load_raw_config(options)?;
-    // Parse the raw configuration into a toml::Value which can represent any TOML file.
-    let value: Value = toml::from_slice(&content).context("unable to parse sprout config file")?;
-
-    // Check the version of the configuration without parsing the full configuration.
-    let version = value
-        .get("version")
-        .cloned()
-        .unwrap_or_else(|| Value::Integer(latest_version() as i64));
-
-    // Parse the version into an u32.
-    let version: u32 = version
-        .try_into()
-        .context("unable to get configuration version")?;
-
-    // Check if the version is supported.
-    if version != latest_version() {
-        bail!("unsupported configuration version: {}", version);
+    // Determine the path to the configuration file.
+    let path = config_path(options)?;
+
+    // Report the embedded trust anchor for configuration signatures, if there is one.
+    if let Some(fingerprint) = signing_key::embedded_signing_key_fingerprint() {
+        info!("configuration signing key: sha256:{}", fingerprint);
+    }
+
+    // If the configuration file does not exist, use the embedded configuration if there is one.
+    let embedded = match embedded::embedded_config() {
+        Some(embedded) if !config_exists(&path)? => Some(embedded),
+        _ => None,
+    };
+
+    // Load the configuration and all the included configurations.
+    // At this point, each configuration file has been migrated to the latest version.
+    let parsed = if let Some(embedded) = embedded {
+        info!("configuration file not found, using embedded configuration");
+
+        // Measure the embedded configuration into the TPM, if needed and possible.
+        PlatformTpm::log_event(
+            PlatformTpm::PCR_BOOT_LOADER_CONFIG,
+            embedded,
+            "sprout: embedded configuration",
+        )
+        .context("unable to measure the embedded configuration into the TPM")?;
+
+        edera_sprout_config::loader::parse(embedded, "embedded configuration", load_raw_config)?
+    } else {
+        let content = load_raw_config(&path)?;
+        edera_sprout_config::loader::parse(&content, &path, load_raw_config)?
+    };
+
+    // Report any warnings, such as migrations of older configuration versions.
+    for warning in &parsed.warnings {
+        warn!("{}", warning);
     }
 
-    // If the version is supported, parse the full configuration.
-    let config: RootConfiguration = value
-        .try_into()
-        .context("unable to parse sprout.toml file")?;
+    // Parse the full configuration.
+    let config = edera_sprout_config::loader::into_configuration(parsed.value)?;
 
     // Return the parsed configuration.
     Ok(config)
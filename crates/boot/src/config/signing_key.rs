@@ -0,0 +1,14 @@
+// Include the generated signing key section in this file.
+include!(concat!(env!("OUT_DIR"), "/pubkey.generated.rs"));
+
+/// Computes the fingerprint of the embedded signing key, which is the SHA-256 digest of the
+/// DER public key as lowercase hex. This matches `openssl pkey -pubin -outform der | sha256sum`.
+pub fn embedded_signing_key_fingerprint() -> Option<alloc::string::String> {
+    let digest = eficore::hash::sha256(embedded_signing_key()?);
+    Some(
+        digest
+            .iter()
+            .map(|byte| alloc::format!("{:02x}", byte))
+            .collect(),
+    )
+}
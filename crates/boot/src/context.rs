@@ -6,9 +6,13 @@ use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use anyhow::anyhow;
 use anyhow::{Result, bail};
+use core::cell::RefCell;
 use edera_sprout_config::actions::ActionDeclaration;
-use edera_sprout_parsing::stamp_values;
+use edera_sprout_parsing::scope::{ValueScope, resolve};
+use edera_sprout_parsing::{stamp_values, unescape_values};
+use eficore::cleanup::Cleanups;
 use eficore::platform::timer::PlatformTimer;
+use eficore::platform::timer::report::TimingReport;
 use uefi::proto::device_path::DevicePath;
 
 /// The maximum number of iterations that can be performed in [SproutContext::finalize].
@@ -21,26 +25,32 @@ pub struct RootContext {
     actions: BTreeMap<String, ActionDeclaration>,
     /// The device path of the loaded Sprout image.
     loaded_image_path: Option<Box<DevicePath>>,
-    /// Platform timer started at the beginning of the boot process.
-    timer: PlatformTimer,
+    /// Timing report of the boot process, with the platform timer started at its beginning.
+    timing: TimingReport,
     /// The global options of Sprout.
     options: SproutOptions,
+    /// The cleanup scopes of the action groups that are executing, innermost last.
+    cleanup_scopes: RefCell<Vec<Cleanups>>,
+    /// The names of the actions that are executing, outermost first.
+    executing_actions: RefCell<Vec<String>>,
 }
 
 impl RootContext {
     /// Creates a new root context with the `loaded_image_device_path` which will be stored
-    /// in the context for easy access. We also provide a `timer` which is used to measure elapsed
-    /// time for the bootloader.
+    /// in the context for easy access. We also provide a `timing` report which is used to
+    /// measure elapsed time for the bootloader.
     pub fn new(
         loaded_image_device_path: Box<DevicePath>,
-        timer: PlatformTimer,
+        timing: TimingReport,
         options: SproutOptions,
     ) -> Self {
         Self {
             actions: BTreeMap::new(),
-            timer,
+            timing,
             loaded_image_path: Some(loaded_image_device_path),
             options,
+            cleanup_scopes: RefCell::new(Vec::new()),
+            executing_actions: RefCell::new(Vec::new()),
         }
     }
 
@@ -56,7 +66,12 @@ impl RootContext {
 
     /// Access the platform timer that is started at the beginning of the boot process.
     pub fn timer(&self) -> &PlatformTimer {
-        &self.timer
+        self.timing.timer()
+    }
+
+    /// Access the timing report that records the named spans of the boot process.
+    pub fn timing(&self) -> &TimingReport {
+        &self.timing
     }
 
     /// Access the device path of the loaded Sprout image.
@@ -70,6 +85,51 @@ impl RootContext {
     pub fn options(&self) -> &SproutOptions {
         &self.options
     }
+
+    /// Enters a new cleanup scope, which collects the cleanups released by actions
+    /// until [RootContext::exit_cleanup_scope] is called.
+    pub fn enter_cleanup_scope(&self) {
+        self.cleanup_scopes.borrow_mut().push(Cleanups::new());
+    }
+
+    /// Exits the innermost cleanup scope, returning the cleanups that were released into it.
+    pub fn exit_cleanup_scope(&self) -> Cleanups {
+        self.cleanup_scopes.borrow_mut().pop().unwrap_or_default()
+    }
+
+    /// Marks the action with the `name` as executing until [RootContext::exit_action] is called.
+    /// Actions can execute other actions as fallbacks or in groups, so an action that is
+    /// already executing would execute itself again forever, which is an error instead.
+    pub fn enter_action(&self, name: &str) -> Result<()> {
+        let mut executing = self.executing_actions.borrow_mut();
+        if executing.iter().any(|action| action == name) {
+            let chain = executing
+                .iter()
+                .map(String::as_str)
+                .chain([name])
+                .collect::<Vec<_>>()
+                .join("' -> '");
+            bail!("action '{}' is already executing: '{}'", name, chain);
+        }
+        executing.push(name.to_string());
+        Ok(())
+    }
+
+    /// Marks the innermost executing action as done.
+    pub fn exit_action(&self) {
+        self.executing_actions.borrow_mut().pop();
+    }
+
+    /// Releases the `cleanups` of an action once it is done with its resources.
+    /// If a cleanup scope is active, the cleanups are deferred until the scope is exited,
+    /// otherwise they are run immediately.
+    pub fn release_cleanups(&self, mut cleanups: Cleanups) {
+        if let Some(scope) = self.cleanup_scopes.borrow_mut().last_mut() {
+            scope.adopt(cleanups);
+            return;
+        }
+        cleanups.run();
+    }
 }
 
 /// A context of Sprout. This is passed around different parts of Sprout and represents
@@ -81,7 +141,9 @@ impl RootContext {
 pub struct SproutContext {
     root: Rc<RootContext>,
     parent: Option<Rc<SproutContext>>,
-    values: BTreeMap<String, String>,
+    /// The values of this context, with their namespace, if any.
+    /// Values in a namespaced context can also be referenced as `namespace.key`.
+    scope: ValueScope,
 }
 
 impl SproutContext {
@@ -90,7 +152,7 @@ impl SproutContext {
         Self {
             root: Rc::new(root),
             parent: None,
-            values: BTreeMap::new(),
+            scope: ValueScope::default(),
         }
     }
 
@@ -104,29 +166,32 @@ impl SproutContext {
         Rc::get_mut(&mut self.root)
     }
 
+    /// Sets the `namespace` of the values in this context.
+    /// Generators use this so that their values can be referenced unambiguously,
+    /// like `$bls.version`, even if another value named `version` exists.
+    /// The values can still be referenced without the namespace.
+    pub fn set_namespace(&mut self, namespace: impl ToString) {
+        self.scope.namespace = Some(namespace.to_string());
+    }
+
+    /// Iterates over the scopes of this context and its parents, from the innermost.
+    fn scopes(&self) -> impl Iterator<Item = &ValueScope> {
+        core::iter::successors(Some(self), |context| context.parent.as_deref())
+            .map(|context| &context.scope)
+    }
+
     /// Retrieve the value specified by `key` from this context or its parents.
+    /// The `key` can be namespaced with the namespace of the context that holds the value.
     /// Returns `None` if the value is not found.
     pub fn get(&self, key: impl AsRef<str>) -> Option<&String> {
-        self.values.get(key.as_ref()).or_else(|| {
-            self.parent
-                .as_ref()
-                .and_then(|parent| parent.get(key.as_ref()))
-        })
+        resolve(self.scopes(), key.as_ref())
     }
 
     /// Collects all keys that are present in this context or its parents.
     /// This is useful for iterating over all keys in a context.
     pub fn all_keys(&self) -> Vec<String> {
-        let mut keys = BTreeSet::new();
-
-        for key in self.values.keys() {
-            keys.insert(key.clone());
-        }
-
-        if let Some(parent) = &self.parent {
-            keys.extend(parent.all_keys());
-        }
-        keys.into_iter().collect()
+        let keys = self.scopes().flat_map(ValueScope::keys);
+        keys.collect::<BTreeSet<_>>().into_iter().collect()
     }
 
     /// Collects all values that are present in this context or its parents.
@@ -145,7 +210,8 @@ impl SproutContext {
     /// Sets the value `key` to the value specified by `value` in this context.
     /// If the parent context has this key, this will override that key.
     pub fn set(&mut self, key: impl AsRef<str>, value: impl ToString) {
-        self.values
+        self.scope
+            .values
             .insert(key.as_ref().to_string(), value.to_string());
     }
 
@@ -153,7 +219,7 @@ impl SproutContext {
     /// These values will take precedence over its parent context.
     pub fn insert(&mut self, values: &BTreeMap<String, String>) {
         for (key, value) in values {
-            self.values.insert(key.clone(), value.clone());
+            self.scope.values.insert(key.clone(), value.clone());
         }
     }
 
@@ -164,7 +230,7 @@ impl SproutContext {
         Self {
             root: self.root.clone(),
             parent: Some(self.clone()),
-            values: BTreeMap::new(),
+            scope: ValueScope::default(),
         }
     }
 
@@ -179,6 +245,8 @@ impl SproutContext {
     /// Finalizes a context by producing a context with no parent that contains all the values
     /// of all parent contexts merged. This makes it possible to ensure [SproutContext] has no
     /// inheritance with other [SproutContext]s. It will still contain a [RootContext] however.
+    /// Escaped dollar signs are preserved in the finalized values, and are only removed
+    /// by [Self::stamp], so that they are never mistaken for value references.
     pub fn finalize(&self) -> Result<SproutContext> {
         // Collect all the values from the context and its parents.
         let mut current_values = self.all_values();
@@ -216,15 +284,18 @@ impl SproutContext {
         Ok(Self {
             root: self.root.clone(),
             parent: None,
-            values: current_values,
+            scope: ValueScope {
+                namespace: None,
+                values: current_values,
+            },
         })
     }
 
     /// Stamps the input `text` with all the values in this [SproutContext] and it's parents.
     /// For example, if this context contains {"a":"b"}, and the text "hello\\$a", it will produce
-    /// "hello\\b" as an output string.
+    /// "hello\\b" as an output string. Escaped dollar signs (`$$`) produce a literal `$`.
     pub fn stamp(&self, text: impl AsRef<str>) -> String {
-        stamp_values(&self.all_values(), text.as_ref()).1
+        unescape_values(&stamp_values(&self.all_values(), text.as_ref()).1)
     }
 
     /// Stamps all the items from the iterator `input` with all the values in this [SproutContext]
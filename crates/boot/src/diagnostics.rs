@@ -0,0 +1,114 @@
+use crate::entries::BootableEntry;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use anyhow::Result;
+use eficore::bootloader_interface::BootloaderInterface;
+use eficore::filesystem::FilesystemScan;
+use eficore::partition::PartitionMetadata;
+use eficore::variables::VariableController;
+use log::info;
+use uefi::proto::device_path::text::{AllowShortcuts, DisplayOnly};
+
+/// Logs all the filesystems discovered in the UEFI stack.
+/// This includes the device path, volume label, and partition GUIDs or MBR partition type
+/// of each filesystem.
+/// Failing to query a property of a filesystem is not fatal, as this is a diagnostic listing.
+pub fn list_filesystems() -> Result<()> {
+    // Find all the filesystems inside the UEFI stack.
+    let mut scan = FilesystemScan::new()?;
+    let handles = scan.handles();
+
+    info!("discovered {} filesystem(s):", handles.len());
+    for (index, handle) in handles.into_iter().enumerate() {
+        // Fetch the device path of the filesystem.
+        let path = scan.root(handle)?.to_boxed();
+        let path_text = path
+            .to_string16(DisplayOnly(false), AllowShortcuts(false))
+            .map(|path| path.to_string())
+            .unwrap_or_else(|_| "<unknown>".to_string());
+
+        // Fetch the volume label and partition metadata, if available.
+        let label = scan
+            .label(handle)
+            .unwrap_or_else(|_| "<unknown>".to_string());
+        let partition = match eficore::partition::partition_metadata(&path) {
+            Ok(Some(PartitionMetadata::Gpt {
+                unique_guid,
+                type_guid,
+            })) => format!(
+                "partition uuid: {}\n  partition type uuid: {}",
+                unique_guid, type_guid
+            ),
+            Ok(Some(PartitionMetadata::Mbr {
+                disk_signature,
+                partition_type,
+            })) => format!(
+                "mbr disk signature: {}\n  mbr partition type: {:#04x}",
+                disk_signature
+                    .map(|signature| format!("{:08x}", signature))
+                    .unwrap_or_else(|| "<none>".to_string()),
+                partition_type
+            ),
+            _ => "partition: <none>".to_string(),
+        };
+
+        info!(
+            "filesystem {}: {}\n  label: {}\n  {}",
+            index, path_text, label, partition
+        );
+    }
+    Ok(())
+}
+
+/// Logs all the assembled boot `entries` in menu order.
+/// This includes the title, sort key, and stamped actions of each entry.
+pub fn list_entries(entries: &[BootableEntry]) {
+    info!("assembled {} entries:", entries.len());
+    for entry in entries {
+        // Stamp the actions with the entry context so the actions that would be run are shown.
+        let context = entry.context();
+        let actions = entry
+            .declaration()
+            .actions
+            .iter()
+            .map(|action| context.stamp(action))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let default = if entry.is_default() { " (default)" } else { "" };
+        info!(
+            "entry {}{}: {}\n  sort key: {}\n  actions: {}",
+            entry.name(),
+            default,
+            entry.title(),
+            entry.sort_key(),
+            actions
+        );
+    }
+}
+
+/// Logs the Sprout variables and the bootloader interface variables that are set,
+/// with the size and attributes of each variable.
+pub fn list_variables() -> Result<()> {
+    let sets = [
+        (
+            "sprout",
+            VariableController::SPROUT.iter()?.collect::<Vec<_>>(),
+        ),
+        (
+            "bootloader interface",
+            BootloaderInterface::variables()?.collect::<Vec<_>>(),
+        ),
+    ];
+    for (vendor, variables) in sets {
+        info!("{} variables: {}", vendor, variables.len());
+        for variable in variables {
+            info!(
+                "  {}: {} byte(s), attributes {:?}",
+                variable.name, variable.size, variable.attributes
+            );
+        }
+    }
+    Ok(())
+}
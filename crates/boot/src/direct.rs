@@ -0,0 +1,71 @@
+use crate::options::SproutOptions;
+use alloc::format;
+use alloc::string::String;
+use anyhow::{Context, Result};
+use edera_sprout_config::RootConfiguration;
+use eficore::bootloader_interface::{BootloaderInterface, BootloaderInterfaceTimeout};
+use eficore::hibernate::Hibernation;
+
+/// Determines whether the entry forced by `--boot` can be booted directly from `config`,
+/// without autoconfiguration or generators, which scan every filesystem on the system.
+///
+/// This is only the case when the forced entry is a static entry of the configuration,
+/// and nothing else could change which entry is booted or show the boot menu.
+/// Returns the name of the static entry to boot, if any.
+pub fn static_entry(options: &SproutOptions, config: &RootConfiguration) -> Result<Option<String>> {
+    // Without --boot, or with the boot menu forced, any entry could be selected.
+    let Some(ref needle) = options.boot else {
+        return Ok(None);
+    };
+    if options.force_menu || options.is_diagnostic() {
+        return Ok(None);
+    }
+
+    // Partial and index matches depend on the generated entries and their order,
+    // so only an exact name or title match can be resolved without them.
+    if needle.ends_with('*') || needle.parse::<usize>().is_ok() {
+        return Ok(None);
+    }
+    let Some(name) = config
+        .entries
+        .iter()
+        .find(|(name, entry)| *name == needle || entry.title == *needle)
+        .map(|(name, _)| name.clone())
+    else {
+        return Ok(None);
+    };
+
+    // The bootloader interface and hibernation can override the forced entry
+    // or show the boot menu, which may need the generated entries.
+    if BootloaderInterface::has_oneshot_entry()?
+        || matches!(
+            BootloaderInterface::get_timeout()?,
+            BootloaderInterfaceTimeout::MenuForce
+        )
+        || Hibernation::resume_pending().context("unable to determine hibernation state")?
+    {
+        return Ok(None);
+    }
+
+    Ok(Some(name))
+}
+
+/// Reduces `config` to what is needed to boot a static entry directly.
+/// Autoconfiguration is disabled and generators are removed. Extractors whose values
+/// are not referenced are removed, as extractors like `filesystem-device-match`
+/// scan every filesystem. Static entries are kept, as the recovery menu and the
+/// fallback entry can still use them.
+pub fn reduce(config: &mut RootConfiguration) -> Result<()> {
+    config.options.autoconfigure = false;
+    config.generators.clear();
+
+    // Extractors can not reference each other, so any reference to an extracted value
+    // is in the remaining configuration. Serializing it finds references in any field.
+    let mut extractors = core::mem::take(&mut config.extractors);
+    let text = toml::to_string(&config).context("unable to serialize configuration")?;
+    extractors.retain(|key, _| {
+        text.contains(&format!("${}", key)) || text.contains(&format!("${{{}", key))
+    });
+    config.extractors = extractors;
+    Ok(())
+}
@@ -1,29 +1,65 @@
 use crate::context::SproutContext;
-use alloc::collections::BTreeMap;
+use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::format;
 use alloc::rc::Rc;
 use alloc::string::String;
-use anyhow::{Context, Result};
-use edera_sprout_config::drivers::DriverDeclaration;
+use anyhow::{Context, Result, anyhow};
+use edera_sprout_config::drivers::{DriverDeclaration, load_order};
 use eficore::loader::source::ImageSource;
 use eficore::loader::{ImageLoadRequest, ImageLoader};
-use log::info;
-use uefi::boot::SearchType;
+use eficore::platform::tpm::PlatformTpm;
+use log::{info, warn};
+use spin::Mutex;
+
+/// The stamped paths of the drivers that were already started.
+/// Sprout runs again when the boot menu rescans for entries, and starting a driver
+/// a second time would bind it to its hardware twice.
+static LOADED_DRIVERS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
 
 /// Loads the driver specified by the `driver` declaration.
-fn load_driver(context: Rc<SproutContext>, driver: &DriverDeclaration) -> Result<()> {
+/// Returns false if the driver was already started, in which case it is skipped.
+fn load_driver(context: Rc<SproutContext>, driver: &DriverDeclaration) -> Result<bool> {
+    // Skip the driver if it was already started by an earlier run of Sprout.
+    let path = context.stamp(&driver.path);
+    if LOADED_DRIVERS.lock().contains(&path) {
+        return Ok(false);
+    }
+
     // Acquire the handle and device path of the loaded image.
     let sprout_image = uefi::boot::image_handle();
 
     // Resolve the path to the driver image.
-    let resolved = eficore::path::resolve_path(
-        Some(context.root().loaded_image_path()?),
-        context.stamp(&driver.path),
+    let resolved = eficore::path::resolve_path(Some(context.root().loaded_image_path()?), &path)
+        .context("unable to resolve path to driver")?;
+
+    // Read the driver image once, so the verified and measured data is the data that is loaded.
+    let data = resolved
+        .read_file_pages()
+        .context("unable to read driver image")?;
+
+    // Refuse drivers for another architecture before they are verified and measured.
+    eficore::pe::check_native(&data).context("unable to load driver image")?;
+
+    // Verify the driver before it runs, as drivers dropped onto the ESP are not otherwise
+    // covered by the Secure Boot checks of the shim.
+    ImageLoader::verify(&data, Some(&resolved)).context("unable to verify driver image")?;
+
+    // Measure the driver into the TPM, if needed and possible.
+    PlatformTpm::log_event(
+        PlatformTpm::PCR_BOOT_LOADER_CODE,
+        &data,
+        &format!("sprout: driver {}", path),
     )
-    .context("unable to resolve path to driver")?;
+    .context("unable to measure driver image into the TPM")?;
 
-    // Create an image load request with the current image and the resolved path.
-    let request = ImageLoadRequest::new(sprout_image, ImageSource::ResolvedPath(&resolved));
+    // Create an image load request with the current image and the driver image data.
+    let request = ImageLoadRequest::new(
+        sprout_image,
+        ImageSource::DataBuffer {
+            path: Some(&resolved),
+            buffer: &data,
+        },
+    );
 
     // Load the driver image using the image loader support module.
     // It will determine if the image needs to be loaded via the shim or can be loaded directly.
@@ -33,49 +69,79 @@ fn load_driver(context: Rc<SproutContext>, driver: &DriverDeclaration) -> Result
     // There is no guarantee that the driver will actually return control as it is
     // just a standard EFI image.
     uefi::boot::start_image(*image.handle()).context("unable to start driver image")?;
+    LOADED_DRIVERS.lock().insert(path);
 
-    Ok(())
+    Ok(true)
 }
 
-/// Reconnects all handles to their controllers.
-/// This is effectively a UEFI stack reload in a sense.
-/// After we load all the drivers, we need to reconnect all of their handles
-/// so that filesystems are recognized again.
-fn reconnect() -> Result<()> {
-    // Locate all of the handles in the UEFI stack.
-    let handles = uefi::boot::locate_handle_buffer(SearchType::AllHandles)
-        .context("unable to locate handles buffer")?;
-
-    for handle in handles.iter() {
-        // Ignore the result as there is nothing we can do if reconnecting a controller fails.
-        // This is also likely to fail in some cases but should fail safely.
-        let _ = uefi::boot::connect_controller(*handle, None, None, true);
-    }
-
-    Ok(())
-}
-
-/// Load all the drivers specified in `drivers`.
-/// There is no driver order currently. This will reconnect all the controllers
-/// to all handles if at least one driver was loaded.
+/// Load all the drivers specified in `drivers`, each after the drivers in its `after` list.
+/// A driver that is not required and fails to load is skipped with a warning, along with the
+/// drivers that depend on it. This will reconnect all the controllers to all handles if at
+/// least one driver that was loaded should be connected.
+/// Drivers without their own `connect` setting are connected if `connect` is set.
 pub fn load(
     context: Rc<SproutContext>,
     drivers: &BTreeMap<String, DriverDeclaration>,
+    connect: bool,
 ) -> Result<()> {
     // If there are no drivers, we don't need to do anything.
     if drivers.is_empty() {
         return Ok(());
     }
 
+    // In dry run mode, drivers are not loaded as they can modify the firmware environment.
+    if context.root().options().dry_run {
+        for (name, driver) in drivers {
+            info!(
+                "dry run: skipping driver {}: {}",
+                name,
+                context.stamp(&driver.path)
+            );
+        }
+        return Ok(());
+    }
+
     info!("loading drivers");
 
-    // Load all the drivers in no particular order.
-    for (name, driver) in drivers {
-        load_driver(context.clone(), driver).context(format!("unable to load driver: {}", name))?;
+    // Load the drivers in their dependency order.
+    let order = load_order(drivers).map_err(|error| anyhow!(error))?;
+    let mut failed = BTreeSet::new();
+    let mut needs_connect = false;
+    for name in order {
+        let driver = &drivers[name];
+
+        // A driver can't work without the drivers it depends on.
+        let result = match driver
+            .after
+            .iter()
+            .find(|dependency| failed.contains(dependency.as_str()))
+        {
+            Some(dependency) => Err(anyhow!("dependency {} was not loaded", dependency)),
+            None => load_driver(context.clone(), driver),
+        };
+
+        match result {
+            Ok(loaded) => needs_connect |= loaded && driver.connect.unwrap_or(connect),
+            Err(error) if !driver.required => {
+                warn!("unable to load optional driver {}: {:#}", name, error);
+                failed.insert(name);
+            }
+            Err(error) => {
+                return Err(error).context(format!("unable to load required driver: {}", name));
+            }
+        }
+    }
+
+    // If no driver that was just started should be connected, the controllers are left alone.
+    // This is also the case when every driver was already started by an earlier run.
+    if !needs_connect {
+        info!("loaded drivers without connecting controllers");
+        return Ok(());
     }
 
-    // Reconnect all the controllers to all handles.
-    reconnect().context("unable to reconnect drivers")?;
+    // Reconnect all the controllers to all handles, so the drivers bind to their hardware.
+    // The drivers may have connected new filesystems, so paths are resolved again.
+    eficore::controller::connect_all().context("unable to reconnect drivers")?;
     info!("loaded drivers");
 
     // We've now loaded all the drivers, so we can return.
@@ -4,15 +4,55 @@ use alloc::string::String;
 use anyhow::{Result, bail};
 use edera_sprout_config::extractors::ExtractorDeclaration;
 
+/// The boot mode extractor.
+pub mod boot_mode;
+
+/// The date-time extractor.
+pub mod date_time;
+
+/// The disk device match extractor.
+pub mod disk_device_match;
+
+/// The file content extractor.
+pub mod file_content;
+
+/// The file list extractor.
+pub mod file_list;
+
 /// The filesystem device match extractor.
 pub mod filesystem_device_match;
 
+/// The kernel version extractor.
+pub mod kernel_version;
+
+/// The network extractor.
+pub mod network;
+
+/// The SMBIOS extractor.
+pub mod smbios;
+
 /// Extracts the value using the specified `extractor` under the provided `context`.
 /// The extractor must return a value, and if a value cannot be determined, an error
 /// should be returned.
 pub fn extract(context: Rc<SproutContext>, extractor: &ExtractorDeclaration) -> Result<String> {
     if let Some(filesystem) = &extractor.filesystem_device_match {
         filesystem_device_match::extract(context, filesystem)
+    } else if let Some(disk) = &extractor.disk_device_match {
+        disk_device_match::extract(context, disk)
+    } else if let Some(smbios) = &extractor.smbios {
+        smbios::extract(context, smbios)
+    } else if let Some(file_content) = &extractor.file_content {
+        file_content::extract(context, file_content)
+    } else if let Some(kernel_version) = &extractor.kernel_version {
+        kernel_version::extract(context, kernel_version)
+    } else if let Some(boot_mode) = &extractor.boot_mode {
+        boot_mode::extract(context, boot_mode)
+    } else if let Some(date_time) = &extractor.date_time {
+        date_time::extract(context, date_time)
+    } else if let Some(file_list) = &extractor.file_list {
+        file_list::extract(context, file_list)
+    } else if let Some(network) = &extractor.network {
+        network::extract(context, network)
     } else {
         bail!("unknown extractor configuration");
     }
@@ -0,0 +1,34 @@
+use crate::context::SproutContext;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use anyhow::{Context, Result, bail};
+use edera_sprout_config::extractors::boot_mode::{BootModeExtractor, BootModeField};
+use eficore::boot_mode;
+
+/// Extract a field describing how Sprout was started using the specified `extractor` configuration.
+pub fn extract(context: Rc<SproutContext>, extractor: &BootModeExtractor) -> Result<String> {
+    // Select the requested field.
+    let value = match extractor.field {
+        BootModeField::Medium => {
+            let path = context.root().loaded_image_path()?;
+            Some(boot_mode::boot_medium(path).name().to_string())
+        }
+        BootModeField::BootCurrent => {
+            boot_mode::boot_current_name().context("unable to read current boot entry")?
+        }
+        BootModeField::BootEntry => boot_mode::boot_current_description()
+            .context("unable to read current boot entry description")?,
+    };
+
+    if let Some(value) = value {
+        return Ok(value);
+    }
+
+    // If there is a fallback value, use it at this point.
+    if let Some(fallback) = &extractor.fallback {
+        return Ok(fallback.clone());
+    }
+
+    // Without a fallback, we can't continue, so bail.
+    bail!("boot mode field {:?} is not available", extractor.field)
+}
@@ -0,0 +1,23 @@
+use crate::context::SproutContext;
+use alloc::rc::Rc;
+use alloc::string::String;
+use anyhow::{Context, Result};
+use edera_sprout_config::extractors::date_time::DateTimeExtractor;
+use edera_sprout_parsing::datetime::format_datetime;
+use eficore::platform::clock::PlatformClock;
+
+/// Extract the current date and time using the specified `extractor` configuration.
+pub fn extract(context: Rc<SproutContext>, extractor: &DateTimeExtractor) -> Result<String> {
+    // Read the current date and time from the firmware clock.
+    let now = match (PlatformClock::now(), &extractor.fallback) {
+        (Ok(now), _) => now,
+        // If there is a fallback value, use it at this point.
+        (Err(_), Some(fallback)) => return Ok(fallback.clone()),
+        // Without a fallback, we can't continue.
+        (Err(error), None) => return Err(error).context("unable to read current date and time"),
+    };
+
+    // Format the date and time with the stamped format.
+    let format = context.stamp(&extractor.format);
+    Ok(format_datetime(&now, &format))
+}
@@ -0,0 +1,84 @@
+use crate::context::SproutContext;
+use alloc::rc::Rc;
+use alloc::string::String;
+use anyhow::{Context, Result, anyhow, bail};
+use core::str::FromStr;
+use edera_sprout_config::extractors::disk_device_match::DiskDeviceMatchExtractor;
+use uefi::Guid;
+
+/// Extract a disk device path using the specified `context` and `extractor` configuration.
+pub fn extract(context: Rc<SproutContext>, extractor: &DiskDeviceMatchExtractor) -> Result<String> {
+    // If no criteria are provided, bail with an error.
+    if extractor.has_model.is_none()
+        && extractor.has_serial.is_none()
+        && extractor.has_disk_guid.is_none()
+    {
+        bail!("at least one criteria is required for disk-device-match");
+    }
+
+    // Stamp the model and serial criteria.
+    let want_model = extractor
+        .has_model
+        .as_ref()
+        .map(|model| context.stamp(model));
+    let want_serial = extractor
+        .has_serial
+        .as_ref()
+        .map(|serial| context.stamp(serial));
+
+    // Parse the disk guid criteria.
+    let want_disk_guid = extractor
+        .has_disk_guid
+        .as_ref()
+        .map(|guid| {
+            Guid::from_str(&context.stamp(guid))
+                .map_err(|e| anyhow!("unable to parse has-disk-guid: {}", e))
+        })
+        .transpose()?;
+
+    // Find all the physical disks inside the UEFI stack.
+    let handles = eficore::disk::physical_disks().context("unable to find physical disks")?;
+
+    // Iterate over all the disks and check if they match the criteria.
+    for handle in handles {
+        // Check if the disk matches model and serial criteria.
+        if want_model.is_some() || want_serial.is_some() {
+            let identity = eficore::disk::disk_identity(handle)
+                .context("unable to fetch the identity of the disk")?;
+
+            if want_model.is_some() && identity.model != want_model {
+                continue;
+            }
+
+            if want_serial.is_some() && identity.serial != want_serial {
+                continue;
+            }
+        }
+
+        // Check if the disk matches disk guid criteria.
+        if let Some(want_disk_guid) = want_disk_guid {
+            // Disks which can't be read are skipped, as their guid is unknown.
+            let Ok(disk_guid) = eficore::disk::disk_guid(handle) else {
+                continue;
+            };
+
+            if disk_guid != Some(want_disk_guid) {
+                continue;
+            }
+        }
+
+        // If we have a match, return the device root path.
+        // The device path is opened without exclusive access, as the partition driver holds it.
+        let path = eficore::disk::disk_device_path(handle)?;
+        // Acquire the device path root as a string.
+        return eficore::path::device_path_root(&path).context("unable to get device path root");
+    }
+
+    // If there is a fallback value, use it at this point.
+    if let Some(fallback) = &extractor.fallback {
+        return Ok(fallback.clone());
+    }
+
+    // Without a fallback, we can't continue, so bail.
+    bail!("unable to find matching disk")
+}
@@ -0,0 +1,46 @@
+use crate::context::SproutContext;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use anyhow::{Context, Result, anyhow, bail};
+use edera_sprout_config::extractors::file_content::FileContentExtractor;
+use edera_sprout_parsing::regex_capture;
+use eficore::services::services;
+
+/// Reads the file specified by the `extractor` and extracts the value from its content.
+/// Returns None if the regular expression does not match the content.
+fn read_value(
+    context: &Rc<SproutContext>,
+    extractor: &FileContentExtractor,
+) -> Result<Option<String>> {
+    // Stamp the path to the file and read the file contents.
+    let path = context.stamp(&extractor.path);
+    let content = services()
+        .read_file(Some(context.root().loaded_image_path()?), &path)
+        .context("unable to read file")?;
+    let content = String::from_utf8(content).context("file content is not valid UTF-8")?;
+
+    // If a regular expression is specified, use it to extract the value.
+    let value = match extractor.regex {
+        Some(ref regex) => regex_capture(regex, &content)
+            .map_err(|error| anyhow!("unable to parse regex: {}", error))?,
+        None => Some(content),
+    };
+    Ok(value.map(|value| value.trim().to_string()))
+}
+
+/// Extract a value from the content of a file using the specified `context` and `extractor`.
+pub fn extract(context: Rc<SproutContext>, extractor: &FileContentExtractor) -> Result<String> {
+    // Reading the file might fail, in which case the fallback is used if one is provided.
+    let result = read_value(&context, extractor);
+
+    match (result, &extractor.fallback) {
+        // The value was extracted.
+        (Ok(Some(value)), _) => Ok(value),
+        // The value was not extracted, but there is a fallback value.
+        (_, Some(fallback)) => Ok(fallback.clone()),
+        // The regular expression did not match, and there is no fallback.
+        (Ok(None), None) => bail!("regex did not match file content"),
+        // The file could not be read, and there is no fallback.
+        (Err(error), None) => Err(error),
+    }
+}
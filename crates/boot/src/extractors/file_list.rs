@@ -0,0 +1,37 @@
+use crate::context::SproutContext;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use anyhow::{Context, Result};
+use edera_sprout_config::extractors::file_list::FileListExtractor;
+use edera_sprout_parsing::join_list;
+use edera_sprout_parsing::path::{glob, join};
+use eficore::services::services;
+
+/// Lists the names of the files in the directory specified by the `extractor`.
+fn list_files(context: &Rc<SproutContext>, path: &str, pattern: &str) -> Result<Vec<String>> {
+    // Collect the names of the regular files that match the pattern.
+    // The names are sorted so the list is stable across firmware implementations.
+    let names = services()
+        .list_directory(Some(context.root().loaded_image_path()?), path)
+        .context("unable to list directory")?;
+    Ok(glob(pattern, names.iter()))
+}
+
+/// Extract a list of file paths using the specified `context` and `extractor` configuration.
+pub fn extract(context: Rc<SproutContext>, extractor: &FileListExtractor) -> Result<String> {
+    // Stamp the path to the directory and the pattern.
+    let path = context.stamp(&extractor.path);
+    let pattern = context.stamp(&extractor.pattern);
+
+    let names = match (list_files(&context, &path, &pattern), &extractor.fallback) {
+        (Ok(names), _) => names,
+        // If there is a fallback value, use it at this point.
+        (Err(_), Some(fallback)) => return Ok(fallback.clone()),
+        // Without a fallback, we can't continue.
+        (Err(error), None) => return Err(error),
+    };
+
+    // Produce the full path of each file, which is the directory joined with the name.
+    Ok(join_list(names.iter().map(|name| join(&path, name))))
+}
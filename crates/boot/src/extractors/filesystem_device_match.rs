@@ -2,14 +2,11 @@ use crate::context::SproutContext;
 use alloc::rc::Rc;
 use alloc::string::String;
 use anyhow::{Context, Result, anyhow, bail};
-use core::ops::Deref;
 use core::str::FromStr;
 use edera_sprout_config::extractors::filesystem_device_match::FilesystemDeviceMatchExtractor;
-use eficore::partition::PartitionGuidForm;
-use uefi::fs::{FileSystem, Path};
-use uefi::proto::device_path::DevicePath;
-use uefi::proto::media::file::{File, FileSystemVolumeLabel};
-use uefi::proto::media::fs::SimpleFileSystem;
+use eficore::filesystem::FilesystemScan;
+use eficore::partition::{PartitionGuidForm, PartitionMetadata};
+use uefi::fs::Path;
 use uefi::{CString16, Guid};
 
 /// Extract a filesystem device path using the specified `context` and `extractor` configuration.
@@ -22,16 +19,16 @@ pub fn extract(
         && extractor.has_item.is_none()
         && extractor.has_partition_uuid.is_none()
         && extractor.has_partition_type_uuid.is_none()
+        && extractor.has_mbr_partition_type.is_none()
     {
         bail!("at least one criteria is required for filesystem-device-match");
     }
 
     // Find all the filesystems inside the UEFI stack.
-    let handles = uefi::boot::find_handles::<SimpleFileSystem>()
-        .context("unable to find filesystem handles")?;
+    let mut scan = FilesystemScan::new()?;
 
     // Iterate over all the filesystems and check if they match the criteria.
-    for handle in handles {
+    for handle in scan.handles() {
         // This defines whether a match has been found.
         let mut has_match = false;
 
@@ -42,14 +39,11 @@ pub fn extract(
                 .map_err(|e| anyhow!("unable to parse has-partition-uuid: {}", e))?;
 
             // Fetch the root of the device.
-            let root = uefi::boot::open_protocol_exclusive::<DevicePath>(handle)
-                .context("unable to fetch the device path of the filesystem")?
-                .deref()
-                .to_boxed();
+            let root = scan.root(handle)?;
 
             // Fetch the partition uuid for this filesystem.
             let partition_uuid =
-                eficore::partition::partition_guid(&root, PartitionGuidForm::Partition)
+                eficore::partition::partition_guid(root, PartitionGuidForm::Partition)
                     .context("unable to fetch the partition uuid of the filesystem")?;
 
             // Compare the partition uuid to the parsed uuid.
@@ -67,14 +61,11 @@ pub fn extract(
                 .map_err(|e| anyhow!("unable to parse has-partition-type-uuid: {}", e))?;
 
             // Fetch the root of the device.
-            let root = uefi::boot::open_protocol_exclusive::<DevicePath>(handle)
-                .context("unable to fetch the device path of the filesystem")?
-                .deref()
-                .to_boxed();
+            let root = scan.root(handle)?;
 
             // Fetch the partition type uuid for this filesystem.
             let partition_type_uuid =
-                eficore::partition::partition_guid(&root, PartitionGuidForm::PartitionType)
+                eficore::partition::partition_guid(root, PartitionGuidForm::PartitionType)
                     .context("unable to fetch the partition uuid of the filesystem")?;
             // Compare the partition type uuid to the parsed uuid.
             // If it does not match, continue to the next filesystem.
@@ -84,22 +75,39 @@ pub fn extract(
             has_match = true;
         }
 
-        // Open the filesystem protocol for this handle.
-        let mut filesystem = uefi::boot::open_protocol_exclusive::<SimpleFileSystem>(handle)
-            .context("unable to open filesystem protocol")?;
+        // Check if the partition info matches mbr partition type criteria.
+        if let Some(ref has_mbr_partition_type) = extractor.has_mbr_partition_type {
+            // Parse the partition type byte from the extractor, with an optional 0x prefix.
+            let digits = has_mbr_partition_type.trim();
+            let digits = digits
+                .strip_prefix("0x")
+                .or_else(|| digits.strip_prefix("0X"))
+                .unwrap_or(digits);
+            let parsed_type = u8::from_str_radix(digits, 16)
+                .map_err(|e| anyhow!("unable to parse has-mbr-partition-type: {}", e))?;
+
+            // Fetch the root of the device.
+            let root = scan.root(handle)?;
+
+            // Fetch the partition metadata for this filesystem.
+            let metadata = eficore::partition::partition_metadata(root)
+                .context("unable to fetch the partition metadata of the filesystem")?;
+
+            // Compare the mbr partition type to the parsed type.
+            // If it does not match, continue to the next filesystem.
+            let Some(PartitionMetadata::Mbr { partition_type, .. }) = metadata else {
+                continue;
+            };
+            if partition_type != parsed_type {
+                continue;
+            }
+            has_match = true;
+        }
 
         // Check if the filesystem matches label criteria.
         if let Some(ref label) = extractor.has_label {
-            let want_label = CString16::try_from(context.stamp(label).as_str())
-                .context("unable to convert label to CString16")?;
-            let mut root = filesystem
-                .open_volume()
-                .context("unable to open filesystem volume")?;
-            let label = root
-                .get_boxed_info::<FileSystemVolumeLabel>()
-                .context("unable to get filesystem volume label")?;
-
-            if label.volume_label() != want_label {
+            let want_label = context.stamp(label);
+            if scan.label(handle)? != want_label {
                 continue;
             }
             has_match = true;
@@ -109,7 +117,7 @@ pub fn extract(
         if let Some(ref item) = extractor.has_item {
             let want_item = CString16::try_from(context.stamp(item).as_str())
                 .context("unable to convert item to CString16")?;
-            let mut filesystem = FileSystem::new(filesystem);
+            let filesystem = scan.filesystem(handle)?;
 
             // Check the metadata of the item.
             // Ignore filesystem errors as we can't do anything useful with the error.
@@ -130,9 +138,7 @@ pub fn extract(
         }
 
         // If we have a match, return the device root path.
-        let path = uefi::boot::open_protocol_exclusive::<DevicePath>(handle)
-            .context("unable to open filesystem device path")?;
-        let path = path.deref();
+        let path = scan.root(handle)?;
         // Acquire the device path root as a string.
         return eficore::path::device_path_root(path).context("unable to get device path root");
     }
@@ -0,0 +1,35 @@
+use crate::context::SproutContext;
+use alloc::rc::Rc;
+use alloc::string::String;
+use anyhow::{Context, Result, bail};
+use edera_sprout_config::extractors::kernel_version::KernelVersionExtractor;
+use edera_sprout_parsing::kernel::kernel_version;
+use eficore::services::services;
+
+/// Extract the kernel version from the kernel image specified by the `extractor`.
+pub fn extract(context: Rc<SproutContext>, extractor: &KernelVersionExtractor) -> Result<String> {
+    // Stamp the path to the kernel image and read the image.
+    let path = context.stamp(&extractor.path);
+    let image = services()
+        .read_file(Some(context.root().loaded_image_path()?), &path)
+        .context("unable to read kernel image");
+
+    // Extract the version from the kernel image, if it was read.
+    let version = match image {
+        Ok(image) => kernel_version(&image),
+        Err(_) if extractor.fallback.is_some() => None,
+        Err(error) => return Err(error),
+    };
+
+    if let Some(version) = version {
+        return Ok(version);
+    }
+
+    // If there is a fallback value, use it at this point.
+    if let Some(fallback) = &extractor.fallback {
+        return Ok(fallback.clone());
+    }
+
+    // Without a fallback, we can't continue, so bail.
+    bail!("unable to find kernel version in {}", path)
+}
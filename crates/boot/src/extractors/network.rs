@@ -0,0 +1,59 @@
+use crate::context::SproutContext;
+use alloc::rc::Rc;
+use alloc::string::String;
+use anyhow::{Context, Result, bail};
+use edera_sprout_config::extractors::network::{NetworkExtractor, NetworkField};
+use edera_sprout_parsing::dhcp::{format_ipv4, format_mac, format_option};
+
+/// The DHCP option that contains the hostname.
+const OPTION_HOSTNAME: u8 = 12;
+
+/// The DHCP option that contains the domain name.
+const OPTION_DOMAIN_NAME: u8 = 15;
+
+/// The DHCP option that contains the DNS servers.
+const OPTION_DNS_SERVERS: u8 = 6;
+
+/// Extract a field from the network configuration using the specified `extractor` configuration.
+pub fn extract(_context: Rc<SproutContext>, extractor: &NetworkExtractor) -> Result<String> {
+    // Determine the DHCP option to extract, if the field is an option.
+    let option = match extractor.field {
+        NetworkField::Hostname => Some(OPTION_HOSTNAME),
+        NetworkField::DomainName => Some(OPTION_DOMAIN_NAME),
+        NetworkField::DnsServers => Some(OPTION_DNS_SERVERS),
+        NetworkField::DhcpOption => Some(
+            extractor
+                .option
+                .context("network extractor with the dhcp-option field requires an option")?,
+        ),
+        _ => None,
+    };
+
+    // Read the configuration of the primary network interface, if there is one.
+    let info = eficore::network::primary().context("unable to read network configuration")?;
+
+    // Select the requested field from the network configuration.
+    let value = info.and_then(|info| match (extractor.field, option) {
+        (_, Some(option)) => info
+            .options
+            .get(&option)
+            .map(|data| format_option(option, data)),
+        (NetworkField::Mac, _) => Some(format_mac(&info.mac)),
+        (NetworkField::Ip, _) => Some(format_ipv4(info.address)),
+        (NetworkField::SubnetMask, _) => Some(format_ipv4(info.subnet_mask)),
+        (NetworkField::Gateway, _) => info.gateway.map(format_ipv4),
+        _ => None,
+    });
+
+    if let Some(value) = value {
+        return Ok(value);
+    }
+
+    // If there is a fallback value, use it at this point.
+    if let Some(fallback) = &extractor.fallback {
+        return Ok(fallback.clone());
+    }
+
+    // Without a fallback, we can't continue, so bail.
+    bail!("network field {:?} is not available", extractor.field)
+}
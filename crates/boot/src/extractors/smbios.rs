@@ -0,0 +1,38 @@
+use crate::context::SproutContext;
+use alloc::rc::Rc;
+use alloc::string::String;
+use anyhow::{Context, Result, bail};
+use edera_sprout_config::extractors::smbios::{SmbiosExtractor, SmbiosField};
+use eficore::platform::smbios::PlatformSmbios;
+
+/// Extract a field from the SMBIOS tables using the specified `extractor` configuration.
+pub fn extract(_context: Rc<SproutContext>, extractor: &SmbiosExtractor) -> Result<String> {
+    // Read the SMBIOS information from the firmware, if it is available.
+    let info = PlatformSmbios::info().context("unable to read smbios tables")?;
+
+    // Select the requested field from the SMBIOS information.
+    let value = info.and_then(|info| match extractor.field {
+        SmbiosField::BiosVendor => info.bios_vendor,
+        SmbiosField::BiosVersion => info.bios_version,
+        SmbiosField::Vendor => info.vendor,
+        SmbiosField::Product => info.product,
+        SmbiosField::Version => info.version,
+        SmbiosField::Serial => info.serial,
+        SmbiosField::Uuid => info.uuid,
+        SmbiosField::Sku => info.sku,
+        SmbiosField::Family => info.family,
+        SmbiosField::AssetTag => info.asset_tag,
+    });
+
+    if let Some(value) = value {
+        return Ok(value);
+    }
+
+    // If there is a fallback value, use it at this point.
+    if let Some(fallback) = &extractor.fallback {
+        return Ok(fallback.clone());
+    }
+
+    // Without a fallback, we can't continue, so bail.
+    bail!("smbios field {:?} is not available", extractor.field)
+}
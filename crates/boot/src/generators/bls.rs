@@ -10,12 +10,11 @@ use anyhow::{Context, Result};
 use core::{cmp::Ordering, str::FromStr};
 use edera_sprout_bls::{BlsEntry, sort_bls};
 use edera_sprout_config::generators::bls::BlsConfiguration;
-use uefi::{
-    cstr16,
-    fs::{FileSystem, PathBuf},
-    proto::device_path::text::{AllowShortcuts, DisplayOnly},
-    proto::media::fs::SimpleFileSystem,
-};
+use edera_sprout_parsing::path::{join, strip_extension};
+use eficore::services::services;
+
+/// The namespace of the values provided by the BLS generator.
+pub const BLS_NAMESPACE: &str = "bls";
 
 // TODO(azenla): remove this once variable substitution is implemented.
 /// This function is used to remove the `tuned_initrd` variable from entry values.
@@ -40,64 +39,26 @@ pub fn generate(context: Rc<SproutContext>, bls: &BlsConfiguration) -> Result<Ve
     // Stamp the path to the BLS directory.
     let path = context.stamp(&bls.path);
 
-    // Resolve the path to the BLS directory.
-    let bls_resolved =
-        eficore::path::resolve_path(Some(context.root().loaded_image_path()?), &path)
-            .context("unable to resolve bls path")?;
-
-    // Construct a filesystem path to the BLS entries directory.
-    let mut entries_path = PathBuf::from(
-        bls_resolved
-            .sub_path
-            .to_string16(DisplayOnly(false), AllowShortcuts(false))
-            .context("unable to convert bls path to string")?,
-    );
-    entries_path.push(cstr16!("entries"));
-
-    // Open exclusive access to the BLS filesystem.
-    let fs =
-        uefi::boot::open_protocol_exclusive::<SimpleFileSystem>(bls_resolved.filesystem_handle)
-            .context("unable to open bls filesystem")?;
-    let mut fs = FileSystem::new(fs);
-
-    // Read the BLS entries directory.
-    let entries_iter = fs
-        .read_dir(&entries_path)
+    // Construct the path to the BLS entries directory.
+    let entries_path = join(&path, "entries");
+    let root = context.root().loaded_image_path()?;
+
+    // Read the BLS entries directory, which only lists regular files.
+    let names = services()
+        .list_directory(Some(root), &entries_path)
         .context("unable to read bls entries")?;
 
     // For each entry in the BLS entries directory, parse the entry and add it to the list.
-    for entry in entries_iter {
-        // Unwrap the entry file info.
-        let entry = entry.context("unable to read bls item entry")?;
-
-        // Skip items that are not regular files.
-        if !entry.is_regular_file() {
-            continue;
-        }
-
-        // Get the file name of the filesystem item.
-        let mut name = entry.file_name().to_string();
-
-        // Ignore files that are not .conf files.
-        if !name.to_lowercase().ends_with(".conf") {
+    for file_name in names {
+        // Remove the .conf extension, ignoring files that are not .conf files.
+        // Files that are named just ".conf" are not valid entry files and are skipped too.
+        let Some(name) = strip_extension(&file_name, "conf").map(String::from) else {
             continue;
-        }
-
-        // Remove the .conf extension.
-        name.truncate(name.len() - 5);
-
-        // Skip over files that are named just ".conf" as they are not valid entry files.
-        if name.is_empty() {
-            continue;
-        }
-
-        // Create a mutable path so we can append the file name to produce the full path.
-        let mut full_entry_path = entries_path.to_path_buf();
-        full_entry_path.push(entry.file_name());
+        };
 
         // Read the entry file.
-        let content = fs
-            .read(full_entry_path)
+        let content = services()
+            .read_file(Some(root), &join(&entries_path, &file_name))
             .context("unable to read bls file")?;
 
         // Parse the entry file as a UTF-8 string.
@@ -112,7 +73,9 @@ pub fn generate(context: Rc<SproutContext>, bls: &BlsConfiguration) -> Result<Ve
         }
 
         // Produce a new sprout context for the entry with the extracted values.
+        // The values are namespaced so they can be referenced as $bls.version and so on.
         let mut context = context.fork();
+        context.set_namespace(BLS_NAMESPACE);
 
         let title_base = entry.title().unwrap_or_else(|| name.clone());
         let chainload = entry.chainload_path().unwrap_or_default();
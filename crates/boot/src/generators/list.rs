@@ -1,22 +1,50 @@
 use crate::context::SproutContext;
 use crate::entries::BootableEntry;
+use alloc::collections::BTreeMap;
 use alloc::rc::Rc;
 use alloc::string::ToString;
 use alloc::vec::Vec;
 use anyhow::Result;
 use edera_sprout_config::generators::list::ListConfiguration;
+use edera_sprout_parsing::split_list;
+
+/// The namespace of the values provided by the list generator.
+pub const LIST_NAMESPACE: &str = "list";
 
 /// Generates a set of entries using the specified `list` configuration in the `context`.
 pub fn generate(
     context: Rc<SproutContext>,
     list: &ListConfiguration,
+) -> Result<Vec<BootableEntry>> {
+    generate_with_namespace(context, list, LIST_NAMESPACE)
+}
+
+/// Generates a set of entries using the specified `list` configuration in the `context`.
+/// The values of each entry are provided in the specified `namespace`.
+pub fn generate_with_namespace(
+    context: Rc<SproutContext>,
+    list: &ListConfiguration,
+    namespace: &str,
 ) -> Result<Vec<BootableEntry>> {
     let mut entries = Vec::new();
 
+    // Collect the value maps to generate entries from, starting with the literal values.
+    let mut combinations = list.values.clone();
+
+    // Each item of the list value produces an additional value map.
+    if let Some(ref from) = list.from {
+        let items = context.stamp(from);
+        combinations.extend(
+            split_list(&items)
+                .map(|item| BTreeMap::from([(list.item_key.clone(), item.to_string())])),
+        );
+    }
+
     // For each combination, create a new context and entry.
-    for (index, combination) in list.values.iter().enumerate() {
+    for (index, combination) in combinations.iter().enumerate() {
         let mut context = context.fork();
-        // Insert the combination into the context.
+        // Insert the combination into the context under the namespace.
+        context.set_namespace(namespace);
         context.insert(combination);
         let context = context.freeze();
 
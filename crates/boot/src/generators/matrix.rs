@@ -8,6 +8,9 @@ use edera_sprout_config::generators::list::ListConfiguration;
 use edera_sprout_config::generators::matrix::MatrixConfiguration;
 use edera_sprout_parsing::build_matrix;
 
+/// The namespace of the values provided by the matrix generator.
+pub const MATRIX_NAMESPACE: &str = "matrix";
+
 /// Generates a set of entries using the specified `matrix` configuration in the `context`.
 pub fn generate(
     context: Rc<SproutContext>,
@@ -16,11 +19,13 @@ pub fn generate(
     // Produce all the combinations of the input values.
     let combinations = build_matrix(&matrix.values);
     // Use the list generator to generate entries for each combination.
-    list::generate(
+    list::generate_with_namespace(
         context,
         &ListConfiguration {
             entry: matrix.entry.clone(),
             values: combinations,
+            ..Default::default()
         },
+        MATRIX_NAMESPACE,
     )
 }
@@ -6,23 +6,42 @@ extern crate alloc;
 use crate::{
     context::{RootContext, SproutContext},
     entries::BootableEntry,
+    menu::MenuSelection,
     options::SproutOptions,
     phases::phase,
+    recovery::{RecoveryOperation, RecoveryState},
 };
 use alloc::{collections::BTreeMap, format, string::ToString, vec::Vec};
 use anyhow::{Context, Result, bail};
-use core::{ops::Deref, time::Duration};
+use core::{ops::Deref, panic::PanicInfo, time::Duration};
 use edera_sprout_bls::compare_versions;
-use edera_sprout_config::RootConfiguration;
+use edera_sprout_config::phases::{FAILED_ACTION_KEY, PhasesConfiguration};
+use edera_sprout_config::{
+    DEFAULT_BOOT_RECORDS, DEFAULT_ERROR_DELAY_SECONDS, DEFAULT_LOG_FILE_PATH, RootConfiguration,
+    SECURE_BOOT_KEY, SPROUT_COMMIT_KEY, SPROUT_VERSION_KEY, VALUES_NAMESPACE,
+};
+use edera_sprout_parsing::escape_values;
 use eficore::{
+    beep::BeepCode,
+    boot_mode::BootMedium,
     bootloader_interface::{BootloaderInterface, BootloaderInterfaceTimeout},
+    hibernate::Hibernation,
+    logger::{self, FileSink, SerialSink},
+    panic::PanicPolicy,
     partition::PartitionGuidForm,
-    platform::{timer::PlatformTimer, tpm::PlatformTpm},
+    platform::{
+        timer::{PlatformTimer, report::TimingReport},
+        tpm::PlatformTpm,
+    },
+    power::Power,
     secure::SecureBoot,
     setup,
 };
-use log::{error, info, warn};
-use uefi::{entry, proto::device_path::LoadedImageDevicePath};
+use log::{LevelFilter, error, info, warn};
+use uefi::{
+    entry,
+    proto::device_path::{DevicePath, LoadedImageDevicePath},
+};
 use uefi_raw::Status;
 
 /// actions: Code that can be configured and executed by Sprout.
@@ -31,12 +50,24 @@ pub mod actions;
 /// autoconfigure: Autoconfigure Sprout based on the detected environment.
 pub mod autoconfigure;
 
+/// branding: Vendor branding of the boot menu.
+pub mod branding;
+
+/// build_info: Build metadata embedded in the Sprout image.
+pub mod build_info;
+
 /// config: Sprout configuration mechanism.
 pub mod config;
 
 /// context: Stored values that can be cheaply forked and cloned.
 pub mod context;
 
+/// diagnostics: Diagnostic listings of what Sprout discovered.
+pub mod diagnostics;
+
+/// direct: Boot a forced static entry without scanning filesystems.
+pub mod direct;
+
 /// drivers: EFI drivers to load and provide extra functionality.
 pub mod drivers;
 
@@ -49,6 +80,9 @@ pub mod extractors;
 /// generators: Runtime code that can generate entries with specific values.
 pub mod generators;
 
+/// manifest: Verification of the ESP against a signed manifest.
+pub mod manifest;
+
 /// menu: Display a boot menu to select an entry to boot.
 pub mod menu;
 
@@ -58,21 +92,98 @@ pub mod options;
 /// phases: Hooks into specific parts of the boot process.
 pub mod phases;
 
+/// records: Boot records written to the EFI partition.
+pub mod records;
+
+/// recovery: Recovery menu shown when an error occurs.
+pub mod recovery;
+
+/// screenshot: Capture the screen to the EFI partition.
+pub mod screenshot;
+
 /// sbat: Secure Boot Attestation section.
 pub mod sbat;
 
-/// The delay to wait for when an error occurs in Sprout.
-const DELAY_ON_ERROR: Duration = Duration::from_secs(10);
+/// squashfs: Squashfs containers mounted as read-only volumes.
+pub mod squashfs;
+
+/// stub: Boot a kernel embedded in the Sprout image, using Sprout as a UKI stub.
+pub mod stub;
+
+/// verbosity: Raise the log level at runtime with a key.
+pub mod verbosity;
+
+/// The timeout of the boot menu opened from the recovery menu.
+const RECOVERY_MENU_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Registers the `serial` and `file` log sinks if they are configured in `config`,
+/// then applies the configured level and enablement to every configured sink.
+/// The file sink writes to a path relative to the `loaded_image_path`.
+fn configure_log_sinks(config: &RootConfiguration, loaded_image_path: &DevicePath) -> Result<()> {
+    for (name, sink) in &config.options.log_sinks {
+        let level = match sink.level {
+            Some(ref level) => {
+                let Ok(level) = level.parse::<LevelFilter>() else {
+                    bail!(
+                        "unable to parse log level '{}' of log sink '{}'",
+                        level,
+                        name
+                    );
+                };
+                level
+            }
+            None => LevelFilter::Trace,
+        };
+
+        match name.as_str() {
+            logger::serial::SERIAL_SINK_NAME => {
+                logger::register(SerialSink::first()?, level);
+            }
+
+            logger::file::FILE_SINK_NAME => {
+                let path = sink.path.as_deref().unwrap_or(DEFAULT_LOG_FILE_PATH);
+                logger::register(FileSink::new(loaded_image_path, path), level);
+            }
+
+            // The other sinks are always registered, so only the level is applied.
+            _ => logger::set_sink_level(name, level)?,
+        }
+        logger::set_sink_enabled(name, sink.enabled)?;
+    }
+    Ok(())
+}
 
 /// Run Sprout, returning an error if one occurs.
-fn run() -> Result<()> {
+/// The assembled entries are stored in the recovery `state`, so they can be booted
+/// from the recovery menu if an error occurs.
+fn run(state: &mut RecoveryState) -> Result<()> {
     // For safety reasons, we will note that Secure Boot is in beta on Sprout.
-    if SecureBoot::enabled().context("unable to determine Secure Boot status")? {
+    let secure_boot = SecureBoot::enabled().context("unable to determine Secure Boot status")?;
+    if secure_boot {
         warn!("Sprout Secure Boot is in beta. Some functionality may not work as expected.");
     }
 
     // Start the platform timer.
     let timer = PlatformTimer::start();
+    state.timer = Some(timer);
+
+    // Prefix log lines with the time elapsed on the platform timer.
+    eficore::logger::set_timer(timer);
+    eficore::trace::set_timer(timer);
+
+    // Record the named spans of the boot process with the platform timer.
+    let timing = TimingReport::new(timer);
+
+    // Log the build of Sprout, which identifies it in logs collected from a fleet.
+    info!(
+        "sprout {} ({}, built {})",
+        build_info::VERSION,
+        build_info::COMMIT,
+        build_info::DATE
+    );
+
+    // Begin the boot record, which is configured once the configuration is loaded.
+    records::begin(DEFAULT_BOOT_RECORDS);
 
     // Mark the initialization of Sprout in the bootloader interface.
     BootloaderInterface::mark_init(&timer)
@@ -93,19 +204,36 @@ fn run() -> Result<()> {
     BootloaderInterface::set_tpm2_active_pcr_banks(active_pcr_banks)
         .context("unable to set tpm2 active PCR banks in bootloader interface")?;
 
+    // Connect the controllers if the firmware left the filesystems or the keyboard unconnected,
+    // which is common with minimal firmware. Failing to connect should never prevent booting.
+    if let Err(error) = eficore::controller::ensure_connected() {
+        warn!("unable to connect controllers: {:#}", error);
+    }
+
+    // Raise the log level to debug if the debug key was held while Sprout started.
+    // Failing to read the keyboard should never prevent booting.
+    match verbosity::debug_key_pending() {
+        Ok(true) => verbosity::raise_to_debug(),
+        Ok(false) => {}
+        Err(error) => warn!("unable to check for the debug key: {:#}", error),
+    }
+
+    // Verify the files on the ESP against the signed manifest, before any of them are read.
+    manifest::load(secure_boot)?;
+
     // Parse the options to the sprout executable.
-    let options = SproutOptions::parse().context("unable to parse options")?;
+    let mut options = SproutOptions::parse().context("unable to parse options")?;
 
-    // If --autoconfigure is specified, we use a stub configuration.
-    let mut config = if options.autoconfigure {
-        info!("autoconfiguration enabled, configuration file will be ignored");
-        RootConfiguration::default()
-    } else {
-        // Load the configuration of sprout.
-        // At this point, the configuration has been validated and the specified
-        // version is checked to ensure compatibility.
-        config::loader::load(&options)?
-    };
+    // Apply the log level of the options right away, so it covers loading the configuration.
+    if let Some(level) = options.log_level {
+        verbosity::set_level(level);
+    }
+
+    // Configure the environment as the options specify.
+    // Some firmware rejects these changes, which should never prevent booting.
+    if let Err(error) = setup::configure(&options.setup) {
+        warn!("unable to configure environment: {:#}", error);
+    }
 
     // Grab the sprout.efi loaded image path.
     // This is done in a block to ensure the release of the LoadedImageDevicePath protocol.
@@ -117,6 +245,28 @@ fn run() -> Result<()> {
         current_image_device_path_protocol.deref().to_boxed()
     };
 
+    // If a kernel is embedded in the Sprout image, Sprout is used as the stub of a unified
+    // kernel image and boots it directly, without a configuration or boot menu.
+    if !options.no_stub
+        && !options.is_diagnostic()
+        && let Some(image) = stub::embedded()?
+    {
+        info!("kernel embedded in sprout image, booting it directly");
+        let root = RootContext::new(loaded_image_path, timing, options);
+        return stub::boot(SproutContext::new(root).freeze(), &image);
+    }
+
+    // If --autoconfigure is specified, we use a stub configuration.
+    let mut config = if options.autoconfigure {
+        info!("autoconfiguration enabled, configuration file will be ignored");
+        RootConfiguration::default()
+    } else {
+        // Load the configuration of sprout.
+        // At this point, the configuration has been validated and the specified
+        // version is checked to ensure compatibility.
+        timing.measure("load config", || config::loader::load(&options))?
+    };
+
     // Grab the partition GUID of the ESP that sprout was loaded from.
     let loaded_image_partition_guid =
         eficore::partition::partition_guid(&loaded_image_path, PartitionGuidForm::Partition)
@@ -133,8 +283,83 @@ fn run() -> Result<()> {
     BootloaderInterface::set_loader_path(&loaded_image_path)
         .context("unable to set loader path in bootloader interface")?;
 
+    // Use the log level of the configuration unless the options override it.
+    if options.log_level.is_none()
+        && let Some(ref level) = config.options.log_level
+    {
+        let Ok(level) = level.parse::<LevelFilter>() else {
+            bail!("unable to parse log level '{}'", level);
+        };
+        verbosity::set_level(level);
+    }
+
+    // Prefix log lines with the real-time clock time if configured.
+    eficore::logger::set_wall_clock(config.options.log_wall_clock);
+
+    // Play beep codes for the boot menu and fatal errors if configured.
+    eficore::beep::set_enabled(config.options.beeps);
+
+    // Reboot after a panic is reported if configured, so headless machines can recover.
+    if let Some(delay) = config.options.panic_reboot_delay {
+        eficore::panic::set_policy(PanicPolicy::Reboot(Duration::from_secs(delay)));
+    }
+
+    // Register and configure the log sinks of the configuration.
+    configure_log_sinks(&config, &loaded_image_path).context("unable to configure log sinks")?;
+
+    // Apply the branding from the EFI partition or the branding embedded at build time.
+    branding::load(&loaded_image_path);
+
+    // Adjust the defaults with the netboot profile if Sprout was loaded over the network,
+    // as the machine is likely diskless and unattended.
+    if eficore::boot_mode::boot_medium(&loaded_image_path) == BootMedium::Network
+        && edera_sprout_config::netboot::apply(&mut config)
+    {
+        info!("sprout was loaded over the network, applying the netboot profile");
+    }
+
+    // Configure the recovery from errors.
+    state.error_delay = Duration::from_secs(
+        config
+            .options
+            .error_delay
+            .unwrap_or(DEFAULT_ERROR_DELAY_SECONDS),
+    );
+
+    // Dry runs should not leave records behind, as nothing is booted.
+    records::set_limit(if options.dry_run {
+        0
+    } else {
+        config.options.boot_records.unwrap_or(DEFAULT_BOOT_RECORDS)
+    });
+
+    // Register Sprout as a firmware boot option if requested, so restored or flashed ESPs
+    // become bootable. Removable and network boots are temporary, so they are never registered.
+    if (options.install || config.options.register_boot_option)
+        && eficore::boot_mode::boot_medium(&loaded_image_path) == BootMedium::Fixed
+    {
+        let description = branding::product_name().unwrap_or_else(|| "Sprout".to_string());
+        if let Err(error) =
+            eficore::boot_options::register(&description, &loaded_image_path, options.dry_run)
+        {
+            warn!("unable to register boot option: {}", error);
+        }
+    }
+
+    // Remove the boot options Sprout registered that were not booted in a long time.
+    if let Some(max_age) = config.options.variable_max_age
+        && let Err(error) = eficore::boot_options::remove_stale(max_age, options.dry_run)
+    {
+        warn!("unable to remove stale boot options: {}", error);
+    }
+
+    // Use the watchdog timeout of the configuration unless the options override it.
+    options.watchdog_timeout = options
+        .watchdog_timeout
+        .or(Some(config.options.watchdog_timeout));
+
     // Create the root context.
-    let mut root = RootContext::new(loaded_image_path, timer, options);
+    let mut root = RootContext::new(loaded_image_path, timing, options);
 
     // Insert the configuration actions into the root context.
     root.actions_mut().extend(config.actions.clone());
@@ -142,7 +367,16 @@ fn run() -> Result<()> {
     // Create a new sprout context with the root context.
     let mut context = SproutContext::new(root);
 
+    // Insert the built-in values into the sprout context.
+    // These are inserted first so the configuration values can override them.
+    context.set(SECURE_BOOT_KEY, secure_boot);
+    context.set(SPROUT_VERSION_KEY, build_info::VERSION);
+    context.set(SPROUT_COMMIT_KEY, build_info::COMMIT);
+
     // Insert the configuration values into the sprout context.
+    // They are in their own namespace with the built-in values, so they can be referenced
+    // as `$values.name` even where a generator value of the same name shadows them.
+    context.set_namespace(VALUES_NAMESPACE);
     context.insert(&config.values);
 
     // Freeze the sprout context so it can be shared and cheaply cloned.
@@ -152,12 +386,101 @@ fn run() -> Result<()> {
     phase(context.clone(), &config.phases.early).context("unable to execute early phase")?;
 
     // Load all configured drivers.
-    drivers::load(context.clone(), &config.drivers).context("unable to load drivers")?;
+    context
+        .root()
+        .timing()
+        .measure("load drivers", || {
+            drivers::load(
+                context.clone(),
+                &config.drivers,
+                config.options.connect_drivers.unwrap_or(true),
+            )
+        })
+        .context("unable to load drivers")?;
+
+    // Dry runs should not modify the firmware environment before an entry is chosen.
+    let dry_run = context.root().options().dry_run;
+
+    // Attach the iSCSI targets, so their disks are scanned like local disks.
+    // The boot volume may be on a target, so a failure to attach is an error.
+    // In dry run mode, the targets are not attached, as that persists the initiator name.
+    if config.iscsi.is_some() && dry_run {
+        info!("dry run: skipping attaching iscsi targets");
+    } else if let Some(iscsi) = &config.iscsi {
+        context
+            .root()
+            .timing()
+            .measure("attach iscsi", || {
+                eficore::iscsi::attach(iscsi.initiator_name.as_deref())
+            })
+            .context("unable to attach iscsi targets")?;
+    }
+
+    // Provide read-only filesystems for partitions the firmware can not read, after the drivers
+    // and iSCSI targets are attached, so a loaded filesystem driver takes precedence.
+    // Failing to do so only hides those partitions, so it should never prevent booting.
+    // In dry run mode, they are not installed, as they modify the firmware environment.
+    if config.options.builtin_filesystems.unwrap_or(true) && dry_run {
+        info!("dry run: skipping installing read-only filesystems");
+    } else if config.options.builtin_filesystems.unwrap_or(true) {
+        match context.root().timing().measure("install filesystems", || {
+            eficore::fs_driver::install(config.options.btrfs_subvolume.as_deref())
+        }) {
+            Ok(0) => {}
+            Ok(installed) => info!("installed {} read-only filesystems", installed),
+            Err(error) => warn!("unable to install read-only filesystems: {:#}", error),
+        }
+    }
+
+    // Mount the squashfs containers after the read-only filesystems are installed, so the
+    // containers can be stored on any filesystem that Sprout can read.
+    // In dry run mode, they are not mounted, as they modify the firmware environment.
+    if !config.squashfs.is_empty() && dry_run {
+        for name in config.squashfs.keys() {
+            info!("dry run: skipping mounting squashfs {}", name);
+        }
+    } else if !config.squashfs.is_empty() {
+        context.root().timing().measure("mount squashfs", || {
+            squashfs::mount(context.clone(), &config.squashfs)
+        });
+    }
+
+    // Trust the configured CA certificates for HTTPS downloads, once the drivers that provide
+    // TLS are loaded. Failing to do so only breaks HTTPS, so it should never prevent booting.
+    if !config.options.tls_ca_certificates.is_empty() {
+        match eficore::http::trust_certificate_files(
+            context.root().loaded_image_path()?,
+            &config.options.tls_ca_certificates,
+            dry_run,
+        ) {
+            Ok(0) => {}
+            Ok(added) => info!("trusting {} ca certificates for https", added),
+            Err(error) => warn!("unable to trust ca certificates for https: {:#}", error),
+        }
+    }
+
+    // If --boot forces a static entry, the expensive filesystem scanning done by
+    // autoconfiguration, generators, and unreferenced extractors is not needed.
+    if let Some(name) = direct::static_entry(context.root().options(), &config)
+        .context("unable to determine whether to boot directly")?
+    {
+        info!(
+            "booting static entry {} directly, skipping filesystem scans",
+            name
+        );
+        direct::reduce(&mut config).context("unable to reduce configuration")?;
+    }
 
     // If --autoconfigure is specified or the loaded configuration has autoconfigure enabled,
     // trigger the autoconfiguration mechanism.
     if context.root().options().autoconfigure || config.options.autoconfigure {
-        autoconfigure::autoconfigure(&mut config).context("unable to autoconfigure")?;
+        context
+            .root()
+            .timing()
+            .measure("autoconfigure", || {
+                autoconfigure::autoconfigure(&mut config)
+            })
+            .context("unable to autoconfigure")?;
     }
 
     // Unload the context so that it can be modified.
@@ -165,6 +488,13 @@ fn run() -> Result<()> {
         bail!("context safety violation while trying to unload context");
     };
 
+    // If --print-config is specified, retain the effective configuration to print it later.
+    let effective_config = context
+        .root()
+        .options()
+        .print_config
+        .then(|| config.clone());
+
     // Perform root context modification in a block to release the modification when complete.
     {
         // Modify the root context to include the autoconfigured actions.
@@ -185,8 +515,33 @@ fn run() -> Result<()> {
     // Run all the extractors declared in the configuration.
     let mut extracted = BTreeMap::new();
     for (name, extractor) in &config.extractors {
-        let value = extractors::extract(context.clone(), extractor)
-            .context(format!("unable to extract value {}", name))?;
+        let result = context
+            .root()
+            .timing()
+            .measure(format!("extractor {}", name), || {
+                extractors::extract(context.clone(), extractor)
+            });
+        let value = match (result, &extractor.on_error) {
+            // Extracted values come from outside the configuration, like filesystem labels,
+            // so they are escaped to keep them from being expanded as templates.
+            (Ok(value), _) => escape_values(&value),
+            // If the extractor fails, the on-error value is used if one is provided.
+            (Err(error), Some(on_error)) => {
+                warn!(
+                    "unable to extract value {}, using on-error value: {:#}",
+                    name, error
+                );
+                on_error.clone()
+            }
+            // If the extractor is not required, the value is not set.
+            (Err(error), None) if !extractor.required => {
+                warn!("unable to extract optional value {}: {:#}", name, error);
+                continue;
+            }
+            (Err(error), None) => {
+                return Err(error).context(format!("unable to extract value {}", name));
+            }
+        };
         info!("extracted value {}: {}", name, value);
         extracted.insert(name.clone(), value);
     }
@@ -220,7 +575,13 @@ fn run() -> Result<()> {
 
         // Add all the entries generated by the generator to the entry list.
         // The generator specifies the context associated with the entry.
-        for mut entry in generators::generate(context.clone(), &generator)? {
+        let generated = context
+            .root()
+            .timing()
+            .measure(format!("generator {}", name), || {
+                generators::generate(context.clone(), &generator)
+            })?;
+        for mut entry in generated {
             // If the entry name is not pinned, prepend the name prefix.
             if !entry.is_pin_name() {
                 entry.prepend_name_prefix(&prefix);
@@ -230,6 +591,14 @@ fn run() -> Result<()> {
         }
     }
 
+    // Stamp the default entry with the extracted values, which allows the default entry
+    // to depend on the environment, like whether Sprout was booted from removable media.
+    let default_entry = config
+        .options
+        .default_entry
+        .as_ref()
+        .map(|default_entry| context.stamp(default_entry));
+
     for entry in &mut entries {
         let mut context = entry.context().fork();
         // Insert the values from the entry configuration into the
@@ -245,7 +614,7 @@ fn run() -> Result<()> {
         entry.restamp_title();
 
         // Mark this entry as the default entry if it is declared as such.
-        if let Some(ref default_entry) = config.options.default_entry {
+        if let Some(ref default_entry) = default_entry {
             // If the entry matches the default entry, mark it as the default entry.
             if entry.is_match(default_entry) {
                 entry.mark_default();
@@ -257,6 +626,32 @@ fn run() -> Result<()> {
     // in reverse order so that entries that would come last show up first in the menu.
     entries.sort_by(|a, b| compare_versions(a.sort_key(), b.sort_key()).reverse());
 
+    // If --list-filesystems is specified, list the discovered filesystems.
+    if context.root().options().list_filesystems {
+        diagnostics::list_filesystems().context("unable to list filesystems")?;
+    }
+
+    // If --list-entries is specified, list the assembled entries.
+    if context.root().options().list_entries {
+        diagnostics::list_entries(&entries);
+    }
+
+    // If --print-config is specified, print the effective configuration and entries.
+    if let Some(ref effective_config) = effective_config {
+        config::dump::dump(effective_config, &entries)
+            .context("unable to print effective configuration")?;
+    }
+
+    // If --list-variables is specified, list the Sprout and bootloader interface variables.
+    if context.root().options().list_variables {
+        diagnostics::list_variables().context("unable to list variables")?;
+    }
+
+    // If any of the diagnostic options are specified, exit now that they have been handled.
+    if context.root().options().is_diagnostic() {
+        return Ok(());
+    }
+
     // Tell the bootloader interface what entries are available.
     BootloaderInterface::set_entries(entries.iter().map(|entry| entry.name()))
         .context("unable to set entries in bootloader interface")?;
@@ -331,6 +726,15 @@ fn run() -> Result<()> {
         force_boot_entry = Some(bootloader_interface_oneshot_entry.clone());
     }
 
+    // If the operating system is resuming from hibernation, boot the default entry immediately.
+    // Booting a different kernel, or waiting for the user, would break the resume.
+    if Hibernation::resume_pending().context("unable to determine hibernation state")? {
+        info!("hibernation resume pending, booting the default entry");
+        force_boot_entry = None;
+        force_boot_menu = false;
+        menu_timeout = 0;
+    }
+
     // If no entries were the default, pick the first entry as the default entry.
     if entries.iter().all(|entry| !entry.is_default())
         && let Some(entry) = entries.first_mut()
@@ -341,30 +745,97 @@ fn run() -> Result<()> {
     // Convert the menu timeout to a duration.
     let menu_timeout = Duration::from_secs(menu_timeout);
 
+    // Retain the assembled entries in the recovery state, in case booting them fails.
+    // The fallback entry is stamped like the default entry.
+    state.entries = entries;
+    state.phases = config.phases.clone();
+    state.fallback_entry = config
+        .options
+        .fallback_entry
+        .as_ref()
+        .map(|fallback_entry| context.stamp(fallback_entry));
+    let entries = &state.entries;
+
     // Use the forced boot entry if possible, otherwise pick the first entry using a boot menu.
     let entry = if !force_boot_menu && let Some(ref force_boot_entry) = force_boot_entry {
         BootableEntry::find(force_boot_entry, entries.iter())
             .context(format!("unable to find entry: {force_boot_entry}"))?
+    } else if context.root().options().dry_run {
+        // In dry run mode, select the default entry instead of showing the boot menu.
+        entries
+            .iter()
+            .find(|entry| entry.is_default())
+            .context("no entries available to boot")?
     } else {
         // Delegate to the menu to select an entry to boot.
-        menu::select(&timer, menu_timeout, &entries)
-            .context("unable to select entry via boot menu")?
+        let selection = context
+            .root()
+            .timing()
+            .measure("menu", || menu::select(&timer, menu_timeout, entries))
+            .context("unable to select entry via boot menu")?;
+        match selection {
+            MenuSelection::Entry(entry) => entry,
+            MenuSelection::Rescan => {
+                state.rescan_requested = true;
+                return Ok(());
+            }
+        }
     };
 
-    // Tell the bootloader interface what the selected entry is.
-    BootloaderInterface::set_selected_entry(entry.name().to_string())
-        .context("unable to set selected entry in bootloader interface")?;
+    boot_entry(entry, &state.phases)
+}
+
+/// Boots the selected `entry`, executing the entry-related `phases` around its actions.
+fn boot_entry(entry: &BootableEntry, phases: &PhasesConfiguration) -> Result<()> {
+    if entry.context().root().options().dry_run {
+        // In dry run mode, log the selected entry instead of telling the bootloader interface.
+        info!(
+            "dry run: would boot entry '{}' ({})",
+            entry.name(),
+            entry.title()
+        );
+    } else {
+        // Tell the bootloader interface what the selected entry is.
+        BootloaderInterface::set_selected_entry(entry.name().to_string())
+            .context("unable to set selected entry in bootloader interface")?;
+    }
+
+    // Record the selected entry before executing it, as it might not return.
+    records::set_entry(entry.name(), entry.title());
+    records::set_timings(entry.context().root().timing());
+    records::write("executing");
+
+    // Execute the pre-exec phase with the context of the selected entry.
+    phase(entry.context().clone(), &phases.pre_exec).context("unable to execute pre-exec phase")?;
 
     // Execute all the actions for the selected entry.
     for action in &entry.declaration().actions {
         let action = entry.context().stamp(action);
-        actions::execute(entry.context().clone(), &action)
-            .context(format!("unable to execute action '{}'", action))?;
+        if let Err(error) = actions::execute(entry.context().clone(), &action) {
+            // Provide the failed action to the on-failure phase, which can log the failure
+            // or chainload a fallback. If the on-failure phase fails, we report both errors.
+            let mut context = entry.context().fork();
+            context.set(FAILED_ACTION_KEY, &action);
+            if let Err(failure) = phase(context.freeze(), &phases.on_failure) {
+                error!("unable to execute on-failure phase: {}", failure);
+            }
+            return Err(error).context(format!("unable to execute action '{}'", action));
+        }
     }
 
     Ok(())
 }
 
+/// Runs Sprout again with a fresh recovery `state`, after connecting all the controllers,
+/// so that devices that appeared since the last run, like a USB drive, are found.
+fn rerun(state: &mut RecoveryState) -> Result<()> {
+    if let Err(error) = eficore::controller::connect_all() {
+        warn!("unable to connect controllers: {:#}", error);
+    }
+    *state = RecoveryState::default();
+    run(state)
+}
+
 /// The main entrypoint of sprout.
 /// It is possible this function will not return if actions that are executed
 /// exit boot services or do not return control to sprout.
@@ -380,20 +851,87 @@ fn efi_main() -> Status {
         return Status::ABORTED;
     }
 
-    // Run Sprout, then handle the error.
-    let result = run();
-    if let Err(ref error) = result {
+    // Run Sprout, then handle the errors until the user recovers or gives up.
+    let mut state = RecoveryState::default();
+    let mut result = run(&mut state);
+    loop {
+        // A rescan selected in the boot menu runs Sprout again, after connecting the
+        // controllers so that devices inserted since startup are found.
+        if result.is_ok() && state.rescan_requested {
+            result = rerun(&mut state);
+            continue;
+        }
+        let Err(ref error) = result else {
+            break;
+        };
+
+        // Record the failure, which helps reconstruct what happened without a console.
+        records::fail(error);
+
         // Print an error trace.
         error!("sprout encountered an error: {}", error);
         for (index, stack) in error.chain().enumerate() {
             error!("[{}]: {}", index, stack);
         }
-        // Sleep to allow the user to read the error.
-        uefi::boot::stall(DELAY_ON_ERROR);
-        return Status::ABORTED;
+        eficore::beep::play(BeepCode::FatalError);
+
+        // Allow the user to recover from the error. If nobody is around to select an option,
+        // we return to the firmware like before, which moves on to the next boot option.
+        let operation = match recovery::select(&state) {
+            Ok(operation) => operation,
+            Err(recovery_error) => {
+                error!("unable to show recovery menu: {:#}", recovery_error);
+                uefi::boot::stall(state.error_delay);
+                return Status::ABORTED;
+            }
+        };
+
+        result = match operation {
+            RecoveryOperation::Retry => rerun(&mut state),
+
+            RecoveryOperation::BootMenu => {
+                let timer = state.timer.unwrap_or_else(PlatformTimer::start);
+                match menu::select(&timer, RECOVERY_MENU_TIMEOUT, &state.entries)
+                    .context("unable to select entry via boot menu")
+                {
+                    Ok(MenuSelection::Entry(entry)) => boot_entry(entry, &state.phases),
+                    Ok(MenuSelection::Rescan) => rerun(&mut state),
+                    Err(error) => Err(error),
+                }
+            }
+
+            RecoveryOperation::BootDefault => state
+                .entries
+                .iter()
+                .find(|entry| entry.is_default())
+                .context("no default entry available")
+                .and_then(|entry| boot_entry(entry, &state.phases)),
+
+            RecoveryOperation::BootFallback => {
+                state.fallback_attempted = true;
+                state
+                    .fallback()
+                    .context("no fallback entry available")
+                    .and_then(|entry| boot_entry(entry, &state.phases))
+            }
+
+            RecoveryOperation::FirmwareSetup => {
+                Power::reboot_to_firmware_setup().context("unable to reboot to firmware setup")
+            }
+
+            RecoveryOperation::PowerOff => Power::power_off(),
+
+            RecoveryOperation::Exit => return Status::ABORTED,
+        };
     }
 
     // Sprout doesn't necessarily guarantee anything was booted.
     // If we reach here, we will exit back to whoever called us.
     Status::SUCCESS
 }
+
+/// Reports panics on the console, the framebuffer, and the serial port.
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    eficore::panic::handle(info)
+}
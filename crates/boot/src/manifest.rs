@@ -0,0 +1,102 @@
+use crate::config::signing_key;
+use anyhow::{Context, Result, anyhow, bail};
+use core::ops::Deref;
+use edera_sprout_parsing::manifest::Manifest;
+use eficore::platform::tpm::PlatformTpm;
+use log::{info, warn};
+use uefi::proto::device_path::LoadedImageDevicePath;
+
+/// The path of the manifest of the digests of the files on the ESP.
+const MANIFEST_PATH: &str = "\\sprout\\manifest.sha256";
+
+/// The path of the detached signature of the manifest.
+const SIGNATURE_PATH: &str = "\\sprout\\manifest.sha256.sig";
+
+/// Loads the manifest of the ESP Sprout was loaded from and verifies its signature with the
+/// embedded signing key, then checks every file Sprout reads from the ESP against it.
+///
+/// Without an embedded signing key, there is nothing to verify the manifest with, so files are
+/// not checked. This is an error when `secure_boot` is enabled, unless Sprout was built with
+/// `SPROUT_ALLOW_UNVERIFIED_ESP=1`. When `secure_boot` is enabled, a manifest that is missing or
+/// has an invalid signature is an error, and so is every file that does not match the manifest.
+/// Otherwise, these only log a warning.
+pub fn load(secure_boot: bool) -> Result<()> {
+    // The manifest stays active when Sprout runs again to rescan for entries.
+    if eficore::manifest::is_active() {
+        return Ok(());
+    }
+    let Some(key) = signing_key::embedded_signing_key() else {
+        if !secure_boot {
+            return Ok(());
+        }
+        if !signing_key::ALLOW_UNVERIFIED_ESP {
+            bail!(
+                "secure boot is enabled but no signing key is embedded to verify the esp with, \
+                 embed one with SPROUT_CONFIG_SIGNING_KEY or opt out with SPROUT_ALLOW_UNVERIFIED_ESP=1"
+            );
+        }
+        warn!(
+            "secure boot is enabled but no signing key is embedded, files on the esp are NOT verified"
+        );
+        return Ok(());
+    };
+
+    match read_manifest(key) {
+        Ok((manifest, filesystem)) => {
+            info!(
+                "verifying files on the efi system partition against {} manifest entries",
+                manifest.len()
+            );
+            eficore::manifest::activate(manifest, filesystem, secure_boot);
+            Ok(())
+        }
+        Err(error) if secure_boot => Err(error).context("unable to load the esp manifest"),
+        Err(error) => {
+            warn!(
+                "files will not be verified, unable to load the esp manifest: {:#}",
+                error
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Reads the manifest of the ESP and verifies its signature with the DER public `key`.
+/// Returns the manifest with the handle of the filesystem it describes.
+fn read_manifest(key: &[u8]) -> Result<(Manifest, uefi::Handle)> {
+    // Open the LoadedImageDevicePath protocol to get the path to the current image.
+    let current_image_device_path_protocol =
+        uefi::boot::open_protocol_exclusive::<LoadedImageDevicePath>(uefi::boot::image_handle())
+            .context("unable to get loaded image device path")?;
+    // Acquire the device path as a boxed device path.
+    let image_path = current_image_device_path_protocol.deref().to_boxed();
+
+    let manifest = eficore::path::resolve_path(Some(&image_path), MANIFEST_PATH)
+        .context("unable to resolve manifest path")?;
+    if !manifest.exists()? {
+        bail!("manifest {} does not exist", MANIFEST_PATH);
+    }
+    let signature = eficore::path::resolve_path(Some(&image_path), SIGNATURE_PATH)
+        .context("unable to resolve manifest signature path")?;
+
+    // The manifest is read before it is activated, so it is not checked against itself.
+    let content = manifest.read_file().context("unable to read manifest")?;
+    let signature = signature
+        .read_file()
+        .context("unable to read manifest signature")?;
+    edera_sprout_parsing::signature::verify(key, &content, &signature)
+        .map_err(|error| anyhow!("unable to verify manifest signature: {}", error))?;
+
+    // Measure the manifest into the TPM, as it decides which files are trusted.
+    PlatformTpm::log_event(
+        PlatformTpm::PCR_BOOT_LOADER_CONFIG,
+        &content,
+        "sprout: manifest",
+    )
+    .context("unable to measure the manifest into the TPM")?;
+
+    let text = core::str::from_utf8(&content).context("manifest is not valid UTF-8")?;
+    let parsed =
+        Manifest::parse(text).map_err(|error| anyhow!("unable to parse manifest: {}", error))?;
+    Ok((parsed, manifest.filesystem_handle))
+}
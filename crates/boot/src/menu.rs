@@ -1,9 +1,15 @@
+use crate::branding;
+use crate::build_info;
 use crate::entries::BootableEntry;
-use alloc::vec;
+use crate::screenshot;
+use crate::verbosity::{self, DEBUG_KEY};
+use alloc::{format, vec};
 use anyhow::{Context, Result, bail};
 use core::time::Duration;
+use eficore::beep::{self, BeepCode};
 use eficore::bootloader_interface::BootloaderInterface;
 use eficore::platform::timer::PlatformTimer;
+use eficore::strings::truncate_with_ellipsis;
 use log::{info, warn};
 use uefi::ResultExt;
 use uefi::boot::TimerTrigger;
@@ -13,13 +19,30 @@ use uefi_raw::table::boot::{EventType, Tpl};
 /// The characters that can be used to select an entry from keys.
 const ENTRY_NUMBER_TABLE: &[char] = &['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
 
+/// The key that captures the screen to the EFI partition.
+const SCREENSHOT_KEY: ScanCode = ScanCode::FUNCTION_12;
+
+/// The key that shows the build metadata of Sprout.
+const ABOUT_KEY: ScanCode = ScanCode::FUNCTION_1;
+
+/// The key that rescans the filesystems for entries, like a USB drive inserted after startup.
+const RESCAN_KEY: ScanCode = ScanCode::FUNCTION_5;
+
 /// Represents the operation that can be performed by the boot menu.
 #[derive(PartialEq, Eq)]
-enum MenuOperation {
+pub enum MenuOperation {
     /// The user selected a numbered entry.
     Number(usize),
     /// The user selected the escape key to exit the boot menu.
     Exit,
+    /// The user pressed the debug key to raise the log level.
+    Debug,
+    /// The user pressed the screenshot key to capture the screen.
+    Screenshot,
+    /// The user pressed the about key to show the build metadata.
+    About,
+    /// The user pressed the rescan key to assemble the entries again.
+    Rescan,
     /// The user selected the enter key to display the entries again.
     Continue,
     /// Timeout occurred.
@@ -30,7 +53,7 @@ enum MenuOperation {
 
 /// Read a key from the input device with a duration, returning the [MenuOperation] that was
 /// performed.
-fn read(input: &mut Input, timeout: &Duration) -> Result<MenuOperation> {
+pub fn read(input: &mut Input, timeout: &Duration) -> Result<MenuOperation> {
     // The event to wait for a key press.
     let key_event = input
         .wait_for_key_event()
@@ -106,6 +129,9 @@ fn read(input: &mut Input, timeout: &Duration) -> Result<MenuOperation> {
             }
             // Convert the key to a char.
             let c: char = c.into();
+            if c.eq_ignore_ascii_case(&DEBUG_KEY) {
+                return Ok(MenuOperation::Debug);
+            }
             // Find the key pressed in the entry number table or continue.
             Ok(ENTRY_NUMBER_TABLE
                 .iter()
@@ -117,25 +143,68 @@ fn read(input: &mut Input, timeout: &Duration) -> Result<MenuOperation> {
         // The escape key is used to exit the boot menu.
         Key::Special(ScanCode::ESCAPE) => Ok(MenuOperation::Exit),
 
+        // The screenshot key is used to capture the screen.
+        Key::Special(SCREENSHOT_KEY) => Ok(MenuOperation::Screenshot),
+
+        // The about key is used to show the build metadata.
+        Key::Special(ABOUT_KEY) => Ok(MenuOperation::About),
+
+        // The rescan key is used to assemble the entries again.
+        Key::Special(RESCAN_KEY) => Ok(MenuOperation::Rescan),
+
         // If the special key is unknown, do nothing.
         Key::Special(_) => Ok(MenuOperation::Nop),
     }
 }
 
+/// Shows the build metadata of Sprout, which helps identify the build when reporting issues.
+pub fn show_about() {
+    info!("Sprout {}", build_info::VERSION);
+    info!("  commit: {}", build_info::COMMIT);
+    info!("  built: {}", build_info::DATE);
+    info!("  compiler: {}", build_info::RUSTC);
+}
+
+/// Acquires the number of columns of the console, if the console reports its mode.
+fn console_columns() -> Option<usize> {
+    uefi::system::with_stdout(|stdout| stdout.current_mode().ok().flatten())
+        .map(|mode| mode.columns())
+}
+
+/// The selection made in the boot menu.
+pub enum MenuSelection<'a> {
+    /// The entry to boot.
+    Entry(&'a BootableEntry),
+    /// The filesystems should be rescanned and the entries assembled again.
+    Rescan,
+}
+
 /// Selects an entry from the list of entries using the boot menu.
 fn select_with_input<'a>(
     input: &mut Input,
     timeout: Duration,
     entries: &'a [BootableEntry],
-) -> Result<&'a BootableEntry> {
+) -> Result<MenuSelection<'a>> {
     loop {
         // If the timeout is not zero, let's display the boot menu.
         if !timeout.is_zero() {
             // Until a pretty menu is available, we just print all the entries.
-            info!("Boot Menu:");
+            match branding::product_name() {
+                Some(name) => info!("{} Boot Menu:", name),
+                None => info!("Boot Menu:"),
+            }
+            let columns = console_columns();
             for (index, entry) in entries.iter().enumerate() {
                 let title = entry.context().stamp(&entry.declaration().title);
-                info!("  [{}] {}", index, title);
+                // Keep each entry on a single line, so the menu stays readable.
+                let prefix = format!("  [{}] ", index);
+                let title = match columns {
+                    Some(columns) => {
+                        truncate_with_ellipsis(&title, columns.saturating_sub(prefix.len() + 1))
+                    }
+                    None => title,
+                };
+                info!("{}{}", prefix, title);
             }
         }
 
@@ -148,6 +217,11 @@ fn select_with_input<'a>(
 
             info!("Select a boot entry using the number keys.");
             info!("Press Escape to exit and enter to display the entries again.");
+            info!("Press F5 to rescan for entries, like on a USB drive inserted just now.");
+            info!(
+                "Press '{}' to show debug messages, F1 for build details, and F12 to take a screenshot.",
+                DEBUG_KEY
+            );
 
             let operation = read(input, &timeout)?;
             if operation != MenuOperation::Nop {
@@ -162,7 +236,7 @@ fn select_with_input<'a>(
                     info!("invalid entry number");
                     continue;
                 };
-                return Ok(entry);
+                return Ok(MenuSelection::Entry(entry));
             }
 
             // When the user exits the boot menu or a timeout occurs, we should
@@ -171,9 +245,36 @@ fn select_with_input<'a>(
                 return entries
                     .iter()
                     .find(|item| item.is_default())
+                    .map(MenuSelection::Entry)
                     .context("no default entry available");
             }
 
+            // Raise the log level, then display the entries again.
+            MenuOperation::Debug => {
+                verbosity::raise_to_debug();
+                continue;
+            }
+
+            // Capture the screen, which should never prevent booting.
+            MenuOperation::Screenshot => {
+                if let Err(error) = screenshot::capture() {
+                    warn!("unable to capture screenshot: {:#}", error);
+                }
+                continue;
+            }
+
+            // Show the build metadata, then display the entries again.
+            MenuOperation::About => {
+                show_about();
+                continue;
+            }
+
+            // Leave the menu, so the entries are assembled again.
+            MenuOperation::Rescan => {
+                info!("rescanning for entries");
+                return Ok(MenuSelection::Rescan);
+            }
+
             // If the operation is to continue or nop, we can just run the loop again.
             MenuOperation::Continue | MenuOperation::Nop => {
                 continue;
@@ -182,18 +283,28 @@ fn select_with_input<'a>(
     }
 }
 
-/// Shows a boot menu to select a bootable entry to boot.
+/// Shows a boot menu to select a bootable entry to boot, or to rescan for entries.
 /// The actual work is done internally in [select_with_input] which is called
 /// within the context of the standard input device.
 pub fn select<'live>(
     timer: &'live PlatformTimer,
     timeout: Duration,
     entries: &'live [BootableEntry],
-) -> Result<&'live BootableEntry> {
+) -> Result<MenuSelection<'live>> {
     // Notify the bootloader interface that we are about to display the menu.
     BootloaderInterface::mark_menu(timer)
         .context("unable to mark menu display in bootloader interface")?;
 
+    // A hidden menu gives no feedback, as nobody is expected to operate it.
+    if !timeout.is_zero() {
+        beep::play(BeepCode::MenuDisplayed);
+    }
+
     // Acquire the standard input device and run the boot menu.
-    uefi::system::with_stdin(move |input| select_with_input(input, timeout, entries))
+    let selection =
+        uefi::system::with_stdin(move |input| select_with_input(input, timeout, entries))?;
+    if !timeout.is_zero() && matches!(selection, MenuSelection::Entry(_)) {
+        beep::play(BeepCode::EntrySelected);
+    }
+    Ok(selection)
 }
@@ -1,15 +1,57 @@
 use alloc::string::{String, ToString};
-use anyhow::Result;
+use alloc::vec::Vec;
+use anyhow::{Context, Result};
+use core::fmt::{Display, Formatter};
+use core::ops::Deref;
 use core::ptr::null_mut;
+use edera_sprout_parsing::options::split_args;
+use edera_sprout_parsing::{combine_options, empty_is_none};
+use eficore::platform::tpm::PlatformTpm;
+use eficore::secure::SecureBoot;
+use eficore::setup::{ConsoleReset, SetupOptions};
 use jaarg::{
     ErrorUsageWriter, ErrorUsageWriterContext, HelpWriter, HelpWriterContext, Opt, Opts,
-    ParseControl, ParseResult, StandardErrorUsageWriter, StandardFullHelpWriter,
+    ParseControl, ParseError, ParseErrorKind, ParseResult, StandardErrorUsageWriter,
+    StandardFullHelpWriter,
 };
-use log::{error, info};
+use log::{LevelFilter, error, info, warn};
+use uefi::proto::device_path::LoadedImageDevicePath;
 use uefi_raw::Status;
 
-/// Default configuration file path.
-const DEFAULT_CONFIG_PATH: &str = "\\sprout.toml";
+/// The default path to the options file that supplies additional arguments.
+const DEFAULT_OPTIONS_FILE_PATH: &str = "\\sprout\\cmdline";
+
+/// The option that specifies the path to the options file.
+const OPTIONS_FILE_OPTION: &str = "--options-file";
+
+/// The accepted arguments of the log level option.
+const LOG_LEVELS: &[&str] = &["off", "error", "warn", "info", "debug", "trace"];
+
+/// The accepted arguments of the console reset option.
+const CONSOLE_RESETS: &[&str] = &["keep", "reset", "clear"];
+
+/// An option argument that is not one of the accepted choices.
+/// The parse errors of jaarg can only describe invalid numbers, so this is reported separately.
+struct InvalidChoice {
+    /// The name of the option.
+    option: String,
+    /// The argument that was given.
+    value: String,
+    /// The arguments the option accepts.
+    accepted: &'static [&'static str],
+}
+
+impl Display for InvalidChoice {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "Invalid argument '{}' for option '{}', expected one of: {}",
+            self.value,
+            self.option,
+            self.accepted.join(", ")
+        )
+    }
+}
 
 /// The parsed options of sprout.
 #[derive(Debug)]
@@ -17,7 +59,8 @@ pub struct SproutOptions {
     /// Configures Sprout automatically based on the environment.
     pub autoconfigure: bool,
     /// Path to a configuration file to load.
-    pub config: String,
+    /// If not specified, the configuration loader determines the path.
+    pub config: Option<String>,
     /// Entry to boot without showing the boot menu.
     pub boot: Option<String>,
     /// Force display of the boot menu.
@@ -26,6 +69,35 @@ pub struct SproutOptions {
     pub menu_timeout: Option<u64>,
     /// Retains the boot console before boot.
     pub retain_boot_console: bool,
+    /// The timeout of the watchdog armed before starting an image, in seconds.
+    /// If zero, the watchdog is not armed.
+    pub watchdog_timeout: Option<u64>,
+    /// The maximum level of log messages.
+    /// If not specified, the level of the configuration is used.
+    pub log_level: Option<LevelFilter>,
+    /// Controls how the UEFI environment is configured, like the firmware watchdog
+    /// and the console.
+    pub setup: SetupOptions,
+    /// Prints the effective configuration and assembled entries, then exits.
+    pub print_config: bool,
+    /// Performs everything except loading drivers and executing actions,
+    /// logging what would have been done instead.
+    pub dry_run: bool,
+    /// Lists the discovered filesystems, then exits.
+    pub list_filesystems: bool,
+    /// Lists the assembled boot entries, then exits.
+    pub list_entries: bool,
+    /// Lists the Sprout and bootloader interface variables, then exits.
+    pub list_variables: bool,
+    /// Logs the memory usage right before an image is started.
+    pub show_memory: bool,
+    /// Ignores the kernel embedded in the Sprout image and runs the boot manager instead.
+    pub no_stub: bool,
+    /// Creates or repairs the firmware boot option of Sprout and adds it to the boot order.
+    pub install: bool,
+    /// Extra options to append to the options of the booted image.
+    /// This combines all the `--append` options and any arguments after `--`.
+    pub append: Option<String>,
 }
 
 /// The default Sprout options.
@@ -33,17 +105,146 @@ impl Default for SproutOptions {
     fn default() -> Self {
         Self {
             autoconfigure: false,
-            config: DEFAULT_CONFIG_PATH.to_string(),
+            config: None,
             boot: None,
             force_menu: false,
             menu_timeout: None,
             retain_boot_console: false,
+            watchdog_timeout: None,
+            log_level: None,
+            setup: SetupOptions::default(),
+            print_config: false,
+            dry_run: false,
+            list_filesystems: false,
+            list_entries: false,
+            list_variables: false,
+            show_memory: false,
+            no_stub: false,
+            install: false,
+            append: None,
         }
     }
 }
 
+/// Finds the path to the options file specified in `args`, if any.
+fn options_file_path(args: &[String]) -> Option<String> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        // Handle the option with a separate value.
+        if arg == OPTIONS_FILE_OPTION {
+            return args.next().cloned();
+        }
+
+        // Handle the option with an attached value.
+        if let Some(path) = arg
+            .strip_prefix(OPTIONS_FILE_OPTION)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            return Some(path.to_string());
+        }
+    }
+    None
+}
+
+/// Splits the `args` at the first `--` argument.
+/// Returns the arguments before `--` and the arguments after it, which are passed through
+/// to the booted image.
+fn split_passthrough(mut args: Vec<String>) -> (Vec<String>, Vec<String>) {
+    match args.iter().position(|arg| arg == "--") {
+        Some(index) => {
+            let passthrough = args.split_off(index + 1);
+            args.pop();
+            (args, passthrough)
+        }
+        None => (args, Vec::new()),
+    }
+}
+
+/// Reads the arguments from the options file.
+/// If `args` specifies an options file, it is required to exist.
+/// Otherwise, the default options file is read if it exists.
+///
+/// The options file can change the configuration and the options of the booted image, so it is
+/// measured into the TPM like the configuration. Under Secure Boot, the default options file is
+/// only read when the ESP manifest verifies it, as anyone with access to the ESP can place it.
+fn options_file_args(args: &[String]) -> Result<Vec<String>> {
+    // Determine the path to the options file and whether it must exist.
+    let (path, required) = match options_file_path(args) {
+        Some(path) => (path, true),
+        None => (DEFAULT_OPTIONS_FILE_PATH.to_string(), false),
+    };
+
+    // Open the LoadedImageDevicePath protocol to get the path to the current image.
+    let current_image_device_path_protocol =
+        uefi::boot::open_protocol_exclusive::<LoadedImageDevicePath>(uefi::boot::image_handle())
+            .context("unable to get loaded image device path")?;
+    // Acquire the device path as a boxed device path.
+    let image_path = current_image_device_path_protocol.deref().to_boxed();
+
+    // Resolve the path to the options file.
+    let resolved = eficore::path::resolve_path(Some(&image_path), &path)
+        .context("unable to resolve options file path")?;
+
+    // If the options file is optional and does not exist, there are no extra arguments.
+    if !required && !resolved.exists()? {
+        return Ok(Vec::new());
+    }
+
+    // Without a manifest, nothing verifies the default options file under Secure Boot.
+    if !required
+        && !eficore::manifest::is_active()
+        && SecureBoot::enabled().context("unable to determine Secure Boot status")?
+    {
+        warn!(
+            "ignoring options file {} as secure boot is enabled without an esp manifest",
+            path
+        );
+        return Ok(Vec::new());
+    }
+
+    info!("options file: {}", path);
+
+    // Read the options file and split it into arguments.
+    let content = resolved
+        .read_file()
+        .context("unable to read options file")?;
+
+    // Measure the options file into the TPM, if needed and possible.
+    PlatformTpm::log_event(
+        PlatformTpm::PCR_BOOT_LOADER_CONFIG,
+        &content,
+        "sprout: options file",
+    )
+    .context("unable to measure the options file into the TPM")?;
+    let content = String::from_utf8(content).context("options file is not valid UTF-8")?;
+    Ok(split_args(&content))
+}
+
 /// The options parser mechanism for Sprout.
 impl SproutOptions {
+    /// Determines whether any of the diagnostic options are specified.
+    /// Diagnostic options cause Sprout to exit instead of booting an entry.
+    pub fn is_diagnostic(&self) -> bool {
+        self.print_config || self.list_filesystems || self.list_entries || self.list_variables
+    }
+
+    /// The options to append to the options of the booted image, if any.
+    /// The appended options are not signed, so they are ignored when Secure Boot is enabled.
+    /// Otherwise, they are measured into the TPM with the `event` description before they are
+    /// returned, like the command line of the image.
+    pub fn measured_append(&self, event: &str) -> Result<Option<String>> {
+        let Some(ref append) = self.append else {
+            return Ok(None);
+        };
+        if SecureBoot::enabled().context("unable to determine Secure Boot status")? {
+            warn!("ignoring appended options, as Secure Boot is enabled");
+            return Ok(None);
+        }
+        PlatformTpm::log_event(PlatformTpm::PCR_KERNEL_CONFIG, append.as_bytes(), event)
+            .context("unable to measure the appended options into the TPM")?;
+        Ok(Some(append.clone()))
+    }
+
     /// Produces [SproutOptions] from the arguments provided by the UEFI core.
     /// Internally, we use the `jaarg` argument parser which has excellent no_std support.
     pub fn parse() -> Result<Self> {
@@ -55,6 +256,21 @@ impl SproutOptions {
             ForceMenu,
             MenuTimeout,
             RetainBootConsole,
+            WatchdogTimeout,
+            LogLevel,
+            DisableWatchdog,
+            ConsoleReset,
+            ConsoleMode,
+            PrintConfig,
+            DryRun,
+            ListFilesystems,
+            ListEntries,
+            ListVariables,
+            ShowMemory,
+            NoStub,
+            Install,
+            OptionsFile,
+            Append,
         }
 
         // All the options for the Sprout executable.
@@ -71,19 +287,62 @@ impl SproutOptions {
                 .help_text("Boot menu timeout, in seconds"),
             Opt::flag(ArgID::RetainBootConsole, &["--retain-boot-console"])
                 .help_text("Retain boot console before boot"),
+            Opt::value(ArgID::WatchdogTimeout, &["--watchdog-timeout"], "TIMEOUT")
+                .help_text("Watchdog timeout when starting an image, in seconds"),
+            Opt::value(ArgID::LogLevel, &["--log-level"], "LEVEL")
+                .help_text("Maximum log level: off, error, warn, info, debug, or trace"),
+            Opt::flag(ArgID::DisableWatchdog, &["--disable-watchdog"])
+                .help_text("Disable the firmware watchdog while Sprout runs"),
+            Opt::value(ArgID::ConsoleReset, &["--console-reset"], "RESET")
+                .help_text("Prepare the console at startup: keep, reset, or clear"),
+            Opt::value(ArgID::ConsoleMode, &["--console-mode"], "COLUMNSxROWS")
+                .help_text("Console mode to attempt, can be specified multiple times"),
+            Opt::flag(ArgID::PrintConfig, &["--print-config"])
+                .help_text("Print the effective configuration and exit"),
+            Opt::flag(ArgID::DryRun, &["--dry-run"])
+                .help_text("Show what would be booted without booting it"),
+            Opt::flag(ArgID::ListFilesystems, &["--list-filesystems"])
+                .help_text("List discovered filesystems and exit"),
+            Opt::flag(ArgID::ListEntries, &["--list-entries"])
+                .help_text("List assembled boot entries and exit"),
+            Opt::flag(ArgID::ListVariables, &["--list-variables"])
+                .help_text("List Sprout and bootloader interface variables and exit"),
+            Opt::flag(ArgID::ShowMemory, &["--show-memory"])
+                .help_text("Show memory usage before starting an image"),
+            Opt::flag(ArgID::NoStub, &["--no-stub"])
+                .help_text("Ignore the embedded kernel and show the boot manager"),
+            Opt::flag(ArgID::Install, &["--install"])
+                .help_text("Register Sprout as a firmware boot option"),
+            Opt::value(ArgID::OptionsFile, &[OPTIONS_FILE_OPTION], "PATH")
+                .help_text("Path to a file containing additional options"),
+            Opt::value(ArgID::Append, &["--append"], "OPTIONS")
+                .help_text("Options to append to the options of the booted image"),
         ]);
 
         // Acquire the arguments as determined by the UEFI core.
-        let args = eficore::env::args()?;
+        let (args, passthrough) = split_passthrough(eficore::env::args()?);
+
+        // Prepend the arguments from the options file, so the command-line arguments
+        // take precedence over them.
+        let file_args = options_file_args(&args).context("unable to load options file")?;
+        let (mut file_args, file_passthrough) = split_passthrough(file_args);
+        file_args.extend(args);
+        let args = file_args;
+
+        // The options to append to the options of the booted image.
+        let mut appended = Vec::new();
 
         // Use the default value of sprout options and have the raw options be parsed into it.
         let mut result = Self::default();
 
+        // An argument that is not one of the choices of its option, which stops the parser.
+        let mut invalid_choice = None;
+
         // Parse the OPTIONS into a map using jaarg.
-        match OPTIONS.parse(
+        let parsed = OPTIONS.parse(
             "sprout",
             args.iter(),
-            |program_name, id, _opt, _name, value| {
+            |program_name, id, _opt, name, value| {
                 match id {
                     ArgID::AutoConfigure => {
                         // Enable autoconfiguration.
@@ -91,7 +350,7 @@ impl SproutOptions {
                     }
                     ArgID::Config => {
                         // The configuration file to load.
-                        result.config = value.into();
+                        result.config = Some(value.into());
                     }
                     ArgID::Boot => {
                         // The entry to boot.
@@ -109,6 +368,91 @@ impl SproutOptions {
                         // Retain the boot console before booting.
                         result.retain_boot_console = true;
                     }
+                    ArgID::WatchdogTimeout => {
+                        // The timeout of the watchdog when starting an image in seconds.
+                        result.watchdog_timeout = Some(value.parse::<u64>()?);
+                    }
+                    ArgID::LogLevel => {
+                        // The maximum level of log messages.
+                        let Ok(level) = value.parse::<LevelFilter>() else {
+                            invalid_choice = Some(InvalidChoice {
+                                option: name.to_string(),
+                                value: value.to_string(),
+                                accepted: LOG_LEVELS,
+                            });
+                            return Ok(ParseControl::Quit);
+                        };
+                        result.log_level = Some(level);
+                    }
+                    ArgID::DisableWatchdog => {
+                        // Disable the firmware watchdog.
+                        result.setup.disable_watchdog = true;
+                    }
+                    ArgID::ConsoleReset => {
+                        // How the console is prepared at startup.
+                        let Ok(reset) = value.parse::<ConsoleReset>() else {
+                            invalid_choice = Some(InvalidChoice {
+                                option: name.to_string(),
+                                value: value.to_string(),
+                                accepted: CONSOLE_RESETS,
+                            });
+                            return Ok(ParseControl::Quit);
+                        };
+                        result.setup.console_reset = reset;
+                    }
+                    ArgID::ConsoleMode => {
+                        // A console mode to attempt, in order of preference.
+                        let mode = value
+                            .split_once(['x', 'X'])
+                            .and_then(|(columns, rows)| {
+                                Some((columns.parse::<usize>().ok()?, rows.parse::<usize>().ok()?))
+                            })
+                            .ok_or(ParseError::ArgumentError(
+                                "",
+                                "",
+                                ParseErrorKind::InvalidInteger,
+                            ))?;
+                        result.setup.console_modes.push(mode);
+                    }
+                    ArgID::PrintConfig => {
+                        // Print the effective configuration and exit.
+                        result.print_config = true;
+                    }
+                    ArgID::DryRun => {
+                        // Log what would be done instead of doing it.
+                        result.dry_run = true;
+                    }
+                    ArgID::ListFilesystems => {
+                        // List the discovered filesystems and exit.
+                        result.list_filesystems = true;
+                    }
+                    ArgID::ListEntries => {
+                        // List the assembled boot entries and exit.
+                        result.list_entries = true;
+                    }
+                    ArgID::ListVariables => {
+                        // List the Sprout and bootloader interface variables and exit.
+                        result.list_variables = true;
+                    }
+                    ArgID::ShowMemory => {
+                        // Log the memory usage before starting an image.
+                        result.show_memory = true;
+                    }
+                    ArgID::NoStub => {
+                        // Run the boot manager even if a kernel is embedded.
+                        result.no_stub = true;
+                    }
+                    ArgID::Install => {
+                        // Register Sprout as a firmware boot option.
+                        result.install = true;
+                    }
+                    ArgID::OptionsFile => {
+                        // The options file has already been loaded.
+                    }
+                    ArgID::Append => {
+                        // Options to append to the options of the booted image.
+                        appended.push(value.to_string());
+                    }
                     ArgID::Help => {
                         let ctx = HelpWriterContext {
                             options: &OPTIONS,
@@ -128,8 +472,28 @@ impl SproutOptions {
                 };
                 error!("{}", StandardErrorUsageWriter::new(ctx));
             },
-        ) {
-            ParseResult::ContinueSuccess => Ok(result),
+        );
+
+        // The parser quit early on an invalid choice, which is an error like other parse errors.
+        if let Some(invalid_choice) = invalid_choice {
+            error!("{}", invalid_choice);
+            unsafe {
+                uefi::boot::exit(uefi::boot::image_handle(), Status::ABORTED, 0, null_mut());
+            }
+        }
+
+        match parsed {
+            ParseResult::ContinueSuccess => {
+                // Combine the appended options with the arguments after `--`.
+                let append = combine_options(
+                    appended
+                        .into_iter()
+                        .chain(file_passthrough)
+                        .chain(passthrough),
+                );
+                result.append = empty_is_none(Some(append));
+                Ok(result)
+            }
             ParseResult::ExitSuccess => unsafe {
                 uefi::boot::exit(uefi::boot::image_handle(), Status::SUCCESS, 0, null_mut());
             },
@@ -4,6 +4,7 @@ use alloc::format;
 use alloc::rc::Rc;
 use anyhow::{Context, Result};
 use edera_sprout_config::phases::PhaseConfiguration;
+use edera_sprout_parsing::condition;
 
 /// Executes the specified [phase] of the boot process.
 /// The value [phase] should be a reference of a specific phase in the `PhasesConfiguration`.
@@ -16,6 +17,13 @@ pub fn phase(context: Rc<SproutContext>, phase: &[PhaseConfiguration]) -> Result
         context.insert(&item.values);
         let context = context.freeze();
 
+        // Skip this phase configuration if the condition is not met.
+        if let Some(ref when) = item.when
+            && !condition::evaluate(&context.stamp(when))
+        {
+            continue;
+        }
+
         // Execute all the actions in this phase configuration.
         for action in item.actions.iter() {
             actions::execute(context.clone(), action)
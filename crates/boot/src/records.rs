@@ -0,0 +1,247 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use anyhow::{Context, Result};
+use core::ops::Deref;
+use edera_sprout_parsing::datetime::format_datetime;
+use eficore::platform::clock::PlatformClock;
+use eficore::platform::timer::report::TimingReport;
+use eficore::services::services;
+use log::warn;
+use spin::Mutex;
+use toml::{Table, Value};
+use uefi::fs::{FileSystem, PathBuf};
+use uefi::proto::device_path::LoadedImageDevicePath;
+use uefi::proto::device_path::text::{AllowShortcuts, DisplayOnly};
+use uefi::proto::media::fs::SimpleFileSystem;
+
+/// The directory that contains the boot records, on the partition Sprout was loaded from.
+const BOOT_RECORDS_DIRECTORY: &str = "\\sprout\\boot-records";
+
+/// The prefix of the file names of boot records.
+const BOOT_RECORD_PREFIX: &str = "boot-";
+
+/// The extension of the file names of boot records.
+const BOOT_RECORD_EXTENSION: &str = ".toml";
+
+/// The boot record of the current boot, which is assembled as the boot progresses.
+struct BootRecorder {
+    /// The number of records to retain, including the record of the current boot.
+    /// If zero, records are not written.
+    limit: u64,
+    /// The file name of the record of the current boot.
+    file_name: String,
+    /// The contents of the record.
+    record: Table,
+}
+
+/// The boot recorder of the current boot, if recording has begun.
+static BOOT_RECORDER: Mutex<Option<BootRecorder>> = Mutex::new(None);
+
+/// Begins recording the current boot, retaining `limit` records until it is changed.
+/// The record includes the time of the boot, the Sprout version, and the firmware information.
+pub fn begin(limit: u64) {
+    // The file name is derived from the time, so that sorting the names sorts the boots.
+    let now = match PlatformClock::now() {
+        Ok(now) => now,
+        Err(error) => {
+            warn!("unable to record boot: {:#}", error);
+            return;
+        }
+    };
+    let file_name = format!(
+        "{}{}{}",
+        BOOT_RECORD_PREFIX,
+        format_datetime(&now, "%Y%m%dT%H%M%S"),
+        BOOT_RECORD_EXTENSION
+    );
+
+    let firmware_info = services().firmware_info();
+    let mut firmware = Table::new();
+    firmware.insert("vendor".to_string(), Value::String(firmware_info.vendor));
+    firmware.insert(
+        "revision".to_string(),
+        Value::String(format!(
+            "{}.{:02}",
+            firmware_info.revision >> 16,
+            firmware_info.revision & 0xffff
+        )),
+    );
+
+    let mut record = Table::new();
+    record.insert(
+        "time".to_string(),
+        Value::String(format_datetime(&now, "%Y-%m-%dT%H:%M:%S")),
+    );
+    record.insert(
+        "version".to_string(),
+        Value::String(env!("CARGO_PKG_VERSION").to_string()),
+    );
+    record.insert("status".to_string(), Value::String("started".to_string()));
+    record.insert("firmware".to_string(), Value::Table(firmware));
+
+    *BOOT_RECORDER.lock() = Some(BootRecorder {
+        limit,
+        file_name,
+        record,
+    });
+}
+
+/// Changes the number of records to retain. If zero, the current boot is not recorded.
+pub fn set_limit(limit: u64) {
+    if let Some(ref mut recorder) = *BOOT_RECORDER.lock() {
+        recorder.limit = limit;
+    }
+}
+
+/// Records the entry that was selected to boot.
+pub fn set_entry(name: &str, title: &str) {
+    if let Some(ref mut recorder) = *BOOT_RECORDER.lock() {
+        let mut entry = Table::new();
+        entry.insert("name".to_string(), Value::String(name.to_string()));
+        entry.insert("title".to_string(), Value::String(title.to_string()));
+        recorder
+            .record
+            .insert("entry".to_string(), Value::Table(entry));
+    }
+}
+
+/// Records the spans measured by the `timing` report so far.
+pub fn set_timings(timing: &TimingReport) {
+    if let Some(ref mut recorder) = *BOOT_RECORDER.lock() {
+        let timings = timing
+            .spans()
+            .into_iter()
+            .map(|span| {
+                let mut timing = Table::new();
+                timing.insert("name".to_string(), Value::String(span.name));
+                timing.insert(
+                    "start-ms".to_string(),
+                    Value::Float(span.start.as_secs_f64() * 1000.0),
+                );
+                timing.insert(
+                    "duration-ms".to_string(),
+                    Value::Float(span.duration.as_secs_f64() * 1000.0),
+                );
+                Value::Table(timing)
+            })
+            .collect();
+        recorder
+            .record
+            .insert("timings".to_string(), Value::Array(timings));
+    }
+}
+
+/// Acquires a copy of the record of the current boot, if recording has begun.
+pub fn snapshot() -> Option<Table> {
+    BOOT_RECORDER
+        .lock()
+        .as_ref()
+        .map(|recorder| recorder.record.clone())
+}
+
+/// Records that the boot failed with `error`, then writes the record.
+pub fn fail(error: &anyhow::Error) {
+    if let Some(ref mut recorder) = *BOOT_RECORDER.lock() {
+        let chain = error
+            .chain()
+            .map(|cause| Value::String(cause.to_string()))
+            .collect();
+        recorder
+            .record
+            .insert("error".to_string(), Value::Array(chain));
+    }
+    write("failed");
+}
+
+/// Writes the record of the current boot with the specified `status`.
+/// Writing the record again replaces it, so the record reflects the latest status.
+/// Failures are logged, since a boot record should never prevent booting.
+pub fn write(status: &str) {
+    let mut recorder = BOOT_RECORDER.lock();
+    let Some(ref mut recorder) = *recorder else {
+        return;
+    };
+
+    if recorder.limit == 0 {
+        return;
+    }
+
+    recorder
+        .record
+        .insert("status".to_string(), Value::String(status.to_string()));
+    if let Err(error) = write_record(recorder) {
+        warn!("unable to write boot record: {:#}", error);
+    }
+}
+
+/// Writes the record of the `recorder` and removes the oldest records beyond its limit.
+fn write_record(recorder: &BootRecorder) -> Result<()> {
+    let content = toml::to_string(&recorder.record).context("unable to serialize boot record")?;
+
+    // Open the LoadedImageDevicePath protocol to get the path to the current image.
+    // This is done in a block to ensure the release of the protocol.
+    let image_path = {
+        let current_image_device_path_protocol = uefi::boot::open_protocol_exclusive::<
+            LoadedImageDevicePath,
+        >(uefi::boot::image_handle())
+        .context("unable to get loaded image device path")?;
+        current_image_device_path_protocol.deref().to_boxed()
+    };
+
+    // Resolve the path to the boot records directory.
+    let resolved = eficore::path::resolve_path(Some(&image_path), BOOT_RECORDS_DIRECTORY)
+        .context("unable to resolve boot records directory")?;
+    let directory = PathBuf::from(
+        resolved
+            .sub_path
+            .to_string16(DisplayOnly(false), AllowShortcuts(false))
+            .context("unable to convert boot records directory to string")?,
+    );
+
+    // Open exclusive access to the filesystem.
+    let fs = uefi::boot::open_protocol_exclusive::<SimpleFileSystem>(resolved.filesystem_handle)
+        .context("unable to open filesystem")?;
+    let mut fs = FileSystem::new(fs);
+
+    // Write the record of the current boot.
+    fs.create_dir_all(&directory)
+        .context("unable to create boot records directory")?;
+    let path = PathBuf::from(
+        uefi::CString16::try_from(
+            format!("{}\\{}", BOOT_RECORDS_DIRECTORY, recorder.file_name).as_str(),
+        )
+        .context("unable to convert boot record path")?,
+    );
+    fs.write(&path, content.as_bytes())
+        .context("unable to write boot record")?;
+
+    // Collect the names of the boot records, which sort from oldest to newest.
+    let mut names = Vec::new();
+    for item in fs
+        .read_dir(&directory)
+        .context("unable to read boot records directory")?
+    {
+        let item = item.context("unable to read boot records directory item")?;
+        let name = item.file_name().to_string();
+        if item.is_regular_file()
+            && name.starts_with(BOOT_RECORD_PREFIX)
+            && name.ends_with(BOOT_RECORD_EXTENSION)
+        {
+            names.push(name);
+        }
+    }
+    names.sort();
+
+    // Remove the oldest records beyond the limit.
+    let excess = names.len().saturating_sub(recorder.limit as usize);
+    for name in names.iter().take(excess) {
+        let path = PathBuf::from(
+            uefi::CString16::try_from(format!("{}\\{}", BOOT_RECORDS_DIRECTORY, name).as_str())
+                .context("unable to convert boot record path")?,
+        );
+        fs.remove_file(&path)
+            .context(format!("unable to remove boot record {}", name))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,183 @@
+use crate::entries::BootableEntry;
+use crate::menu::{self, MenuOperation};
+use crate::screenshot;
+use crate::verbosity;
+use alloc::string::String;
+use alloc::vec::Vec;
+use anyhow::Result;
+use core::time::Duration;
+use edera_sprout_config::DEFAULT_ERROR_DELAY_SECONDS;
+use edera_sprout_config::phases::PhasesConfiguration;
+use eficore::platform::timer::PlatformTimer;
+use eficore::power::Power;
+use log::{info, warn};
+
+/// The state assembled by Sprout before an error occurred.
+/// This allows the recovery menu to boot the entries that were assembled.
+pub struct RecoveryState {
+    /// The platform timer started at the beginning of the boot process, if it was started.
+    pub timer: Option<PlatformTimer>,
+    /// The entries that were assembled, which is empty if assembly did not complete.
+    pub entries: Vec<BootableEntry>,
+    /// The phases of the configuration, which are executed when booting an entry.
+    pub phases: PhasesConfiguration,
+    /// The delay to wait for a recovery option to be selected.
+    pub error_delay: Duration,
+    /// The entry to boot when no recovery option is selected, if any.
+    pub fallback_entry: Option<String>,
+    /// Whether the fallback entry has already been booted, which prevents booting it in a loop.
+    pub fallback_attempted: bool,
+    /// Whether a rescan was selected in the boot menu, which assembles the entries again.
+    pub rescan_requested: bool,
+}
+
+/// The default recovery state, which is used until the configuration is loaded.
+impl Default for RecoveryState {
+    fn default() -> Self {
+        Self {
+            timer: None,
+            entries: Vec::new(),
+            phases: PhasesConfiguration::default(),
+            error_delay: Duration::from_secs(DEFAULT_ERROR_DELAY_SECONDS),
+            fallback_entry: None,
+            fallback_attempted: false,
+            rescan_requested: false,
+        }
+    }
+}
+
+impl RecoveryState {
+    /// Finds the fallback entry, if it is configured and has not been booted yet.
+    pub fn fallback(&self) -> Option<&BootableEntry> {
+        if self.fallback_attempted {
+            return None;
+        }
+        let name = self.fallback_entry.as_ref()?;
+        BootableEntry::find(name, self.entries.iter())
+    }
+}
+
+/// The operations that can be selected in the recovery menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecoveryOperation {
+    /// Run Sprout again from the beginning.
+    Retry,
+    /// Open the boot menu with the entries that were assembled.
+    BootMenu,
+    /// Boot the default entry of the entries that were assembled.
+    BootDefault,
+    /// Boot the configured fallback entry.
+    BootFallback,
+    /// Reboot into the firmware setup user interface.
+    FirmwareSetup,
+    /// Power off the system.
+    PowerOff,
+    /// Exit Sprout, returning control to the firmware.
+    Exit,
+}
+
+impl RecoveryOperation {
+    /// The description of the operation in the recovery menu.
+    fn description(&self) -> &'static str {
+        match self {
+            RecoveryOperation::Retry => "Retry",
+            RecoveryOperation::BootMenu => "Open the boot menu",
+            RecoveryOperation::BootDefault => "Boot the default entry",
+            RecoveryOperation::BootFallback => "Boot the fallback entry",
+            RecoveryOperation::FirmwareSetup => "Reboot into firmware setup",
+            RecoveryOperation::PowerOff => "Power off",
+            RecoveryOperation::Exit => "Exit",
+        }
+    }
+}
+
+/// Determines the operations that are available with the recovery `state`.
+fn available_operations(state: &RecoveryState) -> Vec<RecoveryOperation> {
+    let mut operations = Vec::new();
+    operations.push(RecoveryOperation::Retry);
+
+    // Entries can only be booted if they were assembled.
+    if !state.entries.is_empty() {
+        operations.push(RecoveryOperation::BootMenu);
+        operations.push(RecoveryOperation::BootDefault);
+    }
+
+    if state.fallback().is_some() {
+        operations.push(RecoveryOperation::BootFallback);
+    }
+
+    // Only offer the firmware setup if the firmware supports it.
+    match Power::firmware_setup_supported() {
+        Ok(true) => operations.push(RecoveryOperation::FirmwareSetup),
+        Ok(false) => {}
+        Err(error) => warn!("unable to determine firmware setup support: {:#}", error),
+    }
+
+    operations.push(RecoveryOperation::PowerOff);
+    operations
+}
+
+/// Shows the recovery menu, which allows the user to recover from an error.
+/// If no operation is selected before the error delay of the `state`, the fallback entry is
+/// booted if available. Otherwise, [RecoveryOperation::Exit] is selected, which retains the
+/// behavior of returning to the firmware on unattended systems.
+pub fn select(state: &RecoveryState) -> Result<RecoveryOperation> {
+    let operations = available_operations(state);
+    let timeout = state.error_delay;
+    uefi::system::with_stdin(|input| {
+        loop {
+            info!("Recovery Menu:");
+            for (index, operation) in operations.iter().enumerate() {
+                info!("  [{}] {}", index, operation.description());
+            }
+            info!("Select a recovery option using the number keys.");
+            info!("Press Escape to exit and enter to display the options again.");
+
+            // Read from input until an operation is performed.
+            let operation = loop {
+                let operation = menu::read(input, &timeout)?;
+                if operation != MenuOperation::Nop {
+                    break operation;
+                }
+            };
+
+            match operation {
+                MenuOperation::Number(index) => {
+                    let Some(operation) = operations.get(index) else {
+                        info!("invalid recovery option number");
+                        continue;
+                    };
+                    return Ok(*operation);
+                }
+
+                MenuOperation::Exit => return Ok(RecoveryOperation::Exit),
+
+                // Unattended systems should come up with the fallback entry, if there is one.
+                MenuOperation::Timeout if state.fallback().is_some() => {
+                    info!("no recovery option selected, booting the fallback entry");
+                    return Ok(RecoveryOperation::BootFallback);
+                }
+
+                MenuOperation::Timeout => return Ok(RecoveryOperation::Exit),
+
+                // Raising the log level helps diagnose the error that brought up the menu.
+                MenuOperation::Debug => verbosity::raise_to_debug(),
+
+                // A screenshot of the recovery menu helps report the error.
+                MenuOperation::Screenshot => {
+                    if let Err(error) = screenshot::capture() {
+                        warn!("unable to capture screenshot: {:#}", error);
+                    }
+                }
+
+                // The build metadata identifies the build when reporting the error.
+                MenuOperation::About => menu::show_about(),
+
+                // Rescanning assembles the entries again, which is what a retry does.
+                MenuOperation::Rescan => return Ok(RecoveryOperation::Retry),
+
+                MenuOperation::Continue | MenuOperation::Nop => {}
+            }
+        }
+    })
+}
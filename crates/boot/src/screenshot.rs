@@ -0,0 +1,68 @@
+use alloc::format;
+use anyhow::{Context, Result};
+use core::ops::Deref;
+use edera_sprout_parsing::datetime::format_datetime;
+use eficore::framebuffer::Framebuffer;
+use eficore::platform::clock::PlatformClock;
+use log::info;
+use uefi::fs::{FileSystem, PathBuf};
+use uefi::proto::console::gop::GraphicsOutput;
+use uefi::proto::device_path::LoadedImageDevicePath;
+use uefi::proto::device_path::text::{AllowShortcuts, DisplayOnly};
+use uefi::proto::media::fs::SimpleFileSystem;
+
+/// The directory that contains the screenshots, on the partition Sprout was loaded from.
+const SCREENSHOTS_DIRECTORY: &str = "\\sprout\\screenshots";
+
+/// Captures the contents of the screen and writes it as a BMP image to the screenshots directory.
+/// The file name is derived from the time, so screenshots never replace each other.
+pub fn capture() -> Result<()> {
+    // Capture the screen first, so the screenshot does not include anything logged while writing it.
+    let bmp = {
+        let handle = uefi::boot::get_handle_for_protocol::<GraphicsOutput>()
+            .context("unable to find graphics output")?;
+        let mut gop = uefi::boot::open_protocol_exclusive::<GraphicsOutput>(handle)
+            .context("unable to open graphics output")?;
+        Framebuffer::capture(&mut gop)?.to_bmp()?
+    };
+
+    let now = PlatformClock::now().context("unable to read the real-time clock")?;
+    let file_name = format!("screenshot-{}.bmp", format_datetime(&now, "%Y%m%dT%H%M%S"));
+
+    // Open the LoadedImageDevicePath protocol to get the path to the current image.
+    // This is done in a block to ensure the release of the protocol.
+    let image_path = {
+        let current_image_device_path_protocol = uefi::boot::open_protocol_exclusive::<
+            LoadedImageDevicePath,
+        >(uefi::boot::image_handle())
+        .context("unable to get loaded image device path")?;
+        current_image_device_path_protocol.deref().to_boxed()
+    };
+
+    // Resolve the path to the screenshots directory.
+    let resolved = eficore::path::resolve_path(Some(&image_path), SCREENSHOTS_DIRECTORY)
+        .context("unable to resolve screenshots directory")?;
+    let directory = PathBuf::from(
+        resolved
+            .sub_path
+            .to_string16(DisplayOnly(false), AllowShortcuts(false))
+            .context("unable to convert screenshots directory to string")?,
+    );
+
+    // Open exclusive access to the filesystem.
+    let fs = uefi::boot::open_protocol_exclusive::<SimpleFileSystem>(resolved.filesystem_handle)
+        .context("unable to open filesystem")?;
+    let mut fs = FileSystem::new(fs);
+
+    fs.create_dir_all(&directory)
+        .context("unable to create screenshots directory")?;
+    let path = format!("{}\\{}", SCREENSHOTS_DIRECTORY, file_name);
+    let file_path = PathBuf::from(
+        uefi::CString16::try_from(path.as_str()).context("unable to convert screenshot path")?,
+    );
+    fs.write(&file_path, &bmp)
+        .context("unable to write screenshot")?;
+
+    info!("screenshot written to {}", path);
+    Ok(())
+}
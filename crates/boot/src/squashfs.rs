@@ -0,0 +1,54 @@
+use crate::context::SproutContext;
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::String;
+use anyhow::{Context, Result, bail};
+use edera_sprout_config::squashfs::SquashfsDeclaration;
+use log::{info, warn};
+
+/// Mounts the squashfs container with the `name` specified by the `squashfs` declaration.
+/// The digest of the container is verified before it is mounted, if one is declared.
+/// Returns false if the container was already mounted by an earlier run of Sprout.
+fn mount_container(
+    context: Rc<SproutContext>,
+    name: &str,
+    squashfs: &SquashfsDeclaration,
+) -> Result<bool> {
+    if eficore::fs_driver::squashfs_root(name).is_some() {
+        return Ok(false);
+    }
+
+    let path = context.stamp(&squashfs.path);
+    let resolved = eficore::path::resolve_path(Some(context.root().loaded_image_path()?), &path)
+        .context("unable to resolve path to squashfs container")?;
+
+    // The container is read once, so the verified data is the data that is mounted.
+    let data = resolved
+        .read_file_pages()
+        .context("unable to read squashfs container")?;
+    if let Some(expected) = &squashfs.sha256 {
+        let actual = hex::encode(eficore::hash::sha256(&data));
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            bail!(
+                "squashfs container {} has digest {}, expected {}",
+                path,
+                actual,
+                expected
+            );
+        }
+    }
+    eficore::fs_driver::mount_squashfs(name, data)
+}
+
+/// Mounts all the squashfs containers specified in `containers` as read-only volumes.
+/// A container that can not be mounted, like one that does not match its digest, is skipped
+/// with a warning, so only the entries that load files from it fail.
+pub fn mount(context: Rc<SproutContext>, containers: &BTreeMap<String, SquashfsDeclaration>) {
+    for (name, squashfs) in containers {
+        match mount_container(context.clone(), name, squashfs) {
+            Ok(true) => info!("mounted squashfs container {}", name),
+            Ok(false) => {}
+            Err(error) => warn!("unable to mount squashfs container {}: {:#}", name, error),
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use crate::actions::chainload::start_image;
+use crate::context::SproutContext;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use anyhow::{Context, Result};
+use edera_sprout_parsing::combine_options;
+use edera_sprout_parsing::pe::{
+    PeImage, SECTION_CMDLINE, SECTION_INITRD, SECTION_LINUX, SECTION_OSREL,
+};
+use eficore::loader::source::ImageSource;
+use eficore::loader::{ImageLoadRequest, ImageLoader};
+use eficore::media_loader::MediaLoaderData;
+use eficore::platform::tpm::PlatformTpm;
+use log::info;
+
+/// The sections of the Sprout image that are measured before the embedded kernel is booted.
+const MEASURED_SECTIONS: &[&str] = &[
+    SECTION_LINUX,
+    SECTION_OSREL,
+    SECTION_CMDLINE,
+    SECTION_INITRD,
+];
+
+/// Acquires the image of Sprout if a kernel is embedded in its `.linux` section,
+/// which means Sprout is used as the stub of a unified kernel image.
+pub fn embedded() -> Result<Option<PeImage<'static>>> {
+    let image = eficore::pe::current_image().context("unable to inspect sprout image")?;
+    Ok(image.linux().is_some().then_some(image))
+}
+
+/// Boots the kernel embedded in the Sprout `image` inside the provided `context`.
+///
+/// The kernel is passed the command line of the `.cmdline` section, and the initrd of the
+/// `.initrd` section is registered with the Linux initrd media loader. The sections are
+/// measured into the TPM before the kernel is loaded. Options appended to Sprout's
+/// command line are ignored when Secure Boot is enabled, as they are not signed.
+pub fn boot(context: Rc<SproutContext>, image: &PeImage<'static>) -> Result<()> {
+    let linux = image
+        .linux()
+        .context("sprout image has no embedded kernel")?;
+    if let Some(osrel) = image.osrel() {
+        info!("embedded kernel os-release:\n{}", osrel);
+    }
+
+    // Measure the sections into the TPM, if needed and possible.
+    for name in MEASURED_SECTIONS {
+        let Some(data) = image.section_data(name) else {
+            continue;
+        };
+        PlatformTpm::log_event(
+            PlatformTpm::PCR_KERNEL_IMAGE,
+            data,
+            &format!("sprout: stub {}", name),
+        )
+        .context(format!(
+            "unable to measure the {} section into the TPM",
+            name
+        ))?;
+    }
+
+    // Append the options of Sprout's command line to the embedded command line,
+    // unless Secure Boot requires the command line to be covered by the signature.
+    let append = context
+        .root()
+        .options()
+        .measured_append("sprout: stub appended options")?;
+    let options = combine_options(image.cmdline().map(String::from).into_iter().chain(append));
+
+    // In dry run mode, log the embedded kernel instead of booting it.
+    if context.root().options().dry_run {
+        info!(
+            "dry run: would boot embedded kernel with options '{}'",
+            options
+        );
+        return Ok(());
+    }
+
+    // Load the embedded kernel from the memory of the Sprout image.
+    let request = ImageLoadRequest::new(
+        uefi::boot::image_handle(),
+        ImageSource::DataBuffer {
+            path: None,
+            buffer: linux,
+        },
+    );
+    let kernel = context
+        .root()
+        .timing()
+        .measure("load image", || ImageLoader::load(request))?;
+
+    // The initrd is copied, as the media loader owns the data it provides.
+    let initrd = image
+        .initrd()
+        .map(|initrd| MediaLoaderData::from(Box::<[u8]>::from(initrd)));
+    start_image(context, &kernel, &options, initrd)
+}
@@ -0,0 +1,55 @@
+use anyhow::{Context, Result};
+use core::sync::atomic::{AtomicBool, Ordering};
+use eficore::logger::{self, console::CONSOLE_SINK_NAME};
+use log::{LevelFilter, info, warn};
+use uefi::proto::console::text::Key;
+
+/// The key that raises the log level to debug for the current boot.
+/// It can be held while Sprout starts or pressed in the boot menu.
+pub const DEBUG_KEY: char = 'd';
+
+/// Whether the log level was raised to debug for the current boot.
+static RAISED: AtomicBool = AtomicBool::new(false);
+
+/// Checks whether the [DEBUG_KEY] was held or pressed while Sprout started.
+/// Every pending key is consumed, so keys pressed before the boot menu is shown
+/// do not select an entry.
+pub fn debug_key_pending() -> Result<bool> {
+    uefi::system::with_stdin(|input| {
+        let mut pending = false;
+        while let Some(key) = input.read_key().context("unable to read key")? {
+            if let Key::Printable(c) = key
+                && char::from(c).eq_ignore_ascii_case(&DEBUG_KEY)
+            {
+                pending = true;
+            }
+        }
+        Ok(pending)
+    })
+}
+
+/// Raises the log level to debug for the current boot, then re-emits the buffered log
+/// on the console, so the diagnostics can be captured without editing the configuration.
+/// Raising the log level more than once does nothing.
+pub fn raise_to_debug() {
+    if RAISED.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    logger::set_level(log::max_level().max(LevelFilter::Debug));
+
+    // The console may have been cleared or scrolled, so the log so far is written again.
+    if let Err(error) = logger::replay(CONSOLE_SINK_NAME) {
+        warn!("unable to replay boot log: {:#}", error);
+    }
+    info!("log level raised to debug for this boot");
+}
+
+/// Sets the maximum `level` of log messages, unless the log level was raised to debug
+/// for the current boot, in which case the level is never lowered below debug.
+pub fn set_level(level: LevelFilter) {
+    if RAISED.load(Ordering::Acquire) {
+        logger::set_level(level.max(LevelFilter::Debug));
+    } else {
+        logger::set_level(level);
+    }
+}
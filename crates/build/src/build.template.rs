@@ -0,0 +1,17 @@
+/// The build metadata of Sprout, which is embedded so it can be read from the image
+/// without running it. Each line is a `key=value` pair.
+#[used]
+#[unsafe(link_section = ".build")]
+static BUILD_INFO: [u8; {size}] = *include_bytes!(concat!(env!("OUT_DIR"), "/build.out"));
+
+/// The version of Sprout.
+pub const VERSION: &str = {version};
+
+/// The git commit Sprout was built from, or `unknown` if it was not built from git.
+pub const COMMIT: &str = {commit};
+
+/// The date Sprout was built on, formatted as `YYYY-MM-DD` in UTC.
+pub const DATE: &str = {date};
+
+/// The version of the Rust compiler Sprout was built with.
+pub const RUSTC: &str = {rustc};
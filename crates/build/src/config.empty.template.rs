@@ -0,0 +1,5 @@
+/// Access the configuration embedded into Sprout at build time, if any.
+/// No configuration was embedded into this build of Sprout.
+pub fn embedded_config() -> Option<&'static [u8]> {
+    None
+}
@@ -0,0 +1,10 @@
+/// The configuration embedded into Sprout at build time.
+/// This is used when no configuration file exists on the ESP.
+#[used]
+#[unsafe(link_section = ".config")]
+static EMBEDDED_CONFIG: [u8; {size}] = *include_bytes!(concat!(env!("OUT_DIR"), "/config.out"));
+
+/// Access the configuration embedded into Sprout at build time, if any.
+pub fn embedded_config() -> Option<&'static [u8]> {
+    Some(&EMBEDDED_CONFIG)
+}
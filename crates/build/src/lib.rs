@@ -1,4 +1,6 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::{env, fs};
 
 /// Block size of the sbat section.
@@ -7,6 +9,46 @@ const SBAT_BLOCK_SIZE: usize = 512;
 /// Template contents for the sbat.generated.rs file.
 const SBAT_RS_TEMPLATE: &str = include_str!("sbat.template.rs");
 
+/// Template contents for the config.generated.rs file when a configuration is embedded.
+const CONFIG_RS_TEMPLATE: &str = include_str!("config.template.rs");
+
+/// Template contents for the config.generated.rs file when no configuration is embedded.
+const CONFIG_EMPTY_RS_TEMPLATE: &str = include_str!("config.empty.template.rs");
+
+/// Template contents for a generated embedded section, when the section has data.
+const SECTION_RS_TEMPLATE: &str = include_str!("section.template.rs");
+
+/// Template contents for a generated embedded section, when the section has no data.
+const SECTION_EMPTY_RS_TEMPLATE: &str = include_str!("section.empty.template.rs");
+
+/// Template contents for the build.generated.rs file.
+const BUILD_RS_TEMPLATE: &str = include_str!("build.template.rs");
+
+/// Environment variable that specifies the path to a configuration file to embed.
+const EMBEDDED_CONFIG_ENV: &str = "SPROUT_EMBEDDED_CONFIG";
+
+/// Environment variable that specifies the path to a BMP splash image to embed.
+const BRANDING_SPLASH_ENV: &str = "SPROUT_BRANDING_SPLASH";
+
+/// Environment variable that specifies the product name to embed.
+const BRANDING_NAME_ENV: &str = "SPROUT_BRANDING_NAME";
+
+/// Environment variable that specifies the path to a menu theme file to embed.
+const BRANDING_THEME_ENV: &str = "SPROUT_BRANDING_THEME";
+
+/// Environment variable that specifies the path to a PEM or DER public key that signs configurations.
+const CONFIG_SIGNING_KEY_ENV: &str = "SPROUT_CONFIG_SIGNING_KEY";
+
+/// Environment variable that allows booting with Secure Boot enabled when no signing key is
+/// embedded, which leaves the files on the ESP unverified.
+const ALLOW_UNVERIFIED_ESP_ENV: &str = "SPROUT_ALLOW_UNVERIFIED_ESP";
+
+/// Environment variable that overrides the git commit, for builds outside of a git checkout.
+const BUILD_COMMIT_ENV: &str = "SPROUT_BUILD_COMMIT";
+
+/// Environment variable that specifies the build time for reproducible builds.
+const SOURCE_DATE_EPOCH_ENV: &str = "SOURCE_DATE_EPOCH";
+
 /// Pad with zeros the given `data` to a multiple of `block_size`.
 fn block_pad(data: &mut Vec<u8>, block_size: usize) {
     let needed = data.len().div_ceil(block_size).max(1) * block_size;
@@ -74,3 +116,351 @@ pub fn generate_sbat_module() {
     // Write the sbat.generated.rs file to the output directory.
     fs::write(&rs_file, sbat_rs).expect("unable to write sbat.generated.rs");
 }
+
+/// Generate a .config link section module. This should be coupled with including the config module
+/// in the crate that intends to embed the configuration section.
+/// If the SPROUT_EMBEDDED_CONFIG environment variable is set, the configuration file it points to
+/// is written to config.out and included by a generated config.generated.rs file. Otherwise, the
+/// generated module reports that no configuration is embedded.
+pub fn generate_config_module() {
+    // Notify Cargo that if the embedded config path changes, we need to regenerate the module.
+    println!("cargo:rerun-if-env-changed={}", EMBEDDED_CONFIG_ENV);
+
+    // The output directory to place the config.out into.
+    let output_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR not set"));
+
+    // The output path to the config.out file.
+    let out_file = output_dir.join("config.out");
+
+    // The output path to the config.generated.rs file.
+    let rs_file = output_dir.join("config.generated.rs");
+
+    // If no configuration is specified, generate a module without an embedded configuration.
+    let Some(config_file) = env::var_os(EMBEDDED_CONFIG_ENV).filter(|path| !path.is_empty()) else {
+        fs::write(&rs_file, CONFIG_EMPTY_RS_TEMPLATE).expect("unable to write config.generated.rs");
+        return;
+    };
+    let config_file = PathBuf::from(config_file);
+
+    // Notify Cargo that if the configuration file changes, we need to regenerate config.out.
+    println!(
+        "cargo:rerun-if-changed={}",
+        config_file
+            .to_str()
+            .expect("unable to convert embedded config path to a string")
+    );
+
+    // Read the configuration file to embed.
+    let config = fs::read(&config_file).expect("unable to read embedded config file");
+
+    // Write the config.out file to the output directory.
+    fs::write(&out_file, &config).expect("unable to write config.out");
+
+    // Generate the contents of the config.generated.rs file.
+    // The size must be the size of the config.out file.
+    let config_rs = CONFIG_RS_TEMPLATE.replace("{size}", &config.len().to_string());
+
+    // Write the config.generated.rs file to the output directory.
+    fs::write(&rs_file, config_rs).expect("unable to write config.generated.rs");
+}
+
+/// Runs the `program` with `args` and returns its trimmed standard output, if it succeeds.
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let output = String::from_utf8(output.stdout).ok()?;
+    Some(output.trim().to_string()).filter(|output| !output.is_empty())
+}
+
+/// Formats the `timestamp` in seconds since the Unix epoch as a `YYYY-MM-DD` date in UTC.
+fn format_date(timestamp: u64) -> String {
+    // Convert days since the epoch to a civil date, from Howard Hinnant's date algorithms.
+    let days = (timestamp / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Generate a .build link section module with the build metadata of Sprout. This should be
+/// coupled with including the build module in the crate that intends to embed the section.
+/// The git commit, build date, and rustc version are written to build.out, which is included
+/// by a generated build.generated.rs file that also provides them as constants.
+pub fn generate_build_module() {
+    // Notify Cargo that if the inputs of the metadata change, we need to regenerate the module.
+    println!("cargo:rerun-if-env-changed=CARGO_PKG_VERSION");
+    println!("cargo:rerun-if-env-changed={}", BUILD_COMMIT_ENV);
+    println!("cargo:rerun-if-env-changed={}", SOURCE_DATE_EPOCH_ENV);
+
+    // The output directory to place the build.out into.
+    let output_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR not set"));
+
+    // The version of the package.
+    let version = env::var("CARGO_PKG_VERSION").expect("CARGO_PKG_VERSION not set");
+
+    // The git commit, which is regenerated when the checked out commit changes.
+    if let Some(head) = command_output("git", &["rev-parse", "--git-path", "HEAD"]) {
+        println!("cargo:rerun-if-changed={}", head);
+    }
+    if let Some(refs) = command_output("git", &["rev-parse", "--git-path", "refs"]) {
+        println!("cargo:rerun-if-changed={}", refs);
+    }
+    let commit = env::var(BUILD_COMMIT_ENV)
+        .ok()
+        .filter(|commit| !commit.is_empty())
+        .or_else(|| command_output("git", &["rev-parse", "--short=12", "HEAD"]))
+        .unwrap_or_else(|| "unknown".to_string());
+
+    // The build date, which uses SOURCE_DATE_EPOCH if set so builds can be reproduced.
+    let timestamp = match env::var(SOURCE_DATE_EPOCH_ENV) {
+        Ok(epoch) => epoch
+            .parse::<u64>()
+            .expect("unable to parse SOURCE_DATE_EPOCH"),
+        Err(_) => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time is before the unix epoch")
+            .as_secs(),
+    };
+    let date = format_date(timestamp);
+
+    // The version of the compiler that Cargo builds with.
+    let rustc_path = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc =
+        command_output(&rustc_path, &["--version"]).unwrap_or_else(|| "unknown".to_string());
+
+    // Write the build.out file to the output directory.
+    let encoded = format!(
+        "version={}\ncommit={}\ndate={}\nrustc={}\n",
+        version, commit, date, rustc
+    );
+    fs::write(output_dir.join("build.out"), &encoded).expect("unable to write build.out");
+
+    // Generate the contents of the build.generated.rs file.
+    // The size must be the size of the build.out file.
+    let build_rs = BUILD_RS_TEMPLATE
+        .replace("{size}", &encoded.len().to_string())
+        .replace("{version}", &format!("{:?}", version))
+        .replace("{commit}", &format!("{:?}", commit))
+        .replace("{date}", &format!("{:?}", date))
+        .replace("{rustc}", &format!("{:?}", rustc));
+
+    // Write the build.generated.rs file to the output directory.
+    fs::write(output_dir.join("build.generated.rs"), build_rs)
+        .expect("unable to write build.generated.rs");
+}
+
+/// A resource that is embedded into a link section by [generate_branding_module].
+struct EmbeddedSection {
+    /// The name of the link section, which must be at most 8 bytes.
+    section: &'static str,
+    /// The name of the static that holds the data.
+    constant: &'static str,
+    /// The name of the generated accessor function.
+    accessor: &'static str,
+    /// The description of the resource, used in the generated documentation.
+    description: &'static str,
+    /// The name of the file in the output directory that holds the data.
+    file: &'static str,
+}
+
+/// Generates the module code for the embedded `resource` with the specified `data`,
+/// writing the data to the output directory if there is any.
+fn generate_section(
+    output_dir: &Path,
+    resource: &EmbeddedSection,
+    data: Option<Vec<u8>>,
+) -> String {
+    let Some(data) = data else {
+        return SECTION_EMPTY_RS_TEMPLATE
+            .replace("{description}", resource.description)
+            .replace("{accessor}", resource.accessor);
+    };
+
+    // Write the data to the output directory, so the generated module can include it.
+    fs::write(output_dir.join(resource.file), &data)
+        .unwrap_or_else(|_| panic!("unable to write {}", resource.file));
+
+    // The size must be the size of the written data.
+    let doc = format!("The {} embedded into Sprout.", resource.description);
+    SECTION_RS_TEMPLATE
+        .replace("{doc}", &doc)
+        .replace("{section}", resource.section)
+        .replace("{constant}", resource.constant)
+        .replace("{size}", &data.len().to_string())
+        .replace("{file}", resource.file)
+        .replace("{description}", resource.description)
+        .replace("{accessor}", resource.accessor)
+}
+
+/// Reads the file specified by the environment variable `name`, if it is set.
+/// Cargo is notified to regenerate when the variable or the file changes.
+fn read_env_file(name: &str) -> Option<Vec<u8>> {
+    println!("cargo:rerun-if-env-changed={}", name);
+    let path = PathBuf::from(env::var_os(name).filter(|path| !path.is_empty())?);
+    println!(
+        "cargo:rerun-if-changed={}",
+        path.to_str()
+            .expect("unable to convert embedded file path to a string")
+    );
+    Some(fs::read(&path).unwrap_or_else(|_| panic!("unable to read {}", path.display())))
+}
+
+/// Generate the branding link section module. This should be coupled with including the branding
+/// module in the crate that intends to embed the branding sections.
+/// Downstreams can brand Sprout without changing its source by setting the SPROUT_BRANDING_SPLASH,
+/// SPROUT_BRANDING_NAME, and SPROUT_BRANDING_THEME environment variables at build time, which
+/// embed a splash image, a product name, and a menu theme into `.splash`, `.brand`, and `.theme`.
+pub fn generate_branding_module() {
+    // The output directory to place the branding files into.
+    let output_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR not set"));
+
+    // The product name is provided directly rather than as a file.
+    println!("cargo:rerun-if-env-changed={}", BRANDING_NAME_ENV);
+    let name = env::var(BRANDING_NAME_ENV)
+        .ok()
+        .filter(|name| !name.is_empty())
+        .map(String::into_bytes);
+
+    let sections = [
+        (
+            EmbeddedSection {
+                section: ".splash",
+                constant: "EMBEDDED_SPLASH",
+                accessor: "embedded_splash",
+                description: "splash image",
+                file: "splash.out",
+            },
+            read_env_file(BRANDING_SPLASH_ENV),
+        ),
+        (
+            EmbeddedSection {
+                section: ".brand",
+                constant: "EMBEDDED_PRODUCT_NAME",
+                accessor: "embedded_product_name",
+                description: "product name",
+                file: "brand.out",
+            },
+            name,
+        ),
+        (
+            EmbeddedSection {
+                section: ".theme",
+                constant: "EMBEDDED_THEME",
+                accessor: "embedded_theme",
+                description: "menu theme",
+                file: "theme.out",
+            },
+            read_env_file(BRANDING_THEME_ENV),
+        ),
+    ];
+
+    // Generate the contents of the branding.generated.rs file.
+    let branding_rs = sections
+        .into_iter()
+        .map(|(resource, data)| generate_section(&output_dir, &resource, data))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    // Write the branding.generated.rs file to the output directory.
+    fs::write(output_dir.join("branding.generated.rs"), branding_rs)
+        .expect("unable to write branding.generated.rs");
+}
+
+/// Decodes the standard base64 `text`, ignoring whitespace.
+/// Returns [None] if the text is not valid base64.
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut decoded = Vec::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in text.chars().filter(|c| !c.is_whitespace()) {
+        if c == '=' {
+            break;
+        }
+        let value = match c {
+            'A'..='Z' => c as u32 - 'A' as u32,
+            'a'..='z' => c as u32 - 'a' as u32 + 26,
+            '0'..='9' => c as u32 - '0' as u32 + 52,
+            '+' => 62,
+            '/' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+        }
+    }
+    Some(decoded)
+}
+
+/// Converts the public `key` to DER. PEM keys are decoded from their `PUBLIC KEY` block,
+/// and DER keys are returned as is. Panics if the key is neither.
+fn public_key_der(key: Vec<u8>) -> Vec<u8> {
+    let Ok(text) = std::str::from_utf8(&key) else {
+        return key;
+    };
+    let Some(start) = text.find("-----BEGIN PUBLIC KEY-----") else {
+        return key;
+    };
+    let body = &text[start + "-----BEGIN PUBLIC KEY-----".len()..];
+    let end = body
+        .find("-----END PUBLIC KEY-----")
+        .expect("configuration signing key has no end of the PEM block");
+    decode_base64(&body[..end]).expect("configuration signing key has invalid PEM contents")
+}
+
+/// Generate a .pubkey link section module. This should be coupled with including the signing key
+/// module in the crate that intends to embed the public key section.
+/// If the SPROUT_CONFIG_SIGNING_KEY environment variable is set, the PEM or DER public key it points
+/// to is converted to DER and embedded, so the key that verifies configuration signatures is
+/// covered by the signature of Sprout itself. Otherwise, the generated module reports no key.
+/// The module also records whether SPROUT_ALLOW_UNVERIFIED_ESP is set to `1`.
+pub fn generate_signing_key_module() {
+    println!("cargo:rerun-if-env-changed={}", ALLOW_UNVERIFIED_ESP_ENV);
+
+    // The output directory to place the pubkey.out into.
+    let output_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR not set"));
+
+    // A public key is a DER SEQUENCE, so anything else is a mistake in the build inputs.
+    let key = read_env_file(CONFIG_SIGNING_KEY_ENV).map(public_key_der);
+    if let Some(ref key) = key {
+        assert!(
+            key.first() == Some(&0x30),
+            "configuration signing key is not a DER or PEM public key"
+        );
+    }
+
+    let resource = EmbeddedSection {
+        section: ".pubkey",
+        constant: "EMBEDDED_SIGNING_KEY",
+        accessor: "embedded_signing_key",
+        description: "configuration signing public key",
+        file: "pubkey.out",
+    };
+
+    // Only an explicit opt-out allows Secure Boot without a key to verify the ESP with.
+    let allow_unverified_esp = env::var(ALLOW_UNVERIFIED_ESP_ENV).is_ok_and(|value| value == "1");
+    let mut module = generate_section(&output_dir, &resource, key);
+    module.push_str(&format!(
+        "\n/// Whether Sprout was built to boot with Secure Boot enabled without a signing key.\n\
+         pub const ALLOW_UNVERIFIED_ESP: bool = {};\n",
+        allow_unverified_esp
+    ));
+
+    // Write the pubkey.generated.rs file to the output directory.
+    fs::write(output_dir.join("pubkey.generated.rs"), module)
+        .expect("unable to write pubkey.generated.rs");
+}
@@ -0,0 +1,5 @@
+/// Access the {description} embedded into Sprout at build time, if any.
+/// No {description} was embedded into this build of Sprout.
+pub fn {accessor}() -> Option<&'static [u8]> {
+    None
+}
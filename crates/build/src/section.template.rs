@@ -0,0 +1,10 @@
+/// {doc}
+/// This was embedded into Sprout at build time.
+#[used]
+#[unsafe(link_section = "{section}")]
+static {constant}: [u8; {size}] = *include_bytes!(concat!(env!("OUT_DIR"), "/{file}"));
+
+/// Access the {description} embedded into Sprout at build time, if any.
+pub fn {accessor}() -> Option<&'static [u8]> {
+    Some(&{constant})
+}
@@ -0,0 +1,817 @@
+use anyhow::{Context, Result};
+use edera_sprout_config::actions::{ActionDeclaration, ActionErrorPolicy};
+use edera_sprout_config::drivers::load_order;
+use edera_sprout_config::entries::EntryDeclaration;
+use edera_sprout_config::extractors::network::NetworkField;
+use edera_sprout_config::loader::ParsedConfiguration;
+use edera_sprout_config::phases::FAILED_ACTION_KEY;
+use edera_sprout_config::{
+    RootConfiguration, SECURE_BOOT_KEY, SPROUT_COMMIT_KEY, SPROUT_VERSION_KEY, VALUES_NAMESPACE,
+};
+use edera_sprout_parsing::iscsi::is_valid_name;
+use edera_sprout_parsing::template::expression_reference;
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+use toml::Value;
+
+/// The keys provided to the template entry by the BLS generator.
+const BLS_GENERATOR_KEYS: &[&str] = &[
+    "title-base",
+    "title",
+    "chainload",
+    "options",
+    "initrd",
+    "version",
+    "machine-id",
+];
+
+/// The severity of a [Diagnostic].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The configuration will not work as intended.
+    Error,
+    /// The configuration might not work as intended.
+    Warning,
+}
+
+/// A single problem found in a configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The severity of the problem.
+    pub severity: Severity,
+    /// The description of the problem.
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Creates an error [Diagnostic] with the specified `message`.
+    fn error(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+        }
+    }
+
+    /// Creates a warning [Diagnostic] with the specified `message`.
+    fn warning(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}: {}", severity, self.message)
+    }
+}
+
+/// Joins the `key` onto the dotted configuration `path`.
+fn join_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// Reports the keys in `original` that are not present in `known`.
+/// The `known` value is the configuration after a round-trip through [RootConfiguration],
+/// so any key that is missing from it was ignored when parsing the configuration.
+fn check_unknown_keys(
+    original: &Value,
+    known: &Value,
+    path: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    match (original, known) {
+        (Value::Table(original), Value::Table(known)) => {
+            for (key, value) in original {
+                let path = join_path(path, key);
+                match known.get(key) {
+                    // The key is known, so check the keys inside of it.
+                    Some(known) => check_unknown_keys(value, known, &path, diagnostics),
+                    // The key was dropped when parsing, so it is unknown.
+                    None => diagnostics.push(Diagnostic::error(format!("unknown key `{}`", path))),
+                }
+            }
+        }
+
+        (Value::Array(original), Value::Array(known)) => {
+            for (index, (value, known)) in original.iter().zip(known).enumerate() {
+                let path = format!("{}[{}]", path, index);
+                check_unknown_keys(value, known, &path, diagnostics);
+            }
+        }
+
+        // Other values do not contain keys.
+        _ => {}
+    }
+}
+
+/// Reports the action references in `actions` that do not name a declared action.
+/// The `owner` describes what references the actions in the diagnostic.
+fn check_action_references(
+    config: &RootConfiguration,
+    owner: &str,
+    actions: &[String],
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for action in actions {
+        // Action names that use values can only be resolved at runtime.
+        if action.contains('$') {
+            continue;
+        }
+
+        if !config.actions.contains_key(action) {
+            diagnostics.push(Diagnostic::error(format!(
+                "{} references unknown action `{}`",
+                owner, action
+            )));
+        }
+    }
+}
+
+/// The actions that the `action` can execute itself, which are its fallback action and the
+/// actions of its action group. Action names that use values can only be resolved at runtime,
+/// so they are skipped.
+fn executed_actions(action: &ActionDeclaration) -> Vec<&String> {
+    let mut actions = Vec::new();
+    if let ActionErrorPolicy::Fallback(ref fallback) = action.on_error {
+        actions.push(fallback);
+    }
+    if let Some(ref action_group) = action.action_group {
+        actions.extend(&action_group.actions);
+    }
+    actions.retain(|action| !action.contains('$'));
+    actions
+}
+
+/// Finds a chain of actions from the last action of `chain` back to its first action, only
+/// passing actions that sort after the first action. Returns whether the chain was closed.
+fn close_action_cycle<'a>(
+    config: &'a RootConfiguration,
+    chain: &mut Vec<&'a String>,
+    visited: &mut BTreeSet<&'a String>,
+) -> bool {
+    let (Some(first), Some(last)) = (chain.first().copied(), chain.last().copied()) else {
+        return false;
+    };
+    let Some(action) = config.actions.get(last) else {
+        return false;
+    };
+    for next in executed_actions(action) {
+        if next == first {
+            chain.push(next);
+            return true;
+        }
+        if next < first || !visited.insert(next) {
+            continue;
+        }
+        chain.push(next);
+        if close_action_cycle(config, chain, visited) {
+            return true;
+        }
+        chain.pop();
+    }
+    false
+}
+
+/// Reports the actions that execute themselves again, which Sprout refuses at runtime.
+/// Each cycle is reported once, starting at the action that sorts first.
+fn check_action_cycles(config: &RootConfiguration, diagnostics: &mut Vec<Diagnostic>) {
+    for name in config.actions.keys() {
+        let mut chain = vec![name];
+        if close_action_cycle(config, &mut chain, &mut BTreeSet::new()) {
+            let chain = chain
+                .iter()
+                .map(|action| format!("`{}`", action))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            diagnostics.push(Diagnostic::error(format!(
+                "action `{}` executes itself again: {}",
+                name, chain
+            )));
+        }
+    }
+}
+
+/// Reports all the dangling action references in the `config`.
+fn check_dangling_actions(config: &RootConfiguration, diagnostics: &mut Vec<Diagnostic>) {
+    for (name, action) in &config.actions {
+        let owner = format!("action `{}`", name);
+        if let ActionErrorPolicy::Fallback(ref fallback) = action.on_error {
+            check_action_references(config, &owner, core::slice::from_ref(fallback), diagnostics);
+        }
+
+        if let Some(ref action_group) = action.action_group {
+            check_action_references(config, &owner, &action_group.actions, diagnostics);
+        }
+    }
+
+    check_action_cycles(config, diagnostics);
+
+    for (name, entry) in &config.entries {
+        let owner = format!("entry `{}`", name);
+        check_action_references(config, &owner, &entry.actions, diagnostics);
+    }
+
+    for (name, generator) in &config.generators {
+        for entry in generator_entries(generator) {
+            let owner = format!("generator `{}`", name);
+            check_action_references(config, &owner, &entry.actions, diagnostics);
+        }
+    }
+
+    let phases = [
+        ("early", &config.phases.early),
+        ("startup", &config.phases.startup),
+        ("late", &config.phases.late),
+        ("pre-exec", &config.phases.pre_exec),
+        ("on-failure", &config.phases.on_failure),
+    ];
+    for (phase, configurations) in phases {
+        for configuration in configurations {
+            let owner = format!("{} phase", phase);
+            check_action_references(config, &owner, &configuration.actions, diagnostics);
+        }
+    }
+}
+
+/// Reports drivers that depend on unknown drivers or on each other in a cycle.
+fn check_driver_order(config: &RootConfiguration, diagnostics: &mut Vec<Diagnostic>) {
+    if let Err(error) = load_order(&config.drivers) {
+        diagnostics.push(Diagnostic::error(error));
+    }
+}
+
+/// Reports an iSCSI initiator name that the firmware would reject.
+fn check_iscsi(config: &RootConfiguration, diagnostics: &mut Vec<Diagnostic>) {
+    if let Some(name) = config
+        .iscsi
+        .as_ref()
+        .and_then(|iscsi| iscsi.initiator_name.as_ref())
+        && !is_valid_name(name)
+    {
+        diagnostics.push(Diagnostic::error(format!(
+            "`iscsi.initiator-name` is not a valid iscsi name: {}",
+            name
+        )));
+    }
+}
+
+/// Reports squashfs containers whose digest is not a SHA-256 digest in hex, which would
+/// never match the container, so it would never be mounted.
+fn check_squashfs(config: &RootConfiguration, diagnostics: &mut Vec<Diagnostic>) {
+    for (name, squashfs) in &config.squashfs {
+        if let Some(digest) = &squashfs.sha256
+            && (digest.len() != 64 || !digest.chars().all(|c| c.is_ascii_hexdigit()))
+        {
+            diagnostics.push(Diagnostic::error(format!(
+                "squashfs `{}` has a sha256 digest that is not 64 hex digits: {}",
+                name, digest
+            )));
+        }
+    }
+}
+
+/// Reports network extractors that read a DHCP option without specifying its code.
+fn check_network_extractors(config: &RootConfiguration, diagnostics: &mut Vec<Diagnostic>) {
+    for (name, extractor) in &config.extractors {
+        if let Some(network) = &extractor.network
+            && network.field == NetworkField::DhcpOption
+            && network.option.is_none()
+        {
+            diagnostics.push(Diagnostic::error(format!(
+                "extractor `{}` reads a dhcp option but does not specify `option`",
+                name
+            )));
+        }
+    }
+}
+
+/// Acquires the template entries of the `generator`.
+fn generator_entries(
+    generator: &edera_sprout_config::generators::GeneratorDeclaration,
+) -> Vec<&EntryDeclaration> {
+    let mut entries = Vec::new();
+    if let Some(ref matrix) = generator.matrix {
+        entries.push(&matrix.entry);
+    }
+    if let Some(ref bls) = generator.bls {
+        entries.push(&bls.entry);
+    }
+    if let Some(ref list) = generator.list {
+        entries.push(&list.entry);
+    }
+    entries
+}
+
+/// Determines whether the `declaration` has no configuration set.
+/// Declarations consist of optional configurations, so an empty declaration serializes
+/// to an empty table. The `ignored` keys are settings that are not a configuration.
+fn is_empty_declaration(declaration: &impl serde::Serialize, ignored: &[&str]) -> Result<bool> {
+    let value = Value::try_from(declaration).context("unable to serialize declaration")?;
+    Ok(value
+        .as_table()
+        .is_some_and(|table| table.keys().all(|key| ignored.contains(&key.as_str()))))
+}
+
+/// Reports the actions, extractors and generators that have no configuration set.
+/// Sprout fails at runtime when it encounters one of these.
+fn check_empty_declarations(
+    config: &RootConfiguration,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<()> {
+    for (name, action) in &config.actions {
+        if is_empty_declaration(action, &["when", "on-error", "timeout"])? {
+            diagnostics.push(Diagnostic::error(format!(
+                "action `{}` does not declare a configuration",
+                name
+            )));
+        }
+    }
+
+    for (name, extractor) in &config.extractors {
+        // The error handling settings are not an extractor configuration.
+        if is_empty_declaration(extractor, &["required", "on-error"])? {
+            diagnostics.push(Diagnostic::error(format!(
+                "extractor `{}` does not declare a configuration",
+                name
+            )));
+        }
+    }
+
+    for (name, generator) in &config.generators {
+        if is_empty_declaration(generator, &[])? {
+            diagnostics.push(Diagnostic::error(format!(
+                "generator `{}` does not declare a configuration",
+                name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Collects the names of all the values that can be provided to the sprout context.
+fn known_value_keys(config: &RootConfiguration) -> BTreeSet<String> {
+    let mut keys = BTreeSet::new();
+
+    // Values declared globally and the values calculated by extractors.
+    keys.extend(config.values.keys().cloned());
+    keys.extend(config.extractors.keys().cloned());
+    keys.insert(SECURE_BOOT_KEY.to_string());
+    keys.insert(SPROUT_VERSION_KEY.to_string());
+    keys.insert(SPROUT_COMMIT_KEY.to_string());
+
+    // Global values and built-in values can also be referenced with the values namespace.
+    let global: Vec<String> = keys.iter().cloned().collect();
+    for key in global {
+        keys.insert(format!("{}.{}", VALUES_NAMESPACE, key));
+    }
+
+    // Values declared by entries.
+    for entry in config.entries.values() {
+        keys.extend(entry.values.keys().cloned());
+    }
+
+    // Values declared by phases.
+    let phases = [
+        &config.phases.early,
+        &config.phases.startup,
+        &config.phases.late,
+        &config.phases.pre_exec,
+        &config.phases.on_failure,
+    ];
+    for configuration in phases.into_iter().flatten() {
+        keys.extend(configuration.values.keys().cloned());
+    }
+
+    // The on-failure phase is provided the name of the failed action.
+    if !config.phases.on_failure.is_empty() {
+        keys.insert(FAILED_ACTION_KEY.to_string());
+    }
+
+    // Values declared by generators and their template entries.
+    for generator in config.generators.values() {
+        for entry in generator_entries(generator) {
+            keys.extend(entry.values.keys().cloned());
+        }
+
+        // Generator values can be referenced with or without the generator namespace.
+        let mut generated = BTreeSet::new();
+        let mut namespace = "";
+
+        if let Some(ref matrix) = generator.matrix {
+            generated.extend(matrix.values.keys().cloned());
+            namespace = "matrix";
+        }
+
+        if let Some(ref list) = generator.list {
+            for values in &list.values {
+                generated.extend(values.keys().cloned());
+            }
+
+            if list.from.is_some() {
+                generated.insert(list.item_key.clone());
+            }
+            namespace = "list";
+        }
+
+        if generator.bls.is_some() {
+            generated.extend(BLS_GENERATOR_KEYS.iter().map(|key| key.to_string()));
+            namespace = "bls";
+        }
+
+        for key in generated {
+            keys.insert(format!("{}.{}", namespace, key));
+            keys.insert(key);
+        }
+    }
+    keys
+}
+
+/// Extracts the names of the value references in `text`.
+/// A value reference is a `$` followed by the characters that make up a value name,
+/// or a braced expression like `${name}` or `${upper:name}`. Braced expressions with a default
+/// are not references, as they resolve even when the value is missing.
+/// Escaped dollar signs (`$$`) are literal text and not references.
+fn value_references(text: &str) -> Vec<&str> {
+    let mut references = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        rest = &rest[start + 1..];
+
+        // Escaped dollar signs are literal text and not references.
+        if let Some(escaped) = rest.strip_prefix('$') {
+            rest = escaped;
+            continue;
+        }
+
+        // Braced expressions contain the name up to the closing brace.
+        if let Some(braced) = rest.strip_prefix('{')
+            && let Some(end) = braced.find('}')
+        {
+            if let Some(reference) = expression_reference(&braced[..end]) {
+                references.push(reference);
+            }
+            rest = &braced[end + 1..];
+            continue;
+        }
+
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+            .unwrap_or(rest.len());
+        if end > 0 {
+            references.push(&rest[..end]);
+        }
+        rest = &rest[end..];
+    }
+    references
+}
+
+/// Reports the value references in `value` that do not resolve to any of the `known` keys.
+/// Stamping replaces `$KEY` anywhere in a string, so a reference resolves
+/// if it starts with a known key.
+fn check_value_references(
+    value: &Value,
+    known: &BTreeSet<String>,
+    path: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    match value {
+        Value::String(text) => {
+            for reference in value_references(text) {
+                let resolved = known
+                    .iter()
+                    .any(|key| !key.is_empty() && reference.starts_with(key.as_str()));
+                if !resolved {
+                    diagnostics.push(Diagnostic::warning(format!(
+                        "`{}` references unknown value `${}`",
+                        path, reference
+                    )));
+                }
+            }
+        }
+
+        Value::Table(table) => {
+            for (key, value) in table {
+                check_value_references(value, known, &join_path(path, key), diagnostics);
+            }
+        }
+
+        Value::Array(array) => {
+            for (index, value) in array.iter().enumerate() {
+                let path = format!("{}[{}]", path, index);
+                check_value_references(value, known, &path, diagnostics);
+            }
+        }
+
+        // Other values can not reference values.
+        _ => {}
+    }
+}
+
+/// Checks the `parsed` configuration and reports all the problems found.
+/// The configuration loader already migrates the configuration versions,
+/// and any warnings it produced are reported as well.
+pub fn check(parsed: &ParsedConfiguration) -> Result<Vec<Diagnostic>> {
+    let value = &parsed.value;
+    let mut diagnostics = parsed
+        .warnings
+        .iter()
+        .map(|warning| Diagnostic::warning(warning.clone()))
+        .collect::<Vec<_>>();
+
+    // Parse the configuration the same way Sprout does.
+    let config: RootConfiguration = edera_sprout_config::loader::into_configuration(value.clone())?;
+
+    // Serialize the parsed configuration to find the keys that were ignored.
+    let known = Value::try_from(&config).context("unable to serialize sprout configuration")?;
+    check_unknown_keys(value, &known, "", &mut diagnostics);
+
+    check_dangling_actions(&config, &mut diagnostics);
+    check_driver_order(&config, &mut diagnostics);
+    check_iscsi(&config, &mut diagnostics);
+    check_squashfs(&config, &mut diagnostics);
+    check_network_extractors(&config, &mut diagnostics);
+    check_empty_declarations(&config, &mut diagnostics)?;
+
+    let keys = known_value_keys(&config);
+    check_value_references(value, &keys, "", &mut diagnostics);
+    Ok(diagnostics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_str(config: &str) -> Vec<Diagnostic> {
+        let parsed = edera_sprout_config::loader::parse(config.as_bytes(), "test", |path| {
+            anyhow::bail!("unexpected include of {}", path)
+        })
+        .unwrap();
+        check(&parsed).unwrap()
+    }
+
+    #[test]
+    fn valid_configuration_has_no_diagnostics() {
+        let diagnostics = check_str(
+            r#"
+            version = 1
+
+            [values]
+            greeting = "hello"
+
+            [actions.hello.print]
+            text = "$greeting world"
+
+            [entries.hello]
+            title = "Hello"
+            actions = ["hello"]
+            "#,
+        );
+        assert_eq!(diagnostics, []);
+    }
+
+    #[test]
+    fn reports_unknown_keys() {
+        let diagnostics = check_str(
+            r#"
+            [options]
+            menu-timeot = 5
+
+            [entries.hello]
+            title = "Hello"
+            sortkey = "a"
+            "#,
+        );
+        assert_eq!(
+            diagnostics,
+            [
+                Diagnostic::error("unknown key `entries.hello.sortkey`"),
+                Diagnostic::error("unknown key `options.menu-timeot`"),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_fallback_cycles() {
+        let diagnostics = check_str(
+            r#"
+            [actions.retry]
+            print.text = "retrying"
+            on-error = "fallback(retry)"
+
+            [actions.first]
+            print.text = "first"
+            on-error = "fallback(second)"
+
+            [actions.second]
+            print.text = "second"
+            on-error = "fallback(first)"
+
+            [actions.rescue]
+            print.text = "rescue"
+            on-error = "fallback(first)"
+            "#,
+        );
+        assert_eq!(
+            diagnostics,
+            [
+                Diagnostic::error(
+                    "action `first` executes itself again: `first` -> `second` -> `first`"
+                ),
+                Diagnostic::error("action `retry` executes itself again: `retry` -> `retry`"),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_group_cycles() {
+        let diagnostics = check_str(
+            r#"
+            [actions.itself]
+            action-group.actions = ["itself"]
+
+            [actions.outer]
+            action-group.actions = ["hello", "inner"]
+
+            [actions.inner]
+            action-group.actions = ["$dynamic", "outer"]
+
+            [actions.hello]
+            print.text = "hello"
+            on-error = "fallback(outer)"
+            "#,
+        );
+        assert_eq!(
+            diagnostics,
+            [
+                Diagnostic::error(
+                    "action `hello` executes itself again: `hello` -> `outer` -> `hello`"
+                ),
+                Diagnostic::error(
+                    "action `inner` executes itself again: `inner` -> `outer` -> `inner`"
+                ),
+                Diagnostic::error("action `itself` executes itself again: `itself` -> `itself`"),
+                Diagnostic::warning(
+                    "`actions.inner.action-group.actions[0]` references unknown value `$dynamic`"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_dangling_actions() {
+        let diagnostics = check_str(
+            r#"
+            [entries.hello]
+            title = "Hello"
+            actions = ["missing", "$dynamic"]
+
+            [[phases.early]]
+            actions = ["also-missing"]
+
+            [actions.fails]
+            print.text = "failing"
+            on-error = "fallback(rescue)"
+
+            [actions.group]
+            action-group.actions = ["fails", "gone"]
+            "#,
+        );
+        assert_eq!(
+            diagnostics,
+            [
+                Diagnostic::error("action `fails` references unknown action `rescue`"),
+                Diagnostic::error("action `group` references unknown action `gone`"),
+                Diagnostic::error("entry `hello` references unknown action `missing`"),
+                Diagnostic::error("early phase references unknown action `also-missing`"),
+                Diagnostic::warning(
+                    "`entries.hello.actions[1]` references unknown value `$dynamic`"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_invalid_iscsi_names() {
+        let diagnostics = check_str(
+            r#"
+            [iscsi]
+            initiator-name = "iqn.2025-01.com.Example:host"
+            "#,
+        );
+        assert_eq!(
+            diagnostics,
+            [Diagnostic::error(
+                "`iscsi.initiator-name` is not a valid iscsi name: iqn.2025-01.com.Example:host"
+            )]
+        );
+    }
+
+    #[test]
+    fn reports_invalid_squashfs_digests() {
+        let diagnostics = check_str(
+            r#"
+            [squashfs.appliance]
+            path = "\\appliance.squashfs"
+            sha256 = "abc"
+            "#,
+        );
+        assert_eq!(
+            diagnostics,
+            [Diagnostic::error(
+                "squashfs `appliance` has a sha256 digest that is not 64 hex digits: abc"
+            )]
+        );
+    }
+
+    #[test]
+    fn reports_dhcp_options_without_code() {
+        let diagnostics = check_str(
+            r#"
+            [extractors.root-path.network]
+            field = "dhcp-option"
+            "#,
+        );
+        assert_eq!(
+            diagnostics,
+            [Diagnostic::error(
+                "extractor `root-path` reads a dhcp option but does not specify `option`"
+            )]
+        );
+    }
+
+    #[test]
+    fn reports_driver_cycles() {
+        let diagnostics = check_str(
+            r#"
+            [drivers.bus]
+            path = "\\bus.efi"
+            after = ["fs"]
+
+            [drivers.fs]
+            path = "\\fs.efi"
+            after = ["bus"]
+            "#,
+        );
+        assert_eq!(
+            diagnostics,
+            [Diagnostic::error(
+                "drivers depend on each other in a cycle: bus, fs"
+            )]
+        );
+    }
+
+    #[test]
+    fn reports_empty_declarations() {
+        let diagnostics = check_str(
+            r#"
+            [actions.nothing]
+            [extractors.nothing]
+            "#,
+        );
+        assert_eq!(
+            diagnostics,
+            [
+                Diagnostic::error("action `nothing` does not declare a configuration"),
+                Diagnostic::error("extractor `nothing` does not declare a configuration"),
+            ]
+        );
+    }
+
+    #[test]
+    fn generator_values_are_known() {
+        let diagnostics = check_str(
+            r#"
+            [actions.boot.chainload]
+            path = "$chainload"
+            options = ["$options $extra"]
+
+            [generators.kernels.bls.entry]
+            title = "$title ($bls.version)"
+            actions = ["boot"]
+
+            [generators.kernels.bls.entry.values]
+            extra = "quiet"
+            "#,
+        );
+        assert_eq!(diagnostics, []);
+    }
+
+    #[test]
+    fn extracts_value_references() {
+        assert_eq!(
+            value_references("$a-b/$c_d $ $$e $$$f"),
+            ["a-b", "c_d", "f"]
+        );
+        assert_eq!(value_references("${a}x ${b:-default} ${}"), ["a"]);
+    }
+}
@@ -0,0 +1,169 @@
+//! sprout-check: validates Sprout configuration files on the host.
+//! This performs the same configuration loading as Sprout does on-target,
+//! and additionally reports problems that Sprout would silently ignore or only detect at boot.
+
+use crate::checks::Severity;
+use anyhow::{Context, Result, bail};
+use edera_sprout_parsing::path::{PathScheme, split_scheme};
+use jaarg::{
+    ErrorUsageWriter, ErrorUsageWriterContext, HelpWriter, HelpWriterContext, Opt, Opts,
+    ParseControl, ParseResult, StandardErrorUsageWriter, StandardFullHelpWriter,
+};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// checks: The checks performed on a loaded configuration.
+pub mod checks;
+
+/// The parsed options of sprout-check.
+#[derive(Debug, Default)]
+struct CheckOptions {
+    /// The directory that represents the root of the partition Sprout is loaded from.
+    /// If not specified, the directory containing the configuration file is used.
+    esp: Option<PathBuf>,
+    /// The path to the configuration file to check.
+    config: PathBuf,
+}
+
+impl CheckOptions {
+    /// Produces [CheckOptions] from the command line arguments.
+    /// Returns the [ExitCode] to exit with if the program should not continue.
+    fn parse() -> Result<CheckOptions, ExitCode> {
+        enum ArgID {
+            Help,
+            Esp,
+            Config,
+        }
+
+        // All the options for the sprout-check executable.
+        const OPTIONS: Opts<ArgID> = Opts::new(&[
+            Opt::help_flag(ArgID::Help, &["--help"]).help_text("Display sprout-check Help"),
+            Opt::value(ArgID::Esp, &["--esp"], "DIR")
+                .help_text("Directory to resolve configuration includes from"),
+            Opt::positional(ArgID::Config, "CONFIG")
+                .required()
+                .help_text("Path to Sprout configuration file"),
+        ]);
+
+        let mut result = Self::default();
+
+        match OPTIONS.parse(
+            "sprout-check",
+            std::env::args().skip(1),
+            |program_name, id, _opt, _name, value| {
+                match id {
+                    ArgID::Esp => {
+                        // The directory to resolve includes from.
+                        result.esp = Some(value.into());
+                    }
+                    ArgID::Config => {
+                        // The configuration file to check.
+                        result.config = value.into();
+                    }
+                    ArgID::Help => {
+                        let ctx = HelpWriterContext {
+                            options: &OPTIONS,
+                            program_name,
+                        };
+                        print!("{}", StandardFullHelpWriter::new(ctx));
+                        return Ok(ParseControl::Quit);
+                    }
+                }
+                Ok(ParseControl::Continue)
+            },
+            |program_name, error| {
+                let ctx = ErrorUsageWriterContext {
+                    options: &OPTIONS,
+                    program_name,
+                    error,
+                };
+                eprint!("{}", StandardErrorUsageWriter::new(ctx));
+            },
+        ) {
+            ParseResult::ContinueSuccess => Ok(result),
+            ParseResult::ExitSuccess => Err(ExitCode::SUCCESS),
+            ParseResult::ExitError => Err(ExitCode::FAILURE),
+        }
+    }
+}
+
+/// Resolves the include `path` to a file inside the `esp` directory.
+/// Include paths are relative to the root of the partition Sprout is loaded from.
+fn resolve_include(esp: &Path, path: &str) -> Result<PathBuf> {
+    // Device paths and filesystem schemes other than the ESP can only be resolved by the firmware.
+    let path = match split_scheme(path) {
+        Some((PathScheme::Esp, subpath)) => subpath,
+        Some(_) => bail!("unable to resolve path {} on the host", path),
+        None if path.contains('(') => bail!("unable to resolve device path {} on the host", path),
+        None => path.to_string(),
+    };
+
+    // Convert the path separators and resolve it inside the ESP.
+    let mut resolved = esp.to_path_buf();
+    resolved.extend(path.split(['\\', '/']).filter(|part| !part.is_empty()));
+    Ok(resolved)
+}
+
+/// Loads and checks the configuration specified by `options`.
+/// Returns whether the configuration has no errors.
+fn run(options: &CheckOptions) -> Result<bool> {
+    // Determine the directory to resolve includes from.
+    let esp = match options.esp {
+        Some(ref esp) => esp.clone(),
+        None => options
+            .config
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default(),
+    };
+
+    // Read the configuration file.
+    let content = std::fs::read(&options.config).with_context(|| {
+        format!(
+            "unable to read configuration file {}",
+            options.config.display()
+        )
+    })?;
+
+    // Load the configuration and all the included configurations, exactly as Sprout does.
+    let name = options.config.display().to_string();
+    let parsed = edera_sprout_config::loader::parse(&content, &name, |path| {
+        let resolved = resolve_include(&esp, path)?;
+        std::fs::read(&resolved).with_context(|| format!("unable to read {}", resolved.display()))
+    })?;
+
+    // Check the loaded configuration and print all the diagnostics.
+    let diagnostics = checks::check(&parsed)?;
+    for diagnostic in &diagnostics {
+        eprintln!("{}", diagnostic);
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+    println!(
+        "{}: {} error(s), {} warning(s)",
+        options.config.display(),
+        errors,
+        warnings
+    );
+    Ok(errors == 0)
+}
+
+fn main() -> ExitCode {
+    let options = match CheckOptions::parse() {
+        Ok(options) => options,
+        Err(code) => return code,
+    };
+
+    match run(&options) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(error) => {
+            eprintln!("error: {:#}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
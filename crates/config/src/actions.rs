@@ -1,11 +1,28 @@
+use alloc::format;
+use alloc::string::{String, ToString};
 use serde::{Deserialize, Serialize};
 
+/// Configuration for the beacon action.
+pub mod beacon;
+
+/// Configuration for the boot variables backup and restore actions.
+pub mod boot_variables;
+
 /// Configuration for the chainload action.
 pub mod chainload;
 
 /// Configuration for the edera action.
 pub mod edera;
 
+/// Configuration for the action group action.
+pub mod group;
+
+/// Configuration for the http-boot action.
+pub mod http_boot;
+
+/// Configuration for the install-fallback action.
+pub mod install_fallback;
+
 /// Configuration for the print action.
 pub mod print;
 
@@ -16,6 +33,20 @@ pub mod print;
 /// Actions are the main work that Sprout gets done, like booting Linux.
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct ActionDeclaration {
+    /// The condition that must be true for the action to execute.
+    /// The condition is stamped with the context values before it is evaluated.
+    /// If the condition is false, the action is skipped.
+    #[serde(default)]
+    pub when: Option<String>,
+    /// The policy for handling an error from the action.
+    /// By default, an error aborts the execution of further actions.
+    #[serde(default, rename = "on-error")]
+    pub on_error: ActionErrorPolicy,
+    /// The timeout of the action in seconds.
+    /// Cancellable operations of the action, like file reads, fail once the timeout passes.
+    /// Operations that can't be cancelled, like a running EFI application, are not interrupted.
+    #[serde(default)]
+    pub timeout: Option<u64>,
     /// Chainload to another EFI application.
     /// This allows you to load any EFI application, either to boot an operating system
     /// or to perform more EFI actions and return to sprout.
@@ -29,4 +60,102 @@ pub struct ActionDeclaration {
     /// is specific to Edera.
     #[serde(default, rename = "edera")]
     pub edera: Option<edera::EderaConfiguration>,
+    /// Execute several actions as a unit.
+    /// Resources acquired by the actions are released when the group ends,
+    /// or rolled back if any of the actions fail.
+    #[serde(default, rename = "action-group")]
+    pub action_group: Option<group::ActionGroupConfiguration>,
+    /// Back up the firmware boot variables to a file on the ESP.
+    #[serde(default, rename = "backup-boot-variables")]
+    pub backup_boot_variables: Option<boot_variables::BootVariablesConfiguration>,
+    /// Restore the firmware boot variables from a backup file on the ESP.
+    /// This recovers boot options that firmware updates removed or reordered.
+    #[serde(default, rename = "restore-boot-variables")]
+    pub restore_boot_variables: Option<boot_variables::BootVariablesConfiguration>,
+    /// Copy the Sprout image to the removable media boot path of the ESP,
+    /// like `\EFI\BOOT\BOOTX64.EFI`, when it is missing or outdated.
+    #[serde(default, rename = "install-fallback")]
+    pub install_fallback: Option<install_fallback::InstallFallbackConfiguration>,
+    /// Download an image over HTTP and chainload it.
+    /// The network is configured with DHCP when it has no address yet.
+    #[serde(default, rename = "http-boot")]
+    pub http_boot: Option<http_boot::HttpBootConfiguration>,
+    /// Send a JSON record of the boot to a server with an HTTP POST request.
+    /// Failures are logged and never fail the boot.
+    #[serde(default)]
+    pub beacon: Option<beacon::BeaconConfiguration>,
+}
+
+/// The prefix of the fallback error policy, which is followed by the action name and `)`.
+const FALLBACK_POLICY_PREFIX: &str = "fallback(";
+
+/// The policy for handling an error from an action.
+/// This is declared as `abort`, `continue`, or `fallback(<action>)`.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub enum ActionErrorPolicy {
+    /// Propagate the error, which aborts the execution of further actions.
+    #[default]
+    Abort,
+    /// Log the error and continue with the next action.
+    Continue,
+    /// Log the error and execute the specified action instead.
+    /// The error from the fallback action is propagated.
+    Fallback(String),
+}
+
+impl TryFrom<String> for ActionErrorPolicy {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "abort" => Ok(Self::Abort),
+            "continue" => Ok(Self::Continue),
+            _ => value
+                .strip_prefix(FALLBACK_POLICY_PREFIX)
+                .and_then(|rest| rest.strip_suffix(')'))
+                .filter(|action| !action.is_empty())
+                .map(|action| Self::Fallback(action.to_string()))
+                .ok_or_else(|| format!("unknown action error policy '{}'", value)),
+        }
+    }
+}
+
+impl From<ActionErrorPolicy> for String {
+    fn from(value: ActionErrorPolicy) -> Self {
+        match value {
+            ActionErrorPolicy::Abort => "abort".to_string(),
+            ActionErrorPolicy::Continue => "continue".to_string(),
+            ActionErrorPolicy::Fallback(action) => {
+                format!("{}{})", FALLBACK_POLICY_PREFIX, action)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_policies_are_parsed() {
+        let parse = |value: &str| ActionErrorPolicy::try_from(value.to_string());
+        assert_eq!(parse("abort"), Ok(ActionErrorPolicy::Abort));
+        assert_eq!(parse("continue"), Ok(ActionErrorPolicy::Continue));
+        assert_eq!(
+            parse("fallback(rescue)"),
+            Ok(ActionErrorPolicy::Fallback("rescue".to_string()))
+        );
+        assert!(parse("fallback()").is_err());
+        assert!(parse("fallback(rescue").is_err());
+        assert!(parse("ignore").is_err());
+    }
+
+    #[test]
+    fn error_policies_round_trip() {
+        let policy = ActionErrorPolicy::Fallback("rescue".to_string());
+        let value = String::from(policy.clone());
+        assert_eq!(value, "fallback(rescue)");
+        assert_eq!(ActionErrorPolicy::try_from(value), Ok(policy));
+    }
 }
@@ -0,0 +1,30 @@
+use alloc::string::String;
+use serde::{Deserialize, Serialize};
+
+/// The default timeout of the beacon in seconds.
+const BEACON_TIMEOUT: u64 = 5;
+
+/// The configuration of the beacon action.
+/// The beacon sends a JSON record of the boot to a server, for monitoring a fleet of machines.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BeaconConfiguration {
+    /// The HTTP URL to send the record to with a POST request.
+    pub url: String,
+    /// The timeout of the beacon in seconds, after which the beacon is abandoned.
+    /// The beacon never fails the boot, so this bounds the delay a slow server can cause.
+    #[serde(default = "default_beacon_timeout")]
+    pub timeout: u64,
+}
+
+impl Default for BeaconConfiguration {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            timeout: default_beacon_timeout(),
+        }
+    }
+}
+
+fn default_beacon_timeout() -> u64 {
+    BEACON_TIMEOUT
+}
@@ -0,0 +1,26 @@
+use alloc::string::{String, ToString};
+use serde::{Deserialize, Serialize};
+
+/// The default path to the backup of the firmware boot variables.
+const BOOT_VARIABLES_BACKUP_PATH: &str = "\\sprout\\boot-variables.bak";
+
+/// The configuration of the actions that back up and restore the firmware boot variables,
+/// which are the boot options, `BootOrder`, and `Timeout`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BootVariablesConfiguration {
+    /// The path to the backup file.
+    #[serde(default = "default_boot_variables_backup_path")]
+    pub path: String,
+}
+
+impl Default for BootVariablesConfiguration {
+    fn default() -> Self {
+        Self {
+            path: default_boot_variables_backup_path(),
+        }
+    }
+}
+
+fn default_boot_variables_backup_path() -> String {
+    BOOT_VARIABLES_BACKUP_PATH.to_string()
+}
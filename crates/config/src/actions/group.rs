@@ -0,0 +1,14 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// The configuration of the action group action.
+/// The actions in the group execute as a unit: the resources they acquire are released
+/// when the group ends, and if any action fails, they are rolled back before the error
+/// is propagated.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct ActionGroupConfiguration {
+    /// The actions to execute in order.
+    #[serde(default)]
+    pub actions: Vec<String>,
+}
@@ -0,0 +1,20 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// The configuration of the http-boot action.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct HttpBootConfiguration {
+    /// The HTTP URL of the image to download and chainload.
+    /// Hostnames are resolved with the DNS servers provided by DHCP.
+    pub url: String,
+    /// The options to pass to the image.
+    /// The options are concatenated by a space and then passed to the EFI application.
+    #[serde(default)]
+    pub options: Vec<String>,
+    /// An optional HTTP URL of a Linux initrd to download.
+    /// The initrd is provided with the `LINUX_EFI_INITRD_MEDIA_GUID` mechanism,
+    /// just like the `linux-initrd` option of the chainload action.
+    #[serde(default, rename = "linux-initrd")]
+    pub linux_initrd: Option<String>,
+}
@@ -0,0 +1,12 @@
+use alloc::string::String;
+use serde::{Deserialize, Serialize};
+
+/// The configuration of the install-fallback action.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct InstallFallbackConfiguration {
+    /// The path to install the Sprout image to.
+    /// If not specified, the removable media boot path of the architecture is used,
+    /// like `\EFI\BOOT\BOOTX64.EFI`.
+    #[serde(default)]
+    pub path: Option<String>,
+}
@@ -0,0 +1,17 @@
+use alloc::string::String;
+use serde::{Deserialize, Serialize};
+
+/// The colors of the boot menu, which allow a downstream to brand Sprout.
+/// The theme is read from `\sprout\branding\theme.toml` on the EFI partition,
+/// or from the theme embedded into Sprout at build time.
+///
+/// Colors are the names of the console colors, like `white`, `light-gray`, or `blue`.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct ThemeConfiguration {
+    /// The color of the text of the boot menu.
+    #[serde(default)]
+    pub foreground: Option<String>,
+    /// The color behind the text of the boot menu.
+    #[serde(default)]
+    pub background: Option<String>,
+}
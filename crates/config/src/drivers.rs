@@ -1,13 +1,134 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
 use alloc::string::String;
+use alloc::vec::Vec;
 use serde::{Deserialize, Serialize};
 
 /// Declares a driver configuration.
 /// Drivers allow extending the functionality of Sprout.
 /// Drivers are loaded at runtime and can provide extra functionality like filesystem support.
 /// Drivers are loaded by their name, which is used to reference them in other concepts.
-#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DriverDeclaration {
     /// The filesystem path to the driver.
     /// This file should be an EFI executable that can be located and executed.
     pub path: String,
+    /// Whether the controllers are connected after the driver is started, which binds the
+    /// driver to its hardware. If not specified, `options.connect-drivers` is used.
+    #[serde(default)]
+    pub connect: Option<bool>,
+    /// The names of the drivers that must be loaded before this driver,
+    /// like a bus driver that a filesystem driver depends on.
+    #[serde(default)]
+    pub after: Vec<String>,
+    /// Whether the driver is required. If a driver that is not required fails to load,
+    /// a warning is logged and the drivers that depend on it are skipped, instead of
+    /// failing the boot. Defaults to true.
+    #[serde(default = "default_required")]
+    pub required: bool,
+}
+
+impl Default for DriverDeclaration {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            connect: None,
+            after: Vec::new(),
+            required: default_required(),
+        }
+    }
+}
+
+fn default_required() -> bool {
+    true
+}
+
+/// Determines the order to load the `drivers` in, so each driver is loaded after the drivers
+/// in its `after` list. Drivers without an ordering between them are loaded by name.
+/// Fails if a driver depends on a driver that is not declared, or the drivers depend on
+/// each other in a cycle.
+pub fn load_order(drivers: &BTreeMap<String, DriverDeclaration>) -> Result<Vec<&str>, String> {
+    for (name, driver) in drivers {
+        if let Some(missing) = driver
+            .after
+            .iter()
+            .find(|dependency| !drivers.contains_key(*dependency))
+        {
+            return Err(format!(
+                "driver `{}` is loaded after unknown driver `{}`",
+                name, missing
+            ));
+        }
+    }
+
+    // Repeatedly load the first driver by name whose dependencies are all loaded.
+    let mut order = Vec::with_capacity(drivers.len());
+    let mut loaded = BTreeSet::new();
+    while order.len() < drivers.len() {
+        let next = drivers.iter().find(|(name, driver)| {
+            !loaded.contains(name.as_str())
+                && driver
+                    .after
+                    .iter()
+                    .all(|dependency| loaded.contains(dependency.as_str()))
+        });
+        let Some((name, _)) = next else {
+            let remaining = drivers
+                .keys()
+                .filter(|name| !loaded.contains(name.as_str()))
+                .map(|name| name.as_str())
+                .collect::<Vec<_>>();
+            return Err(format!(
+                "drivers depend on each other in a cycle: {}",
+                remaining.join(", ")
+            ));
+        };
+        loaded.insert(name.as_str());
+        order.push(name.as_str());
+    }
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn drivers(declarations: &[(&str, &[&str])]) -> BTreeMap<String, DriverDeclaration> {
+        declarations
+            .iter()
+            .map(|(name, after)| {
+                let driver = DriverDeclaration {
+                    after: after.iter().map(|name| name.to_string()).collect(),
+                    ..Default::default()
+                };
+                (name.to_string(), driver)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn drivers_load_after_their_dependencies() {
+        let drivers = drivers(&[("a", &["c"]), ("b", &[]), ("c", &["b"]), ("d", &[])]);
+        assert_eq!(load_order(&drivers), Ok(vec!["b", "c", "a", "d"]));
+    }
+
+    #[test]
+    fn invalid_dependencies_are_rejected() {
+        let unknown = drivers(&[("a", &["missing"])]);
+        assert!(load_order(&unknown).unwrap_err().contains("`missing`"));
+        let cycle = drivers(&[("a", &["b"]), ("b", &["a"]), ("c", &[])]);
+        assert_eq!(
+            load_order(&cycle),
+            Err("drivers depend on each other in a cycle: a, b".to_string())
+        );
+    }
+
+    #[test]
+    fn drivers_are_required_by_default() {
+        let driver: DriverDeclaration = toml::from_str("path = \"x.efi\"").unwrap();
+        assert!(driver.required);
+        assert!(driver.after.is_empty());
+    }
 }
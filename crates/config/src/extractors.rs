@@ -1,18 +1,121 @@
+use crate::extractors::boot_mode::BootModeExtractor;
+use crate::extractors::date_time::DateTimeExtractor;
+use crate::extractors::disk_device_match::DiskDeviceMatchExtractor;
+use crate::extractors::file_content::FileContentExtractor;
+use crate::extractors::file_list::FileListExtractor;
 use crate::extractors::filesystem_device_match::FilesystemDeviceMatchExtractor;
+use crate::extractors::kernel_version::KernelVersionExtractor;
+use crate::extractors::network::NetworkExtractor;
+use crate::extractors::smbios::SmbiosExtractor;
+use alloc::string::String;
 use serde::{Deserialize, Serialize};
 
+/// Configuration for the boot-mode extractor.
+pub mod boot_mode;
+
+/// Configuration for the date-time extractor.
+pub mod date_time;
+
+/// Configuration for the disk-device-match extractor.
+pub mod disk_device_match;
+
+/// Configuration for the file-content extractor.
+pub mod file_content;
+
+/// Configuration for the file-list extractor.
+pub mod file_list;
+
 /// Configuration for the filesystem-device-match extractor.
 pub mod filesystem_device_match;
 
+/// Configuration for the kernel-version extractor.
+pub mod kernel_version;
+
+/// Configuration for the network extractor.
+pub mod network;
+
+/// Configuration for the smbios extractor.
+pub mod smbios;
+
 /// Declares an extractor configuration.
 /// Extractors allow calculating values at runtime
 /// using built-in sprout modules.
-#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ExtractorDeclaration {
+    /// Whether the extracted value is required. If an extractor that is not required
+    /// fails, a warning is logged and the value is not set, instead of failing the boot.
+    /// Defaults to true.
+    #[serde(default = "default_required")]
+    pub required: bool,
+    /// The value to use if the extractor fails, which also allows the boot to continue.
+    /// Unlike the fallback of a specific extractor, this applies to any failure.
+    #[serde(default, rename = "on-error")]
+    pub on_error: Option<String>,
     /// The filesystem device match extractor.
     /// This extractor finds a filesystem using some search criteria and returns
     /// the device root path that can concatenated with subpaths to access files
     /// on a particular filesystem.
     #[serde(default, rename = "filesystem-device-match")]
     pub filesystem_device_match: Option<FilesystemDeviceMatchExtractor>,
+    /// The disk device match extractor.
+    /// This extractor finds a whole physical disk using some search criteria,
+    /// like the model or serial number, and returns the device root path of the disk.
+    #[serde(default, rename = "disk-device-match")]
+    pub disk_device_match: Option<DiskDeviceMatchExtractor>,
+    /// The SMBIOS extractor.
+    /// This extractor reads a field from the SMBIOS tables, like the product name
+    /// or serial number of the system.
+    #[serde(default)]
+    pub smbios: Option<SmbiosExtractor>,
+    /// The file content extractor.
+    /// This extractor reads the content of a file, optionally extracting
+    /// a part of it using a regular expression.
+    #[serde(default, rename = "file-content")]
+    pub file_content: Option<FileContentExtractor>,
+    /// The kernel version extractor.
+    /// This extractor reads the kernel version from the header of a Linux kernel image.
+    #[serde(default, rename = "kernel-version")]
+    pub kernel_version: Option<KernelVersionExtractor>,
+    /// The boot mode extractor.
+    /// This extractor describes how Sprout was started, like whether it was
+    /// loaded from removable media or the network.
+    #[serde(default, rename = "boot-mode")]
+    pub boot_mode: Option<BootModeExtractor>,
+    /// The date-time extractor.
+    /// This extractor reads the current date and time from the firmware clock.
+    #[serde(default, rename = "date-time")]
+    pub date_time: Option<DateTimeExtractor>,
+    /// The file list extractor.
+    /// This extractor lists the files in a directory that match a pattern,
+    /// producing a list value that the list generator can iterate.
+    #[serde(default, rename = "file-list")]
+    pub file_list: Option<FileListExtractor>,
+    /// The network extractor.
+    /// This extractor reads the configuration that DHCP assigned to the primary
+    /// network interface, like the IP address, hostname, or any DHCP option.
+    #[serde(default)]
+    pub network: Option<NetworkExtractor>,
+}
+
+impl Default for ExtractorDeclaration {
+    fn default() -> Self {
+        Self {
+            required: default_required(),
+            on_error: None,
+            filesystem_device_match: None,
+            disk_device_match: None,
+            smbios: None,
+            file_content: None,
+            kernel_version: None,
+            boot_mode: None,
+            date_time: None,
+            file_list: None,
+            network: None,
+        }
+    }
+}
+
+/// Extractors are required by default.
+fn default_required() -> bool {
+    true
 }
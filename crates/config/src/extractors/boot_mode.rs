@@ -0,0 +1,29 @@
+use alloc::string::String;
+use serde::{Deserialize, Serialize};
+
+/// The boot mode extractor.
+/// This extractor describes how Sprout itself was started, which makes it possible
+/// for installer images to default to different entries than installed systems.
+/// The fallback value can be used to provide a value if the field is not available.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct BootModeExtractor {
+    /// The boot mode field to extract.
+    #[serde(default)]
+    pub field: BootModeField,
+    /// The fallback value to use if the field is not available.
+    #[serde(default)]
+    pub fallback: Option<String>,
+}
+
+/// The fields that can be extracted about how Sprout was started.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum BootModeField {
+    /// The medium Sprout was loaded from: `fixed`, `removable`, or `network`.
+    #[default]
+    Medium,
+    /// The name of the firmware boot entry that started Sprout, like `Boot0001`.
+    BootCurrent,
+    /// The description of the firmware boot entry that started Sprout.
+    BootEntry,
+}
@@ -0,0 +1,34 @@
+use alloc::string::{String, ToString};
+use serde::{Deserialize, Serialize};
+
+/// The default format of the date-time extractor, which is ISO 8601.
+pub const DEFAULT_DATE_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// The date-time extractor.
+/// This extractor reads the current date and time from the firmware real-time clock.
+/// The fallback value can be used to provide a value if the clock is not available.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DateTimeExtractor {
+    /// The strftime-like format of the value.
+    /// Supports `%Y`, `%m`, `%d`, `%H`, `%M`, `%S`, `%u` (day of the week),
+    /// `%s` (seconds since 1970-01-01), and `%%`.
+    #[serde(default = "default_date_time_format")]
+    pub format: String,
+    /// The fallback value to use if the clock is not available.
+    #[serde(default)]
+    pub fallback: Option<String>,
+}
+
+impl Default for DateTimeExtractor {
+    fn default() -> Self {
+        Self {
+            format: default_date_time_format(),
+            fallback: None,
+        }
+    }
+}
+
+/// Produces the default format of the date-time extractor.
+fn default_date_time_format() -> String {
+    DEFAULT_DATE_TIME_FORMAT.to_string()
+}
@@ -0,0 +1,28 @@
+use alloc::string::String;
+use serde::{Deserialize, Serialize};
+
+/// The disk device match extractor.
+/// This extractor finds a whole physical disk using some search criteria and returns
+/// the device root path of the disk. This is useful on systems with multiple disks
+/// that must boot from a specific physical device.
+/// The fallback value can be used to provide a value if no match is found.
+///
+/// This extractor requires all the criteria to match. If no criteria is provided,
+/// an error is returned.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct DiskDeviceMatchExtractor {
+    /// Matches a disk that reports the specified model.
+    /// This is supported for ATA, NVMe, SCSI, and USB disks.
+    #[serde(default, rename = "has-model")]
+    pub has_model: Option<String>,
+    /// Matches a disk that reports the specified serial number.
+    /// This is supported for ATA and NVMe disks.
+    #[serde(default, rename = "has-serial")]
+    pub has_serial: Option<String>,
+    /// Matches a disk that has the specified GPT disk GUID.
+    #[serde(default, rename = "has-disk-guid")]
+    pub has_disk_guid: Option<String>,
+    /// The fallback value to use if no disk matches the criteria.
+    #[serde(default)]
+    pub fallback: Option<String>,
+}
@@ -0,0 +1,22 @@
+use alloc::string::String;
+use serde::{Deserialize, Serialize};
+
+/// The file content extractor.
+/// This extractor reads a file and returns its trimmed content, which is useful for
+/// version files, slot markers, and simple boot hints stored on a filesystem.
+/// If a regular expression is specified, the first capture group of the first match
+/// is returned instead, or the entire match if the expression has no capture groups.
+/// The fallback value can be used to provide a value if the file can not be read
+/// or the regular expression does not match.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct FileContentExtractor {
+    /// The path to the file to read. This path is stamped with values.
+    pub path: String,
+    /// The regular expression to match against the content of the file.
+    #[serde(default)]
+    pub regex: Option<String>,
+    /// The fallback value to use if the file can not be read or the regular expression
+    /// does not match.
+    #[serde(default)]
+    pub fallback: Option<String>,
+}
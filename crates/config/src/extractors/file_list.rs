@@ -0,0 +1,35 @@
+use alloc::string::{String, ToString};
+use serde::{Deserialize, Serialize};
+
+/// The file list extractor.
+/// This extractor lists the files in a directory that match a wildcard pattern
+/// and returns their paths as a list value, with one path per line.
+/// List values can be used by the list generator to generate an entry for each item.
+/// The fallback value can be used to provide a value if the directory can not be read.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FileListExtractor {
+    /// The path to the directory to list. This path is stamped with values.
+    pub path: String,
+    /// The wildcard pattern that file names must match, ignoring case.
+    /// A `*` matches any sequence of characters and a `?` matches a single character.
+    #[serde(default = "default_file_list_pattern")]
+    pub pattern: String,
+    /// The fallback value to use if the directory can not be read.
+    #[serde(default)]
+    pub fallback: Option<String>,
+}
+
+impl Default for FileListExtractor {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            pattern: default_file_list_pattern(),
+            fallback: None,
+        }
+    }
+}
+
+/// Produces the default pattern of the file list extractor, which matches all files.
+fn default_file_list_pattern() -> String {
+    "*".to_string()
+}
@@ -24,6 +24,10 @@ pub struct FilesystemDeviceMatchExtractor {
     /// Matches a filesystem that has the specified partition type UUID.
     #[serde(default, rename = "has-partition-type-uuid")]
     pub has_partition_type_uuid: Option<String>,
+    /// Matches a filesystem on an MBR partition with the specified partition type,
+    /// which is a hexadecimal byte like `0x83`.
+    #[serde(default, rename = "has-mbr-partition-type")]
+    pub has_mbr_partition_type: Option<String>,
     /// The fallback value to use if no filesystem matches the criteria.
     #[serde(default)]
     pub fallback: Option<String>,
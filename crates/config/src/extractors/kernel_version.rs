@@ -0,0 +1,15 @@
+use alloc::string::String;
+use serde::{Deserialize, Serialize};
+
+/// The kernel version extractor.
+/// This extractor reads a Linux kernel image and returns the kernel version
+/// from its header, which is useful when the filename does not include it.
+/// The fallback value can be used to provide a value if the version is not available.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct KernelVersionExtractor {
+    /// The path to the kernel image. This path is stamped with values.
+    pub path: String,
+    /// The fallback value to use if the kernel version is not available.
+    #[serde(default)]
+    pub fallback: Option<String>,
+}
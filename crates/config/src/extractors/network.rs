@@ -0,0 +1,41 @@
+use alloc::string::String;
+use serde::{Deserialize, Serialize};
+
+/// The network extractor.
+/// This extractor reads the configuration that DHCP assigned to the primary network
+/// interface, which makes it possible to customize entries for specific hosts.
+/// The fallback value can be used to provide a value if the field is not available.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct NetworkExtractor {
+    /// The network field to extract.
+    pub field: NetworkField,
+    /// The code of the DHCP option to extract, if the field is `dhcp-option`.
+    #[serde(default)]
+    pub option: Option<u8>,
+    /// The fallback value to use if the field is not available.
+    #[serde(default)]
+    pub fallback: Option<String>,
+}
+
+/// The fields that can be extracted from the network configuration.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum NetworkField {
+    /// The hardware address of the interface, like `52:54:00:12:34:56`.
+    #[default]
+    Mac,
+    /// The IPv4 address of the interface.
+    Ip,
+    /// The subnet mask of the interface.
+    SubnetMask,
+    /// The default gateway of the interface.
+    Gateway,
+    /// The hostname provided by DHCP.
+    Hostname,
+    /// The domain name provided by DHCP.
+    DomainName,
+    /// The DNS servers provided by DHCP, separated by spaces.
+    DnsServers,
+    /// The DHCP option specified by `option`.
+    DhcpOption,
+}
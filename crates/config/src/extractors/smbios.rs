@@ -0,0 +1,42 @@
+use alloc::string::String;
+use serde::{Deserialize, Serialize};
+
+/// The SMBIOS extractor.
+/// This extractor reads a field from the SMBIOS tables provided by the firmware,
+/// which makes it possible to customize entries for specific hardware.
+/// The fallback value can be used to provide a value if the field is not available.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct SmbiosExtractor {
+    /// The SMBIOS field to extract.
+    pub field: SmbiosField,
+    /// The fallback value to use if the field is not available.
+    #[serde(default)]
+    pub fallback: Option<String>,
+}
+
+/// The fields that can be extracted from the SMBIOS tables.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SmbiosField {
+    /// The vendor of the BIOS.
+    BiosVendor,
+    /// The version of the BIOS.
+    BiosVersion,
+    /// The manufacturer of the system.
+    Vendor,
+    /// The product name of the system.
+    #[default]
+    Product,
+    /// The version of the system.
+    Version,
+    /// The serial number of the system.
+    Serial,
+    /// The UUID of the system.
+    Uuid,
+    /// The SKU number of the system.
+    Sku,
+    /// The family of the system.
+    Family,
+    /// The asset tag of the system enclosure.
+    AssetTag,
+}
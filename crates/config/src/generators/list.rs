@@ -1,13 +1,13 @@
 use crate::entries::EntryDeclaration;
 use alloc::collections::BTreeMap;
-use alloc::string::String;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use serde::{Deserialize, Serialize};
 
 /// List generator configuration.
 /// The list generator produces multiple entries based
 /// on a set of input maps.
-#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ListConfiguration {
     /// The template entry to use for each generated entry.
     #[serde(default)]
@@ -15,4 +15,28 @@ pub struct ListConfiguration {
     /// The values to use as the input for the matrix.
     #[serde(default)]
     pub values: Vec<BTreeMap<String, String>>,
+    /// A list value to generate entries from, like `$kernels`, which is stamped with values.
+    /// An entry is generated for each item of the list, in addition to the entries
+    /// generated from `values`. Each item is provided to its entry as the `item-key` value.
+    #[serde(default)]
+    pub from: Option<String>,
+    /// The name of the value that holds the list item for entries generated from `from`.
+    #[serde(default = "default_item_key", rename = "item-key")]
+    pub item_key: String,
+}
+
+impl Default for ListConfiguration {
+    fn default() -> Self {
+        Self {
+            entry: EntryDeclaration::default(),
+            values: Vec::new(),
+            from: None,
+            item_key: default_item_key(),
+        }
+    }
+}
+
+/// Produces the default name of the value that holds the list item.
+fn default_item_key() -> String {
+    "item".to_string()
 }
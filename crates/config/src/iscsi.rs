@@ -0,0 +1,13 @@
+use alloc::string::String;
+use serde::{Deserialize, Serialize};
+
+/// Configures the attachment of iSCSI targets with the firmware iSCSI initiator.
+/// The targets are the ones configured in the firmware, either as iSCSI attempts in the
+/// firmware setup or with the DHCP root path option, as defined by RFC 4173.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct IscsiConfiguration {
+    /// The iSCSI name of the initiator, like `iqn.2025-01.com.example:host`.
+    /// If not specified, the name configured in the firmware is used.
+    #[serde(default, rename = "initiator-name")]
+    pub initiator_name: Option<String>,
+}
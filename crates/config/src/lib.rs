@@ -8,22 +8,56 @@ use crate::drivers::DriverDeclaration;
 use crate::entries::EntryDeclaration;
 use crate::extractors::ExtractorDeclaration;
 use crate::generators::GeneratorDeclaration;
+use crate::iscsi::IscsiConfiguration;
+use crate::netboot::NetbootConfiguration;
 use crate::phases::PhasesConfiguration;
+use crate::squashfs::SquashfsDeclaration;
 use alloc::collections::BTreeMap;
 use alloc::string::String;
+use alloc::vec::Vec;
 use serde::{Deserialize, Serialize};
 
 pub mod actions;
+pub mod branding;
 pub mod drivers;
 pub mod entries;
 pub mod extractors;
 pub mod generators;
+pub mod iscsi;
+pub mod loader;
+pub mod migration;
+pub mod netboot;
 pub mod phases;
+pub mod squashfs;
 
 /// This is the latest version of the sprout configuration format.
-/// This must be incremented when the configuration breaks compatibility.
+/// This must be incremented when the configuration breaks compatibility,
+/// along with adding a migration from the previous version to [migration::MIGRATIONS].
 pub const LATEST_VERSION: u32 = 1;
 
+/// The key of the built-in value that indicates whether Secure Boot is enabled.
+/// The value is `true` or `false` and can be used in `when` conditions.
+pub const SECURE_BOOT_KEY: &str = "secure-boot";
+
+/// The key of the built-in value that contains the version of Sprout.
+pub const SPROUT_VERSION_KEY: &str = "sprout-version";
+
+/// The key of the built-in value that contains the git commit Sprout was built from.
+pub const SPROUT_COMMIT_KEY: &str = "sprout-commit";
+
+/// The namespace of the configuration values and the built-in values, so they can be
+/// referenced as `values.name` when a generator provides a value with the same name.
+pub const VALUES_NAMESPACE: &str = "values";
+
+/// The default number of boot records to retain on the EFI partition.
+pub const DEFAULT_BOOT_RECORDS: u64 = 10;
+
+/// The default path of the file that the `file` log sink writes to.
+pub const DEFAULT_LOG_FILE_PATH: &str = "\\sprout\\sprout.log";
+
+/// The default delay in seconds to wait for a recovery option when an error occurs.
+pub const DEFAULT_ERROR_DELAY_SECONDS: u64 = 10;
+
 /// The default timeout for the boot menu in seconds.
 pub const DEFAULT_MENU_TIMEOUT_SECONDS: u64 = 10;
 
@@ -35,6 +69,12 @@ pub struct RootConfiguration {
     /// the configuration is the latest version.
     #[serde(default = "latest_version")]
     pub version: u32,
+    /// Additional configuration files to load and merge into this configuration.
+    /// Paths are relative to the partition Sprout was loaded from and can use values
+    /// declared in the `values` table of the including file. Values declared in the
+    /// including file take precedence over values declared in included files.
+    #[serde(default)]
+    pub include: Vec<String>,
     /// Default options for Sprout.
     #[serde(default)]
     pub options: OptionsConfiguration,
@@ -46,6 +86,19 @@ pub struct RootConfiguration {
     /// Each driver has a name which uniquely identifies it inside Sprout.
     #[serde(default)]
     pub drivers: BTreeMap<String, DriverDeclaration>,
+    /// Attaches iSCSI targets after the drivers are loaded, so the disks of SAN-booted
+    /// machines are scanned like local disks. Targets are only attached when this is declared.
+    #[serde(default)]
+    pub iscsi: Option<IscsiConfiguration>,
+    /// Squashfs containers to mount as read-only volumes, after the drivers are loaded and the
+    /// read-only filesystems are installed. Each container has a name that selects it in
+    /// `squashfs:<name>/` paths.
+    #[serde(default)]
+    pub squashfs: BTreeMap<String, SquashfsDeclaration>,
+    /// The profile that adjusts the defaults when Sprout was loaded over the network,
+    /// like with PXE or HTTP boot. The profile is enabled unless it is disabled here.
+    #[serde(default)]
+    pub netboot: NetbootConfiguration,
     /// Declares the extractors that add values to the sprout context that are calculated
     /// at runtime. Each extractor has a name which corresponds to the value it will set
     /// inside the sprout context.
@@ -87,6 +140,101 @@ pub struct OptionsConfiguration {
     /// Enables autoconfiguration of Sprout based on the environment.
     #[serde(default)]
     pub autoconfigure: bool,
+    /// The timeout of the watchdog armed before starting an image, in seconds.
+    /// If the image does not exit boot services or return in time, the system is reset.
+    /// If zero, the watchdog is not armed.
+    #[serde(rename = "watchdog-timeout", default)]
+    pub watchdog_timeout: u64,
+    /// The number of boot records to retain in `\sprout\boot-records` on the EFI partition.
+    /// If zero, boot records are not written. If not specified, [DEFAULT_BOOT_RECORDS] is used.
+    #[serde(rename = "boot-records", default)]
+    pub boot_records: Option<u64>,
+    /// The delay in seconds to wait for a recovery option to be selected when an error occurs.
+    /// If not specified, [DEFAULT_ERROR_DELAY_SECONDS] is used.
+    #[serde(rename = "error-delay", default)]
+    pub error_delay: Option<u64>,
+    /// The entry to boot automatically when an error occurs after the entries are assembled
+    /// and no recovery option is selected.
+    #[serde(rename = "fallback-entry", default)]
+    pub fallback_entry: Option<String>,
+    /// The maximum level of log messages, one of `off`, `error`, `warn`, `info`, `debug`,
+    /// or `trace`. If not specified, the level Sprout was built with is used.
+    #[serde(rename = "log-level", default)]
+    pub log_level: Option<String>,
+    /// Prefixes every log line with the time of the real-time clock,
+    /// in addition to the time elapsed since Sprout started.
+    #[serde(rename = "log-wall-clock", default)]
+    pub log_wall_clock: bool,
+    /// The delay in seconds after which the system is rebooted when Sprout panics.
+    /// If not specified, Sprout halts after a panic, so the panic stays on screen.
+    #[serde(rename = "panic-reboot-delay", default)]
+    pub panic_reboot_delay: Option<u64>,
+    /// Creates or repairs the firmware boot option of Sprout at startup and adds it to the
+    /// boot order, so copies of the ESP become bootable without registering Sprout from an OS.
+    /// This only happens when Sprout is booted from a fixed disk.
+    #[serde(rename = "register-boot-option", default)]
+    pub register_boot_option: bool,
+    /// The age in days after which the firmware boot options that Sprout registered and that
+    /// were not booted since are removed at startup, so ESPs on disks that are gone do not
+    /// slowly exhaust the variable store. If not specified, boot options are never removed.
+    #[serde(rename = "variable-max-age", default)]
+    pub variable_max_age: Option<u64>,
+    /// Connects all controllers to their drivers after the drivers are loaded, so the drivers
+    /// bind to their hardware before entries are generated. Drivers can override this with
+    /// their own `connect` setting. If not specified, controllers are connected.
+    #[serde(rename = "connect-drivers", default)]
+    pub connect_drivers: Option<bool>,
+    /// Provides read-only filesystems for partitions the firmware can not read, like an ext4
+    /// or btrfs /boot partition or ISO9660 media, so they are used without loading a
+    /// filesystem driver. If not specified, the filesystems are provided.
+    #[serde(rename = "builtin-filesystems", default)]
+    pub builtin_filesystems: Option<bool>,
+    /// The subvolume whose files are provided for btrfs filesystems, as a path from the
+    /// top-level subvolume, like `@/.snapshots/2/snapshot`. An empty path provides the
+    /// top-level subvolume, where every subvolume and snapshot is a directory.
+    /// If not specified, the default subvolume is provided.
+    #[serde(rename = "btrfs-subvolume", default)]
+    pub btrfs_subvolume: Option<String>,
+    /// The paths to PEM or DER files with the CA certificates that are trusted for HTTPS
+    /// downloads. They are added to the CA certificates that the firmware TLS driver trusts.
+    #[serde(rename = "tls-ca-certificates", default)]
+    pub tls_ca_certificates: Vec<String>,
+    /// Plays beep codes on the PC speaker when the boot menu is displayed, when an entry is
+    /// selected, and when a fatal error occurs, so the boot menu can be operated without
+    /// seeing the screen. Only x86 machines have a PC speaker.
+    #[serde(default)]
+    pub beeps: bool,
+    /// The log sinks to configure, keyed by the name of the sink.
+    /// The `console` and `memory` sinks are always registered, while the `serial`
+    /// and `file` sinks are registered when they are configured.
+    #[serde(rename = "log-sinks", default)]
+    pub log_sinks: BTreeMap<String, LogSinkConfiguration>,
+}
+
+/// Configuration of a log sink, which is a destination of log lines.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LogSinkConfiguration {
+    /// Whether lines are written to the sink.
+    #[serde(default = "default_log_sink_enabled")]
+    pub enabled: bool,
+    /// The maximum level of the lines written to the sink, one of `off`, `error`, `warn`,
+    /// `info`, `debug`, or `trace`. If not specified, the sink receives every logged line.
+    #[serde(default)]
+    pub level: Option<String>,
+    /// The path to the file that the `file` sink writes to,
+    /// relative to the partition Sprout was loaded from.
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+impl Default for LogSinkConfiguration {
+    fn default() -> Self {
+        Self {
+            enabled: default_log_sink_enabled(),
+            level: None,
+            path: None,
+        }
+    }
 }
 
 /// Get the latest version of the Sprout configuration format.
@@ -94,6 +242,10 @@ pub fn latest_version() -> u32 {
     LATEST_VERSION
 }
 
+fn default_log_sink_enabled() -> bool {
+    true
+}
+
 fn default_menu_timeout() -> u64 {
     DEFAULT_MENU_TIMEOUT_SECONDS
 }
@@ -0,0 +1,230 @@
+use crate::RootConfiguration;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use anyhow::{Context, Result, bail};
+use toml::Value;
+use toml::map::Map;
+
+/// A configuration that has been parsed with all of its includes resolved.
+pub struct ParsedConfiguration {
+    /// The merged configuration, migrated to the latest version.
+    pub value: Value,
+    /// Warnings produced while parsing, such as configuration migrations.
+    pub warnings: Vec<String>,
+}
+
+/// The maximum depth of nested configuration includes.
+/// This protects against excessively deep include chains.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Merges the `overlay` value into the `base` value.
+/// Tables are merged recursively, while all other values in `overlay` replace those in `base`.
+pub fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Table(base), Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    // The key exists in both tables, so merge the values.
+                    Some(existing) => merge(existing, value),
+                    // The key only exists in the overlay, so insert it.
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+
+        // The overlay is not a table, so it replaces the base value.
+        (base, overlay) => {
+            *base = overlay;
+        }
+    }
+}
+
+/// Collects the string values declared in the `values` table of the configuration `value`.
+/// These are used to stamp the include paths of the configuration.
+fn string_values(value: &Value) -> BTreeMap<String, String> {
+    value
+        .get("values")
+        .and_then(|values| values.as_table())
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Removes the include paths from the configuration `value` and returns them.
+fn take_includes(value: &mut Value) -> Result<Vec<String>> {
+    let Some(table) = value.as_table_mut() else {
+        bail!("configuration is not a table");
+    };
+
+    // If there is no include key, there are no includes.
+    let Some(includes) = table.remove("include") else {
+        return Ok(Vec::new());
+    };
+
+    // Parse the includes as a list of strings.
+    includes
+        .try_into()
+        .context("include must be a list of configuration file paths")
+}
+
+/// Parses the configuration `content` as a [Value], recursively resolving includes using `read`.
+/// The `name` identifies the configuration in error messages and include cycle detection.
+/// The `stack` contains the names of the configurations currently being loaded,
+/// which is used to detect include cycles.
+fn parse_with_stack(
+    content: &[u8],
+    name: &str,
+    read: &mut impl FnMut(&str) -> Result<Vec<u8>>,
+    stack: &mut Vec<String>,
+    warnings: &mut Vec<String>,
+) -> Result<Value> {
+    // Paths on the ESP are case-insensitive, so compare them case-insensitively.
+    let key = name.to_lowercase();
+
+    // If the configuration is already being loaded, we have an include cycle.
+    if stack.contains(&key) {
+        bail!("configuration include cycle detected at {}", name);
+    }
+
+    // Ensure that we do not recurse too deeply.
+    if stack.len() >= MAX_INCLUDE_DEPTH {
+        bail!("configuration include depth limit reached at {}", name);
+    }
+    stack.push(key);
+
+    // Parse the raw configuration into a toml::Value which can represent any TOML file.
+    let mut value: Value = toml::from_slice(content)
+        .with_context(|| format!("unable to parse sprout config file {}", name))?;
+
+    // Migrate the configuration to the latest version before processing it further.
+    // This also checks that the version of the configuration is supported.
+    let migrations = crate::migration::migrate(&mut value)
+        .with_context(|| format!("invalid configuration file {}", name))?;
+    warnings.extend(
+        migrations
+            .into_iter()
+            .map(|warning| format!("{}: {}", name, warning)),
+    );
+
+    // Acquire the include paths of this configuration.
+    let includes = take_includes(&mut value)?;
+
+    // Include paths can use the values declared in this configuration file.
+    let values = string_values(&value);
+
+    // Merge all the included configurations in order, so later includes take precedence.
+    let mut merged = Value::Table(Map::new());
+    for include in includes {
+        let (_, include) = edera_sprout_parsing::stamp_values(&values, &include);
+        let content = read(&include)
+            .with_context(|| format!("unable to read configuration file {}", include))?;
+        let included = parse_with_stack(&content, &include, read, stack, warnings)
+            .with_context(|| format!("unable to include configuration file {}", include))?;
+        merge(&mut merged, included);
+    }
+
+    // Merge this configuration last so it takes precedence over the included configurations.
+    merge(&mut merged, value);
+
+    stack.pop();
+    Ok(merged)
+}
+
+/// Parses the configuration `content` as a [Value], recursively resolving includes.
+/// The `name` identifies the configuration in error messages, usually by path.
+/// The `read` function is called with the path of each included configuration file
+/// and must return the raw contents of that file.
+///
+/// Every configuration file is migrated to the latest version, and all the included
+/// configurations are merged into the returned [ParsedConfiguration].
+pub fn parse(
+    content: &[u8],
+    name: &str,
+    mut read: impl FnMut(&str) -> Result<Vec<u8>>,
+) -> Result<ParsedConfiguration> {
+    let mut warnings = Vec::new();
+    let value = parse_with_stack(content, name, &mut read, &mut Vec::new(), &mut warnings)?;
+    Ok(ParsedConfiguration { value, warnings })
+}
+
+/// Converts a fully merged configuration `value` into a [RootConfiguration].
+pub fn into_configuration(value: Value) -> Result<RootConfiguration> {
+    value
+        .try_into()
+        .context("unable to parse sprout configuration")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn no_includes(path: &str) -> Result<Vec<u8>> {
+        bail!("unexpected include of {}", path)
+    }
+
+    #[test]
+    fn parse_without_includes() {
+        let parsed = parse(b"version = 1\n[values]\na = \"b\"\n", "root", no_includes).unwrap();
+        assert!(parsed.warnings.is_empty());
+        let config = into_configuration(parsed.value).unwrap();
+        assert_eq!(config.values.get("a").map(String::as_str), Some("b"));
+    }
+
+    #[test]
+    fn parse_rejects_unsupported_version() {
+        assert!(parse(b"version = 999\n", "root", no_includes).is_err());
+    }
+
+    #[test]
+    fn parse_merges_includes_with_root_precedence() {
+        let root = b"include = [\"\\\\common.toml\"]\n[values]\na = \"root\"\n";
+        let parsed = parse(root, "root", |path| {
+            assert_eq!(path, "\\common.toml");
+            Ok(b"[values]\na = \"common\"\nb = \"common\"\n".to_vec())
+        })
+        .unwrap();
+        let config = into_configuration(parsed.value).unwrap();
+        assert_eq!(config.values.get("a").map(String::as_str), Some("root"));
+        assert_eq!(config.values.get("b").map(String::as_str), Some("common"));
+    }
+
+    #[test]
+    fn parse_stamps_include_paths() {
+        let root = b"include = [\"\\\\$machine.toml\"]\n[values]\nmachine = \"server\"\n";
+        let mut seen = vec![];
+        parse(root, "root", |path| {
+            seen.push(path.to_string());
+            Ok(Vec::new())
+        })
+        .unwrap();
+        assert_eq!(seen, ["\\server.toml"]);
+    }
+
+    #[test]
+    fn parse_detects_include_cycles() {
+        let result = parse(b"include = [\"a.toml\"]\n", "root.toml", |path| {
+            Ok(format!("include = [\"{}\"]\n", path).into_bytes())
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn merge_replaces_non_table_values() {
+        let mut base: Value = toml::from_str("a = [1]\n[t]\nx = 1\n").unwrap();
+        let overlay: Value = toml::from_str("a = [2]\n[t]\ny = 2\n").unwrap();
+        merge(&mut base, overlay);
+        assert_eq!(base["a"].as_array().map(|a| a.len()), Some(1));
+        assert_eq!(base["a"][0].as_integer(), Some(2));
+        assert_eq!(base["t"]["x"].as_integer(), Some(1));
+        assert_eq!(base["t"]["y"].as_integer(), Some(2));
+    }
+}
@@ -0,0 +1,150 @@
+use crate::latest_version;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use anyhow::{Context, Result, bail};
+use toml::Value;
+
+/// Describes a migration of the configuration from one version to the next.
+/// Migrations operate on the raw [Value] so that they can handle fields
+/// which no longer exist in the current configuration structures.
+pub struct Migration {
+    /// The version this migration upgrades from. The migrated configuration is `from + 1`.
+    pub from: u32,
+    /// A description of the changes made by the migration, which is reported as a warning.
+    pub description: &'static str,
+    /// Transforms the configuration from the `from` version to the next version.
+    pub migrate: fn(&mut Value) -> Result<()>,
+}
+
+/// All the configuration migrations, in order of the version they upgrade from.
+/// When the configuration format breaks compatibility, a migration from the previous
+/// version should be added here so that existing configurations continue to work.
+pub const MIGRATIONS: &[Migration] = &[];
+
+/// Acquires the version of the configuration `value`.
+/// If not specified, the configuration is assumed to be the latest version.
+pub fn version(value: &Value) -> Result<u32> {
+    match value.get("version") {
+        Some(version) => version
+            .clone()
+            .try_into()
+            .context("unable to get configuration version"),
+        None => Ok(latest_version()),
+    }
+}
+
+/// Migrates the configuration `value` to the latest version using the `migrations`.
+/// Returns warnings describing every migration that was applied.
+fn migrate_with(value: &mut Value, migrations: &[Migration]) -> Result<Vec<String>> {
+    let mut version = version(value)?;
+
+    // Configurations from the future can not be understood.
+    if version > latest_version() {
+        bail!("unsupported configuration version: {}", version);
+    }
+
+    // Apply each migration in order until the configuration is the latest version.
+    let mut warnings = Vec::new();
+    while version < latest_version() {
+        let Some(migration) = migrations
+            .iter()
+            .find(|migration| migration.from == version)
+        else {
+            bail!("unsupported configuration version: {}", version);
+        };
+
+        (migration.migrate)(value)
+            .with_context(|| format!("unable to migrate configuration version {}", version))?;
+        version += 1;
+
+        warnings.push(format!(
+            "configuration migrated from version {} to version {}: {}",
+            migration.from, version, migration.description
+        ));
+    }
+
+    // Record the version the configuration was migrated to.
+    if !warnings.is_empty() {
+        let Some(table) = value.as_table_mut() else {
+            bail!("configuration is not a table");
+        };
+        table.insert("version".into(), Value::Integer(version as i64));
+    }
+    Ok(warnings)
+}
+
+/// Migrates the configuration `value` to the latest version.
+/// Returns warnings describing every migration that was applied, which should be
+/// reported to the user so the configuration can be updated.
+pub fn migrate(value: &mut Value) -> Result<Vec<String>> {
+    migrate_with(value, MIGRATIONS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A migration from a version before the latest, which renames a field.
+    fn rename_field(value: &mut Value) -> Result<()> {
+        let table = value.as_table_mut().context("not a table")?;
+        if let Some(old) = table.remove("old-field") {
+            table.insert("values".into(), old);
+        }
+        Ok(())
+    }
+
+    const TEST_MIGRATIONS: &[Migration] = &[Migration {
+        from: crate::LATEST_VERSION - 1,
+        description: "old-field was renamed to values",
+        migrate: rename_field,
+    }];
+
+    #[test]
+    fn latest_version_is_not_migrated() {
+        let mut value: Value = toml::from_str("version = 1\n").unwrap();
+        assert!(
+            migrate_with(&mut value, TEST_MIGRATIONS)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn missing_version_is_latest() {
+        let mut value: Value = toml::from_str("").unwrap();
+        assert!(
+            migrate_with(&mut value, TEST_MIGRATIONS)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(value.get("version").is_none());
+    }
+
+    #[test]
+    fn older_version_is_migrated() {
+        let config = format!(
+            "version = {}\n[old-field]\na = \"b\"\n",
+            crate::LATEST_VERSION - 1
+        );
+        let mut value: Value = toml::from_str(&config).unwrap();
+        let warnings = migrate_with(&mut value, TEST_MIGRATIONS).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(version(&value).unwrap(), latest_version());
+        assert_eq!(value["values"]["a"].as_str(), Some("b"));
+        assert!(value.get("old-field").is_none());
+    }
+
+    #[test]
+    fn future_version_is_rejected() {
+        let mut value: Value = toml::from_str("version = 999\n").unwrap();
+        assert!(migrate_with(&mut value, TEST_MIGRATIONS).is_err());
+    }
+
+    #[test]
+    fn version_without_migration_is_rejected() {
+        let config = format!("version = {}\n", crate::LATEST_VERSION - 1);
+        let mut value: Value = toml::from_str(&config).unwrap();
+        assert!(migrate_with(&mut value, &[]).is_err());
+    }
+}
@@ -0,0 +1,157 @@
+use crate::RootConfiguration;
+use alloc::string::String;
+use serde::{Deserialize, Serialize};
+
+/// The default menu timeout of the netboot profile in seconds.
+const NETBOOT_MENU_TIMEOUT_SECONDS: u64 = 3;
+
+/// The default error delay of the netboot profile in seconds.
+const NETBOOT_ERROR_DELAY_SECONDS: u64 = 3;
+
+/// The profile that adjusts the defaults when Sprout was itself loaded over the network,
+/// like with PXE or HTTP boot. Machines that boot from the network usually have nothing
+/// worth scanning on their local disks and nobody waiting at the console.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NetbootConfiguration {
+    /// Whether the profile is applied when Sprout was loaded over the network.
+    #[serde(default = "default_netboot_enabled")]
+    pub enabled: bool,
+    /// Whether autoconfiguration still scans the local disks for entries.
+    #[serde(default)]
+    pub autoconfigure: bool,
+    /// The maximum timeout of the boot menu in seconds.
+    /// A shorter timeout in the options is kept.
+    #[serde(rename = "menu-timeout", default = "default_netboot_menu_timeout")]
+    pub menu_timeout: u64,
+    /// The maximum delay in seconds to wait for a recovery option when an error occurs.
+    /// A shorter delay in the options is kept.
+    #[serde(rename = "error-delay", default = "default_netboot_error_delay")]
+    pub error_delay: u64,
+    /// The entry to mark as the default entry.
+    /// If not specified, the default entry of the options is used, and without one,
+    /// the first entry that boots with the `http-boot` action is preferred.
+    #[serde(rename = "default-entry", default)]
+    pub default_entry: Option<String>,
+}
+
+impl Default for NetbootConfiguration {
+    fn default() -> Self {
+        Self {
+            enabled: default_netboot_enabled(),
+            autoconfigure: false,
+            menu_timeout: default_netboot_menu_timeout(),
+            error_delay: default_netboot_error_delay(),
+            default_entry: None,
+        }
+    }
+}
+
+/// Applies the netboot profile of the `config` to its options, which is done when Sprout
+/// was loaded over the network. Returns whether the profile was applied.
+pub fn apply(config: &mut RootConfiguration) -> bool {
+    let profile = &config.netboot;
+    if !profile.enabled {
+        return false;
+    }
+
+    let options = &mut config.options;
+    options.autoconfigure &= profile.autoconfigure;
+    options.menu_timeout = options.menu_timeout.min(profile.menu_timeout);
+    options.error_delay = Some(
+        options
+            .error_delay
+            .unwrap_or(crate::DEFAULT_ERROR_DELAY_SECONDS)
+            .min(profile.error_delay),
+    );
+
+    // Prefer the entries that boot from the network, like the one that loaded Sprout.
+    let network_entry = || {
+        config.entries.iter().find_map(|(name, entry)| {
+            entry
+                .actions
+                .iter()
+                .filter_map(|action| config.actions.get(action))
+                .any(|action| action.http_boot.is_some())
+                .then(|| name.clone())
+        })
+    };
+    if let Some(ref default_entry) = profile.default_entry {
+        options.default_entry = Some(default_entry.clone());
+    } else if options.default_entry.is_none() {
+        options.default_entry = network_entry();
+    }
+    true
+}
+
+fn default_netboot_enabled() -> bool {
+    true
+}
+
+fn default_netboot_menu_timeout() -> u64 {
+    NETBOOT_MENU_TIMEOUT_SECONDS
+}
+
+fn default_netboot_error_delay() -> u64 {
+    NETBOOT_ERROR_DELAY_SECONDS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::ActionDeclaration;
+    use crate::actions::http_boot::HttpBootConfiguration;
+    use crate::entries::EntryDeclaration;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn config() -> RootConfiguration {
+        let mut config = RootConfiguration::default();
+        config.options.autoconfigure = true;
+        config.options.menu_timeout = 10;
+        config.actions.insert(
+            "netboot".to_string(),
+            ActionDeclaration {
+                http_boot: Some(HttpBootConfiguration::default()),
+                ..Default::default()
+            },
+        );
+        for (name, action) in [("disk", "chainload"), ("network", "netboot")] {
+            config.entries.insert(
+                name.to_string(),
+                EntryDeclaration {
+                    actions: vec![action.to_string()],
+                    ..Default::default()
+                },
+            );
+        }
+        config
+    }
+
+    #[test]
+    fn profile_adjusts_defaults() {
+        let mut config = config();
+        assert!(apply(&mut config));
+        assert!(!config.options.autoconfigure);
+        assert_eq!(config.options.menu_timeout, 3);
+        assert_eq!(config.options.error_delay, Some(3));
+        assert_eq!(config.options.default_entry.as_deref(), Some("network"));
+    }
+
+    #[test]
+    fn profile_keeps_explicit_settings() {
+        let mut config = config();
+        config.options.menu_timeout = 1;
+        config.options.default_entry = Some("disk".to_string());
+        config.netboot.autoconfigure = true;
+        assert!(apply(&mut config));
+        assert!(config.options.autoconfigure);
+        assert_eq!(config.options.menu_timeout, 1);
+        assert_eq!(config.options.default_entry.as_deref(), Some("disk"));
+
+        let mut config = self::config();
+        config.netboot.enabled = false;
+        assert!(!apply(&mut config));
+        assert!(config.options.autoconfigure);
+        assert_eq!(config.options.menu_timeout, 10);
+    }
+}
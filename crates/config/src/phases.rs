@@ -3,6 +3,9 @@ use alloc::string::String;
 use alloc::vec::Vec;
 use serde::{Deserialize, Serialize};
 
+/// The key of the value that provides the name of the failed action to the on-failure phase.
+pub const FAILED_ACTION_KEY: &str = "failed-action";
+
 /// Configures the various phases of the boot process.
 /// This allows hooking various phases to run actions.
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
@@ -13,9 +16,18 @@ pub struct PhasesConfiguration {
     /// The startup phase is run after drivers are loaded, but before entries are displayed.
     #[serde(default)]
     pub startup: Vec<PhaseConfiguration>,
-    /// The late phase is run after the entry is chosen, but before the actions are executed.
+    /// The late phase is run after the entries are prepared, but before an entry is chosen.
     #[serde(default)]
     pub late: Vec<PhaseConfiguration>,
+    /// The pre-exec phase is run after the entry is chosen, but before its actions are executed.
+    /// The actions in this phase are run with the context of the chosen entry.
+    #[serde(default, rename = "pre-exec")]
+    pub pre_exec: Vec<PhaseConfiguration>,
+    /// The on-failure phase is run when an action of the chosen entry fails.
+    /// The actions in this phase are run with the context of the chosen entry, and
+    /// the name of the failed action is provided as the `failed-action` value.
+    #[serde(default, rename = "on-failure")]
+    pub on_failure: Vec<PhaseConfiguration>,
 }
 
 /// Configures a single phase of the boot process.
@@ -23,6 +35,11 @@ pub struct PhasesConfiguration {
 /// executed sequentially.
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct PhaseConfiguration {
+    /// The condition that must be true for the phase configuration to execute.
+    /// The condition is stamped with the phase values before it is evaluated.
+    /// If the condition is false, the actions in this phase configuration are skipped.
+    #[serde(default)]
+    pub when: Option<String>,
     /// The actions to run when the phase is executed.
     #[serde(default)]
     pub actions: Vec<String>,
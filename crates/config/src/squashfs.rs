@@ -0,0 +1,15 @@
+use alloc::string::String;
+use serde::{Deserialize, Serialize};
+
+/// Declares a squashfs container, which holds files like kernels and initrds in a single
+/// compressed image. The container is mounted as a read-only volume when Sprout starts,
+/// and files inside of it are referenced with `squashfs:<name>/` paths.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct SquashfsDeclaration {
+    /// The filesystem path to the squashfs image.
+    pub path: String,
+    /// The SHA-256 digest of the image as hex. If specified, the image is only mounted
+    /// when its contents match the digest.
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
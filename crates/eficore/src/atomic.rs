@@ -0,0 +1,304 @@
+use crate::path::resolve_path;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use anyhow::{Context, Result, anyhow, bail};
+use edera_sprout_parsing::atomic::{self, FileCopy};
+use log::warn;
+use uefi::proto::device_path::DevicePath;
+use uefi::proto::device_path::text::{AllowShortcuts, DisplayOnly};
+use uefi::proto::media::file::{
+    Directory, File, FileAttribute, FileHandle, FileInfo, FileMode, RegularFile,
+};
+use uefi::proto::media::fs::SimpleFileSystem;
+use uefi::{CString16, Handle, Status};
+
+/// The paths of the copies of a file that is replaced atomically.
+struct Copies {
+    /// The path of the file itself.
+    target: String,
+    /// The path of the copy that is written before it replaces the file.
+    new: String,
+    /// The path of the previous copy while the file is replaced.
+    old: String,
+}
+
+impl Copies {
+    /// The copies of the file at `path`.
+    fn new(path: String) -> Self {
+        Self {
+            new: atomic::new_path(&path),
+            old: atomic::old_path(&path),
+            target: path,
+        }
+    }
+
+    /// The path of the `copy`.
+    fn path(&self, copy: FileCopy) -> &str {
+        match copy {
+            FileCopy::Target => &self.target,
+            FileCopy::New => &self.new,
+            FileCopy::Old => &self.old,
+        }
+    }
+
+    /// Checks which of the copies exist in the `root` directory.
+    fn exist(&self, root: &mut Directory) -> Result<(bool, bool, bool)> {
+        Ok((
+            open(root, &self.target, FileMode::Read)?.is_some(),
+            open(root, &self.new, FileMode::Read)?.is_some(),
+            open(root, &self.old, FileMode::Read)?.is_some(),
+        ))
+    }
+}
+
+/// Opens the file at `path` in the `root` directory with the `mode`.
+/// Returns [None] if the file does not exist.
+fn open(root: &mut Directory, path: &str, mode: FileMode) -> Result<Option<FileHandle>> {
+    let name = CString16::try_from(path).context("unable to convert file path")?;
+    match root.open(&name, mode, FileAttribute::empty()) {
+        Ok(file) => Ok(Some(file)),
+        Err(error) if error.status() == Status::NOT_FOUND => Ok(None),
+        Err(error) => Err(error).with_context(|| format!("unable to open file {}", path)),
+    }
+}
+
+/// Deletes the file at `path` in the `root` directory, if it exists.
+fn delete(root: &mut Directory, path: &str) -> Result<()> {
+    if let Some(file) = open(root, path, FileMode::ReadWrite)? {
+        file.delete()
+            .with_context(|| format!("unable to delete file {}", path))?;
+    }
+    Ok(())
+}
+
+/// Renames the file at `from` in the `root` directory to the file at `to`,
+/// which must be in the same directory.
+///
+/// Renaming only rewrites the directory entry of the file, which is a single sector
+/// on FAT filesystems, so the file is either at `from` or `to` after an interruption.
+fn rename(root: &mut Directory, from: &str, to: &str) -> Result<()> {
+    let mut file = open(root, from, FileMode::ReadWrite)?
+        .with_context(|| format!("unable to find file {} to rename", from))?;
+    let info = file
+        .get_boxed_info::<FileInfo>()
+        .context("unable to get file info")?;
+
+    // The new name is relative to the directory that contains the file.
+    let name = to.rsplit('\\').next().unwrap_or(to);
+    let name = CString16::try_from(name).context("unable to convert file name")?;
+    let mut storage = vec![0u8; size_of_val(&*info) + (name.num_chars() + 1) * 2 + 8];
+    let renamed = FileInfo::new(
+        &mut storage,
+        info.file_size(),
+        info.physical_size(),
+        *info.create_time(),
+        *info.last_access_time(),
+        *info.modification_time(),
+        info.attribute(),
+        &name,
+    )
+    .map_err(|error| anyhow!("unable to build file info: {:?}", error))?;
+    file.set_info(renamed)
+        .with_context(|| format!("unable to rename file {} to {}", from, to))?;
+    file.flush()
+        .with_context(|| format!("unable to flush file {}", to))
+}
+
+/// Flushes the directory that contains the file at `path` in the `root` directory,
+/// so the entries of the file reach the disk before the next step of a replacement.
+fn flush_directory(root: &mut Directory, path: &str) -> Result<()> {
+    let directory = match path.rsplit_once('\\') {
+        Some((directory, _)) if !directory.is_empty() => directory,
+        _ => return root.flush().context("unable to flush root directory"),
+    };
+    if let Some(mut directory) = open(root, directory, FileMode::Read)? {
+        directory.flush().context("unable to flush directory")?;
+    }
+    Ok(())
+}
+
+/// Creates the directories that contain the file at `path` in the `root` directory.
+fn create_directories(root: &mut Directory, path: &str) -> Result<()> {
+    let Some((directory, _)) = path.rsplit_once('\\') else {
+        return Ok(());
+    };
+    let mut current = String::new();
+    for component in directory
+        .split('\\')
+        .filter(|component| !component.is_empty())
+    {
+        current.push('\\');
+        current.push_str(component);
+        let name = CString16::try_from(current.as_str()).context("unable to convert path")?;
+        let directory = root
+            .open(&name, FileMode::CreateReadWrite, FileAttribute::DIRECTORY)
+            .with_context(|| format!("unable to create directory {}", current))?;
+        if !directory.is_directory().unwrap_or(false) {
+            bail!("{} is not a directory", current);
+        }
+    }
+    Ok(())
+}
+
+/// Reads the entire contents of the regular `file`.
+fn read_contents(mut file: RegularFile) -> Result<Vec<u8>> {
+    let size = file
+        .get_boxed_info::<FileInfo>()
+        .context("unable to get file info")?
+        .file_size();
+    let size = usize::try_from(size).context("file is too large")?;
+    let mut content = vec![0u8; size];
+    let mut offset = 0;
+    while offset < size {
+        let read = file
+            .read(&mut content[offset..])
+            .context("unable to read file contents")?;
+        if read == 0 {
+            break;
+        }
+        offset += read;
+    }
+    content.truncate(offset);
+    Ok(content)
+}
+
+/// Writes `data` to the file at `path` in the `root` directory and flushes it.
+fn write_contents(root: &mut Directory, path: &str, data: &[u8]) -> Result<()> {
+    let name = CString16::try_from(path).context("unable to convert file path")?;
+    let mut file = root
+        .open(&name, FileMode::CreateReadWrite, FileAttribute::empty())
+        .with_context(|| format!("unable to create file {}", path))?
+        .into_regular_file()
+        .context("path is not a regular file")?;
+    file.write(data)
+        .map_err(|error| anyhow!("unable to write file {}: {}", path, error.status()))?;
+    file.flush()
+        .with_context(|| format!("unable to flush file {}", path))
+}
+
+/// Resolves the `input` path against `default_root_path` and opens the root directory of its
+/// filesystem exclusively, then calls `operation` with the directory, the path of the file
+/// in it, and the filesystem handle. Files replaced atomically are always on a filesystem,
+/// never at a URL.
+fn with_root<T>(
+    default_root_path: Option<&DevicePath>,
+    input: &str,
+    operation: impl FnOnce(&mut Directory, Copies, Handle) -> Result<T>,
+) -> Result<T> {
+    let resolved = resolve_path(default_root_path, input)?;
+    if resolved.url.is_some() {
+        bail!("unable to replace url {}", input);
+    }
+    let path = resolved
+        .sub_path
+        .to_string16(DisplayOnly(false), AllowShortcuts(false))
+        .context("unable to convert file path to string")?
+        .to_string();
+    let mut fs =
+        uefi::boot::open_protocol_exclusive::<SimpleFileSystem>(resolved.filesystem_handle)
+            .context("unable to open filesystem")?;
+    let mut root = fs
+        .open_volume()
+        .context("unable to open filesystem volume")?;
+    operation(&mut root, Copies::new(path), resolved.filesystem_handle)
+}
+
+/// Recovers the file of the `copies` from an interrupted replacement, so that only the target
+/// exists afterwards, if any copy is complete. See [atomic::recovered_copy].
+fn recover(root: &mut Directory, copies: &Copies) -> Result<()> {
+    let (target, new, old) = copies.exist(root)?;
+    if !new && !old {
+        return Ok(());
+    }
+    let recovered = atomic::recovered_copy(target, new, old);
+    if let Some(copy) = recovered
+        && copy != FileCopy::Target
+    {
+        warn!(
+            "recovering {} from an interrupted write",
+            copies.path(FileCopy::Target)
+        );
+        rename(root, copies.path(copy), &copies.target)?;
+    }
+    for copy in [FileCopy::New, FileCopy::Old] {
+        if recovered != Some(copy) {
+            delete(root, copies.path(copy))?;
+        }
+    }
+    flush_directory(root, &copies.target)
+}
+
+/// Writes `data` to the file at the location specified with the `input` path, replacing the
+/// file atomically if it exists. The directory containing the file is created if it is missing.
+/// Internally, this uses [resolve_path] to resolve the path, which is passed the
+/// `default_root_path` which should specify a base root.
+///
+/// The data is written and flushed to a new copy next to the file first, which then replaces
+/// the file by renaming, so an interruption like a power loss leaves either the previous or
+/// the new contents. A replacement that was interrupted before is recovered first.
+/// Use [read_file_contents] to read files that are written this way.
+///
+/// This acquires exclusive protocol access to the [SimpleFileSystem] protocol of the resolved
+/// filesystem handle, like [crate::path::read_file_contents].
+pub fn write_file_contents(
+    default_root_path: Option<&DevicePath>,
+    input: &str,
+    data: &[u8],
+) -> Result<()> {
+    with_root(default_root_path, input, |root, copies, _| {
+        create_directories(root, &copies.target)?;
+        recover(root, &copies)?;
+
+        // The new copy is complete once it is flushed, see [atomic::readable_copies].
+        write_contents(root, &copies.new, data)?;
+        flush_directory(root, &copies.target)?;
+        if open(root, &copies.target, FileMode::Read)?.is_some() {
+            rename(root, &copies.target, &copies.old)?;
+        }
+        rename(root, &copies.new, &copies.target)?;
+        delete(root, &copies.old)?;
+        flush_directory(root, &copies.target)
+    })
+}
+
+/// Reads the contents of the file at the location specified with the `input` path, which is
+/// written with [write_file_contents]. Internally, this uses [resolve_path] to resolve the
+/// path, which is passed the `default_root_path` which should specify a base root.
+///
+/// If a replacement of the file was interrupted, the latest complete copy is read instead.
+/// Copies are tried in order of preference until one can be read and is accepted by `valid`,
+/// so a caller that can tell torn contents apart falls back to the previous copy.
+/// Returns [None] if there is no copy of the file that can be read.
+///
+/// The copy that is read is checked against the active [crate::manifest] like any other file,
+/// with the path of the file itself.
+///
+/// This acquires exclusive protocol access to the [SimpleFileSystem] protocol of the resolved
+/// filesystem handle, like [crate::path::read_file_contents].
+pub fn read_file_contents(
+    default_root_path: Option<&DevicePath>,
+    input: &str,
+    valid: impl Fn(&[u8]) -> bool,
+) -> Result<Option<Vec<u8>>> {
+    with_root(default_root_path, input, |root, copies, filesystem| {
+        let (target, new, old) = copies.exist(root)?;
+        for copy in atomic::readable_copies(target, new, old) {
+            let path = copies.path(copy);
+            let content = open(root, path, FileMode::Read)?
+                .and_then(|file| file.into_regular_file())
+                .context("path is not a regular file")
+                .and_then(read_contents);
+            match content {
+                Ok(content) if valid(&content) => {
+                    crate::manifest::check(filesystem, &copies.target, &content)?;
+                    return Ok(Some(content));
+                }
+                Ok(_) => warn!("file {} has invalid contents", path),
+                Err(error) => warn!("unable to read file {}: {:#}", path, error),
+            }
+        }
+        Ok(None)
+    })
+}
@@ -0,0 +1,122 @@
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+
+/// Whether beep codes are played, which is off until the configuration enables it.
+/// This is atomic instead of locked, so beep codes can be played while handling a panic.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The pause between the tones of a beep code.
+const TONE_GAP: Duration = Duration::from_millis(80);
+
+/// A tone of a beep code, as the frequency in hertz and the duration in milliseconds.
+type Tone = (u32, u64);
+
+/// The beep codes that give audible feedback, so the boot menu can be operated without
+/// seeing the screen. Each code has a distinct rhythm and pitch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BeepCode {
+    /// The boot menu is displayed and waits for a selection. One short high tone.
+    MenuDisplayed,
+    /// An entry was selected and is about to boot. Two short rising tones.
+    EntrySelected,
+    /// A fatal error occurred. Three long low tones.
+    FatalError,
+}
+
+impl BeepCode {
+    /// The tones of the beep code, in the order they are played.
+    fn tones(&self) -> &'static [Tone] {
+        match self {
+            BeepCode::MenuDisplayed => &[(1000, 100)],
+            BeepCode::EntrySelected => &[(800, 80), (1200, 80)],
+            BeepCode::FatalError => &[(400, 300), (400, 300), (400, 300)],
+        }
+    }
+}
+
+/// Enables or disables beep codes, as the `enabled` flag specifies.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Release);
+}
+
+/// Plays the beep `code` if beep codes are enabled.
+/// Beep codes are played on the PC speaker, which only x86 machines have, so this does
+/// nothing on other architectures. Machines without a speaker simply stay silent.
+pub fn play(code: BeepCode) {
+    if !ENABLED.load(Ordering::Acquire) {
+        return;
+    }
+    for (index, (frequency, duration)) in code.tones().iter().enumerate() {
+        if index > 0 {
+            uefi::boot::stall(TONE_GAP);
+        }
+        speaker::tone(*frequency, Duration::from_millis(*duration));
+    }
+}
+
+/// Support for the PC speaker, which is driven by channel 2 of the programmable interval timer.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod speaker {
+    use core::arch::asm;
+    use core::time::Duration;
+
+    /// The frequency of the programmable interval timer in hertz.
+    const PIT_FREQUENCY: u32 = 1_193_182;
+
+    /// The I/O port of the mode register of the programmable interval timer.
+    const PIT_COMMAND_PORT: u16 = 0x43;
+
+    /// The I/O port of the data register of channel 2 of the programmable interval timer.
+    const PIT_CHANNEL_2_PORT: u16 = 0x42;
+
+    /// The command that programs channel 2 as a square wave generator with a 16-bit divisor.
+    const PIT_CHANNEL_2_SQUARE_WAVE: u8 = 0xb6;
+
+    /// The I/O port that gates channel 2 to the speaker.
+    const SPEAKER_PORT: u16 = 0x61;
+
+    /// The bits of the speaker port that enable channel 2 and connect it to the speaker.
+    const SPEAKER_ENABLE: u8 = 0x03;
+
+    /// Writes the `value` to the I/O `port`.
+    fn outb(port: u16, value: u8) {
+        // SAFETY: the ports of the interval timer and the speaker gate are standard on x86
+        // and writing them only affects the speaker.
+        unsafe {
+            asm!("out dx, al", in("dx") port, in("al") value, options(nomem, nostack));
+        }
+    }
+
+    /// Reads a value from the I/O `port`.
+    fn inb(port: u16) -> u8 {
+        let value: u8;
+        // SAFETY: reading the speaker gate has no side effects.
+        unsafe {
+            asm!("in al, dx", in("dx") port, out("al") value, options(nomem, nostack));
+        }
+        value
+    }
+
+    /// Plays a tone with the `frequency` in hertz on the speaker for the `duration`.
+    pub fn tone(frequency: u32, duration: Duration) {
+        let divisor = (PIT_FREQUENCY / frequency.max(1)).clamp(1, u16::MAX as u32) as u16;
+        let [low, high] = divisor.to_le_bytes();
+        outb(PIT_COMMAND_PORT, PIT_CHANNEL_2_SQUARE_WAVE);
+        outb(PIT_CHANNEL_2_PORT, low);
+        outb(PIT_CHANNEL_2_PORT, high);
+
+        let gate = inb(SPEAKER_PORT);
+        outb(SPEAKER_PORT, gate | SPEAKER_ENABLE);
+        uefi::boot::stall(duration);
+        outb(SPEAKER_PORT, gate & !SPEAKER_ENABLE);
+    }
+}
+
+/// Machines other than x86 have no PC speaker, so tones are not played.
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+mod speaker {
+    use core::time::Duration;
+
+    /// Does nothing, as there is no speaker to play the tone on.
+    pub fn tone(_frequency: u32, _duration: Duration) {}
+}
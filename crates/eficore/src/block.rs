@@ -0,0 +1,126 @@
+use crate::disk::open_shared;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use anyhow::{Context, Result, anyhow, bail};
+use edera_sprout_parsing::filesystem::ReadAt;
+use uefi::boot::ScopedProtocol;
+use uefi::mem::AlignedBuffer;
+use uefi::proto::media::block::BlockIO;
+use uefi::proto::media::disk::DiskIo;
+use uefi::{Handle, Status};
+
+/// A block device that can be read by LBA or by byte offset.
+///
+/// Blocks are read with the block io protocol into buffers that meet the alignment
+/// requirements of the device. Byte reads use the disk io protocol when the firmware
+/// provides it, and otherwise read the covering blocks.
+pub struct BlockDevice {
+    /// The block io protocol of the device.
+    block: ScopedProtocol<BlockIO>,
+    /// The disk io protocol of the device, if the firmware provides it.
+    disk: Option<ScopedProtocol<DiskIo>>,
+}
+
+impl BlockDevice {
+    /// Opens the block device on `handle`.
+    /// The device is opened shared, so drivers on top of it are not disconnected.
+    pub fn open(handle: Handle) -> Result<Self> {
+        let block = open_shared::<BlockIO>(handle).context("unable to open block io protocol")?;
+        // Not all block devices provide the disk io protocol, which is not an error.
+        let disk = open_shared::<DiskIo>(handle).ok();
+        Ok(Self { block, disk })
+    }
+
+    /// The size of a block of the device in bytes.
+    pub fn block_size(&self) -> usize {
+        self.block.media().block_size() as usize
+    }
+
+    /// The number of blocks on the device.
+    pub fn block_count(&self) -> u64 {
+        self.block.media().last_block().saturating_add(1)
+    }
+
+    /// Whether the device is a partition rather than a whole disk.
+    pub fn is_partition(&self) -> bool {
+        self.block.media().is_logical_partition()
+    }
+
+    /// Read `count` blocks starting at `lba`.
+    pub fn read_blocks(&self, lba: u64, count: usize) -> Result<Vec<u8>> {
+        let end = lba
+            .checked_add(count as u64)
+            .context("block read range overflow")?;
+        if end > self.block_count() {
+            bail!(
+                "block read of {} blocks at lba {} is past the end of the device",
+                count,
+                lba
+            );
+        }
+        let size = self
+            .block_size()
+            .checked_mul(count)
+            .context("block read size overflow")?;
+        if size == 0 {
+            return Ok(Vec::new());
+        }
+
+        // Allocate a buffer that meets the alignment requirements of the device.
+        let media = self.block.media();
+        let mut buffer = AlignedBuffer::from_size_align(size, media.io_align().max(1) as usize)
+            .map_err(|error| anyhow!("unable to allocate block buffer: {}", error))?;
+        self.retry_media_change(|media_id| {
+            self.block.read_blocks(media_id, lba, buffer.as_slice_mut())
+        })
+        .context("unable to read blocks")?;
+        Ok(buffer.as_slice().to_vec())
+    }
+
+    /// Read `size` bytes starting at the byte `offset`, which does not need to be block aligned.
+    pub fn read_bytes(&self, offset: u64, size: usize) -> Result<Vec<u8>> {
+        if let Some(ref disk) = self.disk {
+            let mut buffer = alloc::vec![0u8; size];
+            self.retry_media_change(|media_id| disk.read_disk(media_id, offset, &mut buffer))
+                .context("unable to read disk")?;
+            return Ok(buffer);
+        }
+
+        // Without disk io, read the blocks that cover the range and slice out the bytes.
+        let block_size = self.block_size().max(1) as u64;
+        let end = offset
+            .checked_add(size as u64)
+            .context("disk read range overflow")?;
+        let first = offset / block_size;
+        let count = end.div_ceil(block_size) - first;
+        let blocks = self.read_blocks(first, count as usize)?;
+        let start = (offset - first * block_size) as usize;
+        Ok(blocks[start..start + size].to_vec())
+    }
+
+    /// Runs the `read` operation with the current media id, retrying once if the media
+    /// changed since the device was opened. The firmware updates the media of the
+    /// device when it reports the change, so the retry uses the new media id.
+    fn retry_media_change(&self, mut read: impl FnMut(u32) -> uefi::Result) -> uefi::Result {
+        match read(self.block.media().media_id()) {
+            Err(error) if error.status() == Status::MEDIA_CHANGED => {
+                if !self.block.media().is_media_present() {
+                    return Err(Status::NO_MEDIA.into());
+                }
+                read(self.block.media().media_id())
+            }
+            result => result,
+        }
+    }
+}
+
+impl ReadAt for BlockDevice {
+    fn read_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), String> {
+        let data = self
+            .read_bytes(offset, buffer.len())
+            .map_err(|error| format!("{:#}", error))?;
+        buffer.copy_from_slice(&data);
+        Ok(())
+    }
+}
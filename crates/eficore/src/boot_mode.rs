@@ -0,0 +1,92 @@
+use crate::disk::open_shared;
+use crate::variables::VariableController;
+use alloc::string::String;
+use anyhow::Result;
+use edera_sprout_parsing::load_option::{boot_option_name, load_option_description};
+use uefi::proto::device_path::{DevicePath, DeviceSubType, DeviceType};
+use uefi::proto::media::block::BlockIO;
+
+/// The medium that an image was loaded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BootMedium {
+    /// The image was loaded from a fixed disk.
+    Fixed,
+    /// The image was loaded from removable media, like a USB drive or optical disc.
+    Removable,
+    /// The image was loaded over the network.
+    Network,
+}
+
+impl BootMedium {
+    /// Acquire the name of the medium, which is the form exposed to configurations.
+    pub fn name(&self) -> &'static str {
+        match self {
+            BootMedium::Fixed => "fixed",
+            BootMedium::Removable => "removable",
+            BootMedium::Network => "network",
+        }
+    }
+}
+
+/// Determine the [BootMedium] that the image at the device `path` was loaded from.
+/// Network boots are detected by network nodes in the path. Removable media is detected
+/// by USB or CD-ROM nodes in the path, or by the block device reporting removable media.
+pub fn boot_medium(path: &DevicePath) -> BootMedium {
+    let mut removable = false;
+    for node in path.node_iter() {
+        match (node.device_type(), node.sub_type()) {
+            // Network nodes indicate the image was loaded over the network.
+            (
+                DeviceType::MESSAGING,
+                DeviceSubType::MESSAGING_MAC_ADDRESS
+                | DeviceSubType::MESSAGING_IPV4
+                | DeviceSubType::MESSAGING_IPV6
+                | DeviceSubType::MESSAGING_URI,
+            ) => return BootMedium::Network,
+
+            // USB and CD-ROM nodes indicate removable media.
+            (DeviceType::MESSAGING, DeviceSubType::MESSAGING_USB)
+            | (DeviceType::MEDIA, DeviceSubType::MEDIA_CD_ROM) => removable = true,
+
+            _ => {}
+        }
+    }
+
+    // Ask the block device if the media is removable, like an SD card.
+    // If the block device can't be found, we rely only on the path nodes.
+    if !removable {
+        let mut path = path;
+        removable = uefi::boot::locate_device_path::<BlockIO>(&mut path)
+            .ok()
+            .and_then(|handle| open_shared::<BlockIO>(handle).ok())
+            .map(|block| block.media().is_removable_media())
+            .unwrap_or(false);
+    }
+
+    if removable {
+        BootMedium::Removable
+    } else {
+        BootMedium::Fixed
+    }
+}
+
+/// Acquire the number of the firmware boot entry that started the current boot, if any.
+/// This is read from the `BootCurrent` variable.
+pub fn boot_current() -> Result<Option<u16>> {
+    VariableController::GLOBAL.get_u16le("BootCurrent")
+}
+
+/// Acquire the name of the firmware boot entry that started the current boot, like `Boot0001`.
+pub fn boot_current_name() -> Result<Option<String>> {
+    Ok(boot_current()?.map(boot_option_name))
+}
+
+/// Acquire the description of the firmware boot entry that started the current boot.
+/// Returns None if the firmware did not report a boot entry or it has no description.
+pub fn boot_current_description() -> Result<Option<String>> {
+    let Some(name) = boot_current_name()? else {
+        return Ok(None);
+    };
+    let data = VariableController::GLOBAL.get(&name)?;
+    Ok(data.and_then(|data| load_option_description(&data)))
+}
@@ -0,0 +1,161 @@
+use crate::variables::{VariableClass, VariableController};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use anyhow::{Context, Result, bail};
+use edera_sprout_parsing::load_option::{
+    LOAD_OPTION_ACTIVE, LoadOption, boot_option_name, encode_boot_order, parse_boot_option_name,
+    parse_boot_order, plan_registration, short_form_device_path,
+};
+use log::{info, warn};
+use uefi::proto::device_path::DevicePath;
+
+/// The name of the variable that holds the order in which the firmware tries boot options.
+const BOOT_ORDER_VARIABLE: &str = "BootOrder";
+
+/// Reads the firmware boot options, skipping any that are malformed.
+fn boot_options() -> Result<Vec<(u16, LoadOption)>> {
+    let mut options = Vec::new();
+    let variables = VariableController::GLOBAL
+        .iter()
+        .context("unable to enumerate boot options")?;
+    for number in variables.filter_map(|variable| parse_boot_option_name(&variable.name)) {
+        let name = boot_option_name(number);
+        let Some(data) = VariableController::GLOBAL.get(&name)? else {
+            continue;
+        };
+        match LoadOption::parse(&data) {
+            Some(option) => options.push((number, option)),
+            None => warn!("ignoring malformed boot option {}", name),
+        }
+    }
+    Ok(options)
+}
+
+/// Ensures a firmware boot option named `description` exists that boots the image at the
+/// device `path`, and that the boot option is in the boot order.
+///
+/// An existing boot option for the image is repaired, and otherwise a new one is created
+/// and placed first in the boot order. Returns the name of the boot option, like `Boot0001`.
+/// The boot option is tracked, so [remove_stale] can remove it once it is no longer booted.
+/// If `dry_run` is set, the changes are only logged.
+pub fn register(description: &str, path: &DevicePath, dry_run: bool) -> Result<String> {
+    let Some(file_path) = short_form_device_path(path.as_bytes()) else {
+        bail!("unable to determine the boot option path of the image");
+    };
+    let desired = LoadOption {
+        attributes: LOAD_OPTION_ACTIVE,
+        description: description.into(),
+        file_path: file_path.to_vec(),
+        optional_data: Vec::new(),
+    };
+
+    let existing = boot_options()?;
+    let boot_order = VariableController::GLOBAL
+        .get(BOOT_ORDER_VARIABLE)
+        .context("unable to read boot order")?
+        .map(|data| parse_boot_order(&data))
+        .unwrap_or_default();
+    let Some(plan) = plan_registration(&existing, &boot_order, &desired) else {
+        bail!("no free boot option number is available");
+    };
+    let name = boot_option_name(plan.number);
+
+    if let Some(option) = plan.option {
+        if dry_run {
+            info!("dry run: would write boot option {}", name);
+        } else {
+            info!("writing boot option {}", name);
+            VariableController::GLOBAL
+                .set_preserving_attributes(
+                    &name,
+                    &option.encode(),
+                    VariableClass::BootAndRuntimePersistent,
+                )
+                .with_context(|| format!("unable to write boot option {}", name))?;
+        }
+    }
+
+    if let Some(order) = plan.boot_order {
+        if dry_run {
+            info!("dry run: would add boot option {} to the boot order", name);
+        } else {
+            info!("adding boot option {} to the boot order", name);
+            VariableController::GLOBAL
+                .set_preserving_attributes(
+                    BOOT_ORDER_VARIABLE,
+                    &encode_boot_order(&order),
+                    VariableClass::BootAndRuntimePersistent,
+                )
+                .context("unable to write boot order")?;
+        }
+    }
+
+    if !dry_run {
+        crate::variable_gc::touch(&name, crate::variable_gc::now()?)
+            .context("unable to track boot option")?;
+    }
+    Ok(name)
+}
+
+/// Removes the boot options that [register] created and that were not booted in the last
+/// `max_age_days` days, along with their place in the boot order. This keeps copies of the
+/// ESP on disks that are gone from filling up the variable store. Boot options that Sprout
+/// did not create and the boot option of the current boot are never removed.
+/// Returns the names of the removed boot options, or that would have been removed if
+/// `dry_run` is set, in which case nothing is changed.
+pub fn remove_stale(max_age_days: u64, dry_run: bool) -> Result<Vec<String>> {
+    let now = crate::variable_gc::now().context("unable to determine the age of boot options")?;
+    remove_stale_at(max_age_days, now, dry_run)
+}
+
+/// Removes the stale boot options like [remove_stale], at the unix timestamp `now`.
+fn remove_stale_at(max_age_days: u64, now: i64, dry_run: bool) -> Result<Vec<String>> {
+    let names = boot_options()?
+        .into_iter()
+        .map(|(number, _)| boot_option_name(number))
+        .collect::<Vec<_>>();
+    let current = crate::boot_mode::boot_current()?.map(boot_option_name);
+    let stale =
+        crate::variable_gc::collect(&names, current.as_deref(), max_age_days, now, dry_run)?;
+    if stale.is_empty() {
+        return Ok(stale);
+    }
+    if dry_run {
+        for name in &stale {
+            info!("dry run: would remove stale boot option {}", name);
+        }
+        return Ok(stale);
+    }
+
+    for name in &stale {
+        info!("removing stale boot option {}", name);
+        if let Err(error) = VariableController::GLOBAL.remove(name) {
+            warn!("unable to remove stale boot option {}: {}", name, error);
+        }
+    }
+
+    // Remove the stale boot options from the boot order, so the firmware does not try them.
+    let Some(data) = VariableController::GLOBAL
+        .get(BOOT_ORDER_VARIABLE)
+        .context("unable to read boot order")?
+    else {
+        return Ok(stale);
+    };
+    let boot_order = parse_boot_order(&data);
+    let order = boot_order
+        .iter()
+        .copied()
+        .filter(|number| !stale.contains(&boot_option_name(*number)))
+        .collect::<Vec<_>>();
+    if order != boot_order {
+        VariableController::GLOBAL
+            .set_preserving_attributes(
+                BOOT_ORDER_VARIABLE,
+                &encode_boot_order(&order),
+                VariableClass::BootAndRuntimePersistent,
+            )
+            .context("unable to write boot order")?;
+    }
+    Ok(stale)
+}
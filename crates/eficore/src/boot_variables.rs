@@ -0,0 +1,109 @@
+use crate::services::services;
+use crate::variables::{VariableClass, VariableController};
+use alloc::format;
+use alloc::vec::Vec;
+use anyhow::{Context, Result, anyhow, bail};
+use edera_sprout_parsing::boot_variables::{BootVariablesBackup, is_boot_variable};
+use log::info;
+use uefi::proto::device_path::DevicePath;
+
+/// Writes a backup of the firmware boot variables, which are the boot options, `BootOrder`,
+/// and `Timeout`, to the file at `path`, which is resolved against `root`.
+/// Returns the number of variables in the backup.
+pub fn backup(root: &DevicePath, path: &str) -> Result<usize> {
+    let mut variables = Vec::new();
+    for variable in VariableController::GLOBAL
+        .iter()
+        .context("unable to enumerate boot variables")?
+        .filter(|variable| is_boot_variable(&variable.name))
+    {
+        // Variables that are removed while they are backed up are skipped.
+        if let Some(data) = VariableController::GLOBAL.get(&variable.name)? {
+            variables.push((variable.name, data));
+        }
+    }
+
+    let backup = BootVariablesBackup::new(variables);
+    services()
+        .write_file_atomic(Some(root), path, &backup.encode())
+        .context("unable to write boot variables backup")?;
+    info!(
+        "backed up {} boot variables to {}",
+        backup.variables.len(),
+        path
+    );
+    Ok(backup.variables.len())
+}
+
+/// Restores the firmware boot variables from the backup in the file at `path`, which is
+/// resolved against `root`. Returns the number of variables that were changed.
+///
+/// Variables that already have the data of the backup are not written, to spare the variable
+/// store. Boot options that are not in the backup are kept, as the restored `BootOrder`
+/// decides which boot options are used.
+///
+/// The backup is written at runtime, so it can't be in the ESP manifest. While the manifest
+/// is enforced, the backup is not trusted and nothing is restored.
+pub fn restore(root: &DevicePath, path: &str) -> Result<usize> {
+    if crate::manifest::is_enforced() {
+        bail!("boot variables backups are not restored while the esp manifest is enforced");
+    }
+    let data = services()
+        .read_file_atomic(Some(root), path)
+        .context("unable to read boot variables backup")?;
+    let backup = BootVariablesBackup::parse(&data)
+        .map_err(|error| anyhow!("unable to parse boot variables backup: {}", error))?;
+
+    let mut changed = 0;
+    for (name, value) in &backup.variables {
+        if VariableController::GLOBAL.get(name)?.as_deref() == Some(value.as_slice()) {
+            continue;
+        }
+        info!("restoring boot variable {}", name);
+        VariableController::GLOBAL
+            .set_preserving_attributes(name, value, VariableClass::BootAndRuntimePersistent)
+            .with_context(|| format!("unable to restore boot variable {}", name))?;
+        changed += 1;
+    }
+    info!("restored {} boot variables from {}", changed, path);
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::testing::install_memory;
+    use alloc::vec;
+
+    #[test]
+    fn boot_variables_are_restored() {
+        install_memory();
+        // The device path that only holds the end node, as the memory services ignore the root.
+        let root = <&DevicePath>::try_from([0x7f, 0xff, 0x04, 0x00].as_slice()).unwrap();
+        let boot = VariableClass::BootAndRuntimePersistent;
+        VariableController::GLOBAL
+            .set("Boot0001", b"sprout", boot)
+            .unwrap();
+        VariableController::GLOBAL
+            .set("BootOrder", &[1, 0], boot)
+            .unwrap();
+        assert_eq!(backup(root, "\\sprout\\boot-variables.bak").unwrap(), 2);
+
+        // A firmware update reorders the boot options and adds one of its own.
+        VariableController::GLOBAL
+            .set("BootOrder", &[2, 0, 1, 0], boot)
+            .unwrap();
+        VariableController::GLOBAL
+            .set("Boot0002", b"firmware", boot)
+            .unwrap();
+        assert_eq!(restore(root, "\\sprout\\boot-variables.bak").unwrap(), 1);
+        assert_eq!(
+            VariableController::GLOBAL.get("BootOrder").unwrap(),
+            Some(vec![1, 0])
+        );
+        assert_eq!(
+            VariableController::GLOBAL.get("Boot0002").unwrap(),
+            Some(b"firmware".to_vec())
+        );
+    }
+}
@@ -1,6 +1,8 @@
 use crate::bootloader_interface::bitflags::LoaderFeatures;
 use crate::platform::timer::PlatformTimer;
-use crate::variables::{VariableClass, VariableController};
+use crate::services::services;
+use crate::strings;
+use crate::variables::{VariableClass, VariableController, VariableInfo};
 use alloc::format;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
@@ -51,6 +53,11 @@ impl BootloaderInterface {
             | LoaderFeatures::EntryOneShot
     }
 
+    /// Enumerate the bootloader interface variables that are set.
+    pub fn variables() -> Result<impl Iterator<Item = VariableInfo>> {
+        Self::VENDOR.iter()
+    }
+
     /// Tell the system that Sprout was initialized at the current time.
     pub fn mark_init(timer: &PlatformTimer) -> Result<()> {
         Self::mark_time("LoaderTimeInitUSec", timer)
@@ -126,16 +133,8 @@ impl BootloaderInterface {
         // Iterate over the entries and convert them to CString16 placing them into data.
         let mut data = Vec::new();
         for entry in entries {
-            // Convert the entry to CString16 little endian.
-            let encoded = entry
-                .as_ref()
-                .encode_utf16()
-                .flat_map(|c| c.to_le_bytes())
-                .collect::<Vec<u8>>();
-            // Write the bytes into the data buffer.
-            data.extend_from_slice(&encoded);
-            // Add a null terminator to the end of the entry.
-            data.extend_from_slice(&[0, 0]);
+            // Convert the entry to a null-terminated CString16 little endian.
+            data.extend_from_slice(&strings::encode_utf16le_nul(entry.as_ref()));
         }
 
         // If no data was generated, we will do nothing.
@@ -161,18 +160,14 @@ impl BootloaderInterface {
 
     /// Tell the system about the UEFI firmware we are running on.
     pub fn set_firmware_info() -> Result<()> {
-        // Access the firmware revision.
-        let firmware_revision = uefi::system::firmware_revision();
-
-        // Access the UEFI revision.
-        let uefi_revision = uefi::system::uefi_revision();
+        let firmware = services().firmware_info();
 
         // Format the firmware information string into something human-readable.
         let firmware_info = format!(
             "{} {}.{:02}",
-            uefi::system::firmware_vendor(),
-            firmware_revision >> 16,
-            firmware_revision & 0xffff,
+            firmware.vendor,
+            firmware.revision >> 16,
+            firmware.revision & 0xffff,
         );
         Self::VENDOR.set_cstr16(
             "LoaderFirmwareInfo",
@@ -181,11 +176,7 @@ impl BootloaderInterface {
         )?;
 
         // Format the firmware revision into something human-readable.
-        let firmware_type = format!(
-            "UEFI {}.{:02}",
-            uefi_revision.major(),
-            uefi_revision.minor()
-        );
+        let firmware_type = format!("UEFI {}.{:02}", firmware.uefi_major, firmware.uefi_minor);
         Self::VENDOR.set_cstr16(
             "LoaderFirmwareType",
             &firmware_type,
@@ -294,6 +285,15 @@ impl BootloaderInterface {
             .context("unable to get default entry from bootloader interface")
     }
 
+    /// Checks whether a oneshot entry is set by the bootloader interface.
+    /// Unlike [BootloaderInterface::get_oneshot_entry], this does not remove the entry.
+    pub fn has_oneshot_entry() -> Result<bool> {
+        Ok(Self::VENDOR
+            .get_cstr16("LoaderEntryOneShot")
+            .context("unable to get oneshot entry from bootloader interface")?
+            .is_some())
+    }
+
     /// Get the oneshot entry set by the bootloader interface.
     /// This should be the entry we boot.
     pub fn get_oneshot_entry() -> Result<Option<String>> {
@@ -315,3 +315,35 @@ impl BootloaderInterface {
         Ok(Some(value))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::FirmwareInfo;
+    use crate::services::testing::install_memory;
+
+    #[test]
+    fn firmware_info_is_published() {
+        install_memory().set_firmware_info(FirmwareInfo {
+            vendor: "EDK II".to_string(),
+            revision: 0x0001_0005,
+            uefi_major: 2,
+            uefi_minor: 70,
+        });
+        BootloaderInterface::set_firmware_info().unwrap();
+        assert_eq!(
+            BootloaderInterface::VENDOR
+                .get_cstr16("LoaderFirmwareInfo")
+                .unwrap()
+                .as_deref(),
+            Some("EDK II 1.05")
+        );
+        assert_eq!(
+            BootloaderInterface::VENDOR
+                .get_cstr16("LoaderFirmwareType")
+                .unwrap()
+                .as_deref(),
+            Some("UEFI 2.70")
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use anyhow::Result;
+use log::error;
+
+/// A cleanup operation registered with [Cleanups].
+struct Cleanup {
+    /// The description of the operation, used in error messages.
+    description: String,
+    /// The operation to run.
+    operation: Box<dyn FnOnce() -> Result<()>>,
+}
+
+/// An ordered set of cleanup operations, like unregistering media loaders or uninstalling hooks.
+/// Operations are run in the reverse order of their registration.
+/// Calling `drop` on this will run any operations that have not been run yet,
+/// which ensures resources are released on early returns.
+#[derive(Default)]
+pub struct Cleanups {
+    /// The registered operations, in the order of registration.
+    operations: Vec<Cleanup>,
+}
+
+impl Cleanups {
+    /// Creates an empty set of cleanup operations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the cleanup `operation`. The `description` should describe what the operation
+    /// does, like `unregister kernel media loader`, and is used if the operation fails.
+    pub fn defer(
+        &mut self,
+        description: impl ToString,
+        operation: impl FnOnce() -> Result<()> + 'static,
+    ) {
+        self.operations.push(Cleanup {
+            description: description.to_string(),
+            operation: Box::new(operation),
+        });
+    }
+
+    /// Moves all the operations of `other` into this set.
+    /// The operations of `other` will run before the operations already registered.
+    pub fn adopt(&mut self, mut other: Cleanups) {
+        self.operations.append(&mut other.operations);
+    }
+
+    /// Checks whether there are no registered operations.
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Runs all the registered operations in the reverse order of their registration.
+    /// Errors are logged, as the other operations should run regardless of a failure.
+    pub fn run(&mut self) {
+        while let Some(cleanup) = self.operations.pop() {
+            if let Err(error) = (cleanup.operation)() {
+                error!("unable to {}: {}", cleanup.description, error);
+            }
+        }
+    }
+}
+
+impl Drop for Cleanups {
+    fn drop(&mut self) {
+        self.run();
+    }
+}
@@ -0,0 +1,40 @@
+use crate::services::services;
+use anyhow::{Context, Result};
+use log::warn;
+use uefi::Identify;
+use uefi::proto::console::text::Input;
+use uefi::proto::media::fs::SimpleFileSystem;
+
+/// Connects all controllers to their drivers, recursively.
+/// This binds drivers that were loaded, or that the firmware did not connect during a fast
+/// boot, to their hardware. Paths are resolved again afterwards, as new filesystems may appear.
+pub fn connect_all() -> Result<()> {
+    services()
+        .connect_controllers()
+        .context("unable to connect controllers")?;
+    crate::path::clear_cache();
+    Ok(())
+}
+
+/// Connects all controllers if no filesystem or no console input is available.
+/// Minimal firmware and fast boot modes often leave controllers, like the USB controller of
+/// the keyboard, unconnected. Returns whether the controllers were connected.
+pub fn ensure_connected() -> Result<bool> {
+    let filesystems = services()
+        .find_handles(&SimpleFileSystem::GUID)
+        .context("unable to find filesystem handles")?;
+    let inputs = services()
+        .find_handles(&Input::GUID)
+        .context("unable to find console input handles")?;
+    if !filesystems.is_empty() && !inputs.is_empty() {
+        return Ok(false);
+    }
+
+    warn!(
+        "found {} filesystems and {} console inputs, connecting controllers",
+        filesystems.len(),
+        inputs.len()
+    );
+    connect_all()?;
+    Ok(true)
+}
@@ -0,0 +1,165 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use anyhow::{Context, Result, bail};
+use core::time::Duration;
+use log::warn;
+use spin::Mutex;
+use uefi::Event;
+use uefi::boot::{EventType, TimerTrigger, Tpl};
+
+/// A timer that tells whether a deadline has passed.
+trait DeadlineTimer {
+    /// Checks whether the deadline has passed.
+    fn expired(&self) -> Result<bool>;
+}
+
+/// A deadline timer that is backed by a UEFI timer event, which is closed when dropped.
+struct EventTimer(Option<Event>);
+
+impl DeadlineTimer for EventTimer {
+    fn expired(&self) -> Result<bool> {
+        let Some(ref event) = self.0 else {
+            return Ok(false);
+        };
+        uefi::boot::check_event(event).context("unable to check deadline timer")
+    }
+}
+
+impl Drop for EventTimer {
+    fn drop(&mut self) {
+        if let Some(event) = self.0.take()
+            && let Err(error) = uefi::boot::close_event(event)
+        {
+            warn!("unable to close deadline timer event: {}", error);
+        }
+    }
+}
+
+/// A deadline of an operation that is currently running.
+struct ActiveDeadline {
+    /// The timer that tells when the deadline has passed.
+    timer: Box<dyn DeadlineTimer>,
+    /// The timeout of the deadline, used for error messages.
+    timeout: Duration,
+}
+
+// SAFETY: UEFI boot services are single-threaded, so the timer is never used from another thread.
+unsafe impl Send for ActiveDeadline {}
+
+/// The deadlines of the operations that are currently running, innermost last.
+/// Every deadline is checked, so an inner deadline can not extend an outer one.
+/// This is messy, but it is safe given the mutex.
+static ACTIVE_DEADLINES: Mutex<Vec<ActiveDeadline>> = Mutex::new(Vec::new());
+
+/// The check of the cancellation of the operation that is currently running, if any.
+/// It returns true when the operation should be cancelled.
+static ACTIVE_CANCEL: Mutex<Option<fn() -> bool>> = Mutex::new(None);
+
+/// Creates a timer event that is signaled after `timeout`.
+fn create_timer(timeout: Duration) -> Result<Event> {
+    // SAFETY: The timer event creation allocated a timer pointer on the UEFI heap.
+    // This is validated safe as long as we are in boot services.
+    let event = unsafe {
+        uefi::boot::create_event(EventType::TIMER, Tpl::CALLBACK, None, None)
+            .context("unable to create deadline timer event")?
+    };
+
+    // The timeout is in increments of 100 nanoseconds.
+    let timeout_hundred_nanos = u64::try_from(timeout.as_nanos() / 100).unwrap_or(u64::MAX);
+    let trigger = TimerTrigger::Relative(timeout_hundred_nanos);
+    if let Err(error) = uefi::boot::set_timer(&event, trigger) {
+        // Close the event since we will not be able to use it.
+        if let Err(close_error) = uefi::boot::close_event(event) {
+            warn!("unable to close deadline timer event: {}", close_error);
+        }
+        bail!("unable to set deadline timer: {}", error);
+    }
+    Ok(event)
+}
+
+/// Runs `operation` with a deadline of `timeout`.
+/// Cancellable operations, like chunked file reads, call [check] and fail once the deadline
+/// has passed. Operations that do not check the deadline can not be interrupted.
+/// Deadlines can be nested, in which case the operation fails once any of them has passed.
+pub fn with_deadline<T>(timeout: Duration, operation: impl FnOnce() -> Result<T>) -> Result<T> {
+    let timer = EventTimer(Some(create_timer(timeout)?));
+    with_timer(Box::new(timer), timeout, operation)
+}
+
+/// Runs `operation` with the deadline of `timeout` that the `timer` tracks, like [with_deadline].
+fn with_timer<T>(
+    timer: Box<dyn DeadlineTimer>,
+    timeout: Duration,
+    operation: impl FnOnce() -> Result<T>,
+) -> Result<T> {
+    ACTIVE_DEADLINES
+        .lock()
+        .push(ActiveDeadline { timer, timeout });
+    let result = operation();
+
+    // Drop the deadline outside of the lock, as closing its timer may log.
+    let current = ACTIVE_DEADLINES.lock().pop();
+    drop(current);
+    result
+}
+
+/// Runs `operation` so that it can be cancelled by the user.
+/// Cancellable operations call [check], which calls `cancelled` and fails once it returns true,
+/// like when a key is pressed. Cancellation checks can be nested, in which case only the
+/// innermost check is used.
+pub fn with_cancel<T>(cancelled: fn() -> bool, operation: impl FnOnce() -> Result<T>) -> Result<T> {
+    let previous = ACTIVE_CANCEL.lock().replace(cancelled);
+    let result = operation();
+    *ACTIVE_CANCEL.lock() = previous;
+    result
+}
+
+/// Checks whether any active deadline has passed or the operation was cancelled,
+/// returning an error if it has.
+/// This should be called periodically by long-running operations to allow cancellation.
+pub fn check() -> Result<()> {
+    // Copy the check out of the lock, as it may run arbitrary code.
+    let cancelled = *ACTIVE_CANCEL.lock();
+    if let Some(cancelled) = cancelled
+        && cancelled()
+    {
+        bail!("operation cancelled");
+    }
+
+    for deadline in ACTIVE_DEADLINES.lock().iter() {
+        if deadline.timer.expired()? {
+            bail!("operation timed out after {:?}", deadline.timeout);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    /// A deadline timer that has either passed or not.
+    struct FixedTimer(bool);
+
+    impl DeadlineTimer for FixedTimer {
+        fn expired(&self) -> Result<bool> {
+            Ok(self.0)
+        }
+    }
+
+    #[test]
+    fn outer_deadlines_apply_to_nested_operations() {
+        let short = Duration::from_secs(1);
+        let long = Duration::from_secs(3600);
+        let error = with_timer(Box::new(FixedTimer(true)), short, || {
+            with_timer(Box::new(FixedTimer(false)), long, check)
+        })
+        .unwrap_err();
+        assert_eq!(error.to_string(), "operation timed out after 1s");
+
+        // The deadlines are removed once their operations complete.
+        with_timer(Box::new(FixedTimer(false)), long, check).unwrap();
+        check().unwrap();
+    }
+}
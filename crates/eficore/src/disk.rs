@@ -0,0 +1,146 @@
+use crate::block::BlockDevice;
+use crate::services::services;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use anyhow::{Context, Result};
+use edera_sprout_parsing::disk::{
+    DiskIdentity, parse_ata_identify, parse_gpt_disk_guid, parse_nvme_identify, parse_scsi_inquiry,
+};
+use edera_sprout_parsing::gpt::{
+    GptHeader, GptPartitionEntry, parse_gpt_entries, parse_gpt_header,
+};
+use edera_sprout_parsing::mbr::{Mbr, parse_mbr};
+use uefi::Identify;
+use uefi::boot::{OpenProtocolAttributes, OpenProtocolParams, ScopedProtocol};
+use uefi::proto::ProtocolPointer;
+use uefi::proto::device_path::DevicePath;
+use uefi::proto::media::block::BlockIO;
+use uefi::proto::media::disk_info::{DiskInfo, DiskInfoInterface};
+use uefi::{Guid, Handle};
+
+/// The size of the buffer used for disk identify and inquiry data.
+/// This is large enough for the NVMe Identify Controller data structure.
+const IDENTIFY_BUFFER_SIZE: usize = 4096;
+
+/// The LBA of the MBR on a disk.
+const MBR_LBA: u64 = 0;
+
+/// The LBA of the GPT header on a disk.
+const GPT_HEADER_LBA: u64 = 1;
+
+/// Open the protocol `P` on `handle` without taking exclusive access.
+/// Block devices are held open by the disk and partition drivers, and opening them
+/// exclusively would disconnect those drivers and the filesystems on top of them.
+pub(crate) fn open_shared<P: ProtocolPointer + ?Sized>(
+    handle: Handle,
+) -> uefi::Result<ScopedProtocol<P>> {
+    crate::instrument!("open protocol");
+    // SAFETY: The protocol is only read from, which is safe while other drivers hold it open.
+    unsafe {
+        uefi::boot::open_protocol::<P>(
+            OpenProtocolParams {
+                handle,
+                agent: uefi::boot::image_handle(),
+                controller: None,
+            },
+            OpenProtocolAttributes::GetProtocol,
+        )
+    }
+}
+
+/// Find all the handles of whole physical disks in the UEFI stack.
+/// Partitions and disks without media are not included.
+pub fn physical_disks() -> Result<Vec<Handle>> {
+    let handles = services()
+        .find_handles(&BlockIO::GUID)
+        .context("unable to find block io handles")?;
+
+    let mut disks = Vec::new();
+    for handle in handles {
+        // Skip any handles that we can't open, they can't be inspected anyway.
+        let Ok(block) = open_shared::<BlockIO>(handle) else {
+            continue;
+        };
+
+        let media = block.media();
+        if media.is_logical_partition() || !media.is_media_present() {
+            continue;
+        }
+        disks.push(handle);
+    }
+    Ok(disks)
+}
+
+/// Acquire the device path of the disk `handle`.
+pub fn disk_device_path(handle: Handle) -> Result<Box<DevicePath>> {
+    let path = open_shared::<DevicePath>(handle).context("unable to open disk device path")?;
+    Ok(path.to_boxed())
+}
+
+/// Acquire the [DiskIdentity] of the disk `handle` using the disk info protocol.
+/// If the disk does not support the disk info protocol, an empty identity is returned.
+pub fn disk_identity(handle: Handle) -> Result<DiskIdentity> {
+    // Not all disks provide the disk info protocol, which is not an error.
+    let Ok(info) = open_shared::<DiskInfo>(handle) else {
+        return Ok(DiskIdentity::default());
+    };
+
+    let mut buffer = [0u8; IDENTIFY_BUFFER_SIZE];
+    let identity = match info.interface() {
+        // IDE and AHCI disks report ATA identify data.
+        DiskInfoInterface::IDE | DiskInfoInterface::AHCI => info
+            .identify(&mut buffer)
+            .map(|length| parse_ata_identify(&buffer[..length])),
+        // NVMe disks report the NVMe identify controller data.
+        DiskInfoInterface::NVME => info
+            .identify(&mut buffer)
+            .map(|length| parse_nvme_identify(&buffer[..length])),
+        // SCSI and USB disks report SCSI inquiry data.
+        DiskInfoInterface::SCSI | DiskInfoInterface::USB => info
+            .inquiry(&mut buffer)
+            .map(|length| parse_scsi_inquiry(&buffer[..length])),
+        // Other interfaces do not provide any identity we understand.
+        _ => Ok(DiskIdentity::default()),
+    };
+
+    // The disk might not support the identify or inquiry commands.
+    // This is not fatal, the identity is just unknown.
+    Ok(identity.unwrap_or_default())
+}
+
+/// Read and parse the GPT header and partition entries of the disk `handle`.
+/// Returns [None] if the disk is not partitioned with GPT or the partition table is corrupt.
+pub fn gpt_partitions(handle: Handle) -> Result<Option<(GptHeader, Vec<GptPartitionEntry>)>> {
+    let device = BlockDevice::open(handle)?;
+    let header = device
+        .read_blocks(GPT_HEADER_LBA, 1)
+        .context("unable to read gpt header")?;
+    let Some(header) = parse_gpt_header(&header) else {
+        return Ok(None);
+    };
+
+    // Read every block the partition entry array spans.
+    let count = header.entries_size().div_ceil(device.block_size().max(1));
+    let entries = device
+        .read_blocks(header.entries_lba, count)
+        .context("unable to read gpt partition entries")?;
+    Ok(parse_gpt_entries(&header, &entries).map(|entries| (header, entries)))
+}
+
+/// Read and parse the MBR of the disk `handle`.
+/// Returns [None] if the disk does not have an MBR.
+pub fn mbr(handle: Handle) -> Result<Option<Mbr>> {
+    let sector = BlockDevice::open(handle)?
+        .read_blocks(MBR_LBA, 1)
+        .context("unable to read mbr")?;
+    Ok(parse_mbr(&sector))
+}
+
+/// Acquire the GPT disk GUID of the disk `handle` by reading the GPT header.
+/// Returns [None] if the disk is not partitioned with GPT.
+pub fn disk_guid(handle: Handle) -> Result<Option<Guid>> {
+    let header = BlockDevice::open(handle)?
+        .read_blocks(GPT_HEADER_LBA, 1)
+        .context("unable to read gpt header")?;
+    Ok(parse_gpt_disk_guid(&header).map(Guid::from_bytes))
+}
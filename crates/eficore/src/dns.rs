@@ -0,0 +1,245 @@
+use alloc::format;
+use alloc::vec::Vec;
+use anyhow::{Context, Result, bail};
+use core::ffi::c_void;
+use core::ptr::{self, NonNull};
+use uefi::boot::{EventType, ScopedProtocol, Tpl};
+use uefi::proto::unsafe_protocol;
+use uefi::{CString16, Guid, Handle, StatusExt, guid};
+use uefi_raw::protocol::driver::ServiceBindingProtocol;
+use uefi_raw::{Boolean, Ipv4Address, Status};
+
+/// GUID for the EFI_DNS4_SERVICE_BINDING protocol.
+const DNS4_SERVICE_BINDING_GUID: Guid = guid!("b625b186-e063-44f7-8905-6a74dc6f52b4");
+/// GUID for the EFI_DNS4 protocol.
+const DNS4_GUID: Guid = guid!("ae3d28cc-e05b-4fa1-a011-7eb55a3f1401");
+
+/// The UDP protocol number, which is the transport DNS queries use.
+const PROTOCOL_UDP: u8 = 17;
+
+/// The number of times a DNS query is retried before it fails.
+const RETRY_COUNT: u32 = 3;
+
+/// The interval between retries of a DNS query, in seconds.
+const RETRY_INTERVAL: u32 = 2;
+
+/// EFI_DNS4_SERVICE_BINDING protocol definition.
+#[unsafe_protocol(DNS4_SERVICE_BINDING_GUID)]
+struct Dns4ServiceBinding(ServiceBindingProtocol);
+
+/// EFI_DNS4_CONFIG_DATA definition.
+#[repr(C)]
+struct Dns4ConfigData {
+    /// The number of DNS servers, or zero to use the servers of the interface.
+    dns_server_list_count: usize,
+    /// The DNS servers to query.
+    dns_server_list: *const Ipv4Address,
+    /// Whether to use the address of the interface as the station address.
+    use_default_setting: Boolean,
+    /// Whether to cache the responses.
+    enable_dns_cache: Boolean,
+    /// The transport protocol of the queries.
+    protocol: u8,
+    /// The station address, unless the default setting is used.
+    station_ip: Ipv4Address,
+    /// The subnet mask, unless the default setting is used.
+    subnet_mask: Ipv4Address,
+    /// The local port of the queries, or zero for any port.
+    local_port: u16,
+    /// The number of times a query is retried.
+    retry_count: u32,
+    /// The interval between retries, in seconds.
+    retry_interval: u32,
+}
+
+/// DNS_HOST_TO_ADDR_DATA definition, which is allocated by the DNS driver.
+#[repr(C)]
+struct HostToAddrData {
+    /// The number of addresses.
+    ip_count: u32,
+    /// The addresses, which are allocated by the DNS driver.
+    ip_list: *mut Ipv4Address,
+}
+
+/// EFI_DNS4_COMPLETION_TOKEN definition.
+/// The response data is a union, of which only the host to address data is used.
+#[repr(C)]
+struct Dns4CompletionToken {
+    /// The event that is signaled when the query completes.
+    event: *mut c_void,
+    /// The status of the query, which is not ready until the query completes.
+    status: Status,
+    /// The number of retries, or zero to use the configured number.
+    retry_count: u32,
+    /// The interval between retries, or zero to use the configured interval.
+    retry_interval: u32,
+    /// The response to the query.
+    host_to_addr_data: *mut HostToAddrData,
+}
+
+/// EFI_DNS4 protocol definition.
+#[unsafe_protocol(DNS4_GUID)]
+struct Dns4Protocol {
+    /// Unused function that retrieves the mode data.
+    _get_mode_data: *mut c_void,
+    /// Configures the DNS instance.
+    configure:
+        unsafe extern "efiapi" fn(this: *mut Dns4Protocol, config: *const Dns4ConfigData) -> Status,
+    /// Starts resolving a hostname to its IPv4 addresses.
+    host_name_to_ip: unsafe extern "efiapi" fn(
+        this: *mut Dns4Protocol,
+        host_name: *const u16,
+        token: *mut Dns4CompletionToken,
+    ) -> Status,
+    /// Unused function that resolves an address to its hostname.
+    _ip_to_host_name: *mut c_void,
+    /// Unused function that performs general DNS lookups.
+    _general_look_up: *mut c_void,
+    /// Unused function that updates the DNS cache.
+    _update_dns_cache: *mut c_void,
+    /// Polls the network for incoming responses.
+    poll: unsafe extern "efiapi" fn(this: *mut Dns4Protocol) -> Status,
+    /// Cancels a pending query.
+    cancel: unsafe extern "efiapi" fn(
+        this: *mut Dns4Protocol,
+        token: *mut Dns4CompletionToken,
+    ) -> Status,
+}
+
+/// A child DNS instance of a network interface, which is destroyed when dropped.
+struct Dns4Instance {
+    /// The service binding that created the instance.
+    binding: ScopedProtocol<Dns4ServiceBinding>,
+    /// The handle of the instance.
+    child: Handle,
+    /// The DNS protocol of the instance, which is closed before the instance is destroyed.
+    protocol: Option<ScopedProtocol<Dns4Protocol>>,
+}
+
+impl Dns4Instance {
+    /// Creates a DNS instance on the network interface on `interface`.
+    fn new(interface: Handle) -> Result<Self> {
+        let mut binding = crate::disk::open_shared::<Dns4ServiceBinding>(interface)
+            .context("network interface does not support dns")?;
+
+        let mut child = ptr::null_mut();
+        // SAFETY: The binding is a valid service binding protocol.
+        let status = unsafe { (binding.0.create_child)(&mut binding.0, &mut child) };
+        status
+            .to_result()
+            .context("unable to create dns instance")?;
+        // SAFETY: The handle was just created by the binding.
+        let child = unsafe { Handle::from_ptr(child) }.context("dns instance has no handle")?;
+        let mut instance = Self {
+            binding,
+            child,
+            protocol: None,
+        };
+
+        // The protocol is closed before the child is destroyed on drop.
+        let protocol = crate::disk::open_shared::<Dns4Protocol>(child)
+            .context("unable to open dns protocol")?;
+        instance.protocol = Some(protocol);
+        Ok(instance)
+    }
+
+    /// Accesses the DNS protocol of the instance.
+    fn protocol(&mut self) -> &mut Dns4Protocol {
+        self.protocol.as_mut().expect("dns protocol is open")
+    }
+}
+
+impl Drop for Dns4Instance {
+    fn drop(&mut self) {
+        // The protocol must be closed before the child that provides it is destroyed.
+        self.protocol = None;
+        // SAFETY: The child was created by this binding.
+        let _ = unsafe { (self.binding.0.destroy_child)(&mut self.binding.0, self.child.as_ptr()) };
+    }
+}
+
+/// Resolves the `host` name to its IPv4 addresses with the firmware DNS protocol of the
+/// network interface on `interface`. The DNS servers are the ones provided by DHCP,
+/// so the interface must be configured first.
+pub fn resolve(interface: Handle, host: &str) -> Result<Vec<Ipv4Address>> {
+    let mut instance = Dns4Instance::new(interface)?;
+    let dns = instance.protocol();
+
+    // The default settings use the address and the DNS servers of the interface.
+    let config = Dns4ConfigData {
+        dns_server_list_count: 0,
+        dns_server_list: ptr::null(),
+        use_default_setting: Boolean::TRUE,
+        enable_dns_cache: Boolean::TRUE,
+        protocol: PROTOCOL_UDP,
+        station_ip: Ipv4Address([0; 4]),
+        subnet_mask: Ipv4Address([0; 4]),
+        local_port: 0,
+        retry_count: RETRY_COUNT,
+        retry_interval: RETRY_INTERVAL,
+    };
+    // SAFETY: The config data is valid for the duration of the call.
+    unsafe { (dns.configure)(dns, &config) }
+        .to_result()
+        .context("unable to configure dns")?;
+
+    // The DNS driver requires an event, which is signaled once the query completes.
+    // SAFETY: The event has no notification function, so it can not be misused.
+    let event = unsafe { uefi::boot::create_event(EventType::empty(), Tpl::CALLBACK, None, None) }
+        .context("unable to create dns event")?;
+    let mut token = Dns4CompletionToken {
+        event: event.as_ptr(),
+        status: Status::NOT_READY,
+        retry_count: 0,
+        retry_interval: 0,
+        host_to_addr_data: ptr::null_mut(),
+    };
+
+    let name = CString16::try_from(host).context("unable to convert hostname")?;
+    // SAFETY: The name and the token outlive the query, which completes or is cancelled below.
+    let result = unsafe { (dns.host_name_to_ip)(dns, name.as_ptr().cast(), &mut token) }
+        .to_result()
+        .context(format!("unable to resolve {}", host))
+        .and_then(|_| {
+            // The query completes once the driver updates the status of the token.
+            // SAFETY: The token is only read after the driver writes it during the poll.
+            while unsafe { ptr::read_volatile(&token.status) } == Status::NOT_READY {
+                if let Err(error) = crate::deadline::check() {
+                    let _ = unsafe { (dns.cancel)(dns, &mut token) };
+                    return Err(error).context(format!("unable to resolve {}", host));
+                }
+                let _ = unsafe { (dns.poll)(dns) };
+            }
+            Ok(())
+        });
+    let _ = uefi::boot::close_event(event);
+    result?;
+    token
+        .status
+        .to_result()
+        .context(format!("unable to resolve {}", host))?;
+
+    // The response is allocated by the driver, so it is copied and freed.
+    let Some(data) = NonNull::new(token.host_to_addr_data) else {
+        bail!("dns returned no addresses for {}", host);
+    };
+    // SAFETY: The driver returns a valid response with `ip_count` addresses on success.
+    let addresses = unsafe {
+        let data = data.as_ref();
+        let addresses = match NonNull::new(data.ip_list) {
+            Some(list) => {
+                let addresses =
+                    core::slice::from_raw_parts(list.as_ptr(), data.ip_count as usize).to_vec();
+                let _ = uefi::boot::free_pool(list.cast());
+                addresses
+            }
+            None => Vec::new(),
+        };
+        let _ = uefi::boot::free_pool(NonNull::from(data).cast());
+        addresses
+    };
+    if addresses.is_empty() {
+        bail!("dns returned no addresses for {}", host);
+    }
+    Ok(addresses)
+}
@@ -1,6 +1,7 @@
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use anyhow::{Context, Result, bail};
+use edera_sprout_parsing::options::{split_args, strip_firmware_args};
 use uefi::proto::loaded_image::{LoadOptionsError, LoadedImage};
 
 /// Loads the command-line arguments passed to the current image.
@@ -34,36 +35,6 @@ pub fn args() -> Result<Vec<String>> {
     // Convert the options to a string.
     let options = options.to_string();
 
-    // Use shlex to parse the options.
-    // If shlex fails, we will perform a simple whitespace split.
-    let mut args = shlex::split(&options).unwrap_or_else(|| {
-        options
-            .split_ascii_whitespace()
-            .map(|string| string.to_string())
-            .collect::<Vec<_>>()
-    });
-
-    // Correct firmware that may add invalid arguments at the start.
-    // Witnessed this on a Dell Precision 5690 when direct booting.
-    args = args
-        .into_iter()
-        .skip_while(|arg| {
-            arg.chars()
-                .next()
-                // Filter out unprintable characters and backticks.
-                // Both of which have been observed in the wild.
-                .map(|c| c < 0x1f as char || c == '`')
-                .unwrap_or(false)
-        })
-        .collect();
-
-    // If there is a first argument, check if it is not an option.
-    // If it is not, we will assume it is the path to the executable and remove it.
-    if let Some(arg) = args.first()
-        && !arg.starts_with('-')
-    {
-        args.remove(0);
-    }
-
-    Ok(args)
+    // Split the options into arguments, removing what firmware adds in front of them.
+    Ok(strip_firmware_args(split_args(&options)))
 }
@@ -0,0 +1,50 @@
+use crate::pe::{self, NATIVE_MACHINE};
+use crate::services::services;
+use anyhow::{Context, Result, bail};
+use edera_sprout_parsing::path::normalize;
+use log::info;
+use uefi::proto::device_path::DevicePath;
+
+/// Copies the Sprout image at the device `image_path` to the removable media boot path of the
+/// ESP, like `\EFI\BOOT\BOOTX64.EFI`, or to `destination` if it is specified.
+/// This keeps the path that firmware boots without a boot option in sync with Sprout.
+///
+/// The copy is skipped when Sprout is running from the destination or the destination already
+/// has the same contents. Returns whether the destination was written.
+pub fn install(image_path: &DevicePath, destination: Option<&str>) -> Result<bool> {
+    let source = crate::path::device_path_subpath(image_path)
+        .context("unable to determine the path of the sprout image")?;
+    let destination = match destination {
+        Some(destination) => destination.into(),
+        None => match NATIVE_MACHINE.removable_media_path() {
+            Some(destination) => destination,
+            None => bail!("removable media path is not known for this architecture"),
+        },
+    };
+
+    // Paths on the ESP are case-insensitive, as it is a FAT filesystem.
+    if normalize(&source).eq_ignore_ascii_case(&normalize(&destination)) {
+        info!("sprout is running from the fallback path {}", destination);
+        return Ok(false);
+    }
+
+    let image = services()
+        .read_file(Some(image_path), &source)
+        .context("unable to read the sprout image")?;
+    if !pe::is_native(&pe::parse(&image)?) {
+        bail!("sprout image is not for the current architecture");
+    }
+
+    // A destination that can't be read is treated as missing, so a broken copy is replaced.
+    let existing = services().read_file(Some(image_path), &destination).ok();
+    if existing.as_deref() == Some(image.as_slice()) {
+        info!("fallback path {} is up to date", destination);
+        return Ok(false);
+    }
+
+    info!("installing sprout to the fallback path {}", destination);
+    services()
+        .write_file_atomic(Some(image_path), &destination, &image)
+        .context("unable to write the sprout image to the fallback path")?;
+    Ok(true)
+}
@@ -0,0 +1,104 @@
+use crate::services::services;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use anyhow::{Context, Result};
+use core::ops::Deref;
+use uefi::Handle;
+use uefi::Identify;
+use uefi::fs::FileSystem;
+use uefi::proto::device_path::DevicePath;
+use uefi::proto::media::file::{File, FileSystemVolumeLabel};
+use uefi::proto::media::fs::SimpleFileSystem;
+
+/// A filesystem that was accessed during a [FilesystemScan].
+/// Each property is acquired the first time it is used, then shared for the rest of the scan.
+#[derive(Default)]
+struct ScannedFilesystem {
+    /// The device path of the filesystem.
+    root: Option<Box<DevicePath>>,
+    /// The filesystem, which is held open exclusively until the scan is dropped.
+    filesystem: Option<FileSystem>,
+    /// The volume label of the filesystem, if it could be read when the filesystem was opened.
+    label: Option<String>,
+}
+
+/// A scan pass over the filesystems of the system.
+/// The filesystem handles are found once, and each filesystem is opened at most once
+/// and shared by every user of the scan, instead of opening it over and over.
+///
+/// Opened filesystems are held open exclusively until the scan is dropped,
+/// so the scan must be dropped before the filesystems are accessed by other means,
+/// like [crate::path::ResolvedPath::read_file].
+pub struct FilesystemScan {
+    /// The handles of the filesystems, in the order the firmware reported them.
+    handles: Vec<Handle>,
+    /// The filesystems accessed during the scan, keyed by their handle.
+    scanned: BTreeMap<Handle, ScannedFilesystem>,
+}
+
+impl FilesystemScan {
+    /// Starts a scan pass over the filesystems that are currently on the system.
+    pub fn new() -> Result<Self> {
+        let handles = services()
+            .find_handles(&SimpleFileSystem::GUID)
+            .context("unable to find filesystem handles")?;
+        Ok(Self {
+            handles,
+            scanned: BTreeMap::new(),
+        })
+    }
+
+    /// Acquires the handles of the filesystems in the scan.
+    pub fn handles(&self) -> Vec<Handle> {
+        self.handles.clone()
+    }
+
+    /// Acquires the device path of the filesystem on `handle`.
+    pub fn root(&mut self, handle: Handle) -> Result<&DevicePath> {
+        let scanned = self.scanned.entry(handle).or_default();
+        if scanned.root.is_none() {
+            let root = uefi::boot::open_protocol_exclusive::<DevicePath>(handle)
+                .context("unable to fetch the device path of the filesystem")?
+                .deref()
+                .to_boxed();
+            scanned.root = Some(root);
+        }
+        scanned
+            .root
+            .as_deref()
+            .context("filesystem device path is missing")
+    }
+
+    /// Acquires the filesystem on `handle`, opening it if it is not open yet.
+    pub fn filesystem(&mut self, handle: Handle) -> Result<&mut FileSystem> {
+        let scanned = self.scanned.entry(handle).or_default();
+        if scanned.filesystem.is_none() {
+            let mut protocol = crate::instrument!(
+                "open filesystem",
+                uefi::boot::open_protocol_exclusive::<SimpleFileSystem>(handle)
+            )
+            .context("unable to open filesystem protocol")?;
+
+            // The volume label can only be read from the protocol before it is wrapped,
+            // so it is read right away.
+            scanned.label = protocol
+                .open_volume()
+                .ok()
+                .and_then(|mut volume| volume.get_boxed_info::<FileSystemVolumeLabel>().ok())
+                .map(|label| label.volume_label().to_string());
+            scanned.filesystem = Some(FileSystem::new(protocol));
+        }
+        scanned.filesystem.as_mut().context("filesystem is missing")
+    }
+
+    /// Acquires the volume label of the filesystem on `handle`, opening it if it is not open yet.
+    pub fn label(&mut self, handle: Handle) -> Result<String> {
+        self.filesystem(handle)?;
+        self.scanned
+            .get(&handle)
+            .and_then(|scanned| scanned.label.clone())
+            .context("unable to get filesystem volume label")
+    }
+}
@@ -3,14 +3,66 @@ use alloc::vec::Vec;
 use anyhow::{Context, Result};
 use uefi::proto::console::gop::{BltOp, BltPixel, BltRegion, GraphicsOutput};
 
+/// font: Bitmap font used to render text to the framebuffer.
+pub mod font;
+
+/// region: Rectangular regions of the framebuffer.
+pub mod region;
+
+pub use region::Region;
+
+/// The byte layout of pixel data copied into the framebuffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// Three bytes per pixel in red, green, blue order.
+    Rgb8,
+    /// Four bytes per pixel in red, green, blue, alpha order. The alpha is ignored.
+    Rgba8,
+    /// Four bytes per pixel in blue, green, red, reserved order.
+    /// This matches the layout of the firmware framebuffer, so no conversion is needed.
+    Bgrx8,
+}
+
+impl PixelFormat {
+    /// The number of bytes used by a single pixel in this format.
+    pub fn bytes_per_pixel(&self) -> usize {
+        match self {
+            PixelFormat::Rgb8 => 3,
+            PixelFormat::Rgba8 | PixelFormat::Bgrx8 => 4,
+        }
+    }
+
+    /// Converts a single pixel in this format, given as `bytes`, to a [BltPixel].
+    fn convert(&self, bytes: &[u8]) -> BltPixel {
+        match self {
+            PixelFormat::Rgb8 | PixelFormat::Rgba8 => BltPixel::new(bytes[0], bytes[1], bytes[2]),
+            PixelFormat::Bgrx8 => BltPixel::new(bytes[2], bytes[1], bytes[0]),
+        }
+    }
+}
+
+/// The size of the BMP file header and the BITMAPINFOHEADER that follows it.
+const BMP_HEADER_SIZE: usize = 14 + 40;
+
+/// The horizontal space between glyphs, in unscaled pixels.
+const GLYPH_SPACING: usize = 1;
+
+/// The maximum number of separate dirty regions tracked before they are
+/// collapsed into a single region that covers all of them.
+const MAX_DIRTY_REGIONS: usize = 16;
+
 /// Represents the EFI framebuffer.
+/// Drawing happens in a back buffer, and only the regions that changed since
+/// the last blit are pushed to the screen.
 pub struct Framebuffer {
     /// The width of the framebuffer in pixels.
     width: usize,
     /// The height of the framebuffer in pixels.
     height: usize,
-    /// The pixels of the framebuffer.
+    /// The pixels of the back buffer.
     pixels: Vec<BltPixel>,
+    /// The regions of the back buffer that changed since the last blit.
+    dirty: Vec<Region>,
 }
 
 impl Framebuffer {
@@ -24,15 +76,39 @@ impl Framebuffer {
         // Initialize the pixel buffer with black pixels, with the verified size.
         let pixels = vec![BltPixel::new(0, 0, 0); size];
 
-        Ok(Framebuffer {
+        // The screen contents are unknown, so the whole framebuffer starts dirty.
+        let mut framebuffer = Framebuffer {
             width,
             height,
             pixels,
+            dirty: Vec::new(),
+        };
+        framebuffer.invalidate();
+        Ok(framebuffer)
+    }
+
+    /// Captures the current contents of the screen of the specified `gop` [GraphicsOutput].
+    pub fn capture(gop: &mut GraphicsOutput) -> Result<Self> {
+        let (width, height) = gop.current_mode_info().resolution();
+        let mut framebuffer = Self::new(width, height)?;
+        gop.blt(BltOp::VideoToBltBuffer {
+            buffer: &mut framebuffer.pixels,
+            src: (0, 0),
+            dest: BltRegion::Full,
+            dims: (width, height),
         })
+        .context("unable to capture framebuffer")?;
+        // The back buffer now matches the screen.
+        framebuffer.dirty.clear();
+        Ok(framebuffer)
     }
 
     /// Mutably acquires a pixel of the framebuffer at the specified `x` and `y` coordinate.
+    /// The pixel is marked dirty, as the caller is expected to change it.
+    /// Prefer [Framebuffer::row] or [Framebuffer::write_row] when drawing many pixels.
     pub fn pixel(&mut self, x: usize, y: usize) -> Option<&mut BltPixel> {
+        self.mark_dirty(Region::new(x, y, 1, 1));
+
         // Verify that the coordinates are within the bounds of the framebuffer.
         if x >= self.width || y >= self.height {
             return None;
@@ -44,15 +120,239 @@ impl Framebuffer {
         self.pixels.get_mut(index)
     }
 
-    /// Blit the framebuffer to the specified `gop` [GraphicsOutput].
-    pub fn blit(&self, gop: &mut GraphicsOutput) -> Result<()> {
-        gop.blt(BltOp::BufferToVideo {
-            buffer: &self.pixels,
-            src: BltRegion::Full,
-            dest: (0, 0),
-            dims: (self.width, self.height),
-        })
-        .context("unable to blit framebuffer")?;
+    /// Acquires the width of the framebuffer in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Acquires the height of the framebuffer in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Sets every pixel of the framebuffer to `color`.
+    pub fn fill(&mut self, color: BltPixel) {
+        self.pixels.fill(color);
+        self.invalidate();
+    }
+
+    /// Sets every pixel within the specified `region` to `color`.
+    /// The part of the region outside the framebuffer is ignored.
+    pub fn fill_region(&mut self, region: Region, color: BltPixel) {
+        self.fill_unmarked(region, color);
+        self.mark_dirty(region);
+    }
+
+    /// Sets every pixel within the specified `region` to `color`, without marking it dirty.
+    /// Callers must mark the pixels they change themselves.
+    fn fill_unmarked(&mut self, region: Region, color: BltPixel) {
+        let region = region.clip(self.width, self.height);
+        if region.is_empty() {
+            return;
+        }
+
+        for row in region.y..region.y + region.height {
+            let start = row * self.width + region.x;
+            self.pixels[start..start + region.width].fill(color);
+        }
+    }
+
+    /// Mutably acquires the pixels of row `y`, starting at `x`, up to `length` pixels long.
+    /// The row is clipped to the framebuffer and marked dirty.
+    /// Returns None if the start of the row is outside the framebuffer.
+    pub fn row(&mut self, x: usize, y: usize, length: usize) -> Option<&mut [BltPixel]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        let length = length.min(self.width - x);
+        self.mark_dirty(Region::new(x, y, length, 1));
+        let start = y * self.width + x;
+        Some(&mut self.pixels[start..start + length])
+    }
+
+    /// Copies the `pixels` into row `y`, starting at `x`.
+    /// Pixels that do not fit within the framebuffer are clipped.
+    pub fn write_row(&mut self, x: usize, y: usize, pixels: &[BltPixel]) {
+        if let Some(row) = self.row(x, y, pixels.len()) {
+            let length = row.len();
+            row.copy_from_slice(&pixels[..length]);
+        }
+    }
+
+    /// Converts the `bytes` of pixel data in the specified `format` into row `y`, starting at `x`.
+    /// Pixels that do not fit within the framebuffer are clipped, as is a trailing partial pixel.
+    pub fn write_row_bytes(&mut self, x: usize, y: usize, bytes: &[u8], format: PixelFormat) {
+        let size = format.bytes_per_pixel();
+        if let Some(row) = self.row(x, y, bytes.len() / size) {
+            for (pixel, bytes) in row.iter_mut().zip(bytes.chunks_exact(size)) {
+                *pixel = format.convert(bytes);
+            }
+        }
+    }
+
+    /// Draws an image of the specified `width` with its top left corner at `x` and `y`.
+    /// The `bytes` of the image are rows of pixel data in the specified `format`, top to bottom,
+    /// with no padding between rows. The image is clipped to the framebuffer.
+    pub fn draw_image(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        bytes: &[u8],
+        format: PixelFormat,
+    ) {
+        let stride = width.saturating_mul(format.bytes_per_pixel());
+        if stride == 0 {
+            return;
+        }
+
+        for (index, row) in bytes.chunks_exact(stride).enumerate() {
+            let row_y = y.saturating_add(index);
+            if row_y >= self.height {
+                break;
+            }
+            self.write_row_bytes(x, row_y, row, format);
+        }
+    }
+
+    /// Marks the specified `region` as changed, so it is pushed to the screen on the next blit.
+    /// Regions that touch an already dirty region are merged with it.
+    pub fn mark_dirty(&mut self, region: Region) {
+        let mut region = region.clip(self.width, self.height);
+        if region.is_empty() {
+            return;
+        }
+
+        // Merge with every dirty region this one touches. Merging can grow the region
+        // into others, so keep going until nothing else touches it.
+        while let Some(index) = self.dirty.iter().position(|it| it.touches(&region)) {
+            region = region.union(&self.dirty.swap_remove(index));
+        }
+        self.dirty.push(region);
+
+        // Too many small regions make blitting slower than a single larger one.
+        if self.dirty.len() > MAX_DIRTY_REGIONS {
+            let bounds = self
+                .dirty
+                .drain(..)
+                .fold(region, |bounds, it| bounds.union(&it));
+            self.dirty.push(bounds);
+        }
+    }
+
+    /// Marks the entire framebuffer as changed, so it is pushed to the screen on the next blit.
+    pub fn invalidate(&mut self) {
+        self.dirty.clear();
+        self.mark_dirty(Region::new(0, 0, self.width, self.height));
+    }
+
+    /// Checks whether any part of the framebuffer changed since the last blit.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Acquires the size of a character cell of text drawn at the specified `scale`,
+    /// as a tuple of the width and height in pixels. This includes the space between glyphs.
+    pub fn cell_size(scale: usize) -> (usize, usize) {
+        (
+            (font::GLYPH_WIDTH + GLYPH_SPACING) * scale,
+            (font::GLYPH_HEIGHT + GLYPH_SPACING) * scale,
+        )
+    }
+
+    /// Draws `text` with its top left corner at the specified `x` and `y` coordinate.
+    /// Each pixel of the font is drawn as a square of `scale` pixels in the specified `color`.
+    /// Text that does not fit within the framebuffer is clipped.
+    pub fn draw_text(&mut self, x: usize, y: usize, text: &str, scale: usize, color: BltPixel) {
+        let (cell_width, cell_height) = Self::cell_size(scale);
+        self.mark_dirty(Region::new(
+            x,
+            y,
+            cell_width.saturating_mul(text.chars().count()),
+            cell_height,
+        ));
+        for (index, c) in text.chars().enumerate() {
+            let glyph = font::glyph(c);
+            let left = x + index * cell_width;
+            for (row, bits) in glyph.iter().enumerate() {
+                for column in 0..font::GLYPH_WIDTH {
+                    if bits & (1 << (font::GLYPH_WIDTH - 1 - column)) == 0 {
+                        continue;
+                    }
+
+                    // Draw the font pixel as a square of pixels, one row at a time.
+                    self.fill_unmarked(
+                        Region::new(left + column * scale, y + row * scale, scale, scale),
+                        color,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Encodes the framebuffer as an uncompressed 24-bit BMP image.
+    pub fn to_bmp(&self) -> Result<Vec<u8>> {
+        // Rows of a BMP image are padded to a multiple of 4 bytes.
+        let row_size = (self.width * 3).div_ceil(4) * 4;
+        let image_size = row_size
+            .checked_mul(self.height)
+            .context("bmp image size overflow")?;
+        let file_size =
+            u32::try_from(BMP_HEADER_SIZE + image_size).context("bmp image too large")?;
+        let width = i32::try_from(self.width).context("bmp image too wide")?;
+        let height = i32::try_from(self.height).context("bmp image too tall")?;
+
+        let mut bmp = Vec::with_capacity(file_size as usize);
+        // The file header.
+        bmp.extend_from_slice(b"BM");
+        bmp.extend_from_slice(&file_size.to_le_bytes());
+        bmp.extend_from_slice(&0u32.to_le_bytes());
+        bmp.extend_from_slice(&(BMP_HEADER_SIZE as u32).to_le_bytes());
+        // The BITMAPINFOHEADER.
+        bmp.extend_from_slice(&40u32.to_le_bytes());
+        bmp.extend_from_slice(&width.to_le_bytes());
+        bmp.extend_from_slice(&height.to_le_bytes());
+        bmp.extend_from_slice(&1u16.to_le_bytes());
+        bmp.extend_from_slice(&24u16.to_le_bytes());
+        bmp.extend_from_slice(&0u32.to_le_bytes());
+        bmp.extend_from_slice(&(image_size as u32).to_le_bytes());
+        bmp.extend_from_slice(&0u32.to_le_bytes());
+        bmp.extend_from_slice(&0u32.to_le_bytes());
+        bmp.extend_from_slice(&0u32.to_le_bytes());
+        bmp.extend_from_slice(&0u32.to_le_bytes());
+
+        // The rows are stored from the bottom of the image to the top.
+        let padding = row_size - self.width * 3;
+        for row in self.pixels.chunks(self.width.max(1)).rev() {
+            for pixel in row {
+                bmp.extend_from_slice(&[pixel.blue, pixel.green, pixel.red]);
+            }
+            bmp.extend(core::iter::repeat_n(0u8, padding));
+        }
+        Ok(bmp)
+    }
+
+    /// Blit the regions of the framebuffer that changed since the last blit
+    /// to the specified `gop` [GraphicsOutput].
+    pub fn blit(&mut self, gop: &mut GraphicsOutput) -> Result<()> {
+        while let Some(region) = self.dirty.pop() {
+            let result = gop.blt(BltOp::BufferToVideo {
+                buffer: &self.pixels,
+                src: BltRegion::SubRectangle {
+                    coords: (region.x, region.y),
+                    px_stride: self.width,
+                },
+                dest: (region.x, region.y),
+                dims: (region.width, region.height),
+            });
+
+            // Keep the region dirty if it failed to blit, so it can be retried.
+            if result.is_err() {
+                self.dirty.push(region);
+            }
+            result.context("unable to blit framebuffer")?;
+        }
         Ok(())
     }
 }
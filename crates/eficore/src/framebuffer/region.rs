@@ -0,0 +1,81 @@
+/// A rectangular region of the framebuffer, in pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Region {
+    /// The x coordinate of the left edge of the region.
+    pub x: usize,
+    /// The y coordinate of the top edge of the region.
+    pub y: usize,
+    /// The width of the region in pixels.
+    pub width: usize,
+    /// The height of the region in pixels.
+    pub height: usize,
+}
+
+impl Region {
+    /// Creates a new region with its top left corner at `x` and `y` of the specified size.
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Checks whether the region covers no pixels.
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// The x coordinate one past the right edge of the region.
+    fn right(&self) -> usize {
+        self.x.saturating_add(self.width)
+    }
+
+    /// The y coordinate one past the bottom edge of the region.
+    fn bottom(&self) -> usize {
+        self.y.saturating_add(self.height)
+    }
+
+    /// Clips the region to a surface of the specified `width` and `height`.
+    /// The resulting region may be empty.
+    pub fn clip(&self, width: usize, height: usize) -> Region {
+        let x = self.x.min(width);
+        let y = self.y.min(height);
+        Region::new(
+            x,
+            y,
+            self.right().min(width) - x,
+            self.bottom().min(height) - y,
+        )
+    }
+
+    /// Checks whether this region overlaps or directly borders the `other` region.
+    /// Touching regions are merged so that adjacent drawing produces a single blit.
+    pub fn touches(&self, other: &Region) -> bool {
+        self.x <= other.right()
+            && other.x <= self.right()
+            && self.y <= other.bottom()
+            && other.y <= self.bottom()
+    }
+
+    /// Computes the smallest region that contains both this region and the `other` region.
+    pub fn union(&self, other: &Region) -> Region {
+        if self.is_empty() {
+            return *other;
+        }
+
+        if other.is_empty() {
+            return *self;
+        }
+
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Region::new(
+            x,
+            y,
+            self.right().max(other.right()) - x,
+            self.bottom().max(other.bottom()) - y,
+        )
+    }
+}
@@ -0,0 +1,827 @@
+use crate::block::BlockDevice;
+use crate::pages::PageBuffer;
+use crate::services::services;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use anyhow::{Context, Result, anyhow};
+use core::ffi::c_void;
+use core::mem::{offset_of, size_of};
+use core::ptr;
+use edera_sprout_parsing::btrfs::{self, Btrfs};
+use edera_sprout_parsing::datetime::DateTime;
+use edera_sprout_parsing::ext4::{self, Ext4};
+use edera_sprout_parsing::filesystem::{DirectoryEntry, NodeKind, ReadOnlyFilesystem};
+use edera_sprout_parsing::iso9660::{self, Iso9660};
+use edera_sprout_parsing::squashfs::Squashfs;
+use log::{info, warn};
+use spin::Mutex;
+use uefi::proto::device_path::DevicePath;
+use uefi::proto::device_path::build::DevicePathBuilder;
+use uefi::proto::device_path::build::hardware::Vendor;
+use uefi::proto::device_path::build::media::CdRom;
+use uefi::proto::media::block::BlockIO;
+use uefi::{CStr16, Guid, Handle, Identify, guid};
+use uefi_raw::protocol::block::{BlockIoMedia, BlockIoProtocol};
+use uefi_raw::protocol::device_path::DevicePathProtocol;
+use uefi_raw::protocol::file_system::{
+    FileAttribute, FileInfo, FileMode, FileProtocolRevision, FileProtocolV1, FileSystemInfo,
+    FileSystemVolumeLabel, SimpleFileSystemProtocol,
+};
+use uefi_raw::time::{Daylight, Time};
+use uefi_raw::{Boolean, Char16, Status};
+
+/// The revision of the simple filesystem protocol that is provided.
+const SIMPLE_FILE_SYSTEM_REVISION: u64 = 0x00010000;
+
+/// The revision of the block io protocol that is provided for El Torito boot images.
+const BLOCK_IO_REVISION: u64 = 0x00010000;
+
+/// The size of the blocks of El Torito boot images, which are counted in virtual sectors.
+const BOOT_IMAGE_BLOCK_SIZE: u32 = 512;
+
+/// The size of the sectors of CD media and ISO9660 filesystems.
+const SECTOR_SIZE: u64 = 2048;
+
+/// The vendor of the device path nodes of squashfs containers, whose data is their name.
+const SQUASHFS_VENDOR_GUID: Guid = guid!("3d0f7c52-6a1e-4b8d-9f27-c51e8a04b6d3");
+
+/// The device paths of the squashfs containers that were mounted, keyed by their name.
+/// Sprout runs again when the boot menu rescans for entries, and the containers stay mounted.
+static SQUASHFS_ROOTS: Mutex<BTreeMap<String, &'static DevicePath>> = Mutex::new(BTreeMap::new());
+
+/// The result of opening a filesystem of a block device.
+type OpenResult = Result<Box<dyn ReadOnlyFilesystem>, String>;
+
+/// A filesystem that is provided with the simple filesystem protocol.
+/// The protocol is the first field, so the protocol pointer is also a volume pointer.
+#[repr(C)]
+struct Volume {
+    /// The simple filesystem protocol that is installed on the partition.
+    protocol: SimpleFileSystemProtocol,
+    /// The filesystem of the partition.
+    filesystem: Box<dyn ReadOnlyFilesystem>,
+}
+
+/// A file or directory opened on a [Volume].
+/// The protocol is the first field, so the protocol pointer is also an open file pointer.
+#[repr(C)]
+struct OpenFile {
+    /// The file protocol that is handed to the caller.
+    protocol: FileProtocolV1,
+    /// The volume of the file, which is never uninstalled.
+    volume: *const Volume,
+    /// The entries from the root down to the file.
+    stack: Vec<DirectoryEntry>,
+    /// The byte position of a file, or the index of the next entry of a directory.
+    position: u64,
+    /// The entries of a directory, which are listed on the first read.
+    entries: Option<Vec<DirectoryEntry>>,
+}
+
+impl OpenFile {
+    /// Opens the file at the end of the `stack` of entries on the `volume`.
+    fn new(volume: *const Volume, stack: Vec<DirectoryEntry>) -> Box<Self> {
+        Box::new(Self {
+            protocol: FileProtocolV1 {
+                revision: FileProtocolRevision::REVISION_1,
+                open: Self::open,
+                close: Self::close,
+                delete: Self::delete,
+                read: Self::read,
+                write: Self::write,
+                get_position: Self::get_position,
+                set_position: Self::set_position,
+                get_info: Self::get_info,
+                set_info: Self::set_info,
+                flush: Self::flush,
+            },
+            volume,
+            stack,
+            position: 0,
+            entries: None,
+        })
+    }
+
+    /// Acquires the filesystem of the file.
+    fn filesystem(&self) -> &dyn ReadOnlyFilesystem {
+        // SAFETY: Volumes are leaked when installed and never freed.
+        unsafe { &*(*self.volume).filesystem }
+    }
+
+    /// Acquires the entry of the file itself.
+    fn entry(&self) -> &DirectoryEntry {
+        self.stack.last().expect("open file has an entry")
+    }
+
+    /// Whether the file is a directory.
+    fn is_directory(&self) -> bool {
+        self.entry().node.kind == NodeKind::Directory
+    }
+
+    /// Lists the entries of the directory. Symbolic links are listed as their targets,
+    /// and links that can not be resolved and other kinds of nodes are left out.
+    fn list(&self) -> Result<Vec<DirectoryEntry>, String> {
+        let filesystem = self.filesystem();
+        let mut entries = Vec::new();
+        for entry in filesystem.read_dir(&self.entry().node)? {
+            let node = match entry.node.kind {
+                NodeKind::File | NodeKind::Directory => entry.node,
+                NodeKind::Symlink => match filesystem.resolve(&self.stack, &entry.name) {
+                    Ok(Some(mut target)) => match target.pop() {
+                        Some(target) => target.node,
+                        None => continue,
+                    },
+                    _ => continue,
+                },
+                NodeKind::Other => continue,
+            };
+            entries.push(DirectoryEntry {
+                name: entry.name,
+                node,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Opens the file at the path relative to `this`, which must not be opened for writing.
+    unsafe extern "efiapi" fn open(
+        this: *mut FileProtocolV1,
+        new_handle: *mut *mut FileProtocolV1,
+        file_name: *const Char16,
+        open_mode: FileMode,
+        _attributes: FileAttribute,
+    ) -> Status {
+        if this.is_null() || new_handle.is_null() || file_name.is_null() {
+            return Status::INVALID_PARAMETER;
+        }
+        if open_mode.intersects(FileMode::WRITE | FileMode::CREATE) {
+            return Status::WRITE_PROTECTED;
+        }
+
+        // SAFETY: The protocol is the first field of an open file, and the name is a
+        // null-terminated string, as required of the caller.
+        let (file, path) = unsafe {
+            let file = &*(this as *const OpenFile);
+            let path = CStr16::from_ptr(file_name.cast()).to_string();
+            (file, path)
+        };
+
+        // Paths are relative to a directory, or to the directory of a file.
+        let base = if file.is_directory() {
+            &file.stack[..]
+        } else {
+            &file.stack[..file.stack.len() - 1]
+        };
+        match file.filesystem().resolve(base, &path) {
+            Ok(Some(stack)) => {
+                let opened = Box::into_raw(Self::new(file.volume, stack));
+                // SAFETY: The handle pointer was checked to be non-null.
+                unsafe { *new_handle = opened.cast() };
+                Status::SUCCESS
+            }
+            Ok(None) => Status::NOT_FOUND,
+            Err(error) => {
+                warn!("unable to open {}: {}", path, error);
+                Status::DEVICE_ERROR
+            }
+        }
+    }
+
+    /// Closes the file, which frees it.
+    unsafe extern "efiapi" fn close(this: *mut FileProtocolV1) -> Status {
+        if this.is_null() {
+            return Status::INVALID_PARAMETER;
+        }
+        // SAFETY: Open files are leaked boxes, which the caller no longer uses once closed.
+        drop(unsafe { Box::from_raw(this as *mut OpenFile) });
+        Status::SUCCESS
+    }
+
+    /// Closes the file without deleting it, as the filesystem is read-only.
+    unsafe extern "efiapi" fn delete(this: *mut FileProtocolV1) -> Status {
+        // SAFETY: The caller passes a file, as required of [OpenFile::close].
+        let _ = unsafe { Self::close(this) };
+        Status::WARN_DELETE_FAILURE
+    }
+
+    /// Reads the contents of a file, or the next entry of a directory as a [FileInfo].
+    unsafe extern "efiapi" fn read(
+        this: *mut FileProtocolV1,
+        buffer_size: *mut usize,
+        buffer: *mut c_void,
+    ) -> Status {
+        if this.is_null() || buffer_size.is_null() {
+            return Status::INVALID_PARAMETER;
+        }
+        // SAFETY: The protocol is the first field of an open file.
+        let file = unsafe { &mut *(this as *mut OpenFile) };
+        // SAFETY: The buffer size was checked to be non-null.
+        let size = unsafe { &mut *buffer_size };
+
+        if !file.is_directory() {
+            let node = &file.entry().node;
+            if file.position >= node.size || *size == 0 {
+                *size = 0;
+                return Status::SUCCESS;
+            }
+            if buffer.is_null() {
+                return Status::INVALID_PARAMETER;
+            }
+            // SAFETY: The buffer is valid for `size` bytes, as required of the caller.
+            let buffer = unsafe { core::slice::from_raw_parts_mut(buffer.cast::<u8>(), *size) };
+            return match file.filesystem().read(node, file.position, buffer) {
+                Ok(read) => {
+                    file.position += read as u64;
+                    *size = read;
+                    Status::SUCCESS
+                }
+                Err(error) => {
+                    warn!("unable to read {}: {}", file.entry().name, error);
+                    Status::DEVICE_ERROR
+                }
+            };
+        }
+
+        if file.entries.is_none() {
+            match file.list() {
+                Ok(entries) => file.entries = Some(entries),
+                Err(error) => {
+                    warn!("unable to list {}: {}", file.entry().name, error);
+                    return Status::DEVICE_ERROR;
+                }
+            }
+        }
+        let entries = file.entries.as_deref().unwrap_or_default();
+        let Some(entry) = entries.get(file.position as usize) else {
+            // The end of a directory is reported as an empty read.
+            *size = 0;
+            return Status::SUCCESS;
+        };
+        let info = file_info(entry, file.filesystem().block_size());
+        let status = copy_out(&info, size, buffer);
+        if status == Status::SUCCESS {
+            file.position += 1;
+        }
+        status
+    }
+
+    /// Fails to write, as the filesystem is read-only.
+    unsafe extern "efiapi" fn write(
+        _this: *mut FileProtocolV1,
+        _buffer_size: *mut usize,
+        _buffer: *const c_void,
+    ) -> Status {
+        Status::WRITE_PROTECTED
+    }
+
+    /// Acquires the byte position of a file.
+    unsafe extern "efiapi" fn get_position(
+        this: *const FileProtocolV1,
+        position: *mut u64,
+    ) -> Status {
+        if this.is_null() || position.is_null() {
+            return Status::INVALID_PARAMETER;
+        }
+        // SAFETY: The protocol is the first field of an open file.
+        let file = unsafe { &*(this as *const OpenFile) };
+        if file.is_directory() {
+            return Status::UNSUPPORTED;
+        }
+        // SAFETY: The position was checked to be non-null.
+        unsafe { *position = file.position };
+        Status::SUCCESS
+    }
+
+    /// Sets the byte position of a file, where the maximum position is the end of the file.
+    /// The position of a directory can only be set to zero, which restarts its listing.
+    unsafe extern "efiapi" fn set_position(this: *mut FileProtocolV1, position: u64) -> Status {
+        if this.is_null() {
+            return Status::INVALID_PARAMETER;
+        }
+        // SAFETY: The protocol is the first field of an open file.
+        let file = unsafe { &mut *(this as *mut OpenFile) };
+        if file.is_directory() {
+            if position != 0 {
+                return Status::UNSUPPORTED;
+            }
+            file.entries = None;
+        }
+        file.position = if position == u64::MAX {
+            file.entry().node.size
+        } else {
+            position
+        };
+        Status::SUCCESS
+    }
+
+    /// Acquires the [FileInfo] of the file, or the [FileSystemInfo] or
+    /// [FileSystemVolumeLabel] of its volume.
+    unsafe extern "efiapi" fn get_info(
+        this: *mut FileProtocolV1,
+        information_type: *const Guid,
+        buffer_size: *mut usize,
+        buffer: *mut c_void,
+    ) -> Status {
+        if this.is_null() || information_type.is_null() || buffer_size.is_null() {
+            return Status::INVALID_PARAMETER;
+        }
+        // SAFETY: The protocol is the first field of an open file, and the other
+        // pointers were checked to be non-null.
+        let (file, information_type, size) = unsafe {
+            (
+                &*(this as *const OpenFile),
+                *information_type,
+                &mut *buffer_size,
+            )
+        };
+        let filesystem = file.filesystem();
+        let info = if information_type == FileInfo::ID {
+            file_info(file.entry(), filesystem.block_size())
+        } else if information_type == FileSystemInfo::ID {
+            let label = filesystem.label();
+            let info_size = named_size(offset_of!(FileSystemInfo, volume_label), &label);
+            encode_named(
+                FileSystemInfo {
+                    size: info_size as u64,
+                    read_only: Boolean::TRUE,
+                    volume_size: filesystem.size(),
+                    free_space: 0,
+                    block_size: filesystem.block_size(),
+                    volume_label: [],
+                },
+                offset_of!(FileSystemInfo, volume_label),
+                &label,
+            )
+        } else if information_type == FileSystemVolumeLabel::ID {
+            encode_named(
+                FileSystemVolumeLabel { volume_label: [] },
+                offset_of!(FileSystemVolumeLabel, volume_label),
+                &filesystem.label(),
+            )
+        } else {
+            return Status::UNSUPPORTED;
+        };
+        copy_out(&info, size, buffer)
+    }
+
+    /// Fails to set information, as the filesystem is read-only.
+    unsafe extern "efiapi" fn set_info(
+        _this: *mut FileProtocolV1,
+        _information_type: *const Guid,
+        _buffer_size: usize,
+        _buffer: *const c_void,
+    ) -> Status {
+        Status::WRITE_PROTECTED
+    }
+
+    /// Fails to flush, as the filesystem is read-only.
+    unsafe extern "efiapi" fn flush(_this: *mut FileProtocolV1) -> Status {
+        Status::WRITE_PROTECTED
+    }
+}
+
+/// Opens the root directory of the volume.
+unsafe extern "efiapi" fn open_volume(
+    this: *mut SimpleFileSystemProtocol,
+    root: *mut *mut FileProtocolV1,
+) -> Status {
+    if this.is_null() || root.is_null() {
+        return Status::INVALID_PARAMETER;
+    }
+    let volume = this as *const Volume;
+    // SAFETY: The protocol is the first field of a volume, which is never freed.
+    let node = match unsafe { (*volume).filesystem.root() } {
+        Ok(node) => node,
+        Err(error) => {
+            warn!("unable to open filesystem root: {}", error);
+            return Status::DEVICE_ERROR;
+        }
+    };
+    let stack = vec![DirectoryEntry {
+        name: String::new(),
+        node,
+    }];
+    let file = Box::into_raw(OpenFile::new(volume, stack));
+    // SAFETY: The root pointer was checked to be non-null.
+    unsafe { *root = file.cast() };
+    Status::SUCCESS
+}
+
+/// Copies the `data` to the `buffer` of `size` bytes, or sets `size` to the size of the data
+/// if the buffer is too small for it.
+fn copy_out(data: &[u8], size: &mut usize, buffer: *mut c_void) -> Status {
+    if *size < data.len() || buffer.is_null() {
+        *size = data.len();
+        return Status::BUFFER_TOO_SMALL;
+    }
+    // SAFETY: The buffer is valid for `size` bytes, as required of the caller.
+    unsafe { ptr::copy_nonoverlapping(data.as_ptr(), buffer.cast::<u8>(), data.len()) };
+    *size = data.len();
+    Status::SUCCESS
+}
+
+/// The size of a structure whose null-terminated `name` starts at `name_offset`.
+fn named_size(name_offset: usize, name: &str) -> usize {
+    name_offset + (name.encode_utf16().count() + 1) * size_of::<Char16>()
+}
+
+/// Encodes the `header` of a structure followed by its null-terminated `name`,
+/// which starts at `name_offset`, like [FileInfo].
+fn encode_named<T>(header: T, name_offset: usize, name: &str) -> Vec<u8> {
+    let size = named_size(name_offset, name);
+    let mut data = vec![0u8; size.max(size_of::<T>())];
+    // SAFETY: The data is large enough for the header, which is written unaligned.
+    unsafe { ptr::write_unaligned(data.as_mut_ptr().cast::<T>(), header) };
+    for (index, unit) in name.encode_utf16().enumerate() {
+        let offset = name_offset + index * size_of::<Char16>();
+        data[offset..offset + size_of::<Char16>()].copy_from_slice(&unit.to_le_bytes());
+    }
+    // The header may have trailing padding, which overlaps the name and is overwritten.
+    data.truncate(size);
+    data
+}
+
+/// Encodes the [FileInfo] of the `entry` on a filesystem with blocks of `block_size` bytes.
+fn file_info(entry: &DirectoryEntry, block_size: u32) -> Vec<u8> {
+    let mut attribute = FileAttribute::READ_ONLY;
+    if entry.node.kind == NodeKind::Directory {
+        attribute |= FileAttribute::DIRECTORY;
+    }
+    let time = efi_time(entry.node.modified);
+    let block_size = u64::from(block_size.max(1));
+    encode_named(
+        FileInfo {
+            size: named_size(offset_of!(FileInfo, file_name), &entry.name) as u64,
+            file_size: entry.node.size,
+            physical_size: entry.node.size.div_ceil(block_size) * block_size,
+            create_time: time,
+            last_access_time: time,
+            modification_time: time,
+            attribute,
+            file_name: [],
+        },
+        offset_of!(FileInfo, file_name),
+        &entry.name,
+    )
+}
+
+/// Converts a `timestamp` in seconds since 1970-01-01 00:00:00 to an EFI time,
+/// which is zero if the year is outside of the range EFI supports.
+fn efi_time(timestamp: i64) -> Time {
+    let date = DateTime::from_unix_timestamp(timestamp);
+    if !(1900..=9999).contains(&date.year) {
+        return Time::invalid();
+    }
+    Time {
+        year: date.year,
+        month: date.month,
+        day: date.day,
+        hour: date.hour,
+        minute: date.minute,
+        second: date.second,
+        pad1: 0,
+        nanosecond: 0,
+        time_zone: Time::UNSPECIFIED_TIMEZONE,
+        daylight: Daylight::empty(),
+        pad2: 0,
+    }
+}
+
+/// Opens the filesystem of the block `device` if it is a supported filesystem.
+/// The root of btrfs filesystems is the `btrfs_subvolume` if specified.
+/// Returns the kind of the filesystem with the result of opening it, or [None] if the
+/// device does not contain a supported filesystem.
+fn open_filesystem(
+    device: BlockDevice,
+    btrfs_subvolume: Option<&str>,
+) -> Option<(&'static str, OpenResult)> {
+    if ext4::probe(&device) {
+        let filesystem = Ext4::open(device).map(|fs| Box::new(fs) as Box<dyn ReadOnlyFilesystem>);
+        return Some(("ext4", filesystem));
+    }
+    if iso9660::probe(&device) {
+        let filesystem =
+            Iso9660::open(device).map(|fs| Box::new(fs) as Box<dyn ReadOnlyFilesystem>);
+        return Some(("iso9660", filesystem));
+    }
+    if btrfs::probe(&device) {
+        let filesystem = Btrfs::open(device).and_then(|mut filesystem| {
+            if let Some(subvolume) = btrfs_subvolume {
+                filesystem.select(subvolume)?;
+            }
+            Ok(Box::new(filesystem) as Box<dyn ReadOnlyFilesystem>)
+        });
+        return Some(("btrfs", filesystem));
+    }
+    None
+}
+
+/// A read-only block device that spans the El Torito EFI boot image of an ISO9660 disk.
+/// The protocol is the first field, so the protocol pointer is also a boot image pointer.
+#[repr(C)]
+struct BootImageDevice {
+    /// The block io protocol that is installed on the boot image handle.
+    protocol: BlockIoProtocol,
+    /// The media of the block io protocol.
+    media: BlockIoMedia,
+    /// The disk that holds the boot image.
+    disk: BlockDevice,
+    /// The byte offset of the boot image on the disk.
+    offset: u64,
+}
+
+impl BootImageDevice {
+    /// Does nothing, as the boot image has no hardware to reset.
+    unsafe extern "efiapi" fn reset(
+        _this: *mut BlockIoProtocol,
+        _extended_verification: Boolean,
+    ) -> Status {
+        Status::SUCCESS
+    }
+
+    /// Reads blocks of the boot image from the disk.
+    unsafe extern "efiapi" fn read_blocks(
+        this: *const BlockIoProtocol,
+        media_id: u32,
+        lba: u64,
+        buffer_size: usize,
+        buffer: *mut c_void,
+    ) -> Status {
+        if this.is_null() || buffer.is_null() {
+            return Status::INVALID_PARAMETER;
+        }
+        // SAFETY: The protocol is the first field of a boot image device, which is never freed.
+        let device = unsafe { &*(this as *const BootImageDevice) };
+        let block_size = device.media.block_size as u64;
+        if media_id != device.media.media_id {
+            return Status::MEDIA_CHANGED;
+        }
+        if !(buffer_size as u64).is_multiple_of(block_size) {
+            return Status::BAD_BUFFER_SIZE;
+        }
+        let end = lba
+            .saturating_mul(block_size)
+            .saturating_add(buffer_size as u64);
+        if end > (device.media.last_block + 1) * block_size {
+            return Status::INVALID_PARAMETER;
+        }
+        match device
+            .disk
+            .read_bytes(device.offset + lba * block_size, buffer_size)
+        {
+            Ok(data) => {
+                // SAFETY: The buffer is valid for `buffer_size` bytes, as required of the caller.
+                unsafe { ptr::copy_nonoverlapping(data.as_ptr(), buffer.cast::<u8>(), data.len()) };
+                Status::SUCCESS
+            }
+            Err(error) => {
+                warn!("unable to read el torito boot image: {:#}", error);
+                Status::DEVICE_ERROR
+            }
+        }
+    }
+
+    /// Fails to write, as the boot image is read-only.
+    unsafe extern "efiapi" fn write_blocks(
+        _this: *mut BlockIoProtocol,
+        _media_id: u32,
+        _lba: u64,
+        _buffer_size: usize,
+        _buffer: *const c_void,
+    ) -> Status {
+        Status::WRITE_PROTECTED
+    }
+
+    /// Does nothing, as the boot image is never written.
+    unsafe extern "efiapi" fn flush_blocks(_this: *mut BlockIoProtocol) -> Status {
+        Status::SUCCESS
+    }
+}
+
+/// Exposes the El Torito EFI boot image of the ISO9660 disk `handle` as a block device,
+/// which the firmware FAT driver reads like a partition. The firmware only does this for
+/// CD media, so ISO images that were copied to other disks without a partition table
+/// otherwise hide their EFI files. Returns whether a boot image was exposed.
+fn expose_boot_image(handle: Handle) -> Result<bool> {
+    let filesystem = Iso9660::open(BlockDevice::open(handle)?)
+        .map_err(|error| anyhow!("unable to open iso9660 filesystem: {}", error))?;
+    let image = filesystem
+        .efi_boot_image()
+        .map_err(|error| anyhow!("unable to read el torito boot catalog: {}", error))?;
+    let Some(image) = image else {
+        return Ok(false);
+    };
+    let block_size = BOOT_IMAGE_BLOCK_SIZE as u64;
+    if image.size < block_size {
+        return Ok(false);
+    }
+
+    // The device path of the image is the disk with a CD-ROM node, like firmware uses.
+    let disk_path = crate::disk::disk_device_path(handle)?;
+    let mut path = Vec::new();
+    let mut builder = DevicePathBuilder::with_vec(&mut path);
+    for node in disk_path.node_iter() {
+        builder = builder
+            .push(&node)
+            .map_err(|error| anyhow!("unable to build boot image device path: {:?}", error))?;
+    }
+    let path = builder
+        .push(&CdRom {
+            boot_entry: 0,
+            partition_start: image.offset / SECTOR_SIZE,
+            partition_size: image.size / block_size,
+        })
+        .and_then(|builder| builder.finalize())
+        .map_err(|error| anyhow!("unable to build boot image device path: {:?}", error))?;
+    let path = Box::leak(path.to_boxed());
+
+    let device = Box::leak(Box::new(BootImageDevice {
+        protocol: BlockIoProtocol {
+            revision: BLOCK_IO_REVISION,
+            media: ptr::null(),
+            reset: BootImageDevice::reset,
+            read_blocks: BootImageDevice::read_blocks,
+            write_blocks: BootImageDevice::write_blocks,
+            flush_blocks: BootImageDevice::flush_blocks,
+        },
+        media: BlockIoMedia {
+            media_id: 0,
+            removable_media: Boolean::FALSE,
+            media_present: Boolean::TRUE,
+            logical_partition: Boolean::TRUE,
+            read_only: Boolean::TRUE,
+            write_caching: Boolean::FALSE,
+            block_size: BOOT_IMAGE_BLOCK_SIZE,
+            io_align: 0,
+            last_block: image.size / block_size - 1,
+            lowest_aligned_lba: 0,
+            logical_blocks_per_physical_block: 1,
+            optimal_transfer_length_granularity: 0,
+        },
+        disk: BlockDevice::open(handle)?,
+        offset: image.offset,
+    }));
+    device.protocol.media = &device.media;
+
+    // SAFETY: The device path and the device are leaked, so they are valid for as long as
+    // the protocols are installed. They stay leaked if installing fails, as a handle may
+    // already refer to them.
+    let child = unsafe {
+        uefi::boot::install_protocol_interface(
+            None,
+            &DevicePathProtocol::GUID,
+            path.as_ffi_ptr() as *mut c_void,
+        )
+    }
+    .and_then(|child| {
+        // SAFETY: As above.
+        unsafe {
+            uefi::boot::install_protocol_interface(
+                Some(child),
+                &BlockIoProtocol::GUID,
+                device as *mut BootImageDevice as *mut c_void,
+            )
+        }
+    })
+    .context("unable to install el torito boot image")?;
+
+    // Connecting the image binds the firmware FAT driver, which installs its filesystem.
+    let _ = uefi::boot::connect_controller(child, None, None, true);
+    info!(
+        "exposed el torito boot image of {} bytes at offset {}",
+        image.size, image.offset
+    );
+    Ok(true)
+}
+
+/// Whether the El Torito boot image of the ISO9660 disk `device` on `handle` needs to be
+/// exposed, because the firmware does not expose it as a CD or a partition.
+fn needs_boot_image(handle: Handle, device: &BlockDevice) -> bool {
+    if device.is_partition() || device.block_size() == SECTOR_SIZE as usize {
+        return false;
+    }
+    match crate::disk::mbr(handle) {
+        Ok(Some(mbr)) => mbr.partitions.is_empty(),
+        Ok(None) => true,
+        Err(_) => false,
+    }
+}
+
+/// Installs the simple filesystem protocol on the block device `handle`, which serves the
+/// `filesystem` from memory that is never freed.
+fn install_volume(handle: Handle, filesystem: Box<dyn ReadOnlyFilesystem>) -> Result<()> {
+    let volume = Box::leak(Box::new(Volume {
+        protocol: SimpleFileSystemProtocol {
+            revision: SIMPLE_FILE_SYSTEM_REVISION,
+            open_volume,
+        },
+        filesystem,
+    }));
+
+    // SAFETY: The volume is leaked, so it is valid for as long as the protocol is installed.
+    let result = unsafe {
+        uefi::boot::install_protocol_interface(
+            Some(handle),
+            &SimpleFileSystemProtocol::GUID,
+            volume as *mut Volume as *mut c_void,
+        )
+    };
+    if let Err(error) = result {
+        // SAFETY: The protocol was not installed, so the volume is no longer in use.
+        drop(unsafe { Box::from_raw(volume) });
+        return Err(error).context("unable to install simple filesystem protocol");
+    }
+    Ok(())
+}
+
+/// Installs read-only filesystems on the block devices that have a filesystem the firmware
+/// can not read, like an ext4 /boot partition or the ISO9660 filesystem of installer media,
+/// so they can be used like any other volume.
+/// Block devices that already provide a filesystem are left alone, so drivers that were
+/// loaded for a filesystem take precedence. The root of btrfs filesystems is the
+/// `btrfs_subvolume` if specified, or their default subvolume otherwise.
+/// Returns the number of filesystems installed.
+pub fn install(btrfs_subvolume: Option<&str>) -> Result<usize> {
+    let existing = services()
+        .find_handles(&SimpleFileSystemProtocol::GUID)
+        .context("unable to find filesystem handles")?;
+    let devices = services()
+        .find_handles(&BlockIO::GUID)
+        .context("unable to find block io handles")?;
+
+    let mut installed = 0;
+    for handle in devices {
+        if existing.contains(&handle) {
+            continue;
+        }
+        // Devices that can not be opened, like removable drives without media, are skipped.
+        let Ok(device) = BlockDevice::open(handle) else {
+            continue;
+        };
+        if iso9660::probe(&device)
+            && needs_boot_image(handle, &device)
+            && let Err(error) = expose_boot_image(handle)
+        {
+            warn!("unable to expose el torito boot image: {:#}", error);
+        }
+        let Some((kind, filesystem)) = open_filesystem(device, btrfs_subvolume) else {
+            continue;
+        };
+        let filesystem = match filesystem {
+            Ok(filesystem) => filesystem,
+            Err(error) => {
+                warn!("unable to open {} filesystem: {}", kind, error);
+                continue;
+            }
+        };
+        let label = filesystem.label();
+        install_volume(handle, filesystem)?;
+        info!("installed read-only {} filesystem '{}'", kind, label);
+        installed += 1;
+    }
+    Ok(installed)
+}
+
+/// Mounts the squashfs container `data` with the `name` as a read-only volume, which is
+/// installed on a new handle with a vendor device path, so files inside of it can be loaded
+/// like files on any other volume. The container is held in memory that is never freed.
+/// Returns false if a container with the name was already mounted, in which case it is kept.
+pub fn mount_squashfs(name: &str, data: PageBuffer) -> Result<bool> {
+    if SQUASHFS_ROOTS.lock().contains_key(name) {
+        return Ok(false);
+    }
+    let filesystem = Squashfs::open(data)
+        .map_err(|error| anyhow!("unable to open squashfs container: {}", error))?;
+
+    let mut path = Vec::new();
+    let path = DevicePathBuilder::with_vec(&mut path)
+        .push(&Vendor {
+            vendor_guid: SQUASHFS_VENDOR_GUID,
+            vendor_defined_data: name.as_bytes(),
+        })
+        .and_then(|builder| builder.finalize())
+        .map_err(|error| anyhow!("unable to build squashfs device path: {:?}", error))?;
+    let path: &'static DevicePath = Box::leak(path.to_boxed());
+
+    // SAFETY: The device path is leaked, so it is valid for as long as the protocol is
+    // installed. It stays leaked if installing fails, as a handle may already refer to it.
+    let handle = unsafe {
+        uefi::boot::install_protocol_interface(
+            None,
+            &DevicePathProtocol::GUID,
+            path.as_ffi_ptr() as *mut c_void,
+        )
+    }
+    .context("unable to install squashfs device path")?;
+    install_volume(handle, Box::new(filesystem))?;
+    SQUASHFS_ROOTS.lock().insert(name.to_string(), path);
+    Ok(true)
+}
+
+/// The device path of the volume of the mounted squashfs container with the `name`.
+pub fn squashfs_root(name: &str) -> Option<&'static DevicePath> {
+    SQUASHFS_ROOTS.lock().get(name).copied()
+}
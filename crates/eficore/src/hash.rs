@@ -0,0 +1,166 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// Support for the ARMv8 cryptography extensions.
+#[cfg(target_arch = "aarch64")]
+pub mod aarch64;
+
+/// The size of a SHA-256 digest in bytes.
+pub const SHA256_DIGEST_SIZE: usize = 32;
+
+/// The size of a SHA-256 block in bytes.
+const SHA256_BLOCK_SIZE: usize = 64;
+
+/// The initial state of a SHA-256 hash.
+const SHA256_INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// The SHA-256 compression implementation that was detected, as a [Backend] discriminant.
+/// Zero means the detection has not run yet.
+static DETECTED_BACKEND: AtomicU8 = AtomicU8::new(0);
+
+/// The implementation used to compress SHA-256 blocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// The portable implementation of the sha2 crate, which also detects SHA-NI on x86_64.
+    Portable = 1,
+    /// The ARMv8 cryptography extensions.
+    Armv8Crypto = 2,
+}
+
+impl Backend {
+    /// Detects the fastest implementation supported by the processor.
+    /// The result is cached, as it does not change while booting.
+    pub fn detect() -> Backend {
+        match DETECTED_BACKEND.load(Ordering::Relaxed) {
+            1 => return Backend::Portable,
+            2 => return Backend::Armv8Crypto,
+            _ => {}
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        let backend = if aarch64::supported() {
+            Backend::Armv8Crypto
+        } else {
+            Backend::Portable
+        };
+
+        // sha2 detects SHA-NI itself using cpuid, which works without an operating system.
+        #[cfg(not(target_arch = "aarch64"))]
+        let backend = Backend::Portable;
+
+        DETECTED_BACKEND.store(backend as u8, Ordering::Relaxed);
+        backend
+    }
+
+    /// Compresses the `blocks` into the SHA-256 `state`.
+    fn compress(&self, state: &mut [u32; 8], blocks: &[[u8; SHA256_BLOCK_SIZE]]) {
+        match self {
+            Backend::Portable => sha2::block_api::compress256(state, blocks),
+            #[cfg(target_arch = "aarch64")]
+            // SAFETY: The backend is only selected when the processor supports the extensions.
+            Backend::Armv8Crypto => unsafe { aarch64::compress(state, blocks) },
+            #[cfg(not(target_arch = "aarch64"))]
+            Backend::Armv8Crypto => unreachable!("armv8 crypto is only detected on aarch64"),
+        }
+    }
+}
+
+/// An incremental SHA-256 hash that uses the fastest implementation the processor supports.
+/// Hashing large images like kernels and initrds is several times faster with
+/// hardware acceleration than with the portable implementation.
+pub struct Sha256 {
+    /// The implementation used to compress blocks.
+    backend: Backend,
+    /// The current hash state.
+    state: [u32; 8],
+    /// Data that does not fill a complete block yet.
+    buffer: [u8; SHA256_BLOCK_SIZE],
+    /// The number of bytes in the buffer.
+    buffered: usize,
+    /// The total number of bytes hashed.
+    length: u64,
+}
+
+impl Sha256 {
+    /// Creates a new hash using the detected [Backend].
+    pub fn new() -> Self {
+        Self::with_backend(Backend::detect())
+    }
+
+    /// Creates a new hash using the specified `backend`.
+    fn with_backend(backend: Backend) -> Self {
+        Self {
+            backend,
+            state: SHA256_INITIAL_STATE,
+            buffer: [0u8; SHA256_BLOCK_SIZE],
+            buffered: 0,
+            length: 0,
+        }
+    }
+
+    /// Adds `data` to the hash.
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.length = self.length.wrapping_add(data.len() as u64);
+
+        // Complete a partially filled block first.
+        if self.buffered > 0 {
+            let size = (SHA256_BLOCK_SIZE - self.buffered).min(data.len());
+            self.buffer[self.buffered..self.buffered + size].copy_from_slice(&data[..size]);
+            self.buffered += size;
+            data = &data[size..];
+            if self.buffered < SHA256_BLOCK_SIZE {
+                return;
+            }
+            let block = self.buffer;
+            self.backend.compress(&mut self.state, &[block]);
+            self.buffered = 0;
+        }
+
+        // Compress all complete blocks directly from the input.
+        let (blocks, remainder) = data.as_chunks::<SHA256_BLOCK_SIZE>();
+        if !blocks.is_empty() {
+            self.backend.compress(&mut self.state, blocks);
+        }
+
+        // Keep the remainder until more data arrives.
+        self.buffer[..remainder.len()].copy_from_slice(remainder);
+        self.buffered = remainder.len();
+    }
+
+    /// Finishes the hash and returns the digest.
+    pub fn finalize(mut self) -> [u8; SHA256_DIGEST_SIZE] {
+        let bits = self.length.wrapping_mul(8);
+
+        // Pad with a single one bit, then zeros until the length fits at the end of a block.
+        let mut padding = [0u8; SHA256_BLOCK_SIZE * 2];
+        padding[0] = 0x80;
+        let size = if self.buffered < SHA256_BLOCK_SIZE - 8 {
+            SHA256_BLOCK_SIZE - self.buffered
+        } else {
+            SHA256_BLOCK_SIZE * 2 - self.buffered
+        };
+        padding[size - 8..size].copy_from_slice(&bits.to_be_bytes());
+        // The padding is not part of the hashed length, which was already captured above.
+        self.update(&padding[..size]);
+
+        let mut digest = [0u8; SHA256_DIGEST_SIZE];
+        for (bytes, word) in digest.chunks_exact_mut(4).zip(self.state) {
+            bytes.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+}
+
+impl Default for Sha256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Computes the SHA-256 digest of `data`.
+pub fn sha256(data: &[u8]) -> [u8; SHA256_DIGEST_SIZE] {
+    let mut hash = Sha256::new();
+    hash.update(data);
+    hash.finalize()
+}
@@ -0,0 +1,101 @@
+// Adapted from the aarch64 backend of the sha2 crate (MIT license), which is in turn
+// adapted from mbedtls. The sha2 crate can only detect the extensions on Linux and macOS.
+
+use core::arch::aarch64::*;
+use core::arch::asm;
+
+/// The SHA-256 round constants.
+const K32: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Checks whether the processor supports the SHA-256 instructions of the
+/// ARMv8 cryptography extensions, using the ID_AA64ISAR0_EL1 register.
+pub fn supported() -> bool {
+    let isar0: u64;
+    // SAFETY: UEFI runs at EL1 or EL2, where the ID registers are always readable.
+    unsafe {
+        asm!("mrs {}, id_aa64isar0_el1", out(reg) isar0);
+    }
+    // The SHA2 field is bits 12 to 15, and is nonzero when SHA-256 is supported.
+    (isar0 >> 12) & 0xf != 0
+}
+
+/// Compresses the `blocks` into the SHA-256 `state` using the ARMv8 cryptography extensions.
+///
+/// # Safety
+/// The processor must support the extensions, which is checked by [supported].
+#[target_feature(enable = "sha2")]
+pub unsafe fn compress(state: &mut [u32; 8], blocks: &[[u8; 64]]) {
+    // Load the state into vectors.
+    let mut abcd = unsafe { vld1q_u32(state[0..4].as_ptr()) };
+    let mut efgh = unsafe { vld1q_u32(state[4..8].as_ptr()) };
+
+    for block in blocks {
+        // Keep the original state values.
+        let abcd_orig = abcd;
+        let efgh_orig = efgh;
+
+        // Load the message block into vectors, converting the words from big endian.
+        let (mut s0, mut s1, mut s2, mut s3) = unsafe {
+            (
+                vreinterpretq_u32_u8(vrev32q_u8(vld1q_u8(block[0..16].as_ptr()))),
+                vreinterpretq_u32_u8(vrev32q_u8(vld1q_u8(block[16..32].as_ptr()))),
+                vreinterpretq_u32_u8(vrev32q_u8(vld1q_u8(block[32..48].as_ptr()))),
+                vreinterpretq_u32_u8(vrev32q_u8(vld1q_u8(block[48..64].as_ptr()))),
+            )
+        };
+
+        for t in (0..64).step_by(16) {
+            // The first 16 rounds use the message block directly,
+            // the remaining rounds use the expanded message schedule.
+            if t > 0 {
+                s0 = vsha256su1q_u32(vsha256su0q_u32(s0, s1), s2, s3);
+            }
+            round(&mut abcd, &mut efgh, s0, t);
+
+            if t > 0 {
+                s1 = vsha256su1q_u32(vsha256su0q_u32(s1, s2), s3, s0);
+            }
+            round(&mut abcd, &mut efgh, s1, t + 4);
+
+            if t > 0 {
+                s2 = vsha256su1q_u32(vsha256su0q_u32(s2, s3), s0, s1);
+            }
+            round(&mut abcd, &mut efgh, s2, t + 8);
+
+            if t > 0 {
+                s3 = vsha256su1q_u32(vsha256su0q_u32(s3, s0), s1, s2);
+            }
+            round(&mut abcd, &mut efgh, s3, t + 12);
+        }
+
+        // Add the block-specific state to the original state.
+        abcd = vaddq_u32(abcd, abcd_orig);
+        efgh = vaddq_u32(efgh, efgh_orig);
+    }
+
+    // Store the vectors into the state.
+    unsafe {
+        vst1q_u32(state[0..4].as_mut_ptr(), abcd);
+        vst1q_u32(state[4..8].as_mut_ptr(), efgh);
+    }
+}
+
+/// Performs four rounds starting at round `t` with the message `schedule` words.
+#[inline]
+#[target_feature(enable = "sha2")]
+fn round(abcd: &mut uint32x4_t, efgh: &mut uint32x4_t, schedule: uint32x4_t, t: usize) {
+    // SAFETY: The constants are loaded from a fixed array with four words available at `t`.
+    let tmp = vaddq_u32(schedule, unsafe { vld1q_u32(K32[t..t + 4].as_ptr()) });
+    let abcd_prev = *abcd;
+    *abcd = vsha256hq_u32(abcd_prev, *efgh, tmp);
+    *efgh = vsha256h2q_u32(*efgh, abcd_prev, tmp);
+}
@@ -0,0 +1,22 @@
+use crate::variables::VariableController;
+use anyhow::Result;
+use uefi::guid;
+use uefi_raw::table::runtime::VariableVendor;
+
+/// Hibernation resume services.
+pub struct Hibernation;
+
+impl Hibernation {
+    /// The systemd vendor variables, which include the hibernation location.
+    /// See https://systemd.io/EFI_VARIABLES/ for more information.
+    const SYSTEMD: VariableController = VariableController::new(VariableVendor(guid!(
+        "8cf2644b-4b0b-428f-9387-6d876050dc67"
+    )));
+
+    /// Checks if the operating system has a pending hibernation resume.
+    /// Linux sets the HibernateLocation variable when it hibernates, and removes it on resume.
+    /// This might fail if retrieving the variable fails in an irrecoverable way.
+    pub fn resume_pending() -> Result<bool> {
+        Ok(Self::SYSTEMD.get("HibernateLocation")?.is_some())
+    }
+}
@@ -0,0 +1,476 @@
+use crate::deadline;
+use crate::services::services;
+use crate::variables::{VariableClass, VariableController};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use anyhow::{Context, Result, anyhow, bail};
+use core::ffi::c_void;
+use core::ptr::{self, NonNull};
+use core::time::Duration;
+use edera_sprout_parsing::certificates::{
+    encode_signature_list, parse_certificates, signature_list_certificates,
+};
+use edera_sprout_parsing::url::{HttpUrl, content_length};
+use log::{info, warn};
+use uefi::boot::ScopedProtocol;
+use uefi::proto::device_path::DevicePath;
+use uefi::proto::network::http::{Http, HttpBinding, HttpHelper};
+use uefi::proto::network::ip4config2::Ip4Config2;
+use uefi::{Handle, Identify, Status, StatusExt, guid};
+use uefi_raw::protocol::network::http::{
+    HttpAccessPoint, HttpConfigData, HttpHeader, HttpMessage, HttpMethod, HttpRequestData,
+    HttpResponseData, HttpStatusCode, HttpToken, HttpV4AccessPoint, HttpVersion,
+};
+use uefi_raw::protocol::network::tls::TlsConfigurationProtocol;
+use uefi_raw::table::runtime::VariableVendor;
+
+/// The vendor of the variable that holds the CA certificates of the firmware TLS driver.
+const TLS_CA_CERTIFICATE: VariableController = VariableController::new(VariableVendor(guid!(
+    "fd2340d0-3dab-4349-a6c7-3b4f12b56a51"
+)));
+
+/// The name of the variable that holds the CA certificates of the firmware TLS driver.
+/// The HTTP driver reads it when it opens an HTTPS connection, so certificates that are
+/// added before a download are trusted for that download.
+const TLS_CA_CERTIFICATE_NAME: &str = "TlsCaCertificate";
+
+/// The owner GUID of the certificates that Sprout adds, which is the Sprout vendor GUID.
+const CERTIFICATE_OWNER: uefi::Guid = guid!("418edfbc-dcc5-466e-95cc-bf5b641b7295");
+
+/// Finds the handles of the network interfaces that support HTTP.
+pub fn interfaces() -> Result<Vec<Handle>> {
+    services()
+        .find_handles(&HttpBinding::GUID)
+        .context("unable to find http network interfaces")
+}
+
+/// Reads the CA certificates that the firmware TLS driver trusts for HTTPS.
+fn trusted_certificates() -> Result<Vec<Vec<u8>>> {
+    let Some(data) = TLS_CA_CERTIFICATE.get(TLS_CA_CERTIFICATE_NAME)? else {
+        return Ok(Vec::new());
+    };
+    signature_list_certificates(&data).context("tls ca certificate variable is malformed")
+}
+
+/// Adds the DER `certificates` to the CA certificates that the firmware TLS driver trusts
+/// for HTTPS. Certificates that are already trusted are skipped, and certificates that were
+/// configured in the firmware are kept. Returns the number of certificates that were added.
+/// If `dry_run` is set, the certificates that would be added are only counted.
+pub fn trust_certificates(certificates: &[Vec<u8>], dry_run: bool) -> Result<usize> {
+    let trusted = trusted_certificates()?;
+    let missing = certificates
+        .iter()
+        .filter(|certificate| !trusted.contains(certificate))
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        return Ok(0);
+    }
+    if dry_run {
+        info!("dry run: would trust {} ca certificates", missing.len());
+        return Ok(0);
+    }
+
+    // The certificates only need to last for this boot, unless the firmware stores them.
+    TLS_CA_CERTIFICATE
+        .update(
+            TLS_CA_CERTIFICATE_NAME,
+            VariableClass::BootAndRuntimeTemporary,
+            |current| {
+                let mut data = current.map(<[u8]>::to_vec).unwrap_or_default();
+                for certificate in &missing {
+                    data.extend(encode_signature_list(
+                        certificate,
+                        CERTIFICATE_OWNER.to_bytes(),
+                    ));
+                }
+                Some(data)
+            },
+        )
+        .context("unable to set tls ca certificate variable")?;
+    Ok(missing.len())
+}
+
+/// Adds the PEM or DER certificates in the files at `paths` to the CA certificates that the
+/// firmware TLS driver trusts for HTTPS. The paths are resolved relative to `root`.
+/// Returns the number of certificates that were added.
+/// If `dry_run` is set, the files are read but the certificates are not added.
+pub fn trust_certificate_files(
+    root: &DevicePath,
+    paths: &[String],
+    dry_run: bool,
+) -> Result<usize> {
+    let mut certificates = Vec::new();
+    for path in paths {
+        let data = crate::path::read_file_contents(Some(root), path)
+            .context(format!("unable to read ca certificate file {}", path))?;
+        let parsed = parse_certificates(&data)
+            .map_err(|error| anyhow!("ca certificate file {} is invalid: {}", path, error))?;
+        certificates.extend(parsed);
+    }
+    trust_certificates(&certificates, dry_run)
+}
+
+/// Checks that HTTPS downloads can succeed, which requires a firmware TLS driver
+/// and at least one trusted CA certificate.
+fn check_https_support() -> Result<()> {
+    let drivers = services()
+        .find_handles(&TlsConfigurationProtocol::SERVICE_BINDING_GUID)
+        .context("unable to find tls drivers")?;
+    if drivers.is_empty() {
+        bail!("https is not supported, as the firmware has no tls driver, like TlsDxe");
+    }
+    if trusted_certificates()?.is_empty() {
+        bail!(
+            "https requires ca certificates, add the certificate that issued the server \
+             certificate to the tls-ca-certificates option"
+        );
+    }
+    Ok(())
+}
+
+/// Converts the `error` of an HTTPS request or response to an error that explains
+/// how to fix a failed TLS connection to `host`, if the error indicates one.
+fn explain_tls_error(error: uefi::Error, host: &str) -> anyhow::Error {
+    match error.status() {
+        Status::ABORTED | Status::ACCESS_DENIED | Status::SECURITY_VIOLATION => anyhow!(
+            "tls connection to {} failed ({:?}), check that the server certificate is issued \
+             by a certificate in the tls-ca-certificates option, that it is valid for the \
+             hostname, and that the firmware clock is correct",
+            host,
+            error.status()
+        ),
+        _ => anyhow::Error::from(error),
+    }
+}
+
+/// Brings up the network interface on `handle`, using DHCP if it has no address yet.
+/// The DHCP configuration also provides the DNS servers, which resolve hostnames in URLs.
+pub fn configure_interface(handle: Handle) -> Result<()> {
+    let mut config = Ip4Config2::new(handle).context("unable to open ip4 config protocol")?;
+    config
+        .ifup()
+        .context("unable to configure network interface with dhcp")
+}
+
+/// Downloads the body of `url` over the network interface on `handle`.
+fn download_with(
+    handle: Handle,
+    url: &HttpUrl,
+    raw_url: &str,
+    progress: &mut impl FnMut(usize, usize),
+) -> Result<Vec<u8>> {
+    configure_interface(handle)?;
+    let mut http = HttpHelper::new(handle).context("unable to open http protocol")?;
+    http.configure()
+        .context("unable to configure http protocol")?;
+
+    // TLS failures surface when the connection is opened by the request or response.
+    let explain = |error: uefi::Error| match url.secure {
+        true => explain_tls_error(error, &url.host),
+        false => anyhow::Error::from(error),
+    };
+    http.request_get(raw_url)
+        .map_err(explain)
+        .context("unable to send http request")?;
+
+    let response = http
+        .response_first(true)
+        .map_err(explain)
+        .context("unable to receive http response")?;
+    if response.status != HttpStatusCode::STATUS_200_OK {
+        bail!("server responded with {:?}", response.status);
+    }
+
+    // Without a content length, the body ends when the server closes the connection.
+    let length = content_length(&response.headers);
+    let mut body = response.body;
+    loop {
+        progress(body.len(), length.unwrap_or(body.len()));
+        if length.is_some_and(|length| body.len() >= length) {
+            break;
+        }
+        deadline::check().context("unable to download file")?;
+        match http.response_more(&mut body) {
+            Ok([]) if length.is_none() => break,
+            Ok([]) => bail!("connection closed before the response body was received"),
+            Ok(_) => {}
+            Err(_) if length.is_none() => break,
+            Err(error) => return Err(error).context("unable to receive http response body"),
+        }
+    }
+
+    if let Some(length) = length {
+        body.truncate(length);
+    }
+    Ok(body)
+}
+
+/// Downloads the body of the HTTP `url`, calling `progress` with the number of bytes received
+/// so far and the total size, which is the received size if the server does not report it.
+///
+/// Each network interface that supports HTTP is tried in order until one succeeds. Interfaces
+/// without an address are configured with DHCP first. The download checks the active
+/// [deadline] between chunks, so it can be cancelled with a timeout.
+pub fn download(url: &str, mut progress: impl FnMut(usize, usize)) -> Result<Vec<u8>> {
+    let Some(parsed) = HttpUrl::parse(url) else {
+        bail!("'{}' is not an http url", url);
+    };
+    if parsed.secure {
+        check_https_support()?;
+    }
+
+    let interfaces = interfaces()?;
+    if interfaces.is_empty() {
+        bail!("no network interface supports http");
+    }
+
+    info!("downloading {}", url);
+    let mut last_error = anyhow!("no network interface supports http");
+    for handle in interfaces {
+        match download_with(handle, &parsed, url, &mut progress) {
+            Ok(body) => {
+                info!("downloaded {} bytes from {}", body.len(), url);
+                return Ok(body);
+            }
+            Err(error) => {
+                warn!(
+                    "unable to download {} on a network interface: {:#}",
+                    url, error
+                );
+                last_error = error;
+            }
+        }
+    }
+    Err(last_error).context(format!("unable to download {}", url))
+}
+
+/// The size of the buffer that receives the start of a response body that is discarded.
+const DISCARDED_BODY_SIZE: usize = 1024;
+
+/// A child HTTP instance of a network interface, which is destroyed when dropped.
+/// Unlike [HttpHelper], the instance allows sending requests with arbitrary headers.
+struct HttpInstance {
+    /// The service binding that created the instance.
+    binding: ScopedProtocol<HttpBinding>,
+    /// The handle of the instance.
+    child: Handle,
+    /// The HTTP protocol of the instance, which is closed before the instance is destroyed.
+    protocol: Option<ScopedProtocol<Http>>,
+}
+
+impl HttpInstance {
+    /// Creates an HTTP instance on the network interface on `interface`, which gives up on
+    /// connections and requests after `timeout`.
+    fn new(interface: Handle, timeout: Duration) -> Result<Self> {
+        let mut binding = crate::disk::open_shared::<HttpBinding>(interface)
+            .context("network interface does not support http")?;
+        let child = binding
+            .create_child()
+            .context("unable to create http instance")?;
+        let mut instance = Self {
+            binding,
+            child,
+            protocol: None,
+        };
+
+        // The protocol is closed before the child is destroyed on drop.
+        let protocol =
+            crate::disk::open_shared::<Http>(child).context("unable to open http protocol")?;
+        let http = instance.protocol.insert(protocol);
+
+        let access_point = HttpV4AccessPoint {
+            use_default_addr: true.into(),
+            ..Default::default()
+        };
+        http.configure(&HttpConfigData {
+            http_version: HttpVersion::HTTP_VERSION_11,
+            time_out_millisec: timeout.as_millis().min(u32::MAX as u128) as u32,
+            local_addr_is_ipv6: false.into(),
+            access_point: HttpAccessPoint {
+                ipv4_node: &access_point,
+            },
+        })
+        .context("unable to configure http protocol")?;
+        Ok(instance)
+    }
+
+    /// Accesses the HTTP protocol of the instance.
+    fn protocol(&mut self) -> &mut Http {
+        self.protocol.as_mut().expect("http protocol is open")
+    }
+
+    /// Polls the instance until the operation of `token` completes.
+    /// The active [deadline] is checked while polling, and the operation is cancelled
+    /// once it passes.
+    fn wait(&mut self, token: &mut HttpToken) -> Result<()> {
+        let http = self.protocol();
+        // SAFETY: The token is only read after the driver writes it during the poll.
+        while unsafe { ptr::read_volatile(&token.status) } == Status::NOT_READY {
+            if let Err(error) = deadline::check() {
+                let _ = http.cancel(token);
+                return Err(error);
+            }
+            let _ = http.poll();
+        }
+        token.status.to_result().map_err(anyhow::Error::from)
+    }
+}
+
+impl Drop for HttpInstance {
+    fn drop(&mut self) {
+        // The protocol must be closed before the child that provides it is destroyed.
+        self.protocol = None;
+        let _ = self.binding.destroy_child(self.child);
+    }
+}
+
+/// Sends `body` with the `content_type` to `url` with a POST request over the network
+/// interface on `handle`. Returns the status code of the response.
+fn post_with(
+    handle: Handle,
+    url: &HttpUrl,
+    raw_url: &str,
+    content_type: &str,
+    body: &[u8],
+    timeout: Duration,
+) -> Result<HttpStatusCode> {
+    configure_interface(handle)?;
+    let mut instance = HttpInstance::new(handle, timeout)?;
+
+    // The header names and values are null-terminated strings.
+    let host = match url.port {
+        Some(port) => format!("{}:{}\0", url.host, port),
+        None => format!("{}\0", url.host),
+    };
+    let content_type = format!("{}\0", content_type);
+    let content_length = format!("{}\0", body.len());
+    let mut headers = [
+        ("Host\0", host.as_str()),
+        ("Content-Type\0", content_type.as_str()),
+        ("Content-Length\0", content_length.as_str()),
+    ]
+    .map(|(name, value)| HttpHeader {
+        field_name: name.as_ptr(),
+        field_value: value.as_ptr(),
+    });
+
+    let url16 = uefi::CString16::try_from(raw_url).context("unable to convert url")?;
+    let request = HttpRequestData {
+        method: HttpMethod::POST,
+        url: url16.as_ptr().cast(),
+    };
+    let mut body = body.to_vec();
+    let mut message = HttpMessage {
+        header_count: headers.len(),
+        header: headers.as_mut_ptr(),
+        body_length: body.len(),
+        body: body.as_mut_ptr().cast::<c_void>(),
+        ..Default::default()
+    };
+    message.data.request = &request;
+    let mut token = HttpToken {
+        status: Status::NOT_READY,
+        message: &mut message,
+        ..Default::default()
+    };
+
+    // TLS failures surface when the connection is opened by the request or response.
+    let explain = |error: anyhow::Error| match (url.secure, error.downcast_ref::<uefi::Error>()) {
+        (true, Some(tls)) => explain_tls_error(tls.clone(), &url.host),
+        _ => error,
+    };
+    instance
+        .protocol()
+        .request(&mut token)
+        .map_err(anyhow::Error::from)
+        .and_then(|_| instance.wait(&mut token))
+        .map_err(explain)
+        .context("unable to send http request")?;
+
+    // Only the status of the response is used, so the start of the body is discarded.
+    let mut response = HttpResponseData {
+        status_code: HttpStatusCode::STATUS_UNSUPPORTED,
+    };
+    let mut discarded = vec![0u8; DISCARDED_BODY_SIZE];
+    let mut message = HttpMessage {
+        body_length: discarded.len(),
+        body: discarded.as_mut_ptr().cast::<c_void>(),
+        ..Default::default()
+    };
+    message.data.response = &mut response;
+    let mut token = HttpToken {
+        status: Status::NOT_READY,
+        message: &mut message,
+        ..Default::default()
+    };
+    let result = instance
+        .protocol()
+        .response(&mut token)
+        .map_err(anyhow::Error::from)
+        .and_then(|_| instance.wait(&mut token));
+
+    // The response headers are allocated by the driver, so they are freed.
+    if let Some(headers) = NonNull::new(message.header) {
+        // SAFETY: The headers were allocated from pool memory by the driver.
+        let _ = unsafe { uefi::boot::free_pool(headers.cast()) };
+    }
+    match result {
+        Ok(()) => {}
+        // The driver reports error status codes as HTTP errors, which still have a status.
+        Err(error)
+            if error
+                .downcast_ref::<uefi::Error>()
+                .is_some_and(|error| error.status() == Status::HTTP_ERROR) => {}
+        Err(error) => {
+            return Err(explain(error)).context("unable to receive http response");
+        }
+    }
+    Ok(response.status_code)
+}
+
+/// Sends `body` with the `content_type` to the HTTP `url` with a POST request, like a JSON
+/// report. The request fails if the server does not accept it with a success status.
+///
+/// Each network interface that supports HTTP is tried in order until one succeeds. Interfaces
+/// without an address are configured with DHCP first. The firmware gives up on connections
+/// after `timeout`, and the active [deadline] is checked while waiting for the server.
+pub fn post(url: &str, content_type: &str, body: &[u8], timeout: Duration) -> Result<()> {
+    let Some(parsed) = HttpUrl::parse(url) else {
+        bail!("'{}' is not an http url", url);
+    };
+    if parsed.secure {
+        check_https_support()?;
+    }
+
+    let interfaces = interfaces()?;
+    if interfaces.is_empty() {
+        bail!("no network interface supports http");
+    }
+
+    let mut last_error = anyhow!("no network interface supports http");
+    for handle in interfaces {
+        match post_with(handle, &parsed, url, content_type, body, timeout) {
+            Ok(
+                HttpStatusCode::STATUS_200_OK
+                | HttpStatusCode::STATUS_201_CREATED
+                | HttpStatusCode::STATUS_202_ACCEPTED
+                | HttpStatusCode::STATUS_204_NO_CONTENT,
+            ) => {
+                info!("sent {} bytes to {}", body.len(), url);
+                return Ok(());
+            }
+            // The server received the request, so other interfaces would fail the same way.
+            Ok(status) => bail!("server responded to {} with {:?}", url, status),
+            Err(error) => {
+                warn!(
+                    "unable to send to {} on a network interface: {:#}",
+                    url, error
+                );
+                last_error = error;
+            }
+        }
+    }
+    Err(last_error).context(format!("unable to send to {}", url))
+}
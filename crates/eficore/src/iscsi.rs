@@ -0,0 +1,95 @@
+use crate::services::services;
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use anyhow::{Context, Result, bail};
+use core::ffi::c_void;
+use edera_sprout_parsing::iscsi::{MAX_NAME_LENGTH, is_valid_name};
+use log::info;
+use uefi::proto::media::block::BlockIO;
+use uefi::proto::unsafe_protocol;
+use uefi::{Guid, Identify, StatusExt, guid};
+use uefi_raw::Status;
+
+/// GUID for the EFI_ISCSI_INITIATOR_NAME protocol.
+const ISCSI_INITIATOR_NAME_GUID: Guid = guid!("59324945-ec44-4c0d-b1cd-9db139df070c");
+
+/// EFI_ISCSI_INITIATOR_NAME protocol definition.
+/// The name is a null-terminated ASCII string, and the sizes include the terminator.
+#[unsafe_protocol(ISCSI_INITIATOR_NAME_GUID)]
+struct IscsiInitiatorNameProtocol {
+    /// Retrieves the current initiator name.
+    get: unsafe extern "efiapi" fn(
+        this: *const IscsiInitiatorNameProtocol,
+        buffer_size: *mut usize,
+        buffer: *mut c_void,
+    ) -> Status,
+    /// Sets the initiator name, which the firmware stores for future boots.
+    set: unsafe extern "efiapi" fn(
+        this: *const IscsiInitiatorNameProtocol,
+        buffer_size: *mut usize,
+        buffer: *mut c_void,
+    ) -> Status,
+}
+
+/// Sets the iSCSI initiator name of the firmware to `name`, unless it is already set.
+/// Returns whether the name was changed.
+fn set_initiator_name(name: &str) -> Result<bool> {
+    if !is_valid_name(name) {
+        bail!("'{}' is not a valid iscsi name", name);
+    }
+
+    let Some(handle) = crate::handle::find_handle(&ISCSI_INITIATOR_NAME_GUID)? else {
+        bail!("firmware has no iscsi initiator, like IScsiDxe");
+    };
+    let protocol = crate::disk::open_shared::<IscsiInitiatorNameProtocol>(handle)
+        .context("unable to open iscsi initiator name protocol")?;
+
+    // The current name is not set on a fresh firmware, in which case it is not compared.
+    let mut current = vec![0u8; MAX_NAME_LENGTH + 1];
+    let mut size = current.len();
+    // SAFETY: The buffer is valid for `size` bytes.
+    let status = unsafe { (protocol.get)(&*protocol, &mut size, current.as_mut_ptr().cast()) };
+    if status == Status::SUCCESS {
+        let current = current[..size.min(current.len())]
+            .split(|byte| *byte == 0)
+            .next()
+            .unwrap_or_default();
+        if current == name.as_bytes() {
+            return Ok(false);
+        }
+    }
+
+    let mut buffer = name.as_bytes().to_vec();
+    buffer.push(0);
+    let mut size = buffer.len();
+    // SAFETY: The buffer is a null-terminated name of `size` bytes.
+    unsafe { (protocol.set)(&*protocol, &mut size, buffer.as_mut_ptr().cast()) }
+        .to_result()
+        .context(format!("unable to set iscsi initiator name to {}", name))?;
+    info!("iscsi initiator name set to {}", name);
+    Ok(true)
+}
+
+/// Finds the block devices, which include the disks of attached iSCSI targets.
+fn block_devices() -> Result<Vec<uefi::Handle>> {
+    services()
+        .find_handles(&BlockIO::GUID)
+        .context("unable to find block io handles")
+}
+
+/// Attaches the iSCSI targets configured in the firmware, after setting the initiator name
+/// to `initiator_name` if one is specified. The firmware logs in to the targets when the
+/// controllers of the network interfaces are connected, which adds their disks as block
+/// devices. Returns the number of block devices that were added.
+pub fn attach(initiator_name: Option<&str>) -> Result<usize> {
+    if let Some(name) = initiator_name {
+        set_initiator_name(name)?;
+    }
+
+    let before = block_devices()?.len();
+    crate::controller::connect_all()?;
+    let attached = block_devices()?.len().saturating_sub(before);
+    info!("iscsi attached {} block devices", attached);
+    Ok(attached)
+}
@@ -9,24 +9,105 @@ extern crate alloc;
 /// EFI handle helpers.
 pub mod handle;
 
+/// Ordered cleanup operations for acquired resources.
+pub mod cleanup;
+
+/// Deadlines that allow long-running operations to be cancelled.
+pub mod deadline;
+
+/// Atomic replacement of files on FAT filesystems.
+pub mod atomic;
+
+/// Audible feedback with beep codes.
+pub mod beep;
+
+/// Raw block device access.
+pub mod block;
+
+/// Detection of how the current image was booted.
+pub mod boot_mode;
+
+/// Registration of Sprout as a firmware boot option.
+pub mod boot_options;
+
+/// Backup and restore of the firmware boot variables.
+pub mod boot_variables;
+
+/// Connection of controllers to their drivers.
+pub mod controller;
+
+/// Hostname resolution with the firmware DNS protocol.
+pub mod dns;
+
+/// Physical disk inspection.
+pub mod disk;
+
+/// Installation of Sprout to the removable media boot path.
+pub mod fallback;
+
+/// Shared access to filesystems during a scan.
+pub mod filesystem;
+
+/// Read-only filesystems for partitions the firmware can not read.
+pub mod fs_driver;
+
+/// Hardware accelerated hashing.
+pub mod hash;
+
+/// Detection of pending hibernation resumes.
+pub mod hibernate;
+
+/// Downloads over the UEFI HTTP protocol.
+pub mod http;
+
+/// Attachment of iSCSI targets with the firmware iSCSI initiator.
+pub mod iscsi;
+
 /// Load and start EFI images.
 pub mod loader;
 
 /// Logging support for EFI applications.
 pub mod logger;
 
+/// Verification of files against a signed manifest of their digests.
+pub mod manifest;
+
+/// Memory usage diagnostics.
+pub mod memory;
+
+/// Network interface configuration assigned by DHCP.
+pub mod network;
+
+/// Reporting of panics on headless and graphical machines.
+pub mod panic;
+
+/// Page-aligned buffers allocated from boot services.
+pub mod pages;
+
 /// Disk partitioning support infrastructure.
 pub mod partition;
 
 /// Path handling for UEFI.
 pub mod path;
 
+/// Inspection of PE images.
+pub mod pe;
+
 /// platform: Integration or support code for specific hardware platforms.
 pub mod platform;
 
+/// Power management support.
+pub mod power;
+
+/// Progress reporting for long-running operations.
+pub mod progress;
+
 /// Secure Boot support.
 pub mod secure;
 
+/// Firmware services that can be replaced outside of UEFI.
+pub mod services;
+
 /// Support for the shim loader application that enables Secure Boot.
 pub mod shim;
 
@@ -43,5 +124,11 @@ pub mod framebuffer;
 pub mod media_loader;
 /// setup: Code that initializes the UEFI environment for Sprout.
 pub mod setup;
+/// Timing spans for performance debugging, compiled out by default.
+pub mod trace;
+/// Garbage collection of stale variables that Sprout wrote.
+pub mod variable_gc;
 /// Support code for EFI variables.
 pub mod variables;
+/// Boot services watchdog timer support.
+pub mod watchdog;
@@ -1,7 +1,10 @@
+use crate::deadline;
 use crate::loader::source::ImageSource;
+use crate::path::ResolvedPath;
 use crate::secure::SecureBoot;
 use crate::shim::hook::SecurityHook;
-use crate::shim::{ShimInput, ShimSupport};
+use crate::shim::{ShimInput, ShimSupport, ShimVerificationOutput};
+use alloc::boxed::Box;
 use anyhow::{Context, Result, bail};
 use log::warn;
 use uefi::Handle;
@@ -28,12 +31,31 @@ impl ImageHandle {
     }
 }
 
+/// The stages of loading an image, which are reported to the progress hook of an
+/// [ImageLoadRequest].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadStage {
+    /// The image is read into memory. Progress is reported in bytes.
+    Read,
+    /// The image is verified and loaded by the firmware or the shim.
+    /// This can not report finer progress, so it is reported once before and once after.
+    Load,
+}
+
+/// A hook that is called with the stage of an image load, the amount of work done,
+/// and the total amount of work of the stage.
+pub type LoadProgressHook<'source> = Box<dyn FnMut(LoadStage, usize, usize) + 'source>;
+
 /// Request to load an image from a source, with support for additional validation features.
 pub struct ImageLoadRequest<'source> {
     /// Handle to the current image.
     current_image: Handle,
     /// Source of the image to load.
     source: ImageSource<'source>,
+    /// The hook that is called as the image load progresses, if any.
+    progress: Option<LoadProgressHook<'source>>,
+    /// The check of whether the image load should be cancelled, if any.
+    cancel: Option<fn() -> bool>,
 }
 
 impl<'source> ImageLoadRequest<'source> {
@@ -42,9 +64,25 @@ impl<'source> ImageLoadRequest<'source> {
         Self {
             current_image,
             source,
+            progress: None,
+            cancel: None,
         }
     }
 
+    /// Calls `hook` as the image load progresses. See [LoadStage] for the reported stages.
+    pub fn with_progress(mut self, hook: impl FnMut(LoadStage, usize, usize) + 'source) -> Self {
+        self.progress = Some(Box::new(hook));
+        self
+    }
+
+    /// Allows the image load to be cancelled while it is read, which is checked by
+    /// calling `cancelled`. A cancelled load fails with an error.
+    /// Once the image is handed to the firmware, it can no longer be cancelled.
+    pub fn with_cancel(mut self, cancelled: fn() -> bool) -> Self {
+        self.cancel = Some(cancelled);
+        self
+    }
+
     /// Retrieve the current image.
     pub fn current_image(&self) -> &Handle {
         &self.current_image
@@ -65,8 +103,58 @@ impl<'source> ImageLoadRequest<'source> {
 pub struct ImageLoader;
 
 impl ImageLoader {
+    /// Verifies the image in `buffer`, loaded from `path` if known, before it is loaded,
+    /// failing if Secure Boot is enabled and the image is not trusted.
+    ///
+    /// When the shim is loaded, the shim verifies the image against its own keys as well as
+    /// the firmware keys. Otherwise, the firmware verifies the image against its keys when
+    /// the image is loaded, so there is nothing to verify up front.
+    pub fn verify(buffer: &[u8], path: Option<&ResolvedPath>) -> Result<()> {
+        if !SecureBoot::enabled().context("unable to determine if secure boot is enabled")? {
+            return Ok(());
+        }
+        if !ShimSupport::loaded().context("unable to determine if shim is loaded")? {
+            return Ok(());
+        }
+        match ShimSupport::verify(ShimInput::DataBuffer(path, buffer))? {
+            ShimVerificationOutput::VerificationFailed(status) => {
+                bail!("image is not trusted by the shim: {}", status)
+            }
+            ShimVerificationOutput::VerifiedDataNotLoaded
+            | ShimVerificationOutput::VerifiedDataBuffer(_) => Ok(()),
+        }
+    }
+
     /// Load an image using the image `request` which allows
     pub fn load(request: ImageLoadRequest) -> Result<ImageHandle> {
+        // Clone the current image handle to use for loading the image.
+        let current_image = *request.current_image();
+        let cancel = request.cancel;
+        let mut progress = request.progress.unwrap_or_else(|| Box::new(|_, _, _| {}));
+
+        // Converts the source to a shim input with an owned data buffer.
+        // This is done before the security hook is installed, so a failed or cancelled read
+        // leaves the firmware untouched. Reading checks for cancellation between chunks.
+        let read = || {
+            ShimInput::from(request.source)
+                .into_owned_data_buffer_with_progress(|done, total| {
+                    progress(LoadStage::Read, done, total)
+                })
+                .context("unable to convert input to loaded data buffer")
+        };
+        let input = match cancel {
+            Some(cancel) => deadline::with_cancel(cancel, read)?,
+            None => read()?,
+        };
+
+        // The image is read once, then reused for verification and LoadImage.
+        let buffer = input.buffer().context("unable to get buffer from input")?;
+        let file_path = input.file_path();
+
+        // Images for another architecture are refused before the firmware is involved, as
+        // firmware fails to load them with errors that don't tell the cause.
+        crate::pe::check_native(buffer)?;
+
         // Determine whether Secure Boot is enabled.
         let secure_boot =
             SecureBoot::enabled().context("unable to determine if secure boot is enabled")?;
@@ -100,22 +188,21 @@ impl ImageLoader {
             ShimSupport::retain()?;
         }
 
-        // Clone the current image handle to use for loading the image.
-        let current_image = *request.current_image();
-
-        // Converts the source to a shim input with an owned data buffer.
-        let input = ShimInput::from(request.into_source())
-            .into_owned_data_buffer()
-            .context("unable to convert input to loaded data buffer")?;
+        // If the security hook is installed, the firmware may ask it to verify the image
+        // by path alone. Point it at the buffer so the image is not read from disk again.
+        if requires_security_hook && let Some(file_path) = file_path {
+            // SAFETY: The buffer outlives the call to LoadImage, after which it is cleared.
+            unsafe { SecurityHook::set_loading_image(file_path, buffer) };
+        }
 
         // Constructs a LoadImageSource from the input.
-        let source = LoadImageSource::FromBuffer {
-            buffer: input.buffer().context("unable to get buffer from input")?,
-            file_path: input.file_path(),
-        };
+        let source = LoadImageSource::FromBuffer { buffer, file_path };
 
         // Loads the image using Boot Services LoadImage function.
+        progress(LoadStage::Load, 0, 1);
         let result = uefi::boot::load_image(current_image, source).context("unable to load image");
+        SecurityHook::clear_loading_image();
+        progress(LoadStage::Load, 1, 1);
 
         // If the security override is required, we will uninstall the security hook.
         if requires_security_hook {
@@ -1,88 +1,165 @@
 //! Based on: https://github.com/rust-osdev/uefi-rs/blob/main/uefi/src/helpers/logger.rs
 
+use crate::platform::clock::PlatformClock;
+use crate::platform::timer::PlatformTimer;
+use crate::variables::{VariableClass, VariableController};
+use alloc::boxed::Box;
 use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use anyhow::{Context, Result, bail};
 use core::fmt::Write;
-use core::ptr;
-use core::sync::atomic::{AtomicPtr, Ordering};
-use log::{Log, Record};
-use uefi::proto::console::text::Output;
+use core::sync::atomic::{AtomicBool, Ordering};
+use edera_sprout_parsing::datetime::format_datetime;
+use log::{LevelFilter, Log, Record};
+use spin::Mutex;
+
+pub use console::ConsoleSink;
+pub use file::FileSink;
+pub use memory::MemorySink;
+pub use serial::SerialSink;
+
+/// console: Sink that writes to the console text output.
+pub mod console;
+
+/// file: Sink that writes to a file on the EFI partition.
+pub mod file;
+
+/// memory: Sink that retains the most recent lines in memory.
+pub mod memory;
+
+/// serial: Sink that writes to a serial port.
+pub mod serial;
 
 /// The global logger object.
 static LOGGER: Logger = Logger::new();
 
+/// The name of the Sprout vendor variable that the log buffer is published to.
+/// The OS can read this variable from `/sys/firmware/efi/efivars` to collect the boot log.
+const LOG_VARIABLE_NAME: &str = "SproutLog";
+
+/// A destination of log lines, like the console or a serial port.
+/// Sinks are called while the logger is locked, so they must never log themselves.
+pub trait LogSink: Send {
+    /// The name of the sink, which is used to configure it at runtime.
+    fn name(&self) -> &str;
+
+    /// Writes the formatted `line`, which does not include a line ending.
+    /// Errors are ignored, as there is nowhere to report them.
+    fn write_line(&mut self, line: &str);
+
+    /// Flushes the lines buffered by the sink, if any.
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A [LogSink] registered with the logger, along with its configuration.
+struct RegisteredSink {
+    /// The sink to write to.
+    sink: Box<dyn LogSink>,
+    /// The maximum level of the lines written to the sink.
+    level: LevelFilter,
+    /// Whether lines are written to the sink.
+    enabled: bool,
+}
+
 /// Logging mechanism for Sprout.
-/// Must be initialized to be used, as we use atomic pointers to store the output to write to.
+/// Log lines are written to every registered [LogSink] whose level includes the line.
+/// Each line is prefixed with the time elapsed since the timer was set, if one is set,
+/// and optionally with the time of the real-time clock.
 pub struct Logger {
-    writer: AtomicPtr<Output>,
+    sinks: Mutex<Vec<RegisteredSink>>,
+    timer: Mutex<Option<PlatformTimer>>,
+    wall_clock: AtomicBool,
 }
 
 impl Default for Logger {
-    /// Creates a default logger, which is uninitialized with an output.
+    /// Creates a default logger, which has no sinks.
     fn default() -> Self {
         Self::new()
     }
 }
 
 impl Logger {
-    /// Create a new logger with an output not specified.
-    /// This will cause the logger to not print anything until it is configured.
+    /// Create a new logger without any sinks.
+    /// This will cause the logger to not write anything until sinks are registered.
     pub const fn new() -> Self {
         Self {
-            writer: AtomicPtr::new(ptr::null_mut()),
+            sinks: Mutex::new(Vec::new()),
+            timer: Mutex::new(None),
+            wall_clock: AtomicBool::new(false),
         }
     }
 
-    /// Retrieves the pointer to the output.
-    /// SAFETY: This pointer might be null, it should be checked before use.
-    #[must_use]
-    fn output(&self) -> *mut Output {
-        self.writer.load(Ordering::Acquire)
-    }
+    /// Formats the prefix of the lines of a message logged right now.
+    /// This includes the real-time clock time if enabled and the elapsed time if a timer is set.
+    fn prefix(&self) -> String {
+        let mut prefix = String::new();
+
+        // The real-time clock is only read if enabled, as it is a runtime service call.
+        // If the clock can't be read, the time is left out rather than failing to log.
+        if self.wall_clock.load(Ordering::Acquire)
+            && let Ok(now) = PlatformClock::now()
+        {
+            let _ = write!(prefix, "{} ", format_datetime(&now, "%Y-%m-%dT%H:%M:%S"));
+        }
 
-    /// Sets the output to write to.
-    ///
-    /// # Safety
-    /// This function is unsafe because the output is technically leaked and unmanaged.
-    pub unsafe fn set_output(&self, output: *mut Output) {
-        self.writer.store(output, Ordering::Release);
+        // Copy the timer out of the lock, as it is cheap to copy.
+        let timer = *self.timer.lock();
+        if let Some(timer) = timer {
+            let _ = write!(
+                prefix,
+                "[{:>11.6}] ",
+                timer.elapsed_since_start().as_secs_f64()
+            );
+        }
+        prefix
     }
 }
 
 impl Log for Logger {
-    /// Enable the logger always.
+    /// Enable the logger always, as each sink filters the lines itself.
     fn enabled(&self, _metadata: &log::Metadata<'_>) -> bool {
         true
     }
 
-    /// Log the specified `record` to the output if one is set.
+    /// Log the specified `record` to every sink that is enabled for its level.
     fn log(&self, record: &Record) {
-        // Acquire the output. If one is not set, we do nothing.
-        let Some(output) = (unsafe { self.output().as_mut() }) else {
-            return;
-        };
-
         // Format the log message.
         let message = format!("{}", record.args());
 
-        // Iterate over every line, formatting the message and writing it to the output.
-        for line in message.lines() {
-            // The format writes the log level in front of every line of text.
-            let _ = writeln!(output, "[{:>5}] {}", record.level(), line);
+        // Format every line of the message, which are all prefixed with the same time.
+        // The format writes the log level in front of every line of text.
+        let prefix = self.prefix();
+        let lines = message
+            .lines()
+            .map(|line| format!("{}[{:>5}] {}", prefix, record.level(), line))
+            .collect::<Vec<_>>();
+
+        let mut sinks = self.sinks.lock();
+        for registered in sinks.iter_mut() {
+            if !registered.enabled || record.level() > registered.level {
+                continue;
+            }
+
+            for line in &lines {
+                registered.sink.write_line(line);
+            }
         }
     }
 
-    /// This log is not buffered, so flushing isn't required.
-    fn flush(&self) {}
+    /// Flush all the sinks, ignoring errors as there is nowhere to report them.
+    fn flush(&self) {
+        let _ = flush();
+    }
 }
 
 /// Initialize the logging environment, calling panic if something goes wrong.
 pub fn init() {
-    // Retrieve the stdout handle and set it as the output for the global logger.
-    uefi::system::with_stdout(|stdout| unsafe {
-        // SAFETY: We are using the stdout handle to create a pointer to the output.
-        // The handle is global and is guaranteed to be valid for the lifetime of the program.
-        LOGGER.set_output(stdout);
-    });
+    // Register the default sinks, which write to the console and the memory buffer.
+    register(ConsoleSink::stdout(), LevelFilter::Trace);
+    register(MemorySink, LevelFilter::Trace);
 
     // Set the logger to the global logger.
     if let Err(error) = log::set_logger(&LOGGER) {
@@ -92,3 +169,111 @@ pub fn init() {
     // Set the max level to the level specified by the log features.
     log::set_max_level(log::STATIC_MAX_LEVEL);
 }
+
+/// Sets the maximum `level` of log messages.
+/// Messages above the level Sprout was built with are never logged, regardless of `level`.
+pub fn set_level(level: LevelFilter) {
+    log::set_max_level(level.min(log::STATIC_MAX_LEVEL));
+}
+
+/// Sets the `timer` that the elapsed time prefixed to every log line is measured with.
+/// Sharing the timer of the boot timing report lines up the log with the timing spans.
+pub fn set_timer(timer: PlatformTimer) {
+    *LOGGER.timer.lock() = Some(timer);
+}
+
+/// Sets whether every log line is prefixed with the time of the real-time clock.
+pub fn set_wall_clock(enabled: bool) {
+    LOGGER.wall_clock.store(enabled, Ordering::Release);
+}
+
+/// Registers the `sink`, which receives lines up to `level`.
+/// A sink that was registered with the same name is replaced.
+pub fn register(sink: impl LogSink + 'static, level: LevelFilter) {
+    let mut sinks = LOGGER.sinks.lock();
+    sinks.retain(|registered| registered.sink.name() != sink.name());
+    sinks.push(RegisteredSink {
+        sink: Box::new(sink),
+        level,
+        enabled: true,
+    });
+}
+
+/// Runs `operation` on the registered sink with the specified `name`.
+fn with_sink(name: &str, operation: impl FnOnce(&mut RegisteredSink)) -> Result<()> {
+    let mut sinks = LOGGER.sinks.lock();
+    let Some(registered) = sinks
+        .iter_mut()
+        .find(|registered| registered.sink.name() == name)
+    else {
+        // The lock must be released before returning, as the caller may log the error.
+        drop(sinks);
+        bail!("unknown log sink '{}'", name);
+    };
+    operation(registered);
+    Ok(())
+}
+
+/// Sets the maximum `level` of the lines written to the sink with the specified `name`.
+pub fn set_sink_level(name: &str, level: LevelFilter) -> Result<()> {
+    with_sink(name, |registered| registered.level = level)
+}
+
+/// Sets whether lines are written to the sink with the specified `name`.
+pub fn set_sink_enabled(name: &str, enabled: bool) -> Result<()> {
+    with_sink(name, |registered| registered.enabled = enabled)
+}
+
+/// Flushes the lines buffered by every enabled sink, like the lines of the file sink.
+/// The sinks are taken out of the logger while flushing, so sinks that log while flushing
+/// do not deadlock. Lines logged while flushing are not written to the flushed sinks.
+pub fn flush() -> Result<()> {
+    let mut flushed = core::mem::take(&mut *LOGGER.sinks.lock());
+
+    let mut result = Ok(());
+    for registered in flushed.iter_mut().filter(|registered| registered.enabled) {
+        if let Err(error) = registered.sink.flush() {
+            let name = registered.sink.name().to_string();
+            result = Err(error.context(format!("unable to flush log sink '{}'", name)));
+        }
+    }
+
+    // Restore the flushed sinks in front of any sinks registered while flushing.
+    let mut sinks = LOGGER.sinks.lock();
+    let registered = core::mem::replace(&mut *sinks, flushed);
+    sinks.extend(registered);
+    result
+}
+
+/// Writes the log lines retained in the memory buffer to the sink with the specified `name`.
+/// This re-emits the early log, like after the log level is raised.
+pub fn replay(name: &str) -> Result<()> {
+    // Copy the buffer first, as the sink lock is held while replaying.
+    let content = buffered();
+    let content = String::from_utf8_lossy(&content);
+    with_sink(name, |registered| {
+        for line in content.lines() {
+            registered.sink.write_line(line);
+        }
+    })
+}
+
+/// Acquires a copy of the log lines retained in the memory buffer, as UTF-8 bytes.
+pub fn buffered() -> Vec<u8> {
+    memory::buffered()
+}
+
+/// Publishes the log buffer to the `SproutLog` Sprout vendor variable, which allows the booted OS
+/// to collect the boot log without writes to the EFI partition.
+/// The variable is volatile, so it only reflects the log of the current boot.
+pub fn publish() -> Result<()> {
+    // Copy the buffer first, as the lock must not be held if the variable write logs.
+    let content = buffered();
+    VariableController::SPROUT
+        .set(
+            LOG_VARIABLE_NAME,
+            &content,
+            VariableClass::BootAndRuntimeTemporary,
+        )
+        .context("unable to publish log buffer")
+}
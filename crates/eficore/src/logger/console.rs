@@ -0,0 +1,37 @@
+use crate::logger::LogSink;
+use core::fmt::Write;
+use uefi::proto::console::text::Output;
+
+/// The name of the console sink.
+pub const CONSOLE_SINK_NAME: &str = "console";
+
+/// A [LogSink] that writes lines to a console text output.
+pub struct ConsoleSink {
+    output: *mut Output,
+}
+
+// SAFETY: UEFI applications run on a single processor, and the output is global
+// for the lifetime of the program.
+unsafe impl Send for ConsoleSink {}
+
+impl ConsoleSink {
+    /// Creates a sink that writes to the stdout handle of the system table.
+    pub fn stdout() -> Self {
+        let output = uefi::system::with_stdout(|stdout| stdout as *mut Output);
+        Self { output }
+    }
+}
+
+impl LogSink for ConsoleSink {
+    fn name(&self) -> &str {
+        CONSOLE_SINK_NAME
+    }
+
+    fn write_line(&mut self, line: &str) {
+        // SAFETY: The stdout handle is guaranteed to be valid for the lifetime of the program.
+        let Some(output) = (unsafe { self.output.as_mut() }) else {
+            return;
+        };
+        let _ = writeln!(output, "{}", line);
+    }
+}
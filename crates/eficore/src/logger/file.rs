@@ -0,0 +1,66 @@
+use crate::logger::LogSink;
+use crate::path::resolve_path;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use anyhow::{Context, Result};
+use uefi::CString16;
+use uefi::fs::{FileSystem, Path};
+use uefi::proto::device_path::DevicePath;
+use uefi::proto::device_path::text::{AllowShortcuts, DisplayOnly};
+use uefi::proto::media::fs::SimpleFileSystem;
+
+/// The name of the file sink.
+pub const FILE_SINK_NAME: &str = "file";
+
+/// A [LogSink] that writes lines to a file, usually on the EFI partition.
+/// Lines are buffered in memory and the whole log is written to the file when flushed,
+/// as writing to the filesystem for every line would be slow.
+pub struct FileSink {
+    /// The root path that the file path is resolved against.
+    root: Box<DevicePath>,
+    /// The path to the file to write.
+    path: String,
+    /// The log lines written to the sink, which are written to the file when flushed.
+    content: String,
+}
+
+impl FileSink {
+    /// Creates a sink that writes to `path`, which is resolved against the `root` path.
+    pub fn new(root: &DevicePath, path: impl ToString) -> Self {
+        Self {
+            root: root.to_boxed(),
+            path: path.to_string(),
+            content: String::new(),
+        }
+    }
+}
+
+impl LogSink for FileSink {
+    fn name(&self) -> &str {
+        FILE_SINK_NAME
+    }
+
+    fn write_line(&mut self, line: &str) {
+        self.content.push_str(line);
+        self.content.push('\n');
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        let resolved = resolve_path(Some(&self.root), &self.path)
+            .context("unable to resolve log file path")?;
+        let path: CString16 = resolved
+            .sub_path
+            .to_string16(DisplayOnly(false), AllowShortcuts(false))
+            .context("unable to convert log file path to string")?;
+
+        // Open exclusive access to the filesystem.
+        let fs =
+            uefi::boot::open_protocol_exclusive::<SimpleFileSystem>(resolved.filesystem_handle)
+                .context("unable to open filesystem")?;
+        let mut fs = FileSystem::new(fs);
+
+        // The whole log is rewritten, so the file always contains the complete log.
+        fs.write(Path::new(&path), self.content.as_bytes())
+            .context("unable to write log file")
+    }
+}
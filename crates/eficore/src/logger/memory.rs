@@ -0,0 +1,71 @@
+use crate::logger::LogSink;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use spin::Mutex;
+
+/// The name of the memory sink.
+pub const MEMORY_SINK_NAME: &str = "memory";
+
+/// The maximum number of bytes retained in the log buffer.
+/// This is kept small enough to fit in the volatile variable storage of most firmware.
+const LOG_BUFFER_CAPACITY: usize = 32 * 1024;
+
+/// The log buffer that the memory sink writes to.
+/// This is global so the buffer can be read while the sink is registered with the logger.
+static BUFFER: Mutex<LogBuffer> = Mutex::new(LogBuffer::new());
+
+/// A ring buffer of the most recent log lines.
+/// When the buffer is full, the oldest lines are discarded.
+struct LogBuffer {
+    /// The UTF-8 bytes of the buffered log lines.
+    bytes: VecDeque<u8>,
+}
+
+impl LogBuffer {
+    /// Creates an empty log buffer.
+    const fn new() -> Self {
+        Self {
+            bytes: VecDeque::new(),
+        }
+    }
+
+    /// Appends `line` to the buffer, discarding the oldest lines if it is over capacity.
+    fn push_line(&mut self, line: &str) {
+        self.bytes.extend(line.as_bytes());
+        self.bytes.push_back(b'\n');
+
+        let excess = self.bytes.len().saturating_sub(LOG_BUFFER_CAPACITY);
+        if excess == 0 {
+            return;
+        }
+        self.bytes.drain(..excess);
+
+        // Discard the remainder of the partially discarded line, so the buffer starts with a line.
+        let partial = self
+            .bytes
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .map(|index| index + 1)
+            .unwrap_or(self.bytes.len());
+        self.bytes.drain(..partial);
+    }
+}
+
+/// A [LogSink] that retains the most recent log lines in memory,
+/// which can be published to the booted OS.
+pub struct MemorySink;
+
+impl LogSink for MemorySink {
+    fn name(&self) -> &str {
+        MEMORY_SINK_NAME
+    }
+
+    fn write_line(&mut self, line: &str) {
+        BUFFER.lock().push_line(line);
+    }
+}
+
+/// Acquires a copy of the log lines retained in the log buffer, as UTF-8 bytes.
+pub fn buffered() -> Vec<u8> {
+    BUFFER.lock().bytes.iter().copied().collect()
+}
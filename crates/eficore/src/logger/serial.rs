@@ -0,0 +1,42 @@
+use crate::disk::open_shared;
+use crate::logger::LogSink;
+use anyhow::{Context, Result};
+use uefi::Handle;
+use uefi::proto::console::serial::Serial;
+
+/// The name of the serial sink.
+pub const SERIAL_SINK_NAME: &str = "serial";
+
+/// A [LogSink] that writes lines to a serial port.
+pub struct SerialSink {
+    handle: Handle,
+}
+
+// SAFETY: UEFI applications run on a single processor, and the handle is only used
+// to open the serial protocol.
+unsafe impl Send for SerialSink {}
+
+impl SerialSink {
+    /// Creates a sink that writes to the first serial port of the system.
+    pub fn first() -> Result<Self> {
+        let handle = uefi::boot::get_handle_for_protocol::<Serial>()
+            .context("unable to find a serial port")?;
+        Ok(Self { handle })
+    }
+}
+
+impl LogSink for SerialSink {
+    fn name(&self) -> &str {
+        SERIAL_SINK_NAME
+    }
+
+    fn write_line(&mut self, line: &str) {
+        // The serial port is shared with the console driver of the firmware,
+        // so it must not be opened exclusively.
+        let Ok(mut serial) = open_shared::<Serial>(self.handle) else {
+            return;
+        };
+        let _ = serial.write(line.as_bytes());
+        let _ = serial.write(b"\r\n");
+    }
+}
@@ -0,0 +1,106 @@
+use anyhow::{Result, bail};
+use edera_sprout_parsing::manifest::Manifest;
+use edera_sprout_parsing::url::is_http_url;
+use log::warn;
+use spin::Mutex;
+use uefi::Handle;
+
+/// A manifest that the files read from a filesystem are checked against.
+struct ActiveManifest {
+    /// The digests of the files of the filesystem.
+    manifest: Manifest,
+    /// The handle of the filesystem the manifest describes, usually the ESP.
+    filesystem: Handle,
+    /// Whether a file that does not match the manifest fails to read,
+    /// instead of only logging a warning.
+    enforce: bool,
+}
+
+// SAFETY: UEFI applications run on a single processor, and the filesystem handle is only
+// compared while boot services are active.
+unsafe impl Send for ActiveManifest {}
+
+/// The manifest that files are checked against, once one was activated.
+static ACTIVE: Mutex<Option<ActiveManifest>> = Mutex::new(None);
+
+/// Activates the `manifest` of the filesystem with the `filesystem` handle, so every file that
+/// is read from the filesystem afterward is checked against it. If `enforce` is set, files that
+/// are not in the manifest or do not match their digest fail to read.
+pub fn activate(manifest: Manifest, filesystem: Handle, enforce: bool) {
+    *ACTIVE.lock() = Some(ActiveManifest {
+        manifest,
+        filesystem,
+        enforce,
+    });
+}
+
+/// Whether a manifest was activated.
+pub fn is_active() -> bool {
+    ACTIVE.lock().is_some()
+}
+
+/// Whether a manifest was activated that fails files which do not match it.
+/// Files that are written at runtime can't be in the manifest, so they are not read while
+/// the manifest is enforced.
+pub fn is_enforced() -> bool {
+    ACTIVE.lock().as_ref().is_some_and(|active| active.enforce)
+}
+
+/// Checks that the configuration at `path` may be loaded, when it is a URL.
+/// Downloaded content is not covered by the manifest, and nothing else authenticates it,
+/// so URLs are refused while the manifest is enforced or `secure_boot` is enabled.
+/// Otherwise, a server could supply a configuration that boots anything.
+pub fn check_download(path: &str, secure_boot: bool) -> Result<()> {
+    if !is_http_url(path) {
+        return Ok(());
+    }
+    if is_enforced() {
+        bail!(
+            "refusing to download configuration from {} while the esp manifest is enforced",
+            path
+        );
+    }
+    if secure_boot {
+        bail!(
+            "refusing to download configuration from {} while secure boot is enabled",
+            path
+        );
+    }
+    Ok(())
+}
+
+/// Checks the `data` of the file at `path` read from the filesystem with the `filesystem`
+/// handle against the active manifest. Files of other filesystems are not checked.
+pub(crate) fn check(filesystem: Handle, path: &str, data: &[u8]) -> Result<()> {
+    let active = ACTIVE.lock();
+    let Some(active) = active
+        .as_ref()
+        .filter(|active| active.filesystem == filesystem)
+    else {
+        return Ok(());
+    };
+    let problem = match active.manifest.digest(path) {
+        None => "is not in the manifest",
+        Some(expected) if *expected != crate::hash::sha256(data) => "does not match the manifest",
+        Some(_) => return Ok(()),
+    };
+    if active.enforce {
+        bail!("file {} {}", path, problem);
+    }
+    warn!("file {} {}", path, problem);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downloads_are_refused_under_secure_boot() {
+        let url = "https://boot.example.com/sprout.toml";
+        assert!(check_download(url, true).is_err());
+        assert!(check_download("http://boot.example.com/sprout.toml", true).is_err());
+        assert!(check_download(url, false).is_ok());
+        assert!(check_download("\\sprout.toml", true).is_ok());
+    }
+}
@@ -1,8 +1,11 @@
+use crate::memory;
+use crate::pages::PageBuffer;
 use alloc::boxed::Box;
+use alloc::format;
 use alloc::vec::Vec;
 use anyhow::{Context, Result, bail};
 use core::ffi::c_void;
-use core::ptr;
+use core::mem::ManuallyDrop;
 use log::error;
 use uefi::proto::device_path::DevicePath;
 use uefi::proto::device_path::build::DevicePathBuilder;
@@ -42,6 +45,40 @@ pub struct MediaLoaderHandle {
     protocol: *mut MediaLoaderProtocol,
     /// The device path pointer.
     path: *mut DevicePath,
+    /// The data of the media loader, which is freed when the media loader is uninstalled.
+    data: Option<MediaLoaderData>,
+    /// The identifier of the tracked allocation of the data.
+    allocation: u64,
+}
+
+/// The data provided by a media loader.
+pub enum MediaLoaderData {
+    /// The data is in a heap buffer.
+    Boxed(Box<[u8]>),
+    /// The data is in page-aligned memory, like a file read with [crate::path::ResolvedPath::read_file_pages].
+    Pages(PageBuffer),
+}
+
+impl MediaLoaderData {
+    /// Acquires the bytes of the data.
+    fn bytes(&self) -> &[u8] {
+        match self {
+            MediaLoaderData::Boxed(data) => data,
+            MediaLoaderData::Pages(data) => data,
+        }
+    }
+}
+
+impl From<Box<[u8]>> for MediaLoaderData {
+    fn from(value: Box<[u8]>) -> Self {
+        MediaLoaderData::Boxed(value)
+    }
+}
+
+impl From<PageBuffer> for MediaLoaderData {
+    fn from(value: PageBuffer) -> Self {
+        MediaLoaderData::Pages(value)
+    }
 }
 
 impl MediaLoaderHandle {
@@ -53,7 +90,7 @@ impl MediaLoaderHandle {
     /// data into that buffer, checking whether it is safe to copy based on
     /// the buffer size.
     ///
-    /// SAFETY: `this.address` and `this.length` are set by leaking a Box<[u8]>, so we can
+    /// SAFETY: `this.address` and `this.length` are set from the data owned by the handle, so we can
     /// be sure their pointers are valid when this is called. The caller must call this function
     /// while inside UEFI boot services to ensure pointers are valid. Copying to `buffer` is
     /// assumed valid because the caller must ensure `buffer` is valid by function contract.
@@ -146,7 +183,9 @@ impl MediaLoaderHandle {
     /// Registers the provided `data` with the UEFI stack as media loader.
     /// This uses a special device path that other EFI programs will look at
     /// to load the data from.
-    pub fn register(guid: Guid, data: Box<[u8]>) -> Result<MediaLoaderHandle> {
+    pub fn register(guid: Guid, data: impl Into<MediaLoaderData>) -> Result<MediaLoaderHandle> {
+        let data = data.into();
+
         // Acquire the vendor device path for the media loader.
         let path = Self::device_path(guid)?;
 
@@ -187,14 +226,12 @@ impl MediaLoaderHandle {
             }
         };
 
-        // Leak the data we need to pass to the UEFI stack.
-        let data = Box::leak(data);
-
         // Allocate a new box for the protocol interface.
+        // The data is owned by the handle, so the pointer stays valid until it is uninstalled.
         let protocol = Box::new(MediaLoaderProtocol {
             load_file: Self::load_file,
-            address: data.as_ptr() as *mut _,
-            length: data.len(),
+            address: data.bytes().as_ptr() as *mut _,
+            length: data.bytes().len(),
         });
 
         // Leak the protocol interface to pass it to the UEFI stack.
@@ -230,14 +267,12 @@ impl MediaLoaderHandle {
 
             // SAFETY: We know that the protocol is leaked, so we can safely take a reference to it.
             let protocol = unsafe { Box::from_raw(protocol) };
-            // SAFETY: We know that the data is leaked, so we can safely take a reference to it.
-            let data = unsafe { Box::from_raw(data) };
             // SAFETY: We know that the path is leaked, so we can safely take a reference to it.
             let path = unsafe { Box::from_raw(path) };
 
             // Drop all the allocations explicitly to clarify the lifetime.
+            // The data is dropped when the error is returned.
             drop(protocol);
-            drop(data);
             drop(path);
         }
 
@@ -245,17 +280,30 @@ impl MediaLoaderHandle {
         // We should have already cleaned up after ourselves, so this is safe.
         secondary_handle.context("unable to install media loader load file handle")?;
 
+        // Track the data, as it stays allocated until the media loader is unregistered.
+        let allocation = memory::track(format!("media loader {}", guid), protocol.length);
+
         // Return a handle to the media loader.
         Ok(Self {
             handle: primary_handle,
             protocol,
             path,
+            data: Some(data),
+            allocation,
         })
     }
 
-    /// Unregisters a media loader from the UEFI stack.
+    /// Unregisters the media loader from the UEFI stack, returning any error
+    /// instead of logging it like `drop` does.
+    pub fn unregister(self) -> Result<()> {
+        // Avoid running drop, which would unregister the media loader a second time.
+        let mut handle = ManuallyDrop::new(self);
+        handle.uninstall()
+    }
+
+    /// Uninstalls a media loader from the UEFI stack.
     /// This will free the memory allocated by the passed data.
-    fn unregister(&self) -> Result<()> {
+    fn uninstall(&mut self) -> Result<()> {
         // SAFETY: We know that the media loader is registered if the handle is valid,
         // so we can safely uninstall it.
         // We should have allocated the pointers involved, so we can safely free them.
@@ -280,15 +328,12 @@ impl MediaLoaderHandle {
             let path = Box::from_raw(self.path);
             let protocol = Box::from_raw(self.protocol);
 
-            // Retrieve a box for the data we passed in.
-            let slice = ptr::slice_from_raw_parts_mut(protocol.address as *mut u8, protocol.length);
-            let data = Box::from_raw(slice);
-
             // Drop all the allocations explicitly, as we don't want to leak them.
             drop(path);
             drop(protocol);
-            drop(data);
         }
+        drop(self.data.take());
+        memory::untrack(self.allocation);
 
         Ok(())
     }
@@ -299,7 +344,7 @@ impl Drop for MediaLoaderHandle {
     fn drop(&mut self) {
         // If unregister fails, print an error to the log.
         // This may leak stuff, but the only other option is to panic.
-        if let Err(error) = self.unregister() {
+        if let Err(error) = self.uninstall() {
             error!("unable to unregister media loader: {}", error);
         }
     }
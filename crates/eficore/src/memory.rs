@@ -0,0 +1,146 @@
+use crate::pages::PAGE_SIZE;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use anyhow::{Context, Result};
+use core::fmt::Write;
+use core::sync::atomic::{AtomicU64, Ordering};
+use log::info;
+use spin::Mutex;
+use uefi::boot::MemoryType;
+use uefi::mem::memory_map::MemoryMap;
+
+/// A large allocation made by Sprout, like an initrd buffer.
+/// Large allocations are tracked so memory exhaustion on small systems can be diagnosed.
+#[derive(Clone, Debug)]
+pub struct TrackedAllocation {
+    /// The identifier of the allocation, which is used to stop tracking it.
+    pub id: u64,
+    /// The name of the allocation, like `media loader` and its GUID.
+    pub name: String,
+    /// The size of the allocation in bytes.
+    pub size: usize,
+}
+
+/// The large allocations that are currently alive.
+static ALLOCATIONS: Mutex<Vec<TrackedAllocation>> = Mutex::new(Vec::new());
+
+/// The identifier of the next tracked allocation.
+static NEXT_ALLOCATION_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Starts tracking a large allocation called `name` of `size` bytes.
+/// Returns the identifier that is passed to [untrack] when the allocation is freed.
+pub fn track(name: impl ToString, size: usize) -> u64 {
+    let id = NEXT_ALLOCATION_ID.fetch_add(1, Ordering::Relaxed);
+    ALLOCATIONS.lock().push(TrackedAllocation {
+        id,
+        name: name.to_string(),
+        size,
+    });
+    id
+}
+
+/// Stops tracking the allocation with the specified `id`, as it was freed.
+pub fn untrack(id: u64) {
+    ALLOCATIONS.lock().retain(|allocation| allocation.id != id);
+}
+
+/// Acquires a copy of the large allocations that are currently alive.
+pub fn tracked() -> Vec<TrackedAllocation> {
+    ALLOCATIONS.lock().clone()
+}
+
+/// A summary of the UEFI memory map, in pages.
+#[derive(Clone, Debug, Default)]
+pub struct MemorySummary {
+    /// The pages described by the memory map.
+    pub total_pages: u64,
+    /// The pages that are free to allocate.
+    pub free_pages: u64,
+    /// The pages of the largest free region, which bounds the largest possible allocation.
+    pub largest_free_pages: u64,
+    /// The pages of loaded images and their allocations, including Sprout itself.
+    pub loader_pages: u64,
+    /// The pages of boot services drivers and their allocations.
+    pub boot_services_pages: u64,
+    /// The pages of runtime services drivers, which remain in use after boot.
+    pub runtime_services_pages: u64,
+    /// The pages of other memory types, like reserved and ACPI memory.
+    pub other_pages: u64,
+}
+
+impl MemorySummary {
+    /// Summarizes the current UEFI memory map.
+    pub fn current() -> Result<Self> {
+        let map =
+            uefi::boot::memory_map(MemoryType::LOADER_DATA).context("unable to get memory map")?;
+
+        let mut summary = Self::default();
+        for descriptor in map.entries() {
+            let pages = descriptor.page_count;
+            summary.total_pages += pages;
+            match descriptor.ty {
+                MemoryType::CONVENTIONAL => {
+                    summary.free_pages += pages;
+                    summary.largest_free_pages = summary.largest_free_pages.max(pages);
+                }
+                MemoryType::LOADER_CODE | MemoryType::LOADER_DATA => summary.loader_pages += pages,
+                MemoryType::BOOT_SERVICES_CODE | MemoryType::BOOT_SERVICES_DATA => {
+                    summary.boot_services_pages += pages
+                }
+                MemoryType::RUNTIME_SERVICES_CODE | MemoryType::RUNTIME_SERVICES_DATA => {
+                    summary.runtime_services_pages += pages
+                }
+                _ => summary.other_pages += pages,
+            }
+        }
+        Ok(summary)
+    }
+
+    /// Formats the summary as a table of the pages and sizes of each category.
+    pub fn table(&self) -> String {
+        let rows = [
+            ("total", self.total_pages),
+            ("free", self.free_pages),
+            ("largest free", self.largest_free_pages),
+            ("loader", self.loader_pages),
+            ("boot services", self.boot_services_pages),
+            ("runtime services", self.runtime_services_pages),
+            ("other", self.other_pages),
+        ];
+
+        let mut table = String::new();
+        // Writing to a string can't fail, so the results are ignored.
+        let _ = write!(table, "{:<16}  {:>10}  {:>10}", "memory", "pages", "KiB");
+        for (name, pages) in rows {
+            let _ = write!(
+                table,
+                "\n{:<16}  {:>10}  {:>10}",
+                name,
+                pages,
+                pages * PAGE_SIZE as u64 / 1024
+            );
+        }
+        table
+    }
+}
+
+/// Logs the summary of the UEFI memory map and the large allocations of Sprout.
+pub fn log() -> Result<()> {
+    let summary = MemorySummary::current()?;
+    info!("memory usage:\n{}", summary.table());
+
+    let allocations = tracked();
+    let total = allocations
+        .iter()
+        .map(|allocation| allocation.size)
+        .sum::<usize>();
+    info!(
+        "{} large allocation(s), {} KiB in total:",
+        allocations.len(),
+        total / 1024
+    );
+    for allocation in allocations {
+        info!("  {}: {} KiB", allocation.name, allocation.size / 1024);
+    }
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use crate::services::services;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use anyhow::{Context, Result};
+use core::mem::{MaybeUninit, size_of};
+use core::ptr;
+use edera_sprout_parsing::dhcp::parse_options;
+use log::info;
+use uefi::proto::unsafe_protocol;
+use uefi::{Handle, StatusExt};
+use uefi_raw::protocol::driver::ServiceBindingProtocol;
+use uefi_raw::protocol::network::dhcp4::{Dhcp4Header, Dhcp4ModeData, Dhcp4Protocol, Dhcp4State};
+
+/// The length of an Ethernet hardware address, which is used if the DHCP reply has none.
+const ETHERNET_ADDRESS_LENGTH: usize = 6;
+
+/// EFI_DHCP4_SERVICE_BINDING protocol definition.
+#[unsafe_protocol(Dhcp4Protocol::SERVICE_BINDING_GUID)]
+struct Dhcp4ServiceBinding(ServiceBindingProtocol);
+
+/// EFI_DHCP4 protocol definition.
+#[unsafe_protocol(Dhcp4Protocol::GUID)]
+struct Dhcp4(Dhcp4Protocol);
+
+/// The configuration of a network interface that was assigned by DHCP.
+#[derive(Clone, Debug, Default)]
+pub struct NetworkInfo {
+    /// The hardware address of the interface.
+    pub mac: Vec<u8>,
+    /// The IPv4 address of the interface.
+    pub address: [u8; 4],
+    /// The subnet mask of the interface.
+    pub subnet_mask: [u8; 4],
+    /// The default gateway of the interface, if there is one.
+    pub gateway: Option<[u8; 4]>,
+    /// The options of the DHCP reply, keyed by the option code.
+    pub options: BTreeMap<u8, Vec<u8>>,
+}
+
+/// Reads the DHCP state of the network interface on `interface`.
+/// Returns [None] if the interface has no address that was assigned by DHCP.
+fn dhcp_info(interface: Handle) -> Result<Option<NetworkInfo>> {
+    let mut binding = crate::disk::open_shared::<Dhcp4ServiceBinding>(interface)
+        .context("unable to open dhcp4 service binding")?;
+    let mut child = ptr::null_mut();
+    // SAFETY: The binding is a valid service binding protocol.
+    unsafe { (binding.0.create_child)(&mut binding.0, &mut child) }
+        .to_result()
+        .context("unable to create dhcp4 instance")?;
+    // SAFETY: The handle was just created by the binding.
+    let child = unsafe { Handle::from_ptr(child) }.context("dhcp4 instance has no handle")?;
+
+    // The mode data describes the DHCP state of the interface, not only of the instance.
+    let result = crate::disk::open_shared::<Dhcp4>(child)
+        .context("unable to open dhcp4 protocol")
+        .and_then(|dhcp| {
+            let mut mode = MaybeUninit::<Dhcp4ModeData>::zeroed();
+            // SAFETY: The mode data is written by the driver on success.
+            unsafe { (dhcp.0.get_mode_data)(&dhcp.0, mode.as_mut_ptr()) }
+                .to_result()
+                .context("unable to get dhcp4 mode data")?;
+            // SAFETY: The mode data was written by the driver.
+            let mode = unsafe { mode.assume_init() };
+            // SAFETY: The reply packet is owned by the driver and valid while it is bound.
+            Ok(unsafe { mode_info(&mode) })
+        });
+
+    // SAFETY: The child was created by this binding and its protocol is closed.
+    let _ = unsafe { (binding.0.destroy_child)(&mut binding.0, child.as_ptr()) };
+    result
+}
+
+/// Converts the DHCP `mode` data to [NetworkInfo], copying the options of the reply packet.
+///
+/// # Safety
+/// The reply packet of the `mode` data must be null or point to a valid packet.
+unsafe fn mode_info(mode: &Dhcp4ModeData) -> Option<NetworkInfo> {
+    if mode.state != Dhcp4State::BOUND {
+        return None;
+    }
+
+    let mut mac_length = ETHERNET_ADDRESS_LENGTH;
+    let mut options = BTreeMap::new();
+    if !mode.reply_packet.is_null() {
+        // SAFETY: The packet is valid, as required by the caller. It is packed, so the
+        // fields are read unaligned and the options follow the header and the magic cookie.
+        unsafe {
+            let packet = mode.reply_packet;
+            let length = ptr::read_unaligned(&raw const (*packet).length) as usize;
+            let hw_addr_len = ptr::read_unaligned(&raw const (*packet).header.hw_addr_len);
+            if (1..=16).contains(&hw_addr_len) {
+                mac_length = hw_addr_len as usize;
+            }
+            let options_length = length.saturating_sub(size_of::<Dhcp4Header>() + 4);
+            let options_start = (&raw const (*packet).option).cast::<u8>();
+            options = parse_options(core::slice::from_raw_parts(options_start, options_length));
+        }
+    }
+
+    let router = mode.router_address.0;
+    Some(NetworkInfo {
+        mac: mode.client_mac_address.0[..mac_length].to_vec(),
+        address: mode.client_address.0,
+        subnet_mask: mode.subnet_mask.0,
+        gateway: (router != [0; 4]).then_some(router),
+        options,
+    })
+}
+
+/// Finds the network configuration of the primary network interface, which is the first
+/// interface with an address that was assigned by DHCP. If no interface has one, the first
+/// interface is configured with DHCP. Returns [None] if there is no network interface.
+pub fn primary() -> Result<Option<NetworkInfo>> {
+    let interfaces = services()
+        .find_handles(&Dhcp4Protocol::SERVICE_BINDING_GUID)
+        .context("unable to find dhcp4 network interfaces")?;
+    for interface in &interfaces {
+        if let Some(info) = dhcp_info(*interface)? {
+            return Ok(Some(info));
+        }
+    }
+
+    let Some(interface) = interfaces.first() else {
+        return Ok(None);
+    };
+    info!("no network interface is configured, configuring with dhcp");
+    crate::http::configure_interface(*interface)?;
+    dhcp_info(*interface)
+}
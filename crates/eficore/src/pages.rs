@@ -0,0 +1,83 @@
+use alloc::string::String;
+use anyhow::{Context, Result};
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+use edera_sprout_parsing::filesystem::ReadAt;
+use log::error;
+use uefi::boot::{AllocateType, MemoryType};
+
+/// The size of a page allocated by boot services.
+pub const PAGE_SIZE: usize = 4096;
+
+/// A page-aligned buffer allocated directly from boot services.
+/// Large files, like kernels and initrds, are read straight into a page buffer,
+/// which avoids growing a heap buffer and copying the data between buffers.
+/// The pages are freed when the buffer is dropped.
+pub struct PageBuffer {
+    /// The start of the allocated pages.
+    pointer: NonNull<u8>,
+    /// The number of bytes of the buffer that are in use.
+    length: usize,
+    /// The number of pages that were allocated.
+    pages: usize,
+}
+
+impl PageBuffer {
+    /// Allocates a zeroed buffer of `length` bytes, rounded up to whole pages.
+    pub fn new(length: usize) -> Result<Self> {
+        // At least one page is allocated, so the pointer is always valid.
+        let pages = length.div_ceil(PAGE_SIZE).max(1);
+        let pointer =
+            uefi::boot::allocate_pages(AllocateType::AnyPages, MemoryType::LOADER_DATA, pages)
+                .context("unable to allocate pages")?;
+
+        // SAFETY: The pages were just allocated with the size of `pages`.
+        unsafe {
+            pointer.as_ptr().write_bytes(0, pages * PAGE_SIZE);
+        }
+        Ok(Self {
+            pointer,
+            length,
+            pages,
+        })
+    }
+
+    /// Shortens the buffer to `length` bytes, like when a file was shorter than reported.
+    /// This does nothing if `length` is larger than the buffer.
+    pub fn truncate(&mut self, length: usize) {
+        self.length = self.length.min(length);
+    }
+}
+
+impl Deref for PageBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // SAFETY: The pages are allocated and initialized for at least `length` bytes.
+        unsafe { core::slice::from_raw_parts(self.pointer.as_ptr(), self.length) }
+    }
+}
+
+impl DerefMut for PageBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        // SAFETY: The pages are allocated and initialized for at least `length` bytes,
+        // and the buffer is borrowed mutably.
+        unsafe { core::slice::from_raw_parts_mut(self.pointer.as_ptr(), self.length) }
+    }
+}
+
+impl ReadAt for PageBuffer {
+    fn read_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), String> {
+        self.deref().read_at(offset, buffer)
+    }
+}
+
+impl Drop for PageBuffer {
+    fn drop(&mut self) {
+        // SAFETY: The pages were allocated by this buffer and are no longer referenced.
+        // If freeing fails, the pages are leaked, which is all we can do.
+        if let Err(error) = unsafe { uefi::boot::free_pages(self.pointer, self.pages) } {
+            error!("unable to free pages: {}", error);
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use crate::framebuffer::Framebuffer;
+use crate::logger::{ConsoleSink, LogSink, SerialSink};
+use crate::power::Power;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use anyhow::{Context, Result};
+use core::panic::PanicInfo;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+use spin::Mutex;
+use uefi::proto::console::gop::{BltPixel, GraphicsOutput};
+
+/// The number of character columns that the panic screen is scaled to fit.
+const PANIC_SCREEN_COLUMNS: usize = 80;
+
+/// The background color of the panic screen.
+const PANIC_BACKGROUND: BltPixel = BltPixel::new(0x80, 0x00, 0x00);
+
+/// The text color of the panic screen.
+const PANIC_FOREGROUND: BltPixel = BltPixel::new(0xff, 0xff, 0xff);
+
+/// What to do after a panic has been reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PanicPolicy {
+    /// Stall forever, so the panic stays on screen.
+    Halt,
+    /// Stall for the specified duration, then reboot the system.
+    Reboot(Duration),
+}
+
+/// The policy applied after a panic has been reported.
+static POLICY: Mutex<PanicPolicy> = Mutex::new(PanicPolicy::Halt);
+
+/// Whether a panic is being handled, which guards against panics while reporting a panic.
+static PANICKING: AtomicBool = AtomicBool::new(false);
+
+/// Sets the `policy` applied after a panic has been reported.
+pub fn set_policy(policy: PanicPolicy) {
+    *POLICY.lock() = policy;
+}
+
+/// Handles the panic described by `info`.
+/// The panic is reported on the console, the framebuffer, and the first serial port,
+/// so it can be seen on machines without a display. Then the [PanicPolicy] is applied.
+/// The logger is not used, as the panic may have occurred while the logger was locked.
+pub fn handle(info: &PanicInfo) -> ! {
+    // A panic while reporting a panic can't be reported, so only the policy is applied.
+    if !PANICKING.swap(true, Ordering::AcqRel) {
+        report(info);
+        crate::beep::play(crate::beep::BeepCode::FatalError);
+    }
+
+    // The policy lock may be held by the code that panicked, so fall back to halting.
+    let policy = POLICY
+        .try_lock()
+        .map(|policy| *policy)
+        .unwrap_or(PanicPolicy::Halt);
+    match policy {
+        PanicPolicy::Halt => loop {
+            uefi::boot::stall(Duration::from_secs(1));
+        },
+        PanicPolicy::Reboot(delay) => {
+            uefi::boot::stall(delay);
+            Power::reboot()
+        }
+    }
+}
+
+/// Reports the panic described by `info` on every output that is available.
+fn report(info: &PanicInfo) {
+    let mut lines = Vec::new();
+    lines.push(String::from("sprout panicked"));
+    lines.push(format!("{}", info.message()));
+    if let Some(location) = info.location() {
+        lines.push(format!(
+            "at {}:{}:{}",
+            location.file(),
+            location.line(),
+            location.column()
+        ));
+    }
+
+    // Write the panic to the console and the serial port, ignoring outputs that are unavailable.
+    let mut console = ConsoleSink::stdout();
+    let mut serial = SerialSink::first().ok();
+    for line in &lines {
+        console.write_line(line);
+        if let Some(ref mut serial) = serial {
+            serial.write_line(line);
+        }
+    }
+
+    // The panic was already written to the console, so a failure to draw it can be ignored.
+    let _ = draw(&lines);
+}
+
+/// Draws the `lines` of a panic report in big text on the framebuffer.
+fn draw(lines: &[String]) -> Result<()> {
+    let handle = uefi::boot::get_handle_for_protocol::<GraphicsOutput>()
+        .context("unable to find graphics output")?;
+    let mut gop = uefi::boot::open_protocol_exclusive::<GraphicsOutput>(handle)
+        .context("unable to open graphics output")?;
+    let (width, height) = gop.current_mode_info().resolution();
+    let mut framebuffer = Framebuffer::new(width, height)?;
+    framebuffer.fill(PANIC_BACKGROUND);
+
+    // Scale the text so a line of the panic screen spans the width of the screen.
+    let (unscaled_width, _) = Framebuffer::cell_size(1);
+    let scale = (width / (PANIC_SCREEN_COLUMNS * unscaled_width)).max(1);
+    let (cell_width, cell_height) = Framebuffer::cell_size(scale);
+    let columns = (width / cell_width).saturating_sub(2).max(1);
+
+    // Wrap the lines to the width of the screen, with a margin of one cell.
+    let mut y = cell_height;
+    for line in lines {
+        let chars = line.chars().collect::<Vec<_>>();
+        for chunk in chars.chunks(columns) {
+            let text = chunk.iter().collect::<String>();
+            framebuffer.draw_text(cell_width, y, &text, scale, PANIC_FOREGROUND);
+            y += cell_height;
+        }
+        // Leave an empty line between the lines of the report.
+        y += cell_height;
+    }
+    framebuffer.blit(&mut gop)
+}
@@ -1,11 +1,23 @@
-use anyhow::{Context, Result};
-use uefi::Guid;
-use uefi::proto::device_path::DevicePath;
+use crate::services::services;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use anyhow::{Context, Result, anyhow};
+use edera_sprout_parsing::gpt::GptPartitionEntry;
+use uefi::Identify;
+use uefi::proto::device_path::build::DevicePathBuilder;
+use uefi::proto::device_path::media::{HardDrive, PartitionFormat, PartitionSignature};
+use uefi::proto::device_path::{DevicePath, DevicePathNodeEnum};
+use uefi::proto::media::block::BlockIO;
+use uefi::proto::media::fs::SimpleFileSystem;
 use uefi::proto::media::partition::PartitionInfo;
+use uefi::{Guid, Handle};
 use uefi_raw::Status;
 
+/// ESP and XBOOTLDR partition discovery.
+pub mod esp;
+
 /// Represents the type of partition GUID that can be retrieved.
-#[derive(PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum PartitionGuidForm {
     /// The partition GUID is the unique partition GUID.
     Partition,
@@ -13,10 +25,170 @@ pub enum PartitionGuidForm {
     PartitionType,
 }
 
+/// Metadata of a partition, which depends on how the disk it is on is partitioned.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PartitionMetadata {
+    /// A partition of a disk partitioned with GPT.
+    Gpt {
+        /// The unique partition GUID.
+        unique_guid: Guid,
+        /// The partition type GUID.
+        type_guid: Guid,
+    },
+    /// A primary partition of a disk partitioned with MBR.
+    Mbr {
+        /// The signature of the disk, if known.
+        disk_signature: Option<u32>,
+        /// The partition type byte, like `0x83` for Linux.
+        partition_type: u8,
+    },
+}
+
+/// A partition found on a block device of the system.
+pub struct PartitionHandle {
+    /// The handle of the partition block device.
+    pub handle: Handle,
+    /// The device path of the partition.
+    pub path: Box<DevicePath>,
+    /// The handle of the filesystem on the partition,
+    /// if the firmware has a driver for the filesystem.
+    pub filesystem: Option<Handle>,
+}
+
+/// Find all the partitions with the partition type `guid`, in the order the firmware
+/// reported them. Partitions whose type can not be determined are skipped.
+pub fn find_by_type_guid(guid: Guid) -> Result<Vec<PartitionHandle>> {
+    find_by_guid(guid, PartitionGuidForm::PartitionType)
+}
+
+/// Find the partition with the unique partition `guid`, if any.
+pub fn find_by_unique_guid(guid: Guid) -> Result<Option<PartitionHandle>> {
+    Ok(find_by_guid(guid, PartitionGuidForm::Partition)?
+        .into_iter()
+        .next())
+}
+
+/// Find all the partitions where the partition GUID in the specified `form` is `guid`,
+/// in the order the firmware reported them. Partitions whose GUID can not be determined
+/// are skipped.
+pub fn find_by_guid(guid: Guid, form: PartitionGuidForm) -> Result<Vec<PartitionHandle>> {
+    let handles = services()
+        .find_handles(&BlockIO::GUID)
+        .context("unable to find block io handles")?;
+    // Filesystems are installed on the handle of the partition they are on.
+    let filesystems = services()
+        .find_handles(&SimpleFileSystem::GUID)
+        .unwrap_or_default();
+
+    let mut partitions = Vec::new();
+    for handle in handles {
+        // Skip any handles that we can't open, they can't be inspected anyway.
+        let Ok(block) = crate::disk::open_shared::<BlockIO>(handle) else {
+            continue;
+        };
+        if !block.media().is_logical_partition() {
+            continue;
+        }
+        let Ok(path) = crate::disk::disk_device_path(handle) else {
+            continue;
+        };
+
+        let Ok(Some(found)) = partition_guid(&path, form) else {
+            continue;
+        };
+        if found != guid {
+            continue;
+        }
+        partitions.push(PartitionHandle {
+            handle,
+            path,
+            filesystem: filesystems.contains(&handle).then_some(handle),
+        });
+    }
+    Ok(partitions)
+}
+
 /// Retrieve the partition / partition type GUID of the device root `path`.
 /// This only works on GPT partitions. If the root is not a GPT partition, None is returned.
 /// If the GUID is all zeros, this will return None.
+/// See [partition_metadata] for how the GUID is acquired.
 pub fn partition_guid(path: &DevicePath, form: PartitionGuidForm) -> Result<Option<Guid>> {
+    let Some(PartitionMetadata::Gpt {
+        unique_guid,
+        type_guid,
+    }) = partition_metadata(path)?
+    else {
+        return Ok(None);
+    };
+    let guid = match form {
+        PartitionGuidForm::Partition => unique_guid,
+        PartitionGuidForm::PartitionType => type_guid,
+    };
+    Ok(Some(guid).filter(|guid| !guid.is_zero()))
+}
+
+/// Retrieve the [PartitionMetadata] of the device root `path`.
+/// Returns None if the root is not a partition or its partition table can not be found.
+///
+/// The metadata is acquired from the [PartitionInfo] protocol, and if the firmware does not
+/// provide it, by reading the partition table of the disk the partition is on.
+pub fn partition_metadata(path: &DevicePath) -> Result<Option<PartitionMetadata>> {
+    let hard_drive = split_hard_drive(path)?;
+
+    // The disk signature of an MBR partition is only available in its hard drive node.
+    let disk_signature =
+        hard_drive
+            .as_ref()
+            .and_then(|(_, hard_drive)| match hard_drive.partition_signature() {
+                PartitionSignature::Mbr(signature) => Some(u32::from_le_bytes(signature)),
+                _ => None,
+            });
+    if let Some(metadata) = partition_info_metadata(path, disk_signature)? {
+        return Ok(Some(metadata));
+    }
+
+    // Some firmware omits the partition info protocol, so read the partition table directly.
+    let Some((disk, hard_drive)) = hard_drive else {
+        return Ok(None);
+    };
+    let Some(handle) = locate_disk(&disk)? else {
+        return Ok(None);
+    };
+    if hard_drive.partition_format() == PartitionFormat::GPT {
+        let entry = gpt_partition_entry(handle, hard_drive)
+            .context("unable to read gpt partition entry")?;
+        Ok(entry.map(|entry| PartitionMetadata::Gpt {
+            unique_guid: Guid::from_bytes(entry.unique_guid),
+            type_guid: Guid::from_bytes(entry.type_guid),
+        }))
+    } else if hard_drive.partition_format() == PartitionFormat::MBR {
+        let Some(mbr) = crate::disk::mbr(handle).context("unable to read mbr")? else {
+            return Ok(None);
+        };
+        // Match both the number and the start, in case the node is stale.
+        Ok(mbr
+            .partitions
+            .iter()
+            .find(|partition| {
+                partition.number == hard_drive.partition_number()
+                    && partition.first_lba as u64 == hard_drive.partition_start()
+            })
+            .map(|partition| PartitionMetadata::Mbr {
+                disk_signature: Some(mbr.disk_signature),
+                partition_type: partition.partition_type,
+            }))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Retrieve the [PartitionMetadata] of the device root `path` using the [PartitionInfo]
+/// protocol. The protocol does not report the disk signature of MBR partitions,
+/// so `disk_signature` is used for them. Returns None if the protocol is not available.
+fn partition_info_metadata(
+    path: &DevicePath,
+    disk_signature: Option<u32>,
+) -> Result<Option<PartitionMetadata>> {
     // Clone the path so we can pass it to the UEFI stack.
     let path = path.to_boxed();
     let result = uefi::boot::locate_device_path::<PartitionInfo>(&mut &*path);
@@ -35,21 +207,76 @@ pub fn partition_guid(path: &DevicePath, form: PartitionGuidForm) -> Result<Opti
     .context("unable to locate device path")?;
 
     // If we have the handle, we can try to open the partition info protocol.
-    if let Some(handle) = handle {
-        // Open the partition info protocol.
-        let partition_info = uefi::boot::open_protocol_exclusive::<PartitionInfo>(handle)
-            .context("unable to open partition info protocol")?;
-        // Find the unique partition GUID.
-        // If this is not a GPT partition, this will produce None.
-        Ok(partition_info
-            .gpt_partition_entry()
-            .map(|entry| match form {
-                // Match the form of the partition GUID.
-                PartitionGuidForm::Partition => entry.unique_partition_guid,
-                PartitionGuidForm::PartitionType => entry.partition_type_guid.0,
-            })
-            .filter(|guid| !guid.is_zero()))
-    } else {
-        Ok(None)
+    let Some(handle) = handle else {
+        return Ok(None);
+    };
+    let partition_info = uefi::boot::open_protocol_exclusive::<PartitionInfo>(handle)
+        .context("unable to open partition info protocol")?;
+
+    // The protocol provides either a GPT entry or an MBR record, depending on the disk.
+    if let Some(entry) = partition_info.gpt_partition_entry() {
+        return Ok(Some(PartitionMetadata::Gpt {
+            unique_guid: entry.unique_partition_guid,
+            type_guid: entry.partition_type_guid.0,
+        }));
     }
+    Ok(partition_info
+        .mbr_partition_record()
+        .map(|record| PartitionMetadata::Mbr {
+            disk_signature,
+            partition_type: record.os_type.0,
+        }))
+}
+
+/// Splits the device `path` of a partition into the device path of the disk it is on
+/// and its hard drive node. Returns None if the path does not contain a hard drive node.
+pub(crate) fn split_hard_drive(path: &DevicePath) -> Result<Option<(Box<DevicePath>, &HardDrive)>> {
+    let mut disk = Vec::new();
+    let mut builder = DevicePathBuilder::with_vec(&mut disk);
+    for node in path.node_iter() {
+        if let Ok(DevicePathNodeEnum::MediaHardDrive(hard_drive)) = node.as_enum() {
+            let disk = builder
+                .finalize()
+                .map_err(|error| anyhow!("unable to build disk device path: {:?}", error))?;
+            return Ok(Some((disk.to_boxed(), hard_drive)));
+        }
+        builder = builder
+            .push(&node)
+            .map_err(|error| anyhow!("unable to build disk device path: {:?}", error))?;
+    }
+    Ok(None)
+}
+
+/// Locate the handle of the disk at the exact device `path`.
+/// Returns None if no device matches the whole path.
+fn locate_disk(path: &DevicePath) -> Result<Option<Handle>> {
+    let mut remaining = path;
+    let handle = match uefi::boot::locate_device_path::<BlockIO>(&mut remaining) {
+        Ok(handle) => handle,
+        Err(error) if error.status() == Status::NOT_FOUND => return Ok(None),
+        Err(error) => return Err(error).context("unable to locate disk device path"),
+    };
+
+    // A partial match is a different device higher up in the path, like a controller.
+    if remaining.node_iter().next().is_some() {
+        return Ok(None);
+    }
+    Ok(Some(handle))
+}
+
+/// Retrieve the GPT partition entry described by `hard_drive` from the disk `handle`.
+/// Returns None if the disk is not partitioned with GPT or has no such entry.
+fn gpt_partition_entry(
+    handle: Handle,
+    hard_drive: &HardDrive,
+) -> Result<Option<GptPartitionEntry>> {
+    let Some((_, entries)) = crate::disk::gpt_partitions(handle)? else {
+        return Ok(None);
+    };
+
+    // Match both the number and the start, in case the node is stale.
+    Ok(entries.into_iter().find(|entry| {
+        entry.number == hard_drive.partition_number()
+            && entry.first_lba == hard_drive.partition_start()
+    }))
 }
@@ -0,0 +1,55 @@
+use crate::partition::{PartitionHandle, find_by_type_guid, split_hard_drive};
+use alloc::vec::Vec;
+use anyhow::{Context, Result};
+use uefi::proto::loaded_image::LoadedImage;
+use uefi::{Guid, guid};
+
+/// The partition type GUID of an EFI system partition.
+pub const ESP_TYPE_GUID: Guid = guid!("c12a7328-f81f-11d2-ba4b-00a0c93ec93b");
+
+/// The partition type GUID of an extended boot loader partition,
+/// as defined by the discoverable partitions specification.
+pub const XBOOTLDR_TYPE_GUID: Guid = guid!("bc13c2ff-59e6-4262-a352-b275fd6f7172");
+
+/// Find all the EFI system partitions on the system.
+pub fn all_esps() -> Result<Vec<PartitionHandle>> {
+    find_by_type_guid(ESP_TYPE_GUID).context("unable to find efi system partitions")
+}
+
+/// Find the EFI system partition that the current image was loaded from.
+/// Returns None if the image was not loaded from an EFI system partition,
+/// like when it was loaded over the network.
+pub fn loaded_esp() -> Result<Option<PartitionHandle>> {
+    // The device of the loaded image is the handle of the partition it was loaded from.
+    let device = {
+        let loaded_image =
+            uefi::boot::open_protocol_exclusive::<LoadedImage>(uefi::boot::image_handle())
+                .context("unable to open loaded image protocol")?;
+        loaded_image.device()
+    };
+    let Some(device) = device else {
+        return Ok(None);
+    };
+    Ok(all_esps()?
+        .into_iter()
+        .find(|partition| partition.handle == device))
+}
+
+/// Find the extended boot loader partition on the same disk as the `esp`.
+/// The discoverable partitions specification only allows one per disk,
+/// so the first one found is returned.
+pub fn xbootldr_for(esp: &PartitionHandle) -> Result<Option<PartitionHandle>> {
+    let Some((disk, _)) = split_hard_drive(&esp.path)? else {
+        return Ok(None);
+    };
+    let partitions = find_by_type_guid(XBOOTLDR_TYPE_GUID)
+        .context("unable to find extended boot loader partitions")?;
+    for partition in partitions {
+        if let Some((other, _)) = split_hard_drive(&partition.path)?
+            && other == disk
+        {
+            return Ok(Some(partition));
+        }
+    }
+    Ok(None)
+}
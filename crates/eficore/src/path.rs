@@ -1,14 +1,32 @@
+use crate::deadline;
+use crate::filesystem::FilesystemScan;
+use crate::pages::PageBuffer;
+use crate::partition::{self, esp};
 use alloc::borrow::ToOwned;
 use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
 use alloc::string::{String, ToString};
+use alloc::vec;
 use alloc::vec::Vec;
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, anyhow, bail};
 use core::ops::Deref;
+use core::str::FromStr;
+use edera_sprout_parsing::path::{PathScheme, glob, split_scheme};
+use edera_sprout_parsing::url::{HttpUrl, is_http_url};
+use log::debug;
+use spin::Mutex;
 use uefi::fs::{FileSystem, Path};
+use uefi::proto::device_path::build::{self, DevicePathBuilder};
 use uefi::proto::device_path::text::{AllowShortcuts, DevicePathFromText, DisplayOnly};
 use uefi::proto::device_path::{DevicePath, PoolDevicePath};
+use uefi::proto::media::file::{File, FileAttribute, FileInfo, FileMode};
 use uefi::proto::media::fs::SimpleFileSystem;
-use uefi::{CString16, Handle};
+use uefi::{CString16, Guid, Handle};
+
+/// The size of the chunks used to read files.
+/// Between chunks, the active [deadline] is checked.
+const READ_CHUNK_SIZE: usize = 1024 * 1024;
 
 /// Represents the components of a resolved path.
 pub struct ResolvedPath {
@@ -23,20 +41,184 @@ pub struct ResolvedPath {
     pub full_path: Box<DevicePath>,
     /// The handle of the filesystem containing the path.
     /// This can be used to acquire a [SimpleFileSystem] protocol to read the file.
+    /// For a URL, this is the handle of the network interface instead.
     pub filesystem_handle: Handle,
+    /// The HTTP URL of the path, if it refers to a network source instead of a file.
+    /// The full path of a URL is the device path of the network interface with a URI node.
+    pub url: Option<String>,
+}
+
+impl Clone for ResolvedPath {
+    fn clone(&self) -> Self {
+        Self {
+            root_path: self.root_path.to_boxed(),
+            sub_path: self.sub_path.to_boxed(),
+            full_path: self.full_path.to_boxed(),
+            filesystem_handle: self.filesystem_handle,
+            url: self.url.clone(),
+        }
+    }
 }
 
 impl ResolvedPath {
     /// Read the file specified by this path into a buffer and return it.
+    /// The file is read in chunks, checking the active [deadline] between chunks,
+    /// so a read from an unresponsive filesystem can be cancelled.
     pub fn read_file(&self) -> Result<Vec<u8>> {
+        crate::instrument!("read file");
+        if let Some(url) = &self.url {
+            return crate::http::download(url, |_, _| {});
+        }
+        let mut fs =
+            uefi::boot::open_protocol_exclusive::<SimpleFileSystem>(self.filesystem_handle)
+                .context("unable to open filesystem protocol")?;
+        let path = self
+            .sub_path
+            .to_string16(DisplayOnly(false), AllowShortcuts(false))?;
+        let mut file = fs
+            .open_volume()
+            .context("unable to open filesystem volume")?
+            .open(&path, FileMode::Read, FileAttribute::empty())
+            .context("unable to open file")?
+            .into_regular_file()
+            .context("path is not a regular file")?;
+
+        let mut content = Vec::new();
+        let mut chunk = vec![0u8; READ_CHUNK_SIZE];
+        loop {
+            deadline::check().context("unable to read file contents")?;
+            let size = file
+                .read(&mut chunk)
+                .context("unable to read file contents")?;
+            if size == 0 {
+                break;
+            }
+            content.extend_from_slice(&chunk[..size]);
+        }
+        crate::manifest::check(self.filesystem_handle, &path.to_string(), &content)?;
+        Ok(content)
+    }
+
+    /// Read at most `length` bytes from the start of the file specified by this path.
+    /// This is meant for inspecting headers of large files, like the version of a kernel.
+    /// A partial read can not be checked against the ESP manifest, so the contents must
+    /// not be trusted beyond what the full read of the file verifies later.
+    pub fn read_file_prefix(&self, length: usize) -> Result<Vec<u8>> {
+        crate::instrument!("read file prefix");
+        if let Some(url) = &self.url {
+            let mut content = crate::http::download(url, |_, _| {})?;
+            content.truncate(length);
+            return Ok(content);
+        }
+        let mut fs =
+            uefi::boot::open_protocol_exclusive::<SimpleFileSystem>(self.filesystem_handle)
+                .context("unable to open filesystem protocol")?;
+        let path = self
+            .sub_path
+            .to_string16(DisplayOnly(false), AllowShortcuts(false))?;
+        let mut file = fs
+            .open_volume()
+            .context("unable to open filesystem volume")?
+            .open(&path, FileMode::Read, FileAttribute::empty())
+            .context("unable to open file")?
+            .into_regular_file()
+            .context("path is not a regular file")?;
+
+        let mut content = vec![0u8; length];
+        let mut filled = 0;
+        while filled < length {
+            deadline::check().context("unable to read file contents")?;
+            let end = length.min(filled + READ_CHUNK_SIZE);
+            let size = file
+                .read(&mut content[filled..end])
+                .context("unable to read file contents")?;
+            if size == 0 {
+                break;
+            }
+            filled += size;
+        }
+        content.truncate(filled);
+        Ok(content)
+    }
+
+    /// Read the file specified by this path into a page-aligned [PageBuffer] and return it.
+    /// The buffer is allocated once with the size of the file and the file is read directly
+    /// into it, which avoids copying large files like kernels and initrds between buffers.
+    /// Like [ResolvedPath::read_file], the active [deadline] is checked between chunks.
+    pub fn read_file_pages(&self) -> Result<PageBuffer> {
+        self.read_file_pages_with_progress(|_, _| {})
+    }
+
+    /// Read the file specified by this path into a page-aligned [PageBuffer], like
+    /// [ResolvedPath::read_file_pages], calling `progress` after each chunk with the
+    /// number of bytes read so far and the total size of the file.
+    pub fn read_file_pages_with_progress(
+        &self,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<PageBuffer> {
+        crate::instrument!("read file pages");
+        if let Some(url) = &self.url {
+            let content = crate::http::download(url, &mut progress)?;
+            let mut buffer =
+                PageBuffer::new(content.len()).context("unable to allocate file buffer")?;
+            buffer.copy_from_slice(&content);
+            return Ok(buffer);
+        }
+        let mut fs =
+            uefi::boot::open_protocol_exclusive::<SimpleFileSystem>(self.filesystem_handle)
+                .context("unable to open filesystem protocol")?;
+        let path = self
+            .sub_path
+            .to_string16(DisplayOnly(false), AllowShortcuts(false))?;
+        let mut file = fs
+            .open_volume()
+            .context("unable to open filesystem volume")?
+            .open(&path, FileMode::Read, FileAttribute::empty())
+            .context("unable to open file")?
+            .into_regular_file()
+            .context("path is not a regular file")?;
+
+        let size = file
+            .get_boxed_info::<FileInfo>()
+            .context("unable to get file info")?
+            .file_size();
+        let size = usize::try_from(size).context("file is too large")?;
+        let mut buffer = PageBuffer::new(size).context("unable to allocate file buffer")?;
+
+        let mut offset = 0;
+        while offset < size {
+            deadline::check().context("unable to read file contents")?;
+            let end = (offset + READ_CHUNK_SIZE).min(size);
+            let read = file
+                .read(&mut buffer[offset..end])
+                .context("unable to read file contents")?;
+            if read == 0 {
+                break;
+            }
+            offset += read;
+            progress(offset, size);
+        }
+
+        // The file may have been shorter than reported, so only the read data is retained.
+        buffer.truncate(offset);
+        crate::manifest::check(self.filesystem_handle, &path.to_string(), &buffer)?;
+        Ok(buffer)
+    }
+
+    /// Check whether the file specified by this path exists.
+    pub fn exists(&self) -> Result<bool> {
+        // A URL can only be checked by downloading it, so it is assumed to exist.
+        if self.url.is_some() {
+            return Ok(true);
+        }
         let fs = uefi::boot::open_protocol_exclusive::<SimpleFileSystem>(self.filesystem_handle)
             .context("unable to open filesystem protocol")?;
         let mut fs = FileSystem::new(fs);
         let path = self
             .sub_path
             .to_string16(DisplayOnly(false), AllowShortcuts(false))?;
-        let content = fs.read(Path::new(&path));
-        content.context("unable to read file contents")
+        fs.try_exists(Path::new(&path))
+            .context("unable to check if file exists")
     }
 }
 
@@ -110,16 +292,195 @@ pub fn device_path_subpath(path: &DevicePath) -> Result<String> {
     Ok(path)
 }
 
+/// A per-boot cache of path conversions and resolved paths.
+/// Converting paths acquires the [DevicePathFromText] protocol and converts every node to text,
+/// which adds up on systems with many entries that resolve the same paths over and over.
+struct PathCache {
+    /// Device paths converted from text, keyed by the text.
+    device_paths: BTreeMap<String, Box<DevicePath>>,
+    /// Root text of device paths, keyed by the bytes of the device path.
+    roots: BTreeMap<Vec<u8>, String>,
+    /// Resolved paths, keyed by the bytes of the default root path and the input.
+    resolved: BTreeMap<(Vec<u8>, String), ResolvedPath>,
+}
+
+// SAFETY: UEFI applications run on a single processor, and the cached handles are only
+// used while boot services are active.
+unsafe impl Send for PathCache {}
+
+/// The cache of path conversions for the current boot.
+static PATH_CACHE: Mutex<PathCache> = Mutex::new(PathCache {
+    device_paths: BTreeMap::new(),
+    roots: BTreeMap::new(),
+    resolved: BTreeMap::new(),
+});
+
+/// Clears the cache of path conversions and resolved paths.
+/// This must be called when filesystems may have been connected or disconnected,
+/// like after loading drivers, as resolved paths contain filesystem handles.
+pub fn clear_cache() {
+    let mut cache = PATH_CACHE.lock();
+    cache.device_paths.clear();
+    cache.roots.clear();
+    cache.resolved.clear();
+}
+
+/// Parses the input `path` as a [DevicePath] like [text_to_device_path],
+/// reusing the result of a previous conversion of the same `path`.
+fn cached_text_to_device_path(path: &str) -> Result<Box<DevicePath>> {
+    if let Some(cached) = PATH_CACHE.lock().device_paths.get(path) {
+        return Ok(cached.to_boxed());
+    }
+    let converted = text_to_device_path(path)?.to_boxed();
+    PATH_CACHE
+        .lock()
+        .device_paths
+        .insert(path.to_string(), converted.to_boxed());
+    Ok(converted)
+}
+
+/// Grabs the root part of the `path` like [device_path_root],
+/// reusing the result of a previous conversion of the same `path`.
+fn cached_device_path_root(path: &DevicePath) -> Result<String> {
+    let key = path.as_bytes().to_vec();
+    if let Some(cached) = PATH_CACHE.lock().roots.get(&key) {
+        return Ok(cached.clone());
+    }
+    let root = device_path_root(path)?;
+    PATH_CACHE.lock().roots.insert(key, root.clone());
+    Ok(root)
+}
+
 /// Resolve a path specified by `input` to its various components.
 /// Uses `default_root_path` as the base root if one is not specified in the path.
 /// Returns [ResolvedPath] which contains the resolved components.
+/// Paths that were resolved before are reused until [clear_cache] is called.
 pub fn resolve_path(
     default_root_path: Option<&DevicePath>,
     input: impl ToString,
 ) -> Result<ResolvedPath> {
-    let mut input = input.to_string();
+    let input = input.to_string();
+    crate::instrument!(format!("resolve path {}", input));
+    let key = (
+        default_root_path
+            .map(|path| path.as_bytes().to_vec())
+            .unwrap_or_default(),
+        input.clone(),
+    );
+    if let Some(cached) = PATH_CACHE.lock().resolved.get(&key) {
+        return Ok(cached.clone());
+    }
+
+    // Only successfully resolved paths are cached, as a missing filesystem may appear later.
+    let resolved = resolve_path_uncached(default_root_path, input)?;
+    PATH_CACHE.lock().resolved.insert(key, resolved.clone());
+    Ok(resolved)
+}
+
+/// Expands the scheme of the `input` path, if it has one, to the device root
+/// of the filesystem the scheme selects. See [split_scheme] for the supported schemes.
+fn expand_scheme(input: &str) -> Result<Option<String>> {
+    let Some((scheme, subpath)) = split_scheme(input) else {
+        return Ok(None);
+    };
+    let root = match scheme {
+        PathScheme::Esp => {
+            // Prefer the ESP sprout was loaded from, but any ESP will do.
+            let esp = match esp::loaded_esp()? {
+                Some(esp) => esp,
+                None => esp::all_esps()?
+                    .into_iter()
+                    .next()
+                    .context("unable to find an efi system partition")?,
+            };
+            cached_device_path_root(&esp.path)?
+        }
+        PathScheme::PartitionUuid(guid) => {
+            let guid = Guid::from_str(guid)
+                .map_err(|error| anyhow!("unable to parse partition uuid {}: {}", guid, error))?;
+            let partition = partition::find_by_unique_guid(guid)?
+                .with_context(|| format!("unable to find partition with uuid {}", guid))?;
+            cached_device_path_root(&partition.path)?
+        }
+        PathScheme::Label(label) => {
+            // The scan holds the filesystems open, so it is dropped before resolving.
+            let mut scan = FilesystemScan::new()?;
+            let mut root = None;
+            for handle in scan.handles() {
+                if scan.label(handle).ok().as_deref() == Some(label) {
+                    root = Some(scan.root(handle)?.to_boxed());
+                    break;
+                }
+            }
+            let root =
+                root.with_context(|| format!("unable to find filesystem with label {}", label))?;
+            cached_device_path_root(&root)?
+        }
+        PathScheme::Squashfs(name) => {
+            let root = crate::fs_driver::squashfs_root(name)
+                .with_context(|| format!("squashfs container {} is not mounted", name))?;
+            cached_device_path_root(root)?
+        }
+    };
+    Ok(Some(format!("{}{}", root, subpath)))
+}
+
+/// Resolve the HTTP `url` to a [ResolvedPath] on the first network interface that supports
+/// HTTP. The interface is configured with DHCP if needed, and the hostname of the URL is
+/// resolved with the firmware DNS protocol, so an unknown host fails before any download.
+/// The full path is the device path of the interface followed by a URI node, which is how
+/// firmware HTTP boot describes a network source.
+pub fn resolve_url(url: &str) -> Result<ResolvedPath> {
+    let parsed = HttpUrl::parse(url).with_context(|| format!("'{}' is not an http url", url))?;
+    let interface = crate::http::interfaces()?
+        .into_iter()
+        .next()
+        .context("no network interface supports http")?;
+    crate::http::configure_interface(interface)?;
+
+    // Addresses don't need to be resolved, and IPv6 is not supported by the DNS protocol.
+    if parsed.host.parse::<core::net::Ipv4Addr>().is_err() && !parsed.host.starts_with('[') {
+        let addresses = crate::dns::resolve(interface, &parsed.host)?;
+        debug!("resolved {} to {:?}", parsed.host, addresses);
+    }
+
+    let root_path = crate::disk::open_shared::<DevicePath>(interface)
+        .context("unable to open network interface device path")?
+        .to_boxed();
+    let mut sub_path = Vec::new();
+    let sub_path = DevicePathBuilder::with_vec(&mut sub_path)
+        .push(&build::messaging::Uri {
+            value: url.as_bytes(),
+        })
+        .and_then(|builder| builder.finalize())
+        .map_err(|error| anyhow!("unable to build uri device path: {:?}", error))?
+        .to_boxed();
+    let full_path = root_path
+        .append_path(&sub_path)
+        .map_err(|error| anyhow!("unable to build url device path: {:?}", error))?
+        .to_boxed();
+    Ok(ResolvedPath {
+        root_path,
+        sub_path,
+        full_path,
+        filesystem_handle: interface,
+        url: Some(url.to_string()),
+    })
+}
 
-    let mut path = text_to_device_path(&input).context("unable to convert text to path")?;
+/// Resolve a path specified by `input` to its various components without the cache.
+/// Paths with a scheme are expanded first, see [expand_scheme].
+fn resolve_path_uncached(
+    default_root_path: Option<&DevicePath>,
+    mut input: String,
+) -> Result<ResolvedPath> {
+    if is_http_url(&input) {
+        return resolve_url(&input);
+    }
+    if let Some(expanded) = expand_scheme(&input).context("unable to expand path scheme")? {
+        input = expanded;
+    }
+    let mut path = cached_text_to_device_path(&input).context("unable to convert text to path")?;
     let path_has_device = path
         .node_iter()
         .next()
@@ -138,18 +499,17 @@ pub fn resolve_path(
 
         input.insert_str(
             0,
-            device_path_root(default_root_path)
+            cached_device_path_root(default_root_path)
                 .context("unable to get loaded image device root")?
                 .as_str(),
         );
-        path = text_to_device_path(input.as_str()).context("unable to convert text to path")?;
+        path =
+            cached_text_to_device_path(input.as_str()).context("unable to convert text to path")?;
     }
 
-    let path = path.to_boxed();
     let root = device_path_root(path.as_ref()).context("unable to convert root to path")?;
-    let root_path = text_to_device_path(root.as_str())
-        .context("unable to convert root to path")?
-        .to_boxed();
+    let root_path =
+        cached_text_to_device_path(root.as_str()).context("unable to convert root to path")?;
     let root_path = root_path.as_ref();
 
     // locate_device_path modifies the path, so we need to clone it.
@@ -159,12 +519,65 @@ pub fn resolve_path(
     let subpath = device_path_subpath(path.deref()).context("unable to get device subpath")?;
     Ok(ResolvedPath {
         root_path: root_path.to_boxed(),
-        sub_path: text_to_device_path(subpath.as_str())?.to_boxed(),
+        sub_path: cached_text_to_device_path(subpath.as_str())?,
         full_path: path,
         filesystem_handle: handle,
+        url: None,
     })
 }
 
+/// Lists the names of the regular files in the `directory` of `filesystem`, sorted by name.
+pub fn read_directory_names(filesystem: &mut FileSystem, directory: &Path) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    for item in filesystem
+        .read_dir(directory)
+        .context("unable to read directory")?
+    {
+        let item = item.context("unable to read directory item")?;
+        if item.is_regular_file() {
+            names.push(item.file_name().to_string());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Lists the names of the regular files in the `directory` of `filesystem`
+/// that match the glob `pattern`, sorted by name.
+/// See [edera_sprout_parsing::path::glob] for how names are matched.
+pub fn glob_directory(
+    filesystem: &mut FileSystem,
+    directory: &Path,
+    pattern: &str,
+) -> Result<Vec<String>> {
+    let names = read_directory_names(filesystem, directory)?;
+    Ok(glob(pattern, names.iter()))
+}
+
+/// Lists the names of the regular files in the directory at the location specified with the
+/// `input` path, sorted by name. Internally, this uses [resolve_path] to resolve the path,
+/// which is passed the `default_root_path` which should specify a base root.
+///
+/// This acquires exclusive protocol access to the [SimpleFileSystem] protocol of the resolved
+/// filesystem handle, like [read_file_contents].
+pub fn list_directory_contents(
+    default_root_path: Option<&DevicePath>,
+    input: &str,
+) -> Result<Vec<String>> {
+    let resolved = resolve_path(default_root_path, input)?;
+    if resolved.url.is_some() {
+        bail!("unable to list the contents of url {}", input);
+    }
+    let directory = resolved
+        .sub_path
+        .to_string16(DisplayOnly(false), AllowShortcuts(false))
+        .context("unable to convert directory path to string")?;
+    let fs = uefi::boot::open_protocol_exclusive::<SimpleFileSystem>(resolved.filesystem_handle)
+        .context("unable to open filesystem")?;
+    let mut fs = FileSystem::new(fs);
+    read_directory_names(&mut fs, Path::new(&directory))
+}
+
 /// Read the contents of a file at the location specified with the `input` path.
 /// Internally, this uses [resolve_path] to resolve the path to its various components.
 /// [resolve_path] is passed the `default_root_path` which should specify a base root.
@@ -176,3 +589,54 @@ pub fn read_file_contents(default_root_path: Option<&DevicePath>, input: &str) -
     let resolved = resolve_path(default_root_path, input)?;
     resolved.read_file()
 }
+
+/// Read at most `length` bytes from the start of the file at the location specified with the
+/// `input` path, like [read_file_contents]. The contents are not checked against the ESP
+/// manifest, see [ResolvedPath::read_file_prefix].
+pub fn read_file_prefix(
+    default_root_path: Option<&DevicePath>,
+    input: &str,
+    length: usize,
+) -> Result<Vec<u8>> {
+    let resolved = resolve_path(default_root_path, input)?;
+    resolved.read_file_prefix(length)
+}
+
+/// Write `data` to the file at the location specified with the `input` path, replacing the
+/// file if it exists. The directory containing the file is created if it is missing.
+/// Internally, this uses [resolve_path] to resolve the path, which is passed the
+/// `default_root_path` which should specify a base root.
+///
+/// This acquires exclusive protocol access to the [SimpleFileSystem] protocol of the resolved
+/// filesystem handle, like [read_file_contents].
+pub fn write_file_contents(
+    default_root_path: Option<&DevicePath>,
+    input: &str,
+    data: &[u8],
+) -> Result<()> {
+    let resolved = resolve_path(default_root_path, input)?;
+    if resolved.url.is_some() {
+        bail!("unable to write to url {}", input);
+    }
+    let path = resolved
+        .sub_path
+        .to_string16(DisplayOnly(false), AllowShortcuts(false))
+        .context("unable to convert file path to string")?;
+    let fs = uefi::boot::open_protocol_exclusive::<SimpleFileSystem>(resolved.filesystem_handle)
+        .context("unable to open filesystem")?;
+    let mut fs = FileSystem::new(fs);
+
+    // Create the directory containing the file, unless the file is at the root.
+    let path = path.to_string();
+    if let Some((directory, _)) = path.rsplit_once('\\')
+        && !directory.is_empty()
+    {
+        let directory =
+            CString16::try_from(directory).context("unable to convert directory path")?;
+        fs.create_dir_all(Path::new(&directory))
+            .context("unable to create directory")?;
+    }
+    let path = CString16::try_from(path.as_str()).context("unable to convert file path")?;
+    fs.write(Path::new(&path), data)
+        .context("unable to write file")
+}
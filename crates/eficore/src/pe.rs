@@ -0,0 +1,73 @@
+use anyhow::{Context, Result, bail};
+use edera_sprout_parsing::pe::{Machine, PeImage, parse_loaded_pe, parse_pe};
+use uefi::proto::loaded_image::LoadedImage;
+
+/// The machine type of PE images that run on the current architecture.
+pub const NATIVE_MACHINE: Machine = if cfg!(target_arch = "x86_64") {
+    Machine::X86_64
+} else if cfg!(target_arch = "aarch64") {
+    Machine::Aarch64
+} else if cfg!(target_arch = "x86") {
+    Machine::I386
+} else if cfg!(target_arch = "arm") {
+    Machine::ArmThumb
+} else if cfg!(target_arch = "riscv64") {
+    Machine::RiscV64
+} else if cfg!(target_arch = "loongarch64") {
+    Machine::LoongArch64
+} else {
+    Machine::Other(0)
+};
+
+/// Parses the PE image in `data`, failing if it is not a valid PE image.
+/// See [edera_sprout_parsing::pe] for the sections that can be extracted.
+pub fn parse(data: &[u8]) -> Result<PeImage<'_>> {
+    match parse_pe(data) {
+        Some(image) => Ok(image),
+        None => bail!("image is not a valid PE image"),
+    }
+}
+
+/// Checks whether the PE `image` can run on the current architecture.
+pub fn is_native(image: &PeImage) -> bool {
+    image.machine == NATIVE_MACHINE
+}
+
+/// Checks that the PE image in `data` can run on the current architecture, failing with an
+/// error that names both architectures if it can't, instead of leaving the firmware to fail
+/// to load it. EFI byte code runs on every architecture, and data that is not a valid PE
+/// image is left for the firmware to reject.
+pub fn check_native(data: &[u8]) -> Result<()> {
+    let Some(image) = parse_pe(data) else {
+        return Ok(());
+    };
+    if is_native(&image) || image.machine == Machine::Ebc {
+        return Ok(());
+    }
+    bail!(
+        "image is built for {}, but this firmware runs {} images",
+        image.machine.name(),
+        NATIVE_MACHINE.name()
+    )
+}
+
+/// Parses the image of Sprout itself, as loaded into memory by the firmware.
+/// This allows reading sections that were added to the image after it was built,
+/// like the sections of a unified kernel image.
+pub fn current_image() -> Result<PeImage<'static>> {
+    let loaded_image =
+        uefi::boot::open_protocol_exclusive::<LoadedImage>(uefi::boot::image_handle())
+            .context("unable to open loaded image protocol")?;
+    let (base, size) = loaded_image.info();
+    if base.is_null() {
+        bail!("loaded image has no image base");
+    }
+
+    // SAFETY: the firmware keeps the image of Sprout loaded at this range while it runs,
+    // and nothing writes to the loaded image after it has been started.
+    let data = unsafe { core::slice::from_raw_parts(base as *const u8, size as usize) };
+    match parse_loaded_pe(data) {
+        Some(image) => Ok(image),
+        None => bail!("loaded image is not a valid PE image"),
+    }
+}
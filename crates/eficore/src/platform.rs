@@ -1,3 +1,7 @@
+/// Real-time clock support.
+pub mod clock;
+/// SMBIOS support.
+pub mod smbios;
 /// Timer support.
 pub mod timer;
 /// TPM support.
@@ -0,0 +1,22 @@
+use anyhow::{Context, Result};
+use edera_sprout_parsing::datetime::DateTime;
+
+/// Represents the real-time clock provided by the platform firmware.
+pub struct PlatformClock;
+
+impl PlatformClock {
+    /// Reads the current date and time from the EFI runtime clock.
+    /// The time is in whatever time zone the real-time clock is configured for,
+    /// which is usually UTC on Linux systems and local time on Windows systems.
+    pub fn now() -> Result<DateTime> {
+        let time = uefi::runtime::get_time().context("unable to read the runtime clock")?;
+        Ok(DateTime {
+            year: time.year(),
+            month: time.month(),
+            day: time.day(),
+            hour: time.hour(),
+            minute: time.minute(),
+            second: time.second(),
+        })
+    }
+}
@@ -0,0 +1,60 @@
+use alloc::vec::Vec;
+use anyhow::{Result, bail};
+use edera_sprout_parsing::smbios::{SmbiosInfo, parse_entry_point, parse_table};
+use uefi::table::cfg::ConfigTableEntry;
+
+/// The maximum size of an SMBIOS entry point structure.
+const ENTRY_POINT_MAX_SIZE: usize = 0x20;
+
+/// The maximum size of the SMBIOS structure table that will be read.
+/// This protects against malformed entry points describing huge tables.
+const TABLE_MAX_SIZE: usize = 1024 * 1024;
+
+/// Represents the SMBIOS tables provided by the platform firmware.
+pub struct PlatformSmbios;
+
+impl PlatformSmbios {
+    /// Reads the SMBIOS structure table provided by the firmware.
+    /// The SMBIOS 3.x entry point is preferred over the SMBIOS 2.x entry point.
+    /// Returns None if the firmware does not provide SMBIOS tables.
+    pub fn read_table() -> Result<Option<Vec<u8>>> {
+        // Find the address of the entry point structure in the configuration table.
+        let entry_point = uefi::system::with_config_table(|entries| {
+            [
+                ConfigTableEntry::SMBIOS3_GUID,
+                ConfigTableEntry::SMBIOS_GUID,
+            ]
+            .iter()
+            .find_map(|guid| entries.iter().find(|entry| entry.guid == *guid))
+            .map(|entry| entry.address as *const u8)
+        });
+        let Some(entry_point) = entry_point.filter(|address| !address.is_null()) else {
+            return Ok(None);
+        };
+
+        // SAFETY: The firmware guarantees that the configuration table entry points
+        // to a valid SMBIOS entry point structure, which is at most ENTRY_POINT_MAX_SIZE bytes.
+        let entry_point = unsafe { core::slice::from_raw_parts(entry_point, ENTRY_POINT_MAX_SIZE) };
+        let Some(location) = parse_entry_point(entry_point) else {
+            bail!("invalid smbios entry point");
+        };
+
+        // Ensure that the table location is sane before reading it.
+        if location.address == 0 || location.length == 0 || location.length > TABLE_MAX_SIZE {
+            bail!("invalid smbios table location");
+        }
+
+        // SAFETY: The entry point describes the location and size of the structure table,
+        // which the firmware keeps mapped for the lifetime of boot services.
+        let table = unsafe {
+            core::slice::from_raw_parts(location.address as usize as *const u8, location.length)
+        };
+        Ok(Some(table.to_vec()))
+    }
+
+    /// Reads the [SmbiosInfo] from the SMBIOS tables provided by the firmware.
+    /// Returns None if the firmware does not provide SMBIOS tables.
+    pub fn info() -> Result<Option<SmbiosInfo>> {
+        Ok(Self::read_table()?.map(|table| parse_table(&table)))
+    }
+}
@@ -3,10 +3,17 @@
 
 use core::time::Duration;
 
+/// Timing reports of named spans.
+pub mod report;
+
 /// Support for aarch64 timers.
 #[cfg(target_arch = "aarch64")]
 pub mod aarch64;
 
+/// Support for 32-bit arm generic timers.
+#[cfg(target_arch = "arm")]
+pub mod arm;
+
 /// Support for x86_64 timers.
 #[cfg(target_arch = "x86_64")]
 pub mod x86_64;
@@ -46,6 +53,8 @@ impl TickFrequency {
 fn arch_ticks() -> u64 {
     #[cfg(target_arch = "aarch64")]
     return aarch64::ticks();
+    #[cfg(target_arch = "arm")]
+    return arm::ticks();
     #[cfg(target_arch = "x86_64")]
     return x86_64::ticks();
 }
@@ -54,6 +63,8 @@ fn arch_ticks() -> u64 {
 fn arch_frequency() -> TickFrequency {
     #[cfg(target_arch = "aarch64")]
     let frequency = aarch64::frequency();
+    #[cfg(target_arch = "arm")]
+    let frequency = arm::frequency();
     #[cfg(target_arch = "x86_64")]
     let frequency = x86_64::frequency();
     // If the frequency is 0, then something went very wrong and we should panic.
@@ -0,0 +1,21 @@
+use crate::platform::timer::TickFrequency;
+use core::arch::asm;
+
+/// Reads the 64-bit cntvct counter of the generic timer and returns the value.
+pub fn ticks() -> u64 {
+    let low: u32;
+    let high: u32;
+    unsafe {
+        asm!("mrrc p15, 1, {}, {}, c14", out(reg) low, out(reg) high);
+    }
+    (u64::from(high) << 32) | u64::from(low)
+}
+
+/// Our frequency is provided by cntfrq on the platform.
+pub fn frequency() -> TickFrequency {
+    let frequency: u32;
+    unsafe {
+        asm!("mrc p15, 0, {}, c14, c0, 0", out(reg) frequency);
+    }
+    TickFrequency::Hardware(u64::from(frequency))
+}
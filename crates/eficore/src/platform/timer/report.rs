@@ -0,0 +1,104 @@
+use crate::platform::timer::PlatformTimer;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt::Write;
+use core::time::Duration;
+use log::info;
+
+/// A named span of time measured during the boot process.
+#[derive(Clone, Debug)]
+pub struct TimingSpan {
+    /// The name of the span, like `load config` or `generator bls`.
+    pub name: String,
+    /// The time the span started, relative to the start of the timer.
+    pub start: Duration,
+    /// The duration of the span.
+    pub duration: Duration,
+}
+
+/// Records named spans of the boot process using a [PlatformTimer].
+/// The spans can be logged as a timing table to diagnose slow boots in the field.
+pub struct TimingReport {
+    /// The timer that measures the spans.
+    timer: PlatformTimer,
+    /// The spans that have been recorded, in the order they completed.
+    spans: RefCell<Vec<TimingSpan>>,
+}
+
+impl TimingReport {
+    /// Creates a new timing report that measures spans with `timer`.
+    pub fn new(timer: PlatformTimer) -> Self {
+        Self {
+            timer,
+            spans: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Access the timer that measures the spans.
+    pub fn timer(&self) -> &PlatformTimer {
+        &self.timer
+    }
+
+    /// Runs `operation` and records its duration as a span called `name`.
+    /// The span is recorded regardless of the result of the operation.
+    pub fn measure<T>(&self, name: impl ToString, operation: impl FnOnce() -> T) -> T {
+        let start = self.timer.elapsed_since_start();
+        let result = operation();
+        let duration = self.timer.elapsed_since_start().saturating_sub(start);
+        self.spans.borrow_mut().push(TimingSpan {
+            name: name.to_string(),
+            start,
+            duration,
+        });
+        result
+    }
+
+    /// Acquires a copy of the spans that have been recorded, ordered by their start time.
+    pub fn spans(&self) -> Vec<TimingSpan> {
+        let mut spans = self.spans.borrow().clone();
+        spans.sort_by_key(|span| span.start);
+        spans
+    }
+
+    /// Formats the recorded spans as a table, including the total elapsed time.
+    pub fn table(&self) -> String {
+        let spans = self.spans();
+        let width = spans
+            .iter()
+            .map(|span| span.name.len())
+            .max()
+            .unwrap_or_default()
+            .max("total".len());
+
+        let mut table = String::new();
+        // Writing to a string can't fail, so the results are ignored.
+        let _ = writeln!(
+            table,
+            "{:<width$}  {:>10}  {:>10}",
+            "span", "start ms", "ms"
+        );
+        for span in spans {
+            let _ = writeln!(
+                table,
+                "{:<width$}  {:>10.3}  {:>10.3}",
+                span.name,
+                span.start.as_secs_f64() * 1000.0,
+                span.duration.as_secs_f64() * 1000.0,
+            );
+        }
+        let _ = write!(
+            table,
+            "{:<width$}  {:>10}  {:>10.3}",
+            "total",
+            "",
+            self.timer.elapsed_since_start().as_secs_f64() * 1000.0,
+        );
+        table
+    }
+
+    /// Logs the timing table of the recorded spans.
+    pub fn log(&self) {
+        info!("boot timing:\n{}", self.table());
+    }
+}
@@ -35,9 +35,18 @@ impl TpmProtocolHandle {
 }
 
 impl PlatformTpm {
+    /// The PCR for measuring the code loaded by the bootloader into, like EFI drivers.
+    pub const PCR_BOOT_LOADER_CODE: PcrIndex = PcrIndex(4);
+
     /// The PCR for measuring the bootloader configuration into.
     pub const PCR_BOOT_LOADER_CONFIG: PcrIndex = PcrIndex(5);
 
+    /// The PCR for measuring the sections of a unified kernel image into.
+    pub const PCR_KERNEL_IMAGE: PcrIndex = PcrIndex(11);
+
+    /// The PCR for measuring the kernel command line and other kernel configuration into.
+    pub const PCR_KERNEL_CONFIG: PcrIndex = PcrIndex(12);
+
     /// Acquire access to the TPM protocol handle, if possible.
     /// Returns None if TPM is not available.
     fn protocol() -> Result<Option<TpmProtocolHandle>> {
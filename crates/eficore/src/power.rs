@@ -0,0 +1,51 @@
+use crate::variables::{VariableClass, VariableController};
+use anyhow::{Context, Result};
+use uefi::runtime::ResetType;
+use uefi_raw::Status;
+
+/// The OsIndications bit that requests the firmware to boot into its setup user interface.
+const EFI_OS_INDICATIONS_BOOT_TO_FW_UI: u64 = 0x1;
+
+/// Decodes the u64 little-endian global variable specified by `key`, which is zero if not set.
+fn get_u64le(key: &str) -> Result<u64> {
+    Ok(VariableController::GLOBAL.get_u64le(key)?.unwrap_or(0))
+}
+
+/// Power management services.
+pub struct Power;
+
+impl Power {
+    /// Checks whether the firmware supports rebooting into its setup user interface.
+    pub fn firmware_setup_supported() -> Result<bool> {
+        let supported = get_u64le("OsIndicationsSupported")
+            .context("unable to get supported os indications")?;
+        Ok(supported & EFI_OS_INDICATIONS_BOOT_TO_FW_UI != 0)
+    }
+
+    /// Reboots the system into the firmware setup user interface.
+    /// This only returns if requesting the firmware setup user interface fails.
+    pub fn reboot_to_firmware_setup() -> Result<()> {
+        // Preserve the other indications that might have been requested.
+        let indications = get_u64le("OsIndications").context("unable to get os indications")?;
+        // The attributes the firmware created the variable with are kept.
+        let indications = indications | EFI_OS_INDICATIONS_BOOT_TO_FW_UI;
+        VariableController::GLOBAL
+            .set_preserving_attributes(
+                "OsIndications",
+                &indications.to_le_bytes(),
+                VariableClass::BootAndRuntimePersistent,
+            )
+            .context("unable to request firmware setup")?;
+        Self::reboot()
+    }
+
+    /// Reboots the system.
+    pub fn reboot() -> ! {
+        uefi::runtime::reset(ResetType::COLD, Status::SUCCESS, None)
+    }
+
+    /// Powers off the system.
+    pub fn power_off() -> ! {
+        uefi::runtime::reset(ResetType::SHUTDOWN, Status::SUCCESS, None)
+    }
+}
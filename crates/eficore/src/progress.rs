@@ -0,0 +1,68 @@
+use crate::services::services;
+use alloc::format;
+use alloc::string::String;
+
+/// The width of the progress bar, in characters.
+const BAR_WIDTH: usize = 40;
+
+/// The minimum size of an operation, in bytes, before progress is shown.
+/// Small files load quickly enough that a progress bar would only flicker.
+const MIN_VISIBLE_SIZE: usize = 8 * 1024 * 1024;
+
+/// A progress bar drawn on a single line of the console.
+/// The line is redrawn in place each time the completed percentage changes.
+pub struct ProgressBar {
+    /// The label shown before the bar.
+    label: String,
+    /// The percentage that was last drawn, if the bar has been drawn.
+    shown: Option<usize>,
+}
+
+impl ProgressBar {
+    /// Creates a progress bar with the specified `label`. Nothing is drawn until [ProgressBar::update].
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            shown: None,
+        }
+    }
+
+    /// Updates the progress bar to show that `done` out of `total` bytes are complete.
+    /// Operations smaller than [MIN_VISIBLE_SIZE] are not shown.
+    pub fn update(&mut self, done: usize, total: usize) {
+        if total < MIN_VISIBLE_SIZE || !log::log_enabled!(log::Level::Info) {
+            return;
+        }
+
+        // Only redraw when the visible percentage changes, as console output is slow.
+        let percent = (done.saturating_mul(100) / total).min(100);
+        if self.shown == Some(percent) {
+            return;
+        }
+        self.shown = Some(percent);
+
+        let filled = percent * BAR_WIDTH / 100;
+        let _ = services().write_console(&format!(
+            "\r{} [{:#<filled$}{:<empty$}] {:>3}%",
+            self.label,
+            "",
+            "",
+            percent,
+            filled = filled,
+            empty = BAR_WIDTH - filled,
+        ));
+    }
+
+    /// Finishes the progress bar, moving the console to the next line if the bar was drawn.
+    pub fn finish(&mut self) {
+        if self.shown.take().is_some() {
+            let _ = services().write_console("\n");
+        }
+    }
+}
+
+impl Drop for ProgressBar {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
@@ -0,0 +1,143 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use anyhow::Result;
+use spin::RwLock;
+use uefi::proto::device_path::DevicePath;
+use uefi::{Guid, Handle};
+use uefi_raw::table::runtime::{VariableAttributes, VariableVendor};
+
+/// efi: Firmware services backed by UEFI boot and runtime services.
+pub mod efi;
+
+/// memory: Firmware services backed by memory, for host builds.
+pub mod memory;
+
+/// The identity of the firmware that Sprout runs on.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FirmwareInfo {
+    /// The vendor of the firmware.
+    pub vendor: String,
+    /// The revision of the firmware, with the major version in the upper 16 bits.
+    pub revision: u32,
+    /// The major version of the UEFI specification the firmware implements.
+    pub uefi_major: u16,
+    /// The minor version of the UEFI specification the firmware implements.
+    pub uefi_minor: u16,
+}
+
+/// The firmware services that Sprout calls into, such as variables, file access,
+/// protocol lookup, and the console.
+///
+/// Production code calls [services] instead of calling `uefi::*` directly, so that the logic
+/// on top of the firmware can run against [memory::MemoryServices] outside of UEFI.
+pub trait FirmwareServices: Sync {
+    /// Retrieves the data and attributes of the variable `name` of the `vendor`.
+    /// Returns [None] if the variable is not set.
+    fn get_variable(
+        &self,
+        vendor: &VariableVendor,
+        name: &str,
+    ) -> Result<Option<(Vec<u8>, VariableAttributes)>>;
+
+    /// Sets the variable `name` of the `vendor` to `data` with the specified `attributes`.
+    fn set_variable(
+        &self,
+        vendor: &VariableVendor,
+        name: &str,
+        attributes: VariableAttributes,
+        data: &[u8],
+    ) -> Result<()>;
+
+    /// Enumerates the names of the variables of the `vendor` that are set.
+    fn variable_names(&self, vendor: &VariableVendor) -> Result<Vec<String>>;
+
+    /// Deletes the variable `name` of the `vendor`. This fails if the variable is not set.
+    fn delete_variable(&self, vendor: &VariableVendor, name: &str) -> Result<()>;
+
+    /// Reads the contents of the file at `path`, which is resolved against `default_root`.
+    fn read_file(&self, default_root: Option<&DevicePath>, path: &str) -> Result<Vec<u8>>;
+
+    /// Reads at most `length` bytes from the start of the file at `path`, which is resolved
+    /// against `default_root`. The contents are not checked against the ESP manifest.
+    fn read_file_prefix(
+        &self,
+        default_root: Option<&DevicePath>,
+        path: &str,
+        length: usize,
+    ) -> Result<Vec<u8>>;
+
+    /// Writes `data` to the file at `path`, which is resolved against `default_root`.
+    /// The file is replaced if it exists, and its directory is created if it is missing.
+    fn write_file(&self, default_root: Option<&DevicePath>, path: &str, data: &[u8]) -> Result<()>;
+
+    /// Writes `data` to the file at `path` like [FirmwareServices::write_file], but replaces
+    /// the file atomically, so an interruption leaves either the previous or the new contents.
+    /// Files written this way must be read with [FirmwareServices::read_file_atomic].
+    fn write_file_atomic(
+        &self,
+        default_root: Option<&DevicePath>,
+        path: &str,
+        data: &[u8],
+    ) -> Result<()> {
+        self.write_file(default_root, path, data)
+    }
+
+    /// Reads the contents of the file at `path` that is written with
+    /// [FirmwareServices::write_file_atomic], recovering the latest complete copy if a
+    /// replacement of the file was interrupted.
+    fn read_file_atomic(&self, default_root: Option<&DevicePath>, path: &str) -> Result<Vec<u8>> {
+        self.read_file(default_root, path)
+    }
+
+    /// Checks whether a file or directory exists at `path`, which is resolved against
+    /// `default_root`.
+    fn file_exists(&self, default_root: Option<&DevicePath>, path: &str) -> Result<bool>;
+
+    /// Lists the names of the regular files in the directory at `path`, which is resolved
+    /// against `default_root`. The names are sorted so the list is stable.
+    fn list_directory(&self, default_root: Option<&DevicePath>, path: &str) -> Result<Vec<String>>;
+
+    /// Finds the handles that provide the specified `protocol`.
+    /// Returns an empty list if no handle provides the protocol.
+    fn find_handles(&self, protocol: &Guid) -> Result<Vec<Handle>>;
+
+    /// Connects all controllers to their drivers, recursively. Controllers that fail to
+    /// connect are skipped, as connecting is expected to fail for some handles.
+    fn connect_controllers(&self) -> Result<()>;
+
+    /// Writes `text` to the console as is, without adding a line ending.
+    fn write_console(&self, text: &str) -> Result<()>;
+
+    /// Acquires the identity of the firmware.
+    fn firmware_info(&self) -> FirmwareInfo;
+}
+
+/// The firmware services in use, which are the UEFI services unless others are installed.
+static SERVICES: RwLock<&'static dyn FirmwareServices> = RwLock::new(&efi::EfiServices);
+
+/// Acquires the firmware services in use.
+pub fn services() -> &'static dyn FirmwareServices {
+    *SERVICES.read()
+}
+
+/// Installs `services` as the firmware services in use, which is intended for host builds
+/// that run Sprout logic against [memory::MemoryServices].
+pub fn install(services: &'static dyn FirmwareServices) {
+    *SERVICES.write() = services;
+}
+
+/// The [memory::MemoryServices] that the tests of Sprout logic run against.
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::memory::MemoryServices;
+
+    /// The memory services shared by the tests. The installed services are global,
+    /// so every test installs the same services, and tests use distinct variables and files.
+    static MEMORY: MemoryServices = MemoryServices::new();
+
+    /// Installs the shared memory services and returns them.
+    pub fn install_memory() -> &'static MemoryServices {
+        super::install(&MEMORY);
+        &MEMORY
+    }
+}
@@ -0,0 +1,147 @@
+use crate::services::{FirmwareInfo, FirmwareServices};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use anyhow::{Context, Result};
+use core::fmt::Write;
+use uefi::boot::SearchType;
+use uefi::proto::device_path::DevicePath;
+use uefi::{CString16, Guid, Handle};
+use uefi_raw::Status;
+use uefi_raw::table::runtime::{VariableAttributes, VariableVendor};
+
+/// Firmware services backed by UEFI boot and runtime services.
+pub struct EfiServices;
+
+impl EfiServices {
+    /// Convert `name` to a variable name as a CString16.
+    fn variable_name(name: &str) -> Result<CString16> {
+        CString16::try_from(name).context("unable to convert variable name to CString16")
+    }
+}
+
+impl FirmwareServices for EfiServices {
+    fn get_variable(
+        &self,
+        vendor: &VariableVendor,
+        name: &str,
+    ) -> Result<Option<(Vec<u8>, VariableAttributes)>> {
+        let key = Self::variable_name(name)?;
+
+        // Retrieve the variable data, handling variable not existing as None.
+        match uefi::runtime::get_variable_boxed(&key, vendor) {
+            Ok((data, attributes)) => Ok(Some((data.to_vec(), attributes))),
+            Err(error) => {
+                if error.status() == Status::NOT_FOUND {
+                    Ok(None)
+                } else {
+                    Err(error).with_context(|| format!("unable to get efi variable {}", name))
+                }
+            }
+        }
+    }
+
+    fn set_variable(
+        &self,
+        vendor: &VariableVendor,
+        name: &str,
+        attributes: VariableAttributes,
+        data: &[u8],
+    ) -> Result<()> {
+        let key = Self::variable_name(name)?;
+        uefi::runtime::set_variable(&key, vendor, attributes, data)
+            .with_context(|| format!("unable to set efi variable {}", name))
+    }
+
+    fn variable_names(&self, vendor: &VariableVendor) -> Result<Vec<String>> {
+        // Walk every variable with GetNextVariableName, keeping the ones of the vendor.
+        let mut names = Vec::new();
+        for key in uefi::runtime::variable_keys() {
+            let key = key.context("unable to enumerate efi variables")?;
+            if key.vendor == *vendor {
+                names.push(key.name.to_string());
+            }
+        }
+        Ok(names)
+    }
+
+    fn delete_variable(&self, vendor: &VariableVendor, name: &str) -> Result<()> {
+        let key = Self::variable_name(name)?;
+        uefi::runtime::delete_variable(&key, vendor)
+            .with_context(|| format!("unable to remove efi variable {}", name))
+    }
+
+    fn read_file(&self, default_root: Option<&DevicePath>, path: &str) -> Result<Vec<u8>> {
+        crate::path::read_file_contents(default_root, path)
+    }
+
+    fn read_file_prefix(
+        &self,
+        default_root: Option<&DevicePath>,
+        path: &str,
+        length: usize,
+    ) -> Result<Vec<u8>> {
+        crate::path::read_file_prefix(default_root, path, length)
+    }
+
+    fn write_file(&self, default_root: Option<&DevicePath>, path: &str, data: &[u8]) -> Result<()> {
+        crate::path::write_file_contents(default_root, path, data)
+    }
+
+    fn write_file_atomic(
+        &self,
+        default_root: Option<&DevicePath>,
+        path: &str,
+        data: &[u8],
+    ) -> Result<()> {
+        crate::atomic::write_file_contents(default_root, path, data)
+    }
+
+    fn read_file_atomic(&self, default_root: Option<&DevicePath>, path: &str) -> Result<Vec<u8>> {
+        crate::atomic::read_file_contents(default_root, path, |_| true)?
+            .with_context(|| format!("unable to read file {}", path))
+    }
+
+    fn file_exists(&self, default_root: Option<&DevicePath>, path: &str) -> Result<bool> {
+        crate::path::resolve_path(default_root, path)?.exists()
+    }
+
+    fn list_directory(&self, default_root: Option<&DevicePath>, path: &str) -> Result<Vec<String>> {
+        crate::path::list_directory_contents(default_root, path)
+    }
+
+    fn find_handles(&self, protocol: &Guid) -> Result<Vec<Handle>> {
+        match uefi::boot::locate_handle_buffer(SearchType::ByProtocol(protocol)) {
+            Ok(handles) => Ok(handles.to_vec()),
+            // If no handle provides the protocol, the list is empty.
+            Err(error) if error.status() == Status::NOT_FOUND => Ok(Vec::new()),
+            Err(error) => Err(error).context("unable to locate protocol handles"),
+        }
+    }
+
+    fn connect_controllers(&self) -> Result<()> {
+        let handles = uefi::boot::locate_handle_buffer(SearchType::AllHandles)
+            .context("unable to locate handles buffer")?;
+        for handle in handles.iter() {
+            // Ignore the result as there is nothing we can do if connecting a controller fails.
+            // This is also likely to fail in some cases but should fail safely.
+            let _ = uefi::boot::connect_controller(*handle, None, None, true);
+        }
+        Ok(())
+    }
+
+    fn write_console(&self, text: &str) -> Result<()> {
+        uefi::system::with_stdout(|stdout| stdout.write_str(text))
+            .map_err(|_| anyhow::anyhow!("unable to write to the console"))
+    }
+
+    fn firmware_info(&self) -> FirmwareInfo {
+        let uefi_revision = uefi::system::uefi_revision();
+        FirmwareInfo {
+            vendor: uefi::system::firmware_vendor().to_string(),
+            revision: uefi::system::firmware_revision(),
+            uefi_major: uefi_revision.major(),
+            uefi_minor: uefi_revision.minor(),
+        }
+    }
+}
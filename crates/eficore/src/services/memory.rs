@@ -0,0 +1,326 @@
+use crate::services::{FirmwareInfo, FirmwareServices};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use anyhow::{Context, Result, bail};
+use spin::Mutex;
+use uefi::proto::device_path::DevicePath;
+use uefi::{Guid, Handle};
+use uefi_raw::table::runtime::{VariableAttributes, VariableVendor};
+
+/// The variables of [MemoryServices], keyed by their vendor and name.
+type VariableStore = BTreeMap<(Guid, String), (Vec<u8>, VariableAttributes)>;
+
+/// The handles registered for each protocol in [MemoryServices].
+struct ProtocolHandles(BTreeMap<Guid, Vec<Handle>>);
+
+// SAFETY: The handles are opaque values that are only stored and returned, never dereferenced.
+unsafe impl Send for ProtocolHandles {}
+
+/// Firmware services backed by memory, for running Sprout logic on the host.
+///
+/// Variables, files, and protocol handles are inserted up front, and console output is
+/// captured so it can be inspected. Paths are plain strings with `\` separators, and the
+/// default root is ignored, as there is only a single filesystem.
+pub struct MemoryServices {
+    /// The variables, keyed by their vendor and name.
+    variables: Mutex<VariableStore>,
+    /// The files, keyed by their normalized path.
+    files: Mutex<BTreeMap<String, Vec<u8>>>,
+    /// The handles that provide each protocol.
+    handles: Mutex<ProtocolHandles>,
+    /// The text written to the console.
+    console: Mutex<String>,
+    /// The identity of the firmware.
+    firmware: Mutex<FirmwareInfo>,
+}
+
+impl MemoryServices {
+    /// Creates empty firmware services.
+    pub const fn new() -> Self {
+        Self {
+            variables: Mutex::new(BTreeMap::new()),
+            files: Mutex::new(BTreeMap::new()),
+            handles: Mutex::new(ProtocolHandles(BTreeMap::new())),
+            console: Mutex::new(String::new()),
+            firmware: Mutex::new(FirmwareInfo {
+                vendor: String::new(),
+                revision: 0,
+                uefi_major: 0,
+                uefi_minor: 0,
+            }),
+        }
+    }
+
+    /// Normalizes `path` to an absolute path with `\` separators and no trailing separator.
+    fn normalize(path: &str) -> String {
+        let mut normalized = String::new();
+        for component in path.split(['\\', '/']).filter(|part| !part.is_empty()) {
+            normalized.push('\\');
+            normalized.push_str(component);
+        }
+        normalized
+    }
+
+    /// Inserts a file at `path` with the specified `data`, replacing any existing file.
+    pub fn insert_file(&self, path: &str, data: impl Into<Vec<u8>>) {
+        self.files.lock().insert(Self::normalize(path), data.into());
+    }
+
+    /// Sets the identity of the firmware to `firmware`.
+    pub fn set_firmware_info(&self, firmware: FirmwareInfo) {
+        *self.firmware.lock() = firmware;
+    }
+
+    /// Registers `handle` as providing the specified `protocol`.
+    pub fn insert_handle(&self, protocol: Guid, handle: Handle) {
+        self.handles
+            .lock()
+            .0
+            .entry(protocol)
+            .or_default()
+            .push(handle);
+    }
+
+    /// Acquires the variables of the `vendor` that are set, sorted by name.
+    pub fn variables(&self, vendor: &VariableVendor) -> Vec<(String, Vec<u8>)> {
+        self.variables
+            .lock()
+            .iter()
+            .filter(|((guid, _), _)| *guid == vendor.0)
+            .map(|((_, name), (data, _))| (name.clone(), data.clone()))
+            .collect()
+    }
+
+    /// Acquires the text written to the console so far.
+    pub fn console(&self) -> String {
+        self.console.lock().clone()
+    }
+}
+
+impl Default for MemoryServices {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FirmwareServices for MemoryServices {
+    fn get_variable(
+        &self,
+        vendor: &VariableVendor,
+        name: &str,
+    ) -> Result<Option<(Vec<u8>, VariableAttributes)>> {
+        Ok(self
+            .variables
+            .lock()
+            .get(&(vendor.0, name.to_string()))
+            .cloned())
+    }
+
+    fn set_variable(
+        &self,
+        vendor: &VariableVendor,
+        name: &str,
+        attributes: VariableAttributes,
+        data: &[u8],
+    ) -> Result<()> {
+        let key = (vendor.0, name.to_string());
+        let mut variables = self.variables.lock();
+
+        // Like UEFI, appending adds to the existing data and keeps the variable attributes.
+        if attributes.contains(VariableAttributes::APPEND_WRITE) {
+            let attributes = attributes - VariableAttributes::APPEND_WRITE;
+            let (existing, _) = variables.entry(key).or_insert((Vec::new(), attributes));
+            existing.extend_from_slice(data);
+            return Ok(());
+        }
+
+        // Like UEFI, setting a variable to no data deletes it.
+        if data.is_empty() {
+            variables.remove(&key);
+        } else {
+            variables.insert(key, (data.to_vec(), attributes));
+        }
+        Ok(())
+    }
+
+    fn variable_names(&self, vendor: &VariableVendor) -> Result<Vec<String>> {
+        Ok(self
+            .variables(vendor)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect())
+    }
+
+    fn delete_variable(&self, vendor: &VariableVendor, name: &str) -> Result<()> {
+        if self
+            .variables
+            .lock()
+            .remove(&(vendor.0, name.to_string()))
+            .is_none()
+        {
+            bail!("unable to remove efi variable {}", name);
+        }
+        Ok(())
+    }
+
+    fn read_file(&self, _default_root: Option<&DevicePath>, path: &str) -> Result<Vec<u8>> {
+        self.files
+            .lock()
+            .get(&Self::normalize(path))
+            .cloned()
+            .with_context(|| format!("unable to read file {}", path))
+    }
+
+    fn read_file_prefix(
+        &self,
+        default_root: Option<&DevicePath>,
+        path: &str,
+        length: usize,
+    ) -> Result<Vec<u8>> {
+        let mut content = self.read_file(default_root, path)?;
+        content.truncate(length);
+        Ok(content)
+    }
+
+    fn write_file(
+        &self,
+        _default_root: Option<&DevicePath>,
+        path: &str,
+        data: &[u8],
+    ) -> Result<()> {
+        self.insert_file(path, data);
+        Ok(())
+    }
+
+    fn file_exists(&self, _default_root: Option<&DevicePath>, path: &str) -> Result<bool> {
+        let path = Self::normalize(path);
+        let prefix = format!("{}\\", path);
+        Ok(self
+            .files
+            .lock()
+            .keys()
+            .any(|file| *file == path || file.starts_with(&prefix)))
+    }
+
+    fn list_directory(
+        &self,
+        _default_root: Option<&DevicePath>,
+        path: &str,
+    ) -> Result<Vec<String>> {
+        let prefix = format!("{}\\", Self::normalize(path));
+        let files = self.files.lock();
+        let names = files
+            .keys()
+            .filter_map(|file| file.strip_prefix(&prefix))
+            .filter(|name| !name.contains('\\'))
+            .map(String::from)
+            .collect::<Vec<_>>();
+        if names.is_empty() && !files.keys().any(|file| file.starts_with(&prefix)) {
+            bail!("unable to read directory {}", path);
+        }
+        Ok(names)
+    }
+
+    fn find_handles(&self, protocol: &Guid) -> Result<Vec<Handle>> {
+        Ok(self
+            .handles
+            .lock()
+            .0
+            .get(protocol)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn connect_controllers(&self) -> Result<()> {
+        // There are no controllers in memory, so there is nothing to connect.
+        Ok(())
+    }
+
+    fn write_console(&self, text: &str) -> Result<()> {
+        self.console.lock().push_str(text);
+        Ok(())
+    }
+
+    fn firmware_info(&self) -> FirmwareInfo {
+        self.firmware.lock().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use uefi::guid;
+
+    const VENDOR: VariableVendor = VariableVendor(guid!("418edfbc-dcc5-466e-95cc-bf5b641b7295"));
+
+    #[test]
+    fn variables_behave_like_uefi() {
+        let services = MemoryServices::new();
+        let attributes = VariableAttributes::BOOTSERVICE_ACCESS;
+        services
+            .set_variable(&VENDOR, "Log", attributes, b"one")
+            .unwrap();
+        services
+            .set_variable(
+                &VENDOR,
+                "Log",
+                attributes | VariableAttributes::APPEND_WRITE,
+                b" two",
+            )
+            .unwrap();
+        assert_eq!(
+            services.get_variable(&VENDOR, "Log").unwrap(),
+            Some((b"one two".to_vec(), attributes))
+        );
+        assert_eq!(services.variable_names(&VENDOR).unwrap(), ["Log"]);
+
+        // Setting no data deletes the variable, and deleting it again fails.
+        services
+            .set_variable(&VENDOR, "Log", attributes, b"")
+            .unwrap();
+        assert_eq!(services.get_variable(&VENDOR, "Log").unwrap(), None);
+        assert!(services.delete_variable(&VENDOR, "Log").is_err());
+    }
+
+    #[test]
+    fn files_are_listed_by_directory() {
+        let services = MemoryServices::new();
+        services.insert_file("/boot/vmlinuz-6.1", b"kernel".to_vec());
+        services
+            .write_file(None, "\\boot\\initrd.img-6.1", b"initrd")
+            .unwrap();
+        services.insert_file("\\boot\\grub\\grub.cfg", vec![]);
+
+        assert_eq!(
+            services.read_file(None, "boot/vmlinuz-6.1").unwrap(),
+            b"kernel"
+        );
+        assert_eq!(
+            services
+                .read_file_prefix(None, "\\boot\\vmlinuz-6.1", 3)
+                .unwrap(),
+            b"ker"
+        );
+        assert_eq!(
+            services
+                .read_file_prefix(None, "\\boot\\vmlinuz-6.1", 64)
+                .unwrap(),
+            b"kernel"
+        );
+        assert_eq!(
+            services.list_directory(None, "\\boot").unwrap(),
+            ["initrd.img-6.1", "vmlinuz-6.1"]
+        );
+        assert!(services.list_directory(None, "\\efi").is_err());
+        assert!(services.file_exists(None, "\\boot\\grub").unwrap());
+        assert!(
+            services
+                .file_exists(None, "\\boot\\initrd.img-6.1")
+                .unwrap()
+        );
+        assert!(!services.file_exists(None, "\\boot\\gru").unwrap());
+    }
+}
@@ -1,5 +1,46 @@
 use crate::logger;
-use anyhow::{Context, Result};
+use alloc::vec::Vec;
+use anyhow::{Context, Result, bail};
+use core::str::FromStr;
+use log::warn;
+
+/// How the console is prepared once the environment is configured.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConsoleReset {
+    /// The console is left as the firmware set it up, which preserves a vendor splash.
+    #[default]
+    Keep,
+    /// The console is reset, which restores the default attributes and cursor.
+    Reset,
+    /// The console is reset and cleared.
+    Clear,
+}
+
+impl FromStr for ConsoleReset {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "keep" => Ok(Self::Keep),
+            "reset" => Ok(Self::Reset),
+            "clear" => Ok(Self::Clear),
+            _ => bail!("unknown console reset '{}'", value),
+        }
+    }
+}
+
+/// Options that control how the UEFI environment is configured by [configure].
+/// The defaults leave the environment as the firmware set it up.
+#[derive(Clone, Debug, Default)]
+pub struct SetupOptions {
+    /// Disables the watchdog the firmware arms before starting a boot option.
+    pub disable_watchdog: bool,
+    /// How the console is prepared.
+    pub console_reset: ConsoleReset,
+    /// The console modes to attempt as columns and rows, in order of preference.
+    /// The first mode the console supports is selected. If empty, the mode is not changed.
+    pub console_modes: Vec<(usize, usize)>,
+}
 
 /// Initializes the UEFI environment.
 pub fn init() -> Result<()> {
@@ -12,3 +53,40 @@ pub fn init() -> Result<()> {
     uefi::helpers::init().context("unable to initialize uefi environment")?;
     Ok(())
 }
+
+/// Configures the UEFI environment with the specified `options`.
+/// This is done once the options are known, after [init].
+pub fn configure(options: &SetupOptions) -> Result<()> {
+    if options.disable_watchdog {
+        crate::watchdog::disarm().context("unable to disable firmware watchdog")?;
+    }
+
+    uefi::system::with_stdout(|stdout| -> Result<()> {
+        // Select the first supported console mode. Selecting a mode also clears the console.
+        if !options.console_modes.is_empty() {
+            let supported = stdout.modes().collect::<Vec<_>>();
+            let mode = options.console_modes.iter().find_map(|(columns, rows)| {
+                supported
+                    .iter()
+                    .find(|mode| mode.columns() == *columns && mode.rows() == *rows)
+                    .copied()
+            });
+            match mode {
+                Some(mode) => stdout
+                    .set_mode(mode)
+                    .context("unable to set console mode")?,
+                None => warn!("none of the requested console modes are supported"),
+            }
+        }
+
+        match options.console_reset {
+            ConsoleReset::Keep => {}
+            ConsoleReset::Reset => stdout.reset(false).context("unable to reset console")?,
+            ConsoleReset::Clear => {
+                stdout.reset(false).context("unable to reset console")?;
+                stdout.clear().context("unable to clear console")?;
+            }
+        }
+        Ok(())
+    })
+}
@@ -1,8 +1,9 @@
+use crate::pages::PageBuffer;
 use crate::path::ResolvedPath;
+use crate::progress::ProgressBar;
 use crate::variables::{VariableClass, VariableController};
 use alloc::boxed::Box;
 use alloc::string::ToString;
-use alloc::vec::Vec;
 use anyhow::{Context, Result, anyhow, bail};
 use core::ffi::c_void;
 use core::pin::Pin;
@@ -22,6 +23,8 @@ pub struct ShimSupport;
 pub enum ShimInput<'a> {
     /// Data loaded into a buffer and ready to be verified, owned.
     OwnedDataBuffer(Option<&'a ResolvedPath>, Pin<Box<[u8]>>),
+    /// Data loaded into page-aligned memory and ready to be verified, owned.
+    OwnedPageBuffer(Option<&'a ResolvedPath>, PageBuffer),
     /// Data loaded into a buffer and ready to be verified.
     DataBuffer(Option<&'a ResolvedPath>, &'a [u8]),
     /// Low-level data buffer provided by the security hook.
@@ -40,6 +43,7 @@ impl<'a> ShimInput<'a> {
     pub fn buffer(&self) -> Option<&[u8]> {
         match self {
             ShimInput::OwnedDataBuffer(_, data) => Some(data),
+            ShimInput::OwnedPageBuffer(_, data) => Some(data),
             ShimInput::SecurityHookOwnedBuffer(_, data) => Some(data),
             ShimInput::SecurityHookBuffer(_, data) => Some(data),
             ShimInput::SecurityHookPath(_) => None,
@@ -52,6 +56,7 @@ impl<'a> ShimInput<'a> {
     pub fn file_path(&self) -> Option<&DevicePath> {
         match self {
             ShimInput::OwnedDataBuffer(path, _) => path.as_ref().map(|it| it.full_path.as_ref()),
+            ShimInput::OwnedPageBuffer(path, _) => path.as_ref().map(|it| it.full_path.as_ref()),
             ShimInput::DataBuffer(path, _) => path.as_ref().map(|it| it.full_path.as_ref()),
             ShimInput::SecurityHookBuffer(path, _) => {
                 path.map(|it| unsafe { DevicePath::from_ffi_ptr(it) })
@@ -65,11 +70,23 @@ impl<'a> ShimInput<'a> {
     }
 
     /// Converts this input into an owned data buffer, where the data is loaded.
-    /// For ResolvedPath, this will read the file.
+    /// For ResolvedPath, this will read the file directly into page-aligned memory.
     pub fn into_owned_data_buffer(self) -> Result<ShimInput<'a>> {
+        self.into_owned_data_buffer_with_progress(|_, _| {})
+    }
+
+    /// Converts this input into an owned data buffer, like [ShimInput::into_owned_data_buffer],
+    /// calling `progress` with the number of bytes read so far and the total size of the file
+    /// while a ResolvedPath is read.
+    pub fn into_owned_data_buffer_with_progress(
+        self,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<ShimInput<'a>> {
         match self {
             ShimInput::OwnedDataBuffer(root, data) => Ok(ShimInput::OwnedDataBuffer(root, data)),
 
+            ShimInput::OwnedPageBuffer(root, data) => Ok(ShimInput::OwnedPageBuffer(root, data)),
+
             ShimInput::DataBuffer(root, data) => Ok(ShimInput::OwnedDataBuffer(
                 root,
                 Box::into_pin(data.to_vec().into_boxed_slice()),
@@ -90,7 +107,7 @@ impl<'a> ShimInput<'a> {
                 let data = path.read_file()?;
                 Ok(ShimInput::SecurityHookOwnedBuffer(
                     Some(ffi_path),
-                    Box::into_pin(data.to_vec().into_boxed_slice()),
+                    Box::into_pin(data.into_boxed_slice()),
                 ))
             }
 
@@ -99,12 +116,14 @@ impl<'a> ShimInput<'a> {
             }
 
             ShimInput::ResolvedPath(path) => {
-                // Read the file path.
-                let data = path.read_file()?;
-                Ok(ShimInput::OwnedDataBuffer(
-                    Some(path),
-                    Box::into_pin(data.to_vec().into_boxed_slice()),
-                ))
+                // Read the file path straight into pages, as images can be large.
+                let mut bar = ProgressBar::new("loading image");
+                let data = path.read_file_pages_with_progress(|done, total| {
+                    bar.update(done, total);
+                    progress(done, total);
+                })?;
+                bar.finish();
+                Ok(ShimInput::OwnedPageBuffer(Some(path), data))
             }
 
             ShimInput::SecurityHookOwnedBuffer(path, data) => {
@@ -124,20 +143,20 @@ pub enum ShimVerificationOutput {
     VerifiedDataNotLoaded,
     /// Verifying the data resulted in loading the data from the source.
     /// This contains the data that was loaded, so it won't need to be loaded again.
-    VerifiedDataBuffer(Vec<u8>),
+    VerifiedDataBuffer(PageBuffer),
 }
 
 /// The shim lock protocol as defined by the shim loader application.
 #[unsafe_protocol(ShimSupport::SHIM_LOCK_GUID)]
 struct ShimLockProtocol {
     /// Verify the data in `buffer` with the size `buffer_size` to determine if it is valid.
-    /// NOTE: On x86_64, this function uses SYSV calling conventions. On aarch64 it uses the
-    /// efiapi calling convention. This is truly wild, but you can verify it yourself by
+    /// NOTE: On x86_64, this function uses SYSV calling conventions. On aarch64 and arm it uses
+    /// the efiapi calling convention. This is truly wild, but you can verify it yourself by
     /// looking at: https://github.com/rhboot/shim/blob/15.8/shim.h#L207-L212
     /// There is no calling convention declared like there should be.
     #[cfg(target_arch = "x86_64")]
     pub shim_verify: unsafe extern "sysv64" fn(buffer: *const c_void, buffer_size: u32) -> Status,
-    #[cfg(target_arch = "aarch64")]
+    #[cfg(any(target_arch = "aarch64", target_arch = "arm"))]
     pub shim_verify: unsafe extern "efiapi" fn(buffer: *const c_void, buffer_size: u32) -> Status,
     /// Unused function that is defined by the shim.
     _generate_header: *mut c_void,
@@ -180,31 +199,23 @@ impl ShimSupport {
             .context("unable to open shim lock protocol")?;
 
         // If the input type is a device path, we need to load the data.
+        // The data is read into pages once, so it can be reused to load the image.
         let maybe_loaded_data = match input {
-            ShimInput::OwnedDataBuffer(_, _data) => {
-                bail!("owned data buffer is not supported in the verification function");
-            }
-            ShimInput::SecurityHookBuffer(_, _) => None,
-            ShimInput::SecurityHookOwnedBuffer(_, _) => None,
-            ShimInput::DataBuffer(_, _) => None,
-            ShimInput::ResolvedPath(path) => Some(path.read_file()?),
-            ShimInput::SecurityHookPath(_) => None,
+            ShimInput::ResolvedPath(path) => Some(path.read_file_pages()?),
+            _ => None,
         };
 
         // Convert the input to a buffer.
         // If the input provides the data buffer, we will use that.
         // Otherwise, we will use the data loaded by this function.
-        let buffer = match &input {
-            ShimInput::OwnedDataBuffer(_root, data) => data,
-            ShimInput::DataBuffer(_root, data) => *data,
-            ShimInput::ResolvedPath(_path) => maybe_loaded_data
-                .as_deref()
-                .context("expected data buffer to be loaded already")?,
-            ShimInput::SecurityHookBuffer(_, data) => data,
-            ShimInput::SecurityHookOwnedBuffer(_, data) => data,
-            ShimInput::SecurityHookPath(_) => {
+        let buffer = match (&input, &maybe_loaded_data) {
+            (_, Some(data)) => &data[..],
+            (ShimInput::SecurityHookPath(_), None) => {
                 bail!("security hook path input not supported in the verification function")
             }
+            (input, None) => input
+                .buffer()
+                .context("expected data buffer to be loaded already")?,
         };
 
         // Check if the buffer is too large to verify.
@@ -1,9 +1,10 @@
 use crate::shim::{ShimInput, ShimSupport, ShimVerificationOutput};
+use alloc::boxed::Box;
 use anyhow::{Context, Result};
 use core::slice;
 use log::warn;
 use spin::{Lazy, Mutex};
-use uefi::proto::device_path::FfiDevicePath;
+use uefi::proto::device_path::{DevicePath, FfiDevicePath};
 use uefi::proto::unsafe_protocol;
 use uefi::{Guid, guid};
 use uefi_raw::Status;
@@ -47,6 +48,25 @@ struct SecurityHookState {
 /// This is messy, but it is safe given the mutex.
 static GLOBAL_HOOK_STATE: Lazy<Mutex<Option<SecurityHookState>>> = Lazy::new(|| Mutex::new(None));
 
+/// The image that is currently being loaded from a buffer.
+/// Path-only verification requests for this image reuse the buffer instead of reading the file again.
+struct LoadingImage {
+    /// The device path the image is loaded with.
+    path: Box<DevicePath>,
+    /// The start of the buffer containing the image.
+    buffer: *const u8,
+    /// The length of the buffer containing the image.
+    length: usize,
+}
+
+// SAFETY: UEFI boot services are single-threaded, and the buffer is only
+// referenced while the image is being loaded.
+unsafe impl Send for LoadingImage {}
+
+/// The image that is currently being loaded, if any.
+/// This is messy, but it is safe given the mutex.
+static LOADING_IMAGE: Mutex<Option<LoadingImage>> = Mutex::new(None);
+
 /// Security hook helper.
 pub struct SecurityHook;
 
@@ -93,16 +113,28 @@ impl SecurityHook {
             return Status::INVALID_PARAMETER;
         }
 
-        // Construct a shim input from the path.
-        let input = ShimInput::SecurityHookPath(path);
-
-        // Convert the input to an owned data buffer.
-        let input = match input.into_owned_data_buffer() {
-            Ok(input) => input,
-            // If an error occurs, log the error and return the not found status.
-            Err(error) => {
-                warn!("unable to read data to be authenticated: {}", error);
-                return Status::NOT_FOUND;
+        // If the path is the image being loaded, verify the buffer that is already in memory.
+        // SAFETY: The path was checked to not be null and is provided by the firmware.
+        let device_path = unsafe { DevicePath::from_ffi_ptr(path) };
+        let loading = LOADING_IMAGE
+            .lock()
+            .as_ref()
+            .filter(|loading| *loading.path == *device_path)
+            .map(|loading| (loading.buffer, loading.length));
+
+        let input = if let Some((buffer, length)) = loading {
+            // SAFETY: The buffer is valid while the image is being loaded, see [Self::set_loading_image].
+            let buffer = unsafe { slice::from_raw_parts(buffer, length) };
+            ShimInput::SecurityHookBuffer(Some(path), buffer)
+        } else {
+            // Construct a shim input from the path and convert it to an owned data buffer.
+            match ShimInput::SecurityHookPath(path).into_owned_data_buffer() {
+                Ok(input) => input,
+                // If an error occurs, log the error and return the not found status.
+                Err(error) => {
+                    warn!("unable to read data to be authenticated: {}", error);
+                    return Status::NOT_FOUND;
+                }
             }
         };
 
@@ -177,6 +209,24 @@ impl SecurityHook {
         }
     }
 
+    /// Marks the image at `path` as being loaded from `buffer`, so that path-only
+    /// verification requests for it reuse the buffer instead of reading the file again.
+    ///
+    /// # Safety
+    /// The `buffer` must remain valid until [SecurityHook::clear_loading_image] is called.
+    pub unsafe fn set_loading_image(path: &DevicePath, buffer: &[u8]) {
+        LOADING_IMAGE.lock().replace(LoadingImage {
+            path: path.to_boxed(),
+            buffer: buffer.as_ptr(),
+            length: buffer.len(),
+        });
+    }
+
+    /// Clears the image marked as being loaded by [SecurityHook::set_loading_image].
+    pub fn clear_loading_image() {
+        LOADING_IMAGE.lock().take();
+    }
+
     /// Install the security hook if needed.
     pub fn install() -> Result<bool> {
         // Find the security arch protocol. If we can't find it, we will return false.
@@ -1,22 +1,90 @@
+use alloc::string::String;
 use alloc::vec::Vec;
 use anyhow::{Context, Result, bail};
-use uefi::CString16;
+use uefi::{CStr16, CString16};
+
+/// The marker appended to text that was truncated by [truncate_with_ellipsis].
+/// Firmware fonts only reliably cover ASCII, so this is not the ellipsis character.
+const ELLIPSIS: &str = "...";
 
 /// Convert a byte slice into a CString16.
 pub fn utf16_bytes_to_cstring16(bytes: &[u8]) -> Result<CString16> {
+    CString16::try_from(decode_utf16le_units(bytes)?)
+        .context("unable to convert utf16 bytes to CString16")
+}
+
+/// Reinterprets UTF-16LE `bytes` as UTF-16 code units.
+fn decode_utf16le_units(bytes: &[u8]) -> Result<Vec<u16>> {
     // Validate the input bytes are the right length.
     if !bytes.len().is_multiple_of(2) {
         bail!("utf16 bytes must be a multiple of 2");
     }
 
-    // Convert the bytes to UTF-16 data.
-    let data = bytes
+    Ok(bytes
         // Chunk everything into two bytes.
         .chunks_exact(2)
         // Reinterpret the bytes as u16 little-endian.
         .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
         // Collect the result into a vector.
-        .collect::<Vec<_>>();
+        .collect::<Vec<_>>())
+}
 
-    CString16::try_from(data).context("unable to convert utf16 bytes to CString16")
+/// Encode `value` as UTF-16LE bytes, without a null terminator.
+pub fn encode_utf16le(value: &str) -> Vec<u8> {
+    value
+        .encode_utf16()
+        .flat_map(|unit| unit.to_le_bytes())
+        .collect()
+}
+
+/// Encode `value` as UTF-16LE bytes, followed by a null terminator,
+/// which is how strings are stored in EFI variables.
+pub fn encode_utf16le_nul(value: &str) -> Vec<u8> {
+    let mut encoded = encode_utf16le(value);
+    encoded.extend_from_slice(&[0, 0]);
+    encoded
+}
+
+/// Decode UTF-16LE `bytes` into a [String], stopping at the first null terminator if any.
+/// Fails if the bytes are not valid UTF-16.
+pub fn decode_utf16le(bytes: &[u8]) -> Result<String> {
+    let units = decode_utf16le_units(bytes)?;
+    let end = units
+        .iter()
+        .position(|unit| *unit == 0)
+        .unwrap_or(units.len());
+    String::from_utf16(&units[..end]).context("utf16 bytes are not valid utf16")
+}
+
+/// Checks whether `value` can be represented in UCS-2, the encoding of [CString16].
+/// UCS-2 only covers the basic multilingual plane, and null characters are not allowed.
+pub fn is_ucs2(value: &str) -> bool {
+    value
+        .chars()
+        .all(|c| c != '\0' && u32::from(c) <= u32::from(u16::MAX))
+}
+
+/// Compares `a` and `b` for equality, ignoring case.
+pub fn eq_ignore_case(a: &CStr16, b: &CStr16) -> bool {
+    let lower = |c: &uefi::Char16| char::from(*c).to_lowercase();
+    a.iter().flat_map(lower).eq(b.iter().flat_map(lower))
+}
+
+/// Truncates `value` to at most `width` characters for display.
+/// Truncated text ends with an ellipsis, so it is clear that text is missing.
+pub fn truncate_with_ellipsis(value: &str, width: usize) -> String {
+    if value.chars().count() <= width {
+        return String::from(value);
+    }
+
+    // Without room for the ellipsis, the text is cut off as is.
+    if width < ELLIPSIS.len() {
+        return value.chars().take(width).collect();
+    }
+    let mut truncated = value
+        .chars()
+        .take(width - ELLIPSIS.len())
+        .collect::<String>();
+    truncated.push_str(ELLIPSIS);
+    truncated
 }
@@ -0,0 +1,98 @@
+use crate::platform::timer::PlatformTimer;
+use alloc::string::{String, ToString};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::time::Duration;
+use spin::Mutex;
+
+/// The timer that spans are measured with, if set.
+static TRACE_TIMER: Mutex<Option<PlatformTimer>> = Mutex::new(None);
+
+/// The number of spans that are currently entered, used to report nesting.
+static DEPTH: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets the `timer` that spans are measured with.
+/// Sharing the timer of the boot timing report lines up the trace with the timing spans.
+/// Spans entered before a timer is set are not recorded.
+pub fn set_timer(timer: PlatformTimer) {
+    *TRACE_TIMER.lock() = Some(timer);
+}
+
+/// Checks whether spans are compiled in, which requires the `trace-spans` feature.
+pub const fn enabled() -> bool {
+    cfg!(feature = "trace-spans")
+}
+
+/// A span of time that is logged as a trace line when it is dropped.
+/// Spans are created with the [instrument](crate::instrument) macro, which compiles
+/// them out entirely unless the `trace-spans` feature is enabled.
+pub struct Span {
+    /// The name of the span.
+    name: String,
+    /// The nesting depth of the span, where zero is the outermost span.
+    depth: usize,
+    /// The timer and the time the span started, if a timer is set.
+    start: Option<(PlatformTimer, Duration)>,
+}
+
+impl Span {
+    /// Enters a span with the specified `name`.
+    pub fn enter(name: impl ToString) -> Self {
+        let timer = *TRACE_TIMER.lock();
+        let start = timer.map(|timer| (timer, timer.elapsed_since_start()));
+        let depth = DEPTH.fetch_add(1, Ordering::Relaxed);
+        Self {
+            name: name.to_string(),
+            depth,
+            start,
+        }
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        DEPTH.fetch_sub(1, Ordering::Relaxed);
+        let Some((timer, start)) = self.start else {
+            return;
+        };
+        let duration = timer.elapsed_since_start().saturating_sub(start);
+        // The line is formatted as key-value pairs so it can be extracted from the log.
+        log::info!(
+            "trace span={:?} depth={} start_us={} duration_us={}",
+            self.name,
+            self.depth,
+            start.as_micros(),
+            duration.as_micros()
+        );
+    }
+}
+
+/// Measures a span of time and logs it as a trace line.
+///
+/// `instrument!(name)` measures until the end of the enclosing scope, while
+/// `instrument!(name, expression)` measures the evaluation of the expression.
+/// Without the `trace-spans` feature, neither the span nor the name are evaluated.
+#[cfg(feature = "trace-spans")]
+#[macro_export]
+macro_rules! instrument {
+    ($name:expr) => {
+        let _span = $crate::trace::Span::enter($name);
+    };
+    ($name:expr, $body:expr) => {{
+        let _span = $crate::trace::Span::enter($name);
+        $body
+    }};
+}
+
+/// Measures a span of time and logs it as a trace line.
+///
+/// `instrument!(name)` measures until the end of the enclosing scope, while
+/// `instrument!(name, expression)` measures the evaluation of the expression.
+/// Without the `trace-spans` feature, neither the span nor the name are evaluated.
+#[cfg(not(feature = "trace-spans"))]
+#[macro_export]
+macro_rules! instrument {
+    ($name:expr) => {};
+    ($name:expr, $body:expr) => {
+        $body
+    };
+}
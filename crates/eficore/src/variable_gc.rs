@@ -0,0 +1,71 @@
+use crate::platform::clock::PlatformClock;
+use crate::variables::{VariableClass, VariableController};
+use alloc::string::String;
+use alloc::vec::Vec;
+use anyhow::{Context, Result};
+use edera_sprout_parsing::variable_stamps::{SECONDS_PER_DAY, VariableStamps};
+
+/// The name of the Sprout variable that stores the last use times of the variables that
+/// Sprout wrote and that are collected when they become stale.
+const STAMPS_VARIABLE: &str = "SproutVariableStamps";
+
+/// Reads the current time of the real-time clock as a unix timestamp.
+pub(crate) fn now() -> Result<i64> {
+    Ok(PlatformClock::now()?.unix_timestamp())
+}
+
+/// Reads the stamps of the collectable variables.
+fn load_stamps() -> Result<VariableStamps> {
+    let data = VariableController::SPROUT
+        .get(STAMPS_VARIABLE)
+        .context("unable to read variable stamps")?;
+    Ok(data
+        .map(|data| VariableStamps::parse(&data))
+        .unwrap_or_default())
+}
+
+/// Writes the stamps of the collectable variables, keeping the attributes they have.
+fn store_stamps(stamps: &VariableStamps) -> Result<()> {
+    VariableController::SPROUT
+        .set_preserving_attributes(
+            STAMPS_VARIABLE,
+            &stamps.encode(),
+            VariableClass::BootAndRuntimePersistent,
+        )
+        .context("unable to write variable stamps")
+}
+
+/// Records that Sprout wrote or used the variable `name` at the unix timestamp `now`,
+/// which makes it collectable and restarts its age.
+pub(crate) fn touch(name: &str, now: i64) -> Result<()> {
+    let mut stamps = load_stamps()?;
+    let original = stamps.clone();
+    stamps.touch(name, now);
+    // Only write the stamps when they changed, to spare the variable store.
+    if stamps != original {
+        store_stamps(&stamps)?;
+    }
+    Ok(())
+}
+
+/// Determines which of the collectable variables in `names` were not used in the last
+/// `max_age_days` days at the unix timestamp `now`, and stops tracking them. The `in_use`
+/// variable is never collected. If `dry_run` is set, the stamps are not changed.
+pub(crate) fn collect(
+    names: &[String],
+    in_use: Option<&str>,
+    max_age_days: u64,
+    now: i64,
+    dry_run: bool,
+) -> Result<Vec<String>> {
+    let mut stamps = load_stamps()?;
+    let original = stamps.clone();
+    let max_age = i64::try_from(max_age_days)
+        .unwrap_or(i64::MAX)
+        .saturating_mul(SECONDS_PER_DAY);
+    let stale = stamps.sweep(names, in_use, now, max_age);
+    if !dry_run && stamps != original {
+        store_stamps(&stamps)?;
+    }
+    Ok(stale)
+}
@@ -1,11 +1,11 @@
+use crate::services::services;
 use crate::strings;
 use alloc::format;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use anyhow::{Context, Result};
 use log::warn;
-use uefi::{CString16, guid};
-use uefi_raw::Status;
+use uefi::{Guid, guid};
 use uefi_raw::table::runtime::{VariableAttributes, VariableVendor};
 
 /// The classification of a variable.
@@ -14,6 +14,11 @@ use uefi_raw::table::runtime::{VariableAttributes, VariableVendor};
 pub enum VariableClass {
     /// The variable is available in Boot Services and Runtime Services and is not persistent.
     BootAndRuntimeTemporary,
+    /// The variable is available in Boot Services and Runtime Services and is persistent.
+    BootAndRuntimePersistent,
+    /// The variable is available in Boot Services and Runtime Services and is persistent,
+    /// and writes append to the existing data instead of replacing it.
+    BootAndRuntimeAppend,
 }
 
 impl VariableClass {
@@ -23,10 +28,30 @@ impl VariableClass {
             VariableClass::BootAndRuntimeTemporary => {
                 VariableAttributes::BOOTSERVICE_ACCESS | VariableAttributes::RUNTIME_ACCESS
             }
+            VariableClass::BootAndRuntimePersistent => {
+                VariableAttributes::NON_VOLATILE
+                    | VariableAttributes::BOOTSERVICE_ACCESS
+                    | VariableAttributes::RUNTIME_ACCESS
+            }
+            VariableClass::BootAndRuntimeAppend => {
+                VariableClass::BootAndRuntimePersistent.attributes()
+                    | VariableAttributes::APPEND_WRITE
+            }
         }
     }
 }
 
+/// Information about a variable, as enumerated by [VariableController::iter].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VariableInfo {
+    /// The name of the variable.
+    pub name: String,
+    /// The size of the data of the variable in bytes.
+    pub size: usize,
+    /// The attributes of the variable.
+    pub attributes: VariableAttributes,
+}
+
 /// Provides access to a particular set of vendor variables.
 pub struct VariableController {
     /// The GUID of the vendor.
@@ -39,98 +64,167 @@ impl VariableController {
         "8be4df61-93ca-11d2-aa0d-00e098032b8c"
     )));
 
+    /// Sprout-specific variables.
+    pub const SPROUT: VariableController = VariableController::new(VariableVendor(guid!(
+        "418edfbc-dcc5-466e-95cc-bf5b641b7295"
+    )));
+
     /// Create a new [VariableController] for the `vendor`.
     pub const fn new(vendor: VariableVendor) -> Self {
         Self { vendor }
     }
 
-    /// Convert `key` to a variable name as a CString16.
-    fn name(key: &str) -> Result<CString16> {
-        CString16::try_from(key).context("unable to convert variable name to CString16")
-    }
-
     /// Retrieve the cstr16 value specified by the `key`.
     /// Returns None if the value isn't set.
     /// If the value is not decodable, we will return None and log a warning.
     pub fn get_cstr16(&self, key: &str) -> Result<Option<String>> {
-        let name = Self::name(key)?;
-
         // Retrieve the variable data, handling variable not existing as None.
-        match uefi::runtime::get_variable_boxed(&name, &self.vendor) {
-            Ok((data, _)) => {
-                // Try to decode UTF-16 bytes to a CString16.
-                match strings::utf16_bytes_to_cstring16(&data) {
-                    Ok(value) => {
-                        // We have a value, so return the UTF-8 value.
-                        Ok(Some(value.to_string()))
-                    }
-
-                    Err(error) => {
-                        // We encountered an error, so warn and return None.
-                        warn!("efi variable '{}' is not valid UTF-16: {}", key, error);
-                        Ok(None)
-                    }
-                }
-            }
+        let Some(data) = self.get(key)? else {
+            return Ok(None);
+        };
+
+        // Try to decode UTF-16 bytes to a CString16.
+        match strings::utf16_bytes_to_cstring16(&data) {
+            // We have a value, so return the UTF-8 value.
+            Ok(value) => Ok(Some(value.to_string())),
 
             Err(error) => {
-                // If the variable does not exist, we will return None.
-                if error.status() == Status::NOT_FOUND {
-                    Ok(None)
-                } else {
-                    Err(error).with_context(|| format!("unable to get efi variable {}", key))
-                }
+                // We encountered an error, so warn and return None.
+                warn!("efi variable '{}' is not valid UTF-16: {}", key, error);
+                Ok(None)
             }
         }
     }
 
-    /// Retrieve a boolean value specified by the `key`.
-    pub fn get_bool(&self, key: &str) -> Result<bool> {
-        let name = Self::name(key)?;
+    /// Retrieve the raw data of the variable specified by the `key`.
+    /// Returns None if the value isn't set.
+    pub fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let value = services().get_variable(&self.vendor, key)?;
+        Ok(value.map(|(data, _)| data))
+    }
 
-        // Retrieve the variable data, handling variable not existing as false.
-        match uefi::runtime::get_variable_boxed(&name, &self.vendor) {
-            Ok((data, _)) => {
-                // If the variable is zero-length, we treat it as false.
-                if data.is_empty() {
-                    Ok(false)
-                } else {
-                    // We treat the variable as true if the first byte is non-zero.
-                    Ok(data[0] > 0)
-                }
-            }
+    /// Retrieve the raw data and attributes of the variable specified by the `key`.
+    /// Returns None if the value isn't set.
+    pub fn get_raw_with_attributes(
+        &self,
+        key: &str,
+    ) -> Result<Option<(Vec<u8>, VariableAttributes)>> {
+        services().get_variable(&self.vendor, key)
+    }
 
-            Err(error) => {
-                // If the variable does not exist, we treat it as false.
-                if error.status() == Status::NOT_FOUND {
-                    Ok(false)
-                } else {
-                    Err(error).with_context(|| format!("unable to get efi variable {}", key))
-                }
-            }
+    /// Retrieve the fixed-size value of the variable specified by the `key`.
+    /// Returns None if the value isn't set, and fails if the value is too short.
+    /// Data past the size of the value is ignored, as some firmware pads variables.
+    fn get_array<const N: usize>(&self, key: &str, kind: &str) -> Result<Option<[u8; N]>> {
+        let Some(data) = self.get(key)? else {
+            return Ok(None);
+        };
+        let bytes = data
+            .get(..N)
+            .and_then(|bytes| <[u8; N]>::try_from(bytes).ok())
+            .with_context(|| format!("efi variable {} is not a {}", key, kind))?;
+        Ok(Some(bytes))
+    }
+
+    /// Retrieve the u16 little-endian value of the variable specified by the `key`.
+    /// Returns None if the value isn't set, and fails if the value is too short for a u16.
+    pub fn get_u16le(&self, key: &str) -> Result<Option<u16>> {
+        Ok(self.get_array(key, "u16")?.map(u16::from_le_bytes))
+    }
+
+    /// Retrieve the u64 little-endian value of the variable specified by the `key`.
+    /// Returns None if the value isn't set, and fails if the value is too short for a u64.
+    pub fn get_u64le(&self, key: &str) -> Result<Option<u64>> {
+        Ok(self.get_array(key, "u64")?.map(u64::from_le_bytes))
+    }
+
+    /// Retrieve the GUID value of the variable specified by the `key`, stored in its
+    /// mixed-endian binary form. Returns None if the value isn't set,
+    /// and fails if the value is too short for a GUID.
+    pub fn get_guid(&self, key: &str) -> Result<Option<Guid>> {
+        Ok(self.get_array(key, "guid")?.map(Guid::from_bytes))
+    }
+
+    /// Enumerate all the variables of the vendor, with their sizes and attributes.
+    /// Variables that are removed while they are enumerated are skipped.
+    pub fn iter(&self) -> Result<impl Iterator<Item = VariableInfo>> {
+        let mut variables = Vec::new();
+        for name in services().variable_names(&self.vendor)? {
+            let Some((data, attributes)) = services().get_variable(&self.vendor, &name)? else {
+                continue;
+            };
+            variables.push(VariableInfo {
+                name,
+                size: data.len(),
+                attributes,
+            });
         }
+        Ok(variables.into_iter())
+    }
+
+    /// Retrieve a boolean value specified by the `key`.
+    pub fn get_bool(&self, key: &str) -> Result<bool> {
+        // Retrieve the variable data, handling variable not existing as false.
+        // If the variable is zero-length, we treat it as false.
+        // We treat the variable as true if the first byte is non-zero.
+        Ok(self
+            .get(key)?
+            .is_some_and(|data| data.first().is_some_and(|byte| *byte > 0)))
     }
 
     /// Set a variable specified by `key` to `value`.
     /// The variable `class` controls the attributes for the variable.
     pub fn set(&self, key: &str, value: &[u8], class: VariableClass) -> Result<()> {
-        let name = Self::name(key)?;
-        uefi::runtime::set_variable(&name, &self.vendor, class.attributes(), value)
-            .with_context(|| format!("unable to set efi variable {}", key))?;
-        Ok(())
+        services().set_variable(&self.vendor, key, class.attributes(), value)
+    }
+
+    /// Append `value` to the data of the variable specified by `key`,
+    /// creating the variable if it is not set. Appended variables are persistent.
+    pub fn append(&self, key: &str, value: &[u8]) -> Result<()> {
+        self.set(key, value, VariableClass::BootAndRuntimeAppend)
+    }
+
+    /// Replace the data of the variable specified by `key` with the result of `update`,
+    /// which is passed the current data, or [None] if the variable is not set.
+    ///
+    /// The attributes of an existing variable are preserved, so a persistent variable stays
+    /// persistent. A variable that is not set is created with the attributes of `class`.
+    /// If `update` returns [None], the variable is left unchanged.
+    pub fn update(
+        &self,
+        key: &str,
+        class: VariableClass,
+        update: impl FnOnce(Option<&[u8]>) -> Option<Vec<u8>>,
+    ) -> Result<()> {
+        let current = self.get_raw_with_attributes(key)?;
+        let Some(value) = update(current.as_ref().map(|(data, _)| data.as_slice())) else {
+            return Ok(());
+        };
+
+        // Appending is a property of a single write, so it is never preserved.
+        let attributes = match current {
+            Some((_, attributes)) => attributes - VariableAttributes::APPEND_WRITE,
+            None => class.attributes(),
+        };
+        services().set_variable(&self.vendor, key, attributes, &value)
+    }
+
+    /// Set a variable specified by `key` to `value`, keeping the attributes of the variable
+    /// if it is already set. Otherwise, the variable `class` controls the attributes.
+    pub fn set_preserving_attributes(
+        &self,
+        key: &str,
+        value: &[u8],
+        class: VariableClass,
+    ) -> Result<()> {
+        self.update(key, class, |_| Some(value.to_vec()))
     }
 
     /// Set a variable specified by `key` to `value`, converting the value to
-    /// a [CString16]. The variable `class` controls the attributes for the variable.
+    /// a [uefi::CString16]. The variable `class` controls the attributes for the variable.
     pub fn set_cstr16(&self, key: &str, value: &str, class: VariableClass) -> Result<()> {
-        // Encode the value as a CString16 little endian.
-        let mut encoded = value
-            .encode_utf16()
-            .flat_map(|c| c.to_le_bytes())
-            .collect::<Vec<u8>>();
-        // Add a null terminator to the end of the value.
-        encoded.extend_from_slice(&[0, 0]);
-        self.set(key, &encoded, class)
+        // Encode the value as a null-terminated CString16 little endian.
+        self.set(key, &strings::encode_utf16le_nul(value), class)
     }
 
     /// Set a boolean variable specified by `key` to `value`, converting the value.
@@ -148,10 +242,7 @@ impl VariableController {
     /// Remove the variable specified by `key`.
     /// This can fail if the variable is not set.
     pub fn remove(&self, key: &str) -> Result<()> {
-        let name = Self::name(key)?;
-
-        // Delete the variable from UEFI.
-        uefi::runtime::delete_variable(&name, &self.vendor)
-            .with_context(|| format!("unable to remove efi variable {}", key))
+        // Delete the variable from the firmware.
+        services().delete_variable(&self.vendor, key)
     }
 }
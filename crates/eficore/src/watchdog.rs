@@ -0,0 +1,21 @@
+use anyhow::{Context, Result};
+use core::time::Duration;
+
+/// The code logged by the firmware when the watchdog expires.
+/// Codes up to 0xFFFF are reserved for the firmware.
+const SPROUT_WATCHDOG_CODE: u64 = 0x10000;
+
+/// Arms the boot services watchdog timer, which resets the system once `timeout` passes.
+/// The watchdog is disabled by the firmware when boot services are exited, so an operating
+/// system that takes ownership of the system in time is not affected.
+pub fn arm(timeout: Duration) -> Result<()> {
+    let seconds = usize::try_from(timeout.as_secs()).unwrap_or(usize::MAX);
+    uefi::boot::set_watchdog_timer(seconds, SPROUT_WATCHDOG_CODE, None)
+        .context("unable to arm watchdog timer")
+}
+
+/// Disarms the boot services watchdog timer.
+pub fn disarm() -> Result<()> {
+    uefi::boot::set_watchdog_timer(0, SPROUT_WATCHDOG_CODE, None)
+        .context("unable to disarm watchdog timer")
+}
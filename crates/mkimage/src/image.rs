@@ -0,0 +1,256 @@
+use anyhow::{Context, Result, bail};
+use edera_sprout_parsing::pe::{Machine, parse_pe};
+
+/// The offset of the pointer to the PE signature in the DOS header.
+const PE_POINTER_OFFSET: usize = 0x3c;
+
+/// The size of the PE signature in front of the COFF file header.
+const PE_SIGNATURE_SIZE: usize = 4;
+
+/// The size of the COFF file header.
+const COFF_HEADER_SIZE: usize = 20;
+
+/// The size of an entry of the section table.
+const SECTION_HEADER_SIZE: usize = 40;
+
+/// The magic of the optional header of a PE32+ image.
+const PE32_PLUS_MAGIC: u16 = 0x20b;
+
+/// The index of the certificate table in the data directories.
+const CERTIFICATE_TABLE_INDEX: usize = 4;
+
+/// The characteristics of an added section: initialized data that is readable.
+const SECTION_CHARACTERISTICS: u32 = 0x40000040;
+
+/// Reads a little-endian u16 from `data` at `offset`.
+fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+    let bytes = data.get(offset..offset + 2).context("image is truncated")?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+/// Reads a little-endian u32 from `data` at `offset`.
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    let bytes = data.get(offset..offset + 4).context("image is truncated")?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Writes `value` as a little-endian u16 into `data` at `offset`.
+fn write_u16(data: &mut [u8], offset: usize, value: u16) {
+    data[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+/// Writes `value` as a little-endian u32 into `data` at `offset`.
+fn write_u32(data: &mut [u8], offset: usize, value: u32) {
+    data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+/// Rounds `value` up to the next multiple of `alignment`.
+fn align_up(value: usize, alignment: usize) -> usize {
+    value.div_ceil(alignment.max(1)) * alignment.max(1)
+}
+
+/// A section to add to the stub when assembling an image.
+pub struct Section {
+    /// The name of the section, like `.linux`.
+    pub name: String,
+    /// The contents of the section.
+    pub data: Vec<u8>,
+}
+
+impl Section {
+    /// Creates a section with the specified `name` and `data`.
+    pub fn new(name: &str, data: Vec<u8>) -> Self {
+        Self {
+            name: name.to_string(),
+            data,
+        }
+    }
+}
+
+/// Determines the machine type of the PE image in `data`, if it is a PE image.
+pub fn machine(data: &[u8]) -> Option<Machine> {
+    parse_pe(data).map(|image| image.machine)
+}
+
+/// Assembles an image from the `stub` PE image with the `sections` added to it.
+///
+/// Sections of the stub with the same name as an added section are removed from the
+/// section table, so the added section replaces them. The signature of the stub is removed,
+/// as it does not cover the added sections, so the image must be signed after assembly.
+/// The headers of the stub must have room for the added entries of the section table.
+pub fn assemble(stub: &[u8], sections: &[Section]) -> Result<Vec<u8>> {
+    // Validate the stub and acquire its sections.
+    let parsed = parse_pe(stub).context("stub is not a valid PE image")?;
+
+    let coff = read_u32(stub, PE_POINTER_OFFSET)? as usize + PE_SIGNATURE_SIZE;
+    let optional_header_size = read_u16(stub, coff + 16)? as usize;
+    let optional = coff + COFF_HEADER_SIZE;
+    let section_alignment = read_u32(stub, optional + 32)? as usize;
+    let file_alignment = read_u32(stub, optional + 36)? as usize;
+    let headers_size = read_u32(stub, optional + 60)? as usize;
+    let directories = if read_u16(stub, optional)? == PE32_PLUS_MAGIC {
+        optional + 112
+    } else {
+        optional + 96
+    };
+    let table = optional + optional_header_size;
+
+    // Keep the raw entries of the sections that are not replaced, so their
+    // characteristics are preserved.
+    let mut entries = Vec::new();
+    for (index, section) in parsed.sections.iter().enumerate() {
+        if sections.iter().any(|added| added.name == section.name) {
+            continue;
+        }
+        let offset = table + index * SECTION_HEADER_SIZE;
+        entries.push(stub[offset..offset + SECTION_HEADER_SIZE].to_vec());
+    }
+
+    // The section table must fit in the headers, in front of the first section data.
+    let count = entries.len() + sections.len();
+    if table + count * SECTION_HEADER_SIZE > headers_size {
+        bail!(
+            "stub has no room in its headers for {} sections, only {} bytes are available",
+            count,
+            headers_size.saturating_sub(table)
+        );
+    }
+
+    // Added sections are placed after every section of the stub in memory,
+    // including the replaced sections, so nothing overlaps.
+    let mut virtual_end = parsed
+        .sections
+        .iter()
+        .map(|section| {
+            section.virtual_address as usize + section.virtual_size.max(section.raw_size) as usize
+        })
+        .max()
+        .unwrap_or(headers_size);
+
+    // Remove the signature, which is stored at the end of the file.
+    let mut image = stub.to_vec();
+    let certificate = directories + CERTIFICATE_TABLE_INDEX * 8;
+    // The number of data directories precedes them, and older images might not have the table.
+    let has_certificate_table = read_u32(stub, directories - 4)? as usize > CERTIFICATE_TABLE_INDEX;
+    if has_certificate_table
+        && let (Ok(offset), Ok(size)) =
+            (read_u32(stub, certificate), read_u32(stub, certificate + 4))
+        && offset != 0
+    {
+        if offset as usize + size as usize >= image.len() {
+            image.truncate(offset as usize);
+        }
+        write_u32(&mut image, certificate, 0);
+        write_u32(&mut image, certificate + 4, 0);
+    }
+
+    // Append the data of the added sections and create their entries.
+    for section in sections {
+        if section.name.len() > 8 {
+            bail!("section name {} is longer than 8 bytes", section.name);
+        }
+        let raw_offset = align_up(image.len(), file_alignment);
+        let raw_size = align_up(section.data.len(), file_alignment);
+        let virtual_address = align_up(virtual_end, section_alignment);
+        image.resize(raw_offset, 0);
+        image.extend_from_slice(&section.data);
+        image.resize(raw_offset + raw_size, 0);
+        virtual_end = virtual_address + section.data.len();
+
+        let mut entry = vec![0u8; SECTION_HEADER_SIZE];
+        entry[..section.name.len()].copy_from_slice(section.name.as_bytes());
+        write_u32(&mut entry, 8, section.data.len() as u32);
+        write_u32(&mut entry, 12, virtual_address as u32);
+        write_u32(&mut entry, 16, raw_size as u32);
+        write_u32(&mut entry, 20, raw_offset as u32);
+        write_u32(&mut entry, 36, SECTION_CHARACTERISTICS);
+        entries.push(entry);
+    }
+
+    // Rewrite the section table and the headers that describe the image layout.
+    image[table..headers_size].fill(0);
+    for (index, entry) in entries.iter().enumerate() {
+        let offset = table + index * SECTION_HEADER_SIZE;
+        image[offset..offset + SECTION_HEADER_SIZE].copy_from_slice(entry);
+    }
+    write_u16(&mut image, coff + 2, count as u16);
+    write_u32(
+        &mut image,
+        optional + 56,
+        align_up(virtual_end, section_alignment) as u32,
+    );
+    // The checksum is not verified by firmware, and is invalid after the changes.
+    write_u32(&mut image, optional + 64, 0);
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a PE32+ stub with a `.text` and a `.sbat` section.
+    fn stub() -> Vec<u8> {
+        let mut data = vec![0u8; 0x800];
+        data[..2].copy_from_slice(b"MZ");
+        write_u32(&mut data, 0x3c, 0x80);
+        data[0x80..0x84].copy_from_slice(b"PE\0\0");
+        write_u16(&mut data, 0x84, 0x8664);
+        write_u16(&mut data, 0x86, 2);
+        write_u16(&mut data, 0x94, 0xf0);
+        let optional = 0x98;
+        write_u16(&mut data, optional, PE32_PLUS_MAGIC);
+        write_u32(&mut data, optional + 32, 0x1000);
+        write_u32(&mut data, optional + 36, 0x200);
+        write_u32(&mut data, optional + 56, 0x3000);
+        write_u32(&mut data, optional + 60, 0x400);
+
+        let table = optional + 0xf0;
+        for (index, name) in [".text", ".sbat"].iter().enumerate() {
+            let entry = table + index * SECTION_HEADER_SIZE;
+            data[entry..entry + name.len()].copy_from_slice(name.as_bytes());
+            write_u32(&mut data, entry + 8, 0x10);
+            write_u32(&mut data, entry + 12, 0x1000 * (index as u32 + 1));
+            write_u32(&mut data, entry + 16, 0x200);
+            write_u32(&mut data, entry + 20, 0x400 + 0x200 * index as u32);
+        }
+        data[0x600..0x610].copy_from_slice(b"sbat,1,SBAT,1,x\n");
+        data
+    }
+
+    #[test]
+    fn assemble_unified_kernel_image() {
+        let image = assemble(
+            &stub(),
+            &[
+                Section::new(".osrel", b"ID=test\n".to_vec()),
+                Section::new(".sbat", b"sbat,1,SBAT,1,x\nlinux,1,x\n".to_vec()),
+                Section::new(".linux", b"kernel".to_vec()),
+            ],
+        )
+        .unwrap();
+
+        let parsed = parse_pe(&image).expect("image should parse");
+        let names = parsed
+            .sections
+            .iter()
+            .map(|section| section.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, [".text", ".osrel", ".sbat", ".linux"]);
+        assert_eq!(parsed.osrel(), Some("ID=test\n"));
+        assert_eq!(parsed.sbat(), Some("sbat,1,SBAT,1,x\nlinux,1,x\n"));
+        assert_eq!(parsed.linux(), Some(&b"kernel"[..]));
+        assert_eq!(parsed.sections[1].virtual_address, 0x3000);
+        assert_eq!(parsed.sections[3].virtual_address, 0x5000);
+        assert_eq!(read_u32(&image, 0x98 + 56).unwrap(), 0x6000);
+        assert_eq!(image.len() % 0x200, 0);
+    }
+
+    #[test]
+    fn reject_full_headers() {
+        let sections = (0..16)
+            .map(|index| Section::new(&format!(".s{}", index), vec![0u8; 4]))
+            .collect::<Vec<_>>();
+        assert!(assemble(&stub(), &sections).is_err());
+        assert!(assemble(&[0u8; 0x200], &[]).is_err());
+    }
+}
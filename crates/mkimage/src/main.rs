@@ -0,0 +1,213 @@
+//! sprout-mkimage: assembles unified kernel images on the host.
+//! This combines a stub, like Sprout or systemd-stub, with a kernel, initrds, a command line,
+//! an os-release, and SBAT metadata into a single PE image that can be signed and booted.
+
+use crate::image::Section;
+use anyhow::{Context, Result, bail};
+use edera_sprout_parsing::pe::{
+    SECTION_CMDLINE, SECTION_INITRD, SECTION_LINUX, SECTION_OSREL, SECTION_SBAT, parse_pe,
+};
+use jaarg::{
+    ErrorUsageWriter, ErrorUsageWriterContext, HelpWriter, HelpWriterContext, Opt, Opts,
+    ParseControl, ParseResult, StandardErrorUsageWriter, StandardFullHelpWriter,
+};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// image: Assembly of PE images from a stub and added sections.
+pub mod image;
+
+/// The parsed options of sprout-mkimage.
+#[derive(Debug, Default)]
+struct MkImageOptions {
+    /// The path to the stub to add the sections to.
+    stub: PathBuf,
+    /// The path to the kernel to embed.
+    linux: PathBuf,
+    /// The paths to the initrds to embed, which are concatenated in order.
+    initrds: Vec<PathBuf>,
+    /// The kernel command line to embed.
+    cmdline: Option<String>,
+    /// The path to the os-release to embed.
+    os_release: Option<PathBuf>,
+    /// The path to the SBAT metadata to add to the SBAT metadata of the stub.
+    sbat: Option<PathBuf>,
+    /// The path to write the assembled image to.
+    output: PathBuf,
+}
+
+impl MkImageOptions {
+    /// Produces [MkImageOptions] from the command line arguments.
+    /// Returns the [ExitCode] to exit with if the program should not continue.
+    fn parse() -> Result<MkImageOptions, ExitCode> {
+        enum ArgID {
+            Help,
+            Stub,
+            Linux,
+            Initrd,
+            Cmdline,
+            OsRelease,
+            Sbat,
+            Output,
+        }
+
+        // All the options for the sprout-mkimage executable.
+        const OPTIONS: Opts<ArgID> = Opts::new(&[
+            Opt::help_flag(ArgID::Help, &["--help"]).help_text("Display sprout-mkimage Help"),
+            Opt::value(ArgID::Stub, &["--stub"], "PATH")
+                .required()
+                .help_text("Path to the stub, like sprout.efi"),
+            Opt::value(ArgID::Linux, &["--linux"], "PATH")
+                .required()
+                .help_text("Path to the kernel to embed"),
+            Opt::value(ArgID::Initrd, &["--initrd"], "PATH")
+                .help_text("Path to an initrd to embed, can be specified multiple times"),
+            Opt::value(ArgID::Cmdline, &["--cmdline"], "OPTIONS")
+                .help_text("Kernel command line to embed"),
+            Opt::value(ArgID::OsRelease, &["--os-release"], "PATH")
+                .help_text("Path to the os-release to embed"),
+            Opt::value(ArgID::Sbat, &["--sbat"], "PATH")
+                .help_text("Path to SBAT metadata to add to the SBAT metadata of the stub"),
+            Opt::value(ArgID::Output, &["--output"], "PATH")
+                .required()
+                .help_text("Path to write the assembled image to"),
+        ]);
+
+        let mut result = Self::default();
+
+        match OPTIONS.parse(
+            "sprout-mkimage",
+            std::env::args().skip(1),
+            |program_name, id, _opt, _name, value| {
+                match id {
+                    ArgID::Stub => {
+                        // The stub to add the sections to.
+                        result.stub = value.into();
+                    }
+                    ArgID::Linux => {
+                        // The kernel to embed.
+                        result.linux = value.into();
+                    }
+                    ArgID::Initrd => {
+                        // An initrd to embed, in order.
+                        result.initrds.push(value.into());
+                    }
+                    ArgID::Cmdline => {
+                        // The kernel command line to embed.
+                        result.cmdline = Some(value.to_string());
+                    }
+                    ArgID::OsRelease => {
+                        // The os-release to embed.
+                        result.os_release = Some(value.into());
+                    }
+                    ArgID::Sbat => {
+                        // The SBAT metadata to add.
+                        result.sbat = Some(value.into());
+                    }
+                    ArgID::Output => {
+                        // The path to write the image to.
+                        result.output = value.into();
+                    }
+                    ArgID::Help => {
+                        let ctx = HelpWriterContext {
+                            options: &OPTIONS,
+                            program_name,
+                        };
+                        print!("{}", StandardFullHelpWriter::new(ctx));
+                        return Ok(ParseControl::Quit);
+                    }
+                }
+                Ok(ParseControl::Continue)
+            },
+            |program_name, error| {
+                let ctx = ErrorUsageWriterContext {
+                    options: &OPTIONS,
+                    program_name,
+                    error,
+                };
+                eprint!("{}", StandardErrorUsageWriter::new(ctx));
+            },
+        ) {
+            ParseResult::ContinueSuccess => Ok(result),
+            ParseResult::ExitSuccess => Err(ExitCode::SUCCESS),
+            ParseResult::ExitError => Err(ExitCode::FAILURE),
+        }
+    }
+}
+
+/// Reads the file at `path`, describing it as `what` in errors.
+fn read(path: &Path, what: &str) -> Result<Vec<u8>> {
+    std::fs::read(path).with_context(|| format!("unable to read {} {}", what, path.display()))
+}
+
+/// Assembles the image specified by `options` and writes it to the output path.
+fn run(options: &MkImageOptions) -> Result<()> {
+    let stub = read(&options.stub, "stub")?;
+    let linux = read(&options.linux, "kernel")?;
+    let parsed = parse_pe(&stub).context("stub is not a valid PE image")?;
+
+    // A kernel that is a PE image must run on the same architecture as the stub.
+    if let Some(machine) = image::machine(&linux)
+        && machine != parsed.machine
+    {
+        bail!(
+            "kernel architecture {} does not match stub architecture {}",
+            machine.name(),
+            parsed.machine.name()
+        );
+    }
+
+    // The sections are ordered like systemd-stub expects, with the kernel last.
+    let mut sections = Vec::new();
+    if let Some(ref path) = options.os_release {
+        sections.push(Section::new(SECTION_OSREL, read(path, "os-release")?));
+    }
+    if let Some(ref cmdline) = options.cmdline {
+        sections.push(Section::new(SECTION_CMDLINE, cmdline.as_bytes().to_vec()));
+    }
+
+    // The SBAT metadata of the stub is kept, as it describes the stub itself.
+    if let Some(ref path) = options.sbat {
+        let mut sbat = parsed.sbat().unwrap_or_default().as_bytes().to_vec();
+        if !sbat.is_empty() && !sbat.ends_with(b"\n") {
+            sbat.push(b'\n');
+        }
+        sbat.extend(read(path, "SBAT metadata")?);
+        sections.push(Section::new(SECTION_SBAT, sbat));
+    }
+
+    // Initrds are cpio archives, which can be concatenated into a single initrd.
+    if !options.initrds.is_empty() {
+        let mut initrd = Vec::new();
+        for path in &options.initrds {
+            initrd.extend(read(path, "initrd")?);
+        }
+        sections.push(Section::new(SECTION_INITRD, initrd));
+    }
+    sections.push(Section::new(SECTION_LINUX, linux));
+
+    let assembled = image::assemble(&stub, &sections)?;
+    std::fs::write(&options.output, assembled)
+        .with_context(|| format!("unable to write image {}", options.output.display()))?;
+    println!(
+        "{}: assembled {} sections",
+        options.output.display(),
+        sections.len()
+    );
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let options = match MkImageOptions::parse() {
+        Ok(options) => options,
+        Err(code) => return code,
+    };
+
+    match run(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {:#}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
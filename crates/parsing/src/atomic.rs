@@ -0,0 +1,99 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// The suffix of the copy of a file that is written before it replaces the file.
+pub const NEW_SUFFIX: &str = ".new";
+
+/// The suffix of the previous copy of a file while it is being replaced.
+pub const OLD_SUFFIX: &str = ".old";
+
+/// The path of the copy of the file at `path` that is written before it replaces the file.
+pub fn new_path(path: &str) -> String {
+    format!("{}{}", path, NEW_SUFFIX)
+}
+
+/// The path of the previous copy of the file at `path` while it is being replaced.
+pub fn old_path(path: &str) -> String {
+    format!("{}{}", path, OLD_SUFFIX)
+}
+
+/// A copy of a file that is replaced atomically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileCopy {
+    /// The file itself.
+    Target,
+    /// The copy with the [NEW_SUFFIX], which is complete once it is flushed.
+    New,
+    /// The copy with the [OLD_SUFFIX], which holds the previous contents.
+    Old,
+}
+
+/// Decides which copies of a file that is replaced atomically can be read, in order of
+/// preference, from whether the `target`, `new`, and `old` copies exist.
+///
+/// A file is replaced by writing and flushing the new copy, renaming the target to the old
+/// copy, renaming the new copy to the target, and then deleting the old copy. An interruption
+/// at any point leaves copies that tell how far the replacement got:
+/// - If the target exists, it is complete, and the old copy is only a fallback.
+/// - If only the new and old copies exist, the new copy was flushed before the target was
+///   renamed away, so it is complete.
+/// - If only the old copy exists, it is the last complete copy.
+/// - A new copy without an old copy may have been torn while it was written, so it is never
+///   read. Either the file never existed, or it was replaced by the target.
+pub fn readable_copies(target: bool, new: bool, old: bool) -> Vec<FileCopy> {
+    let mut copies = match (target, new) {
+        (true, _) => vec![FileCopy::Target],
+        (false, true) if old => vec![FileCopy::New],
+        _ => Vec::new(),
+    };
+    if old {
+        copies.push(FileCopy::Old);
+    }
+    copies
+}
+
+/// Decides how to recover a file that is replaced atomically before it is replaced again,
+/// from whether the `target`, `new`, and `old` copies exist. Returns the copy that becomes
+/// the target, if any, which is renamed to the target when it isn't the target already.
+/// All other copies are stale and are deleted. See [readable_copies].
+pub fn recovered_copy(target: bool, new: bool, old: bool) -> Option<FileCopy> {
+    readable_copies(target, new, old).first().copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn readable_copies_follow_the_replacement() {
+        // A file that was never replaced.
+        assert_eq!(readable_copies(false, false, false), vec![]);
+        assert_eq!(readable_copies(true, false, false), vec![FileCopy::Target]);
+        // Interrupted while the new copy was written.
+        assert_eq!(readable_copies(false, true, false), vec![]);
+        assert_eq!(readable_copies(true, true, false), vec![FileCopy::Target]);
+        // Interrupted after the target was renamed away.
+        assert_eq!(
+            readable_copies(false, true, true),
+            vec![FileCopy::New, FileCopy::Old]
+        );
+        // Interrupted before the old copy was deleted.
+        assert_eq!(
+            readable_copies(true, false, true),
+            vec![FileCopy::Target, FileCopy::Old]
+        );
+        assert_eq!(readable_copies(false, false, true), vec![FileCopy::Old]);
+    }
+
+    #[test]
+    fn recovery_prefers_the_latest_complete_copy() {
+        assert_eq!(recovered_copy(true, true, true), Some(FileCopy::Target));
+        assert_eq!(recovered_copy(false, true, true), Some(FileCopy::New));
+        assert_eq!(recovered_copy(false, false, true), Some(FileCopy::Old));
+        assert_eq!(recovered_copy(false, true, false), None);
+        assert_eq!(new_path("\\sprout\\seed"), "\\sprout\\seed.new");
+        assert_eq!(old_path("\\sprout\\seed"), "\\sprout\\seed.old");
+    }
+}
@@ -0,0 +1,143 @@
+use alloc::vec::Vec;
+
+/// The signature at the start of a BMP file.
+const BMP_SIGNATURE: &[u8] = b"BM";
+
+/// The compression of a BMP that stores pixels without compression.
+const BI_RGB: u32 = 0;
+
+/// The compression of a BMP that stores pixels with bit field masks.
+/// Sprout only accepts the standard blue, green, red masks of 32-bit images.
+const BI_BITFIELDS: u32 = 3;
+
+/// The maximum width or height of a BMP that is decoded, which guards against
+/// corrupt headers requesting enormous allocations.
+const MAXIMUM_DIMENSION: usize = 8192;
+
+/// A decoded BMP image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bitmap {
+    /// The width of the image in pixels.
+    pub width: usize,
+    /// The height of the image in pixels.
+    pub height: usize,
+    /// The pixels of the image, four bytes per pixel in blue, green, red, reserved order,
+    /// with rows from top to bottom and no padding between rows.
+    pub pixels: Vec<u8>,
+}
+
+/// Reads a little-endian u16 from `data` at `offset`.
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+/// Reads a little-endian u32 from `data` at `offset`.
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// Decodes the BMP image in `data`.
+/// Only uncompressed 24-bit and 32-bit images are supported, which is what image editors
+/// produce by default. Returns [None] if the image is not a supported BMP.
+pub fn decode_bmp(data: &[u8]) -> Option<Bitmap> {
+    if !data.starts_with(BMP_SIGNATURE) {
+        return None;
+    }
+
+    let pixel_offset = read_u32(data, 10)? as usize;
+    let width = read_u32(data, 18)? as i32;
+    let height = read_u32(data, 22)? as i32;
+    let bits_per_pixel = read_u16(data, 28)?;
+    let compression = read_u32(data, 30)?;
+    let valid_compression =
+        compression == BI_RGB || (compression == BI_BITFIELDS && bits_per_pixel == 32);
+    if !valid_compression || !matches!(bits_per_pixel, 24 | 32) {
+        return None;
+    }
+
+    // A negative height means the rows are stored top to bottom instead of bottom to top.
+    let top_down = height < 0;
+    let width: usize = width.try_into().ok().filter(|width| *width > 0)?;
+    let height = height.unsigned_abs() as usize;
+    if width > MAXIMUM_DIMENSION || height > MAXIMUM_DIMENSION || height == 0 {
+        return None;
+    }
+
+    // Rows are padded to a multiple of four bytes.
+    let bytes_per_pixel = bits_per_pixel as usize / 8;
+    let stride = (width * bytes_per_pixel).div_ceil(4) * 4;
+    let rows = data.get(pixel_offset..pixel_offset.checked_add(stride * height)?)?;
+
+    let mut pixels = Vec::with_capacity(width * height * 4);
+    for index in 0..height {
+        let row = if top_down { index } else { height - 1 - index };
+        let row = &rows[row * stride..row * stride + width * bytes_per_pixel];
+        for pixel in row.chunks_exact(bytes_per_pixel) {
+            pixels.extend_from_slice(&[pixel[0], pixel[1], pixel[2], 0]);
+        }
+    }
+
+    Some(Bitmap {
+        width,
+        height,
+        pixels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    /// Builds a 24-bit BMP of 3x2 pixels, where each pixel has a distinct blue value.
+    fn bmp(height: i32) -> Vec<u8> {
+        let mut data = vec![0u8; 54];
+        data[..2].copy_from_slice(BMP_SIGNATURE);
+        data[10..14].copy_from_slice(&54u32.to_le_bytes());
+        data[14..18].copy_from_slice(&40u32.to_le_bytes());
+        data[18..22].copy_from_slice(&3i32.to_le_bytes());
+        data[22..26].copy_from_slice(&height.to_le_bytes());
+        data[26..28].copy_from_slice(&1u16.to_le_bytes());
+        data[28..30].copy_from_slice(&24u16.to_le_bytes());
+        for row in 0..2u8 {
+            for column in 0..3u8 {
+                data.extend_from_slice(&[row * 3 + column, 0x10, 0x20]);
+            }
+            // Each row of 9 bytes is padded to 12 bytes.
+            data.extend_from_slice(&[0, 0, 0]);
+        }
+        data
+    }
+
+    #[test]
+    fn decode_bottom_up_image() {
+        let bitmap = decode_bmp(&bmp(2)).expect("bmp should decode");
+        assert_eq!((bitmap.width, bitmap.height), (3, 2));
+        assert_eq!(bitmap.pixels.len(), 3 * 2 * 4);
+        // The last row in the file is the top row of the image.
+        assert_eq!(&bitmap.pixels[..4], &[3, 0x10, 0x20, 0]);
+        assert_eq!(&bitmap.pixels[12..16], &[0, 0x10, 0x20, 0]);
+    }
+
+    #[test]
+    fn decode_top_down_image() {
+        let bitmap = decode_bmp(&bmp(-2)).expect("bmp should decode");
+        assert_eq!(&bitmap.pixels[..4], &[0, 0x10, 0x20, 0]);
+        assert_eq!(&bitmap.pixels[20..24], &[5, 0x10, 0x20, 0]);
+    }
+
+    #[test]
+    fn reject_unsupported_images() {
+        assert_eq!(decode_bmp(b"not a bmp"), None);
+        let mut truncated = bmp(2);
+        truncated.truncate(60);
+        assert_eq!(decode_bmp(&truncated), None);
+        let mut paletted = bmp(2);
+        paletted[28..30].copy_from_slice(&8u16.to_le_bytes());
+        assert_eq!(decode_bmp(&paletted), None);
+    }
+}
@@ -0,0 +1,122 @@
+use crate::load_option::parse_boot_option_name;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// The first line of a boot variables backup, which identifies the format.
+const BACKUP_HEADER: &str = "# sprout boot variables backup v1";
+
+/// Checks whether the global variable `name` is a boot variable that is backed up,
+/// which are the boot options, like `Boot0001`, `BootOrder`, and `Timeout`.
+pub fn is_boot_variable(name: &str) -> bool {
+    name == "BootOrder" || name == "Timeout" || parse_boot_option_name(name).is_some()
+}
+
+/// A backup of the firmware boot variables, as their names and data.
+/// This is stored as lines of `name=hex`, after a header line, which keeps the backup
+/// readable and editable from the booted OS.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BootVariablesBackup {
+    /// The backed up variables, sorted by name.
+    pub variables: Vec<(String, Vec<u8>)>,
+}
+
+impl BootVariablesBackup {
+    /// Creates a backup of the boot variables in `variables`, skipping any other variables.
+    pub fn new(variables: impl IntoIterator<Item = (String, Vec<u8>)>) -> Self {
+        let mut variables = variables
+            .into_iter()
+            .filter(|(name, _)| is_boot_variable(name))
+            .collect::<Vec<_>>();
+        variables.sort();
+        variables.dedup_by(|a, b| a.0 == b.0);
+        Self { variables }
+    }
+
+    /// Parses the backup encoded in `data`. Empty lines and `#` comments are ignored.
+    /// A corrupted backup is rejected as a whole, as restoring only
+    /// some of the boot options could leave the boot order pointing at missing entries.
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        let text = core::str::from_utf8(data).map_err(|_| "backup is not valid UTF-8")?;
+        if !text.starts_with(BACKUP_HEADER) {
+            return Err("backup is missing its header".to_string());
+        }
+
+        let mut variables = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {} is not a variable", index + 1))?;
+            let name = name.trim();
+            if !is_boot_variable(name) {
+                return Err(format!("line {} is not a boot variable", index + 1));
+            }
+            let value = hex::decode(value.trim())
+                .map_err(|_| format!("line {} has invalid data", index + 1))?;
+            variables.push((name.to_string(), value));
+        }
+        Ok(Self::new(variables))
+    }
+
+    /// Encodes the backup, in the format accepted by [BootVariablesBackup::parse].
+    pub fn encode(&self) -> Vec<u8> {
+        let mut text = format!("{}\n", BACKUP_HEADER);
+        for (name, value) in &self.variables {
+            text.push_str(&format!("{}={}\n", name, hex::encode(value)));
+        }
+        text.into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn variable(name: &str, data: &[u8]) -> (String, Vec<u8>) {
+        (name.to_string(), data.to_vec())
+    }
+
+    #[test]
+    fn boot_variables_are_recognized() {
+        assert!(is_boot_variable("Boot0001"));
+        assert!(is_boot_variable("BootOrder"));
+        assert!(is_boot_variable("Timeout"));
+        assert!(!is_boot_variable("BootCurrent"));
+        assert!(!is_boot_variable("Boot0001x"));
+        assert!(!is_boot_variable("LoaderEntries"));
+    }
+
+    #[test]
+    fn backup_round_trips() {
+        let backup = BootVariablesBackup::new(vec![
+            variable("Timeout", &[5, 0]),
+            variable("Boot0001", &[1, 0, 0, 0]),
+            variable("ConOut", &[1]),
+            variable("BootOrder", &[1, 0]),
+        ]);
+        assert_eq!(
+            backup.encode(),
+            b"# sprout boot variables backup v1\nBoot0001=01000000\nBootOrder=0100\nTimeout=0500\n"
+        );
+        assert_eq!(BootVariablesBackup::parse(&backup.encode()), Ok(backup));
+    }
+
+    #[test]
+    fn corrupted_backup_is_rejected() {
+        let parse = |text: &str| BootVariablesBackup::parse(text.as_bytes());
+        assert!(parse("Boot0001=00\n").is_err());
+        assert!(parse("# sprout boot variables backup v1\nBoot0001=0g\n").is_err());
+        assert!(parse("# sprout boot variables backup v1\nConOut=00\n").is_err());
+        assert!(parse("# sprout boot variables backup v1\nBootOrder\n").is_err());
+        let backup = parse("# sprout boot variables backup v1\n\n# note\n Timeout = 0a00 \n");
+        assert_eq!(
+            backup.unwrap().variables,
+            vec![variable("Timeout", &[10, 0])]
+        );
+    }
+}
@@ -0,0 +1,814 @@
+use crate::filesystem::{
+    DirectoryEntry, Node, NodeKind, ReadAt, ReadOnlyFilesystem, le_at, read_le, read_vec, slice_at,
+};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+/// The byte offset of the superblock.
+const SUPERBLOCK_OFFSET: u64 = 0x10000;
+
+/// The size of the superblock.
+const SUPERBLOCK_SIZE: usize = 4096;
+
+/// The magic number of the superblock.
+const SUPERBLOCK_MAGIC: &[u8] = b"_BHRfS_M";
+
+/// The offset of the system chunk array in the superblock.
+const SYS_CHUNK_ARRAY_OFFSET: usize = 0x32b;
+
+/// The size of the header of tree nodes.
+const NODE_HEADER_SIZE: usize = 0x65;
+
+/// The size of a key.
+const KEY_SIZE: usize = 17;
+
+/// The size of a key pointer of an internal tree node.
+const KEY_POINTER_SIZE: usize = 33;
+
+/// The size of an item header of a tree leaf.
+const ITEM_SIZE: usize = 25;
+
+/// The maximum level of a tree node.
+const MAX_LEVEL: u8 = 8;
+
+/// The size of a chunk item without its stripes.
+const CHUNK_ITEM_SIZE: usize = 48;
+
+/// The size of a stripe of a chunk item.
+const STRIPE_SIZE: usize = 32;
+
+/// The tree of the top-level subvolume.
+const FS_TREE_OBJECTID: u64 = 5;
+
+/// The object of the directory in the root tree that holds the default subvolume.
+const ROOT_TREE_DIR_OBJECTID: u64 = 6;
+
+/// The object of chunk items in the chunk tree.
+const FIRST_CHUNK_TREE_OBJECTID: u64 = 256;
+
+/// The item type of inodes.
+const INODE_ITEM: u8 = 1;
+
+/// The item type of directory entries, keyed by the hash of their name.
+const DIR_ITEM: u8 = 84;
+
+/// The item type of directory entries, keyed by their index in the directory.
+const DIR_INDEX: u8 = 96;
+
+/// The item type of file extents.
+const EXTENT_DATA: u8 = 108;
+
+/// The item type of subvolume roots in the root tree.
+const ROOT_ITEM: u8 = 132;
+
+/// The item type of chunks in the chunk tree.
+const CHUNK_ITEM: u8 = 228;
+
+/// The directory entry type of directories.
+const FT_DIR: u8 = 2;
+
+/// The directory entry type of symbolic links.
+const FT_SYMLINK: u8 = 7;
+
+/// The file type bits of a POSIX file mode.
+const MODE_TYPE_MASK: u32 = 0o170000;
+
+/// The file type of directories in a POSIX file mode.
+const MODE_DIRECTORY: u32 = 0o040000;
+
+/// The file type of regular files in a POSIX file mode.
+const MODE_FILE: u32 = 0o100000;
+
+/// The file type of symbolic links in a POSIX file mode.
+const MODE_SYMLINK: u32 = 0o120000;
+
+/// The file extent type of data stored in the tree.
+const EXTENT_INLINE: u8 = 0;
+
+/// The file extent type of extents that were allocated but never written.
+const EXTENT_PREALLOC: u8 = 2;
+
+/// The chunk types that stripe data across devices, which are not supported.
+const CHUNK_STRIPED: u64 = 0x8 // RAID0
+    | 0x40 // RAID10
+    | 0x80 // RAID5
+    | 0x100; // RAID6
+
+/// The incompatible features that change how trees or chunks are read, which are not supported.
+const INCOMPAT_UNSUPPORTED: u64 = 0x2000 // EXTENT_TREE_V2
+    | 0x4000; // RAID_STRIPE_TREE
+
+/// Checks whether the `source` holds a btrfs filesystem, without validating it.
+pub fn probe(source: &(impl ReadAt + ?Sized)) -> bool {
+    let mut magic = [0u8; 8];
+    source.read_at(SUPERBLOCK_OFFSET + 0x40, &mut magic).is_ok() && magic == SUPERBLOCK_MAGIC
+}
+
+/// The key of a tree item, which orders the items of a tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Key {
+    /// The object the item belongs to, like an inode number.
+    objectid: u64,
+    /// The type of the item.
+    item_type: u8,
+    /// The meaning of the offset depends on the type of the item.
+    offset: u64,
+}
+
+impl Key {
+    /// Parses the key at `offset` of `data`.
+    fn parse(data: &[u8], offset: usize) -> Result<Self, String> {
+        Ok(Self {
+            objectid: le_at::<u64>(data, offset)?,
+            item_type: le_at::<u8>(data, offset + 8)?,
+            offset: le_at::<u64>(data, offset + 9)?,
+        })
+    }
+}
+
+/// The location of the root node of a tree.
+#[derive(Debug, Clone, Copy)]
+struct TreeRoot {
+    /// The logical address of the root node.
+    bytenr: u64,
+    /// The level of the root node, which is zero for a leaf.
+    level: u8,
+}
+
+/// A range of logical addresses that is stored on the device.
+#[derive(Debug, Clone, Copy)]
+struct Chunk {
+    /// The size of the chunk in bytes.
+    length: u64,
+    /// The byte offset of the chunk on the device.
+    physical: u64,
+}
+
+/// A read-only btrfs filesystem on a single device.
+///
+/// Subvolumes are directories that continue in the tree of the subvolume, so the
+/// [Node::tree] of every node is the subvolume that holds it. The root is the default
+/// subvolume, unless another one is selected with [Btrfs::select].
+pub struct Btrfs<R: ReadAt> {
+    /// The source the filesystem is read from.
+    source: R,
+    /// The chunks of the device, keyed by their first logical address.
+    chunks: BTreeMap<u64, Chunk>,
+    /// The size of tree nodes in bytes.
+    node_size: usize,
+    /// The size of data blocks in bytes.
+    sector_size: u32,
+    /// The size of the filesystem in bytes.
+    total_bytes: u64,
+    /// The label of the filesystem.
+    label: String,
+    /// The root tree, which holds the roots of the other trees.
+    root_tree: TreeRoot,
+    /// The root directory.
+    root: Node,
+    /// The roots of the subvolume trees that were looked up, keyed by the subvolume.
+    subvolumes: RefCell<BTreeMap<u64, TreeRoot>>,
+}
+
+impl<R: ReadAt> Btrfs<R> {
+    /// Opens the btrfs filesystem of the `source`, with the default subvolume as the root.
+    pub fn open(source: R) -> Result<Self, String> {
+        let superblock = read_vec(&source, SUPERBLOCK_OFFSET, SUPERBLOCK_SIZE)?;
+        if slice_at(&superblock, 0x40, 8)? != SUPERBLOCK_MAGIC {
+            return Err("not a btrfs filesystem".to_string());
+        }
+        let incompat = le_at::<u64>(&superblock, 0xbc)?;
+        if incompat & INCOMPAT_UNSUPPORTED != 0 {
+            return Err(format!(
+                "btrfs features {:#x} are not supported",
+                incompat & INCOMPAT_UNSUPPORTED
+            ));
+        }
+        if le_at::<u64>(&superblock, 0x88)? != 1 {
+            return Err("btrfs filesystems on more than one device are not supported".to_string());
+        }
+        let node_size = le_at::<u32>(&superblock, 0x94)? as usize;
+        if !(4096..=65536).contains(&node_size) {
+            return Err(format!("btrfs node size {} is not supported", node_size));
+        }
+        let label = slice_at(&superblock, 0x12b, 256)?;
+        let label = label.split(|byte| *byte == 0).next().unwrap_or_default();
+
+        let mut filesystem = Self {
+            source,
+            chunks: BTreeMap::new(),
+            node_size,
+            sector_size: le_at::<u32>(&superblock, 0x90)?,
+            total_bytes: le_at::<u64>(&superblock, 0x70)?,
+            label: String::from_utf8_lossy(label).to_string(),
+            root_tree: TreeRoot {
+                bytenr: le_at::<u64>(&superblock, 0x50)?,
+                level: le_at::<u8>(&superblock, 0xc6)?,
+            },
+            root: Node {
+                id: 0,
+                tree: 0,
+                kind: NodeKind::Directory,
+                size: 0,
+                modified: 0,
+            },
+            subvolumes: RefCell::new(BTreeMap::new()),
+        };
+
+        // The system chunks in the superblock map the chunk tree, which maps everything else.
+        let device = le_at::<u64>(&superblock, 0xc9)?;
+        let array_size = (le_at::<u32>(&superblock, 0xa0)? as usize).min(2048);
+        let array = slice_at(&superblock, SYS_CHUNK_ARRAY_OFFSET, array_size)?;
+        let mut offset = 0;
+        while offset + KEY_SIZE + CHUNK_ITEM_SIZE <= array.len() {
+            let key = Key::parse(array, offset)?;
+            let stripes = le_at::<u16>(array, offset + KEY_SIZE + 44)? as usize;
+            let chunk = slice_at(
+                array,
+                offset + KEY_SIZE,
+                CHUNK_ITEM_SIZE + stripes * STRIPE_SIZE,
+            )
+            .ok()
+            .filter(|_| key.item_type == CHUNK_ITEM)
+            .ok_or("invalid btrfs system chunk array")?;
+            filesystem.add_chunk(key.offset, chunk, device)?;
+            offset += KEY_SIZE + chunk.len();
+        }
+        let chunk_tree = TreeRoot {
+            bytenr: le_at::<u64>(&superblock, 0x58)?,
+            level: le_at::<u8>(&superblock, 0xc7)?,
+        };
+        for (key, chunk) in filesystem.items(chunk_tree, FIRST_CHUNK_TREE_OBJECTID, CHUNK_ITEM)? {
+            let stripes = read_le::<u16>(&chunk, 44).unwrap_or_default();
+            let size = CHUNK_ITEM_SIZE + stripes as usize * STRIPE_SIZE;
+            if chunk.len() < size {
+                return Err("invalid btrfs chunk item".to_string());
+            }
+            filesystem.add_chunk(key.offset, &chunk, device)?;
+        }
+
+        // The default subvolume is named by an entry in the directory of the root tree.
+        let mut default = FS_TREE_OBJECTID;
+        for (_, item) in filesystem.items(filesystem.root_tree, ROOT_TREE_DIR_OBJECTID, DIR_ITEM)? {
+            for entry in parse_dir_items(&item)? {
+                if entry.name == b"default" {
+                    default = entry.location.objectid;
+                }
+            }
+        }
+        filesystem.root = filesystem.subvolume_root(default)?;
+        Ok(filesystem)
+    }
+
+    /// Selects the subvolume or directory at `path` from the top-level subvolume as the root,
+    /// like `@/.snapshots/2/snapshot`. An empty path selects the top-level subvolume, where
+    /// every subvolume and snapshot is a directory.
+    pub fn select(&mut self, path: &str) -> Result<(), String> {
+        self.root = self.subvolume_root(FS_TREE_OBJECTID)?;
+        let stack = self
+            .resolve(&[], path)?
+            .ok_or_else(|| format!("btrfs subvolume {} does not exist", path))?;
+        let node = stack.last().map(|entry| entry.node.clone());
+        match node {
+            Some(node) if node.kind == NodeKind::Directory => {
+                self.root = node;
+                Ok(())
+            }
+            _ => Err(format!("btrfs subvolume {} is not a directory", path)),
+        }
+    }
+
+    /// Adds the `chunk` item that starts at the `logical` address, if it has a stripe on the
+    /// `device`. Chunks that are only on other devices or striped are left out, so reading
+    /// them fails.
+    fn add_chunk(&mut self, logical: u64, chunk: &[u8], device: u64) -> Result<(), String> {
+        if le_at::<u64>(chunk, 24)? & CHUNK_STRIPED != 0 && le_at::<u16>(chunk, 44)? > 1 {
+            return Ok(());
+        }
+        let stripes = chunk
+            .get(CHUNK_ITEM_SIZE..)
+            .ok_or("invalid btrfs chunk item")?
+            .chunks_exact(STRIPE_SIZE);
+        if let Some(stripe) = stripes
+            .into_iter()
+            .find(|stripe| read_le::<u64>(stripe, 0) == Some(device))
+        {
+            self.chunks.insert(
+                logical,
+                Chunk {
+                    length: le_at::<u64>(chunk, 0)?,
+                    physical: le_at::<u64>(stripe, 8)?,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Reads `buffer.len()` bytes at the `logical` address.
+    fn read_logical(&self, logical: u64, buffer: &mut [u8]) -> Result<(), String> {
+        let mut done = 0;
+        while done < buffer.len() {
+            let address = logical + done as u64;
+            let (start, chunk) = self
+                .chunks
+                .range(..=address)
+                .next_back()
+                .filter(|(start, chunk)| address < *start + chunk.length)
+                .ok_or_else(|| format!("btrfs address {:#x} is not mapped", address))?;
+            let within = address - start;
+            let count = ((chunk.length - within) as usize).min(buffer.len() - done);
+            self.source
+                .read_at(chunk.physical + within, &mut buffer[done..done + count])?;
+            done += count;
+        }
+        Ok(())
+    }
+
+    /// Collects the items of the `tree` with the `objectid` and `item_type`, ordered by key.
+    fn items(
+        &self,
+        tree: TreeRoot,
+        objectid: u64,
+        item_type: u8,
+    ) -> Result<Vec<(Key, Vec<u8>)>, String> {
+        let mut items = Vec::new();
+        self.search(tree.bytenr, tree.level, objectid, item_type, &mut items)?;
+        Ok(items)
+    }
+
+    /// Searches the node at the logical address `bytenr`, which is at `level`, for the items
+    /// with the `objectid` and `item_type`.
+    fn search(
+        &self,
+        bytenr: u64,
+        level: u8,
+        objectid: u64,
+        item_type: u8,
+        items: &mut Vec<(Key, Vec<u8>)>,
+    ) -> Result<(), String> {
+        let mut node = alloc::vec![0u8; self.node_size];
+        self.read_logical(bytenr, &mut node)?;
+        let count = le_at::<u32>(&node, 0x60)? as usize;
+        if le_at::<u8>(&node, 0x64)? != level || level >= MAX_LEVEL {
+            return Err(format!("invalid btrfs tree node at {:#x}", bytenr));
+        }
+        let first = Key {
+            objectid,
+            item_type,
+            offset: 0,
+        };
+        let last = Key {
+            objectid,
+            item_type,
+            offset: u64::MAX,
+        };
+
+        if level > 0 {
+            if NODE_HEADER_SIZE + count * KEY_POINTER_SIZE > node.len() {
+                return Err(format!("invalid btrfs tree node at {:#x}", bytenr));
+            }
+            let pointer = |index: usize| NODE_HEADER_SIZE + index * KEY_POINTER_SIZE;
+            for index in 0..count {
+                // Each child holds the keys from its own key up to the key of the next child.
+                let key = Key::parse(&node, pointer(index))?;
+                let next = match index + 1 < count {
+                    true => Some(Key::parse(&node, pointer(index + 1))?),
+                    false => None,
+                };
+                if key > last {
+                    break;
+                }
+                if next.is_some_and(|next| next <= first) {
+                    continue;
+                }
+                let child = le_at::<u64>(&node, pointer(index) + KEY_SIZE)?;
+                self.search(child, level - 1, objectid, item_type, items)?;
+            }
+            return Ok(());
+        }
+
+        if NODE_HEADER_SIZE + count * ITEM_SIZE > node.len() {
+            return Err(format!("invalid btrfs tree leaf at {:#x}", bytenr));
+        }
+        for index in 0..count {
+            let item = NODE_HEADER_SIZE + index * ITEM_SIZE;
+            let key = Key::parse(&node, item)?;
+            if key < first || key > last {
+                continue;
+            }
+            let start = NODE_HEADER_SIZE + le_at::<u32>(&node, item + KEY_SIZE)? as usize;
+            let size = le_at::<u32>(&node, item + KEY_SIZE + 4)? as usize;
+            let data = slice_at(&node, start, size)
+                .map_err(|_| format!("invalid btrfs tree item at {:#x}", bytenr))?;
+            items.push((key, data.to_vec()));
+        }
+        Ok(())
+    }
+
+    /// Finds the root of the tree of the `subvolume`.
+    fn tree(&self, subvolume: u64) -> Result<TreeRoot, String> {
+        if let Some(root) = self.subvolumes.borrow().get(&subvolume) {
+            return Ok(*root);
+        }
+        // Snapshots key their root item by the generation they were taken in, so the last
+        // root item is the current one.
+        let items = self.items(self.root_tree, subvolume, ROOT_ITEM)?;
+        let (_, item) = items
+            .last()
+            .ok_or_else(|| format!("btrfs subvolume {} does not exist", subvolume))?;
+        if item.len() < 239 {
+            return Err(format!(
+                "invalid btrfs root item of subvolume {}",
+                subvolume
+            ));
+        }
+        let root = TreeRoot {
+            bytenr: le_at::<u64>(item, 176)?,
+            level: le_at::<u8>(item, 238)?,
+        };
+        self.subvolumes.borrow_mut().insert(subvolume, root);
+        Ok(root)
+    }
+
+    /// Reads the root directory of the `subvolume`.
+    fn subvolume_root(&self, subvolume: u64) -> Result<Node, String> {
+        let items = self.items(self.root_tree, subvolume, ROOT_ITEM)?;
+        let (_, item) = items
+            .last()
+            .ok_or_else(|| format!("btrfs subvolume {} does not exist", subvolume))?;
+        if item.len() < 176 {
+            return Err(format!(
+                "invalid btrfs root item of subvolume {}",
+                subvolume
+            ));
+        }
+        self.node(subvolume, le_at::<u64>(item, 168)?)
+    }
+
+    /// Reads the inode `id` of the `subvolume` as a [Node].
+    fn node(&self, subvolume: u64, id: u64) -> Result<Node, String> {
+        let items = self.items(self.tree(subvolume)?, id, INODE_ITEM)?;
+        let (_, inode) = items
+            .first()
+            .filter(|(_, inode)| inode.len() >= 160)
+            .ok_or_else(|| format!("btrfs inode {} of subvolume {} is missing", id, subvolume))?;
+        let kind = match le_at::<u32>(inode, 52)? & MODE_TYPE_MASK {
+            MODE_DIRECTORY => NodeKind::Directory,
+            MODE_FILE => NodeKind::File,
+            MODE_SYMLINK => NodeKind::Symlink,
+            _ => NodeKind::Other,
+        };
+        Ok(Node {
+            id,
+            tree: subvolume,
+            kind,
+            size: le_at::<u64>(inode, 16)?,
+            modified: le_at::<u64>(inode, 136)? as i64,
+        })
+    }
+}
+
+impl<R: ReadAt> ReadOnlyFilesystem for Btrfs<R> {
+    fn root(&self) -> Result<Node, String> {
+        Ok(self.root.clone())
+    }
+
+    fn read_dir(&self, directory: &Node) -> Result<Vec<DirectoryEntry>, String> {
+        let tree = self.tree(directory.tree)?;
+        let mut entries = Vec::new();
+        for (_, item) in self.items(tree, directory.id, DIR_INDEX)? {
+            for entry in parse_dir_items(&item)? {
+                let name = String::from_utf8_lossy(&entry.name).to_string();
+                // Subvolumes are entered through their root directory.
+                let node = match entry.location.item_type {
+                    ROOT_ITEM => self.subvolume_root(entry.location.objectid)?,
+                    INODE_ITEM => self.node(directory.tree, entry.location.objectid)?,
+                    _ => continue,
+                };
+                // The inode is authoritative, but the entry type helps with broken inodes.
+                let node = match (node.kind, entry.file_type) {
+                    (NodeKind::Other, FT_DIR) => Node {
+                        kind: NodeKind::Directory,
+                        ..node
+                    },
+                    (NodeKind::Other, FT_SYMLINK) => Node {
+                        kind: NodeKind::Symlink,
+                        ..node
+                    },
+                    _ => node,
+                };
+                entries.push(DirectoryEntry { name, node });
+            }
+        }
+        Ok(entries)
+    }
+
+    fn read(&self, node: &Node, offset: u64, buffer: &mut [u8]) -> Result<usize, String> {
+        let end = node.size.min(offset.saturating_add(buffer.len() as u64));
+        if offset >= end {
+            return Ok(0);
+        }
+        let size = (end - offset) as usize;
+        let buffer = &mut buffer[..size];
+        // Ranges without an extent are holes, which read as zeros.
+        buffer.fill(0);
+
+        let tree = self.tree(node.tree)?;
+        for (key, extent) in self.items(tree, node.id, EXTENT_DATA)? {
+            if extent.len() < 21 {
+                return Err("invalid btrfs file extent".to_string());
+            }
+            let compression = le_at::<u8>(&extent, 16)?;
+            let encryption = le_at::<u8>(&extent, 17)?;
+            if compression != 0 || encryption != 0 || le_at::<u16>(&extent, 18)? != 0 {
+                return Err("compressed or encrypted btrfs extents are not supported".to_string());
+            }
+            let extent_type = le_at::<u8>(&extent, 20)?;
+            let length = match extent_type {
+                EXTENT_INLINE => (extent.len() - 21) as u64,
+                _ if extent.len() >= 53 => le_at::<u64>(&extent, 45)?,
+                _ => return Err("invalid btrfs file extent".to_string()),
+            };
+
+            // Copy the part of the extent that overlaps the requested range.
+            let start = key.offset.max(offset);
+            let stop = key.offset.saturating_add(length).min(end);
+            if start >= stop {
+                continue;
+            }
+            let target = &mut buffer[(start - offset) as usize..(stop - offset) as usize];
+            let within = start - key.offset;
+            match extent_type {
+                EXTENT_INLINE => {
+                    let data = slice_at(&extent, 21 + within as usize, target.len())?;
+                    target.copy_from_slice(data);
+                }
+                EXTENT_PREALLOC => {}
+                _ => {
+                    let disk_bytenr = le_at::<u64>(&extent, 21)?;
+                    if disk_bytenr != 0 {
+                        let address = disk_bytenr + le_at::<u64>(&extent, 37)? + within;
+                        self.read_logical(address, target)?;
+                    }
+                }
+            }
+        }
+        Ok(size)
+    }
+
+    fn label(&self) -> String {
+        self.label.clone()
+    }
+
+    fn size(&self) -> u64 {
+        self.total_bytes
+    }
+
+    fn block_size(&self) -> u32 {
+        self.sector_size
+    }
+}
+
+/// A directory entry of a directory item.
+struct DirItem {
+    /// The key of the inode or subvolume root the entry refers to.
+    location: Key,
+    /// The type of the entry.
+    file_type: u8,
+    /// The name of the entry.
+    name: Vec<u8>,
+}
+
+/// Parses the entries of a directory `item`, which holds more than one entry when names
+/// have the same hash.
+fn parse_dir_items(item: &[u8]) -> Result<Vec<DirItem>, String> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    while offset + 30 <= item.len() {
+        let data_length = le_at::<u16>(item, offset + 25)? as usize;
+        let name_length = le_at::<u16>(item, offset + 27)? as usize;
+        let name =
+            slice_at(item, offset + 30, name_length).map_err(|_| "invalid btrfs directory item")?;
+        entries.push(DirItem {
+            location: Key::parse(item, offset)?,
+            file_type: le_at::<u8>(item, offset + 29)?,
+            name: name.to_vec(),
+        });
+        offset += 30 + name_length + data_length;
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    /// The size of the nodes and blocks of the test filesystem.
+    const NODE: usize = 4096;
+
+    fn put16(image: &mut [u8], offset: usize, value: u16) {
+        image[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn put32(image: &mut [u8], offset: usize, value: u32) {
+        image[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn put64(image: &mut [u8], offset: usize, value: u64) {
+        image[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn key(objectid: u64, item_type: u8, offset: u64) -> Vec<u8> {
+        let mut key = objectid.to_le_bytes().to_vec();
+        key.push(item_type);
+        key.extend_from_slice(&offset.to_le_bytes());
+        key
+    }
+
+    /// Writes a leaf of the `items` at `offset`, which must be ordered by key.
+    fn put_leaf(image: &mut [u8], offset: usize, items: &[(Vec<u8>, Vec<u8>)]) {
+        put32(image, offset + 0x60, items.len() as u32);
+        let mut data_end = NODE - NODE_HEADER_SIZE;
+        for (index, (key, data)) in items.iter().enumerate() {
+            data_end -= data.len();
+            let item = offset + NODE_HEADER_SIZE + index * ITEM_SIZE;
+            image[item..item + KEY_SIZE].copy_from_slice(key);
+            put32(image, item + KEY_SIZE, data_end as u32);
+            put32(image, item + KEY_SIZE + 4, data.len() as u32);
+            let start = offset + NODE_HEADER_SIZE + data_end;
+            image[start..start + data.len()].copy_from_slice(data);
+        }
+    }
+
+    fn inode(mode: u32, size: u64) -> Vec<u8> {
+        let mut inode = vec![0u8; 160];
+        put64(&mut inode, 16, size);
+        put32(&mut inode, 52, mode);
+        put64(&mut inode, 136, 1700000000);
+        inode
+    }
+
+    fn dir_entry(location: Vec<u8>, file_type: u8, name: &str) -> Vec<u8> {
+        let mut entry = location;
+        entry.extend_from_slice(&[0u8; 8]);
+        entry.extend_from_slice(&0u16.to_le_bytes());
+        entry.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        entry.push(file_type);
+        entry.extend_from_slice(name.as_bytes());
+        entry
+    }
+
+    fn inline_extent(data: &[u8]) -> Vec<u8> {
+        let mut extent = vec![0u8; 21];
+        extent.extend_from_slice(data);
+        extent
+    }
+
+    fn root_item(bytenr: u64) -> Vec<u8> {
+        let mut item = vec![0u8; 439];
+        put64(&mut item, 168, 256);
+        put64(&mut item, 176, bytenr);
+        item
+    }
+
+    /// Builds a filesystem whose top-level subvolume has a `boot` directory with an inline
+    /// kernel, and a `snapshot` subvolume with a kernel in a regular extent and a link to it.
+    /// The default subvolume is the snapshot, and the root tree has an internal node.
+    fn image() -> Vec<u8> {
+        let mut image = vec![0u8; 0x10000 + 7 * NODE];
+        let superblock = 0x10000;
+        let block = |index: usize| superblock + index * NODE;
+        image[superblock + 0x40..superblock + 0x48].copy_from_slice(SUPERBLOCK_MAGIC);
+        put64(&mut image, superblock + 0x50, block(2) as u64);
+        put64(&mut image, superblock + 0x58, block(1) as u64);
+        let size = image.len() as u64;
+        put64(&mut image, superblock + 0x70, size);
+        put64(&mut image, superblock + 0x88, 1);
+        put32(&mut image, superblock + 0x90, NODE as u32);
+        put32(&mut image, superblock + 0x94, NODE as u32);
+        image[superblock + 0xc6] = 1;
+        put64(&mut image, superblock + 0xc9, 1);
+        image[superblock + 0x12b..superblock + 0x12f].copy_from_slice(b"root");
+
+        // One system chunk maps every logical address to the same physical address.
+        let mut chunk = key(FIRST_CHUNK_TREE_OBJECTID, CHUNK_ITEM, 0);
+        let mut item = vec![0u8; CHUNK_ITEM_SIZE + STRIPE_SIZE];
+        put64(&mut item, 0, size);
+        put16(&mut item, 44, 1);
+        put64(&mut item, CHUNK_ITEM_SIZE, 1);
+        chunk.extend_from_slice(&item);
+        put32(&mut image, superblock + 0xa0, chunk.len() as u32);
+        let array = superblock + SYS_CHUNK_ARRAY_OFFSET;
+        image[array..array + chunk.len()].copy_from_slice(&chunk);
+        put_leaf(&mut image, block(1), &[]);
+
+        // The root tree is an internal node at block 2 with a leaf at block 3.
+        put32(&mut image, block(2) + 0x60, 1);
+        image[block(2) + 0x64] = 1;
+        let pointer = block(2) + NODE_HEADER_SIZE;
+        image[pointer..pointer + KEY_SIZE].copy_from_slice(&key(0, 0, 0));
+        put64(&mut image, pointer + KEY_SIZE, block(3) as u64);
+        put_leaf(
+            &mut image,
+            block(3),
+            &[
+                (key(5, ROOT_ITEM, 0), root_item(block(4) as u64)),
+                (
+                    key(6, DIR_ITEM, 1),
+                    dir_entry(key(256, ROOT_ITEM, u64::MAX), FT_DIR, "default"),
+                ),
+                (key(256, ROOT_ITEM, 7), root_item(block(5) as u64)),
+            ],
+        );
+
+        put_leaf(
+            &mut image,
+            block(4),
+            &[
+                (key(256, INODE_ITEM, 0), inode(MODE_DIRECTORY, 0)),
+                (
+                    key(256, DIR_INDEX, 2),
+                    dir_entry(key(257, INODE_ITEM, 0), FT_DIR, "boot"),
+                ),
+                (
+                    key(256, DIR_INDEX, 3),
+                    dir_entry(key(256, ROOT_ITEM, u64::MAX), FT_DIR, "snapshot"),
+                ),
+                (key(257, INODE_ITEM, 0), inode(MODE_DIRECTORY, 0)),
+                (
+                    key(257, DIR_INDEX, 2),
+                    dir_entry(key(258, INODE_ITEM, 0), 1, "vmlinuz"),
+                ),
+                (key(258, INODE_ITEM, 0), inode(MODE_FILE, 6)),
+                (key(258, EXTENT_DATA, 0), inline_extent(b"inline")),
+            ],
+        );
+
+        // The snapshot kernel has a hole in its first block, then data at block 6.
+        let mut extent = vec![0u8; 53];
+        extent[20] = 1;
+        put64(&mut extent, 21, block(6) as u64);
+        put64(&mut extent, 45, NODE as u64);
+        put_leaf(
+            &mut image,
+            block(5),
+            &[
+                (key(256, INODE_ITEM, 0), inode(MODE_DIRECTORY, 0)),
+                (
+                    key(256, DIR_INDEX, 2),
+                    dir_entry(key(257, INODE_ITEM, 0), 1, "vmlinuz-6.1"),
+                ),
+                (
+                    key(256, DIR_INDEX, 3),
+                    dir_entry(key(258, INODE_ITEM, 0), FT_SYMLINK, "vmlinuz"),
+                ),
+                (key(257, INODE_ITEM, 0), inode(MODE_FILE, NODE as u64 + 6)),
+                (key(257, EXTENT_DATA, NODE as u64), extent),
+                (key(258, INODE_ITEM, 0), inode(MODE_SYMLINK, 11)),
+                (key(258, EXTENT_DATA, 0), inline_extent(b"vmlinuz-6.1")),
+            ],
+        );
+        image[block(6)..block(6) + 6].copy_from_slice(b"kernel");
+        image
+    }
+
+    fn read(filesystem: &Btrfs<Vec<u8>>, path: &str) -> Option<Vec<u8>> {
+        let stack = filesystem.resolve(&[], path).unwrap()?;
+        Some(filesystem.read_all(&stack.last().unwrap().node).unwrap())
+    }
+
+    #[test]
+    fn default_subvolume_is_read() {
+        let image = image();
+        assert!(probe(&image));
+        let filesystem = Btrfs::open(image).unwrap();
+        assert_eq!(filesystem.label(), "root");
+        let kernel = read(&filesystem, "\\vmlinuz").unwrap();
+        assert_eq!(kernel.len(), NODE + 6);
+        assert!(kernel[..NODE].iter().all(|byte| *byte == 0));
+        assert_eq!(&kernel[NODE..], b"kernel");
+        assert_eq!(read(&filesystem, "\\boot\\vmlinuz"), None);
+    }
+
+    #[test]
+    fn subvolumes_are_selected_and_entered() {
+        let mut filesystem = Btrfs::open(image()).unwrap();
+        filesystem.select("").unwrap();
+        assert_eq!(
+            read(&filesystem, "\\boot\\vmlinuz"),
+            Some(b"inline".to_vec())
+        );
+        let kernel = read(&filesystem, "\\snapshot\\vmlinuz-6.1").unwrap();
+        assert_eq!(&kernel[NODE..], b"kernel");
+
+        filesystem.select("snapshot").unwrap();
+        let stack = filesystem.resolve(&[], "\\vmlinuz").unwrap().unwrap();
+        assert_eq!(stack.last().unwrap().node.tree, 256);
+        assert!(filesystem.select("missing").is_err());
+        assert!(Btrfs::open(vec![0u8; 0x20000]).is_err());
+    }
+}
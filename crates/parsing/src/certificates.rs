@@ -0,0 +1,162 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// The `EFI_CERT_X509_GUID` signature type, in its mixed-endian byte order.
+pub const CERT_X509_GUID: [u8; 16] = [
+    0xa1, 0x59, 0xc0, 0xa5, 0xe4, 0x94, 0xa7, 0x4a, 0x87, 0xb5, 0xab, 0x15, 0x5c, 0x2b, 0xf0, 0x72,
+];
+
+/// The header of a PEM certificate block.
+const PEM_BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+
+/// The footer of a PEM certificate block.
+const PEM_END: &str = "-----END CERTIFICATE-----";
+
+/// The size of the `EFI_SIGNATURE_LIST` header.
+const SIGNATURE_LIST_HEADER_SIZE: usize = 28;
+
+/// Decodes the standard base64 `text`, ignoring whitespace.
+/// Returns [None] if the text is not valid base64.
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut decoded = Vec::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in text.chars().filter(|c| !c.is_whitespace()) {
+        if c == '=' {
+            break;
+        }
+        let value = match c {
+            'A'..='Z' => c as u32 - 'A' as u32,
+            'a'..='z' => c as u32 - 'a' as u32 + 26,
+            '0'..='9' => c as u32 - '0' as u32 + 52,
+            '+' => 62,
+            '/' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+        }
+    }
+    Some(decoded)
+}
+
+/// Parses the X.509 certificates in `data`, which is either a PEM file with one or more
+/// `CERTIFICATE` blocks or a single DER certificate. The certificates are returned as DER.
+pub fn parse_certificates(data: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+    // A DER certificate is an ASN.1 sequence, which never starts with a PEM header.
+    if data.first() == Some(&0x30) {
+        return Ok(alloc::vec![data.to_vec()]);
+    }
+
+    let text = core::str::from_utf8(data).map_err(|_| "not a PEM or DER certificate")?;
+    let mut certificates = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(PEM_BEGIN) {
+        let body = &rest[start + PEM_BEGIN.len()..];
+        let end = body
+            .find(PEM_END)
+            .ok_or("PEM certificate has no end of the block")?;
+        let certificate =
+            decode_base64(&body[..end]).ok_or("PEM certificate has invalid contents")?;
+        certificates.push(certificate);
+        rest = &body[end + PEM_END.len()..];
+    }
+    if certificates.is_empty() {
+        return Err("no certificates found".to_string());
+    }
+    Ok(certificates)
+}
+
+/// Encodes the DER `certificate` as an `EFI_SIGNATURE_LIST` with a single X.509 signature
+/// that is owned by `owner`. Lists like this are concatenated to build a signature database.
+pub fn encode_signature_list(certificate: &[u8], owner: [u8; 16]) -> Vec<u8> {
+    let signature_size = 16 + certificate.len();
+    let list_size = SIGNATURE_LIST_HEADER_SIZE + signature_size;
+    let mut list = Vec::with_capacity(list_size);
+    list.extend_from_slice(&CERT_X509_GUID);
+    list.extend_from_slice(&(list_size as u32).to_le_bytes());
+    list.extend_from_slice(&0u32.to_le_bytes());
+    list.extend_from_slice(&(signature_size as u32).to_le_bytes());
+    list.extend_from_slice(&owner);
+    list.extend_from_slice(certificate);
+    list
+}
+
+/// Extracts the DER X.509 certificates from the concatenated `EFI_SIGNATURE_LIST`s in `data`.
+/// Signatures of other types are skipped. Returns [None] if the lists are malformed.
+pub fn signature_list_certificates(data: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let read_u32 = |offset: usize| -> Option<usize> {
+        let bytes = data.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().ok()?) as usize)
+    };
+
+    let mut certificates = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        let signature_type = data.get(offset..offset + 16)?;
+        let list_size = read_u32(offset + 16)?;
+        let header_size = read_u32(offset + 20)?;
+        let signature_size = read_u32(offset + 24)?;
+        let list_end = offset
+            .checked_add(list_size)
+            .filter(|end| *end <= data.len())?;
+
+        // Every signature starts with the 16-byte owner GUID.
+        let signatures_start = offset + SIGNATURE_LIST_HEADER_SIZE + header_size;
+        if signature_size <= 16 || signatures_start > list_end {
+            return None;
+        }
+        if signature_type == CERT_X509_GUID {
+            for signature in data[signatures_start..list_end].chunks(signature_size) {
+                if signature.len() != signature_size {
+                    return None;
+                }
+                certificates.push(signature[16..].to_vec());
+            }
+        }
+        offset = list_end;
+    }
+    Some(certificates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn certificates_are_parsed() {
+        let pem = "subject=CN=test\n-----BEGIN CERTIFICATE-----\nMAMCAQE=\n-----END CERTIFICATE-----\n\
+                   -----BEGIN CERTIFICATE-----\nMAMCAQI=\n-----END CERTIFICATE-----\n";
+        assert_eq!(
+            parse_certificates(pem.as_bytes()),
+            Ok(vec![
+                vec![0x30, 0x03, 0x02, 0x01, 0x01],
+                vec![0x30, 0x03, 0x02, 0x01, 0x02]
+            ])
+        );
+        assert_eq!(
+            parse_certificates(&[0x30, 0x03, 0x02, 0x01, 0x01]),
+            Ok(vec![vec![0x30, 0x03, 0x02, 0x01, 0x01]])
+        );
+        assert!(parse_certificates(b"-----BEGIN CERTIFICATE-----\nMAMCAQE=\n").is_err());
+        assert!(parse_certificates(b"not a certificate").is_err());
+    }
+
+    #[test]
+    fn signature_lists_round_trip() {
+        let owner = [0x11; 16];
+        let mut data = encode_signature_list(&[0x30, 0x00], owner);
+        assert_eq!(data.len(), 28 + 16 + 2);
+        data.extend(encode_signature_list(&[0x30, 0x01, 0x05], owner));
+        assert_eq!(
+            signature_list_certificates(&data),
+            Some(vec![vec![0x30, 0x00], vec![0x30, 0x01, 0x05]])
+        );
+        assert_eq!(signature_list_certificates(&[]), Some(vec![]));
+        assert_eq!(signature_list_certificates(&data[..30]), None);
+    }
+}
@@ -0,0 +1,102 @@
+/// The operator that requires both sides of a condition to be true.
+const AND_OPERATOR: &str = "&&";
+
+/// The operator that requires either side of a condition to be true.
+const OR_OPERATOR: &str = "||";
+
+/// The operator that checks whether two sides of a comparison are equal.
+const EQUAL_OPERATOR: &str = "==";
+
+/// The operator that checks whether two sides of a comparison are not equal.
+const NOT_EQUAL_OPERATOR: &str = "!=";
+
+/// The prefix that negates a condition term.
+const NOT_PREFIX: char = '!';
+
+/// Determines whether the `text` is a true value.
+/// Empty text, `false`, `0`, `no`, and `off` are false, and everything else is true.
+pub fn is_truthy(text: &str) -> bool {
+    let text = text.trim();
+    !(text.is_empty()
+        || text.eq_ignore_ascii_case("false")
+        || text == "0"
+        || text.eq_ignore_ascii_case("no")
+        || text.eq_ignore_ascii_case("off"))
+}
+
+/// Evaluates a single term of a condition, which is a comparison or a value.
+fn evaluate_term(term: &str) -> bool {
+    let term = term.trim();
+
+    // Comparisons are checked before negation so `a != b` is not read as a negation.
+    if let Some((left, right)) = term.split_once(NOT_EQUAL_OPERATOR) {
+        return left.trim() != right.trim();
+    } else if let Some((left, right)) = term.split_once(EQUAL_OPERATOR) {
+        return left.trim() == right.trim();
+    }
+
+    match term.strip_prefix(NOT_PREFIX) {
+        Some(negated) => !evaluate_term(negated),
+        None => is_truthy(term),
+    }
+}
+
+/// Evaluates the `condition`, which should already be stamped with the context values.
+///
+/// A condition consists of terms joined by `&&` and `||`, where `&&` binds tighter than `||`.
+/// Each term is one of the following:
+/// - `value`: true if the value is truthy, see [is_truthy].
+/// - `!term`: true if the term is false.
+/// - `left == right` and `left != right`: compares the trimmed text on both sides.
+///
+/// An empty condition is true, so that an empty `when` does not skip anything.
+pub fn evaluate(condition: &str) -> bool {
+    if condition.trim().is_empty() {
+        return true;
+    }
+
+    condition
+        .split(OR_OPERATOR)
+        .any(|alternative| alternative.split(AND_OPERATOR).all(evaluate_term))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_truthy() {
+        assert!(evaluate("true"));
+        assert!(evaluate("1"));
+        assert!(evaluate("enabled"));
+        assert!(evaluate("  "));
+        assert!(!evaluate("false"));
+        assert!(!evaluate("0"));
+        assert!(!evaluate("Off"));
+        assert!(!evaluate("no"));
+    }
+
+    #[test]
+    fn comparisons_are_evaluated() {
+        assert!(evaluate("x86_64 == x86_64"));
+        assert!(evaluate(" a != b "));
+        assert!(!evaluate("a == b"));
+        assert!(!evaluate("a != a"));
+    }
+
+    #[test]
+    fn negation_is_evaluated() {
+        assert!(evaluate("!false"));
+        assert!(!evaluate("!true"));
+        assert!(evaluate("!!true"));
+    }
+
+    #[test]
+    fn operators_are_evaluated_with_precedence() {
+        assert!(evaluate("true && a == a"));
+        assert!(!evaluate("true && false"));
+        assert!(evaluate("false || true"));
+        assert!(evaluate("false && false || true"));
+        assert!(!evaluate("true && false || false"));
+    }
+}
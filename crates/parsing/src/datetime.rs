@@ -0,0 +1,179 @@
+use alloc::format;
+use alloc::string::String;
+
+/// A calendar date and time of day, as reported by a real-time clock.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DateTime {
+    /// The full year, like 2026.
+    pub year: u16,
+    /// The month of the year, from 1 to 12.
+    pub month: u8,
+    /// The day of the month, from 1 to 31.
+    pub day: u8,
+    /// The hour of the day, from 0 to 23.
+    pub hour: u8,
+    /// The minute of the hour, from 0 to 59.
+    pub minute: u8,
+    /// The second of the minute, from 0 to 59.
+    pub second: u8,
+}
+
+impl DateTime {
+    /// Calculates the number of days since 1970-01-01 for this date.
+    /// This uses the days-from-civil algorithm, which handles the proleptic Gregorian calendar.
+    fn days_since_epoch(&self) -> i64 {
+        let month = self.month as i64;
+        let year = self.year as i64 - if month <= 2 { 1 } else { 0 };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year =
+            (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146097 + day_of_era - 719468
+    }
+
+    /// Calculates the number of seconds since 1970-01-01 00:00:00 for this date and time.
+    /// The time zone is not taken into account.
+    pub fn unix_timestamp(&self) -> i64 {
+        self.days_since_epoch() * 86400
+            + self.hour as i64 * 3600
+            + self.minute as i64 * 60
+            + self.second as i64
+    }
+
+    /// Converts the number of seconds since 1970-01-01 00:00:00 to a date and time.
+    /// This uses the civil-from-days algorithm, the inverse of the days-from-civil algorithm.
+    pub fn from_unix_timestamp(timestamp: i64) -> Self {
+        let days = timestamp.div_euclid(86400);
+        let seconds = timestamp.rem_euclid(86400);
+        let days = days + 719468;
+        let era = days.div_euclid(146097);
+        let day_of_era = days - era * 146097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 {
+            month_index + 3
+        } else {
+            month_index - 9
+        };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+        Self {
+            year: year.clamp(0, u16::MAX as i64) as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: (seconds / 3600) as u8,
+            minute: (seconds / 60 % 60) as u8,
+            second: (seconds % 60) as u8,
+        }
+    }
+
+    /// Calculates the ISO 8601 day of the week, from 1 (Monday) to 7 (Sunday).
+    pub fn weekday(&self) -> u8 {
+        // 1970-01-01 was a Thursday, which is day 4.
+        ((self.days_since_epoch() + 3).rem_euclid(7) + 1) as u8
+    }
+}
+
+/// Formats `datetime` using the strftime-like `format`.
+///
+/// The following specifiers are supported:
+/// - `%Y`: the full year, like 2026
+/// - `%m`: the month, from 01 to 12
+/// - `%d`: the day of the month, from 01 to 31
+/// - `%H`: the hour, from 00 to 23
+/// - `%M`: the minute, from 00 to 59
+/// - `%S`: the second, from 00 to 59
+/// - `%u`: the day of the week, from 1 (Monday) to 7 (Sunday)
+/// - `%s`: the number of seconds since 1970-01-01 00:00:00
+/// - `%%`: a literal `%`
+///
+/// Unknown specifiers are left in the output as-is.
+pub fn format_datetime(datetime: &DateTime, format: &str) -> String {
+    let mut result = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('Y') => result.push_str(&format!("{:04}", datetime.year)),
+            Some('m') => result.push_str(&format!("{:02}", datetime.month)),
+            Some('d') => result.push_str(&format!("{:02}", datetime.day)),
+            Some('H') => result.push_str(&format!("{:02}", datetime.hour)),
+            Some('M') => result.push_str(&format!("{:02}", datetime.minute)),
+            Some('S') => result.push_str(&format!("{:02}", datetime.second)),
+            Some('u') => result.push_str(&format!("{}", datetime.weekday())),
+            Some('s') => result.push_str(&format!("{}", datetime.unix_timestamp())),
+            Some('%') => result.push('%'),
+            // Unknown specifiers are preserved.
+            Some(other) => {
+                result.push('%');
+                result.push(other);
+            }
+            // A trailing percent sign is preserved.
+            None => result.push('%'),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATETIME: DateTime = DateTime {
+        year: 2026,
+        month: 3,
+        day: 7,
+        hour: 9,
+        minute: 5,
+        second: 2,
+    };
+
+    #[test]
+    fn format_iso8601() {
+        assert_eq!(
+            format_datetime(&DATETIME, "%Y-%m-%dT%H:%M:%S"),
+            "2026-03-07T09:05:02"
+        );
+    }
+
+    #[test]
+    fn format_escapes_and_unknown_specifiers() {
+        assert_eq!(format_datetime(&DATETIME, "100%% %q %"), "100% %q %");
+    }
+
+    #[test]
+    fn unix_timestamp_is_calculated() {
+        let epoch = DateTime {
+            year: 1970,
+            month: 1,
+            day: 1,
+            ..Default::default()
+        };
+        assert_eq!(epoch.unix_timestamp(), 0);
+        assert_eq!(DATETIME.unix_timestamp(), 1772874302);
+        assert_eq!(format_datetime(&DATETIME, "%s"), "1772874302");
+        assert_eq!(DateTime::from_unix_timestamp(1772874302), DATETIME);
+        assert_eq!(DateTime::from_unix_timestamp(0), epoch);
+    }
+
+    #[test]
+    fn weekday_is_calculated() {
+        let leap_day = DateTime {
+            year: 2000,
+            month: 2,
+            day: 29,
+            ..Default::default()
+        };
+        assert_eq!(leap_day.unix_timestamp(), 951782400);
+        assert_eq!(DateTime::from_unix_timestamp(951782400), leap_day);
+        assert_eq!(leap_day.weekday(), 2);
+        assert_eq!(format_datetime(&DATETIME, "%u"), "6");
+    }
+}
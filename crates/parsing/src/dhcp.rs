@@ -0,0 +1,121 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// The option that pads the options to an alignment.
+const OPTION_PAD: u8 = 0;
+
+/// The option that ends the options.
+const OPTION_END: u8 = 255;
+
+/// The options whose data is a list of IPv4 addresses, like the routers and DNS servers.
+const ADDRESS_OPTIONS: &[u8] = &[1, 3, 4, 5, 6, 7, 9, 28, 41, 42, 44, 45, 48, 49, 50, 54];
+
+/// The options whose data is a 32-bit unsigned integer, like the lease time.
+const INTEGER_OPTIONS: &[u8] = &[2, 24, 35, 38, 51, 58, 59];
+
+/// Parses the DHCP `options` that follow the magic cookie of a DHCP packet into a map of the
+/// option code to its data. Options that appear multiple times are concatenated, as defined
+/// by RFC 3396. Parsing stops at the end option or at a truncated option.
+pub fn parse_options(options: &[u8]) -> BTreeMap<u8, Vec<u8>> {
+    let mut parsed: BTreeMap<u8, Vec<u8>> = BTreeMap::new();
+    let mut offset = 0;
+    while let Some(&code) = options.get(offset) {
+        match code {
+            OPTION_PAD => {
+                offset += 1;
+                continue;
+            }
+            OPTION_END => break,
+            _ => {}
+        }
+        let Some(&length) = options.get(offset + 1) else {
+            break;
+        };
+        let start = offset + 2;
+        let Some(data) = options.get(start..start + length as usize) else {
+            break;
+        };
+        parsed.entry(code).or_default().extend_from_slice(data);
+        offset = start + length as usize;
+    }
+    parsed
+}
+
+/// Formats the IPv4 `address` in dotted decimal notation.
+pub fn format_ipv4(address: [u8; 4]) -> String {
+    format!(
+        "{}.{}.{}.{}",
+        address[0], address[1], address[2], address[3]
+    )
+}
+
+/// Formats the hardware `address` as lowercase hex bytes separated by colons.
+pub fn format_mac(address: &[u8]) -> String {
+    address
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Formats the `data` of the DHCP option `code` as text. Address options are formatted as
+/// dotted decimal addresses separated by spaces, integer options as decimal numbers, and
+/// printable text as is. Any other data is formatted as lowercase hex.
+pub fn format_option(code: u8, data: &[u8]) -> String {
+    if ADDRESS_OPTIONS.contains(&code) && !data.is_empty() && data.len().is_multiple_of(4) {
+        return data
+            .chunks(4)
+            .map(|address| format_ipv4([address[0], address[1], address[2], address[3]]))
+            .collect::<Vec<_>>()
+            .join(" ");
+    }
+    if INTEGER_OPTIONS.contains(&code)
+        && let Ok(bytes) = <[u8; 4]>::try_from(data)
+    {
+        return format!("{}", u32::from_be_bytes(bytes));
+    }
+
+    // Text options are sometimes terminated with a null byte, which is not part of the text.
+    let text = data.strip_suffix(&[0]).unwrap_or(data);
+    if !text.is_empty() && text.iter().all(|byte| (0x20..0x7f).contains(byte)) {
+        return text.iter().map(|byte| *byte as char).collect();
+    }
+    hex::encode(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_are_parsed() {
+        let options = [
+            0, 12, 4, b'h', b'o', b's', b't', 81, 2, b'a', b'b', 81, 1, b'c', 255, 6, 4, 1, 1, 1, 1,
+        ];
+        let parsed = parse_options(&options);
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[&12], b"host");
+        assert_eq!(parsed[&81], b"abc");
+        assert!(parse_options(&[6, 8, 1, 1, 1, 1]).is_empty());
+        assert!(parse_options(&[]).is_empty());
+    }
+
+    #[test]
+    fn options_are_formatted() {
+        assert_eq!(
+            format_option(6, &[1, 1, 1, 1, 8, 8, 8, 8]),
+            "1.1.1.1 8.8.8.8"
+        );
+        assert_eq!(format_option(51, &[0, 1, 81, 128]), "86400");
+        assert_eq!(format_option(12, b"host-1\0"), "host-1");
+        assert_eq!(format_option(43, &[1, 2, 0xff]), "0102ff");
+        assert_eq!(format_option(3, &[10, 0, 0]), "0a0000");
+        assert_eq!(
+            format_mac(&[0x52, 0x54, 0, 0x12, 0x34, 0xab]),
+            "52:54:00:12:34:ab"
+        );
+        assert_eq!(format_ipv4([192, 168, 1, 10]), "192.168.1.10");
+    }
+}
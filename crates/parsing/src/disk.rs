@@ -0,0 +1,149 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// The signature at the start of a GPT header.
+const GPT_HEADER_SIGNATURE: &[u8] = b"EFI PART";
+
+/// The offset of the disk GUID in a GPT header.
+const GPT_HEADER_DISK_GUID_OFFSET: usize = 56;
+
+/// The identity of a physical disk, as reported by the disk itself.
+/// Fields that are not present or are empty are [None].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiskIdentity {
+    /// The model of the disk.
+    pub model: Option<String>,
+    /// The serial number of the disk.
+    pub serial: Option<String>,
+}
+
+/// Converts the ASCII `bytes` into a trimmed string.
+/// Strings that are empty after trimming are treated as not present.
+fn ascii_string(bytes: &[u8]) -> Option<String> {
+    let string = String::from_utf8_lossy(bytes);
+    let string = string.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+    if string.is_empty() {
+        None
+    } else {
+        Some(string.to_string())
+    }
+}
+
+/// Converts the ATA string in `bytes` into a trimmed string.
+/// ATA strings are stored as 16-bit words with the bytes of each word swapped.
+fn ata_string(bytes: &[u8]) -> Option<String> {
+    let swapped = bytes
+        .chunks_exact(2)
+        .flat_map(|word| [word[1], word[0]])
+        .collect::<Vec<_>>();
+    ascii_string(&swapped)
+}
+
+/// Parses the response to the ATA IDENTIFY DEVICE command in `data`.
+/// This is the identify data reported by IDE and AHCI disks.
+pub fn parse_ata_identify(data: &[u8]) -> DiskIdentity {
+    DiskIdentity {
+        // The serial number is stored in words 10 through 19.
+        serial: data.get(20..40).and_then(ata_string),
+        // The model number is stored in words 27 through 46.
+        model: data.get(54..94).and_then(ata_string),
+    }
+}
+
+/// Parses the NVMe Identify Controller data structure in `data`.
+/// This is the identify data reported by NVMe disks.
+pub fn parse_nvme_identify(data: &[u8]) -> DiskIdentity {
+    DiskIdentity {
+        serial: data.get(4..24).and_then(ascii_string),
+        model: data.get(24..64).and_then(ascii_string),
+    }
+}
+
+/// Parses the SCSI INQUIRY response in `data`.
+/// This is the data reported by SCSI and USB disks, which only provides a model.
+/// The model is the vendor identification followed by the product identification.
+pub fn parse_scsi_inquiry(data: &[u8]) -> DiskIdentity {
+    let vendor = data.get(8..16).and_then(ascii_string);
+    let product = data.get(16..32).and_then(ascii_string);
+    let model = match (vendor, product) {
+        (Some(vendor), Some(product)) => Some(alloc::format!("{} {}", vendor, product)),
+        (vendor, product) => vendor.or(product),
+    };
+    DiskIdentity {
+        model,
+        serial: None,
+    }
+}
+
+/// Parses the disk GUID from the GPT `header`, which is stored at LBA 1 of a disk.
+/// The GUID is returned in its on-disk mixed-endian byte order.
+/// Returns [None] if the header is not a GPT header.
+pub fn parse_gpt_disk_guid(header: &[u8]) -> Option<[u8; 16]> {
+    if !header.starts_with(GPT_HEADER_SIGNATURE) {
+        return None;
+    }
+    header
+        .get(GPT_HEADER_DISK_GUID_OFFSET..GPT_HEADER_DISK_GUID_OFFSET + 16)?
+        .try_into()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn ata_bytes(text: &str, length: usize) -> Vec<u8> {
+        let mut padded = text.as_bytes().to_vec();
+        padded.resize(length, b' ');
+        padded
+            .chunks_exact(2)
+            .flat_map(|word| [word[1], word[0]])
+            .collect()
+    }
+
+    #[test]
+    fn parse_ata_identify_data() {
+        let mut data = vec![0u8; 512];
+        data[20..40].copy_from_slice(&ata_bytes("  S1234", 20));
+        data[54..94].copy_from_slice(&ata_bytes("Sprout SSD 1TB", 40));
+        let identity = parse_ata_identify(&data);
+        assert_eq!(identity.serial.as_deref(), Some("S1234"));
+        assert_eq!(identity.model.as_deref(), Some("Sprout SSD 1TB"));
+    }
+
+    #[test]
+    fn parse_nvme_identify_data() {
+        let mut data = vec![0u8; 4096];
+        data[4..24].copy_from_slice(b"NV-0001             ");
+        data[24..64].copy_from_slice(b"Sprout NVMe 2TB                         ");
+        let identity = parse_nvme_identify(&data);
+        assert_eq!(identity.serial.as_deref(), Some("NV-0001"));
+        assert_eq!(identity.model.as_deref(), Some("Sprout NVMe 2TB"));
+    }
+
+    #[test]
+    fn parse_scsi_inquiry_data() {
+        let mut data = vec![0u8; 36];
+        data[8..16].copy_from_slice(b"EDERA   ");
+        data[16..32].copy_from_slice(b"USB STICK       ");
+        let identity = parse_scsi_inquiry(&data);
+        assert_eq!(identity.model.as_deref(), Some("EDERA USB STICK"));
+        assert_eq!(identity.serial, None);
+    }
+
+    #[test]
+    fn parse_truncated_identify_data() {
+        assert_eq!(parse_ata_identify(&[0u8; 16]), DiskIdentity::default());
+        assert_eq!(parse_nvme_identify(&[]), DiskIdentity::default());
+    }
+
+    #[test]
+    fn parse_gpt_header_disk_guid() {
+        let mut header = vec![0u8; 92];
+        header[..8].copy_from_slice(b"EFI PART");
+        header[56..72].copy_from_slice(&[7u8; 16]);
+        assert_eq!(parse_gpt_disk_guid(&header), Some([7u8; 16]));
+        assert_eq!(parse_gpt_disk_guid(&[0u8; 92]), None);
+    }
+}
@@ -0,0 +1,610 @@
+use crate::filesystem::{
+    DirectoryEntry, Node, NodeKind, ReadAt, ReadOnlyFilesystem, le_at, read_le, read_vec, slice_at,
+};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// The byte offset of the superblock.
+const SUPERBLOCK_OFFSET: u64 = 1024;
+
+/// The size of the superblock.
+const SUPERBLOCK_SIZE: usize = 1024;
+
+/// The magic number of the superblock.
+const SUPERBLOCK_MAGIC: u16 = 0xef53;
+
+/// The inode number of the root directory.
+const ROOT_INODE: u64 = 2;
+
+/// The size of the block pointers, inline data, or extent tree stored in an inode.
+const INODE_BLOCK_SIZE: usize = 60;
+
+/// The inode size of filesystems with the original revision.
+const ORIGINAL_INODE_SIZE: usize = 128;
+
+/// The group descriptor size of filesystems without the 64-bit feature.
+const ORIGINAL_DESCRIPTOR_SIZE: usize = 32;
+
+/// The number of direct block pointers in an inode.
+const DIRECT_BLOCKS: u64 = 12;
+
+/// The magic number of an extent tree node header.
+const EXTENT_MAGIC: u16 = 0xf30a;
+
+/// The maximum depth of an extent tree, which guards against loops in corrupt trees.
+const MAX_EXTENT_DEPTH: u16 = 5;
+
+/// Extents longer than this are uninitialized, which reads as zeros.
+const MAX_INITIALIZED_EXTENT: u16 = 32768;
+
+/// The inode flag of inodes that map their blocks with an extent tree.
+const INODE_FLAG_EXTENTS: u32 = 0x80000;
+
+/// The inode flag of inodes that store their data in the inode.
+const INODE_FLAG_INLINE_DATA: u32 = 0x10000000;
+
+/// The incompatible feature of directory entries that record the file type.
+const INCOMPAT_FILETYPE: u32 = 0x2;
+
+/// The incompatible feature of group descriptors with 64-bit block numbers.
+const INCOMPAT_64BIT: u32 = 0x80;
+
+/// The incompatible features that can be read. Filesystems that need recovery are read
+/// without replaying the journal, so changes that are only in the journal are not seen.
+/// The remaining features only concern writes, metadata placement that the group
+/// descriptors describe, or extended attributes.
+const INCOMPAT_SUPPORTED: u32 = INCOMPAT_FILETYPE
+    | 0x4 // RECOVER
+    | 0x40 // EXTENTS
+    | INCOMPAT_64BIT
+    | 0x100 // MMP
+    | 0x200 // FLEX_BG
+    | 0x400 // EA_INODE
+    | 0x2000 // CSUM_SEED
+    | 0x4000 // LARGEDIR
+    | 0x8000 // INLINE_DATA
+    | 0x10000 // ENCRYPT
+    | 0x20000; // CASEFOLD
+
+/// Checks whether `source` has an ext2, ext3, or ext4 superblock.
+pub fn probe(source: &(impl ReadAt + ?Sized)) -> bool {
+    let mut magic = [0u8; 2];
+    source
+        .read_at(SUPERBLOCK_OFFSET + 0x38, &mut magic)
+        .is_ok_and(|_| u16::from_le_bytes(magic) == SUPERBLOCK_MAGIC)
+}
+
+/// The fields of an inode that are used to read it.
+struct Inode {
+    /// The mode, which contains the file type.
+    mode: u16,
+    /// The size in bytes.
+    size: u64,
+    /// The time of the last modification.
+    modified: u32,
+    /// The flags.
+    flags: u32,
+    /// The block pointers, inline data, or extent tree.
+    block: [u8; INODE_BLOCK_SIZE],
+}
+
+impl Inode {
+    /// The kind of node the inode is.
+    fn kind(&self) -> NodeKind {
+        match self.mode & 0xf000 {
+            0x4000 => NodeKind::Directory,
+            0x8000 => NodeKind::File,
+            0xa000 => NodeKind::Symlink,
+            _ => NodeKind::Other,
+        }
+    }
+
+    /// Whether the data is stored in the block field instead of in blocks. This is the case
+    /// for inline data and for symbolic links with short targets.
+    fn is_inline(&self) -> bool {
+        self.flags & INODE_FLAG_INLINE_DATA != 0
+            || (self.kind() == NodeKind::Symlink
+                && self.flags & INODE_FLAG_EXTENTS == 0
+                && self.size < INODE_BLOCK_SIZE as u64)
+    }
+}
+
+/// A contiguous run of blocks of a file.
+struct BlockRun {
+    /// The physical block of the run, or [None] if the run reads as zeros.
+    physical: Option<u64>,
+    /// The number of blocks in the run.
+    length: u64,
+}
+
+/// A read-only ext2, ext3, or ext4 filesystem.
+pub struct Ext4<R: ReadAt> {
+    /// The source the filesystem is read from.
+    source: R,
+    /// The size of a block in bytes.
+    block_size: u64,
+    /// The number of blocks.
+    block_count: u64,
+    /// The number of inodes in each block group.
+    inodes_per_group: u64,
+    /// The size of an inode in bytes.
+    inode_size: usize,
+    /// The size of a group descriptor in bytes.
+    descriptor_size: usize,
+    /// The first block of the group descriptor table.
+    descriptor_block: u64,
+    /// Whether directory entries record the file type.
+    has_file_type: bool,
+    /// The volume label.
+    label: String,
+}
+
+impl<R: ReadAt> Ext4<R> {
+    /// Opens the filesystem on `source`, checking that its features can be read.
+    pub fn open(source: R) -> Result<Self, String> {
+        let superblock = read_vec(&source, SUPERBLOCK_OFFSET, SUPERBLOCK_SIZE)?;
+        if le_at::<u16>(&superblock, 0x38)? != SUPERBLOCK_MAGIC {
+            return Err("not an ext filesystem".to_string());
+        }
+
+        let incompat = le_at::<u32>(&superblock, 0x60)?;
+        if incompat & !INCOMPAT_SUPPORTED != 0 {
+            return Err(format!(
+                "unsupported ext4 features {:#x}",
+                incompat & !INCOMPAT_SUPPORTED
+            ));
+        }
+
+        let log_block_size = le_at::<u32>(&superblock, 0x18)?;
+        if log_block_size > 6 {
+            return Err(format!("invalid ext4 block size shift {}", log_block_size));
+        }
+        let block_size = 1024u64 << log_block_size;
+        let is_64bit = incompat & INCOMPAT_64BIT != 0;
+        let mut block_count = le_at::<u32>(&superblock, 0x04)? as u64;
+        if is_64bit {
+            block_count |= (le_at::<u32>(&superblock, 0x150)? as u64) << 32;
+        }
+        let inodes_per_group = le_at::<u32>(&superblock, 0x28)? as u64;
+        if inodes_per_group == 0 {
+            return Err("ext4 filesystem has no inodes".to_string());
+        }
+        let inode_size = match le_at::<u32>(&superblock, 0x4c)? {
+            0 => ORIGINAL_INODE_SIZE,
+            _ => le_at::<u16>(&superblock, 0x58)? as usize,
+        };
+        if inode_size < ORIGINAL_INODE_SIZE || !inode_size.is_power_of_two() {
+            return Err(format!("invalid ext4 inode size {}", inode_size));
+        }
+        let descriptor_size = match is_64bit {
+            true => (le_at::<u16>(&superblock, 0xfe)? as usize).max(ORIGINAL_DESCRIPTOR_SIZE),
+            false => ORIGINAL_DESCRIPTOR_SIZE,
+        };
+        let label = slice_at(&superblock, 0x78, 16)?
+            .split(|byte| *byte == 0)
+            .next()
+            .map(|label| String::from_utf8_lossy(label).to_string())
+            .unwrap_or_default();
+
+        Ok(Self {
+            source,
+            block_size,
+            block_count,
+            inodes_per_group,
+            inode_size,
+            descriptor_size,
+            descriptor_block: le_at::<u32>(&superblock, 0x14)? as u64 + 1,
+            has_file_type: incompat & INCOMPAT_FILETYPE != 0,
+            label,
+        })
+    }
+
+    /// Reads `count` bytes starting at byte `offset` of the `block`.
+    fn read_block(&self, block: u64, offset: u64, count: usize) -> Result<Vec<u8>, String> {
+        if block >= self.block_count {
+            return Err(format!(
+                "ext4 block {} is past the end of the filesystem",
+                block
+            ));
+        }
+        read_vec(&self.source, block * self.block_size + offset, count)
+    }
+
+    /// Reads the inode with the number `number`.
+    fn inode(&self, number: u64) -> Result<Inode, String> {
+        if number == 0 {
+            return Err("invalid ext4 inode 0".to_string());
+        }
+        let group = (number - 1) / self.inodes_per_group;
+        let index = (number - 1) % self.inodes_per_group;
+
+        // The group descriptors are packed into the blocks after the superblock.
+        let descriptor_offset = group * self.descriptor_size as u64;
+        let descriptor = read_vec(
+            &self.source,
+            self.descriptor_block * self.block_size + descriptor_offset,
+            self.descriptor_size,
+        )?;
+        let mut table = le_at::<u32>(&descriptor, 0x08)? as u64;
+        if self.descriptor_size >= 64 {
+            table |= (le_at::<u32>(&descriptor, 0x28)? as u64) << 32;
+        }
+
+        let data = self.read_block(table, index * self.inode_size as u64, ORIGINAL_INODE_SIZE)?;
+        let mut block = [0u8; INODE_BLOCK_SIZE];
+        block.copy_from_slice(slice_at(&data, 0x28, INODE_BLOCK_SIZE)?);
+        Ok(Inode {
+            mode: le_at::<u16>(&data, 0x00)?,
+            size: le_at::<u32>(&data, 0x04)? as u64 | (le_at::<u32>(&data, 0x6c)? as u64) << 32,
+            modified: le_at::<u32>(&data, 0x10)?,
+            flags: le_at::<u32>(&data, 0x20)?,
+            block,
+        })
+    }
+
+    /// Converts the inode with the number `number` to a [Node].
+    fn node(&self, number: u64) -> Result<Node, String> {
+        let inode = self.inode(number)?;
+        Ok(Node {
+            id: number,
+            tree: 0,
+            kind: inode.kind(),
+            size: inode.size,
+            modified: inode.modified as i64,
+        })
+    }
+
+    /// Finds the run of blocks of the extent tree `node` that contains the `logical` block.
+    fn extent_run(&self, node: &[u8], logical: u64, depth: u16) -> Result<BlockRun, String> {
+        if le_at::<u16>(node, 0)? != EXTENT_MAGIC {
+            return Err("invalid ext4 extent header".to_string());
+        }
+        let entries = le_at::<u16>(node, 2)? as usize;
+        let node_depth = le_at::<u16>(node, 6)?;
+        if depth > MAX_EXTENT_DEPTH || 12 + entries * 12 > node.len() {
+            return Err("invalid ext4 extent tree".to_string());
+        }
+        let entry = |index: usize| slice_at(node, 12 + index * 12, 12);
+
+        // The entries are sorted, so the last entry that starts at or before the block
+        // is the one that can contain it.
+        let found = (0..entries).rev().find(|index| {
+            read_le::<u32>(node, 12 + index * 12).is_some_and(|start| start as u64 <= logical)
+        });
+        if node_depth == 0 {
+            for index in found
+                .into_iter()
+                .chain(found.map_or(0, |index| index + 1)..entries)
+            {
+                let extent = entry(index)?;
+                let start = le_at::<u32>(extent, 0)? as u64;
+                let length = le_at::<u16>(extent, 4)?;
+                let (initialized, length) = match length > MAX_INITIALIZED_EXTENT {
+                    true => (false, (length - MAX_INITIALIZED_EXTENT) as u64),
+                    false => (true, length as u64),
+                };
+                if logical < start {
+                    // The block is in a hole before this extent.
+                    return Ok(BlockRun {
+                        physical: None,
+                        length: start - logical,
+                    });
+                }
+                if logical < start + length {
+                    let physical =
+                        (le_at::<u16>(extent, 6)? as u64) << 32 | le_at::<u32>(extent, 8)? as u64;
+                    return Ok(BlockRun {
+                        physical: initialized.then_some(physical + logical - start),
+                        length: start + length - logical,
+                    });
+                }
+            }
+            // The block is in a hole at the end of the file.
+            return Ok(BlockRun {
+                physical: None,
+                length: 1,
+            });
+        }
+
+        let Some(index) = found else {
+            return Ok(BlockRun {
+                physical: None,
+                length: 1,
+            });
+        };
+        let pointer = entry(index)?;
+        let leaf = (le_at::<u16>(pointer, 8)? as u64) << 32 | le_at::<u32>(pointer, 4)? as u64;
+        let child = self.read_block(leaf, 0, self.block_size as usize)?;
+        self.extent_run(&child, logical, depth + 1)
+    }
+
+    /// Finds the physical block that the `logical` block of a block-mapped inode is at,
+    /// following the indirect blocks. Returns [None] for a hole.
+    fn mapped_block(&self, block: &[u8], logical: u64) -> Result<Option<u64>, String> {
+        let per_block = self.block_size / 4;
+        let mut index = logical;
+        if index < DIRECT_BLOCKS {
+            return Ok(
+                Some(le_at::<u32>(block, index as usize * 4)? as u64).filter(|block| *block != 0)
+            );
+        }
+        index -= DIRECT_BLOCKS;
+
+        // Find the level of indirection and the index of the block within it.
+        let mut level = 1;
+        let mut capacity = per_block;
+        while index >= capacity {
+            index -= capacity;
+            level += 1;
+            capacity = capacity.saturating_mul(per_block);
+            if level > 3 {
+                return Err("ext4 block is beyond the triple indirect block".to_string());
+            }
+        }
+
+        let mut current = le_at::<u32>(block, (DIRECT_BLOCKS as usize + level - 1) * 4)? as u64;
+        for depth in (0..level).rev() {
+            if current == 0 {
+                return Ok(None);
+            }
+            let slot = (index / per_block.pow(depth as u32)) % per_block;
+            let pointer = self.read_block(current, slot * 4, 4)?;
+            current = le_at::<u32>(&pointer, 0)? as u64;
+        }
+        Ok(Some(current).filter(|block| *block != 0))
+    }
+
+    /// Finds the run of blocks of the `inode` that contains the `logical` block.
+    fn block_run(&self, inode: &Inode, logical: u64) -> Result<BlockRun, String> {
+        if inode.flags & INODE_FLAG_EXTENTS != 0 {
+            return self.extent_run(&inode.block, logical, 0);
+        }
+
+        // Block-mapped files are mapped block by block, so consecutive blocks are merged.
+        let first = self.mapped_block(&inode.block, logical)?;
+        let mut length = 1;
+        if let Some(first) = first {
+            while length < DIRECT_BLOCKS
+                && self.mapped_block(&inode.block, logical + length)? == Some(first + length)
+            {
+                length += 1;
+            }
+        }
+        Ok(BlockRun {
+            physical: first,
+            length,
+        })
+    }
+}
+
+impl<R: ReadAt> ReadOnlyFilesystem for Ext4<R> {
+    fn root(&self) -> Result<Node, String> {
+        self.node(ROOT_INODE)
+    }
+
+    fn read_dir(&self, directory: &Node) -> Result<Vec<DirectoryEntry>, String> {
+        // Hashed directories keep their tree in entries that are skipped like deleted
+        // entries, so every directory can be read as a list of entries.
+        let data = self.read_all(directory)?;
+        let mut entries = Vec::new();
+        let mut offset = 0;
+        while offset + 8 <= data.len() {
+            let inode = le_at::<u32>(&data, offset)? as u64;
+            let record_length = le_at::<u16>(&data, offset + 4)? as usize;
+            // Without the file type feature, the file type byte holds the high byte of the
+            // name length instead.
+            let name_length = match self.has_file_type {
+                true => le_at::<u8>(&data, offset + 6)? as usize,
+                false => le_at::<u16>(&data, offset + 6)? as usize,
+            };
+            if record_length < 8 || offset + 8 + name_length > data.len() {
+                return Err("invalid ext4 directory entry".to_string());
+            }
+            let name = slice_at(&data, offset + 8, name_length)?;
+            offset += record_length;
+            if inode == 0 || name == b"." || name == b".." {
+                continue;
+            }
+            let name = String::from_utf8_lossy(name).to_string();
+            entries.push(DirectoryEntry {
+                name,
+                node: self.node(inode)?,
+            });
+        }
+        Ok(entries)
+    }
+
+    fn read(&self, node: &Node, offset: u64, buffer: &mut [u8]) -> Result<usize, String> {
+        let inode = self.inode(node.id)?;
+        let end = inode.size.min(offset.saturating_add(buffer.len() as u64));
+        if offset >= end {
+            return Ok(0);
+        }
+        let size = (end - offset) as usize;
+        let buffer = &mut buffer[..size];
+
+        if inode.is_inline() {
+            let data = inode
+                .block
+                .get(offset as usize..end as usize)
+                .ok_or("ext4 inline data is larger than the inode")?;
+            buffer.copy_from_slice(data);
+            return Ok(size);
+        }
+
+        // Read each run of blocks at once, which keeps large reads like kernels fast.
+        let mut done = 0;
+        while done < size {
+            let position = offset + done as u64;
+            let logical = position / self.block_size;
+            let within = position % self.block_size;
+            let run = self.block_run(&inode, logical)?;
+            let available = (run.length * self.block_size - within) as usize;
+            let count = available.min(size - done);
+            let target = &mut buffer[done..done + count];
+            match run.physical {
+                Some(physical) => {
+                    if physical + run.length > self.block_count {
+                        return Err("ext4 file data is past the end of the filesystem".to_string());
+                    }
+                    self.source
+                        .read_at(physical * self.block_size + within, target)?;
+                }
+                None => target.fill(0),
+            }
+            done += count;
+        }
+        Ok(size)
+    }
+
+    fn label(&self) -> String {
+        self.label.clone()
+    }
+
+    fn size(&self) -> u64 {
+        self.block_count * self.block_size
+    }
+
+    fn block_size(&self) -> u32 {
+        self.block_size as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    /// The size of the blocks of the test filesystem.
+    const BLOCK: usize = 1024;
+
+    fn put16(image: &mut [u8], offset: usize, value: u16) {
+        image[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn put32(image: &mut [u8], offset: usize, value: u32) {
+        image[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    /// Writes the inode `number` to the inode table at block 3.
+    fn put_inode(image: &mut [u8], number: usize, mode: u16, size: u32, flags: u32) -> usize {
+        let offset = 3 * BLOCK + (number - 1) * 128;
+        put16(image, offset, mode);
+        put32(image, offset + 0x04, size);
+        put32(image, offset + 0x10, 1700000000);
+        put32(image, offset + 0x20, flags);
+        offset + 0x28
+    }
+
+    /// Writes a directory entry at `offset` and returns the offset of the next entry.
+    fn put_entry(image: &mut [u8], offset: usize, inode: u32, name: &str, length: u16) -> usize {
+        put32(image, offset, inode);
+        put16(image, offset + 4, length);
+        image[offset + 6] = name.len() as u8;
+        image[offset + 8..offset + 8 + name.len()].copy_from_slice(name.as_bytes());
+        offset + length as usize
+    }
+
+    /// Builds a filesystem with a `boot` directory that holds a kernel mapped by extents,
+    /// a link to the kernel, and a configuration file mapped by block pointers.
+    fn image() -> Vec<u8> {
+        let mut image = vec![0u8; 16 * BLOCK];
+        let superblock = BLOCK;
+        put32(&mut image, superblock, 16);
+        put32(&mut image, superblock + 0x04, 16);
+        put32(&mut image, superblock + 0x14, 1);
+        put32(&mut image, superblock + 0x20, 8192);
+        put32(&mut image, superblock + 0x28, 16);
+        put16(&mut image, superblock + 0x38, SUPERBLOCK_MAGIC);
+        put32(&mut image, superblock + 0x4c, 1);
+        put16(&mut image, superblock + 0x58, 128);
+        put32(&mut image, superblock + 0x60, INCOMPAT_FILETYPE | 0x40);
+        image[superblock + 0x78..superblock + 0x7c].copy_from_slice(b"boot");
+
+        // The inode table of the only group is at block 3.
+        put32(&mut image, 2 * BLOCK + 0x08, 3);
+
+        let root = put_inode(&mut image, 2, 0x41ed, BLOCK as u32, 0);
+        put32(&mut image, root, 5);
+        let next = put_entry(&mut image, 5 * BLOCK, 2, ".", 12);
+        let next = put_entry(&mut image, next, 2, "..", 12);
+        put_entry(&mut image, next, 11, "boot", (BLOCK - 24) as u16);
+
+        let boot = put_inode(&mut image, 11, 0x41ed, BLOCK as u32, 0);
+        put32(&mut image, boot, 6);
+        let next = put_entry(&mut image, 6 * BLOCK, 11, ".", 12);
+        let next = put_entry(&mut image, next, 2, "..", 12);
+        let next = put_entry(&mut image, next, 12, "vmlinuz-6.1", 20);
+        let next = put_entry(&mut image, next, 0, "deleted", 16);
+        let next = put_entry(&mut image, next, 13, "vmlinuz", 16);
+        put_entry(&mut image, next, 14, "config", (7 * BLOCK - next) as u16);
+
+        // The kernel has a hole in its first block, then two blocks at 7.
+        let kernel = put_inode(&mut image, 12, 0x81a4, 2500, INODE_FLAG_EXTENTS);
+        put16(&mut image, kernel, EXTENT_MAGIC);
+        put16(&mut image, kernel + 2, 1);
+        put16(&mut image, kernel + 4, 4);
+        put32(&mut image, kernel + 12, 1);
+        put16(&mut image, kernel + 16, 2);
+        put32(&mut image, kernel + 20, 7);
+        image[7 * BLOCK..9 * BLOCK].fill(b'k');
+
+        let link = put_inode(&mut image, 13, 0xa1ff, 11, 0);
+        image[link..link + 11].copy_from_slice(b"vmlinuz-6.1");
+
+        let config = put_inode(&mut image, 14, 0x81a4, 10, 0);
+        put32(&mut image, config, 9);
+        image[9 * BLOCK..9 * BLOCK + 10].copy_from_slice(b"CONFIG_X=y");
+        image
+    }
+
+    #[test]
+    fn files_are_read() {
+        let filesystem = Ext4::open(image()).unwrap();
+        assert_eq!(filesystem.label(), "boot");
+        assert_eq!(filesystem.size(), 16 * BLOCK as u64);
+
+        let boot = filesystem.resolve(&[], "\\boot").unwrap().unwrap();
+        let names = filesystem
+            .read_dir(&boot[1].node)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["vmlinuz-6.1", "vmlinuz", "config"]);
+
+        let kernel = filesystem.resolve(&boot, "vmlinuz").unwrap().unwrap();
+        let kernel = &kernel.last().unwrap().node;
+        assert_eq!(kernel.kind, NodeKind::File);
+        assert_eq!(kernel.modified, 1700000000);
+        let data = filesystem.read_all(kernel).unwrap();
+        assert_eq!(data.len(), 2500);
+        assert!(data[..BLOCK].iter().all(|byte| *byte == 0));
+        assert!(data[BLOCK..].iter().all(|byte| *byte == b'k'));
+
+        let mut buffer = [0u8; 8];
+        let config = filesystem.resolve(&[], "/BOOT/Config").unwrap().unwrap();
+        let config = &config.last().unwrap().node;
+        assert_eq!(filesystem.read(config, 7, &mut buffer), Ok(3));
+        assert_eq!(&buffer[..3], b"X=y");
+    }
+
+    #[test]
+    fn corrupted_sizes_are_rejected() {
+        let mut image = image();
+        // A directory with a size far beyond the filesystem, like a fuzzed image had.
+        put32(&mut image, 3 * BLOCK + 10 * 128 + 0x6c, 0x1b00_0000);
+        let filesystem = Ext4::open(image).unwrap();
+        assert!(filesystem.resolve(&[], "\\boot\\config").is_err());
+    }
+
+    #[test]
+    fn unsupported_filesystems_are_rejected() {
+        assert!(!probe(&vec![0u8; 4 * BLOCK]));
+        let mut image = image();
+        assert!(probe(&image));
+        put32(&mut image, BLOCK + 0x60, 0x10);
+        assert!(Ext4::open(image).is_err());
+    }
+}
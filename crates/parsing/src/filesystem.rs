@@ -0,0 +1,409 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// The maximum number of symbolic links that are followed while resolving a path.
+const MAX_SYMLINK_HOPS: usize = 8;
+
+/// The maximum size of a directory that is read entirely, which bounds the allocation for
+/// directories with a corrupted size.
+const MAX_DIRECTORY_SIZE: u64 = 16 * 1024 * 1024;
+
+/// The maximum size of a symbolic link target, which is the path length limit of Linux.
+const MAX_SYMLINK_SIZE: u64 = 4096;
+
+/// A source of bytes that a filesystem is read from, like a block device.
+pub trait ReadAt {
+    /// Reads exactly `buffer.len()` bytes starting at the byte `offset` into `buffer`.
+    fn read_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), String>;
+}
+
+impl ReadAt for [u8] {
+    fn read_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), String> {
+        let start = usize::try_from(offset).map_err(|_| "read offset overflow")?;
+        buffer.copy_from_slice(slice_at(self, start, buffer.len())?);
+        Ok(())
+    }
+}
+
+impl ReadAt for Vec<u8> {
+    fn read_at(&self, offset: u64, buffer: &mut [u8]) -> Result<(), String> {
+        self.as_slice().read_at(offset, buffer)
+    }
+}
+
+/// Reads `size` bytes starting at the byte `offset` of the `source`.
+pub fn read_vec(
+    source: &(impl ReadAt + ?Sized),
+    offset: u64,
+    size: usize,
+) -> Result<Vec<u8>, String> {
+    let mut buffer = vec![0u8; size];
+    source.read_at(offset, &mut buffer)?;
+    Ok(buffer)
+}
+
+/// An integer that filesystems store in little-endian byte order.
+pub trait LittleEndian: Sized {
+    /// Decodes the integer from `bytes`, or returns [None] if they are not its size.
+    fn from_le_slice(bytes: &[u8]) -> Option<Self>;
+}
+
+impl LittleEndian for u8 {
+    fn from_le_slice(bytes: &[u8]) -> Option<Self> {
+        Some(u8::from_le_bytes(bytes.try_into().ok()?))
+    }
+}
+
+impl LittleEndian for u16 {
+    fn from_le_slice(bytes: &[u8]) -> Option<Self> {
+        Some(u16::from_le_bytes(bytes.try_into().ok()?))
+    }
+}
+
+impl LittleEndian for u32 {
+    fn from_le_slice(bytes: &[u8]) -> Option<Self> {
+        Some(u32::from_le_bytes(bytes.try_into().ok()?))
+    }
+}
+
+impl LittleEndian for u64 {
+    fn from_le_slice(bytes: &[u8]) -> Option<Self> {
+        Some(u64::from_le_bytes(bytes.try_into().ok()?))
+    }
+}
+
+/// Reads a little-endian integer at `offset` of `data`.
+/// Returns [None] if `data` is too short to hold it.
+pub fn read_le<T: LittleEndian>(data: &[u8], offset: usize) -> Option<T> {
+    let end = offset.checked_add(size_of::<T>())?;
+    T::from_le_slice(data.get(offset..end)?)
+}
+
+/// Reads a little-endian integer at `offset` of `data`, like [read_le],
+/// but fails with an error if `data` is too short to hold it.
+pub fn le_at<T: LittleEndian>(data: &[u8], offset: usize) -> Result<T, String> {
+    read_le(data, offset).ok_or_else(|| {
+        format!(
+            "read of {} bytes at {} is out of range",
+            size_of::<T>(),
+            offset
+        )
+    })
+}
+
+/// Borrows the `length` bytes at `offset` of `data`,
+/// failing with an error if `data` is too short to hold them.
+pub fn slice_at(data: &[u8], offset: usize, length: usize) -> Result<&[u8], String> {
+    offset
+        .checked_add(length)
+        .and_then(|end| data.get(offset..end))
+        .ok_or_else(|| format!("read of {} bytes at {} is out of range", length, offset))
+}
+
+/// The kind of a [Node].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    /// A regular file.
+    File,
+    /// A directory.
+    Directory,
+    /// A symbolic link, which is followed when resolving paths.
+    Symlink,
+    /// Any other kind of node, like a device node, which can not be read.
+    Other,
+}
+
+/// A file, directory, or link of a filesystem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    /// The identifier of the node that is meaningful to its filesystem, like an inode number.
+    pub id: u64,
+    /// A second identifier for filesystems that need one, like the tree that holds the node.
+    pub tree: u64,
+    /// The kind of the node.
+    pub kind: NodeKind,
+    /// The size of the node in bytes.
+    pub size: u64,
+    /// The time the node was last modified, in seconds since 1970-01-01 00:00:00.
+    pub modified: i64,
+}
+
+/// A named [Node] in a directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryEntry {
+    /// The name of the node in the directory.
+    pub name: String,
+    /// The node that the name refers to.
+    pub node: Node,
+}
+
+/// A filesystem that can be read, but never modified.
+///
+/// Filesystems only need to list directories and read nodes. Paths are resolved on top of
+/// those by [ReadOnlyFilesystem::resolve], which follows symbolic links.
+pub trait ReadOnlyFilesystem {
+    /// The root directory of the filesystem.
+    fn root(&self) -> Result<Node, String>;
+
+    /// Lists the entries of the `directory`, excluding `.` and `..`.
+    fn read_dir(&self, directory: &Node) -> Result<Vec<DirectoryEntry>, String>;
+
+    /// Reads the contents of the `node` starting at the byte `offset` into `buffer`.
+    /// Returns the number of bytes read, which is less than the buffer at the end of the node.
+    fn read(&self, node: &Node, offset: u64, buffer: &mut [u8]) -> Result<usize, String>;
+
+    /// The label of the filesystem, which is empty if it has none.
+    fn label(&self) -> String;
+
+    /// The size of the filesystem in bytes.
+    fn size(&self) -> u64;
+
+    /// The size of the blocks of the filesystem in bytes.
+    fn block_size(&self) -> u32;
+
+    /// Reads the entire contents of the `node`.
+    ///
+    /// The size of the node comes from the filesystem, which may be corrupted. Directories and
+    /// symbolic links larger than the filesystem, [MAX_DIRECTORY_SIZE], or [MAX_SYMLINK_SIZE]
+    /// are rejected. Files can be larger than the filesystem when they are compressed or sparse,
+    /// so a buffer that can't be allocated for them is an error instead.
+    fn read_all(&self, node: &Node) -> Result<Vec<u8>, String> {
+        let limit = match node.kind {
+            NodeKind::Directory => Some(MAX_DIRECTORY_SIZE),
+            NodeKind::Symlink => Some(MAX_SYMLINK_SIZE),
+            NodeKind::File | NodeKind::Other => None,
+        };
+        if let Some(limit) = limit
+            && (node.size > self.size() || node.size > limit)
+        {
+            return Err(format!("node size {} is too large to read", node.size));
+        }
+        let size = usize::try_from(node.size).map_err(|_| "node is too large to read")?;
+        let mut buffer = Vec::new();
+        buffer
+            .try_reserve_exact(size)
+            .map_err(|_| format!("unable to allocate {} bytes to read node", size))?;
+        buffer.resize(size, 0);
+        let read = self.read(node, 0, &mut buffer)?;
+        buffer.truncate(read);
+        Ok(buffer)
+    }
+
+    /// Resolves the `path` starting at the directory at the end of the `from` entries,
+    /// which list the directories from the root down. The root entry has an empty name.
+    ///
+    /// Returns the entries from the root down to the node of the path, or [None] if the path
+    /// does not exist. Both `\` and `/` separate components, and a leading separator starts
+    /// at the root. Names are matched exactly, or ignoring ASCII case if there is no exact
+    /// match, as UEFI paths are usually written for case-insensitive FAT filesystems.
+    fn resolve(
+        &self,
+        from: &[DirectoryEntry],
+        path: &str,
+    ) -> Result<Option<Vec<DirectoryEntry>>, String> {
+        let root = DirectoryEntry {
+            name: String::new(),
+            node: self.root()?,
+        };
+        let mut stack = if path.starts_with(['\\', '/']) || from.is_empty() {
+            vec![root.clone()]
+        } else {
+            from.to_vec()
+        };
+
+        // The components that remain to be resolved, in reverse order, so that link
+        // targets can be pushed in front of the remaining components.
+        let mut pending = path
+            .split(['\\', '/'])
+            .rev()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let mut hops = 0;
+        while let Some(component) = pending.pop() {
+            match component.as_str() {
+                "" | "." => continue,
+                ".." => {
+                    if stack.len() > 1 {
+                        stack.pop();
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+
+            let Some(directory) = stack.last().map(|entry| entry.node.clone()) else {
+                return Err("path resolution lost the root directory".to_string());
+            };
+            if directory.kind != NodeKind::Directory {
+                return Ok(None);
+            }
+            let entries = self.read_dir(&directory)?;
+            let found = entries
+                .iter()
+                .find(|entry| entry.name == component)
+                .or_else(|| {
+                    entries
+                        .iter()
+                        .find(|entry| entry.name.eq_ignore_ascii_case(&component))
+                });
+            let Some(entry) = found.cloned() else {
+                return Ok(None);
+            };
+
+            // Links are resolved relative to the directory that contains them.
+            if entry.node.kind == NodeKind::Symlink {
+                hops += 1;
+                if hops > MAX_SYMLINK_HOPS {
+                    return Err(format!("too many symbolic links resolving {}", path));
+                }
+                let target = self.read_all(&entry.node)?;
+                let target = String::from_utf8(target).map_err(|_| "symbolic link is not utf-8")?;
+                if target.starts_with('/') {
+                    stack.truncate(1);
+                }
+                pending.extend(target.split('/').rev().map(ToString::to_string));
+                continue;
+            }
+            stack.push(entry);
+        }
+        Ok(Some(stack))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::collections::BTreeMap;
+
+    /// A node held in memory, with its contents and its directory entries.
+    type MemoryNode = (Node, Vec<u8>, Vec<(&'static str, u64)>);
+
+    /// A filesystem with nodes held in memory, keyed by their identifier.
+    struct MemoryFilesystem {
+        nodes: BTreeMap<u64, MemoryNode>,
+    }
+
+    impl MemoryFilesystem {
+        fn new() -> Self {
+            let mut filesystem = Self {
+                nodes: BTreeMap::new(),
+            };
+            filesystem.add(1, NodeKind::Directory, b"", &[("boot", 2), ("up", 5)]);
+            filesystem.add(
+                2,
+                NodeKind::Directory,
+                b"",
+                &[("vmlinuz-6.1", 3), ("vmlinuz", 4)],
+            );
+            filesystem.add(3, NodeKind::File, b"kernel", &[]);
+            filesystem.add(4, NodeKind::Symlink, b"vmlinuz-6.1", &[]);
+            filesystem.add(5, NodeKind::Symlink, b"/boot/../up", &[]);
+            filesystem
+        }
+
+        fn add(&mut self, id: u64, kind: NodeKind, data: &[u8], entries: &[(&'static str, u64)]) {
+            let node = Node {
+                id,
+                tree: 0,
+                kind,
+                size: data.len() as u64,
+                modified: 0,
+            };
+            self.nodes
+                .insert(id, (node, data.to_vec(), entries.to_vec()));
+        }
+    }
+
+    impl ReadOnlyFilesystem for MemoryFilesystem {
+        fn root(&self) -> Result<Node, String> {
+            Ok(self.nodes[&1].0.clone())
+        }
+
+        fn read_dir(&self, directory: &Node) -> Result<Vec<DirectoryEntry>, String> {
+            Ok(self.nodes[&directory.id]
+                .2
+                .iter()
+                .map(|(name, id)| DirectoryEntry {
+                    name: name.to_string(),
+                    node: self.nodes[id].0.clone(),
+                })
+                .collect())
+        }
+
+        fn read(&self, node: &Node, offset: u64, buffer: &mut [u8]) -> Result<usize, String> {
+            let data = &self.nodes[&node.id].1[offset as usize..];
+            let size = data.len().min(buffer.len());
+            buffer[..size].copy_from_slice(&data[..size]);
+            Ok(size)
+        }
+
+        fn label(&self) -> String {
+            String::new()
+        }
+
+        fn size(&self) -> u64 {
+            self.nodes.values().map(|node| node.1.len() as u64).sum()
+        }
+
+        fn block_size(&self) -> u32 {
+            512
+        }
+    }
+
+    fn resolve(filesystem: &MemoryFilesystem, path: &str) -> Option<Vec<String>> {
+        filesystem
+            .resolve(&[], path)
+            .unwrap()
+            .map(|stack| stack.into_iter().map(|entry| entry.name).collect())
+    }
+
+    #[test]
+    fn paths_are_resolved() {
+        let filesystem = MemoryFilesystem::new();
+        assert_eq!(
+            resolve(&filesystem, "\\BOOT\\vmlinuz-6.1"),
+            Some(vec![
+                "".to_string(),
+                "boot".to_string(),
+                "vmlinuz-6.1".to_string()
+            ])
+        );
+        assert_eq!(
+            resolve(&filesystem, "/boot/./vmlinuz"),
+            Some(vec![
+                "".to_string(),
+                "boot".to_string(),
+                "vmlinuz-6.1".to_string()
+            ])
+        );
+        assert_eq!(
+            resolve(&filesystem, "boot\\..\\boot"),
+            Some(vec!["".to_string(), "boot".to_string()])
+        );
+        assert_eq!(resolve(&filesystem, "\\boot\\missing"), None);
+        assert_eq!(resolve(&filesystem, "\\boot\\vmlinuz-6.1\\file"), None);
+        assert!(filesystem.resolve(&[], "\\up").is_err());
+    }
+
+    #[test]
+    fn slices_are_read_at_offsets() {
+        let data = [1u8, 2, 3, 4];
+        assert_eq!(read_vec(&data[..], 1, 2), Ok(vec![2, 3]));
+        assert!(read_vec(&data[..], 3, 2).is_err());
+    }
+
+    #[test]
+    fn integers_are_read_checked() {
+        let data = [0x34u8, 0x12, 0x78, 0x56];
+        assert_eq!(read_le::<u16>(&data, 0), Some(0x1234));
+        assert_eq!(read_le::<u32>(&data, 0), Some(0x56781234));
+        assert_eq!(read_le::<u16>(&data, 3), None);
+        assert_eq!(read_le::<u64>(&data, 0), None);
+        assert_eq!(read_le::<u8>(&data, usize::MAX), None);
+        assert!(le_at::<u32>(&data, 1).is_err());
+        assert_eq!(slice_at(&data, 2, 2), Ok(&data[2..]));
+        assert!(slice_at(&data, 2, 3).is_err());
+    }
+}
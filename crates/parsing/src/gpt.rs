@@ -0,0 +1,232 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// The signature at the start of a GPT header.
+const GPT_HEADER_SIGNATURE: &[u8] = b"EFI PART";
+
+/// The minimum size of a GPT header, which covers every field that is parsed.
+const GPT_HEADER_MIN_SIZE: usize = 92;
+
+/// The minimum size of a GPT partition entry, which covers every field that is parsed.
+const GPT_ENTRY_MIN_SIZE: usize = 128;
+
+/// The maximum size of the partition entry array that is accepted, which guards
+/// against corrupt headers requesting enormous reads.
+pub const GPT_MAX_ENTRIES_SIZE: usize = 1024 * 1024;
+
+/// A GPT header, which is stored at LBA 1 of a disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GptHeader {
+    /// The disk GUID, in its on-disk mixed-endian byte order.
+    pub disk_guid: [u8; 16],
+    /// The LBA of the partition entry array.
+    pub entries_lba: u64,
+    /// The number of entries in the partition entry array.
+    pub entry_count: u32,
+    /// The size of each entry in the partition entry array.
+    pub entry_size: u32,
+    /// The CRC32 of the partition entry array.
+    pub entries_crc32: u32,
+}
+
+impl GptHeader {
+    /// The size of the partition entry array in bytes.
+    pub fn entries_size(&self) -> usize {
+        self.entry_count as usize * self.entry_size as usize
+    }
+}
+
+/// A used entry of a GPT partition entry array.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GptPartitionEntry {
+    /// The partition number, which is the one-based index of the entry in the array.
+    pub number: u32,
+    /// The partition type GUID, in its on-disk mixed-endian byte order.
+    pub type_guid: [u8; 16],
+    /// The unique partition GUID, in its on-disk mixed-endian byte order.
+    pub unique_guid: [u8; 16],
+    /// The first LBA of the partition.
+    pub first_lba: u64,
+    /// The last LBA of the partition, inclusive.
+    pub last_lba: u64,
+    /// The attribute flags of the partition.
+    pub attributes: u64,
+    /// The name of the partition.
+    pub name: String,
+}
+
+/// Computes the CRC32 of `data`, as used by GPT.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb88320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Reads a little-endian u32 from `data` at `offset`.
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// Reads a little-endian u64 from `data` at `offset`.
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        data.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+/// Reads a 16-byte GUID from `data` at `offset`.
+fn read_guid(data: &[u8], offset: usize) -> Option<[u8; 16]> {
+    data.get(offset..offset + 16)?.try_into().ok()
+}
+
+/// Parses the GPT `header`, which is the first block read from LBA 1 of a disk.
+/// Returns [None] if the block is not a GPT header or its checksum does not match.
+pub fn parse_gpt_header(header: &[u8]) -> Option<GptHeader> {
+    if !header.starts_with(GPT_HEADER_SIGNATURE) {
+        return None;
+    }
+
+    // The checksum covers the header size, with the checksum field itself zeroed.
+    let size = read_u32(header, 12)? as usize;
+    if size < GPT_HEADER_MIN_SIZE {
+        return None;
+    }
+    let mut checked = header.get(..size)?.to_vec();
+    checked[16..20].fill(0);
+    if crc32(&checked) != read_u32(header, 16)? {
+        return None;
+    }
+
+    let parsed = GptHeader {
+        disk_guid: read_guid(header, 56)?,
+        entries_lba: read_u64(header, 72)?,
+        entry_count: read_u32(header, 80)?,
+        entry_size: read_u32(header, 84)?,
+        entries_crc32: read_u32(header, 88)?,
+    };
+
+    // Entries smaller than the specification allows can't be parsed,
+    // and an enormous entry array is a sign of corruption.
+    if (parsed.entry_size as usize) < GPT_ENTRY_MIN_SIZE
+        || parsed.entries_size() > GPT_MAX_ENTRIES_SIZE
+    {
+        return None;
+    }
+    Some(parsed)
+}
+
+/// Parses the used entries of the partition entry array `entries` described by `header`.
+/// Returns [None] if the array is truncated or its checksum does not match.
+pub fn parse_gpt_entries(header: &GptHeader, entries: &[u8]) -> Option<Vec<GptPartitionEntry>> {
+    let entries = entries.get(..header.entries_size())?;
+    if crc32(entries) != header.entries_crc32 {
+        return None;
+    }
+
+    let mut parsed = Vec::new();
+    for (index, entry) in entries.chunks_exact(header.entry_size as usize).enumerate() {
+        // Unused entries have a zero type GUID.
+        let type_guid = read_guid(entry, 0)?;
+        if type_guid == [0u8; 16] {
+            continue;
+        }
+
+        // The name is stored as UTF-16LE, padded with zeros.
+        let name = entry[56..128]
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .take_while(|unit| *unit != 0)
+            .collect::<Vec<_>>();
+
+        parsed.push(GptPartitionEntry {
+            number: index as u32 + 1,
+            type_guid,
+            unique_guid: read_guid(entry, 16)?,
+            first_lba: read_u64(entry, 32)?,
+            last_lba: read_u64(entry, 40)?,
+            attributes: read_u64(entry, 48)?,
+            name: String::from_utf16_lossy(&name),
+        });
+    }
+    Some(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn entry(type_guid: u8, unique_guid: u8, first_lba: u64, name: &str) -> Vec<u8> {
+        let mut entry = vec![0u8; 128];
+        entry[0..16].fill(type_guid);
+        entry[16..32].fill(unique_guid);
+        entry[32..40].copy_from_slice(&first_lba.to_le_bytes());
+        entry[40..48].copy_from_slice(&(first_lba + 99).to_le_bytes());
+        for (index, unit) in name.encode_utf16().enumerate() {
+            entry[56 + index * 2..58 + index * 2].copy_from_slice(&unit.to_le_bytes());
+        }
+        entry
+    }
+
+    fn header(entries: &[u8], count: u32) -> Vec<u8> {
+        let mut header = vec![0u8; 512];
+        header[..8].copy_from_slice(b"EFI PART");
+        header[12..16].copy_from_slice(&92u32.to_le_bytes());
+        header[56..72].copy_from_slice(&[7u8; 16]);
+        header[72..80].copy_from_slice(&2u64.to_le_bytes());
+        header[80..84].copy_from_slice(&count.to_le_bytes());
+        header[84..88].copy_from_slice(&128u32.to_le_bytes());
+        header[88..92].copy_from_slice(&crc32(entries).to_le_bytes());
+        let crc = crc32(&header[..92]);
+        header[16..20].copy_from_slice(&crc.to_le_bytes());
+        header
+    }
+
+    #[test]
+    fn crc32_matches_known_value() {
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+    }
+
+    #[test]
+    fn parse_header_and_entries() {
+        let mut entries = entry(1, 2, 2048, "EFI system");
+        entries.extend(vec![0u8; 128]);
+        entries.extend(entry(3, 4, 4096, "root"));
+        let header = parse_gpt_header(&header(&entries, 3)).expect("header should parse");
+        assert_eq!(header.disk_guid, [7u8; 16]);
+        assert_eq!(header.entries_lba, 2);
+
+        let parsed = parse_gpt_entries(&header, &entries).expect("entries should parse");
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].number, 1);
+        assert_eq!(parsed[0].name, "EFI system");
+        assert_eq!(parsed[1].number, 3);
+        assert_eq!(parsed[1].type_guid, [3u8; 16]);
+        assert_eq!(parsed[1].unique_guid, [4u8; 16]);
+        assert_eq!(parsed[1].first_lba, 4096);
+        assert_eq!(parsed[1].last_lba, 4195);
+    }
+
+    #[test]
+    fn reject_corrupt_header_and_entries() {
+        let entries = entry(1, 2, 2048, "EFI system");
+        let mut corrupt = header(&entries, 1);
+        corrupt[56] ^= 0xff;
+        assert_eq!(parse_gpt_header(&corrupt), None);
+        assert_eq!(parse_gpt_header(&[0u8; 512]), None);
+
+        let header = parse_gpt_header(&header(&entries, 1)).expect("header should parse");
+        let mut corrupt = entries.clone();
+        corrupt[40] ^= 0xff;
+        assert_eq!(parse_gpt_entries(&header, &corrupt), None);
+        assert_eq!(parse_gpt_entries(&header, &entries[..64]), None);
+    }
+}
@@ -0,0 +1,57 @@
+/// The maximum length of an iSCSI name in bytes, as defined by RFC 3720.
+pub const MAX_NAME_LENGTH: usize = 223;
+
+/// Checks whether `name` is a valid iSCSI name, like an initiator name.
+/// Names are either `iqn.yyyy-mm.<reversed domain>[:<identifier>]`, `eui.` followed by
+/// 16 hex digits, or `naa.` followed by 16 or 32 hex digits, as defined by RFC 3720.
+/// Qualified names must be lowercase, as the firmware does not normalize them.
+pub fn is_valid_name(name: &str) -> bool {
+    if name.is_empty() || name.len() > MAX_NAME_LENGTH {
+        return false;
+    }
+    let is_hex = |digits: &str| digits.bytes().all(|byte| byte.is_ascii_hexdigit());
+    if let Some(digits) = name.strip_prefix("eui.") {
+        return digits.len() == 16 && is_hex(digits);
+    }
+    if let Some(digits) = name.strip_prefix("naa.") {
+        return (digits.len() == 16 || digits.len() == 32) && is_hex(digits);
+    }
+    let Some(rest) = name.strip_prefix("iqn.") else {
+        return false;
+    };
+
+    // The date is the year and month the naming authority owned the domain.
+    let Some((date, rest)) = rest.split_once('.') else {
+        return false;
+    };
+    let date_valid = date.len() == 7
+        && date.bytes().enumerate().all(|(index, byte)| match index {
+            4 => byte == b'-',
+            _ => byte.is_ascii_digit(),
+        });
+    let (domain, _) = rest.split_once(':').unwrap_or((rest, ""));
+    date_valid
+        && !domain.is_empty()
+        && rest
+            .bytes()
+            .all(|byte| matches!(byte, b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b':'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_validated() {
+        assert!(is_valid_name("iqn.2025-01.com.example:host-1"));
+        assert!(is_valid_name("iqn.2025-01.com.example"));
+        assert!(is_valid_name("eui.02004567a425678d"));
+        assert!(is_valid_name("naa.52004567ba64678d"));
+        assert!(!is_valid_name("iqn.2025-01.com.Example:host"));
+        assert!(!is_valid_name("iqn.2025.com.example"));
+        assert!(!is_valid_name("iqn.2025-01.:host"));
+        assert!(!is_valid_name("eui.02004567"));
+        assert!(!is_valid_name("host-1"));
+        assert!(!is_valid_name(""));
+    }
+}
@@ -0,0 +1,848 @@
+use crate::datetime::DateTime;
+use crate::filesystem::{
+    DirectoryEntry, Node, NodeKind, ReadAt, ReadOnlyFilesystem, le_at, read_le, read_vec, slice_at,
+};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// The size of the sectors of ISO9660 filesystems.
+const SECTOR_SIZE: u64 = 2048;
+
+/// The sector of the first volume descriptor.
+const DESCRIPTORS_SECTOR: u64 = 16;
+
+/// The maximum number of volume descriptors that are read before the terminator.
+const MAX_DESCRIPTORS: u64 = 64;
+
+/// The identifier of every volume descriptor.
+const STANDARD_IDENTIFIER: &[u8] = b"CD001";
+
+/// The type of the boot record volume descriptor.
+const DESCRIPTOR_BOOT_RECORD: u8 = 0;
+
+/// The type of the primary volume descriptor.
+const DESCRIPTOR_PRIMARY: u8 = 1;
+
+/// The type of the supplementary volume descriptor, which Joliet uses.
+const DESCRIPTOR_SUPPLEMENTARY: u8 = 2;
+
+/// The type of the volume descriptor that ends the descriptors.
+const DESCRIPTOR_TERMINATOR: u8 = 255;
+
+/// The escape sequences of the three levels of Joliet supplementary volume descriptors.
+const JOLIET_ESCAPES: [&[u8]; 3] = [b"%/@", b"%/C", b"%/E"];
+
+/// The boot system identifier of the El Torito boot record.
+const EL_TORITO_IDENTIFIER: &[u8] = b"EL TORITO SPECIFICATION";
+
+/// The offset of the root directory record in primary and supplementary volume descriptors.
+const ROOT_RECORD_OFFSET: usize = 156;
+
+/// The size of a directory record without its name and system use area.
+const RECORD_HEADER_SIZE: usize = 33;
+
+/// The directory record flag of directories.
+const FLAG_DIRECTORY: u8 = 0x2;
+
+/// The directory record flag of records that are followed by another extent of the file.
+const FLAG_MULTI_EXTENT: u8 = 0x80;
+
+/// The maximum number of continuation areas of a system use area, which guards against loops.
+const MAX_CONTINUATIONS: usize = 16;
+
+/// The file type bits of a POSIX file mode.
+const MODE_TYPE_MASK: u32 = 0o170000;
+
+/// The file type of symbolic links in a POSIX file mode.
+const MODE_SYMLINK: u32 = 0o120000;
+
+/// The El Torito platform identifier of EFI boot images.
+const PLATFORM_EFI: u8 = 0xef;
+
+/// The boot indicator of bootable El Torito entries.
+const BOOT_INDICATOR_BOOTABLE: u8 = 0x88;
+
+/// The size of the entries of an El Torito boot catalog.
+const CATALOG_ENTRY_SIZE: usize = 32;
+
+/// The size of the virtual sectors that El Torito boot images are counted in.
+const VIRTUAL_SECTOR_SIZE: u64 = 512;
+
+/// Checks whether the `source` holds an ISO9660 filesystem, without validating it.
+pub fn probe(source: &(impl ReadAt + ?Sized)) -> bool {
+    let mut identifier = [0u8; 5];
+    source
+        .read_at(DESCRIPTORS_SECTOR * SECTOR_SIZE + 1, &mut identifier)
+        .is_ok()
+        && identifier == STANDARD_IDENTIFIER
+}
+
+/// How the names of directory records are encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Naming {
+    /// Plain ISO9660 names, which are upper case and end with a version.
+    Plain,
+    /// Joliet names, which are UCS-2 in big-endian byte order.
+    Joliet,
+    /// Rock Ridge names and attributes, which are in the system use area of each record,
+    /// after the given number of bytes.
+    RockRidge(usize),
+}
+
+/// A directory record of an ISO9660 directory.
+struct Record {
+    /// The byte offset of the record in the filesystem.
+    location: u64,
+    /// The first sector of the data of the record.
+    extent: u64,
+    /// The size of the data of the record in bytes.
+    size: u64,
+    /// The flags of the record.
+    flags: u8,
+    /// The name of the record as it is stored.
+    name: Vec<u8>,
+    /// The system use area of the record, which holds Rock Ridge entries.
+    system_use: Vec<u8>,
+    /// The recording time of the record, in seconds since 1970-01-01 00:00:00.
+    recorded: i64,
+}
+
+/// The Rock Ridge attributes of a directory record.
+#[derive(Default)]
+struct RockRidge {
+    /// The name of the record, which replaces the stored name.
+    name: Option<String>,
+    /// The POSIX file mode of the record.
+    mode: Option<u32>,
+    /// The target of a symbolic link.
+    link: Option<String>,
+    /// The sector of a directory that was relocated to keep the hierarchy shallow.
+    child: Option<u64>,
+    /// Whether the record is the relocated directory itself, which is listed at its child link.
+    relocated: bool,
+}
+
+/// The location of a boot image in the El Torito boot catalog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BootImage {
+    /// The byte offset of the image in the filesystem.
+    pub offset: u64,
+    /// The size of the image in bytes.
+    pub size: u64,
+}
+
+/// A read-only ISO9660 filesystem, with Joliet and Rock Ridge names and El Torito boot images.
+pub struct Iso9660<R: ReadAt> {
+    /// The source the filesystem is read from.
+    source: R,
+    /// The root directory.
+    root: Node,
+    /// The label of the volume.
+    label: String,
+    /// The size of the volume in bytes.
+    size: u64,
+    /// How the names of directory records are encoded.
+    naming: Naming,
+    /// The sector of the El Torito boot catalog, if the filesystem is bootable.
+    boot_catalog: Option<u64>,
+}
+
+impl<R: ReadAt> Iso9660<R> {
+    /// Opens the ISO9660 filesystem of the `source`, preferring Rock Ridge names,
+    /// then Joliet names, then plain names.
+    pub fn open(source: R) -> Result<Self, String> {
+        let mut primary = None;
+        let mut joliet = None;
+        let mut boot_catalog = None;
+        for index in 0..MAX_DESCRIPTORS {
+            let offset = (DESCRIPTORS_SECTOR + index) * SECTOR_SIZE;
+            let descriptor = read_vec(&source, offset, SECTOR_SIZE as usize)?;
+            if slice_at(&descriptor, 1, 5)? != STANDARD_IDENTIFIER {
+                return Err("not an iso9660 filesystem".to_string());
+            }
+            match le_at::<u8>(&descriptor, 0)? {
+                DESCRIPTOR_BOOT_RECORD
+                    if slice_at(&descriptor, 7, EL_TORITO_IDENTIFIER.len())?
+                        == EL_TORITO_IDENTIFIER =>
+                {
+                    boot_catalog = Some(le_at::<u32>(&descriptor, 71)? as u64);
+                }
+                DESCRIPTOR_PRIMARY if primary.is_none() => primary = Some((offset, descriptor)),
+                DESCRIPTOR_SUPPLEMENTARY
+                    if JOLIET_ESCAPES.contains(&slice_at(&descriptor, 88, 3)?) =>
+                {
+                    joliet = Some((offset, descriptor));
+                }
+                DESCRIPTOR_TERMINATOR => break,
+                _ => {}
+            }
+        }
+        let (primary_offset, primary) =
+            primary.ok_or("iso9660 filesystem has no primary volume descriptor")?;
+        let block_size = le_at::<u16>(&primary, 128)? as u64;
+        if block_size != SECTOR_SIZE {
+            return Err(format!(
+                "iso9660 logical block size {} is not supported",
+                block_size
+            ));
+        }
+
+        let mut filesystem = Self {
+            source,
+            root: Node {
+                id: 0,
+                tree: 0,
+                kind: NodeKind::Directory,
+                size: 0,
+                modified: 0,
+            },
+            label: String::from_utf8_lossy(slice_at(&primary, 40, 32)?)
+                .trim_end()
+                .to_string(),
+            size: le_at::<u32>(&primary, 80)? as u64 * SECTOR_SIZE,
+            naming: Naming::Plain,
+            boot_catalog,
+        };
+        let root = filesystem.descriptor_root(&primary, primary_offset)?;
+
+        // Rock Ridge is announced by a sharing protocol entry in the first record of the root.
+        filesystem.root = root;
+        let first = filesystem
+            .records(&filesystem.root)?
+            .into_iter()
+            .next()
+            .ok_or("iso9660 root directory is empty")?;
+        let sharing = &first.system_use;
+        if sharing.starts_with(b"SP") && sharing.get(4..6) == Some(&[0xbe, 0xef]) {
+            filesystem.naming = Naming::RockRidge(le_at::<u8>(sharing, 6)? as usize);
+        } else if let Some((joliet_offset, joliet)) = joliet {
+            filesystem.naming = Naming::Joliet;
+            filesystem.root = filesystem.descriptor_root(&joliet, joliet_offset)?;
+            let label = decode_ucs2(slice_at(&joliet, 40, 32)?);
+            filesystem.label = label.trim_end().to_string();
+        }
+        Ok(filesystem)
+    }
+
+    /// Reads the root directory of the volume `descriptor` at the byte offset `location`.
+    fn descriptor_root(&self, descriptor: &[u8], location: u64) -> Result<Node, String> {
+        let record = parse_record(
+            descriptor,
+            ROOT_RECORD_OFFSET,
+            location + ROOT_RECORD_OFFSET as u64,
+        )?
+        .ok_or("iso9660 volume descriptor has no root directory")?;
+        Ok(Node {
+            id: record.extent,
+            tree: record.location,
+            kind: NodeKind::Directory,
+            size: record.size,
+            modified: record.recorded,
+        })
+    }
+
+    /// Reads the directory records of the `directory`, including `.` and `..`.
+    fn records(&self, directory: &Node) -> Result<Vec<Record>, String> {
+        let data = self.read_all(directory)?;
+        let start = directory.id * SECTOR_SIZE;
+        let mut records = Vec::new();
+        let mut offset = 0;
+        while offset < data.len() {
+            // Records never cross sectors, so the rest of a sector is padding after a zero length.
+            match parse_record(&data, offset, start + offset as u64)? {
+                Some(record) => {
+                    offset += le_at::<u8>(&data, offset)? as usize;
+                    records.push(record);
+                }
+                None => offset = (offset / SECTOR_SIZE as usize + 1) * SECTOR_SIZE as usize,
+            }
+        }
+        Ok(records)
+    }
+
+    /// Reads the entries of the system use `area`, following continuation areas.
+    fn system_use_entries(&self, area: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+        let mut entries = Vec::new();
+        let mut area = area.to_vec();
+        for _ in 0..MAX_CONTINUATIONS {
+            let mut continuation = None;
+            let mut offset = 0;
+            while offset + 4 <= area.len() {
+                let length = le_at::<u8>(&area, offset + 2)? as usize;
+                let Some(entry) = area.get(offset..offset + length).filter(|_| length >= 4) else {
+                    break;
+                };
+                let entry = entry.to_vec();
+                offset += length;
+                match slice_at(&entry, 0, 2)? {
+                    b"ST" => break,
+                    b"CE" if length >= 28 => {
+                        let location = le_at::<u32>(&entry, 4)? as u64 * SECTOR_SIZE
+                            + le_at::<u32>(&entry, 12)? as u64;
+                        let length = (le_at::<u32>(&entry, 20)? as u64).min(SECTOR_SIZE);
+                        continuation = Some((location, length as usize));
+                    }
+                    _ => entries.push(entry),
+                }
+            }
+            match continuation {
+                Some((location, length)) => area = read_vec(&self.source, location, length)?,
+                None => return Ok(entries),
+            }
+        }
+        Err("too many iso9660 continuation areas".to_string())
+    }
+
+    /// Reads the Rock Ridge attributes of the `record`, which skips `skip` bytes of its system
+    /// use area.
+    fn rock_ridge(&self, record: &Record, skip: usize) -> Result<RockRidge, String> {
+        let mut attributes = RockRidge::default();
+        let area = record.system_use.get(skip..).unwrap_or_default();
+        let mut name = Vec::new();
+        let mut link: Option<String> = None;
+        for entry in self.system_use_entries(area)? {
+            match slice_at(&entry, 0, 2)? {
+                // Names of the current and parent directories are not stored as names.
+                b"NM" if read_le::<u8>(&entry, 4).is_some_and(|flags| flags & 0x6 == 0) => {
+                    name.extend_from_slice(&entry[5..]);
+                }
+                b"PX" if entry.len() >= 8 => attributes.mode = Some(le_at::<u32>(&entry, 4)?),
+                b"SL" if entry.len() >= 5 => {
+                    let target = link.get_or_insert_with(String::new);
+                    parse_link_components(&entry[5..], target);
+                }
+                b"CL" if entry.len() >= 8 => {
+                    attributes.child = Some(le_at::<u32>(&entry, 4)? as u64)
+                }
+                b"RE" => attributes.relocated = true,
+                _ => {}
+            }
+        }
+        if !name.is_empty() {
+            attributes.name = Some(String::from_utf8_lossy(&name).to_string());
+        }
+        attributes.link = link;
+        Ok(attributes)
+    }
+
+    /// Converts the `record` to a directory entry, or [None] if it is not listed.
+    fn entry(&self, record: &Record) -> Result<Option<DirectoryEntry>, String> {
+        let mut node = Node {
+            id: record.extent,
+            tree: record.location,
+            kind: match record.flags & FLAG_DIRECTORY != 0 {
+                true => NodeKind::Directory,
+                false => NodeKind::File,
+            },
+            size: record.size,
+            modified: record.recorded,
+        };
+        let name = match self.naming {
+            Naming::Plain => plain_name(&record.name),
+            Naming::Joliet => strip_version(&decode_ucs2(&record.name)).to_string(),
+            Naming::RockRidge(skip) => {
+                let attributes = self.rock_ridge(record, skip)?;
+                if attributes.relocated {
+                    return Ok(None);
+                }
+                if let Some(child) = attributes.child {
+                    // The directory is stored elsewhere, and its `.` record holds its size.
+                    let directory =
+                        read_vec(&self.source, child * SECTOR_SIZE, SECTOR_SIZE as usize)?;
+                    let current = parse_record(&directory, 0, child * SECTOR_SIZE)?
+                        .ok_or("iso9660 relocated directory is empty")?;
+                    node.id = current.extent;
+                    node.size = current.size;
+                    node.kind = NodeKind::Directory;
+                }
+                if attributes.mode.map(|mode| mode & MODE_TYPE_MASK) == Some(MODE_SYMLINK) {
+                    node.kind = NodeKind::Symlink;
+                    node.size = attributes.link.as_deref().unwrap_or_default().len() as u64;
+                }
+                attributes.name.unwrap_or_else(|| plain_name(&record.name))
+            }
+        };
+        Ok(Some(DirectoryEntry { name, node }))
+    }
+
+    /// Locates the EFI boot image in the El Torito boot catalog.
+    /// Returns [None] if the filesystem has no EFI boot image.
+    pub fn efi_boot_image(&self) -> Result<Option<BootImage>, String> {
+        let Some(catalog) = self.boot_catalog else {
+            return Ok(None);
+        };
+        let catalog = read_vec(&self.source, catalog * SECTOR_SIZE, SECTOR_SIZE as usize)?;
+        let Some((sector, count)) = efi_catalog_entry(&catalog) else {
+            return Ok(None);
+        };
+        let offset = sector * SECTOR_SIZE;
+
+        // Images of one virtual sector or less usually span their whole FAT filesystem,
+        // whose size is in its boot sector.
+        let mut size = count * VIRTUAL_SECTOR_SIZE;
+        if count <= 1 {
+            let boot_sector = read_vec(&self.source, offset, VIRTUAL_SECTOR_SIZE as usize)?;
+            let sector_size = le_at::<u16>(&boot_sector, 11)? as u64;
+            let sectors = match le_at::<u16>(&boot_sector, 19)? {
+                0 => le_at::<u32>(&boot_sector, 32)? as u64,
+                sectors => sectors as u64,
+            };
+            if sector_size * sectors > size {
+                size = sector_size * sectors;
+            }
+        }
+        Ok(Some(BootImage { offset, size }))
+    }
+}
+
+impl<R: ReadAt> ReadOnlyFilesystem for Iso9660<R> {
+    fn root(&self) -> Result<Node, String> {
+        Ok(self.root.clone())
+    }
+
+    fn read_dir(&self, directory: &Node) -> Result<Vec<DirectoryEntry>, String> {
+        let mut entries: Vec<DirectoryEntry> = Vec::new();
+        // A file with more than one extent has a record for each, which are joined when
+        // the extents are contiguous.
+        let mut partial: Option<DirectoryEntry> = None;
+        for record in self.records(directory)? {
+            if record.name == [0] || record.name == [1] {
+                continue;
+            }
+            let Some(mut entry) = self.entry(&record)? else {
+                continue;
+            };
+            if let Some(previous) = partial.take() {
+                let end = previous.node.id + previous.node.size.div_ceil(SECTOR_SIZE);
+                if previous.name != entry.name || end != record.extent {
+                    return Err(format!("iso9660 file {} is fragmented", previous.name));
+                }
+                entry.node.id = previous.node.id;
+                entry.node.size += previous.node.size;
+            }
+            if record.flags & FLAG_MULTI_EXTENT != 0 {
+                partial = Some(entry);
+            } else {
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+
+    fn read(&self, node: &Node, offset: u64, buffer: &mut [u8]) -> Result<usize, String> {
+        let end = node.size.min(offset.saturating_add(buffer.len() as u64));
+        if offset >= end {
+            return Ok(0);
+        }
+        let size = (end - offset) as usize;
+
+        // The target of a link is in the Rock Ridge entries of its record.
+        if node.kind == NodeKind::Symlink {
+            let Naming::RockRidge(skip) = self.naming else {
+                return Err("iso9660 link without rock ridge".to_string());
+            };
+            let header = read_vec(&self.source, node.tree, 1)?;
+            let data = read_vec(&self.source, node.tree, le_at::<u8>(&header, 0)? as usize)?;
+            let record = parse_record(&data, 0, node.tree)?.ok_or("invalid iso9660 link")?;
+            let link = self.rock_ridge(&record, skip)?.link.unwrap_or_default();
+            let data = link
+                .as_bytes()
+                .get(offset as usize..end as usize)
+                .ok_or("iso9660 link changed size")?;
+            buffer[..size].copy_from_slice(data);
+            return Ok(size);
+        }
+
+        self.source
+            .read_at(node.id * SECTOR_SIZE + offset, &mut buffer[..size])?;
+        Ok(size)
+    }
+
+    fn label(&self) -> String {
+        self.label.clone()
+    }
+
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn block_size(&self) -> u32 {
+        SECTOR_SIZE as u32
+    }
+}
+
+/// Parses the directory record at `offset` of `data`, which is at the byte offset `location`
+/// of the filesystem. Returns [None] if there is no record at the offset.
+fn parse_record(data: &[u8], offset: usize, location: u64) -> Result<Option<Record>, String> {
+    let length = le_at::<u8>(data, offset)? as usize;
+    if length == 0 {
+        return Ok(None);
+    }
+    let record = slice_at(data, offset, length)
+        .ok()
+        .filter(|record| record.len() > RECORD_HEADER_SIZE)
+        .ok_or("invalid iso9660 directory record")?;
+    let name_length = le_at::<u8>(record, 32)? as usize;
+    // The name is padded to an even length, and the system use area follows it.
+    let system_use = RECORD_HEADER_SIZE + name_length + (name_length + 1) % 2;
+    if RECORD_HEADER_SIZE + name_length > length {
+        return Err("invalid iso9660 directory record name".to_string());
+    }
+    Ok(Some(Record {
+        location,
+        extent: le_at::<u32>(record, 2)? as u64,
+        size: le_at::<u32>(record, 10)? as u64,
+        flags: le_at::<u8>(record, 25)?,
+        name: slice_at(record, RECORD_HEADER_SIZE, name_length)?.to_vec(),
+        system_use: record.get(system_use..).unwrap_or_default().to_vec(),
+        recorded: recording_time(slice_at(record, 18, 7)?),
+    }))
+}
+
+/// Converts the recording `time` of a directory record to seconds since 1970-01-01 00:00:00.
+fn recording_time(time: &[u8]) -> i64 {
+    if time[1] == 0 {
+        return 0;
+    }
+    let date = DateTime {
+        year: 1900 + time[0] as u16,
+        month: time[1],
+        day: time[2],
+        hour: time[3],
+        minute: time[4],
+        second: time[5],
+    };
+    // The offset from UTC is in intervals of 15 minutes.
+    date.unix_timestamp() - time[6] as i8 as i64 * 15 * 60
+}
+
+/// Appends the components of a Rock Ridge symbolic link entry to the link `target`.
+fn parse_link_components(components: &[u8], target: &mut String) {
+    let mut offset = 0;
+    let mut continued = target.is_empty() || target.ends_with('/');
+    while offset + 2 <= components.len() {
+        let flags = components[offset];
+        let length = components[offset + 1] as usize;
+        let content = components
+            .get(offset + 2..offset + 2 + length)
+            .unwrap_or_default();
+        offset += 2 + length;
+        if !continued && !target.ends_with('/') {
+            target.push('/');
+        }
+        match flags & 0xe {
+            0x2 => target.push('.'),
+            0x4 => target.push_str(".."),
+            0x8 => target.push('/'),
+            _ => target.push_str(&String::from_utf8_lossy(content)),
+        }
+        continued = flags & 0x1 != 0;
+    }
+}
+
+/// Finds the first bootable EFI entry of the El Torito boot `catalog`.
+/// Returns the sector of the image and the number of virtual sectors it spans.
+fn efi_catalog_entry(catalog: &[u8]) -> Option<(u64, u64)> {
+    let validation = catalog.get(..CATALOG_ENTRY_SIZE)?;
+    let checksum = validation.chunks(2).fold(0u16, |sum, word| {
+        sum.wrapping_add(read_le::<u16>(word, 0).unwrap_or_default())
+    });
+    if validation[0] != 1 || validation[30..32] != [0x55, 0xaa] || checksum != 0 {
+        return None;
+    }
+
+    let bootable = |entry: &[u8]| {
+        if read_le::<u8>(entry, 0)? != BOOT_INDICATOR_BOOTABLE {
+            return None;
+        }
+        Some((
+            read_le::<u32>(entry, 8)? as u64,
+            read_le::<u16>(entry, 6)? as u64,
+        ))
+    };
+    let default = catalog.get(CATALOG_ENTRY_SIZE..CATALOG_ENTRY_SIZE * 2)?;
+    if validation[1] == PLATFORM_EFI
+        && let Some(image) = bootable(default)
+    {
+        return Some(image);
+    }
+
+    // Section headers list the entries of each platform, and the last header is 0x91.
+    let mut entries = catalog
+        .get(CATALOG_ENTRY_SIZE * 2..)?
+        .chunks_exact(CATALOG_ENTRY_SIZE);
+    while let Some(header) = entries.next() {
+        if header[0] != 0x90 && header[0] != 0x91 {
+            break;
+        }
+        let mut count = read_le::<u16>(header, 2)?;
+        while count > 0 {
+            let entry = entries.next()?;
+            // Extension entries continue the previous entry and are not counted.
+            if entry[0] == 0x44 {
+                continue;
+            }
+            count -= 1;
+            if header[1] == PLATFORM_EFI
+                && let Some(image) = bootable(entry)
+            {
+                return Some(image);
+            }
+        }
+        if header[0] == 0x91 {
+            break;
+        }
+    }
+    None
+}
+
+/// Decodes a UCS-2 string in big-endian byte order, like Joliet names.
+fn decode_ucs2(data: &[u8]) -> String {
+    let units = data
+        .chunks_exact(2)
+        .map(|unit| u16::from_be_bytes([unit[0], unit[1]]));
+    char::decode_utf16(units)
+        .map(|unit| unit.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+/// Removes the version of a `name`, like `;1`.
+fn strip_version(name: &str) -> &str {
+    name.split(';').next().unwrap_or_default()
+}
+
+/// Converts a plain ISO9660 `name` to the lower case name without a version or an empty
+/// extension, which is how Linux shows them.
+fn plain_name(name: &[u8]) -> String {
+    let name = String::from_utf8_lossy(name);
+    let name = strip_version(&name);
+    name.strip_suffix('.').unwrap_or(name).to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    /// The size of the sectors of the test filesystem.
+    const SECTOR: usize = SECTOR_SIZE as usize;
+
+    fn put16(image: &mut [u8], offset: usize, value: u16) {
+        image[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn put32(image: &mut [u8], offset: usize, value: u32) {
+        image[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    /// Writes a directory record at `offset` and returns the offset of the next record.
+    fn put_record(
+        image: &mut [u8],
+        offset: usize,
+        name: &[u8],
+        extent: u32,
+        size: u32,
+        flags: u8,
+        system_use: &[u8],
+    ) -> usize {
+        let padding = (name.len() + 1) % 2;
+        let length = RECORD_HEADER_SIZE + name.len() + padding + system_use.len();
+        image[offset] = length as u8;
+        put32(image, offset + 2, extent);
+        put32(image, offset + 10, size);
+        image[offset + 18..offset + 25].copy_from_slice(&[124, 1, 2, 3, 4, 5, 0]);
+        image[offset + 25] = flags;
+        image[offset + 32] = name.len() as u8;
+        image[offset + 33..offset + 33 + name.len()].copy_from_slice(name);
+        let area = offset + RECORD_HEADER_SIZE + name.len() + padding;
+        image[area..area + system_use.len()].copy_from_slice(system_use);
+        offset + length
+    }
+
+    /// Builds a system use entry with the `signature` and `data`.
+    fn entry(signature: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut entry = signature.to_vec();
+        entry.push((data.len() + 4) as u8);
+        entry.push(1);
+        entry.extend_from_slice(data);
+        entry
+    }
+
+    /// Builds a filesystem whose root is at sector 19 and holds a `BOOT` directory at sector 20
+    /// with a kernel at sector 21. With `rock_ridge`, the kernel is named `vmlinuz-6.1` and
+    /// `vmlinuz` links to it. The El Torito catalog at sector 22 has an EFI image at sector 23.
+    fn image(rock_ridge: bool) -> Vec<u8> {
+        let mut image = vec![0u8; 25 * SECTOR];
+        let primary = 16 * SECTOR;
+        image[primary] = DESCRIPTOR_PRIMARY;
+        image[primary + 1..primary + 6].copy_from_slice(STANDARD_IDENTIFIER);
+        image[primary + 40..primary + 72].fill(b' ');
+        image[primary + 40..primary + 46].copy_from_slice(b"UBUNTU");
+        put32(&mut image, primary + 80, 25);
+        put16(&mut image, primary + 128, SECTOR as u16);
+        put_record(
+            &mut image,
+            primary + 156,
+            &[0],
+            19,
+            SECTOR as u32,
+            FLAG_DIRECTORY,
+            &[],
+        );
+
+        let boot = 17 * SECTOR;
+        image[boot] = DESCRIPTOR_BOOT_RECORD;
+        image[boot + 1..boot + 6].copy_from_slice(STANDARD_IDENTIFIER);
+        image[boot + 7..boot + 7 + EL_TORITO_IDENTIFIER.len()]
+            .copy_from_slice(EL_TORITO_IDENTIFIER);
+        put32(&mut image, boot + 71, 22);
+        image[18 * SECTOR] = DESCRIPTOR_TERMINATOR;
+        image[18 * SECTOR + 1..18 * SECTOR + 6].copy_from_slice(STANDARD_IDENTIFIER);
+
+        let (sharing, boot_name, kernel_name) = match rock_ridge {
+            true => (
+                entry(b"SP", &[0xbe, 0xef, 0]),
+                entry(b"NM", b"\0boot"),
+                entry(b"NM", b"\0vmlinuz-6.1"),
+            ),
+            false => (Vec::new(), Vec::new(), Vec::new()),
+        };
+        let directory = FLAG_DIRECTORY;
+        let next = put_record(
+            &mut image,
+            19 * SECTOR,
+            &[0],
+            19,
+            SECTOR as u32,
+            directory,
+            &sharing,
+        );
+        let next = put_record(&mut image, next, &[1], 19, SECTOR as u32, directory, &[]);
+        put_record(
+            &mut image,
+            next,
+            b"BOOT",
+            20,
+            SECTOR as u32,
+            directory,
+            &boot_name,
+        );
+
+        let next = put_record(
+            &mut image,
+            20 * SECTOR,
+            &[0],
+            20,
+            SECTOR as u32,
+            directory,
+            &[],
+        );
+        let next = put_record(&mut image, next, &[1], 19, SECTOR as u32, directory, &[]);
+        let next = put_record(&mut image, next, b"VMLINUZ.;1", 21, 6, 0, &kernel_name);
+        if rock_ridge {
+            let mut link = entry(b"PX", &0o120777u32.to_le_bytes());
+            link.extend(entry(b"NM", b"\0vmlinuz"));
+            link.extend(entry(b"SL", b"\0\0\x0bvmlinuz-6.1"));
+            put_record(&mut image, next, b"VMLINUZ0.;1", 0, 0, 0, &link);
+        }
+        image[21 * SECTOR..21 * SECTOR + 6].copy_from_slice(b"kernel");
+
+        // The validation entry is followed by a default entry for BIOS and an EFI section.
+        let catalog = 22 * SECTOR;
+        image[catalog] = 1;
+        image[catalog + 30..catalog + 32].copy_from_slice(&[0x55, 0xaa]);
+        let sum = image[catalog..catalog + 32]
+            .chunks(2)
+            .fold(0u16, |sum, word| {
+                sum.wrapping_add(read_le::<u16>(word, 0).unwrap())
+            });
+        put16(&mut image, catalog + 28, 0u16.wrapping_sub(sum));
+        image[catalog + 32] = BOOT_INDICATOR_BOOTABLE;
+        put32(&mut image, catalog + 40, 24);
+        image[catalog + 64] = 0x91;
+        image[catalog + 65] = PLATFORM_EFI;
+        put16(&mut image, catalog + 66, 1);
+        image[catalog + 96] = BOOT_INDICATOR_BOOTABLE;
+        put16(&mut image, catalog + 102, 1);
+        put32(&mut image, catalog + 104, 23);
+
+        // The EFI image is a FAT filesystem of 4 sectors of 512 bytes.
+        put16(&mut image, 23 * SECTOR + 11, 512);
+        put16(&mut image, 23 * SECTOR + 19, 4);
+        image
+    }
+
+    fn names(filesystem: &Iso9660<Vec<u8>>, path: &str) -> Vec<String> {
+        let stack = filesystem.resolve(&[], path).unwrap().unwrap();
+        let directory = &stack.last().unwrap().node;
+        filesystem
+            .read_dir(directory)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect()
+    }
+
+    fn read(filesystem: &Iso9660<Vec<u8>>, path: &str) -> Option<Vec<u8>> {
+        let stack = filesystem.resolve(&[], path).unwrap()?;
+        Some(filesystem.read_all(&stack.last().unwrap().node).unwrap())
+    }
+
+    #[test]
+    fn plain_names_are_read() {
+        let image = image(false);
+        assert!(probe(&image));
+        let filesystem = Iso9660::open(image).unwrap();
+        assert_eq!(filesystem.label(), "UBUNTU");
+        assert_eq!(filesystem.size(), 25 * SECTOR_SIZE);
+        assert_eq!(names(&filesystem, "\\"), vec!["boot".to_string()]);
+        assert_eq!(names(&filesystem, "\\boot"), vec!["vmlinuz".to_string()]);
+        assert_eq!(
+            read(&filesystem, "\\BOOT\\VMLINUZ"),
+            Some(b"kernel".to_vec())
+        );
+
+        let stack = filesystem.resolve(&[], "\\boot").unwrap().unwrap();
+        let modified = stack.last().unwrap().node.modified;
+        assert_eq!(DateTime::from_unix_timestamp(modified).year, 2024);
+    }
+
+    #[test]
+    fn rock_ridge_names_and_links_are_read() {
+        let filesystem = Iso9660::open(image(true)).unwrap();
+        assert_eq!(
+            names(&filesystem, "\\boot"),
+            vec!["vmlinuz-6.1".to_string(), "vmlinuz".to_string()]
+        );
+        assert_eq!(
+            read(&filesystem, "\\boot\\vmlinuz"),
+            Some(b"kernel".to_vec())
+        );
+        assert_eq!(read(&filesystem, "\\boot\\vmlinuz.;1"), None);
+    }
+
+    #[test]
+    fn efi_boot_image_is_located() {
+        let filesystem = Iso9660::open(image(false)).unwrap();
+        assert_eq!(
+            filesystem.efi_boot_image(),
+            Ok(Some(BootImage {
+                offset: 23 * SECTOR_SIZE,
+                size: 2048,
+            }))
+        );
+        assert!(!probe(&vec![0u8; 17 * SECTOR]));
+        assert!(Iso9660::open(vec![0u8; 17 * SECTOR]).is_err());
+    }
+
+    #[test]
+    fn truncated_structures_are_rejected() {
+        let image = image(false);
+        let catalog = &image[22 * SECTOR..22 * SECTOR + 128];
+        assert_eq!(efi_catalog_entry(catalog), Some((23, 1)));
+        assert_eq!(efi_catalog_entry(&catalog[..40]), None);
+        assert!(parse_record(&[40], 0, 0).is_err());
+        assert!(parse_record(&[], 0, 0).is_err());
+    }
+}
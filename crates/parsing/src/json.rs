@@ -0,0 +1,118 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+/// A JSON value, which can be encoded to compact JSON text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    /// The null value.
+    Null,
+    /// A boolean value.
+    Bool(bool),
+    /// A number value. Numbers that are not finite are encoded as null.
+    Number(f64),
+    /// A string value.
+    String(String),
+    /// An array of values.
+    Array(Vec<JsonValue>),
+    /// An object with values keyed by name, which are encoded in key order.
+    Object(BTreeMap<String, JsonValue>),
+}
+
+impl JsonValue {
+    /// Encodes the value as compact JSON text.
+    pub fn encode(&self) -> String {
+        let mut output = String::new();
+        self.encode_into(&mut output);
+        output
+    }
+
+    /// Appends the JSON text of the value to `output`.
+    fn encode_into(&self, output: &mut String) {
+        match self {
+            JsonValue::Null => output.push_str("null"),
+            JsonValue::Bool(value) => output.push_str(if *value { "true" } else { "false" }),
+            JsonValue::Number(value) if !value.is_finite() => output.push_str("null"),
+            JsonValue::Number(value) => output.push_str(&format!("{}", value)),
+            JsonValue::String(value) => encode_string(value, output),
+            JsonValue::Array(items) => {
+                output.push('[');
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        output.push(',');
+                    }
+                    item.encode_into(output);
+                }
+                output.push(']');
+            }
+            JsonValue::Object(fields) => {
+                output.push('{');
+                for (index, (key, value)) in fields.iter().enumerate() {
+                    if index > 0 {
+                        output.push(',');
+                    }
+                    encode_string(key, output);
+                    output.push(':');
+                    value.encode_into(output);
+                }
+                output.push('}');
+            }
+        }
+    }
+}
+
+/// Appends the `value` to `output` as a quoted JSON string, escaping it as needed.
+fn encode_string(value: &str, output: &mut String) {
+    output.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(output, "\\u{:04x}", c as u32);
+            }
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn values_are_encoded() {
+        let mut fields = BTreeMap::new();
+        fields.insert("status".to_string(), JsonValue::String("ok".to_string()));
+        fields.insert("duration".to_string(), JsonValue::Number(1.5));
+        fields.insert(
+            "items".to_string(),
+            JsonValue::Array(vec![
+                JsonValue::Bool(true),
+                JsonValue::Null,
+                JsonValue::Number(3.0),
+                JsonValue::Number(f64::NAN),
+            ]),
+        );
+        assert_eq!(
+            JsonValue::Object(fields).encode(),
+            r#"{"duration":1.5,"items":[true,null,3,null],"status":"ok"}"#
+        );
+    }
+
+    #[test]
+    fn strings_are_escaped() {
+        assert_eq!(
+            JsonValue::String("a \"b\"\\c\n\u{1}é".to_string()).encode(),
+            r#""a \"b\"\\c\n\u0001é""#
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use alloc::string::{String, ToString};
+
+/// The offset of the `HdrS` magic in the x86 boot protocol setup header.
+const BZIMAGE_HEADER_MAGIC_OFFSET: usize = 0x202;
+
+/// The magic value of the x86 boot protocol setup header.
+const BZIMAGE_HEADER_MAGIC: &[u8] = b"HdrS";
+
+/// The offset of the boot protocol version in the x86 boot protocol setup header.
+const BZIMAGE_PROTOCOL_VERSION_OFFSET: usize = 0x206;
+
+/// The offset of the kernel version string pointer in the x86 boot protocol setup header.
+const BZIMAGE_KERNEL_VERSION_OFFSET: usize = 0x20E;
+
+/// The base that the kernel version string pointer is relative to.
+const BZIMAGE_KERNEL_VERSION_BASE: usize = 0x200;
+
+/// The oldest boot protocol version that provides the kernel version string pointer.
+const BZIMAGE_MINIMUM_PROTOCOL_VERSION: u16 = 0x200;
+
+/// The prefix of the Linux banner that is embedded in uncompressed kernel images.
+const LINUX_BANNER_PREFIX: &[u8] = b"Linux version ";
+
+/// The maximum length of a kernel version string.
+const MAXIMUM_VERSION_LENGTH: usize = 256;
+
+/// The length of the start of a kernel image that [kernel_version] needs to read.
+/// It covers the furthest kernel version string the bzImage setup header can point to, so only
+/// this much of a kernel has to be read. The Linux banner is only found if it is within it.
+pub const KERNEL_VERSION_PREFIX_LENGTH: usize =
+    BZIMAGE_KERNEL_VERSION_BASE + u16::MAX as usize + MAXIMUM_VERSION_LENGTH;
+
+/// Extracts the first whitespace-delimited token from `bytes`, stopping at a null byte.
+/// The token must begin with a digit to be considered a kernel version.
+fn version_token(bytes: &[u8]) -> Option<String> {
+    let bytes = &bytes[..bytes.len().min(MAXIMUM_VERSION_LENGTH)];
+    let end = bytes
+        .iter()
+        .position(|byte| *byte == 0 || byte.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    let token = core::str::from_utf8(&bytes[..end]).ok()?;
+    if !token.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(token.to_string())
+}
+
+/// Extracts the kernel version from the x86 boot protocol setup header of a bzImage.
+fn bzimage_version(image: &[u8]) -> Option<String> {
+    // Check that the image has the setup header magic.
+    let magic = image.get(BZIMAGE_HEADER_MAGIC_OFFSET..BZIMAGE_HEADER_MAGIC_OFFSET + 4)?;
+    if magic != BZIMAGE_HEADER_MAGIC {
+        return None;
+    }
+
+    // The kernel version pointer is only available in boot protocol 2.00 and later.
+    let protocol = u16::from_le_bytes(
+        image
+            .get(BZIMAGE_PROTOCOL_VERSION_OFFSET..BZIMAGE_PROTOCOL_VERSION_OFFSET + 2)?
+            .try_into()
+            .ok()?,
+    );
+    if protocol < BZIMAGE_MINIMUM_PROTOCOL_VERSION {
+        return None;
+    }
+
+    // The pointer is relative to the start of the setup header, and zero means no version.
+    let pointer = u16::from_le_bytes(
+        image
+            .get(BZIMAGE_KERNEL_VERSION_OFFSET..BZIMAGE_KERNEL_VERSION_OFFSET + 2)?
+            .try_into()
+            .ok()?,
+    ) as usize;
+    if pointer == 0 {
+        return None;
+    }
+    version_token(image.get(pointer + BZIMAGE_KERNEL_VERSION_BASE..)?)
+}
+
+/// Extracts the kernel version from the Linux banner embedded in an uncompressed image.
+/// This covers arm64 and RISC-V Image files, which carry no version in their header.
+fn banner_version(image: &[u8]) -> Option<String> {
+    image
+        .windows(LINUX_BANNER_PREFIX.len())
+        .enumerate()
+        .filter(|(_, window)| *window == LINUX_BANNER_PREFIX)
+        .find_map(|(index, _)| version_token(&image[index + LINUX_BANNER_PREFIX.len()..]))
+}
+
+/// Extracts the kernel version string from a Linux kernel `image`.
+/// The x86 bzImage setup header is used if present, otherwise the Linux banner is searched,
+/// which works for uncompressed arm64 Image files. Compressed images are not supported.
+/// Returns [None] if no kernel version could be found.
+pub fn kernel_version(image: &[u8]) -> Option<String> {
+    bzimage_version(image).or_else(|| banner_version(image))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn bzimage(protocol: u16, version: &[u8]) -> alloc::vec::Vec<u8> {
+        let mut image = vec![0u8; 0x400];
+        image[0x202..0x206].copy_from_slice(b"HdrS");
+        image[0x206..0x208].copy_from_slice(&protocol.to_le_bytes());
+        image[0x20E..0x210].copy_from_slice(&0x100u16.to_le_bytes());
+        image[0x300..0x300 + version.len()].copy_from_slice(version);
+        image
+    }
+
+    #[test]
+    fn bzimage_version_is_extracted() {
+        let image = bzimage(0x20F, b"6.12.1-arch1-1 (linux@archlinux) #1 SMP\0");
+        assert_eq!(kernel_version(&image).as_deref(), Some("6.12.1-arch1-1"));
+    }
+
+    #[test]
+    fn bzimage_version_is_within_prefix() {
+        let mut image = vec![0u8; KERNEL_VERSION_PREFIX_LENGTH + 0x1000];
+        image[0x202..0x206].copy_from_slice(b"HdrS");
+        image[0x206..0x208].copy_from_slice(&0x20Fu16.to_le_bytes());
+        image[0x20E..0x210].copy_from_slice(&u16::MAX.to_le_bytes());
+        let version = 0x200 + u16::MAX as usize;
+        image[version..version + 7].copy_from_slice(b"6.12.1\0");
+        image.truncate(KERNEL_VERSION_PREFIX_LENGTH);
+        assert_eq!(kernel_version(&image).as_deref(), Some("6.12.1"));
+    }
+
+    #[test]
+    fn bzimage_old_protocol_is_ignored() {
+        let image = bzimage(0x1FF, b"6.12.1\0");
+        assert_eq!(kernel_version(&image), None);
+    }
+
+    #[test]
+    fn banner_version_is_extracted() {
+        let mut image = vec![0u8; 0x100];
+        image[0x38..0x3C].copy_from_slice(b"ARMd");
+        image.extend_from_slice(b"Linux version %s\0");
+        image.extend_from_slice(b"Linux version 6.1.0-13-arm64 (debian-kernel) #1 SMP\0");
+        assert_eq!(kernel_version(&image).as_deref(), Some("6.1.0-13-arm64"));
+    }
+
+    #[test]
+    fn unknown_image_has_no_version() {
+        assert_eq!(kernel_version(&[0u8; 0x1000]), None);
+        assert_eq!(kernel_version(&[]), None);
+    }
+}
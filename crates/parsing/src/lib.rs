@@ -1,13 +1,144 @@
 #![no_std]
 extern crate alloc;
 
+use alloc::boxed::Box;
 use alloc::collections::BTreeMap;
 use alloc::format;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use core::cmp::Reverse;
+use regex_automata::meta::{BuildError, Regex};
 use sha2::{Digest, Sha256};
 
+/// Atomic replacement of files.
+pub mod atomic;
+
+/// BMP image decoding.
+pub mod bmp;
+
+/// Backups of firmware boot variables.
+pub mod boot_variables;
+
+/// Read-only btrfs filesystems with subvolumes.
+pub mod btrfs;
+
+/// X.509 certificate and signature list encoding.
+pub mod certificates;
+
+/// Conditions for `when` clauses.
+pub mod condition;
+
+/// Date and time formatting.
+pub mod datetime;
+
+/// DHCP option parsing and formatting.
+pub mod dhcp;
+
+/// Disk identity parsing.
+pub mod disk;
+
+/// Read-only ext2, ext3, and ext4 filesystems.
+pub mod ext4;
+
+/// Read-only filesystem access.
+pub mod filesystem;
+
+/// GUID partition table parsing.
+pub mod gpt;
+
+/// iSCSI name validation.
+pub mod iscsi;
+
+/// Read-only ISO9660 filesystems and El Torito boot catalogs.
+pub mod iso9660;
+
+/// JSON encoding.
+pub mod json;
+
+/// Linux kernel image parsing.
+pub mod kernel;
+
+/// EFI load option parsing.
+pub mod load_option;
+
+/// Signed manifests of file digests.
+pub mod manifest;
+
+/// Master boot record parsing.
+pub mod mbr;
+
+/// Command-line option tokenizing.
+pub mod options;
+
+/// Filesystem path manipulation.
+pub mod path;
+
+/// PE/COFF image parsing.
+pub mod pe;
+
+/// Layered value scopes with namespaces.
+pub mod scope;
+
+/// Detached signature verification.
+pub mod signature;
+
+/// SMBIOS table parsing.
+pub mod smbios;
+
+/// Read-only squashfs filesystems.
+pub mod squashfs;
+
+/// Braced template expressions.
+pub mod template;
+
+/// HTTP URL parsing.
+pub mod url;
+
+/// Age tracking of variables for garbage collection.
+pub mod variable_stamps;
+
+/// The escape sequence that produces a literal `$` in stamped text.
+/// Escaped dollar signs are preserved by [stamp_values] and removed by [unescape_values].
+pub const ESCAPED_DOLLAR: &str = "$$";
+
+/// Escapes every `$` in `text`, so it stays literal when `text` is stored as a value and
+/// stamped. This should be used for values that come from outside the configuration, like
+/// filesystem labels, which must not be expanded as templates.
+pub fn escape_values(text: &str) -> String {
+    text.replace('$', ESCAPED_DOLLAR)
+}
+
+/// Replaces every `$KEY` reference to `key` in `text` with `value`.
+/// Escaped dollar signs are skipped, so `$$KEY` is not a reference.
+fn replace_key(text: &str, key: &str, value: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        if let Some(after_escape) = after.strip_prefix('$') {
+            // Preserve the escape sequence so it can be unescaped later.
+            result.push_str(ESCAPED_DOLLAR);
+            rest = after_escape;
+        } else if let Some(after_key) = after.strip_prefix(key) {
+            result.push_str(value);
+            rest = after_key;
+        } else {
+            result.push('$');
+            rest = after;
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Removes the escaping from stamped `text`, turning every `$$` into a literal `$`.
+/// This should only be done once the text is fully stamped, as an unescaped `$`
+/// would be treated as a value reference by further stamping.
+pub fn unescape_values(text: &str) -> String {
+    text.replace(ESCAPED_DOLLAR, "$")
+}
+
 /// Stamps the `text` value with the specified `values` map. The returned value indicates
 /// whether the `text` has been changed and the value that was stamped and changed.
 ///
@@ -18,6 +149,12 @@ use sha2::{Digest, Sha256};
 /// - Each follow-up iteration acts upon the last iterations result.
 /// - We keep track if the text changes during the replacement.
 /// - We return both whether the text changed during any iteration and the final result.
+///
+/// An escaped dollar sign `$$` is never treated as a reference and is preserved in the result.
+/// Use [unescape_values] to produce the final text.
+///
+/// After the plain keys are stamped, braced expressions like `${key:-default}` are stamped.
+/// See [template::stamp_expressions] for the supported expressions.
 pub fn stamp_values(values: &BTreeMap<String, String>, text: impl AsRef<str>) -> (bool, String) {
     let mut result = text.as_ref().to_string();
     let mut did_change = false;
@@ -40,13 +177,16 @@ pub fn stamp_values(values: &BTreeMap<String, String>, text: impl AsRef<str>) ->
             unreachable!("keys iterated over is collected on a map that cannot be modified");
         };
 
-        let next_result = result.replace(&format!("${key}"), value);
+        let next_result = replace_key(&result, key, value);
         if result != next_result {
             did_change = true;
         }
         result = next_result;
     }
-    (did_change, result)
+
+    // Stamp the braced expressions, which can provide defaults for missing values.
+    let (changed, result) = template::stamp_expressions(values, &result);
+    (did_change || changed, result)
 }
 
 /// Builds out multiple generations of `input` based on a matrix style.
@@ -149,6 +289,74 @@ pub fn initramfs_candidates<'a>(
         .map(move |prefix| format!("{}{}", prefix, suffix))
 }
 
+/// The separator between the items of a list value.
+/// Values are always strings, so lists are stored with one item per line.
+pub const LIST_SEPARATOR: char = '\n';
+
+/// Split the list `value` into its items, ignoring empty items and surrounding whitespace.
+pub fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(LIST_SEPARATOR)
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+}
+
+/// Join the `items` into a list value.
+pub fn join_list<T: AsRef<str>>(items: impl Iterator<Item = T>) -> String {
+    let mut separator = [0u8; 4];
+    items
+        .map(|item| item.as_ref().to_string())
+        .collect::<Vec<_>>()
+        .join(LIST_SEPARATOR.encode_utf8(&mut separator))
+}
+
+/// Check whether `name` matches the wildcard `pattern`, ignoring ASCII case.
+/// A `*` matches any sequence of characters and a `?` matches a single character.
+pub fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+
+    // The position in the pattern and name, and the last star to backtrack to.
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p].eq_ignore_ascii_case(&name[n])) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            // Let the last star consume one more character and try again.
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+
+    // Any trailing stars match the empty remainder.
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Searches `text` for the first match of the regular expression `pattern`.
+/// If the pattern contains a capture group, the first capture group is returned,
+/// otherwise the entire match is returned. If there is no match, [None] is returned.
+pub fn regex_capture(pattern: &str, text: &str) -> Result<Option<String>, Box<BuildError>> {
+    let regex = Regex::new(pattern).map_err(Box::new)?;
+    let mut captures = regex.create_captures();
+    regex.captures(text, &mut captures);
+
+    // Use the first capture group if there is one, otherwise use the entire match.
+    let span = if regex.captures_len() > 1 {
+        captures.get_group(1)
+    } else {
+        captures.get_match().map(|found| found.span())
+    };
+    Ok(span.map(|span| text[span].to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -209,6 +417,68 @@ mod tests {
         assert_eq!(result, "");
     }
 
+    #[test]
+    fn stamp_braced_default() {
+        let values = map(&[("name", "world")]);
+        let (changed, result) = stamp_values(&values, "$name ${name} ${missing:-default}");
+        assert!(changed);
+        assert_eq!(result, "world world default");
+    }
+
+    #[test]
+    fn list_values_round_trip() {
+        let list = join_list(["a", "b c", "d"].iter());
+        assert_eq!(list, "a\nb c\nd");
+        assert_eq!(split_list(&list).collect::<Vec<_>>(), ["a", "b c", "d"]);
+        assert_eq!(split_list("\n a \n\n").collect::<Vec<_>>(), ["a"]);
+        assert_eq!(split_list("").count(), 0);
+    }
+
+    #[test]
+    fn wildcard_matches() {
+        assert!(wildcard_match("*", "anything"));
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("vmlinuz-*", "VMLINUZ-6.12"));
+        assert!(wildcard_match("*.conf", "entry.conf"));
+        assert!(wildcard_match("a?c*d", "abcxxd"));
+        assert!(wildcard_match("*a*b", "xaxxab"));
+        assert!(!wildcard_match("*.conf", "entry.conf.bak"));
+        assert!(!wildcard_match("a?c", "ac"));
+    }
+
+    #[test]
+    fn stamp_preserves_escaped_dollars() {
+        let values = map(&[("name", "world")]);
+        let (changed, result) = stamp_values(&values, "$$name $${name} $$$name ${name}$$");
+        assert!(changed);
+        assert_eq!(result, "$$name $${name} $$world world$$");
+        assert_eq!(unescape_values(&result), "$name ${name} $world world$");
+    }
+
+    #[test]
+    fn escaped_values_are_not_expanded() {
+        let label = escape_values("$root ${root} $$");
+        let values = map(&[("label", label.as_str()), ("root", "/")]);
+        let (_, result) = stamp_values(&values, "$label ${label} ${upper:label}");
+        assert_eq!(
+            unescape_values(&result),
+            "$root ${root} $$ $root ${root} $$ $ROOT ${ROOT} $$"
+        );
+        let (_, result) = stamp_values(&values, "${substr:label:0:5} ${sha256:label}");
+        assert_eq!(
+            unescape_values(&result),
+            alloc::format!("$root {}", unique_hash("$root ${root} $$"))
+        );
+    }
+
+    #[test]
+    fn stamp_escaped_only_is_unchanged() {
+        let values = map(&[("name", "world")]);
+        let (changed, result) = stamp_values(&values, "cost: $$5");
+        assert!(!changed);
+        assert_eq!(unescape_values(&result), "cost: $5");
+    }
+
     #[test]
     fn stamp_empty_map_returns_unchanged() {
         let values = map(&[]);
@@ -400,4 +670,27 @@ mod tests {
         let candidates: Vec<_> = initramfs_candidates("-6.1.0", &[]).collect();
         assert!(candidates.is_empty());
     }
+
+    #[test]
+    fn regex_capture_returns_first_group() {
+        let result = regex_capture(r"(?m)^SLOT=(\w+)$", "VERSION=1\nSLOT=b\n").unwrap();
+        assert_eq!(result.as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn regex_capture_without_group_returns_match() {
+        let result = regex_capture(r"\d+\.\d+", "version 6.12 stable").unwrap();
+        assert_eq!(result.as_deref(), Some("6.12"));
+    }
+
+    #[test]
+    fn regex_capture_no_match_returns_none() {
+        let result = regex_capture(r"SLOT=(\w+)", "VERSION=1").unwrap();
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn regex_capture_invalid_pattern_is_error() {
+        assert!(regex_capture(r"(", "text").is_err());
+    }
 }
@@ -0,0 +1,333 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// The offset of the description in an EFI_LOAD_OPTION structure.
+/// The description follows the 32-bit attributes and the 16-bit file path list length.
+const LOAD_OPTION_DESCRIPTION_OFFSET: usize = 6;
+
+/// Formats the variable name of the boot option with the specified `number`, like `Boot0001`.
+pub fn boot_option_name(number: u16) -> String {
+    alloc::format!("Boot{:04X}", number)
+}
+
+/// Parses the description of the EFI_LOAD_OPTION structure in `data`.
+/// This is the human-readable name of a firmware boot entry.
+/// Returns [None] if the load option is malformed or the description is empty.
+pub fn load_option_description(data: &[u8]) -> Option<String> {
+    // The description is a null-terminated UTF-16 string.
+    let units = data
+        .get(LOAD_OPTION_DESCRIPTION_OFFSET..)?
+        .chunks_exact(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+        .take_while(|unit| *unit != 0);
+    let description = char::decode_utf16(units)
+        .collect::<Result<String, _>>()
+        .ok()?;
+    if description.is_empty() {
+        None
+    } else {
+        Some(description)
+    }
+}
+
+/// The attribute of a load option that marks it as active, so the firmware can boot it.
+pub const LOAD_OPTION_ACTIVE: u32 = 0x1;
+
+/// The device path type of media nodes.
+const MEDIA_DEVICE_PATH: u8 = 0x04;
+
+/// The device path subtype of hard drive media nodes.
+const MEDIA_HARD_DRIVE: u8 = 0x01;
+
+/// The device path type of end nodes.
+const END_DEVICE_PATH: u8 = 0x7F;
+
+/// A parsed EFI_LOAD_OPTION structure, which describes a firmware boot entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadOption {
+    /// The attributes of the load option, like [LOAD_OPTION_ACTIVE].
+    pub attributes: u32,
+    /// The human-readable name of the load option.
+    pub description: String,
+    /// The device path list of the image to boot, in its binary form.
+    pub file_path: Vec<u8>,
+    /// The data passed to the image as its load options.
+    pub optional_data: Vec<u8>,
+}
+
+impl LoadOption {
+    /// Parses the EFI_LOAD_OPTION structure in `data`.
+    /// Returns [None] if the load option is malformed.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let attributes = u32::from_le_bytes(data.get(..4)?.try_into().ok()?);
+        let file_path_length = u16::from_le_bytes(data.get(4..6)?.try_into().ok()?) as usize;
+
+        // The description is a null-terminated UTF-16 string.
+        let mut units = Vec::new();
+        let mut offset = LOAD_OPTION_DESCRIPTION_OFFSET;
+        loop {
+            let unit = u16::from_le_bytes(data.get(offset..offset + 2)?.try_into().ok()?);
+            offset += 2;
+            if unit == 0 {
+                break;
+            }
+            units.push(unit);
+        }
+        let description = String::from_utf16(&units).ok()?;
+
+        let file_path = data.get(offset..offset.checked_add(file_path_length)?)?;
+        Some(Self {
+            attributes,
+            description,
+            file_path: file_path.to_vec(),
+            optional_data: data[offset + file_path_length..].to_vec(),
+        })
+    }
+
+    /// Encodes the load option as an EFI_LOAD_OPTION structure.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&self.attributes.to_le_bytes());
+        data.extend_from_slice(&(self.file_path.len() as u16).to_le_bytes());
+        for unit in self.description.encode_utf16().chain([0]) {
+            data.extend_from_slice(&unit.to_le_bytes());
+        }
+        data.extend_from_slice(&self.file_path);
+        data.extend_from_slice(&self.optional_data);
+        data
+    }
+
+    /// Checks whether the load option is marked as active.
+    pub fn is_active(&self) -> bool {
+        self.attributes & LOAD_OPTION_ACTIVE != 0
+    }
+}
+
+/// Acquires the part of the binary device `path` that starts at its hard drive node.
+/// Firmware boot entries usually use this short form, which identifies the partition by its
+/// signature instead of the controller it is attached to. Returns the whole path if it has
+/// no hard drive node, or [None] if the path is malformed.
+pub fn short_form_device_path(path: &[u8]) -> Option<&[u8]> {
+    let mut offset = 0;
+    loop {
+        let header = path.get(offset..offset + 4)?;
+        let length = u16::from_le_bytes([header[2], header[3]]) as usize;
+        if length < 4 {
+            return None;
+        }
+        match (header[0], header[1]) {
+            (MEDIA_DEVICE_PATH, MEDIA_HARD_DRIVE) => return path.get(offset..),
+            (END_DEVICE_PATH, _) => return Some(path),
+            _ => offset += length,
+        }
+    }
+}
+
+/// Parses the `BootOrder` variable `data` into boot option numbers.
+pub fn parse_boot_order(data: &[u8]) -> Vec<u16> {
+    data.chunks_exact(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+        .collect()
+}
+
+/// Encodes boot option `numbers` as the data of the `BootOrder` variable.
+pub fn encode_boot_order(numbers: &[u16]) -> Vec<u8> {
+    numbers
+        .iter()
+        .flat_map(|number| number.to_le_bytes())
+        .collect()
+}
+
+/// Parses the boot option number from a variable `name` like `Boot0001`.
+/// Returns [None] if the name is not the name of a boot option.
+pub fn parse_boot_option_name(name: &str) -> Option<u16> {
+    let digits = name.strip_prefix("Boot")?;
+    if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u16::from_str_radix(digits, 16).ok()
+}
+
+/// The changes needed to register a boot option with the firmware.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Registration {
+    /// The number of the boot option to write.
+    pub number: u16,
+    /// The boot option to write, or [None] if the existing boot option is already correct.
+    pub option: Option<LoadOption>,
+    /// The boot order to write, or [None] if the boot order is already correct.
+    pub boot_order: Option<Vec<u16>>,
+}
+
+/// Plans the registration of the `desired` boot option, given the `existing` boot options
+/// and the current `boot_order`.
+///
+/// A boot option that boots the same image, compared by the short form of the device path,
+/// is reused and repaired if it differs. Otherwise, the lowest free number is used.
+/// A boot option that is missing from the boot order is placed first, while one that is
+/// already in the boot order keeps its position, as it might have been chosen by the user.
+/// Returns [None] if there is no free boot option number.
+pub fn plan_registration(
+    existing: &[(u16, LoadOption)],
+    boot_order: &[u16],
+    desired: &LoadOption,
+) -> Option<Registration> {
+    let desired_path = short_form_device_path(&desired.file_path);
+    let found = existing.iter().find(|(_, option)| {
+        desired_path.is_some() && short_form_device_path(&option.file_path) == desired_path
+    });
+
+    let (number, option) = match found {
+        Some((number, option)) => {
+            let repaired = (option != desired).then(|| desired.clone());
+            (*number, repaired)
+        }
+        None => {
+            let number = (0..=u16::MAX).find(|number| {
+                !existing.iter().any(|(used, _)| used == number) && !boot_order.contains(number)
+            })?;
+            (number, Some(desired.clone()))
+        }
+    };
+
+    let boot_order = (!boot_order.contains(&number)).then(|| {
+        let mut order = Vec::with_capacity(boot_order.len() + 1);
+        order.push(number);
+        order.extend_from_slice(boot_order);
+        order
+    });
+
+    Some(Registration {
+        number,
+        option,
+        boot_order,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn load_option(description: &str) -> Vec<u8> {
+        let mut data = vec![1, 0, 0, 0, 4, 0];
+        data.extend(
+            description
+                .encode_utf16()
+                .flat_map(|unit| unit.to_le_bytes()),
+        );
+        data.extend_from_slice(&[0, 0]);
+        // The file path list follows the description.
+        data.extend_from_slice(&[0x7F, 0xFF, 4, 0]);
+        data
+    }
+
+    #[test]
+    fn boot_option_name_is_formatted() {
+        assert_eq!(boot_option_name(1), "Boot0001");
+        assert_eq!(boot_option_name(0xBEEF), "BootBEEF");
+    }
+
+    #[test]
+    fn load_option_description_is_parsed() {
+        let data = load_option("Sprout");
+        assert_eq!(load_option_description(&data).as_deref(), Some("Sprout"));
+    }
+
+    /// Builds a binary device path of a hard drive node behind a controller node,
+    /// followed by a file path node of `file`.
+    fn device_path(controller: u8, file: &str) -> Vec<u8> {
+        let mut path = vec![0x01, 0x01, 6, 0, controller, 0];
+        let mut hard_drive = vec![0x04, 0x01, 42, 0];
+        hard_drive.resize(42, 0xAB);
+        path.extend(hard_drive);
+        let name = file
+            .encode_utf16()
+            .chain([0])
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect::<Vec<_>>();
+        path.extend_from_slice(&[0x04, 0x04]);
+        path.extend_from_slice(&(name.len() as u16 + 4).to_le_bytes());
+        path.extend(name);
+        path.extend_from_slice(&[0x7F, 0xFF, 4, 0]);
+        path
+    }
+
+    fn sprout(file_path: Vec<u8>) -> LoadOption {
+        LoadOption {
+            attributes: LOAD_OPTION_ACTIVE,
+            description: "Sprout".into(),
+            file_path,
+            optional_data: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn load_option_round_trips() {
+        let option = LoadOption {
+            optional_data: vec![1, 2, 3],
+            ..sprout(device_path(1, "\\EFI\\sprout.efi"))
+        };
+        assert_eq!(LoadOption::parse(&option.encode()), Some(option));
+        assert_eq!(
+            LoadOption::parse(&load_option("x")).unwrap().description,
+            "x"
+        );
+        assert_eq!(LoadOption::parse(&[1, 0, 0, 0, 40, 0, 0, 0]), None);
+    }
+
+    #[test]
+    fn short_form_starts_at_hard_drive() {
+        let path = device_path(1, "\\a.efi");
+        assert_eq!(short_form_device_path(&path), Some(&path[6..]));
+        assert_eq!(
+            short_form_device_path(&[0x7F, 0xFF, 4, 0]),
+            Some(&[0x7F, 0xFF, 4, 0][..])
+        );
+        assert_eq!(short_form_device_path(&[0x01, 0x01, 0, 0]), None);
+    }
+
+    #[test]
+    fn boot_order_and_names_are_parsed() {
+        assert_eq!(
+            parse_boot_order(&encode_boot_order(&[3, 0x1000])),
+            vec![3, 0x1000]
+        );
+        assert_eq!(parse_boot_option_name("Boot00A1"), Some(0xA1));
+        assert_eq!(parse_boot_option_name("BootOrder"), None);
+        assert_eq!(parse_boot_option_name("Boot001"), None);
+    }
+
+    #[test]
+    fn registration_creates_missing_option_first() {
+        let other = sprout(device_path(1, "\\EFI\\other.efi"));
+        let plan =
+            plan_registration(&[(0, other)], &[0, 1], &sprout(device_path(1, "\\s.efi"))).unwrap();
+        assert_eq!(plan.number, 2);
+        assert!(plan.option.is_some());
+        assert_eq!(plan.boot_order, Some(vec![2, 0, 1]));
+    }
+
+    #[test]
+    fn registration_reuses_and_repairs_matching_option() {
+        // The existing option uses a different controller, but the same partition and file.
+        let mut existing = sprout(device_path(2, "\\s.efi"));
+        let desired = sprout(device_path(1, "\\s.efi"));
+        existing.file_path = desired.file_path[6..].to_vec();
+        let plan = plan_registration(&[(5, existing.clone())], &[1, 5], &desired).unwrap();
+        assert_eq!(plan.number, 5);
+        assert_eq!(plan.option, Some(desired.clone()));
+        assert_eq!(plan.boot_order, None);
+
+        // An option that is already correct is left alone.
+        let plan = plan_registration(&[(5, desired.clone())], &[5], &desired).unwrap();
+        assert_eq!((plan.option, plan.boot_order), (None, None));
+    }
+
+    #[test]
+    fn malformed_load_option_has_no_description() {
+        assert_eq!(load_option_description(&[1, 0, 0]), None);
+        assert_eq!(load_option_description(&load_option("")), None);
+    }
+}
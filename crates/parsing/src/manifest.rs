@@ -0,0 +1,100 @@
+use crate::path::normalize;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+
+/// The size of a SHA-256 digest in bytes.
+const DIGEST_SIZE: usize = 32;
+
+/// A manifest of the SHA-256 digests of the files of a filesystem.
+///
+/// The manifest is text with a line for every file, in the format of `sha256sum`: the digest
+/// as hex, two spaces, and the path of the file from the root of the filesystem. Paths may use
+/// either separator, and a leading `./` or `*` is ignored. Blank lines and lines starting with
+/// `#` are ignored. Paths are matched ignoring ASCII case, like FAT filesystems do.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    /// The digests of the files, keyed by their normalized path.
+    digests: BTreeMap<String, [u8; DIGEST_SIZE]>,
+}
+
+/// Normalizes the `path` of a file into the key of the manifest.
+fn key(path: &str) -> String {
+    normalize(&format!("\\{}", path)).to_ascii_lowercase()
+}
+
+impl Manifest {
+    /// Parses the manifest `text`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut digests = BTreeMap::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (digest, path) = line
+                .split_once(' ')
+                .ok_or_else(|| format!("manifest line {} has no path", index + 1))?;
+            let mut bytes = [0u8; DIGEST_SIZE];
+            hex::decode_to_slice(digest, &mut bytes)
+                .map_err(|_| format!("manifest line {} has an invalid digest", index + 1))?;
+            let path = path.strip_prefix([' ', '*']).unwrap_or(path);
+            if path.is_empty() {
+                return Err(format!("manifest line {} has no path", index + 1));
+            }
+            if digests.insert(key(path), bytes).is_some() {
+                return Err(format!(
+                    "manifest line {} repeats the path {}",
+                    index + 1,
+                    path
+                ));
+            }
+        }
+        Ok(Self { digests })
+    }
+
+    /// The digest of the file at the `path` from the root of the filesystem,
+    /// or [None] if the file is not in the manifest.
+    pub fn digest(&self, path: &str) -> Option<&[u8; DIGEST_SIZE]> {
+        self.digests.get(&key(path))
+    }
+
+    /// The number of files in the manifest.
+    pub fn len(&self) -> usize {
+        self.digests.len()
+    }
+
+    /// Whether the manifest has no files.
+    pub fn is_empty(&self) -> bool {
+        self.digests.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KERNEL: &str = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03";
+
+    #[test]
+    fn manifests_are_parsed() {
+        let text = format!(
+            "# generated with sha256sum\n\n{}  ./EFI/Linux/vmlinuz\r\n{} *sprout.toml\n",
+            KERNEL, KERNEL
+        );
+        let manifest = Manifest::parse(&text).unwrap();
+        assert_eq!(manifest.len(), 2);
+        let digest = manifest.digest("\\efi\\linux\\VMLINUZ").unwrap();
+        assert_eq!(hex::encode(digest), KERNEL);
+        assert!(manifest.digest("/sprout.toml").is_some());
+        assert!(manifest.digest("\\EFI\\vmlinuz").is_none());
+    }
+
+    #[test]
+    fn invalid_manifests_are_rejected() {
+        assert!(Manifest::parse("abc  \\vmlinuz").is_err());
+        assert!(Manifest::parse(KERNEL).is_err());
+        let repeated = format!("{}  \\vmlinuz\n{}  \\VMLINUZ\n", KERNEL, KERNEL);
+        assert!(Manifest::parse(&repeated).is_err());
+    }
+}
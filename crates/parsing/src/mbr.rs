@@ -0,0 +1,130 @@
+use alloc::vec::Vec;
+
+/// The boot signature at the end of an MBR.
+const MBR_BOOT_SIGNATURE: [u8; 2] = [0x55, 0xaa];
+
+/// The offset of the disk signature in an MBR.
+const MBR_DISK_SIGNATURE_OFFSET: usize = 440;
+
+/// The offset of the partition table in an MBR.
+const MBR_PARTITION_TABLE_OFFSET: usize = 446;
+
+/// The size of an MBR partition record.
+const MBR_PARTITION_RECORD_SIZE: usize = 16;
+
+/// The number of primary partition records in an MBR.
+const MBR_PARTITION_COUNT: usize = 4;
+
+/// The partition type of a protective MBR, which indicates the disk is partitioned with GPT.
+pub const MBR_PROTECTIVE_TYPE: u8 = 0xee;
+
+/// A master boot record, which is stored at LBA 0 of a disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mbr {
+    /// The disk signature.
+    pub disk_signature: u32,
+    /// The used primary partitions.
+    pub partitions: Vec<MbrPartition>,
+}
+
+impl Mbr {
+    /// Checks whether this is a protective MBR in front of a GPT.
+    pub fn is_protective(&self) -> bool {
+        self.partitions
+            .iter()
+            .any(|partition| partition.partition_type == MBR_PROTECTIVE_TYPE)
+    }
+}
+
+/// A used primary partition record of an MBR.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MbrPartition {
+    /// The partition number, which is the one-based index of the record in the table.
+    pub number: u32,
+    /// The partition type byte, like `0x83` for Linux or `0xef` for an EFI system partition.
+    pub partition_type: u8,
+    /// Whether the partition is marked bootable.
+    pub bootable: bool,
+    /// The first LBA of the partition.
+    pub first_lba: u32,
+    /// The number of blocks in the partition.
+    pub block_count: u32,
+}
+
+/// Parses the MBR in `sector`, which is the first block read from LBA 0 of a disk.
+/// Only primary partitions are reported. Returns [None] if the boot signature is missing.
+pub fn parse_mbr(sector: &[u8]) -> Option<Mbr> {
+    if sector.get(510..512)? != MBR_BOOT_SIGNATURE {
+        return None;
+    }
+
+    let disk_signature = u32::from_le_bytes(
+        sector
+            .get(MBR_DISK_SIGNATURE_OFFSET..MBR_DISK_SIGNATURE_OFFSET + 4)?
+            .try_into()
+            .ok()?,
+    );
+
+    let table = sector.get(
+        MBR_PARTITION_TABLE_OFFSET
+            ..MBR_PARTITION_TABLE_OFFSET + MBR_PARTITION_RECORD_SIZE * MBR_PARTITION_COUNT,
+    )?;
+    let partitions = table
+        .chunks_exact(MBR_PARTITION_RECORD_SIZE)
+        .enumerate()
+        // Unused records have a zero partition type.
+        .filter(|(_, record)| record[4] != 0)
+        .map(|(index, record)| MbrPartition {
+            number: index as u32 + 1,
+            partition_type: record[4],
+            bootable: record[0] == 0x80,
+            first_lba: u32::from_le_bytes([record[8], record[9], record[10], record[11]]),
+            block_count: u32::from_le_bytes([record[12], record[13], record[14], record[15]]),
+        })
+        .collect();
+
+    Some(Mbr {
+        disk_signature,
+        partitions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn record(sector: &mut [u8], index: usize, partition_type: u8, first_lba: u32) {
+        let offset = MBR_PARTITION_TABLE_OFFSET + index * MBR_PARTITION_RECORD_SIZE;
+        sector[offset] = 0x80;
+        sector[offset + 4] = partition_type;
+        sector[offset + 8..offset + 12].copy_from_slice(&first_lba.to_le_bytes());
+        sector[offset + 12..offset + 16].copy_from_slice(&1000u32.to_le_bytes());
+    }
+
+    #[test]
+    fn parse_mbr_partitions() {
+        let mut sector = vec![0u8; 512];
+        sector[440..444].copy_from_slice(&0xbe1afdfau32.to_le_bytes());
+        sector[510..512].copy_from_slice(&MBR_BOOT_SIGNATURE);
+        record(&mut sector, 0, 0xef, 63);
+        record(&mut sector, 2, 0x83, 2048);
+
+        let mbr = parse_mbr(&sector).expect("mbr should parse");
+        assert_eq!(mbr.disk_signature, 0xbe1afdfa);
+        assert_eq!(mbr.partitions.len(), 2);
+        assert_eq!(mbr.partitions[0].number, 1);
+        assert_eq!(mbr.partitions[0].partition_type, 0xef);
+        assert!(mbr.partitions[0].bootable);
+        assert_eq!(mbr.partitions[1].number, 3);
+        assert_eq!(mbr.partitions[1].first_lba, 2048);
+        assert_eq!(mbr.partitions[1].block_count, 1000);
+        assert!(!mbr.is_protective());
+    }
+
+    #[test]
+    fn reject_missing_boot_signature() {
+        assert_eq!(parse_mbr(&[0u8; 512]), None);
+        assert_eq!(parse_mbr(&[0u8; 16]), None);
+    }
+}
@@ -0,0 +1,116 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// Splits the command-line `text` into arguments.
+/// Arguments are split like a POSIX shell would, honoring quotes and escapes.
+/// If the text is not valid shell syntax, it is split on whitespace instead.
+pub fn split_args(text: &str) -> Vec<String> {
+    // Remove line continuations first, like a shell does, as shlex would otherwise
+    // produce an empty argument for a line continuation between arguments.
+    let text = text.replace("\\\n", "");
+
+    // Use shlex to parse the options.
+    // If shlex fails, we will perform a simple whitespace split.
+    shlex::split(&text).unwrap_or_else(|| {
+        text.split_ascii_whitespace()
+            .map(|string| string.to_string())
+            .collect::<Vec<_>>()
+    })
+}
+
+/// Removes the arguments that firmware passes in front of the actual options from `args`.
+///
+/// Some firmware adds arguments starting with unprintable characters or backticks,
+/// which are dropped. Then, if the first argument is not an option, it is assumed
+/// to be the path to the executable and is removed.
+pub fn strip_firmware_args(args: Vec<String>) -> Vec<String> {
+    // Correct firmware that may add invalid arguments at the start.
+    // Witnessed this on a Dell Precision 5690 when direct booting.
+    let mut args = args
+        .into_iter()
+        .skip_while(|arg| {
+            arg.chars()
+                .next()
+                // Filter out unprintable characters and backticks.
+                // Both of which have been observed in the wild.
+                .map(|c| c < 0x1f as char || c == '`')
+                .unwrap_or(false)
+        })
+        .collect::<Vec<_>>();
+
+    // If there is a first argument, check if it is not an option.
+    // If it is not, we will assume it is the path to the executable and remove it.
+    if let Some(arg) = args.first()
+        && !arg.starts_with('-')
+    {
+        args.remove(0);
+    }
+
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn split_args_honors_quotes() {
+        assert_eq!(
+            split_args("--config '\\EFI\\my config.toml' \"a b\""),
+            vec!["--config", "\\EFI\\my config.toml", "a b"]
+        );
+    }
+
+    #[test]
+    fn split_args_removes_line_continuations() {
+        assert_eq!(
+            split_args("--boot linux \\\n--force-menu \\\n"),
+            vec!["--boot", "linux", "--force-menu"]
+        );
+    }
+
+    #[test]
+    fn split_args_falls_back_to_whitespace() {
+        assert_eq!(split_args("--boot 'linux"), vec!["--boot", "'linux"]);
+    }
+
+    #[test]
+    fn strip_firmware_args_removes_executable_and_garbage() {
+        let args = vec![
+            "\u{1}".to_string(),
+            "`".to_string(),
+            "\\sprout.efi".to_string(),
+        ];
+        let args = [args, vec!["--boot".to_string(), "linux".to_string()]].concat();
+        assert_eq!(strip_firmware_args(args), vec!["--boot", "linux"]);
+        assert_eq!(
+            strip_firmware_args(vec!["--force-menu".to_string()]),
+            vec!["--force-menu"]
+        );
+        assert!(strip_firmware_args(Vec::new()).is_empty());
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn property_quoted_args_round_trip(
+            args in proptest::collection::vec("[aZ0 '\"\\\\$=\t-]{0,15}", 1..=4),
+        ) {
+            let joined = shlex::try_join(args.iter().map(String::as_str)).unwrap();
+            proptest::prop_assert_eq!(split_args(&joined), args);
+        }
+
+        #[test]
+        fn property_split_args_never_yields_empty_unquoted_args(
+            text in "[a '\"\\\\#\n`\u{1}]{0,15}",
+        ) {
+            let args = split_args(&text);
+            if !text.contains(['\'', '"']) {
+                proptest::prop_assert!(args.iter().all(|arg| !arg.is_empty()));
+            }
+            // Stripping firmware arguments only ever removes arguments from the front.
+            let stripped = strip_firmware_args(args.clone());
+            proptest::prop_assert!(args.ends_with(&stripped));
+        }
+    }
+}
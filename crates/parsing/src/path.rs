@@ -0,0 +1,247 @@
+use crate::wildcard_match;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// The separator between the components of a filesystem path.
+pub const SEPARATOR: char = '\\';
+
+/// Checks whether the text `node` is a device path node, like `HD(1,GPT,...)`.
+fn is_device_node(node: &str) -> bool {
+    node.contains('(') && node.ends_with(')') && !node.contains(['\\', '/'])
+}
+
+/// Splits the text `path` into its device root and the path inside the filesystem.
+/// The device root keeps its trailing `/`, so the parts can be concatenated again.
+///
+/// For example, `PciRoot(0x0)/HD(1,GPT,...)/\EFI\BOOT` is split into
+/// `PciRoot(0x0)/HD(1,GPT,...)/` and `\EFI\BOOT`.
+/// Paths without a device root produce an empty root.
+pub fn split_device_root(path: &str) -> (&str, &str) {
+    let mut end = 0;
+    for node in path.split_inclusive('/') {
+        let trimmed = node.strip_suffix('/').unwrap_or(node);
+        if !is_device_node(trimmed) {
+            break;
+        }
+        end += node.len();
+    }
+    path.split_at(end)
+}
+
+/// Normalizes the text `path`, keeping its device root if it has one.
+///
+/// Forward slashes are converted to backslashes, duplicate separators and `.` components
+/// are removed, and `..` components remove the component before them. A `..` can not
+/// go above the root of the filesystem. Paths with a device root are always absolute.
+pub fn normalize(path: &str) -> String {
+    let (root, path) = split_device_root(path);
+    let absolute = !root.is_empty() || path.starts_with(['\\', '/']);
+
+    let mut components: Vec<&str> = Vec::new();
+    for component in path.split(['\\', '/']) {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            component => components.push(component),
+        }
+    }
+
+    let mut normalized = root.to_string();
+    if absolute {
+        normalized.push(SEPARATOR);
+    }
+    normalized.push_str(&components.join("\\"));
+    normalized
+}
+
+/// Joins the text `path` onto the `base` directory and normalizes the result.
+/// If `path` has a device root, it replaces `base` entirely.
+/// An absolute `path` is joined onto the device root of `base`.
+pub fn join(base: &str, path: &str) -> String {
+    if !split_device_root(path).0.is_empty() {
+        return normalize(path);
+    }
+    if path.starts_with(['\\', '/']) {
+        let (root, _) = split_device_root(base);
+        return normalize(&alloc::format!("{}{}", root, path));
+    }
+    normalize(&alloc::format!("{}{}{}", base, SEPARATOR, path))
+}
+
+/// Acquires the last component of the text `path`, if it has one.
+pub fn file_name(path: &str) -> Option<&str> {
+    let (_, path) = split_device_root(path);
+    path.rsplit(['\\', '/'])
+        .next()
+        .filter(|name| !name.is_empty() && *name != "." && *name != "..")
+}
+
+/// Acquires the extension of the file name in the text `path`, without the dot.
+/// A file name that starts with its only dot, like `.conf`, has no extension.
+pub fn extension(path: &str) -> Option<&str> {
+    let name = file_name(path)?;
+    let index = name.rfind('.').filter(|index| *index > 0)?;
+    Some(&name[index + 1..])
+}
+
+/// Removes the `extension` from the file `name`, ignoring ASCII case.
+/// Returns [None] if the name does not have the extension, or would be empty without it.
+pub fn strip_extension<'a>(name: &'a str, extension: &str) -> Option<&'a str> {
+    let index = name.len().checked_sub(extension.len() + 1)?;
+    if index == 0
+        || !name.is_char_boundary(index)
+        || !name[index..].starts_with('.')
+        || !name[index + 1..].eq_ignore_ascii_case(extension)
+    {
+        return None;
+    }
+    Some(&name[..index])
+}
+
+/// Matches the `names` of a directory listing against the glob `pattern`.
+/// See [wildcard_match] for the supported pattern syntax.
+/// The matching names are sorted, so the result is stable across firmware implementations.
+pub fn glob<T: AsRef<str>>(pattern: &str, names: impl Iterator<Item = T>) -> Vec<String> {
+    let mut matches = names
+        .filter(|name| wildcard_match(pattern, name.as_ref()))
+        .map(|name| name.as_ref().to_string())
+        .collect::<Vec<_>>();
+    matches.sort();
+    matches
+}
+
+/// A scheme that selects the filesystem of a path by a property instead of a device path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathScheme<'a> {
+    /// `esp:`, the EFI system partition Sprout was loaded from.
+    Esp,
+    /// `part-uuid:<guid>/`, the partition with the unique partition GUID.
+    PartitionUuid(&'a str),
+    /// `label:<label>/`, the filesystem with the volume label.
+    Label(&'a str),
+    /// `squashfs:<name>/`, the squashfs container declared with the name.
+    Squashfs(&'a str),
+}
+
+/// Splits the text `path` into its [PathScheme] and the path inside the selected filesystem.
+/// Returns [None] if the path does not start with a known scheme.
+///
+/// For example, `esp:\EFI\foo.efi` is split into [PathScheme::Esp] and `\EFI\foo.efi`,
+/// and `label:BOOT/\kernel` is split into [PathScheme::Label] with `BOOT` and `\kernel`.
+/// The path is made absolute, as it is always relative to the root of the filesystem.
+pub fn split_scheme(path: &str) -> Option<(PathScheme<'_>, String)> {
+    let (scheme, rest) = if let Some(rest) = path.strip_prefix("esp:") {
+        (PathScheme::Esp, rest)
+    } else if let Some(rest) = path.strip_prefix("part-uuid:") {
+        let (guid, rest) = rest.split_once('/').unwrap_or((rest, ""));
+        (PathScheme::PartitionUuid(guid), rest)
+    } else if let Some(rest) = path.strip_prefix("label:") {
+        let (label, rest) = rest.split_once('/').unwrap_or((rest, ""));
+        (PathScheme::Label(label), rest)
+    } else if let Some(rest) = path.strip_prefix("squashfs:") {
+        let (name, rest) = rest.split_once('/').unwrap_or((rest, ""));
+        (PathScheme::Squashfs(name), rest)
+    } else {
+        return None;
+    };
+    Some((scheme, normalize(&alloc::format!("{}{}", SEPARATOR, rest))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROOT: &str = "PciRoot(0x0)/Pci(0x4,0x0)/HD(1,MBR,0xBE1AFDFA,0x3F,0xFBFC1)/";
+
+    #[test]
+    fn split_device_roots() {
+        let path = alloc::format!("{}\\EFI\\BOOT", ROOT);
+        assert_eq!(split_device_root(&path), (ROOT, "\\EFI\\BOOT"));
+        assert_eq!(
+            split_device_root("\\EFI\\foo(1).efi"),
+            ("", "\\EFI\\foo(1).efi")
+        );
+        assert_eq!(split_device_root("HD(1,GPT,x)"), ("HD(1,GPT,x)", ""));
+    }
+
+    #[test]
+    fn normalize_paths() {
+        assert_eq!(normalize("\\EFI\\\\BOOT\\.\\x.efi"), "\\EFI\\BOOT\\x.efi");
+        assert_eq!(normalize("/boot/../EFI/linux/"), "\\EFI\\linux");
+        assert_eq!(normalize("\\..\\..\\x"), "\\x");
+        assert_eq!(normalize("a/./b"), "a\\b");
+        assert_eq!(normalize("/"), "\\");
+        assert_eq!(
+            normalize(&alloc::format!("{}\\EFI\\..\\loader", ROOT)),
+            alloc::format!("{}\\loader", ROOT)
+        );
+    }
+
+    #[test]
+    fn join_paths() {
+        assert_eq!(join("\\EFI\\linux\\", "vmlinuz"), "\\EFI\\linux\\vmlinuz");
+        assert_eq!(join("\\EFI\\linux", "..\\initrd"), "\\EFI\\initrd");
+        assert_eq!(join("\\", "vmlinuz"), "\\vmlinuz");
+        assert_eq!(join("\\EFI", "\\loader"), "\\loader");
+        let base = alloc::format!("{}\\EFI", ROOT);
+        assert_eq!(join(&base, "/loader"), alloc::format!("{}\\loader", ROOT));
+        assert_eq!(join("\\EFI", ROOT), alloc::format!("{}\\", ROOT));
+    }
+
+    #[test]
+    fn file_names_and_extensions() {
+        assert_eq!(file_name("\\EFI\\BOOT\\BOOTX64.EFI"), Some("BOOTX64.EFI"));
+        assert_eq!(file_name("\\EFI\\"), None);
+        assert_eq!(extension("\\loader\\entries\\a.b.conf"), Some("conf"));
+        assert_eq!(extension("\\loader\\.conf"), None);
+        assert_eq!(extension("\\vmlinuz"), None);
+        assert_eq!(strip_extension("linux.CONF", "conf"), Some("linux"));
+        assert_eq!(strip_extension(".conf", "conf"), None);
+        assert_eq!(strip_extension("linuxconf", "conf"), None);
+        assert_eq!(strip_extension("conf", "conf"), None);
+    }
+
+    #[test]
+    fn split_schemes() {
+        assert_eq!(
+            split_scheme("esp:\\EFI\\foo.efi"),
+            Some((PathScheme::Esp, "\\EFI\\foo.efi".to_string()))
+        );
+        assert_eq!(
+            split_scheme("part-uuid:0fc63daf-8483-4772-8e79-3d69d8477de4/\\vmlinuz"),
+            Some((
+                PathScheme::PartitionUuid("0fc63daf-8483-4772-8e79-3d69d8477de4"),
+                "\\vmlinuz".to_string()
+            ))
+        );
+        assert_eq!(
+            split_scheme("label:BOOT/kernel"),
+            Some((PathScheme::Label("BOOT"), "\\kernel".to_string()))
+        );
+        assert_eq!(
+            split_scheme("label:BOOT"),
+            Some((PathScheme::Label("BOOT"), "\\".to_string()))
+        );
+        assert_eq!(
+            split_scheme("squashfs:appliance/\\boot\\vmlinuz"),
+            Some((
+                PathScheme::Squashfs("appliance"),
+                "\\boot\\vmlinuz".to_string()
+            ))
+        );
+        assert_eq!(split_scheme("\\EFI\\foo.efi"), None);
+        assert_eq!(split_scheme("PciRoot(0x0)/\\EFI"), None);
+    }
+
+    #[test]
+    fn glob_names() {
+        let names = ["vmlinuz-6.12", "initrd-6.12", "VMLINUZ-6.1", "config"];
+        assert_eq!(
+            glob("vmlinuz-*", names.iter()),
+            ["VMLINUZ-6.1", "vmlinuz-6.12"]
+        );
+        assert!(glob("*.efi", names.iter()).is_empty());
+    }
+}
@@ -0,0 +1,391 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// The signature at the start of a DOS header, which every PE image starts with.
+const DOS_SIGNATURE: &[u8] = b"MZ";
+
+/// The offset of the pointer to the PE signature in the DOS header.
+const PE_POINTER_OFFSET: usize = 0x3c;
+
+/// The signature in front of the COFF file header.
+const PE_SIGNATURE: &[u8] = b"PE\0\0";
+
+/// The size of the COFF file header, which follows the PE signature.
+const COFF_HEADER_SIZE: usize = 20;
+
+/// The size of an entry of the section table.
+const SECTION_HEADER_SIZE: usize = 40;
+
+/// The maximum number of sections that is accepted, as limited by the specification.
+const MAXIMUM_SECTION_COUNT: usize = 96;
+
+/// The section of a unified kernel image that contains the os-release of the image.
+pub const SECTION_OSREL: &str = ".osrel";
+
+/// The section of a unified kernel image that contains the kernel command line.
+pub const SECTION_CMDLINE: &str = ".cmdline";
+
+/// The section that contains the SBAT metadata of an image.
+pub const SECTION_SBAT: &str = ".sbat";
+
+/// The section of a unified kernel image that contains the kernel.
+pub const SECTION_LINUX: &str = ".linux";
+
+/// The section of a unified kernel image that contains the initrd.
+pub const SECTION_INITRD: &str = ".initrd";
+
+/// The machine type of a PE image, which is the architecture it runs on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Machine {
+    /// 32-bit x86.
+    I386,
+    /// 64-bit x86.
+    X86_64,
+    /// 32-bit ARM in Thumb-2 mode, as used by ARMv7 EFI images.
+    ArmThumb,
+    /// 64-bit ARM.
+    Aarch64,
+    /// 64-bit RISC-V.
+    RiscV64,
+    /// 64-bit LoongArch.
+    LoongArch64,
+    /// EFI byte code, which is interpreted by the firmware of any architecture.
+    Ebc,
+    /// A machine type that is not known to Sprout.
+    Other(u16),
+}
+
+impl Machine {
+    /// Converts the raw machine type of the COFF file header.
+    pub fn from_raw(raw: u16) -> Self {
+        match raw {
+            0x014c => Self::I386,
+            0x8664 => Self::X86_64,
+            0x01c2 | 0x01c4 => Self::ArmThumb,
+            0xaa64 => Self::Aarch64,
+            0x5064 => Self::RiscV64,
+            0x6264 => Self::LoongArch64,
+            0x0ebc => Self::Ebc,
+            raw => Self::Other(raw),
+        }
+    }
+
+    /// The name of the machine type, as used for the EFI boot file name suffix.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::I386 => "ia32",
+            Self::X86_64 => "x64",
+            Self::ArmThumb => "arm",
+            Self::Aarch64 => "aa64",
+            Self::RiscV64 => "riscv64",
+            Self::LoongArch64 => "loongarch64",
+            Self::Ebc => "ebc",
+            Self::Other(_) => "unknown",
+        }
+    }
+
+    /// The path of the removable media boot file for the machine type, like
+    /// `\EFI\BOOT\BOOTX64.EFI`, which firmware boots when it has no boot option.
+    /// Returns [None] for EFI byte code and machine types that are not known to Sprout.
+    pub fn removable_media_path(&self) -> Option<String> {
+        if let Self::Ebc | Self::Other(_) = self {
+            return None;
+        }
+        Some(format!(
+            "\\EFI\\BOOT\\BOOT{}.EFI",
+            self.name().to_uppercase()
+        ))
+    }
+}
+
+/// An entry of the section table of a PE image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeSection {
+    /// The name of the section, like `.linux`.
+    pub name: String,
+    /// The size of the section when loaded into memory.
+    pub virtual_size: u32,
+    /// The address of the section when loaded, relative to the image base.
+    pub virtual_address: u32,
+    /// The size of the section data in the file, which is padded to the file alignment.
+    pub raw_size: u32,
+    /// The offset of the section data in the file.
+    pub raw_offset: u32,
+}
+
+/// A PE image that has been parsed from a byte buffer.
+/// The section contents are borrowed from the buffer.
+#[derive(Debug, Clone)]
+pub struct PeImage<'a> {
+    /// The bytes of the image, either as a file or as loaded into memory.
+    data: &'a [u8],
+    /// Whether the image is loaded into memory, where sections are at their virtual address.
+    loaded: bool,
+    /// The machine type of the image.
+    pub machine: Machine,
+    /// The sections of the image, in the order of the section table.
+    pub sections: Vec<PeSection>,
+}
+
+impl<'a> PeImage<'a> {
+    /// Acquires the first section with the specified `name`.
+    pub fn section(&self, name: &str) -> Option<&PeSection> {
+        self.sections.iter().find(|section| section.name == name)
+    }
+
+    /// Acquires the contents of the first section with the specified `name`.
+    /// The padding of the section data in the file is not included.
+    /// Returns [None] if there is no such section or its data is outside the file.
+    pub fn section_data(&self, name: &str) -> Option<&'a [u8]> {
+        let section = self.section(name)?;
+        // The raw size is padded to the file alignment, while the virtual size is exact.
+        // Zero virtual size is used by some linkers to mean the raw size.
+        let size = match section.virtual_size {
+            0 => section.raw_size,
+            size => size.min(section.raw_size),
+        } as usize;
+        let start = if self.loaded {
+            section.virtual_address
+        } else {
+            section.raw_offset
+        } as usize;
+        self.data.get(start..start.checked_add(size)?)
+    }
+
+    /// Acquires the contents of the section with the specified `name` as text.
+    /// Trailing null bytes are removed. Returns [None] if the contents are not UTF-8.
+    pub fn section_text(&self, name: &str) -> Option<&'a str> {
+        let data = self.section_data(name)?;
+        let end = data
+            .iter()
+            .rposition(|byte| *byte != 0)
+            .map(|index| index + 1)
+            .unwrap_or(0);
+        core::str::from_utf8(&data[..end]).ok()
+    }
+
+    /// Acquires the os-release in the `.osrel` section.
+    pub fn osrel(&self) -> Option<&'a str> {
+        self.section_text(SECTION_OSREL)
+    }
+
+    /// Acquires the kernel command line in the `.cmdline` section.
+    pub fn cmdline(&self) -> Option<&'a str> {
+        self.section_text(SECTION_CMDLINE).map(str::trim)
+    }
+
+    /// Acquires the SBAT metadata in the `.sbat` section.
+    pub fn sbat(&self) -> Option<&'a str> {
+        self.section_text(SECTION_SBAT)
+    }
+
+    /// Acquires the kernel in the `.linux` section.
+    pub fn linux(&self) -> Option<&'a [u8]> {
+        self.section_data(SECTION_LINUX)
+    }
+
+    /// Acquires the initrd in the `.initrd` section.
+    pub fn initrd(&self) -> Option<&'a [u8]> {
+        self.section_data(SECTION_INITRD)
+    }
+}
+
+/// Reads a little-endian u16 from `data` at `offset`.
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+/// Reads a little-endian u32 from `data` at `offset`.
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// Parses the headers and section table of the PE image file in `data`.
+/// Long section names in the COFF string table are not resolved, as images do not use them
+/// for the sections that Sprout reads. Returns [None] if `data` is not a valid PE image.
+pub fn parse_pe(data: &[u8]) -> Option<PeImage<'_>> {
+    parse_pe_layout(data, false)
+}
+
+/// Parses the PE image in `data` that has been loaded into memory by the firmware,
+/// like the image of Sprout itself. Sections are read from their virtual address.
+pub fn parse_loaded_pe(data: &[u8]) -> Option<PeImage<'_>> {
+    parse_pe_layout(data, true)
+}
+
+/// Parses the PE image in `data`, which is `loaded` into memory or a file.
+fn parse_pe_layout(data: &[u8], loaded: bool) -> Option<PeImage<'_>> {
+    if !data.starts_with(DOS_SIGNATURE) {
+        return None;
+    }
+
+    let header = read_u32(data, PE_POINTER_OFFSET)? as usize;
+    if data.get(header..header.checked_add(PE_SIGNATURE.len())?)? != PE_SIGNATURE {
+        return None;
+    }
+    let coff = header + PE_SIGNATURE.len();
+    let machine = Machine::from_raw(read_u16(data, coff)?);
+    let section_count = read_u16(data, coff + 2)? as usize;
+    if section_count > MAXIMUM_SECTION_COUNT {
+        return None;
+    }
+    let optional_header_size = read_u16(data, coff + 16)? as usize;
+
+    // The section table follows the optional header.
+    let table = coff + COFF_HEADER_SIZE + optional_header_size;
+    let table = data.get(table..table + section_count * SECTION_HEADER_SIZE)?;
+    let sections = table
+        .chunks_exact(SECTION_HEADER_SIZE)
+        .map(|entry| {
+            // The name is padded with null bytes if it is shorter than eight bytes.
+            let name = &entry[..8];
+            let end = name.iter().position(|byte| *byte == 0).unwrap_or(8);
+            Some(PeSection {
+                name: String::from_utf8_lossy(&name[..end]).into_owned(),
+                virtual_size: read_u32(entry, 8)?,
+                virtual_address: read_u32(entry, 12)?,
+                raw_size: read_u32(entry, 16)?,
+                raw_offset: read_u32(entry, 20)?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(PeImage {
+        data,
+        loaded,
+        machine,
+        sections,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    /// Builds a PE image with the `sections`, each stored with a raw size padded to 16 bytes.
+    fn image(machine: u16, sections: &[(&str, &[u8])]) -> Vec<u8> {
+        let header = 0x80;
+        let optional_header_size = 0xf0;
+        let table = header + 4 + COFF_HEADER_SIZE + optional_header_size;
+        let mut data = vec![0u8; table + sections.len() * SECTION_HEADER_SIZE];
+        data[..2].copy_from_slice(DOS_SIGNATURE);
+        data[0x3c..0x40].copy_from_slice(&(header as u32).to_le_bytes());
+        data[header..header + 4].copy_from_slice(PE_SIGNATURE);
+        data[header + 4..header + 6].copy_from_slice(&machine.to_le_bytes());
+        data[header + 6..header + 8].copy_from_slice(&(sections.len() as u16).to_le_bytes());
+        data[header + 20..header + 22]
+            .copy_from_slice(&(optional_header_size as u16).to_le_bytes());
+
+        for (index, (name, contents)) in sections.iter().enumerate() {
+            let raw_offset = data.len();
+            let raw_size = contents.len().div_ceil(16) * 16;
+            data.extend_from_slice(contents);
+            data.resize(raw_offset + raw_size, 0);
+
+            let entry = table + index * SECTION_HEADER_SIZE;
+            data[entry..entry + name.len()].copy_from_slice(name.as_bytes());
+            data[entry + 8..entry + 12].copy_from_slice(&(contents.len() as u32).to_le_bytes());
+            data[entry + 12..entry + 16]
+                .copy_from_slice(&(0x1000 * (index as u32 + 1)).to_le_bytes());
+            data[entry + 16..entry + 20].copy_from_slice(&(raw_size as u32).to_le_bytes());
+            data[entry + 20..entry + 24].copy_from_slice(&(raw_offset as u32).to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn parse_unified_kernel_image() {
+        let data = image(
+            0x8664,
+            &[
+                (".text", b"\x90\x90"),
+                (".osrel", b"ID=arch\nVERSION_ID=1\n"),
+                (".cmdline", b"root=/dev/sda1 quiet\n\0"),
+                (".sbat", b"sbat,1,SBAT Version,sbat,1\n"),
+                (".linux", b"MZkernel"),
+                (".initrd", b"070701"),
+            ],
+        );
+        let pe = parse_pe(&data).expect("image should parse");
+        assert_eq!(pe.machine, Machine::X86_64);
+        assert_eq!(pe.sections.len(), 6);
+        assert_eq!(pe.sections[1].name, ".osrel");
+        assert_eq!(pe.sections[1].virtual_address, 0x2000);
+        assert_eq!(pe.osrel(), Some("ID=arch\nVERSION_ID=1\n"));
+        assert_eq!(pe.cmdline(), Some("root=/dev/sda1 quiet"));
+        assert_eq!(pe.sbat(), Some("sbat,1,SBAT Version,sbat,1\n"));
+        assert_eq!(pe.linux(), Some(&b"MZkernel"[..]));
+        assert_eq!(pe.initrd(), Some(&b"070701"[..]));
+        assert_eq!(pe.section(".data"), None);
+    }
+
+    #[test]
+    fn parse_loaded_image() {
+        // Lay out the sections at their virtual address, like the firmware loader does.
+        let data = image(0x8664, &[(".linux", b"kernel"), (".cmdline", b"quiet")]);
+        let file = parse_pe(&data).expect("image should parse");
+        let mut loaded = data[..file.sections[0].raw_offset as usize].to_vec();
+        for section in &file.sections {
+            let start = section.virtual_address as usize;
+            loaded.resize(start + 0x1000, 0);
+            let contents = file
+                .section_data(&section.name)
+                .expect("section should exist");
+            loaded[start..start + contents.len()].copy_from_slice(contents);
+        }
+
+        let pe = parse_loaded_pe(&loaded).expect("image should parse");
+        assert_eq!(pe.linux(), Some(&b"kernel"[..]));
+        assert_eq!(pe.cmdline(), Some("quiet"));
+    }
+
+    #[test]
+    fn machine_types() {
+        let data = image(0xaa64, &[]);
+        let pe = parse_pe(&data).expect("image should parse");
+        assert_eq!(pe.machine, Machine::Aarch64);
+        assert!(pe.sections.is_empty());
+        assert_eq!(Machine::from_raw(0x01c2).name(), "arm");
+        assert_eq!(Machine::from_raw(0x1234), Machine::Other(0x1234));
+        assert_eq!(
+            Machine::X86_64.removable_media_path().as_deref(),
+            Some("\\EFI\\BOOT\\BOOTX64.EFI")
+        );
+        assert_eq!(
+            Machine::ArmThumb.removable_media_path().as_deref(),
+            Some("\\EFI\\BOOT\\BOOTARM.EFI")
+        );
+        assert_eq!(Machine::Other(0x1234).removable_media_path(), None);
+        assert_eq!(Machine::from_raw(0x0ebc), Machine::Ebc);
+        assert_eq!(Machine::Ebc.removable_media_path(), None);
+    }
+
+    #[test]
+    fn reject_invalid_images() {
+        assert!(parse_pe(&[]).is_none());
+        assert!(parse_pe(&[0u8; 0x200]).is_none());
+
+        let mut data = image(0x8664, &[(".linux", b"kernel")]);
+        data[0x80] = b'X';
+        assert!(parse_pe(&data).is_none());
+
+        // A section table that extends past the end of the file is rejected.
+        let data = image(0x8664, &[(".linux", b"kernel")]);
+        let mut truncated = data.clone();
+        truncated[0x86..0x88].copy_from_slice(&2u16.to_le_bytes());
+        truncated.truncate(0x80 + 4 + COFF_HEADER_SIZE + 0xf0 + SECTION_HEADER_SIZE);
+        assert!(parse_pe(&truncated).is_none());
+
+        // Section data outside the file is not returned.
+        let mut data = data;
+        data.truncate(data.len() - 16);
+        let pe = parse_pe(&data).expect("headers should parse");
+        assert_eq!(pe.linux(), None);
+    }
+}
@@ -0,0 +1,92 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// The separator between a namespace and a key, like `bls.version`.
+pub const NAMESPACE_SEPARATOR: char = '.';
+
+/// A set of values that can be placed in a namespace.
+/// Values in a namespace can be referenced by their key, like `version`, or qualified with
+/// the namespace, like `bls.version`. Scopes are layered, and the key of an inner scope
+/// shadows the same key of an outer scope, while the qualified key stays unambiguous.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValueScope {
+    /// The namespace of the values, if any.
+    pub namespace: Option<String>,
+    /// The values of the scope.
+    pub values: BTreeMap<String, String>,
+}
+
+impl ValueScope {
+    /// Retrieves the value of `key` from this scope only. The `key` can be qualified
+    /// with the namespace of the scope.
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.values.get(key).or_else(|| {
+            let key = key
+                .strip_prefix(self.namespace.as_deref()?)?
+                .strip_prefix(NAMESPACE_SEPARATOR)?;
+            self.values.get(key)
+        })
+    }
+
+    /// Collects the keys that the values of this scope can be referenced by,
+    /// which includes the qualified keys if the scope has a namespace.
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys = Vec::new();
+        for key in self.values.keys() {
+            keys.push(key.clone());
+            if let Some(ref namespace) = self.namespace {
+                keys.push(format!("{}{}{}", namespace, NAMESPACE_SEPARATOR, key));
+            }
+        }
+        keys
+    }
+}
+
+/// Retrieves the value of `key` from the first of the `scopes` that has it,
+/// so the scopes must be ordered from the innermost to the outermost.
+pub fn resolve<'a>(
+    scopes: impl IntoIterator<Item = &'a ValueScope>,
+    key: &str,
+) -> Option<&'a String> {
+    scopes.into_iter().find_map(|scope| scope.get(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    fn scope(namespace: &str, values: &[(&str, &str)]) -> ValueScope {
+        ValueScope {
+            namespace: Some(namespace.to_string()),
+            values: values
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn shared_names_are_reachable_by_namespace() {
+        let user = scope("values", &[("version", "custom"), ("root", "/")]);
+        let bls = scope("bls", &[("version", "6.12.1")]);
+        let scopes = [&bls, &user];
+
+        // The generator value shadows the user value of the same name.
+        assert_eq!(resolve(scopes, "version").unwrap(), "6.12.1");
+        // Both can still be referenced unambiguously.
+        assert_eq!(resolve(scopes, "bls.version").unwrap(), "6.12.1");
+        assert_eq!(resolve(scopes, "values.version").unwrap(), "custom");
+        assert_eq!(resolve(scopes, "root").unwrap(), "/");
+        assert_eq!(resolve(scopes, "bls.root"), None);
+    }
+
+    #[test]
+    fn keys_include_qualified_keys() {
+        let bls = scope("bls", &[("version", "6.12.1")]);
+        assert_eq!(bls.keys(), ["version", "bls.version"]);
+        assert_eq!(ValueScope::default().keys(), Vec::<String>::new());
+    }
+}
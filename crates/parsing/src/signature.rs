@@ -0,0 +1,47 @@
+use alloc::format;
+use alloc::string::String;
+use p256::ecdsa::signature::Verifier;
+use p256::ecdsa::{Signature, VerifyingKey};
+use p256::pkcs8::DecodePublicKey;
+
+/// Verifies the detached `signature` of the `message` with the DER `public_key`.
+///
+/// The key is an ECDSA P-256 public key in the SubjectPublicKeyInfo format, and the signature
+/// is a DER ECDSA signature of the SHA-256 digest of the message, which is what
+/// `openssl dgst -sha256 -sign` produces.
+pub fn verify(public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), String> {
+    let key = VerifyingKey::from_public_key_der(public_key)
+        .map_err(|error| format!("public key is not an ecdsa p-256 key: {}", error))?;
+    let signature = Signature::from_der(signature)
+        .map_err(|_| "signature is not a der ecdsa p-256 signature")?;
+    key.verify(message, &signature)
+        .map_err(|_| "signature does not match".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+    use p256::ecdsa::SigningKey;
+    use p256::ecdsa::signature::Signer;
+
+    /// The SubjectPublicKeyInfo prefix of an uncompressed P-256 public key.
+    const SPKI_PREFIX: [u8; 26] = [
+        0x30, 0x59, 0x30, 0x13, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06, 0x08,
+        0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07, 0x03, 0x42, 0x00,
+    ];
+
+    #[test]
+    fn signatures_are_verified() {
+        let signing = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let mut public_key = SPKI_PREFIX.to_vec();
+        public_key.extend_from_slice(signing.verifying_key().to_encoded_point(false).as_bytes());
+        let signature: Signature = signing.sign(b"manifest");
+        let signature: Vec<u8> = signature.to_der().as_bytes().to_vec();
+
+        assert_eq!(verify(&public_key, b"manifest", &signature), Ok(()));
+        assert!(verify(&public_key, b"tampered", &signature).is_err());
+        assert!(verify(&public_key, b"manifest", &signature[1..]).is_err());
+        assert!(verify(&public_key[1..], b"manifest", &signature).is_err());
+    }
+}
@@ -0,0 +1,327 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+
+/// The anchor string of an SMBIOS 2.x entry point structure.
+const SMBIOS2_ANCHOR: &[u8] = b"_SM_";
+
+/// The anchor string of an SMBIOS 3.x entry point structure.
+const SMBIOS3_ANCHOR: &[u8] = b"_SM3_";
+
+/// The structure type of the BIOS information structure.
+const TYPE_BIOS_INFORMATION: u8 = 0;
+
+/// The structure type of the system information structure.
+const TYPE_SYSTEM_INFORMATION: u8 = 1;
+
+/// The structure type of the system enclosure or chassis structure.
+const TYPE_SYSTEM_ENCLOSURE: u8 = 3;
+
+/// The structure type of the end-of-table structure.
+const TYPE_END_OF_TABLE: u8 = 127;
+
+/// The location of the SMBIOS structure table described by an entry point structure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmbiosTableLocation {
+    /// The physical address of the structure table.
+    pub address: u64,
+    /// The length of the structure table in bytes. For SMBIOS 3.x this is the maximum length.
+    pub length: usize,
+}
+
+/// The information extracted from the SMBIOS structure table.
+/// Fields that are not present or are empty in the table are [None].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SmbiosInfo {
+    /// The vendor of the BIOS.
+    pub bios_vendor: Option<String>,
+    /// The version of the BIOS.
+    pub bios_version: Option<String>,
+    /// The manufacturer of the system.
+    pub vendor: Option<String>,
+    /// The product name of the system.
+    pub product: Option<String>,
+    /// The version of the system.
+    pub version: Option<String>,
+    /// The serial number of the system.
+    pub serial: Option<String>,
+    /// The UUID of the system, formatted as a lowercase hyphenated string.
+    pub uuid: Option<String>,
+    /// The SKU number of the system.
+    pub sku: Option<String>,
+    /// The family of the system.
+    pub family: Option<String>,
+    /// The asset tag of the system enclosure.
+    pub asset_tag: Option<String>,
+}
+
+/// Parses the SMBIOS `entry` point structure to find the location of the structure table.
+/// Both SMBIOS 2.x and 3.x entry points are supported.
+/// Returns [None] if the entry point is not valid.
+pub fn parse_entry_point(entry: &[u8]) -> Option<SmbiosTableLocation> {
+    if entry.starts_with(SMBIOS3_ANCHOR) {
+        // The 3.x entry point has a 32-bit maximum table size and a 64-bit table address.
+        let length = u32::from_le_bytes(entry.get(0x0C..0x10)?.try_into().ok()?);
+        let address = u64::from_le_bytes(entry.get(0x10..0x18)?.try_into().ok()?);
+        Some(SmbiosTableLocation {
+            address,
+            length: length as usize,
+        })
+    } else if entry.starts_with(SMBIOS2_ANCHOR) {
+        // The 2.x entry point has a 16-bit table length and a 32-bit table address.
+        let length = u16::from_le_bytes(entry.get(0x16..0x18)?.try_into().ok()?);
+        let address = u32::from_le_bytes(entry.get(0x18..0x1C)?.try_into().ok()?);
+        Some(SmbiosTableLocation {
+            address: address as u64,
+            length: length as usize,
+        })
+    } else {
+        None
+    }
+}
+
+/// A single structure inside the SMBIOS structure table.
+struct Structure<'a> {
+    /// The formatted area of the structure, including the header.
+    formatted: &'a [u8],
+    /// The string area of the structure, which is a sequence of null-terminated strings.
+    strings: &'a [u8],
+}
+
+impl Structure<'_> {
+    /// Acquires the structure type.
+    fn kind(&self) -> u8 {
+        self.formatted[0]
+    }
+
+    /// Acquires the string referenced by the string number at `offset` in the formatted area.
+    /// Empty strings and strings that are only whitespace are treated as not present.
+    fn string(&self, offset: usize) -> Option<String> {
+        // String numbers are one-based, with zero meaning no string.
+        let number = *self.formatted.get(offset)? as usize;
+        if number == 0 {
+            return None;
+        }
+
+        let string = self.strings.split(|byte| *byte == 0).nth(number - 1)?;
+        let string = String::from_utf8_lossy(string);
+        let string = string.trim();
+        if string.is_empty() {
+            None
+        } else {
+            Some(string.to_string())
+        }
+    }
+
+    /// Acquires the UUID at `offset` in the formatted area.
+    /// The first three fields of the UUID are encoded in little-endian.
+    /// UUIDs that are all zeros or all ones are treated as not present.
+    fn uuid(&self, offset: usize) -> Option<String> {
+        let bytes = self.formatted.get(offset..offset + 16)?;
+        if bytes.iter().all(|byte| *byte == 0) || bytes.iter().all(|byte| *byte == 0xFF) {
+            return None;
+        }
+
+        Some(format!(
+            "{:02x}{:02x}{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
+            bytes[3],
+            bytes[2],
+            bytes[1],
+            bytes[0],
+            bytes[5],
+            bytes[4],
+            bytes[7],
+            bytes[6],
+            bytes[8],
+            bytes[9],
+            bytes[10],
+            bytes[11],
+            bytes[12],
+            bytes[13],
+            bytes[14],
+            bytes[15],
+        ))
+    }
+}
+
+/// Iterates over the structures in the SMBIOS structure `table`.
+/// Iteration stops at the end-of-table structure or at the first malformed structure.
+fn structures(table: &[u8]) -> impl Iterator<Item = Structure<'_>> {
+    let mut rest = table;
+    core::iter::from_fn(move || {
+        // Each structure begins with a 4-byte header containing the type and length.
+        let length = *rest.get(1)? as usize;
+        if length < 4 || rest.len() < length {
+            return None;
+        }
+        let (formatted, after) = rest.split_at(length);
+
+        // The string area is terminated by two null bytes.
+        let end = after.windows(2).position(|window| window == [0, 0])?;
+        let strings = &after[..end];
+        rest = &after[end + 2..];
+
+        let structure = Structure { formatted, strings };
+        if structure.kind() == TYPE_END_OF_TABLE {
+            rest = &[];
+            return None;
+        }
+        Some(structure)
+    })
+}
+
+/// Parses the SMBIOS structure `table` into the [SmbiosInfo] that Sprout uses.
+/// Only the first structure of each relevant type is used.
+pub fn parse_table(table: &[u8]) -> SmbiosInfo {
+    let mut info = SmbiosInfo::default();
+    let mut seen_bios = false;
+    let mut seen_system = false;
+    let mut seen_enclosure = false;
+
+    for structure in structures(table) {
+        match structure.kind() {
+            TYPE_BIOS_INFORMATION if !seen_bios => {
+                seen_bios = true;
+                info.bios_vendor = structure.string(0x04);
+                info.bios_version = structure.string(0x05);
+            }
+
+            TYPE_SYSTEM_INFORMATION if !seen_system => {
+                seen_system = true;
+                info.vendor = structure.string(0x04);
+                info.product = structure.string(0x05);
+                info.version = structure.string(0x06);
+                info.serial = structure.string(0x07);
+                info.uuid = structure.uuid(0x08);
+                info.sku = structure.string(0x19);
+                info.family = structure.string(0x1A);
+            }
+
+            TYPE_SYSTEM_ENCLOSURE if !seen_enclosure => {
+                seen_enclosure = true;
+                info.asset_tag = structure.string(0x08);
+            }
+
+            _ => {}
+        }
+    }
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn structure(kind: u8, formatted: &[u8], strings: &[&str]) -> Vec<u8> {
+        let mut data = vec![kind, (formatted.len() + 4) as u8, 0, 0];
+        data.extend_from_slice(formatted);
+        for string in strings {
+            data.extend_from_slice(string.as_bytes());
+            data.push(0);
+        }
+        if strings.is_empty() {
+            data.push(0);
+        }
+        data.push(0);
+        data
+    }
+
+    fn system_information() -> Vec<u8> {
+        let mut formatted = vec![0u8; 0x1B - 4];
+        // manufacturer, product, version, serial
+        formatted[0] = 1;
+        formatted[1] = 2;
+        formatted[2] = 3;
+        formatted[3] = 0;
+        // uuid
+        formatted[4..20].copy_from_slice(&[
+            0x33, 0x22, 0x11, 0x00, 0x55, 0x44, 0x77, 0x66, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ]);
+        // sku, family
+        formatted[0x19 - 4] = 0;
+        formatted[0x1A - 4] = 4;
+        structure(
+            TYPE_SYSTEM_INFORMATION,
+            &formatted,
+            &["Edera", "Sprout Machine", "  ", "Sprouts"],
+        )
+    }
+
+    #[test]
+    fn parse_system_information() {
+        let mut table = structure(TYPE_BIOS_INFORMATION, &[1, 2], &["BIOS Inc", "1.2.3"]);
+        table.extend(system_information());
+        table.extend(structure(
+            TYPE_SYSTEM_ENCLOSURE,
+            &[0, 0, 0, 0, 1],
+            &["TAG-1"],
+        ));
+        table.extend(structure(TYPE_END_OF_TABLE, &[], &[]));
+
+        let info = parse_table(&table);
+        assert_eq!(info.bios_vendor.as_deref(), Some("BIOS Inc"));
+        assert_eq!(info.bios_version.as_deref(), Some("1.2.3"));
+        assert_eq!(info.vendor.as_deref(), Some("Edera"));
+        assert_eq!(info.product.as_deref(), Some("Sprout Machine"));
+        assert_eq!(info.version, None);
+        assert_eq!(info.serial, None);
+        assert_eq!(
+            info.uuid.as_deref(),
+            Some("00112233-4455-6677-8899-aabbccddeeff")
+        );
+        assert_eq!(info.sku, None);
+        assert_eq!(info.family.as_deref(), Some("Sprouts"));
+        assert_eq!(info.asset_tag.as_deref(), Some("TAG-1"));
+    }
+
+    #[test]
+    fn parse_stops_at_end_of_table() {
+        let mut table = structure(TYPE_END_OF_TABLE, &[], &[]);
+        table.extend(system_information());
+        assert_eq!(parse_table(&table), SmbiosInfo::default());
+    }
+
+    #[test]
+    fn parse_truncated_table() {
+        let table = system_information();
+        assert_eq!(parse_table(&table[..10]), SmbiosInfo::default());
+    }
+
+    #[test]
+    fn parse_smbios2_entry_point() {
+        let mut entry = vec![0u8; 0x1F];
+        entry[..4].copy_from_slice(SMBIOS2_ANCHOR);
+        entry[0x16..0x18].copy_from_slice(&0x1234u16.to_le_bytes());
+        entry[0x18..0x1C].copy_from_slice(&0xABCD0000u32.to_le_bytes());
+        assert_eq!(
+            parse_entry_point(&entry),
+            Some(SmbiosTableLocation {
+                address: 0xABCD0000,
+                length: 0x1234,
+            })
+        );
+    }
+
+    #[test]
+    fn parse_smbios3_entry_point() {
+        let mut entry = vec![0u8; 0x18];
+        entry[..5].copy_from_slice(SMBIOS3_ANCHOR);
+        entry[0x0C..0x10].copy_from_slice(&0x4000u32.to_le_bytes());
+        entry[0x10..0x18].copy_from_slice(&0x1_0000_0000u64.to_le_bytes());
+        assert_eq!(
+            parse_entry_point(&entry),
+            Some(SmbiosTableLocation {
+                address: 0x1_0000_0000,
+                length: 0x4000,
+            })
+        );
+    }
+
+    #[test]
+    fn parse_invalid_entry_point() {
+        assert_eq!(parse_entry_point(b"_XX_"), None);
+        assert_eq!(parse_entry_point(b"_SM3_"), None);
+    }
+}
@@ -0,0 +1,704 @@
+use crate::filesystem::{
+    DirectoryEntry, Node, NodeKind, ReadAt, ReadOnlyFilesystem, le_at, read_vec, slice_at,
+};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// The size of the superblock.
+const SUPERBLOCK_SIZE: usize = 96;
+
+/// The magic number of the superblock, `hsqs`.
+const SUPERBLOCK_MAGIC: u32 = 0x73717368;
+
+/// The size of metadata blocks before compression.
+const METADATA_SIZE: usize = 8192;
+
+/// The bit of a metadata block header that marks the block as stored uncompressed.
+const METADATA_UNCOMPRESSED: u16 = 0x8000;
+
+/// The bit of a data block size that marks the block as stored uncompressed.
+const DATA_UNCOMPRESSED: u32 = 1 << 24;
+
+/// The size of an entry of the fragment table.
+const FRAGMENT_ENTRY_SIZE: usize = 16;
+
+/// The fragment index of files that do not end in a fragment.
+const NO_FRAGMENT: u32 = 0xffffffff;
+
+/// The size of the header of a directory listing.
+const DIRECTORY_HEADER_SIZE: usize = 12;
+
+/// The size of an entry of a directory listing, without its name.
+const DIRECTORY_ENTRY_SIZE: usize = 8;
+
+/// The maximum number of entries that follow a directory listing header.
+const MAX_DIRECTORY_ENTRIES: usize = 256;
+
+/// The compressor of gzip compressed filesystems, which store zlib streams.
+const COMPRESSOR_GZIP: u16 = 1;
+
+/// The inode types of basic and extended directories.
+const INODE_DIRECTORY: u16 = 1;
+const INODE_EXTENDED_DIRECTORY: u16 = 8;
+
+/// The inode types of basic and extended regular files.
+const INODE_FILE: u16 = 2;
+const INODE_EXTENDED_FILE: u16 = 9;
+
+/// The inode types of basic and extended symbolic links.
+const INODE_SYMLINK: u16 = 3;
+const INODE_EXTENDED_SYMLINK: u16 = 10;
+
+/// The name of the squashfs `compressor`, for error messages.
+fn compressor_name(compressor: u16) -> &'static str {
+    match compressor {
+        1 => "gzip",
+        2 => "lzma",
+        3 => "lzo",
+        4 => "xz",
+        5 => "lz4",
+        6 => "zstd",
+        _ => "unknown",
+    }
+}
+
+/// Checks whether the `source` holds a squashfs filesystem, without validating it.
+pub fn probe(source: &(impl ReadAt + ?Sized)) -> bool {
+    let mut magic = [0u8; 4];
+    source.read_at(0, &mut magic).is_ok() && u32::from_le_bytes(magic) == SUPERBLOCK_MAGIC
+}
+
+/// A position in a metadata table, which is read across metadata block boundaries.
+struct MetadataCursor {
+    /// The byte offset of the next metadata block.
+    next: u64,
+    /// The uncompressed data of the current metadata block.
+    data: Vec<u8>,
+    /// The offset in the data of the current metadata block.
+    offset: usize,
+}
+
+/// An inode of a squashfs filesystem, with the parts that are needed to read it.
+enum Inode {
+    /// A directory, with the location and size of its listing in the directory table.
+    Directory { block: u32, offset: u16, size: u32 },
+    /// A regular file, with its data blocks and the fragment that holds its tail.
+    File {
+        start: u64,
+        size: u64,
+        blocks: Vec<u32>,
+        fragment: u32,
+        fragment_offset: u32,
+    },
+    /// A symbolic link, with its target.
+    Symlink(Vec<u8>),
+    /// Any other inode, like a device node.
+    Other,
+}
+
+/// A read-only squashfs filesystem, as written by `mksquashfs`.
+///
+/// Data and metadata may be stored uncompressed or compressed with gzip. The [Node::id] of
+/// every node is its inode reference, which is the offset of the metadata block that holds
+/// the inode from the start of the inode table, shifted left by 16, with the offset of the
+/// inode in the uncompressed block.
+pub struct Squashfs<R: ReadAt> {
+    /// The source the filesystem is read from.
+    source: R,
+    /// The size of data blocks in bytes.
+    block_size: u32,
+    /// The compressor of compressed blocks.
+    compressor: u16,
+    /// The number of bytes used by the filesystem.
+    bytes_used: u64,
+    /// The byte offset of the inode table.
+    inode_table: u64,
+    /// The byte offset of the directory table.
+    directory_table: u64,
+    /// The byte offsets of the metadata blocks that hold the fragment table.
+    fragment_blocks: Vec<u64>,
+    /// The root directory.
+    root: Node,
+}
+
+impl<R: ReadAt> Squashfs<R> {
+    /// Opens the squashfs filesystem of the `source`.
+    pub fn open(source: R) -> Result<Self, String> {
+        let superblock = read_vec(&source, 0, SUPERBLOCK_SIZE)?;
+        if le_at::<u32>(&superblock, 0)? != SUPERBLOCK_MAGIC {
+            return Err("not a squashfs filesystem".to_string());
+        }
+        let (major, minor) = (
+            le_at::<u16>(&superblock, 28)?,
+            le_at::<u16>(&superblock, 30)?,
+        );
+        if (major, minor) != (4, 0) {
+            return Err(format!(
+                "squashfs version {}.{} is not supported",
+                major, minor
+            ));
+        }
+        let block_size = le_at::<u32>(&superblock, 12)?;
+        if !block_size.is_power_of_two()
+            || !(4096..=1024 * 1024).contains(&block_size)
+            || block_size.trailing_zeros() != le_at::<u16>(&superblock, 22)? as u32
+        {
+            return Err(format!("invalid squashfs block size {}", block_size));
+        }
+        let compressor = le_at::<u16>(&superblock, 20)?;
+        if compressor != COMPRESSOR_GZIP {
+            return Err(format!(
+                "squashfs compressor {} is not supported",
+                compressor_name(compressor)
+            ));
+        }
+
+        // The fragment table is indexed by an array of the offsets of its metadata blocks.
+        let fragment_count = le_at::<u32>(&superblock, 16)? as usize;
+        let fragment_table = le_at::<u64>(&superblock, 80)?;
+        let fragment_blocks = if fragment_count == 0 {
+            Vec::new()
+        } else {
+            let count = fragment_count.div_ceil(METADATA_SIZE / FRAGMENT_ENTRY_SIZE);
+            read_vec(&source, fragment_table, count * 8)?
+                .chunks_exact(8)
+                .map(|pointer| le_at::<u64>(pointer, 0))
+                .collect::<Result<_, _>>()?
+        };
+
+        let mut filesystem = Self {
+            source,
+            block_size,
+            compressor,
+            bytes_used: le_at::<u64>(&superblock, 40)?,
+            inode_table: le_at::<u64>(&superblock, 64)?,
+            directory_table: le_at::<u64>(&superblock, 72)?,
+            fragment_blocks,
+            root: Node {
+                id: 0,
+                tree: 0,
+                kind: NodeKind::Directory,
+                size: 0,
+                modified: 0,
+            },
+        };
+        filesystem.root = filesystem.node(le_at::<u64>(&superblock, 32)?)?;
+        if filesystem.root.kind != NodeKind::Directory {
+            return Err("squashfs root is not a directory".to_string());
+        }
+        Ok(filesystem)
+    }
+
+    /// Decompresses the compressed block `data`, which is at most `limit` bytes uncompressed.
+    fn decompress(&self, data: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+        match self.compressor {
+            COMPRESSOR_GZIP => miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(data, limit)
+                .map_err(|error| format!("unable to inflate squashfs block: {:?}", error.status)),
+            compressor => Err(format!(
+                "squashfs compressor {} is not supported",
+                compressor_name(compressor)
+            )),
+        }
+    }
+
+    /// Reads the metadata block at the byte `offset`.
+    /// Returns the uncompressed data and the byte offset of the following block.
+    fn metadata_block(&self, offset: u64) -> Result<(Vec<u8>, u64), String> {
+        let header = read_vec(&self.source, offset, 2)?;
+        let header = le_at::<u16>(&header, 0)?;
+        let size = (header & !METADATA_UNCOMPRESSED) as usize;
+        if size == 0 || size > METADATA_SIZE {
+            return Err(format!("invalid squashfs metadata block at {}", offset));
+        }
+        let data = read_vec(&self.source, offset + 2, size)?;
+        let data = if header & METADATA_UNCOMPRESSED != 0 {
+            data
+        } else {
+            self.decompress(&data, METADATA_SIZE)?
+        };
+        Ok((data, offset + 2 + size as u64))
+    }
+
+    /// Starts reading the metadata table at `table` at the metadata block `block` bytes from
+    /// the start of the table, and `offset` bytes into its uncompressed data.
+    fn cursor(&self, table: u64, block: u64, offset: usize) -> Result<MetadataCursor, String> {
+        let (data, next) = self.metadata_block(table + block)?;
+        if offset > data.len() {
+            return Err("squashfs metadata reference is out of range".to_string());
+        }
+        Ok(MetadataCursor { next, data, offset })
+    }
+
+    /// Reads `size` bytes of metadata at the `cursor`, continuing into the following blocks.
+    fn take(&self, cursor: &mut MetadataCursor, size: usize) -> Result<Vec<u8>, String> {
+        let mut data = Vec::with_capacity(size);
+        while data.len() < size {
+            if cursor.offset == cursor.data.len() {
+                let (block, next) = self.metadata_block(cursor.next)?;
+                *cursor = MetadataCursor {
+                    next,
+                    data: block,
+                    offset: 0,
+                };
+            }
+            let count = (size - data.len()).min(cursor.data.len() - cursor.offset);
+            data.extend_from_slice(slice_at(&cursor.data, cursor.offset, count)?);
+            cursor.offset += count;
+        }
+        Ok(data)
+    }
+
+    /// Reads the inode of the inode `reference`.
+    /// Returns the inode with the time it was last modified.
+    fn inode(&self, reference: u64) -> Result<(Inode, u32), String> {
+        let mut cursor = self.cursor(
+            self.inode_table,
+            reference >> 16,
+            (reference & 0xffff) as usize,
+        )?;
+        let header = self.take(&mut cursor, 16)?;
+        let modified = le_at::<u32>(&header, 8)?;
+        let inode = match le_at::<u16>(&header, 0)? {
+            INODE_DIRECTORY => {
+                let data = self.take(&mut cursor, 16)?;
+                Inode::Directory {
+                    block: le_at::<u32>(&data, 0)?,
+                    offset: le_at::<u16>(&data, 10)?,
+                    size: le_at::<u16>(&data, 8)? as u32,
+                }
+            }
+            INODE_EXTENDED_DIRECTORY => {
+                let data = self.take(&mut cursor, 24)?;
+                Inode::Directory {
+                    block: le_at::<u32>(&data, 8)?,
+                    offset: le_at::<u16>(&data, 18)?,
+                    size: le_at::<u32>(&data, 4)?,
+                }
+            }
+            kind @ (INODE_FILE | INODE_EXTENDED_FILE) => {
+                let (start, size, fragment, fragment_offset) = if kind == INODE_FILE {
+                    let data = self.take(&mut cursor, 16)?;
+                    (
+                        le_at::<u32>(&data, 0)? as u64,
+                        le_at::<u32>(&data, 12)? as u64,
+                        le_at::<u32>(&data, 4)?,
+                        le_at::<u32>(&data, 8)?,
+                    )
+                } else {
+                    let data = self.take(&mut cursor, 40)?;
+                    (
+                        le_at::<u64>(&data, 0)?,
+                        le_at::<u64>(&data, 8)?,
+                        le_at::<u32>(&data, 28)?,
+                        le_at::<u32>(&data, 32)?,
+                    )
+                };
+                // Every full block has a size, and so does the tail if it is not a fragment.
+                let block_size = self.block_size as u64;
+                let count = if fragment == NO_FRAGMENT {
+                    size.div_ceil(block_size)
+                } else {
+                    size / block_size
+                };
+                let count = usize::try_from(count).map_err(|_| "squashfs file is too large")?;
+                let blocks = self
+                    .take(
+                        &mut cursor,
+                        count.checked_mul(4).ok_or("squashfs file is too large")?,
+                    )?
+                    .chunks_exact(4)
+                    .map(|entry| le_at::<u32>(entry, 0))
+                    .collect::<Result<_, _>>()?;
+                Inode::File {
+                    start,
+                    size,
+                    blocks,
+                    fragment,
+                    fragment_offset,
+                }
+            }
+            INODE_SYMLINK | INODE_EXTENDED_SYMLINK => {
+                let data = self.take(&mut cursor, 8)?;
+                let size = le_at::<u32>(&data, 4)? as usize;
+                if size > METADATA_SIZE {
+                    return Err("squashfs link target is too long".to_string());
+                }
+                Inode::Symlink(self.take(&mut cursor, size)?)
+            }
+            _ => Inode::Other,
+        };
+        Ok((inode, modified))
+    }
+
+    /// Reads the node of the inode `reference`.
+    fn node(&self, reference: u64) -> Result<Node, String> {
+        let (inode, modified) = self.inode(reference)?;
+        let (kind, size) = match inode {
+            Inode::Directory { size, .. } => (NodeKind::Directory, size as u64),
+            Inode::File { size, .. } => (NodeKind::File, size),
+            Inode::Symlink(target) => (NodeKind::Symlink, target.len() as u64),
+            Inode::Other => (NodeKind::Other, 0),
+        };
+        Ok(Node {
+            id: reference,
+            tree: 0,
+            kind,
+            size,
+            modified: modified as i64,
+        })
+    }
+
+    /// Reads the data block of a file whose `entry` in its block list is at the byte `offset`.
+    /// Sparse blocks, which have a size of zero, are `size` bytes of zeros.
+    fn data_block(&self, offset: u64, entry: u32, size: usize) -> Result<Vec<u8>, String> {
+        let stored = (entry & (DATA_UNCOMPRESSED - 1)) as usize;
+        if stored == 0 {
+            return Ok(vec![0u8; size]);
+        }
+        let data = read_vec(&self.source, offset, stored)?;
+        if entry & DATA_UNCOMPRESSED != 0 {
+            Ok(data)
+        } else {
+            self.decompress(&data, self.block_size as usize)
+        }
+    }
+
+    /// Reads the fragment block with the `index` in the fragment table.
+    fn fragment(&self, index: u32) -> Result<Vec<u8>, String> {
+        let per_block = METADATA_SIZE / FRAGMENT_ENTRY_SIZE;
+        let table = *self
+            .fragment_blocks
+            .get(index as usize / per_block)
+            .ok_or("squashfs fragment is out of range")?;
+        let mut cursor = self.cursor(table, 0, index as usize % per_block * FRAGMENT_ENTRY_SIZE)?;
+        let entry = self.take(&mut cursor, FRAGMENT_ENTRY_SIZE)?;
+        self.data_block(
+            le_at::<u64>(&entry, 0)?,
+            le_at::<u32>(&entry, 8)?,
+            self.block_size as usize,
+        )
+    }
+}
+
+impl<R: ReadAt> ReadOnlyFilesystem for Squashfs<R> {
+    fn root(&self) -> Result<Node, String> {
+        Ok(self.root.clone())
+    }
+
+    fn read_dir(&self, directory: &Node) -> Result<Vec<DirectoryEntry>, String> {
+        let (
+            Inode::Directory {
+                block,
+                offset,
+                size,
+            },
+            _,
+        ) = self.inode(directory.id)?
+        else {
+            return Err("squashfs node is not a directory".to_string());
+        };
+
+        // The size of a listing counts the `.` and `..` entries, which are not stored.
+        let mut remaining = (size as usize).saturating_sub(3);
+        let mut cursor = self.cursor(self.directory_table, block as u64, offset as usize)?;
+        let mut entries = Vec::new();
+        while remaining >= DIRECTORY_HEADER_SIZE {
+            let header = self.take(&mut cursor, DIRECTORY_HEADER_SIZE)?;
+            let count = le_at::<u32>(&header, 0)? as usize + 1;
+            let start = le_at::<u32>(&header, 4)? as u64;
+            if count > MAX_DIRECTORY_ENTRIES {
+                return Err("invalid squashfs directory listing".to_string());
+            }
+            remaining -= DIRECTORY_HEADER_SIZE;
+            for _ in 0..count {
+                let entry = self.take(&mut cursor, DIRECTORY_ENTRY_SIZE)?;
+                let name_size = le_at::<u16>(&entry, 6)? as usize + 1;
+                let name = self.take(&mut cursor, name_size)?;
+                remaining = remaining
+                    .checked_sub(DIRECTORY_ENTRY_SIZE + name_size)
+                    .ok_or("invalid squashfs directory listing")?;
+                let reference = (start << 16) | le_at::<u16>(&entry, 0)? as u64;
+                entries.push(DirectoryEntry {
+                    name: String::from_utf8_lossy(&name).to_string(),
+                    node: self.node(reference)?,
+                });
+            }
+        }
+        Ok(entries)
+    }
+
+    fn read(&self, node: &Node, offset: u64, buffer: &mut [u8]) -> Result<usize, String> {
+        let end = node.size.min(offset.saturating_add(buffer.len() as u64));
+        if offset >= end {
+            return Ok(0);
+        }
+        let size = (end - offset) as usize;
+
+        let (start, file_size, blocks, fragment, fragment_offset) = match self.inode(node.id)?.0 {
+            Inode::File {
+                start,
+                size,
+                blocks,
+                fragment,
+                fragment_offset,
+            } => (start, size, blocks, fragment, fragment_offset),
+            Inode::Symlink(target) => {
+                let data = target
+                    .get(offset as usize..end as usize)
+                    .ok_or("squashfs link changed size")?;
+                buffer[..size].copy_from_slice(data);
+                return Ok(size);
+            }
+            _ => return Err("squashfs node is not a file".to_string()),
+        };
+
+        let block_size = self.block_size as u64;
+        let mut block_offset = start;
+        let mut index = 0;
+        let mut position = offset;
+        while position < end {
+            let block = position / block_size;
+            let block_start = block * block_size;
+            let length = (file_size - block_start).min(block_size) as usize;
+
+            // The blocks before this one are skipped by their stored sizes.
+            while (index as u64) < block && index < blocks.len() {
+                block_offset += (blocks[index] & (DATA_UNCOMPRESSED - 1)) as u64;
+                index += 1;
+            }
+            let data = if let Some(entry) = blocks.get(block as usize) {
+                self.data_block(block_offset, *entry, length)?
+            } else if fragment != NO_FRAGMENT {
+                let data = self.fragment(fragment)?;
+                let tail = fragment_offset as usize;
+                data.get(tail..tail + length)
+                    .ok_or("squashfs fragment is too short")?
+                    .to_vec()
+            } else {
+                return Err("squashfs file is missing blocks".to_string());
+            };
+            if data.len() < length {
+                return Err("squashfs block is too short".to_string());
+            }
+
+            let within = (position - block_start) as usize;
+            let count = (length - within).min((end - position) as usize);
+            let target = (position - offset) as usize;
+            buffer[target..target + count].copy_from_slice(&data[within..within + count]);
+            position += count as u64;
+        }
+        Ok(size)
+    }
+
+    fn label(&self) -> String {
+        String::new()
+    }
+
+    fn size(&self) -> u64 {
+        self.bytes_used
+    }
+
+    fn block_size(&self) -> u32 {
+        self.block_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The size of the data blocks of the test filesystem.
+    const BLOCK: usize = 4096;
+
+    fn push16(data: &mut Vec<u8>, value: u16) {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn push32(data: &mut Vec<u8>, value: u32) {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Pushes the common inode header with the inode `kind`.
+    fn push_inode(data: &mut Vec<u8>, kind: u16, number: u32) {
+        push16(data, kind);
+        push16(data, 0o755);
+        push16(data, 0);
+        push16(data, 0);
+        push32(data, 1_700_000_000);
+        push32(data, number);
+    }
+
+    /// Pushes a basic directory inode whose listing is at `offset` of the directory table.
+    fn push_directory(data: &mut Vec<u8>, number: u32, offset: u16, listing: usize) {
+        push_inode(data, INODE_DIRECTORY, number);
+        push32(data, 0);
+        push32(data, 2);
+        push16(data, listing as u16 + 3);
+        push16(data, offset);
+        push32(data, 1);
+    }
+
+    /// Builds a directory listing of the inodes in the first inode table block.
+    fn listing(entries: &[(&str, u16, u16)]) -> Vec<u8> {
+        let mut data = Vec::new();
+        push32(&mut data, entries.len() as u32 - 1);
+        push32(&mut data, 0);
+        push32(&mut data, 1);
+        for (name, inode, kind) in entries {
+            push16(&mut data, *inode);
+            push16(&mut data, 0);
+            push16(&mut data, *kind);
+            push16(&mut data, name.len() as u16 - 1);
+            data.extend_from_slice(name.as_bytes());
+        }
+        data
+    }
+
+    /// Builds a metadata block, compressed if `compress` is set.
+    fn metadata(data: &[u8], compress: bool) -> Vec<u8> {
+        let mut block = Vec::new();
+        if compress {
+            let compressed = miniz_oxide::deflate::compress_to_vec_zlib(data, 6);
+            push16(&mut block, compressed.len() as u16);
+            block.extend_from_slice(&compressed);
+        } else {
+            push16(&mut block, data.len() as u16 | METADATA_UNCOMPRESSED);
+            block.extend_from_slice(data);
+        }
+        block
+    }
+
+    /// The contents of the test kernel, which spans a compressed block, a sparse block,
+    /// and a tail in a fragment.
+    fn kernel() -> Vec<u8> {
+        let mut data = (0..BLOCK)
+            .map(|index| (index % 251) as u8)
+            .collect::<Vec<_>>();
+        data.extend(core::iter::repeat_n(0, BLOCK));
+        data.extend((0..100).map(|index| index as u8 + 1));
+        data
+    }
+
+    /// Builds a squashfs image with `\boot\vmlinuz` and a link to it at `\vmlinuz`.
+    fn image() -> Vec<u8> {
+        let kernel = kernel();
+        let mut image = vec![0u8; SUPERBLOCK_SIZE];
+
+        let start = image.len();
+        let compressed = miniz_oxide::deflate::compress_to_vec_zlib(&kernel[..BLOCK], 6);
+        image.extend_from_slice(&compressed);
+
+        // The tail of the kernel is stored uncompressed after another file's tail.
+        let fragment_start = image.len();
+        image.extend_from_slice(b"other");
+        image.extend_from_slice(&kernel[2 * BLOCK..]);
+
+        let inodes = {
+            let mut data = Vec::new();
+            push_directory(&mut data, 1, 0, 39);
+            push_directory(&mut data, 2, 39, 27);
+            push_inode(&mut data, INODE_FILE, 3);
+            push32(&mut data, start as u32);
+            push32(&mut data, 0);
+            push32(&mut data, 5);
+            push32(&mut data, kernel.len() as u32);
+            push32(&mut data, compressed.len() as u32);
+            push32(&mut data, 0);
+            push_inode(&mut data, INODE_SYMLINK, 4);
+            push32(&mut data, 1);
+            push32(&mut data, 12);
+            data.extend_from_slice(b"boot/vmlinuz");
+            data
+        };
+        let directories = {
+            let mut data = listing(&[("boot", 32, 1), ("vmlinuz", 104, 3)]);
+            data.extend(listing(&[("vmlinuz", 64, 2)]));
+            data
+        };
+        let fragments = {
+            let mut data = Vec::new();
+            data.extend_from_slice(&(fragment_start as u64).to_le_bytes());
+            push32(&mut data, (5 + 100) as u32 | DATA_UNCOMPRESSED);
+            push32(&mut data, 0);
+            data
+        };
+
+        let inode_table = image.len();
+        image.extend(metadata(&inodes, true));
+        let directory_table = image.len();
+        image.extend(metadata(&directories, false));
+        let fragment_block = image.len();
+        image.extend(metadata(&fragments, false));
+        let fragment_table = image.len();
+        image.extend_from_slice(&(fragment_block as u64).to_le_bytes());
+
+        let mut superblock = Vec::new();
+        push32(&mut superblock, SUPERBLOCK_MAGIC);
+        push32(&mut superblock, 4);
+        push32(&mut superblock, 1_700_000_000);
+        push32(&mut superblock, BLOCK as u32);
+        push32(&mut superblock, 1);
+        push16(&mut superblock, COMPRESSOR_GZIP);
+        push16(&mut superblock, BLOCK.trailing_zeros() as u16);
+        push16(&mut superblock, 0);
+        push16(&mut superblock, 1);
+        push16(&mut superblock, 4);
+        push16(&mut superblock, 0);
+        for value in [
+            0,
+            image.len(),
+            fragment_table,
+            usize::MAX,
+            inode_table,
+            directory_table,
+            fragment_table,
+            usize::MAX,
+        ] {
+            superblock.extend_from_slice(&(value as u64).to_le_bytes());
+        }
+        image[..SUPERBLOCK_SIZE].copy_from_slice(&superblock);
+        image
+    }
+
+    #[test]
+    fn files_are_read() {
+        let image = image();
+        assert!(probe(&image));
+        let filesystem = Squashfs::open(image).unwrap();
+        let root = filesystem.root().unwrap();
+        let names = filesystem
+            .read_dir(&root)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["boot", "vmlinuz"]);
+
+        let stack = filesystem.resolve(&[], "\\vmlinuz").unwrap().unwrap();
+        let node = &stack.last().unwrap().node;
+        assert_eq!(node.kind, NodeKind::File);
+        assert_eq!(node.modified, 1_700_000_000);
+        assert_eq!(filesystem.read_all(node).unwrap(), kernel());
+
+        // Reads that start inside a block and cross into the fragment are joined.
+        let mut buffer = [0u8; 8];
+        let read = filesystem.read(node, 2 * BLOCK as u64 - 4, &mut buffer);
+        assert_eq!(read, Ok(8));
+        assert_eq!(buffer, [0, 0, 0, 0, 1, 2, 3, 4]);
+        assert_eq!(filesystem.read(node, node.size, &mut buffer), Ok(0));
+    }
+
+    #[test]
+    fn unsupported_filesystems_are_rejected() {
+        let mut image = image();
+        image[20] = 4;
+        assert!(Squashfs::open(image.clone()).is_err());
+        image[20] = 1;
+        image[28] = 3;
+        assert!(Squashfs::open(image.clone()).is_err());
+        image[0] = 0;
+        assert!(!probe(&image));
+    }
+}
@@ -0,0 +1,333 @@
+use crate::{ESCAPED_DOLLAR, escape_values, unescape_values};
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use sha2::{Digest, Sha256};
+
+/// The opening sequence of a braced template expression.
+const EXPRESSION_OPEN: &str = "${";
+
+/// The separator between a key and its default value in a braced template expression.
+const DEFAULT_SEPARATOR: &str = ":-";
+
+/// The separator between a function name and its arguments in a braced template expression.
+const FUNCTION_SEPARATOR: char = ':';
+
+/// Formats the hex string `input` as a lowercase hyphenated GUID.
+/// Braces and hyphens in the input are ignored, so any common GUID form is accepted.
+fn format_guid(input: &str) -> Option<String> {
+    let hex = input
+        .chars()
+        .filter(|c| !matches!(c, '-' | '{' | '}'))
+        .map(|c| c.to_ascii_lowercase())
+        .collect::<String>();
+    if hex.len() != 32 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some(alloc::format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    ))
+}
+
+/// Takes a substring of `value` by characters, starting at `start` with an optional `length`.
+fn substring(value: &str, start: &str, length: Option<&str>) -> Option<String> {
+    let start = start.parse::<usize>().ok()?;
+    let chars = value.chars().skip(start);
+    Some(match length {
+        Some(length) => chars.take(length.parse::<usize>().ok()?).collect(),
+        None => chars.collect(),
+    })
+}
+
+/// Applies the integer arithmetic `operation` to the `value` and the `operand`.
+/// Both sides must be decimal integers, and overflow or division by zero is rejected.
+fn arithmetic(operation: &str, value: &str, operand: &str) -> Option<String> {
+    let value = value.trim().parse::<i64>().ok()?;
+    let operand = operand.trim().parse::<i64>().ok()?;
+    let result = match operation {
+        "add" => value.checked_add(operand),
+        "sub" => value.checked_sub(operand),
+        "mul" => value.checked_mul(operand),
+        "div" => value.checked_div(operand),
+        "mod" => value.checked_rem(operand),
+        _ => None,
+    }?;
+    Some(result.to_string())
+}
+
+/// Calls the template function `name` with the `value` of the key and the extra `arguments`.
+/// Returns [None] if the function is unknown or the arguments are invalid.
+fn call_function(name: &str, value: &str, arguments: &[&str]) -> Option<String> {
+    match (name, arguments) {
+        ("upper", []) => Some(value.to_uppercase()),
+        ("lower", []) => Some(value.to_lowercase()),
+        ("sha256", []) => Some(hex::encode(Sha256::digest(value.as_bytes()))),
+        ("guid", []) => format_guid(value),
+        ("substr", [start]) => substring(value, start, None),
+        ("substr", [start, length]) => substring(value, start, Some(length)),
+        ("add" | "sub" | "mul" | "div" | "mod", [operand]) => arithmetic(name, value, operand),
+        _ => None,
+    }
+}
+
+/// Checks whether `name` is the name of a template function.
+fn is_function(name: &str) -> bool {
+    matches!(
+        name,
+        "upper" | "lower" | "sha256" | "guid" | "substr" | "add" | "sub" | "mul" | "div" | "mod"
+    )
+}
+
+/// Evaluates the function call `expression`, like `upper:key` or `substr:key:0:8`.
+/// Returns [None] if the expression is not a function call or the key has no value.
+fn evaluate_function(values: &BTreeMap<String, String>, expression: &str) -> Option<String> {
+    let mut parts = expression.split(FUNCTION_SEPARATOR);
+    let name = parts.next()?;
+    if !is_function(name) {
+        return None;
+    }
+    let key = parts.next()?;
+    let arguments = parts.collect::<Vec<_>>();
+    // Functions operate on the literal value, and their result stays literal.
+    let value = unescape_values(values.get(key)?);
+    call_function(name, &value, &arguments).map(|result| escape_values(&result))
+}
+
+/// Acquires the key referenced by the braced template `expression`, if it requires one.
+/// Expressions with a default value resolve even if the key is missing, so they return [None].
+/// This is used to validate configurations without evaluating them.
+pub fn expression_reference(expression: &str) -> Option<&str> {
+    if expression.contains(DEFAULT_SEPARATOR) {
+        return None;
+    }
+
+    // Function calls reference the key that follows the function name.
+    let key = match expression.split_once(FUNCTION_SEPARATOR) {
+        Some((name, arguments)) if is_function(name) => arguments
+            .split(FUNCTION_SEPARATOR)
+            .next()
+            .unwrap_or_default(),
+        _ => expression,
+    };
+    if key.is_empty() { None } else { Some(key) }
+}
+
+/// Finds the index of the closing brace that matches an expression starting at `text`.
+/// The `text` should begin immediately after the opening `${`.
+/// Nested braced expressions are skipped, which allows defaults to reference other values.
+fn find_closing_brace(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (index, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(index),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Evaluates the braced template `expression` using the specified `values`.
+/// Returns [None] if the expression can't be evaluated yet, in which case it is left as-is.
+fn evaluate(values: &BTreeMap<String, String>, expression: &str) -> Option<String> {
+    // Function calls take precedence over plain keys.
+    if let Some((name, _)) = expression.split_once(FUNCTION_SEPARATOR)
+        && is_function(name)
+    {
+        return evaluate_function(values, expression);
+    }
+
+    // Split the expression into the key and the optional default value.
+    let (key, default) = match expression.split_once(DEFAULT_SEPARATOR) {
+        Some((key, default)) => (key, Some(default)),
+        None => (expression, None),
+    };
+
+    // Like the shell, a value that is empty is replaced by the default value.
+    match (values.get(key), default) {
+        (Some(value), Some(_)) if !value.is_empty() => Some(value.clone()),
+        (Some(value), None) => Some(value.clone()),
+        (_, Some(default)) => Some(stamp_expressions(values, default).1),
+        (None, None) => None,
+    }
+}
+
+/// Stamps the braced template expressions in `text` with the specified `values`.
+/// The returned value indicates whether the `text` has been changed and the stamped result.
+///
+/// The following expressions are supported:
+/// - `${key}`: replaced by the value of `key`, or left as-is if `key` has no value.
+/// - `${key:-default}`: replaced by the value of `key`, or `default` if `key` has no value
+///   or the value is empty. The `default` may itself contain braced expressions.
+/// - `${upper:key}` and `${lower:key}`: the value of `key` converted to upper or lower case.
+/// - `${sha256:key}`: the hex-encoded SHA-256 hash of the value of `key`.
+/// - `${substr:key:start}` and `${substr:key:start:length}`: a substring of the value of `key`.
+/// - `${guid:key}`: the value of `key` formatted as a lowercase hyphenated GUID.
+/// - `${add:key:n}`, `${sub:key:n}`, `${mul:key:n}`, `${div:key:n}` and `${mod:key:n}`:
+///   integer arithmetic on the value of `key` with the integer `n`.
+///
+/// Function calls whose key has no value, or that have invalid arguments, are left as-is.
+/// Escaped dollar signs are preserved, so `$${key}` is not an expression.
+pub fn stamp_expressions(values: &BTreeMap<String, String>, text: &str) -> (bool, String) {
+    let mut result = String::new();
+    let mut did_change = false;
+    let mut rest = text;
+
+    while let Some(start) = rest.find('$') {
+        // Copy everything before the dollar sign into the result.
+        result.push_str(&rest[..start]);
+
+        // Escaped dollar signs are preserved, and a dollar sign without a brace is copied.
+        if rest[start..].starts_with(ESCAPED_DOLLAR) {
+            result.push_str(ESCAPED_DOLLAR);
+            rest = &rest[start + ESCAPED_DOLLAR.len()..];
+            continue;
+        } else if !rest[start..].starts_with(EXPRESSION_OPEN) {
+            result.push('$');
+            rest = &rest[start + 1..];
+            continue;
+        }
+        let after_open = &rest[start + EXPRESSION_OPEN.len()..];
+
+        // An expression without a closing brace is left as-is.
+        let Some(end) = find_closing_brace(after_open) else {
+            result.push_str(&rest[start..]);
+            rest = "";
+            break;
+        };
+
+        let expression = &after_open[..end];
+        match evaluate(values, expression) {
+            Some(value) => {
+                result.push_str(&value);
+                did_change = true;
+            }
+            None => result.push_str(&rest[start..start + EXPRESSION_OPEN.len() + end + 1]),
+        }
+        rest = &after_open[end + 1..];
+    }
+    result.push_str(rest);
+
+    // Avoid allocating a second copy when nothing has changed.
+    if !did_change {
+        return (false, text.to_string());
+    }
+    (did_change, result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn braced_key_is_replaced() {
+        let values = map(&[("name", "world")]);
+        let (changed, result) = stamp_expressions(&values, "hello ${name}!");
+        assert!(changed);
+        assert_eq!(result, "hello world!");
+    }
+
+    #[test]
+    fn unknown_braced_key_is_left_as_is() {
+        let values = map(&[]);
+        let (changed, result) = stamp_expressions(&values, "hello ${name}");
+        assert!(!changed);
+        assert_eq!(result, "hello ${name}");
+    }
+
+    #[test]
+    fn default_is_used_for_missing_or_empty_value() {
+        let values = map(&[("empty", ""), ("set", "value")]);
+        let (changed, result) =
+            stamp_expressions(&values, "${missing:-a} ${empty:-b} ${set:-c} ${missing:-}");
+        assert!(changed);
+        assert_eq!(result, "a b value ");
+    }
+
+    #[test]
+    fn nested_default_is_stamped() {
+        let values = map(&[("fallback", "nested")]);
+        let (_, result) = stamp_expressions(&values, "${missing:-${fallback}}");
+        assert_eq!(result, "nested");
+    }
+
+    #[test]
+    fn functions_are_applied() {
+        let values = map(&[
+            ("name", "Sprout"),
+            ("guid", "{0FC63DAF8483-4772-8E79-3D69D8477DE4}"),
+        ]);
+        let (_, result) = stamp_expressions(
+            &values,
+            "${upper:name} ${lower:name} ${substr:name:1:3} ${substr:name:2} ${guid:guid}",
+        );
+        assert_eq!(
+            result,
+            "SPROUT sprout pro rout 0fc63daf-8483-4772-8e79-3d69d8477de4"
+        );
+    }
+
+    #[test]
+    fn sha256_function_hashes_value() {
+        let values = map(&[("name", "sprout")]);
+        let (_, result) = stamp_expressions(&values, "${sha256:name}");
+        assert_eq!(result, crate::unique_hash("sprout"));
+    }
+
+    #[test]
+    fn invalid_function_calls_are_left_as_is() {
+        let values = map(&[("name", "sprout")]);
+        let text = "${upper:missing} ${substr:name:x} ${guid:name} ${upper:name:extra}";
+        let (changed, result) = stamp_expressions(&values, text);
+        assert!(!changed);
+        assert_eq!(result, text);
+    }
+
+    #[test]
+    fn arithmetic_is_applied() {
+        let values = map(&[("slot", "1"), ("memory", "4096")]);
+        let (_, result) = stamp_expressions(
+            &values,
+            "${add:slot:1} ${sub:slot:2} ${mul:memory:2} ${div:memory:4} ${mod:slot:2}",
+        );
+        assert_eq!(result, "2 -1 8192 1024 1");
+    }
+
+    #[test]
+    fn invalid_arithmetic_is_left_as_is() {
+        let values = map(&[("slot", "a"), ("memory", "4096")]);
+        let text = "${add:slot:1} ${div:memory:0} ${mul:memory:x} ${add:memory}";
+        let (changed, result) = stamp_expressions(&values, text);
+        assert!(!changed);
+        assert_eq!(result, text);
+    }
+
+    #[test]
+    fn expression_references_are_found() {
+        assert_eq!(expression_reference("name"), Some("name"));
+        assert_eq!(expression_reference("substr:name:0:8"), Some("name"));
+        assert_eq!(expression_reference("name:-default"), None);
+        assert_eq!(expression_reference("upper:"), None);
+    }
+
+    #[test]
+    fn unterminated_expression_is_left_as_is() {
+        let values = map(&[("name", "world")]);
+        let (changed, result) = stamp_expressions(&values, "${name} ${name");
+        assert!(changed);
+        assert_eq!(result, "world ${name");
+    }
+}
@@ -0,0 +1,151 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// The number of seconds in a day.
+pub const SECONDS_PER_DAY: i64 = 86400;
+
+/// The last use times of variables, as unix timestamps keyed by variable name.
+/// This is stored as lines of `name=timestamp`, which keeps the encoding readable
+/// from the booted OS.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VariableStamps(BTreeMap<String, i64>);
+
+impl VariableStamps {
+    /// Parses the stamps encoded in `data`. Lines that are not valid stamps are ignored,
+    /// so a corrupted stamp only forgets that variable.
+    pub fn parse(data: &[u8]) -> Self {
+        let text = String::from_utf8_lossy(data);
+        let stamps = text
+            .lines()
+            .filter_map(|line| {
+                let (name, timestamp) = line.split_once('=')?;
+                Some((name.trim().to_string(), timestamp.trim().parse().ok()?))
+            })
+            .filter(|(name, _)| !name.is_empty())
+            .collect();
+        Self(stamps)
+    }
+
+    /// Encodes the stamps, in the format accepted by [VariableStamps::parse].
+    pub fn encode(&self) -> Vec<u8> {
+        self.0
+            .iter()
+            .map(|(name, timestamp)| format!("{}={}\n", name, timestamp))
+            .collect::<String>()
+            .into_bytes()
+    }
+
+    /// Acquires the last use time of the variable `name`, if it is tracked.
+    pub fn get(&self, name: &str) -> Option<i64> {
+        self.0.get(name).copied()
+    }
+
+    /// Records that the variable `name` was used at the unix timestamp `now`, which starts
+    /// tracking it. Stamps are only refreshed once they are a day old, so that using a
+    /// variable on every boot does not rewrite the stamps on every boot.
+    pub fn touch(&mut self, name: &str, now: i64) {
+        let fresh = self
+            .get(name)
+            .is_some_and(|stamp| stamp <= now && now - stamp < SECONDS_PER_DAY);
+        if !fresh {
+            self.0.insert(name.to_string(), now);
+        }
+    }
+
+    /// Determines which of the tracked variables in `names` were not used in the last
+    /// `max_age` seconds at the unix timestamp `now`, and stops tracking them.
+    ///
+    /// Variables that are not tracked are never collected, as Sprout did not write them.
+    /// The `in_use` variable is touched instead of collected, if it is tracked.
+    /// Stamps in the future, which happen when the clock was wrong, are reset to `now`.
+    /// Stamps of variables that are not in `names` anymore are dropped.
+    pub fn sweep(
+        &mut self,
+        names: &[String],
+        in_use: Option<&str>,
+        now: i64,
+        max_age: i64,
+    ) -> Vec<String> {
+        let mut stale = Vec::new();
+        let mut stamps = BTreeMap::new();
+        for name in names {
+            let Some(stamp) = self.get(name) else {
+                continue;
+            };
+            let stamp = if stamp > now { now } else { stamp };
+            if in_use == Some(name.as_str()) {
+                let fresh = now - stamp < SECONDS_PER_DAY;
+                stamps.insert(name.clone(), if fresh { stamp } else { now });
+            } else if now - stamp > max_age {
+                stale.push(name.clone());
+            } else {
+                stamps.insert(name.clone(), stamp);
+            }
+        }
+        self.0 = stamps;
+        stale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_and_encode_round_trip() {
+        let stamps = VariableStamps::parse(b"Boot0001=100\nbroken\n=5\nBoot0002=x\n");
+        assert_eq!(stamps.get("Boot0001"), Some(100));
+        assert_eq!(stamps.get("Boot0002"), None);
+        assert_eq!(stamps.encode(), b"Boot0001=100\n");
+        assert_eq!(VariableStamps::parse(&stamps.encode()), stamps);
+    }
+
+    #[test]
+    fn touch_refreshes_daily() {
+        let mut stamps = VariableStamps::default();
+        stamps.touch("Boot0001", 1000);
+        stamps.touch("Boot0001", 2000);
+        assert_eq!(stamps.get("Boot0001"), Some(1000));
+        stamps.touch("Boot0001", 1000 + SECONDS_PER_DAY);
+        assert_eq!(stamps.get("Boot0001"), Some(1000 + SECONDS_PER_DAY));
+    }
+
+    #[test]
+    fn sweep_collects_stale_tracked_variables() {
+        let mut stamps = VariableStamps::default();
+        stamps.touch("Boot0001", 0);
+        stamps.touch("Boot0002", 900);
+        stamps.touch("Boot0003", 0);
+        stamps.touch("Boot0004", 0);
+        let stale = stamps.sweep(
+            &names(&["Boot0000", "Boot0001", "Boot0002", "Boot0004"]),
+            Some("Boot0004"),
+            SECONDS_PER_DAY + 1000,
+            500,
+        );
+        // Boot0000 is not tracked, Boot0003 is gone, and Boot0004 is in use.
+        assert_eq!(stale, vec!["Boot0001", "Boot0002"]);
+        assert_eq!(
+            stamps.encode(),
+            format!("Boot0004={}\n", SECONDS_PER_DAY + 1000).into_bytes()
+        );
+    }
+
+    #[test]
+    fn sweep_resets_future_stamps() {
+        let mut stamps = VariableStamps::default();
+        stamps.touch("Boot0001", 5000);
+        let stale = stamps.sweep(&names(&["Boot0001"]), None, 1000, 0);
+        assert!(stale.is_empty());
+        assert_eq!(stamps.get("Boot0001"), Some(1000));
+        let stale = stamps.sweep(&names(&["Boot0001"]), None, 1001, 0);
+        assert_eq!(stale, vec!["Boot0001"]);
+    }
+}
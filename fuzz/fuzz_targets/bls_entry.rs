@@ -0,0 +1,12 @@
+#![no_main]
+
+use edera_sprout_bls::BlsEntry;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    // Parsing must never fail or panic, and the accessors must handle any parsed entry.
+    let entry = input.parse::<BlsEntry>().expect("bls entry parsing is infallible");
+    let _ = entry.chainload_path();
+    let _ = entry.initrd_path();
+    let _ = entry.is_valid();
+});
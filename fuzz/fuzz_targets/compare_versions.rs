@@ -0,0 +1,11 @@
+#![no_main]
+
+use edera_sprout_bls::compare_versions;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|versions: (&str, &str)| {
+    let (a, b) = versions;
+    // The comparison must be reflexive and antisymmetric for sorting to be stable.
+    assert!(compare_versions(a, a).is_eq());
+    assert_eq!(compare_versions(a, b), compare_versions(b, a).reverse());
+});
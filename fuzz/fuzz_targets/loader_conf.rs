@@ -0,0 +1,11 @@
+#![no_main]
+
+use edera_sprout_bls::loader_conf::LoaderConf;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    // Parsing must never fail or panic.
+    input
+        .parse::<LoaderConf>()
+        .expect("loader.conf parsing is infallible");
+});
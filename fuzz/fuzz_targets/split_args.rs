@@ -0,0 +1,11 @@
+#![no_main]
+
+use edera_sprout_parsing::options::{split_args, strip_firmware_args};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    // Stripping firmware arguments must only ever remove arguments from the front.
+    let args = split_args(input);
+    let stripped = strip_firmware_args(args.clone());
+    assert!(args.ends_with(&stripped));
+});
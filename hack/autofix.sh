@@ -5,5 +5,5 @@ cd "$(dirname "${0}")/.." || exit 1
 
 . "hack/common.sh"
 
-cargo clippy --workspace --fix --allow-dirty --allow-staged --target "${HOST_ARCH}-unknown-uefi"
+cargo clippy --workspace --exclude edera-sprout-check --exclude edera-sprout-mkimage --fix --allow-dirty --allow-staged --target "${HOST_ARCH}-unknown-uefi"
 ./hack/format.sh
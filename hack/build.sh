@@ -5,7 +5,15 @@ cd "$(dirname "${0}")/.." || exit 1
 
 . "hack/common.sh"
 
+# Without an embedded signing key, Sprout refuses to boot with Secure Boot enabled unless it was
+# built with the opt-out, so require one of them instead of producing an image that fails to boot.
+if [ -z "${SPROUT_CONFIG_SIGNING_KEY}" ] && [ "${SPROUT_ALLOW_UNVERIFIED_ESP}" != "1" ]; then
+	echo "ERROR: set SPROUT_CONFIG_SIGNING_KEY to a public key that verifies the ESP, or set SPROUT_ALLOW_UNVERIFIED_ESP=1 to boot with Secure Boot without verifying it." >/dev/stderr
+	exit 1
+fi
+
 mkdir -p "${FINAL_DIR}"
 
-cargo build --target "${RUST_TARGET}" --profile "${RUST_PROFILE}" --bin sprout
-cp "target/${RUST_TARGET}/${RUST_TARGET_SUBDIR}/sprout.efi" "${FINAL_DIR}/sprout.efi"
+# shellcheck disable=SC2086
+cargo ${CARGO_TOOLCHAIN} build ${CARGO_UNSTABLE_FLAGS} --target "${RUST_TARGET}" --profile "${RUST_PROFILE}" --bin sprout
+cp "${RUST_TARGET_DIR}/${RUST_TARGET_SUBDIR}/sprout.efi" "${FINAL_DIR}/sprout.efi"
@@ -18,8 +18,9 @@ HOST_ARCH="$(uname -m)"
 
 [ "${TARGET_ARCH}" = "arm64" ] && TARGET_ARCH="aarch64"
 [ "${TARGET_ARCH}" = "amd64" ] && TARGET_ARCH="x86_64"
+{ [ "${TARGET_ARCH}" = "armv7" ] || [ "${TARGET_ARCH}" = "armhf" ]; } && TARGET_ARCH="arm"
 
-if [ "${TARGET_ARCH}" != "x86_64" ] && [ "${TARGET_ARCH}" != "aarch64" ]; then
+if [ "${TARGET_ARCH}" != "x86_64" ] && [ "${TARGET_ARCH}" != "aarch64" ] && [ "${TARGET_ARCH}" != "arm" ]; then
 	echo "Unsupported architecture: ${TARGET_ARCH}" >/dev/stderr
 	exit 1
 fi
@@ -30,6 +31,18 @@ RUST_TARGET_SUBDIR="${RUST_PROFILE}"
 [ "${RUST_PROFILE}" = "dev" ] && RUST_TARGET_SUBDIR="debug"
 
 RUST_TARGET="${TARGET_ARCH}-unknown-uefi"
+RUST_TARGET_DIR="target/${RUST_TARGET}"
+CARGO_TOOLCHAIN=""
+CARGO_UNSTABLE_FLAGS=""
+
+# Rust has no built-in target for 32-bit arm UEFI, so a target specification is used,
+# which requires a nightly toolchain that builds core and alloc from source.
+if [ "${TARGET_ARCH}" = "arm" ]; then
+	RUST_TARGET="hack/targets/thumbv7a-unknown-uefi.json"
+	RUST_TARGET_DIR="target/thumbv7a-unknown-uefi"
+	CARGO_TOOLCHAIN="+nightly"
+	CARGO_UNSTABLE_FLAGS="-Z json-target-spec -Z build-std=core,alloc -Z build-std-features=compiler-builtins-mem"
+fi
 
 [ -z "${DOCKER_TAG}" ] && DOCKER_TAG="${DEFAULT_DOCKER_TAG}"
 DOCKER_TARGET="linux/${TARGET_ARCH}"
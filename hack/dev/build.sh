@@ -5,6 +5,11 @@ cd "$(dirname "${0}")/../.." || exit 1
 
 . "hack/common.sh"
 
+if [ "${TARGET_ARCH}" = "arm" ]; then
+	echo "ERROR: the development environment does not support arm, use ./hack/build.sh instead." >/dev/stderr
+	exit 1
+fi
+
 EFI_NAME="BOOTX64"
 if [ "${TARGET_ARCH}" = "aarch64" ]; then
 	EFI_NAME="BOOTAA64"
@@ -14,6 +19,12 @@ if [ -z "${SPROUT_CONFIG_NAME}" ]; then
 	SPROUT_CONFIG_NAME="all"
 fi
 
+# The development environment has no signing key to verify the ESP with by default.
+if [ -z "${SPROUT_ALLOW_UNVERIFIED_ESP}" ]; then
+	SPROUT_ALLOW_UNVERIFIED_ESP="1"
+fi
+export SPROUT_ALLOW_UNVERIFIED_ESP
+
 echo "[build] ${TARGET_ARCH} ${RUST_PROFILE}"
 
 if ! command -v docker >/dev/null 2>&1; then
@@ -106,6 +117,7 @@ if [ "${SKIP_SPROUT_BUILD}" != "1" ]; then
 	else
 		docker build --platform="${DOCKER_TARGET}" -t "${DOCKER_PREFIX}/sprout-${TARGET_ARCH}:${DOCKER_TAG}" \
 			--build-arg="RUST_TARGET_SUBDIR=${RUST_TARGET_SUBDIR}" \
+			--build-arg="SPROUT_ALLOW_UNVERIFIED_ESP=${SPROUT_ALLOW_UNVERIFIED_ESP}" \
 			-f Dockerfile .
 		copy_from_image "${DOCKER_PREFIX}/sprout-${TARGET_ARCH}" "sprout.efi" "${FINAL_DIR}/sprout.efi"
 	fi